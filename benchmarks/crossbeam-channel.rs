@@ -84,6 +84,44 @@ fn mpmc(cap: Option<usize>) {
     });
 }
 
+fn reserve_spsc(cap: Option<usize>) {
+    let (tx, rx) = new(cap);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..MESSAGES {
+                tx.reserve().unwrap().send(message(i)).unwrap();
+            }
+        });
+
+        for _ in 0..MESSAGES {
+            rx.recv().unwrap();
+        }
+    });
+}
+
+fn reserve_mpmc(cap: Option<usize>) {
+    let (tx, rx) = new(cap);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..MESSAGES / THREADS {
+                    tx.reserve().unwrap().send(message(i)).unwrap();
+                }
+            });
+        }
+
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..MESSAGES / THREADS {
+                    rx.recv().unwrap();
+                }
+            });
+        }
+    });
+}
+
 fn select_rx(cap: Option<usize>) {
     let chans = (0..THREADS).map(|_| new(cap)).collect::<Vec<_>>();
 
@@ -166,12 +204,16 @@ fn main() {
 
     run!("bounded1_mpmc", mpmc(Some(1)));
     run!("bounded1_mpsc", mpsc(Some(1)));
+    run!("bounded1_reserve_mpmc", reserve_mpmc(Some(1)));
+    run!("bounded1_reserve_spsc", reserve_spsc(Some(1)));
     run!("bounded1_select_both", select_both(Some(1)));
     run!("bounded1_select_rx", select_rx(Some(1)));
     run!("bounded1_spsc", spsc(Some(1)));
 
     run!("bounded_mpmc", mpmc(Some(MESSAGES)));
     run!("bounded_mpsc", mpsc(Some(MESSAGES)));
+    run!("bounded_reserve_mpmc", reserve_mpmc(Some(MESSAGES)));
+    run!("bounded_reserve_spsc", reserve_spsc(Some(MESSAGES)));
     run!("bounded_select_both", select_both(Some(MESSAGES)));
     run!("bounded_select_rx", select_rx(Some(MESSAGES)));
     run!("bounded_seq", seq(Some(MESSAGES)));
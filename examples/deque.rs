@@ -0,0 +1,85 @@
+//! Workers that wait on a work-stealing deque and a control channel at the same time.
+//!
+//! Every worker has its own deque and steals from the global injector or from other workers when
+//! its deque runs dry. When there is nothing to steal, it blocks in a `Select` until a task is
+//! pushed anywhere or the control channel is disconnected.
+//!
+//! Run with `cargo run --example deque --features deque`.
+
+extern crate crossbeam_channel;
+
+use std::iter;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::deque::{Injector, Stealer, Worker};
+use crossbeam_channel::{unbounded, Select};
+
+// Finds a task in the local deque, the global injector, or the deques of other workers.
+fn find_task<T>(local: &Worker<T>, global: &Injector<T>, stealers: &[Stealer<T>]) -> Option<T> {
+    local.pop().or_else(|| {
+        iter::repeat_with(|| {
+            global
+                .steal_batch_and_pop(local)
+                .or_else(|| stealers.iter().map(|s| s.steal()).collect())
+        }).find(|s| !s.is_retry())
+        .and_then(|s| s.success())
+    })
+}
+
+fn main() {
+    const WORKERS: usize = 4;
+
+    let injector = Arc::new(Injector::new());
+    let workers = (0..WORKERS).map(|_| Worker::new_fifo()).collect::<Vec<_>>();
+    let stealers = workers.iter().map(|w| w.stealer()).collect::<Vec<_>>();
+    let (quit_s, quit_r) = unbounded::<()>();
+
+    let handles = workers
+        .into_iter()
+        .enumerate()
+        .map(|(id, local)| {
+            let injector = injector.clone();
+            let stealers = stealers.clone();
+            let quit_r = quit_r.clone();
+
+            thread::spawn(move || loop {
+                while let Some(job) = find_task(&local, &injector, &stealers) {
+                    println!("Worker {} got job {}", id, job);
+                }
+
+                // Wait until a task shows up somewhere or the control channel is disconnected.
+                let mut sel = Select::new();
+                let global = sel.custom(&*injector);
+                for s in &stealers {
+                    sel.custom(s);
+                }
+                let quit = sel.recv(&quit_r);
+
+                let oper = sel.select();
+                let index = oper.index();
+                if index == quit {
+                    let _ = oper.recv(&quit_r);
+                    break;
+                } else if index == global {
+                    oper.custom(&*injector);
+                } else {
+                    oper.custom(&stealers[index - global - 1]);
+                }
+            })
+        }).collect::<Vec<_>>();
+
+    for job in 0..20 {
+        injector.push(job);
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // Dropping the sender disconnects the control channel and stops all workers.
+    thread::sleep(Duration::from_millis(100));
+    drop(quit_s);
+
+    for h in handles {
+        h.join().unwrap();
+    }
+}
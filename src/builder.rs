@@ -0,0 +1,1004 @@
+//! Configurable channel construction.
+
+use std::fmt;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::sync::Arc;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+use channel::{self, Receiver, Sender};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use clock::{Clock, Time};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use flavors::tick::Schedule;
+use shutdown::{Attachment, ShutdownGroup};
+
+/// Determines what happens to messages left in a channel when it gets disconnected.
+///
+/// The policy is applied when the last [`Receiver`] is dropped, and optionally also when the last
+/// [`Sender`] is dropped (see [`Builder::drain_on_sender_drop`]). Every message still queued at
+/// that moment is taken out of the channel and disposed of according to the policy, so no message
+/// outlives the disconnection. The exceptions are [`DropPolicy::Discard`], which leaves messages
+/// in the channel until it is destroyed together with the last sender or receiver, and
+/// [`DropPolicy::Keep`], which leaves them there for senders to reclaim.
+///
+/// The policy runs on the thread that drops the last receiver (or sender), inside `drop`. It never
+/// blocks: [`DropPolicy::Forward`] drops messages that don't fit into the dead-letter channel and
+/// counts them, see [`Sender::lost_on_disconnect`].
+///
+/// Together with the messages handed back by failed send operations, this means a message is
+/// never lost silently: every message passed to a send operation is eventually either received,
+/// returned inside a [`SendError`], or handed over to the policy. This holds even if senders are
+/// racing with the disconnection, as long as the dead-letter channel of [`DropPolicy::Forward`]
+/// has room for every message. Messages that don't fit are not silent either: they are counted by
+/// [`Sender::lost_on_disconnect`].
+///
+/// # Examples
+///
+/// Keeping track of messages left in a channel during shutdown:
+///
+/// ```
+/// use crossbeam_channel::{unbounded, Builder, DropPolicy};
+///
+/// let (dead_s, dead_r) = unbounded();
+/// let (s, r) = Builder::new()
+///     .drop_policy(DropPolicy::Forward(dead_s))
+///     .bounded(10);
+///
+/// s.send("audit 1").unwrap();
+/// s.send("audit 2").unwrap();
+///
+/// // Shut down without receiving anything.
+/// drop(r);
+/// drop(s);
+///
+/// assert_eq!(dead_r.try_iter().collect::<Vec<_>>(), ["audit 1", "audit 2"]);
+/// ```
+///
+/// [`Receiver`]: struct.Receiver.html
+/// [`Sender`]: struct.Sender.html
+/// [`Builder::drain_on_sender_drop`]: struct.Builder.html#method.drain_on_sender_drop
+/// [`SendError`]: struct.SendError.html
+/// [`Sender::lost_on_disconnect`]: struct.Sender.html#method.lost_on_disconnect
+/// [`DropPolicy::Discard`]: enum.DropPolicy.html#variant.Discard
+/// [`DropPolicy::Forward`]: enum.DropPolicy.html#variant.Forward
+/// [`DropPolicy::Keep`]: enum.DropPolicy.html#variant.Keep
+pub enum DropPolicy<T> {
+    /// Remaining messages are dropped together with the channel.
+    ///
+    /// The channel is destroyed once all senders and receivers are gone, and the messages are
+    /// dropped on the thread that drops the last of them. This is the default.
+    Discard,
+
+    /// Remaining messages are passed one by one to the handler.
+    ///
+    /// The handler runs inside `drop`, so a panic in the handler is caught and swallowed rather
+    /// than propagated, as panicking while the thread is already unwinding would abort the
+    /// process. The remaining messages are still passed to the handler.
+    Handler(Box<Fn(T) + Send + Sync>),
+
+    /// Remaining messages are forwarded into another channel.
+    ///
+    /// Messages are forwarded without blocking, so messages that cannot be forwarded because the
+    /// dead-letter channel is full or disconnected get dropped. Such messages are counted by
+    /// [`Sender::lost_on_disconnect`]. Use an unbounded dead-letter channel to make sure every
+    /// message gets through.
+    ///
+    /// [`Sender::lost_on_disconnect`]: struct.Sender.html#method.lost_on_disconnect
+    Forward(Sender<T>),
+
+    /// Remaining messages are kept in the channel so that senders can take them back.
+    ///
+    /// Messages are taken back with [`Sender::reclaim_unconsumed`]. Messages that are never
+    /// reclaimed are dropped together with the channel.
+    ///
+    /// [`Sender::reclaim_unconsumed`]: struct.Sender.html#method.reclaim_unconsumed
+    Keep,
+}
+
+impl<T> fmt::Debug for DropPolicy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DropPolicy::Discard => "Discard".fmt(f),
+            DropPolicy::Handler(..) => "Handler(..)".fmt(f),
+            DropPolicy::Forward(..) => "Forward(..)".fmt(f),
+            DropPolicy::Keep => "Keep".fmt(f),
+        }
+    }
+}
+
+/// Determines which queued message a lossy send operation displaces when the channel is full.
+///
+/// See [`Sender::send_lossy`] for details.
+///
+/// [`Sender::send_lossy`]: struct.Sender.html#method.send_lossy
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{Builder, Displace};
+///
+/// let (s, r) = Builder::new().displace(Displace::Oldest).bounded(2);
+///
+/// s.send(1).unwrap();
+/// s.send(2).unwrap();
+/// assert_eq!(s.send_lossy(3), Ok(Some(1)));
+///
+/// assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 3]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Displace {
+    /// The most recently sent message is replaced by the new one.
+    ///
+    /// This is the default.
+    Newest,
+
+    /// The oldest message is removed, and the new one is queued behind the remaining messages.
+    Oldest,
+}
+
+/// Determines what a send operation does when the channel is full.
+///
+/// The policy is set per channel with [`Builder::full_policy`] and applies to [`Sender::send`],
+/// [`Sender::send_timeout`], [`Sender::send_by_policy`], and the other methods that would
+/// otherwise block until the channel has room, including [`Sender::reserve`] and
+/// [`Sender::flush`]. A zero-capacity channel counts as full whenever no receive operation is
+/// waiting on the other side. Non-blocking methods like [`Sender::try_send`] and send operations
+/// in [`select!`] and [`Select`] are not affected.
+///
+/// [`Builder::full_policy`]: struct.Builder.html#method.full_policy
+/// [`Sender::send`]: struct.Sender.html#method.send
+/// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+/// [`Sender::send_by_policy`]: struct.Sender.html#method.send_by_policy
+/// [`Sender::try_send`]: struct.Sender.html#method.try_send
+/// [`Sender::reserve`]: struct.Sender.html#method.reserve
+/// [`Sender::flush`]: struct.Sender.html#method.flush
+/// [`select!`]: macro.select.html
+/// [`Select`]: struct.Select.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{Builder, FullPolicy, SendError, SendTimeoutError, TrySendError};
+///
+/// let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+///
+/// assert_eq!(s.send_by_policy(1), Ok(()));
+/// assert_eq!(s.send_by_policy(2), Err(TrySendError::Full(2)));
+/// assert_eq!(s.send(3), Err(SendError(3)));
+/// assert_eq!(
+///     s.send_timeout(4, Duration::from_secs(1)),
+///     Err(SendTimeoutError::Timeout(4)),
+/// );
+/// # drop(r);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullPolicy {
+    /// The operation spins for a little while and then parks the thread until the channel has
+    /// room.
+    ///
+    /// This is the default.
+    Park,
+
+    /// The operation never parks the thread. It spins and then keeps yielding the thread until
+    /// the channel has room.
+    ///
+    /// This keeps the latency of handing a message over low, at the cost of occupying a CPU core
+    /// while waiting.
+    SpinYield,
+
+    /// The operation fails right away, just like [`Sender::try_send`].
+    ///
+    /// [`Sender::send_by_policy`] returns the message in a [`TrySendError::Full`] and
+    /// [`Sender::send_timeout`] returns it in a [`SendTimeoutError::Timeout`] without waiting.
+    /// Operations whose errors don't have a variant for a full channel, like [`Sender::send`] and
+    /// [`Sender::reserve`], return the same [`SendError`] as for a disconnected channel.
+    ///
+    /// Operations that don't send a message right away, like [`Sender::ready`] and
+    /// [`Sender::flush`], and [`Sender::send_cancellable`], which only fails when cancelled or
+    /// disconnected, wait for room as under [`FullPolicy::Park`].
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    /// [`Sender::reserve`]: struct.Sender.html#method.reserve
+    /// [`Sender::ready`]: struct.Sender.html#method.ready
+    /// [`Sender::flush`]: struct.Sender.html#method.flush
+    /// [`Sender::send_cancellable`]: struct.Sender.html#method.send_cancellable
+    /// [`Sender::send_by_policy`]: struct.Sender.html#method.send_by_policy
+    /// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+    /// [`SendError`]: struct.SendError.html
+    /// [`TrySendError::Full`]: enum.TrySendError.html#variant.Full
+    /// [`SendTimeoutError::Timeout`]: enum.SendTimeoutError.html#variant.Timeout
+    /// [`FullPolicy::Park`]: enum.FullPolicy.html#variant.Park
+    Fail,
+}
+
+/// Determines which blocked thread an operation wakes up when several are waiting.
+///
+/// The policy is set per channel with [`Builder::wake_policy`]. It applies whenever an operation
+/// makes another one ready, like a send operation waking up one of several receive operations
+/// blocked on an empty channel.
+///
+/// [`Builder::wake_policy`]: struct.Builder.html#method.wake_policy
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{Builder, WakePolicy};
+///
+/// let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).unbounded();
+///
+/// s.send(1).unwrap();
+/// assert_eq!(r.recv(), Ok(1));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakePolicy {
+    /// Blocked threads are woken up in the order they started waiting.
+    ///
+    /// This is the default.
+    Fifo,
+
+    /// Blocked threads that last ran on the same CPU as the waking thread are preferred, followed
+    /// by ones that last ran on the same NUMA node. Among equally close threads, the one that
+    /// started waiting first is woken up.
+    ///
+    /// Waking up a thread on another socket costs several microseconds more than waking up one
+    /// nearby, but this policy makes waiting threads take turns less fairly. Each blocking
+    /// operation also has to find out which CPU it is running on. Where that isn't supported,
+    /// which is everywhere except Linux, this policy behaves like `Fifo`.
+    Affinity,
+
+    /// Exactly one blocked thread is woken up each time an operation might have become ready.
+    ///
+    /// Blocked operations are still woken up in the order they started waiting, but threads that
+    /// only wait for the channel to become ready, like ones in [`Receiver::wait_ready`] or a
+    /// [`Poller`], are not all woken up at once. Instead, they are woken up only if no blocked
+    /// operation could be, one at a time, skipping threads that were already woken up by
+    /// something else. That avoids waking up many threads to race for a single message when lots
+    /// of them are waiting on the same channels.
+    ///
+    /// A thread woken up this way is expected to go on and take the message. Otherwise, the other
+    /// waiting threads are not woken up until the channel changes again.
+    ///
+    /// [`Receiver::wait_ready`]: struct.Receiver.html#method.wait_ready
+    /// [`Poller`]: struct.Poller.html
+    Exclusive,
+}
+
+/// A function that is shown messages passing through a channel.
+#[cfg(feature = "intercept")]
+pub type Hook<T> = Box<Fn(&T) + Send + Sync>;
+
+/// Settings a channel is created with.
+pub struct Config<T> {
+    /// What happens to messages left in the channel on disconnection.
+    pub drop_policy: DropPolicy<T>,
+
+    /// Equals `true` if the drop policy is also applied when the last sender is dropped.
+    pub drain_on_sender_drop: bool,
+
+    /// Which message a lossy send operation displaces when the channel is full.
+    pub displace: Displace,
+
+    /// What a blocking send operation does when the channel is full.
+    pub full_policy: FullPolicy,
+
+    /// The name the channel is listed under in diagnostics.
+    pub name: Option<String>,
+
+    /// The maximum number of spare blocks an unbounded channel keeps for reuse.
+    pub block_pool: usize,
+
+    /// Equals `true` if blocking operations spin before parking only when it is likely to pay off.
+    pub adaptive_spin: bool,
+
+    /// Which blocked thread an operation wakes up when several are waiting.
+    pub wake_policy: WakePolicy,
+
+    /// The group the channel is attached to.
+    pub shutdown_group: Option<Attachment<channel::Channel<T>>>,
+
+    /// Called with every message a send operation is about to send.
+    #[cfg(feature = "intercept")]
+    pub on_send: Option<Hook<T>>,
+
+    /// Called with every message a receive operation has received.
+    #[cfg(feature = "intercept")]
+    pub on_recv: Option<Hook<T>>,
+}
+
+impl<T> Default for Config<T> {
+    fn default() -> Config<T> {
+        Config {
+            drop_policy: DropPolicy::Discard,
+            drain_on_sender_drop: false,
+            displace: Displace::Newest,
+            full_policy: FullPolicy::Park,
+            name: None,
+            block_pool: 0,
+            adaptive_spin: true,
+            wake_policy: WakePolicy::Fifo,
+            shutdown_group: None,
+            #[cfg(feature = "intercept")]
+            on_send: None,
+            #[cfg(feature = "intercept")]
+            on_recv: None,
+        }
+    }
+}
+
+/// A channel factory, which can be used in order to configure the properties of a new channel.
+///
+/// Methods can be chained on it in order to configure it. The channel itself is created by calling
+/// [`bounded`] or [`unbounded`], which take the builder by value.
+///
+/// [`bounded`]: struct.Builder.html#method.bounded
+/// [`unbounded`]: struct.Builder.html#method.unbounded
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use crossbeam_channel::{Builder, DropPolicy};
+///
+/// let lost = Arc::new(Mutex::new(Vec::new()));
+/// let l = lost.clone();
+///
+/// let (s, r) = Builder::new()
+///     .drop_policy(DropPolicy::Handler(Box::new(move |msg| l.lock().unwrap().push(msg))))
+///     .bounded(10);
+///
+/// s.send(1).unwrap();
+/// s.send(2).unwrap();
+/// drop(r);
+///
+/// assert_eq!(*lost.lock().unwrap(), [1, 2]);
+/// ```
+pub struct Builder<T> {
+    /// Settings for the new channel.
+    config: Config<T>,
+}
+
+impl<T> Builder<T> {
+    /// Creates a builder with the default settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::new().unbounded();
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn new() -> Builder<T> {
+        Builder {
+            config: Config::default(),
+        }
+    }
+
+    /// Sets what happens to messages left in the channel when it gets disconnected.
+    ///
+    /// # Examples
+    ///
+    /// Forwarding remaining messages into a dead-letter channel:
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Builder, DropPolicy};
+    ///
+    /// let (dead_s, dead_r) = unbounded();
+    /// let (s, r) = Builder::new()
+    ///     .drop_policy(DropPolicy::Forward(dead_s))
+    ///     .unbounded();
+    ///
+    /// s.send("foo").unwrap();
+    /// drop(r);
+    ///
+    /// assert_eq!(dead_r.try_recv(), Ok("foo"));
+    /// ```
+    pub fn drop_policy(mut self, policy: DropPolicy<T>) -> Builder<T> {
+        self.config.drop_policy = policy;
+        self
+    }
+
+    /// Sets whether the drop policy is also applied when the last sender is dropped.
+    ///
+    /// By default, the policy is applied only when the last receiver is dropped, while messages
+    /// left in a channel without senders can still be received. If this option is enabled,
+    /// dropping the last sender takes all remaining messages out of the channel as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Builder, DropPolicy, RecvError};
+    ///
+    /// let (dead_s, dead_r) = unbounded();
+    /// let (s, r) = Builder::new()
+    ///     .drop_policy(DropPolicy::Forward(dead_s))
+    ///     .drain_on_sender_drop(true)
+    ///     .unbounded();
+    ///
+    /// s.send(1).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// assert_eq!(dead_r.try_recv(), Ok(1));
+    /// ```
+    pub fn drain_on_sender_drop(mut self, enable: bool) -> Builder<T> {
+        self.config.drain_on_sender_drop = enable;
+        self
+    }
+
+    /// Sets which queued message a lossy send operation displaces when the channel is full.
+    ///
+    /// By default, the newest message is displaced. See [`Sender::send_lossy`] for details.
+    ///
+    /// [`Sender::send_lossy`]: struct.Sender.html#method.send_lossy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, Displace};
+    ///
+    /// let (s, r) = Builder::new().displace(Displace::Newest).bounded(2);
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.send_lossy(3), Ok(Some(2)));
+    ///
+    /// assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    pub fn displace(mut self, displace: Displace) -> Builder<T> {
+        self.config.displace = displace;
+        self
+    }
+
+    /// Sets what a blocking send operation does when the channel is full.
+    ///
+    /// By default, the sending thread is parked until the channel has room. See [`FullPolicy`]
+    /// for details.
+    ///
+    /// [`FullPolicy`]: enum.FullPolicy.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{Builder, FullPolicy, SendTimeoutError};
+    ///
+    /// let (s, r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(1);
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(
+    ///     s.send_timeout(2, Duration::from_millis(10)),
+    ///     Err(SendTimeoutError::Timeout(2)),
+    /// );
+    /// # drop(r);
+    /// ```
+    pub fn full_policy(mut self, policy: FullPolicy) -> Builder<T> {
+        self.config.full_policy = policy;
+        self
+    }
+
+    /// Sets the name of the channel.
+    ///
+    /// The name has no effect on the behavior of the channel. It identifies the channel in
+    /// diagnostics, such as snapshots of the channel [`registry`].
+    ///
+    /// [`registry`]: registry/index.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::new().name("requests").bounded(10);
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn name<S: Into<String>>(mut self, name: S) -> Builder<T> {
+        self.config.name = Some(name.into());
+        self
+    }
+
+    /// Sets the maximum number of spare blocks an unbounded channel keeps for reuse.
+    ///
+    /// An unbounded channel stores messages in blocks of 32, allocating a new block whenever the
+    /// last one fills up and deallocating a block once all its messages are received. With a
+    /// block pool, received blocks are kept for reuse instead, which takes pressure off the
+    /// allocator under bursty load.
+    ///
+    /// Blocks are handed back to the pool by the garbage collector in batches, so pools of a few
+    /// dozen blocks are the most effective. Spare blocks stay allocated until the channel is
+    /// dropped. By default, the pool is disabled. Bounded channels ignore this setting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::new().block_pool(64).unbounded();
+    ///
+    /// for i in 0..1000 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// assert_eq!(r.try_iter().count(), 1000);
+    /// ```
+    pub fn block_pool(mut self, cap: usize) -> Builder<T> {
+        self.config.block_pool = cap;
+        self
+    }
+
+    /// Sets whether blocking operations adapt their spinning to how long they typically wait.
+    ///
+    /// An operation that can't complete right away spins for a little while before parking the
+    /// thread, in case the other side of the channel shows up in the meantime. By default, the
+    /// channel keeps track of how long its operations typically wait, and while waits take much
+    /// longer than a few microseconds, operations park right away instead of burning CPU time.
+    /// Once waits get shorter again, spinning resumes.
+    ///
+    /// Disabling this makes operations always spin for a fixed amount of time before parking.
+    /// Operations in [`select!`] and [`Select`] involving several channels always do so.
+    ///
+    /// [`select!`]: macro.select.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::new().adaptive_spin(false).bounded(1);
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn adaptive_spin(mut self, enable: bool) -> Builder<T> {
+        self.config.adaptive_spin = enable;
+        self
+    }
+
+    /// Sets which blocked thread an operation wakes up when several are waiting.
+    ///
+    /// By default, blocked threads are woken up in the order they started waiting. See
+    /// [`WakePolicy`] for details.
+    ///
+    /// [`WakePolicy`]: enum.WakePolicy.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{Builder, WakePolicy};
+    ///
+    /// let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).bounded(10);
+    ///
+    /// let workers: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let r = r.clone();
+    ///         thread::spawn(move || r.iter().sum::<i32>())
+    ///     })
+    ///     .collect();
+    ///
+    /// for i in 0..100 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// drop(s);
+    ///
+    /// let total: i32 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    /// assert_eq!(total, 4950);
+    /// ```
+    pub fn wake_policy(mut self, policy: WakePolicy) -> Builder<T> {
+        self.config.wake_policy = policy;
+        self
+    }
+
+    /// Attaches the channel to a shutdown group.
+    ///
+    /// Shutting down the group disconnects the channel. See [`ShutdownGroup`] for details.
+    ///
+    /// The group may be used from any thread, so only channels whose messages can be sent to other
+    /// threads can be attached to it.
+    ///
+    /// [`ShutdownGroup`]: struct.ShutdownGroup.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, ShutdownGroup};
+    ///
+    /// let group = ShutdownGroup::new();
+    /// let (s, r) = Builder::new().shutdown_group(&group).bounded(1);
+    ///
+    /// group.shutdown();
+    /// assert!(s.send(1).is_err());
+    /// assert!(r.recv().is_err());
+    /// ```
+    pub fn shutdown_group(mut self, group: &ShutdownGroup) -> Builder<T>
+    where
+        T: Send + 'static,
+    {
+        self.config.shutdown_group = Some(Attachment::new(group));
+        self
+    }
+
+    /// Installs a hook that is called with every message sent into the channel.
+    ///
+    /// The hook is called by the sending thread just before the message is handed to the channel,
+    /// including by send operations in [`select!`] and [`Select`]. If the operation then fails
+    /// because the channel is disconnected, the message is returned in the error even though the
+    /// hook has seen it. An operation that fails without sending, like [`try_send`] on a full
+    /// channel, is seen by the hook too, so each retry of such an operation calls it again.
+    ///
+    /// This is meant for auditing, checking invariants, and debugging. The hook must not perform
+    /// operations on this channel.
+    ///
+    /// This method is available only if the `intercept` feature is enabled. Without it, channels
+    /// carry no hooks and pay nothing for them.
+    ///
+    /// [`select!`]: macro.select.html
+    /// [`Select`]: struct.Select.html
+    /// [`try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::new()
+    ///     .on_send(|msg: &i32| assert!(*msg >= 0, "negative message"))
+    ///     .unbounded();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    #[cfg(feature = "intercept")]
+    pub fn on_send<F>(mut self, f: F) -> Builder<T>
+    where
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        self.config.on_send = Some(Box::new(f));
+        self
+    }
+
+    /// Installs a hook that is called with every message received from the channel.
+    ///
+    /// The hook is called by the receiving thread right after the message has been taken out of
+    /// the channel, including by receive operations in [`select!`] and [`Select`], and before the
+    /// message is returned. Messages that never get received, like the ones left in the channel
+    /// when it is dropped, are not seen by the hook.
+    ///
+    /// This is meant for auditing, checking invariants, and debugging. The hook must not perform
+    /// operations on this channel.
+    ///
+    /// This method is available only if the `intercept` feature is enabled. Without it, channels
+    /// carry no hooks and pay nothing for them.
+    ///
+    /// [`select!`]: macro.select.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let received = Arc::new(AtomicUsize::new(0));
+    /// let count = received.clone();
+    ///
+    /// let (s, r) = Builder::new()
+    ///     .on_recv(move |_: &String| {
+    ///         count.fetch_add(1, Ordering::SeqCst);
+    ///     })
+    ///     .bounded(10);
+    ///
+    /// s.send("hello".to_string()).unwrap();
+    /// s.send("world".to_string()).unwrap();
+    /// r.recv().unwrap();
+    ///
+    /// assert_eq!(received.load(Ordering::SeqCst), 1);
+    /// ```
+    #[cfg(feature = "intercept")]
+    pub fn on_recv<F>(mut self, f: F) -> Builder<T>
+    where
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        self.config.on_recv = Some(Box::new(f));
+        self
+    }
+
+    /// Creates a channel of bounded capacity.
+    ///
+    /// See [`bounded`] for details.
+    ///
+    /// [`bounded`]: fn.bounded.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is greater than `usize::max_value() / 4`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::<i32>::new().bounded(1);
+    /// assert_eq!(s.capacity(), Some(1));
+    /// ```
+    pub fn bounded(self, cap: usize) -> (Sender<T>, Receiver<T>) {
+        channel::bounded_with(cap, self.config)
+    }
+
+    /// Creates a channel of bounded capacity with several priority lanes.
+    ///
+    /// See [`lanes`] for details.
+    ///
+    /// [`lanes`]: fn.lanes.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity or the number of lanes is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::<i32>::new().lanes(4, 2);
+    /// assert_eq!(s.capacity(), Some(4));
+    /// ```
+    pub fn lanes(self, cap: usize, lanes: usize) -> (Sender<T>, Receiver<T>) {
+        channel::lanes_with(cap, lanes, self.config)
+    }
+
+    /// Creates a channel bounded by the total weight of the messages in it.
+    ///
+    /// See [`bounded_by_weight`] for details.
+    ///
+    /// [`bounded_by_weight`]: fn.bounded_by_weight.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_weight` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::new().bounded_by_weight(1024, |msg: &String| msg.len());
+    /// assert_eq!(s.capacity(), Some(1024));
+    /// # drop(r);
+    /// ```
+    pub fn bounded_by_weight<F>(self, max_weight: usize, weigh: F) -> (Sender<T>, Receiver<T>)
+    where
+        F: Fn(&T) -> usize + Send + Sync + 'static,
+    {
+        channel::bounded_by_weight_with(max_weight, Box::new(weigh), self.config)
+    }
+
+    /// Creates a channel of unbounded capacity.
+    ///
+    /// See [`unbounded`] for details.
+    ///
+    /// [`unbounded`]: fn.unbounded.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Builder;
+    ///
+    /// let (s, r) = Builder::<i32>::new().unbounded();
+    /// assert_eq!(s.capacity(), None);
+    /// ```
+    pub fn unbounded(self) -> (Sender<T>, Receiver<T>) {
+        channel::unbounded_with(self.config)
+    }
+}
+
+impl<T> Default for Builder<T> {
+    fn default() -> Builder<T> {
+        Builder::new()
+    }
+}
+
+impl<T> fmt::Debug for Builder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("drop_policy", &self.config.drop_policy)
+            .field("drain_on_sender_drop", &self.config.drain_on_sender_drop)
+            .field("displace", &self.config.displace)
+            .field("full_policy", &self.config.full_policy)
+            .field("name", &self.config.name)
+            .field("block_pool", &self.config.block_pool)
+            .field("wake_policy", &self.config.wake_policy)
+            .field("shutdown_group", &self.config.shutdown_group)
+            .finish()
+    }
+}
+
+/// A factory for channels created by [`tick`], which can be used in order to configure when
+/// messages are delivered.
+///
+/// By default, messages are delivered once per interval, measured from the time the previous
+/// message was received, just like with [`tick`]. Messages can be aligned to multiples of the
+/// interval on the wall clock, e.g. to fire at the start of every minute, and delayed by random
+/// jitter, e.g. so that a fleet of processes doesn't fire all at once.
+///
+/// [`tick`]: fn.tick.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use crossbeam_channel::TickBuilder;
+///
+/// // Fire close to every full second, but not exactly on it.
+/// let r = TickBuilder::new(Duration::from_secs(1))
+///     .align(Duration::from_secs(0))
+///     .jitter(Duration::from_millis(50))
+///     .build();
+///
+/// let start = Instant::now();
+/// r.recv().unwrap();
+/// assert!(start.elapsed() <= Duration::from_millis(1100));
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct TickBuilder {
+    /// The interval between messages.
+    duration: Duration,
+
+    /// Alignment and jitter of messages.
+    schedule: Schedule,
+
+    /// The clock measuring time.
+    time: Time,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl TickBuilder {
+    /// Creates a builder for a channel delivering messages once per `duration`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::TickBuilder;
+    ///
+    /// let r = TickBuilder::new(Duration::from_millis(100)).build();
+    /// assert!(r.recv().is_ok());
+    /// ```
+    pub fn new(duration: Duration) -> TickBuilder {
+        TickBuilder {
+            duration,
+            schedule: Schedule::default(),
+            time: None,
+        }
+    }
+
+    /// Aligns messages to the wall clock.
+    ///
+    /// Messages are delivered at instants when the time elapsed since the Unix epoch, minus
+    /// `offset`, is a multiple of the interval. For example, an interval of one minute and an
+    /// offset of 15 seconds fire at 15 seconds past every minute. Missed instants are skipped, so
+    /// if a message is received late, the next one is due at the next aligned instant.
+    ///
+    /// The wall clock is read once per message, so adjustments of the system time are taken into
+    /// account with a delay of at most one interval.
+    ///
+    /// Creating the channel with [`build`] then panics if the interval is zero.
+    ///
+    /// [`build`]: struct.TickBuilder.html#method.build
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    /// use crossbeam_channel::TickBuilder;
+    ///
+    /// let r = TickBuilder::new(Duration::from_millis(200))
+    ///     .align(Duration::from_millis(0))
+    ///     .build();
+    /// r.recv().unwrap();
+    ///
+    /// let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    /// let past_boundary = since_epoch.subsec_nanos() % 200_000_000;
+    /// assert!(past_boundary < 100_000_000);
+    /// ```
+    pub fn align(mut self, offset: Duration) -> TickBuilder {
+        self.schedule.align = Some(offset);
+        self
+    }
+
+    /// Delays each message by a random duration between zero and `max`.
+    ///
+    /// Delays are drawn independently for each message and don't accumulate: every message is due
+    /// at its usual time plus its own delay.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::TickBuilder;
+    ///
+    /// let start = Instant::now();
+    /// let r = TickBuilder::new(Duration::from_millis(100))
+    ///     .jitter(Duration::from_millis(50))
+    ///     .build();
+    ///
+    /// let msg = r.recv().unwrap();
+    /// assert!(msg >= start + Duration::from_millis(100));
+    /// assert!(msg <= start + Duration::from_millis(200));
+    /// ```
+    pub fn jitter(mut self, max: Duration) -> TickBuilder {
+        self.schedule.jitter = max;
+        self
+    }
+
+    /// Measures time with the given clock instead of the system clock.
+    ///
+    /// See [`tick_with_clock`] for details.
+    ///
+    /// [`tick_with_clock`]: fn.tick_with_clock.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{Clock, ManualClock, TickBuilder};
+    ///
+    /// let clock = ManualClock::new();
+    /// let start = clock.now();
+    /// let r = TickBuilder::new(Duration::from_secs(60))
+    ///     .jitter(Duration::from_secs(10))
+    ///     .clock(clock.clone())
+    ///     .build();
+    ///
+    /// clock.advance(Duration::from_secs(70));
+    /// let msg = r.try_recv().unwrap();
+    /// assert!(msg >= start + Duration::from_secs(60));
+    /// ```
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> TickBuilder {
+        self.time = Some(Arc::new(clock));
+        self
+    }
+
+    /// Creates the channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the messages are aligned to the wall clock and the interval is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::TickBuilder;
+    ///
+    /// let r = TickBuilder::new(Duration::from_millis(10)).build();
+    /// for _ in 0..3 {
+    ///     r.recv().unwrap();
+    /// }
+    /// ```
+    pub fn build(self) -> Receiver<Instant> {
+        if self.schedule.align.is_some() {
+            assert!(
+                self.duration != Duration::from_secs(0),
+                "aligned ticks need a non-zero interval"
+            );
+        }
+        channel::tick_with(self.duration, self.time, self.schedule)
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl fmt::Debug for TickBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TickBuilder")
+            .field("duration", &self.duration)
+            .field("align", &self.schedule.align)
+            .field("jitter", &self.schedule.jitter)
+            .finish()
+    }
+}
@@ -0,0 +1,383 @@
+//! A channel carrying a stream of bytes.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::sync::Arc;
+
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use err::SendError;
+
+/// Bytes in the channel and the state of its endpoints.
+struct Inner {
+    /// Bytes that were written but not read yet.
+    buf: VecDeque<u8>,
+
+    /// The number of senders.
+    senders: usize,
+
+    /// Equals `true` once the receiver is dropped.
+    is_disconnected: bool,
+}
+
+/// State shared by both sides of a byte channel.
+struct Pipe {
+    /// Bytes in the channel and the state of its endpoints.
+    inner: Mutex<Inner>,
+
+    /// The maximum number of bytes the channel can hold.
+    cap: usize,
+
+    /// Signaled when bytes are written or the last sender is dropped.
+    readable: Condvar,
+
+    /// Signaled when bytes are read or the receiver is dropped.
+    writable: Condvar,
+}
+
+impl Pipe {
+    /// Blocks until there are bytes to read or all senders are dropped, and returns the locked
+    /// state.
+    fn lock_readable<'a>(&'a self) -> MutexGuard<'a, Inner> {
+        let mut inner = self.inner.lock();
+        while inner.buf.is_empty() && inner.senders > 0 {
+            self.readable.wait(&mut inner);
+        }
+        inner
+    }
+}
+
+/// Creates a channel that carries a stream of bytes through a buffer of capacity `cap`.
+///
+/// The sending side implements [`Write`] and the receiving side implements [`Read`] and
+/// [`BufRead`], so the channel works as an in-process pipe. Unlike a channel of `Vec<u8>` chunks,
+/// bytes are copied into a ring buffer and the receiver reads them regardless of how they were
+/// split into writes.
+///
+/// Writing blocks while the buffer is full, and writes only as many bytes as fit into it. Reading
+/// blocks while the buffer is empty. Once all senders are dropped, the receiver reads the
+/// remaining bytes and then reaches the end of the stream. Once the receiver is dropped, writing
+/// fails with [`BrokenPipe`].
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+/// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+/// [`BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{BufRead, Write};
+/// use std::thread;
+/// use crossbeam_channel::io::byte_channel;
+///
+/// let (mut s, r) = byte_channel(16);
+///
+/// thread::spawn(move || {
+///     for i in 0..3 {
+///         writeln!(s, "line {}", i).unwrap();
+///     }
+/// });
+///
+/// let lines: Vec<String> = r.lines().map(|l| l.unwrap()).collect();
+/// assert_eq!(lines, ["line 0", "line 1", "line 2"]);
+/// ```
+pub fn byte_channel(cap: usize) -> (ByteSender, ByteReceiver) {
+    assert!(cap > 0, "capacity must be positive");
+
+    let pipe = Arc::new(Pipe {
+        inner: Mutex::new(Inner {
+            buf: VecDeque::with_capacity(cap),
+            senders: 1,
+            is_disconnected: false,
+        }),
+        cap,
+        readable: Condvar::new(),
+        writable: Condvar::new(),
+    });
+
+    let s = ByteSender { pipe: pipe.clone() };
+    let r = ByteReceiver {
+        pipe,
+        buf: Vec::new(),
+        pos: 0,
+    };
+    (s, r)
+}
+
+/// The sending side of a byte channel.
+///
+/// Senders can be cloned. Bytes of a single write are never interleaved with bytes of other
+/// writes, but [`write_all`] may need several writes to get all of its bytes through.
+///
+/// Senders are created by [`byte_channel`].
+///
+/// [`write_all`]: https://doc.rust-lang.org/std/io/trait.Write.html#method.write_all
+/// [`byte_channel`]: fn.byte_channel.html
+pub struct ByteSender {
+    /// State shared with the other side.
+    pipe: Arc<Pipe>,
+}
+
+impl ByteSender {
+    /// Returns the number of bytes in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (mut s, _r) = byte_channel(16);
+    /// s.write_all(b"hello").unwrap();
+    /// assert_eq!(s.len(), 5);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.pipe.inner.lock().buf.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (mut s, _r) = byte_channel(16);
+    /// assert!(s.is_empty());
+    ///
+    /// s.write_all(b"hello").unwrap();
+    /// assert!(!s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the channel is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (mut s, _r) = byte_channel(4);
+    /// assert_eq!(s.write(b"hello").unwrap(), 4);
+    /// assert!(s.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.len() == self.pipe.cap
+    }
+
+    /// Returns the maximum number of bytes the channel can hold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (s, _r) = byte_channel(4096);
+    /// assert_eq!(s.capacity(), 4096);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.pipe.cap
+    }
+}
+
+impl Write for ByteSender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let pipe = &*self.pipe;
+        let mut inner = pipe.inner.lock();
+
+        loop {
+            if inner.is_disconnected {
+                return Err(SendError(()).into());
+            }
+            if buf.is_empty() {
+                return Ok(0);
+            }
+
+            let n = cmp::min(buf.len(), pipe.cap - inner.buf.len());
+            if n > 0 {
+                inner.buf.extend(&buf[..n]);
+                pipe.readable.notify_one();
+                return Ok(n);
+            }
+            pipe.writable.wait(&mut inner);
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Written bytes are readable right away, so there is nothing to flush.
+        Ok(())
+    }
+}
+
+impl Drop for ByteSender {
+    fn drop(&mut self) {
+        let mut inner = self.pipe.inner.lock();
+        inner.senders -= 1;
+
+        if inner.senders == 0 {
+            self.pipe.readable.notify_all();
+        }
+    }
+}
+
+impl Clone for ByteSender {
+    fn clone(&self) -> ByteSender {
+        self.pipe.inner.lock().senders += 1;
+        ByteSender {
+            pipe: self.pipe.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for ByteSender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ByteSender").finish()
+    }
+}
+
+/// The receiving side of a byte channel.
+///
+/// Reading returns `Ok(0)`, the end of the stream, once all senders are dropped and the channel
+/// is empty.
+///
+/// Receivers are created by [`byte_channel`].
+///
+/// [`byte_channel`]: fn.byte_channel.html
+pub struct ByteReceiver {
+    /// State shared with the other side.
+    pipe: Arc<Pipe>,
+
+    /// Bytes taken out of the channel by `fill_buf`.
+    buf: Vec<u8>,
+
+    /// The number of bytes in `buf` that were already consumed.
+    pos: usize,
+}
+
+impl ByteReceiver {
+    /// Returns the number of bytes in the channel.
+    ///
+    /// Bytes already taken out of the channel by [`fill_buf`] are not counted.
+    ///
+    /// [`fill_buf`]: https://doc.rust-lang.org/std/io/trait.BufRead.html#tymethod.fill_buf
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (mut s, mut r) = byte_channel(16);
+    /// s.write_all(b"hello").unwrap();
+    ///
+    /// let mut buf = [0; 2];
+    /// r.read_exact(&mut buf).unwrap();
+    /// assert_eq!(r.len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.pipe.inner.lock().buf.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Bytes already taken out of the channel by [`fill_buf`] are not counted.
+    ///
+    /// [`fill_buf`]: https://doc.rust-lang.org/std/io/trait.BufRead.html#tymethod.fill_buf
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (mut s, r) = byte_channel(16);
+    /// assert!(r.is_empty());
+    ///
+    /// s.write_all(b"hello").unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of bytes the channel can hold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::io::byte_channel;
+    ///
+    /// let (_s, r) = byte_channel(4096);
+    /// assert_eq!(r.capacity(), 4096);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.pipe.cap
+    }
+}
+
+impl Read for ByteReceiver {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Bytes taken out of the channel by `fill_buf` come first.
+        if self.pos < self.buf.len() {
+            let n = cmp::min(buf.len(), self.buf.len() - self.pos);
+            buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.consume(n);
+            return Ok(n);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut inner = self.pipe.lock_readable();
+        let n = cmp::min(buf.len(), inner.buf.len());
+        for (dst, src) in buf.iter_mut().zip(inner.buf.drain(..n)) {
+            *dst = src;
+        }
+        self.pipe.writable.notify_all();
+        Ok(n)
+    }
+}
+
+impl BufRead for ByteReceiver {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+
+            let mut inner = self.pipe.lock_readable();
+            self.buf.extend(inner.buf.drain(..));
+            self.pipe.writable.notify_all();
+        }
+        Ok(&self.buf[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.buf.len());
+    }
+}
+
+impl Drop for ByteReceiver {
+    fn drop(&mut self) {
+        let mut inner = self.pipe.inner.lock();
+        inner.is_disconnected = true;
+        inner.buf.clear();
+        self.pipe.writable.notify_all();
+    }
+}
+
+impl fmt::Debug for ByteReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ByteReceiver").finish()
+    }
+}
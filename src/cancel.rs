@@ -0,0 +1,181 @@
+//! Cancellation of blocking operations.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use context::Context;
+use select::{Operation, SelectHandle, Token};
+use waker::SyncWaker;
+
+/// A token for cancelling blocking operations.
+///
+/// Blocking operations like [`Receiver::recv_cancellable`] and [`Sender::send_cancellable`] take a
+/// token and return early with a `Cancelled` error as soon as the token is cancelled. This makes
+/// it easy to interrupt threads blocked on channels during shutdown, without adding a separate
+/// shutdown channel to every blocking call.
+///
+/// Cancellation is permanent: once cancelled, a token stays cancelled, and every operation using
+/// it fails immediately. Cloning a token creates a new handle to the same token, so one token can
+/// be shared by many threads.
+///
+/// [`Receiver::recv_cancellable`]: struct.Receiver.html#method.recv_cancellable
+/// [`Sender::send_cancellable`]: struct.Sender.html#method.send_cancellable
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{unbounded, CancelToken, RecvCancelError};
+///
+/// let (s, r) = unbounded::<i32>();
+/// let token = CancelToken::new();
+///
+/// let t = token.clone();
+/// let handle = thread::spawn(move || r.recv_cancellable(&t));
+///
+/// thread::sleep(Duration::from_millis(100));
+/// token.cancel();
+///
+/// assert_eq!(handle.join().unwrap(), Err(RecvCancelError::Cancelled));
+/// # drop(s);
+/// ```
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+/// Inner representation of `CancelToken`.
+struct Inner {
+    /// Equals `true` if the token is cancelled.
+    is_cancelled: AtomicBool,
+
+    /// Operations waiting for the token to be cancelled.
+    waiters: SyncWaker,
+}
+
+impl CancelToken {
+    /// Creates a new token that is not cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::CancelToken;
+    ///
+    /// let token = CancelToken::new();
+    /// assert!(!token.is_cancelled());
+    /// ```
+    pub fn new() -> CancelToken {
+        CancelToken {
+            inner: Arc::new(Inner {
+                is_cancelled: AtomicBool::new(false),
+                waiters: SyncWaker::new(),
+            }),
+        }
+    }
+
+    /// Cancels the token and wakes up all operations blocked on it.
+    ///
+    /// Cancelling an already cancelled token has no effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, CancelToken, SendCancelError};
+    ///
+    /// let (s, r) = bounded(0);
+    /// let token = CancelToken::new();
+    ///
+    /// token.cancel();
+    /// assert_eq!(s.send_cancellable(1, &token), Err(SendCancelError::Cancelled(1)));
+    /// # drop(r);
+    /// ```
+    pub fn cancel(&self) {
+        if !self.inner.is_cancelled.swap(true, Ordering::SeqCst) {
+            self.inner.waiters.notify_all();
+        }
+    }
+
+    /// Returns `true` if the token is cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::CancelToken;
+    ///
+    /// let token = CancelToken::new();
+    /// let t = token.clone();
+    ///
+    /// t.cancel();
+    /// assert!(token.is_cancelled());
+    /// ```
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl SelectHandle for CancelToken {
+    fn try(&self, _token: &mut Token) -> bool {
+        self.is_cancelled()
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        self.is_cancelled()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.inner.waiters.register(oper, cx);
+        !self.is_cancelled()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.inner.waiters.unregister(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        self.is_cancelled()
+    }
+
+    fn state(&self) -> usize {
+        self.is_cancelled() as usize
+    }
+
+    fn is_ready(&self) -> bool {
+        self.is_cancelled()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.inner.waiters.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.inner.waiters.unwatch(oper);
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> CancelToken {
+        CancelToken::new()
+    }
+}
+
+impl Clone for CancelToken {
+    fn clone(&self) -> CancelToken {
+        CancelToken {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancelToken")
+            .field("is_cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
@@ -1,21 +1,63 @@
 //! The channel interface.
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::collections::VecDeque;
 use std::fmt;
 use std::isize;
 use std::iter::FusedIterator;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::iter::FromIterator;
 use std::mem;
-use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe, RefUnwindSafe, UnwindSafe};
 use std::process;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::{Duration, Instant};
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
 
+use builder::{Config, DropPolicy};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use builder::FullPolicy;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use cancel::CancelToken;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use clock::{Clock, Time};
 use context::Context;
-use err::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError};
+use err::{ReuniteError, SendError, TryRecvError, TrySendError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvCancelError, RecvError, RecvTimeoutError, SendCancelError, SendTimeoutError};
 use flavors;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use flavors::tick::Schedule;
+#[cfg(feature = "latency")]
+use latency::LatencyStats;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "registry")]
+use registry::{self, ChannelInfo, Flavor, Inspect};
 use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select;
+use shutdown::Member;
+use specialized::{ArrayReceiver, ArraySender, ListReceiver, ListSender};
+use spin::Spin;
+use split::{self, SplitReceiver};
+use stats::{BlockingStats, Recorder};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use tee;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use timeout::{self, TimeoutReceiver};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use utils::{self, Backoff};
 
-/// A channel in the form of one of the three different flavors.
+/// The number of messages `Sender::send_all` takes from its iterator at once when the channel is
+/// unbounded.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+const SEND_BATCH: usize = 32;
+
+/// A channel in the form of one of the four different flavors.
 pub struct Channel<T> {
     /// The number of senders associated with this channel.
     senders: AtomicUsize,
@@ -25,13 +67,33 @@ pub struct Channel<T> {
 
     /// This channel's flavor.
     flavor: ChannelFlavor<T>,
+
+    /// Decides whether blocking operations spin before parking.
+    spin: Arc<Spin>,
+
+    /// Settings this channel was created with.
+    config: Config<T>,
+
+    /// The number of messages that were left in the channel on disconnection.
+    pending_on_disconnect: AtomicUsize,
+
+    /// The number of messages that `DropPolicy::Forward` failed to forward.
+    lost_on_disconnect: AtomicUsize,
 }
 
+// Handles to a channel can be sent to other threads as long as its messages can, so the channel
+// itself is thread-safe under the same condition.
+unsafe impl<T: Send> Send for Channel<T> {}
+unsafe impl<T: Send> Sync for Channel<T> {}
+
 /// Channel flavors.
 enum ChannelFlavor<T> {
     /// Bounded channel based on a preallocated array.
     Array(flavors::array::Channel<T>),
 
+    /// Bounded channel with several priority lanes.
+    Lanes(flavors::lanes::Channel<T>),
+
     /// Unbounded channel implemented as a linked list.
     List(flavors::list::Channel<T>),
 
@@ -39,6 +101,218 @@ enum ChannelFlavor<T> {
     Zero(flavors::zero::Channel<T>),
 }
 
+impl<T> Channel<T> {
+    /// Disconnects the channel and wakes up all blocked operations.
+    fn disconnect(&self) {
+        match &self.flavor {
+            ChannelFlavor::Array(chan) => chan.disconnect(),
+            ChannelFlavor::Lanes(chan) => chan.disconnect(),
+            ChannelFlavor::List(chan) => chan.disconnect(),
+            ChannelFlavor::Zero(chan) => chan.disconnect(),
+        }
+    }
+
+    /// Returns `true` if the channel is disconnected.
+    fn is_disconnected(&self) -> bool {
+        match &self.flavor {
+            ChannelFlavor::Array(chan) => chan.is_disconnected(),
+            ChannelFlavor::Lanes(chan) => chan.is_disconnected(),
+            ChannelFlavor::List(chan) => chan.is_disconnected(),
+            ChannelFlavor::Zero(chan) => chan.is_disconnected(),
+        }
+    }
+
+    /// Returns an estimate of the number of bytes held by the channel.
+    fn memory_usage(&self) -> usize {
+        let heap = match &self.flavor {
+            ChannelFlavor::Array(chan) => chan.memory_usage(),
+            ChannelFlavor::Lanes(chan) => chan.memory_usage(),
+            ChannelFlavor::List(chan) => chan.memory_usage(),
+            ChannelFlavor::Zero(chan) => chan.memory_usage(),
+        };
+        mem::size_of::<Channel<T>>() + heap
+    }
+
+    /// Shows a message that is about to be sent to the send hook.
+    #[cfg(feature = "intercept")]
+    #[inline]
+    fn intercept_send(&self, msg: &T) {
+        if let Some(f) = &self.config.on_send {
+            f(msg);
+        }
+    }
+
+    /// Shows a message that is about to be sent to the send hook.
+    #[cfg(not(feature = "intercept"))]
+    #[inline]
+    fn intercept_send(&self, _msg: &T) {}
+
+    /// Shows a received message to the receive hook.
+    #[cfg(feature = "intercept")]
+    #[inline]
+    fn intercept_recv(&self, msg: &T) {
+        if let Some(f) = &self.config.on_recv {
+            f(msg);
+        }
+    }
+
+    /// Shows a received message to the receive hook.
+    #[cfg(not(feature = "intercept"))]
+    #[inline]
+    fn intercept_recv(&self, _msg: &T) {}
+
+    /// Attaches the channel to its shutdown group, if it has one.
+    fn attach(chan: &Arc<Channel<T>>) {
+        if let Some(group) = &chan.config.shutdown_group {
+            group.attach(chan);
+        }
+    }
+
+    /// Detaches the channel from its shutdown group, if it has one.
+    fn detach(&self) {
+        if let Some(group) = &self.config.shutdown_group {
+            group.detach(self);
+        }
+    }
+
+    /// Lets the channel's shutdown group know that the channel might have been drained.
+    #[inline]
+    fn notify_drained(&self) {
+        if let Some(group) = &self.config.shutdown_group {
+            group.notify(self);
+        }
+    }
+
+    /// Attempts to take a message out of the channel, bypassing hooks.
+    fn try_take(&self) -> Result<T, TryRecvError> {
+        match &self.flavor {
+            ChannelFlavor::Array(chan) => chan.try_recv(),
+            ChannelFlavor::Lanes(chan) => chan.try_recv(),
+            ChannelFlavor::List(chan) => chan.try_recv(),
+            ChannelFlavor::Zero(chan) => chan.try_recv(),
+        }
+    }
+
+    /// Takes all remaining messages out of the channel and disposes of them according to the
+    /// drop policy.
+    ///
+    /// This method must be called only after the channel gets disconnected.
+    fn drain(&self) {
+        if let DropPolicy::Keep = self.config.drop_policy {
+            return;
+        }
+        while let Ok(msg) = self.try_take() {
+            self.dispose(msg);
+        }
+    }
+
+    /// Disposes of a message that can't go into the channel anymore according to the drop policy.
+    ///
+    /// The message counts as left in the channel. `DropPolicy::Keep` has nowhere to keep it, so it
+    /// gets dropped.
+    fn dispose(&self, msg: T) {
+        self.pending_on_disconnect.fetch_add(1, Ordering::SeqCst);
+        match &self.config.drop_policy {
+            DropPolicy::Discard | DropPolicy::Keep => drop(msg),
+            DropPolicy::Handler(f) => {
+                // This usually runs inside `drop`, where propagating a panic would abort the
+                // process if the thread is already unwinding. Swallow handler panics so that the
+                // remaining messages still reach the handler.
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| f(msg)));
+            }
+            DropPolicy::Forward(s) => {
+                if s.try_send(msg).is_err() {
+                    self.lost_on_disconnect.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Member for Channel<T> {
+    fn shut_down(&self) {
+        self.disconnect();
+    }
+
+    fn is_drained(&self) -> bool {
+        if self.receivers.load(Ordering::SeqCst) == 0 {
+            return true;
+        }
+
+        match &self.flavor {
+            ChannelFlavor::Array(chan) => chan.is_empty(),
+            ChannelFlavor::Lanes(chan) => chan.is_empty(),
+            ChannelFlavor::List(chan) => chan.is_empty(),
+            ChannelFlavor::Zero(chan) => chan.is_empty(),
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+impl<T> Inspect for Channel<T> {
+    fn info(&self) -> ChannelInfo {
+        let (flavor, len, capacity, is_disconnected, waiting) = match &self.flavor {
+            ChannelFlavor::Array(chan) => (
+                Flavor::Array,
+                chan.len(),
+                chan.capacity(),
+                chan.is_disconnected(),
+                chan.waiting(),
+            ),
+            ChannelFlavor::Lanes(chan) => (
+                Flavor::Lanes,
+                chan.len(),
+                chan.capacity(),
+                chan.is_disconnected(),
+                chan.waiting(),
+            ),
+            ChannelFlavor::List(chan) => (
+                Flavor::List,
+                chan.len(),
+                chan.capacity(),
+                chan.is_disconnected(),
+                chan.waiting(),
+            ),
+            ChannelFlavor::Zero(chan) => (
+                Flavor::Zero,
+                chan.len(),
+                chan.capacity(),
+                chan.is_disconnected(),
+                chan.waiting(),
+            ),
+        };
+
+        ChannelInfo {
+            name: self.config.name.clone(),
+            flavor,
+            capacity,
+            len,
+            senders: self.senders.load(Ordering::SeqCst),
+            receivers: self.receivers.load(Ordering::SeqCst),
+            waiting_senders: waiting.0,
+            waiting_receivers: waiting.1,
+            is_disconnected,
+        }
+    }
+}
+
+impl<T> Drop for Channel<T> {
+    fn drop(&mut self) {
+        // Make sure the channel is not inspected while it is being destroyed.
+        #[cfg(feature = "registry")]
+        registry::unregister(self);
+        self.detach();
+
+        // A sender that was in the middle of an operation while the channel was being
+        // disconnected might have managed to push one last message. Make sure the drop policy
+        // sees it too.
+        match &self.config.drop_policy {
+            DropPolicy::Discard => {}
+            _ => self.drain(),
+        }
+    }
+}
+
 /// Creates a channel of unbounded capacity.
 ///
 /// This channel has a growable buffer that can hold any number of messages at a time.
@@ -67,15 +341,15 @@ enum ChannelFlavor<T> {
 /// println!("{}", r.recv().unwrap());
 /// ```
 pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
-    let chan = Arc::new(Channel {
-        senders: AtomicUsize::new(0),
-        receivers: AtomicUsize::new(0),
-        flavor: ChannelFlavor::List(flavors::list::Channel::new()),
-    });
+    unbounded_with(Config::default())
+}
 
-    let s = Sender::new(chan.clone());
-    let r = Receiver::new(chan);
-    (s, r)
+/// Creates a channel of unbounded capacity with the given settings.
+pub fn unbounded_with<T>(config: Config<T>) -> (Sender<T>, Receiver<T>) {
+    let flavor = ChannelFlavor::List(
+        flavors::list::Channel::new(config.block_pool).with_wake_policy(config.wake_policy),
+    );
+    new_channel(flavor, config)
 }
 
 /// Creates a channel of bounded capacity.
@@ -133,17 +407,149 @@ pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
 /// assert_eq!(r.recv(), Ok(1));
 /// ```
 pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
-    let chan = Arc::new(Channel {
+    bounded_with(cap, Config::default())
+}
+
+/// Creates a channel of bounded capacity with the given settings.
+pub fn bounded_with<T>(cap: usize, config: Config<T>) -> (Sender<T>, Receiver<T>) {
+    let flavor = if cap == 0 {
+        ChannelFlavor::Zero(flavors::zero::Channel::new().with_wake_policy(config.wake_policy))
+    } else {
+        ChannelFlavor::Array(
+            flavors::array::Channel::with_capacity(cap).with_wake_policy(config.wake_policy),
+        )
+    };
+    new_channel(flavor, config)
+}
+
+/// Creates a channel of bounded capacity with several priority lanes.
+///
+/// This channel has a buffer that can hold at most `cap` messages at a time, shared by `lanes`
+/// lanes. Messages are sent into a lane with [`Sender::send_with_priority`], and receive operations
+/// always take the oldest message from the highest non-empty lane, so lane `lanes - 1` has the
+/// highest priority. Ordinary send operations, including the ones in [`select!`], send into the
+/// lowest lane, lane 0.
+///
+/// Since all lanes share the capacity, senders are blocked once the channel is full, no matter
+/// which lane they are sending into. A single receiver can be used in [`select!`] like any other.
+///
+/// [`Sender::send_with_priority`]: struct.Sender.html#method.send_with_priority
+/// [`select!`]: macro.select.html
+///
+/// # Panics
+///
+/// Panics if the capacity or the number of lanes is zero.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::lanes;
+///
+/// let (s, r) = lanes(10, 3);
+///
+/// s.send("routine").unwrap();
+/// s.send_with_priority("urgent", 2).unwrap();
+/// s.send_with_priority("important", 1).unwrap();
+///
+/// assert_eq!(r.recv(), Ok("urgent"));
+/// assert_eq!(r.recv(), Ok("important"));
+/// assert_eq!(r.recv(), Ok("routine"));
+/// ```
+pub fn lanes<T>(cap: usize, lanes: usize) -> (Sender<T>, Receiver<T>) {
+    lanes_with(cap, lanes, Config::default())
+}
+
+/// Creates a channel of bounded capacity with several priority lanes and the given settings.
+pub fn lanes_with<T>(cap: usize, lanes: usize, config: Config<T>) -> (Sender<T>, Receiver<T>) {
+    let flavor = ChannelFlavor::Lanes(
+        flavors::lanes::Channel::new(cap, lanes).with_wake_policy(config.wake_policy),
+    );
+    new_channel(flavor, config)
+}
+
+/// Creates a channel bounded by the total weight of the messages in it.
+///
+/// Every message is weighed with `weigh` when it is sent, and send operations block while the
+/// total weight of queued messages is at least `max_weight`. This is useful when messages vary
+/// wildly in size, so that a limit on their number would be either too strict for small messages
+/// or too lenient for large ones.
+///
+/// A send operation only waits until the total weight drops below `max_weight`, not until there
+/// is room for its whole message. That way, a message heavier than `max_weight` doesn't block
+/// forever, but the total weight may exceed the limit by the messages sent at the moment it is
+/// reached.
+///
+/// The function `weigh` is called again when the message is received, so it must always return
+/// the same weight for the same message. It must not perform operations on this channel.
+///
+/// The [`capacity`] of the channel is `max_weight`, while [`len`] still counts messages.
+///
+/// [`capacity`]: struct.Sender.html#method.capacity
+/// [`len`]: struct.Sender.html#method.len
+///
+/// # Panics
+///
+/// Panics if `max_weight` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{bounded_by_weight, TrySendError};
+///
+/// let (s, r) = bounded_by_weight(100, |msg: &Vec<u8>| msg.len());
+///
+/// s.send(vec![0; 60]).unwrap();
+/// s.send(vec![0; 60]).unwrap();
+///
+/// // The queued messages weigh 120, which is over the limit.
+/// assert!(s.is_full());
+/// assert_eq!(s.try_send(vec![0; 1]), Err(TrySendError::Full(vec![0; 1])));
+///
+/// // Receiving one message brings the weight back under the limit.
+/// r.recv().unwrap();
+/// s.try_send(vec![0; 1]).unwrap();
+/// assert_eq!(s.len(), 2);
+/// ```
+pub fn bounded_by_weight<T, F>(max_weight: usize, weigh: F) -> (Sender<T>, Receiver<T>)
+where
+    F: Fn(&T) -> usize + Send + Sync + 'static,
+{
+    bounded_by_weight_with(max_weight, Box::new(weigh), Config::default())
+}
+
+/// Creates a channel bounded by the total weight of the messages in it with the given settings.
+pub fn bounded_by_weight_with<T>(
+    max_weight: usize,
+    weigher: flavors::lanes::Weigher<T>,
+    config: Config<T>,
+) -> (Sender<T>, Receiver<T>) {
+    let flavor = ChannelFlavor::Lanes(
+        flavors::lanes::Channel::with_weigher(max_weight, 1, Some(weigher))
+            .with_wake_policy(config.wake_policy),
+    );
+    new_channel(flavor, config)
+}
+
+/// Wraps a channel flavor into a new channel and returns its first sender and receiver.
+fn new_channel<T>(flavor: ChannelFlavor<T>, config: Config<T>) -> (Sender<T>, Receiver<T>) {
+    let chan = Channel {
         senders: AtomicUsize::new(0),
         receivers: AtomicUsize::new(0),
-        flavor: {
-            if cap == 0 {
-                ChannelFlavor::Zero(flavors::zero::Channel::new())
-            } else {
-                ChannelFlavor::Array(flavors::array::Channel::with_capacity(cap))
-            }
-        },
-    });
+        flavor,
+        spin: Arc::new(Spin::new(config.adaptive_spin)),
+        config,
+        pending_on_disconnect: AtomicUsize::new(0),
+        lost_on_disconnect: AtomicUsize::new(0),
+    };
+    share(chan)
+}
+
+/// Makes a channel with no handles reachable and returns its first sender and receiver.
+fn share<T>(chan: Channel<T>) -> (Sender<T>, Receiver<T>) {
+    let chan = Arc::new(chan);
+    #[cfg(feature = "registry")]
+    registry::register(&*chan);
+    Channel::attach(&chan);
 
     let s = Sender::new(chan.clone());
     let r = Receiver::new(chan);
@@ -156,6 +562,10 @@ pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
 /// be sent into the channel after `duration` elapses. The message is the instant at which it is
 /// sent.
 ///
+/// Select operations blocked on timer channels are woken up by a single background thread that
+/// keeps all timers in a shared timer wheel, so selecting over many timers stays cheap. Timers
+/// never fire early, but may fire up to a millisecond late.
+///
 /// # Examples
 ///
 /// Using an `after` channel for timeouts:
@@ -199,9 +609,11 @@ pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
 /// assert!(eq(r.recv().unwrap(), start + ms(100)));
 /// assert!(eq(Instant::now(), start + ms(500)));
 /// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub fn after(duration: Duration) -> Receiver<Instant> {
     Receiver {
-        flavor: ReceiverFlavor::After(flavors::after::Channel::new(duration)),
+        flavor: ReceiverFlavor::After(flavors::after::Channel::new(duration, None)),
+        stats: Recorder::new(),
     }
 }
 
@@ -247,6 +659,7 @@ pub fn after(duration: Duration) -> Receiver<Instant> {
 pub fn never<T>() -> Receiver<T> {
     Receiver {
         flavor: ReceiverFlavor::Never(flavors::never::Channel::new()),
+        stats: Recorder::new(),
     }
 }
 
@@ -256,6 +669,11 @@ pub fn never<T>() -> Receiver<T> {
 /// sent into the channel in intervals of `duration`. Each message is the instant at which it is
 /// sent.
 ///
+/// Like [`after`], a select operation blocked on this channel is woken up by the shared timer
+/// wheel.
+///
+/// [`after`]: fn.after.html
+///
 /// # Examples
 ///
 /// Using a `tick` channel to periodically print elapsed time:
@@ -303,9 +721,93 @@ pub fn never<T>() -> Receiver<T> {
 /// assert!(eq(r.recv().unwrap(), start + ms(700)));
 /// assert!(eq(Instant::now(), start + ms(700)));
 /// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub fn tick(duration: Duration) -> Receiver<Instant> {
     Receiver {
-        flavor: ReceiverFlavor::Tick(flavors::tick::Channel::new(duration)),
+        flavor: ReceiverFlavor::Tick(flavors::tick::Channel::new(duration, None)),
+        stats: Recorder::new(),
+    }
+}
+
+/// Creates a receiver that delivers messages periodically, measuring time with `time` and
+/// aligning and jittering messages as scheduled.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn tick_with(duration: Duration, time: Time, schedule: Schedule) -> Receiver<Instant> {
+    Receiver {
+        flavor: ReceiverFlavor::Tick(flavors::tick::Channel::with_schedule(
+            duration,
+            time,
+            schedule,
+        )),
+        stats: Recorder::new(),
+    }
+}
+
+/// Creates a receiver that delivers a message after a certain duration of time, as measured by
+/// the given clock.
+///
+/// This works just like [`after`], except that time is read from `clock` instead of the system
+/// clock. With a [`ManualClock`], the message is delivered as soon as the clock is advanced past
+/// the deadline, no matter how much real time has passed.
+///
+/// [`after`]: fn.after.html
+/// [`ManualClock`]: struct.ManualClock.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{after_with_clock, Clock, ManualClock, TryRecvError};
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// let r = after_with_clock(Duration::from_secs(10), clock.clone());
+///
+/// clock.advance(Duration::from_secs(9));
+/// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+///
+/// clock.advance(Duration::from_secs(1));
+/// assert_eq!(r.try_recv(), Ok(start + Duration::from_secs(10)));
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn after_with_clock<C: Clock + 'static>(duration: Duration, clock: C) -> Receiver<Instant> {
+    Receiver {
+        flavor: ReceiverFlavor::After(flavors::after::Channel::new(
+            duration,
+            Some(Arc::new(clock)),
+        )),
+        stats: Recorder::new(),
+    }
+}
+
+/// Creates a receiver that delivers messages periodically, as measured by the given clock.
+///
+/// This works just like [`tick`], except that time is read from `clock` instead of the system
+/// clock.
+///
+/// [`tick`]: fn.tick.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{tick_with_clock, Clock, ManualClock, TryRecvError};
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// let r = tick_with_clock(Duration::from_secs(1), clock.clone());
+///
+/// for i in 1..4 {
+///     assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+///     clock.advance(Duration::from_secs(1));
+///     assert_eq!(r.try_recv(), Ok(start + Duration::from_secs(i)));
+/// }
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn tick_with_clock<C: Clock + 'static>(duration: Duration, clock: C) -> Receiver<Instant> {
+    Receiver {
+        flavor: ReceiverFlavor::Tick(flavors::tick::Channel::new(duration, Some(Arc::new(clock)))),
+        stats: Recorder::new(),
     }
 }
 
@@ -330,6 +832,7 @@ pub fn tick(duration: Duration) -> Receiver<Instant> {
 /// ```
 pub struct Sender<T> {
     inner: Arc<Channel<T>>,
+    stats: Recorder,
 }
 
 unsafe impl<T: Send> Send for Sender<T> {}
@@ -350,7 +853,10 @@ impl<T> Sender<T> {
             process::abort();
         }
 
-        Sender { inner: chan }
+        Sender {
+            stats: Recorder::with_spin(chan.spin.clone()),
+            inner: chan,
+        }
     }
 
     /// Attempts to send a message into the channel without blocking.
@@ -375,453 +881,3195 @@ impl<T> Sender<T> {
     /// assert_eq!(s.try_send(3), Err(TrySendError::Disconnected(3)));
     /// ```
     pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.inner.intercept_send(&msg);
+        self.try_send_unhooked(msg)
+    }
+
+    /// Attempts to send a message into the channel without blocking or calling the send hook.
+    fn try_send_unhooked(&self, msg: T) -> Result<(), TrySendError<T>> {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.try_send(msg),
+            ChannelFlavor::Lanes(chan) => chan.try_send(msg, 0),
             ChannelFlavor::List(chan) => chan.try_send(msg),
             ChannelFlavor::Zero(chan) => chan.try_send(msg),
         }
     }
 
-    /// Blocks the current thread until a message is sent or the channel is disconnected.
+    /// Attempts to hand a message over to a receive operation that is already blocked waiting
+    /// for one.
     ///
-    /// If the channel is full and not disconnected, this call will block until the send operation
-    /// can proceed. If the channel becomes disconnected, this call will wake up and return an
-    /// error. The returned error contains the original message.
+    /// Unlike [`try_send`], this method never fills the buffer of a channel that nobody is waiting
+    /// on: the message is sent only if some receiver is currently blocked on the channel, and
+    /// otherwise a [`Full`] error is returned. This makes it possible to offload work to a helper
+    /// thread only while it is idle, without ever blocking or spinning.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a receive operation to
-    /// appear on the other side of the channel.
+    /// On a zero-capacity channel, this method behaves exactly like [`try_send`].
+    ///
+    /// [`try_send`]: struct.Sender.html#method.try_send
+    /// [`Full`]: enum.TrySendError.html#variant.Full
     ///
     /// # Examples
     ///
     /// ```
     /// use std::thread;
     /// use std::time::Duration;
-    /// use crossbeam_channel::{bounded, SendError};
+    /// use crossbeam_channel::{unbounded, TrySendError};
     ///
-    /// let (s, r) = bounded(1);
-    /// assert_eq!(s.send(1), Ok(()));
+    /// let (s, r) = unbounded();
     ///
-    /// thread::spawn(move || {
-    ///     assert_eq!(r.recv(), Ok(1));
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     drop(r);
-    /// });
+    /// // Nobody is waiting, so the message is not sent.
+    /// assert_eq!(s.send_now(1), Err(TrySendError::Full(1)));
+    /// assert!(s.is_empty());
     ///
-    /// assert_eq!(s.send(2), Ok(()));
-    /// assert_eq!(s.send(3), Err(SendError(3)));
+    /// let t = thread::spawn(move || r.recv());
+    /// thread::sleep(Duration::from_millis(100));
+    ///
+    /// // The helper thread is blocked waiting for a message.
+    /// assert_eq!(s.send_now(2), Ok(()));
+    /// assert_eq!(t.join().unwrap(), Ok(2));
     /// ```
-    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
-        match &self.inner.flavor {
-            ChannelFlavor::Array(chan) => chan.send(msg, None),
-            ChannelFlavor::List(chan) => chan.send(msg, None),
-            ChannelFlavor::Zero(chan) => chan.send(msg, None),
-        }.map_err(|err| {
-            match err {
-                SendTimeoutError::Disconnected(msg) => SendError(msg),
-                SendTimeoutError::Timeout(_) => unreachable!(),
-            }
-        })
+    pub fn send_now(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.inner.intercept_send(&msg);
+        let (has_receivers, is_disconnected) = match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => (chan.waiting().1 > 0, chan.is_disconnected()),
+            ChannelFlavor::Lanes(chan) => (chan.waiting().1 > 0, chan.is_disconnected()),
+            ChannelFlavor::List(chan) => (chan.waiting().1 > 0, chan.is_disconnected()),
+            ChannelFlavor::Zero(chan) => return chan.try_send(msg),
+        };
+
+        if has_receivers {
+            self.try_send_unhooked(msg)
+        } else if is_disconnected {
+            Err(TrySendError::Disconnected(msg))
+        } else {
+            Err(TrySendError::Full(msg))
+        }
     }
 
-    /// Waits for a message to be sent into the channel, but only for a limited time.
+    /// Blocks the current thread until a message could be sent without blocking.
     ///
-    /// If the channel is full and not disconnected, this call will block until the send operation
-    /// can proceed or the operation times out. If the channel becomes disconnected, this call will
-    /// wake up and return an error. The returned error contains the original message.
+    /// This method blocks until the channel has room for a message, or a receive operation is
+    /// waiting on the other side in the case of a zero-capacity channel, without sending
+    /// anything. Unlike [`reserve`], it doesn't claim the room, so it is cheaper and doesn't hold
+    /// back other senders, but another sender might take the room before the current thread gets
+    /// to use it and a following [`try_send`] can still fail.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a receive operation to
-    /// appear on the other side of the channel.
+    /// If the channel is disconnected, an error is returned.
+    ///
+    /// [`reserve`]: struct.Sender.html#method.reserve
+    /// [`try_send`]: struct.Sender.html#method.try_send
     ///
     /// # Examples
     ///
     /// ```
     /// use std::thread;
     /// use std::time::Duration;
-    /// use crossbeam_channel::{bounded, SendTimeoutError};
+    /// use crossbeam_channel::{bounded, SendError, TrySendError};
     ///
-    /// let (s, r) = bounded(0);
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
     ///
-    /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     assert_eq!(r.recv(), Ok(2));
-    ///     drop(r);
+    /// let t = thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     r.recv().unwrap();
+    ///     r
     /// });
     ///
-    /// assert_eq!(
-    ///     s.send_timeout(1, Duration::from_millis(500)),
-    ///     Err(SendTimeoutError::Timeout(1)),
-    /// );
-    /// assert_eq!(
-    ///     s.send_timeout(2, Duration::from_secs(1)),
-    ///     Ok(()),
-    /// );
-    /// assert_eq!(
-    ///     s.send_timeout(3, Duration::from_millis(500)),
-    ///     Err(SendTimeoutError::Disconnected(3)),
-    /// );
+    /// // Instead of retrying `try_send` in a loop, wait until there is room.
+    /// let mut msg = 2;
+    /// loop {
+    ///     assert_eq!(s.ready(), Ok(()));
+    ///     match s.try_send(msg) {
+    ///         Ok(()) => break,
+    ///         Err(TrySendError::Full(m)) => msg = m,
+    ///         Err(TrySendError::Disconnected(_)) => unreachable!(),
+    ///     }
+    /// }
+    ///
+    /// let r = t.join().unwrap();
+    /// assert_eq!(r.recv(), Ok(2));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.ready(), Err(SendError(())));
     /// ```
-    pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
-        let deadline = Instant::now() + timeout;
-
-        match &self.inner.flavor {
-            ChannelFlavor::Array(chan) => chan.send(msg, Some(deadline)),
-            ChannelFlavor::List(chan) => chan.send(msg, Some(deadline)),
-            ChannelFlavor::Zero(chan) => chan.send(msg, Some(deadline)),
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn ready(&self) -> Result<(), SendError<()>> {
+        self.wait_ready(self.waiting_policy(), None);
+        if self.inner.is_disconnected() {
+            Err(SendError(()))
+        } else {
+            Ok(())
         }
     }
 
-    /// Returns `true` if the channel is empty.
+    /// Waits until a message could be sent without blocking, but only for a limited time.
     ///
-    /// Note: Zero-capacity channels are always empty.
+    /// This method blocks until the channel has room for a message, or a receive operation is
+    /// waiting on the other side in the case of a zero-capacity channel, without sending
+    /// anything. Another sender might take the room before the current thread gets to use it, so
+    /// a following [`try_send`] can still fail.
+    ///
+    /// If the channel is disconnected, a [`Disconnected`] error is returned. If the timeout
+    /// elapses first, a [`Timeout`] error is returned.
+    ///
+    /// [`try_send`]: struct.Sender.html#method.try_send
+    /// [`Disconnected`]: enum.SendTimeoutError.html#variant.Disconnected
+    /// [`Timeout`]: enum.SendTimeoutError.html#variant.Timeout
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::unbounded;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendTimeoutError};
     ///
-    /// let (s, r) = unbounded();
-    /// assert!(s.is_empty());
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
     ///
-    /// s.send(0).unwrap();
-    /// assert!(!s.is_empty());
+    /// assert_eq!(
+    ///     s.wait_capacity(Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout(())),
+    /// );
+    ///
+    /// let t = thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     r.recv().unwrap();
+    ///     r
+    /// });
+    ///
+    /// // Prepare the message only once there is room for it.
+    /// assert_eq!(s.wait_capacity(Duration::from_secs(1)), Ok(()));
+    /// assert_eq!(s.try_send(2), Ok(()));
+    ///
+    /// let r = t.join().unwrap();
+    /// assert_eq!(r.recv(), Ok(2));
     /// ```
-    pub fn is_empty(&self) -> bool {
-        match &self.inner.flavor {
-            ChannelFlavor::Array(chan) => chan.is_empty(),
-            ChannelFlavor::List(chan) => chan.is_empty(),
-            ChannelFlavor::Zero(chan) => chan.is_empty(),
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait_capacity(&self, timeout: Duration) -> Result<(), SendTimeoutError<()>> {
+        let deadline = Instant::now() + timeout;
+        if !self.wait_ready(self.full_policy(), Some(deadline)) {
+            Err(SendTimeoutError::Timeout(()))
+        } else if self.inner.is_disconnected() {
+            Err(SendTimeoutError::Disconnected(()))
+        } else {
+            Ok(())
         }
     }
 
-    /// Returns `true` if the channel is full.
+    /// Blocks the current thread until all messages sent into the channel so far have been
+    /// received.
     ///
-    /// Note: Zero-capacity channels are always full.
+    /// This covers every message sent before the call, by this sender or any other, so once it
+    /// returns, the receiving side has caught up with the sending side. Messages sent while this
+    /// method is waiting are not waited for.
     ///
-    /// # Examples
+    /// A message counts as received as soon as a receive operation takes it out of the channel.
+    /// Messages set aside by selective receive operations like [`recv_where`] count as received,
+    /// and so do messages displaced by [`send_lossy`]. When the last receiver is dropped, the
+    /// remaining messages can never be received and this method returns as well. Use
+    /// [`pending_on_disconnect`] to find out how many messages were left behind.
+    ///
+    /// If called on a zero-capacity channel, this method returns immediately, since every sent
+    /// message has already been received.
+    ///
+    /// [`recv_where`]: struct.Receiver.html#method.recv_where
+    /// [`send_lossy`]: struct.Sender.html#method.send_lossy
+    /// [`pending_on_disconnect`]: struct.Sender.html#method.pending_on_disconnect
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     for msg in r {
+    ///         thread::sleep(Duration::from_millis(10));
+    ///         println!("{}", msg);
+    ///     }
+    /// });
+    ///
+    /// for i in 0..10 {
+    ///     s.send(i).unwrap();
+    /// }
+    ///
+    /// // Wait for the consumer to catch up.
+    /// s.flush();
+    /// assert!(s.is_empty());
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn flush(&self) {
+        let park = self.waiting_policy() == FullPolicy::Park;
+        let flush = || match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.flush(park),
+            ChannelFlavor::Lanes(chan) => chan.flush(park),
+            ChannelFlavor::List(chan) => chan.flush(park),
+            ChannelFlavor::Zero(_) => {}
+        };
+
+        if park {
+            self.stats.record(flush)
+        } else {
+            flush()
+        }
+    }
+
+    /// Blocks the current thread until a message is sent or the channel is disconnected.
+    ///
+    /// If the channel is full and not disconnected, this call will block until the send operation
+    /// can proceed. If the channel becomes disconnected, this call will wake up and return an
+    /// error. The returned error contains the original message.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a receive operation to
+    /// appear on the other side of the channel.
+    ///
+    /// How the operation waits on a full channel can be changed with [`Builder::full_policy`].
+    /// Under [`FullPolicy::Fail`], it doesn't wait at all and returns the message in the error if
+    /// the channel is full, just like it does if the channel is disconnected. Use
+    /// [`send_by_policy`] to tell the two cases apart.
+    ///
+    /// [`Builder::full_policy`]: struct.Builder.html#method.full_policy
+    /// [`FullPolicy::Fail`]: enum.FullPolicy.html#variant.Fail
+    /// [`send_by_policy`]: struct.Sender.html#method.send_by_policy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// assert_eq!(s.send(1), Ok(()));
+    ///
+    /// thread::spawn(move || {
+    ///     assert_eq!(r.recv(), Ok(1));
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     drop(r);
+    /// });
+    ///
+    /// assert_eq!(s.send(2), Ok(()));
+    /// assert_eq!(s.send(3), Err(SendError(3)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.inner.intercept_send(&msg);
+        self.send_unhooked(msg, None, self.full_policy())
+            .map_err(|err| SendError(err.into_inner()))
+    }
+
+    /// Sends a message into the channel the way the channel's [`FullPolicy`] prescribes.
+    ///
+    /// Under [`FullPolicy::Park`] and [`FullPolicy::SpinYield`], this method waits for room just
+    /// like [`send`] and fails only if the channel is disconnected. Under [`FullPolicy::Fail`], it
+    /// fails right away with [`TrySendError::Full`] if the channel is full, just like
+    /// [`try_send`]. This way the behavior on a full channel is decided where the channel is
+    /// created rather than at every call site.
+    ///
+    /// [`FullPolicy`]: enum.FullPolicy.html
+    /// [`FullPolicy::Park`]: enum.FullPolicy.html#variant.Park
+    /// [`FullPolicy::SpinYield`]: enum.FullPolicy.html#variant.SpinYield
+    /// [`FullPolicy::Fail`]: enum.FullPolicy.html#variant.Fail
+    /// [`TrySendError::Full`]: enum.TrySendError.html#variant.Full
+    /// [`send`]: struct.Sender.html#method.send
+    /// [`try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, FullPolicy, TrySendError};
+    ///
+    /// let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+    ///
+    /// assert_eq!(s.send_by_policy(1), Ok(()));
+    /// assert_eq!(s.send_by_policy(2), Err(TrySendError::Full(2)));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send_by_policy(3), Err(TrySendError::Disconnected(3)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_by_policy(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.inner.intercept_send(&msg);
+        self.send_unhooked(msg, None, self.full_policy())
+            .map_err(|err| match err {
+                SendTimeoutError::Timeout(msg) => TrySendError::Full(msg),
+                SendTimeoutError::Disconnected(msg) => TrySendError::Disconnected(msg),
+            })
+    }
+
+    /// Sends all messages from an iterator into the channel, blocking whenever it is full.
+    ///
+    /// Messages are sent in order. As many messages as there is room for are taken from the
+    /// iterator and pushed into the channel at once, which is cheaper than sending them one by
+    /// one. If the channel becomes disconnected, sending stops and an error containing the
+    /// messages that were taken from the iterator but couldn't be sent is returned, in order. The
+    /// rest of the iterator is left untouched, so passing `iter.by_ref()` keeps the unsent messages
+    /// available to the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(3);
+    /// assert_eq!(s.send_all(vec![1, 2, 3]), Ok(()));
+    /// assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2, 3]);
+    ///
+    /// drop(r);
+    /// let mut iter = 4..10;
+    /// assert_eq!(s.send_all(iter.by_ref()), Err(SendError(vec![4])));
+    /// assert_eq!(iter.next(), Some(5));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_all<I>(&self, iter: I) -> Result<(), SendError<Vec<T>>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut iter = iter.into_iter();
+        let mut batch = VecDeque::new();
+
+        loop {
+            // Take as many messages as there is room for, but at least one.
+            let room = self.room().max(1);
+            for msg in iter.by_ref().take(room) {
+                self.inner.intercept_send(&msg);
+                batch.push_back(msg);
+            }
+            if batch.is_empty() {
+                return Ok(());
+            }
+
+            // Push the batch into the channel, blocking on the first message whenever it doesn't
+            // fit in.
+            while !batch.is_empty() {
+                if self.try_send_many(&mut batch) == 0 {
+                    let msg = batch.pop_front().unwrap();
+                    if let Err(err) = self.send_unhooked(msg, None, self.full_policy()) {
+                        // Hand back the failed message together with the rest of the batch.
+                        let mut unsent = Vec::with_capacity(batch.len() + 1);
+                        unsent.push(err.into_inner());
+                        unsent.extend(batch);
+                        return Err(SendError(unsent));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the number of messages that can be sent into the channel right now.
+    ///
+    /// Unbounded channels report room for `SEND_BATCH` messages.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn room(&self) -> usize {
+        if self.inner.is_disconnected() {
+            return 0;
+        }
+        match self.capacity() {
+            Some(cap) => cap.saturating_sub(self.len()),
+            None => SEND_BATCH,
+        }
+    }
+
+    /// Attempts to send messages from the front of `msgs` without blocking or calling the send
+    /// hook.
+    ///
+    /// Sent messages are removed from `msgs`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn try_send_many(&self, msgs: &mut VecDeque<T>) -> usize {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.try_send_many(msgs),
+            ChannelFlavor::Lanes(chan) => chan.try_send_many(msgs, 0),
+            ChannelFlavor::List(chan) => chan.try_send_many(msgs),
+            // Messages are handed over one by one, so they are sent by blocking.
+            ChannelFlavor::Zero(_) => 0,
+        }
+    }
+
+    /// Busy-waits for a message to be sent into the channel, and then blocks if necessary.
+    ///
+    /// This method repeatedly attempts to send the message without blocking, spinning for up to
+    /// `max_spins` iterations. If the message still couldn't be sent by then, it falls back to
+    /// [`send`]. Spinning avoids the cost of parking and unparking the thread when the channel is
+    /// expected to have space again very soon, at the expense of burning CPU time.
+    ///
+    /// [`send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// assert_eq!(s.spin_send(1, 100), Ok(()));
+    ///
+    /// thread::spawn(move || {
+    ///     assert_eq!(r.recv(), Ok(1));
+    ///     assert_eq!(r.recv(), Ok(2));
+    /// });
+    ///
+    /// assert_eq!(s.spin_send(2, 1000), Ok(()));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn spin_send(&self, mut msg: T, max_spins: usize) -> Result<(), SendError<T>> {
+        self.inner.intercept_send(&msg);
+        let mut backoff = Backoff::new();
+        for _ in 0..max_spins {
+            match self.try_send_unhooked(msg) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(m)) => return Err(SendError(m)),
+                Err(TrySendError::Full(m)) => msg = m,
+            }
+            backoff.spin();
+        }
+        self.send_unhooked(msg, None, self.full_policy())
+            .map_err(|err| SendError(err.into_inner()))
+    }
+
+    /// Waits for a message to be sent into the channel, but only for a limited time.
+    ///
+    /// If the channel is full and not disconnected, this call will block until the send operation
+    /// can proceed or the operation times out. If the channel becomes disconnected, this call will
+    /// wake up and return an error. The returned error contains the original message.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a receive operation to
+    /// appear on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendTimeoutError};
+    ///
+    /// let (s, r) = bounded(0);
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     assert_eq!(r.recv(), Ok(2));
+    ///     drop(r);
+    /// });
+    ///
+    /// assert_eq!(
+    ///     s.send_timeout(1, Duration::from_millis(500)),
+    ///     Err(SendTimeoutError::Timeout(1)),
+    /// );
+    /// assert_eq!(
+    ///     s.send_timeout(2, Duration::from_secs(1)),
+    ///     Ok(()),
+    /// );
+    /// assert_eq!(
+    ///     s.send_timeout(3, Duration::from_millis(500)),
+    ///     Err(SendTimeoutError::Disconnected(3)),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now() + timeout;
+        self.inner.intercept_send(&msg);
+        self.send_unhooked(msg, Some(deadline), self.full_policy())
+    }
+
+    /// Waits for a message to be sent into the channel, but only for a limited time, and returns
+    /// how much of the time budget is left.
+    ///
+    /// This behaves like [`send_timeout`], except that on success it returns the time remaining
+    /// until the deadline the operation was waiting for. When several blocking steps share one
+    /// deadline, the result can be passed on as the budget of the next step.
+    ///
+    /// [`send_timeout`]: struct.Sender.html#method.send_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendTimeoutError};
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// let budget = Duration::from_millis(500);
+    /// let left = s.send_timeout_remaining(1, budget).unwrap();
+    /// assert!(left <= budget);
+    ///
+    /// // The channel is full, so the remaining budget runs out.
+    /// assert_eq!(
+    ///     s.send_timeout_remaining(2, left),
+    ///     Err(SendTimeoutError::Timeout(2)),
+    /// );
+    /// # drop(r);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout_remaining(
+        &self,
+        msg: T,
+        budget: Duration,
+    ) -> Result<Duration, SendTimeoutError<T>> {
+        let deadline = Instant::now() + budget;
+        self.inner.intercept_send(&msg);
+        self.send_unhooked(msg, Some(deadline), self.full_policy())?;
+        Ok(utils::remaining(deadline))
+    }
+
+    /// Blocks the current thread until a message is sent, the channel is disconnected, or the
+    /// deadline is reached, without calling the send hook.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn send_unhooked(
+        &self,
+        msg: T,
+        deadline: Option<Instant>,
+        policy: FullPolicy,
+    ) -> Result<(), SendTimeoutError<T>> {
+        self.send_when_full(
+            policy,
+            msg,
+            deadline,
+            |msg| self.try_send_unhooked(msg),
+            |msg| match &self.inner.flavor {
+                ChannelFlavor::Array(chan) => chan.send(msg, deadline),
+                ChannelFlavor::Lanes(chan) => chan.send(msg, 0, deadline),
+                ChannelFlavor::List(chan) => chan.send(msg, deadline),
+                ChannelFlavor::Zero(chan) => chan.send(msg, deadline),
+            },
+        )
+    }
+
+    /// Returns the policy for send operations that can report a full channel.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn full_policy(&self) -> FullPolicy {
+        self.inner.config.full_policy
+    }
+
+    /// Returns the policy for operations that wait for room but don't send a message right away.
+    ///
+    /// Such operations have nothing to fail with, so under `FullPolicy::Fail` they wait for room
+    /// as under `FullPolicy::Park`.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn waiting_policy(&self) -> FullPolicy {
+        match self.full_policy() {
+            FullPolicy::Fail => FullPolicy::Park,
+            policy => policy,
+        }
+    }
+
+    /// Spins until the send operation can be performed or `cancel` is cancelled, yielding the
+    /// thread between attempts instead of parking it.
+    ///
+    /// Returns `None` if the token was cancelled.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn spin_select(&self, cancel: Option<&CancelToken>) -> Option<Token> {
+        let mut backoff = Backoff::new();
+        loop {
+            if cancel.map_or(false, |c| c.is_cancelled()) {
+                return None;
+            }
+            let mut token = Token::default();
+            if SelectHandle::try(self, &mut token) {
+                return Some(token);
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Waits until the send operation would not block or the deadline is reached, the way
+    /// `policy` prescribes.
+    ///
+    /// Returns `true` if the operation would not block.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn wait_ready(&self, policy: FullPolicy, deadline: Option<Instant>) -> bool {
+        match policy {
+            FullPolicy::Park => self.stats.record(|| select::wait_ready(self, deadline)),
+            FullPolicy::Fail => SelectHandle::is_ready(self),
+            FullPolicy::SpinYield => {
+                let mut backoff = Backoff::new();
+                while !SelectHandle::is_ready(self) {
+                    if deadline.map_or(false, |d| Instant::now() >= d) {
+                        return false;
+                    }
+                    backoff.snooze();
+                }
+                true
+            }
+        }
+    }
+
+    /// Sends a message the way `policy` prescribes, using `try_send` to attempt sending without
+    /// blocking and `send` to block until the message is sent.
+    ///
+    /// Under `FullPolicy::Fail`, a full channel is reported as `SendTimeoutError::Timeout`.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn send_when_full<F, G>(
+        &self,
+        policy: FullPolicy,
+        mut msg: T,
+        deadline: Option<Instant>,
+        try_send: F,
+        send: G,
+    ) -> Result<(), SendTimeoutError<T>>
+    where
+        F: Fn(T) -> Result<(), TrySendError<T>>,
+        G: FnOnce(T) -> Result<(), SendTimeoutError<T>>,
+    {
+        match policy {
+            FullPolicy::Fail => try_send(msg).map_err(|err| match err {
+                TrySendError::Full(msg) => SendTimeoutError::Timeout(msg),
+                TrySendError::Disconnected(msg) => SendTimeoutError::Disconnected(msg),
+            }),
+            FullPolicy::Park => self.stats.record(|| send(msg)),
+            FullPolicy::SpinYield => {
+                let mut backoff = Backoff::new();
+                loop {
+                    match try_send(msg) {
+                        Ok(()) => return Ok(()),
+                        Err(TrySendError::Disconnected(m)) => {
+                            return Err(SendTimeoutError::Disconnected(m))
+                        }
+                        Err(TrySendError::Full(m)) => msg = m,
+                    }
+                    if let Some(d) = deadline {
+                        if Instant::now() >= d {
+                            return Err(SendTimeoutError::Timeout(msg));
+                        }
+                    }
+                    // Spins at first, and then only yields the thread.
+                    backoff.snooze();
+                }
+            }
+        }
+    }
+
+    /// Blocks the current thread until a message is sent or the operation is cancelled.
+    ///
+    /// If the channel is full and not disconnected, this call will block until the send operation
+    /// can proceed or the token is cancelled. If the token is already cancelled, an error is
+    /// returned immediately, even if the message could have been sent. If the channel becomes
+    /// disconnected, this call will wake up and return an error. The returned error contains the
+    /// original message.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a receive operation to
+    /// appear on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, CancelToken, SendCancelError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let token = CancelToken::new();
+    ///
+    /// let t = token.clone();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     t.cancel();
+    /// });
+    ///
+    /// assert_eq!(s.send_cancellable(1, &token), Ok(()));
+    /// assert_eq!(s.send_cancellable(2, &token), Err(SendCancelError::Cancelled(2)));
+    ///
+    /// drop(r);
+    /// assert_eq!(
+    ///     s.send_cancellable(3, &CancelToken::new()),
+    ///     Err(SendCancelError::Disconnected(3)),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_cancellable(&self, msg: T, cancel: &CancelToken) -> Result<(), SendCancelError<T>> {
+        let token = match self.waiting_policy() {
+            FullPolicy::SpinYield => self.spin_select(Some(cancel)),
+            _ => self.stats.record(|| select::run_cancellable(self, cancel)),
+        };
+
+        match token {
+            None => Err(SendCancelError::Cancelled(msg)),
+            Some(mut token) => unsafe {
+                write(self, &mut token, msg).map_err(SendCancelError::Disconnected)
+            },
+        }
+    }
+
+    /// Blocks the current thread until the channel can take a message, and only then constructs
+    /// the message by calling `f` and sends it.
+    ///
+    /// This is useful when messages are expensive to construct, since no message is created while
+    /// the channel is full or, in the case of a zero-capacity channel, while no receive operation
+    /// is waiting on the other side. By the time `f` is called, room for the message has been set
+    /// aside and a receive operation on a zero-capacity channel has paired up with this one, so
+    /// sending can't fail anymore.
+    ///
+    /// If the channel is disconnected, `f` is not called and is returned in the error instead. If
+    /// the channel gets disconnected while `f` runs, the message it returns is handed over to the
+    /// channel's [`DropPolicy`] as if it had been left in the channel. [`DropPolicy::Keep`] can't
+    /// keep such a message for reclaiming, so it gets dropped.
+    ///
+    /// If `f` panics, the room set aside for the message is given back, a receive operation that
+    /// paired up with this one moves on to wait for another message, and the panic is propagated.
+    ///
+    /// [`DropPolicy`]: enum.DropPolicy.html
+    /// [`DropPolicy::Keep`]: enum.DropPolicy.html#variant.Keep
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded::<Vec<u8>>(0);
+    ///
+    /// thread::spawn(move || {
+    ///     assert_eq!(r.recv().unwrap().len(), 1 << 20);
+    /// });
+    ///
+    /// // The buffer is allocated only once the receiver is ready to take it.
+    /// s.send_with(|| vec![0; 1 << 20]).unwrap();
+    ///
+    /// // The receiver is gone, so the closure doesn't get called.
+    /// assert!(s.send_with(|| unreachable!()).is_err());
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_with<F>(&self, f: F) -> Result<(), SendError<F>>
+    where
+        F: FnOnce() -> T,
+    {
+        let res = match &self.inner.flavor {
+            ChannelFlavor::Array(_) | ChannelFlavor::Lanes(_) => {
+                // If `f` panics, dropping the permit gives the reserved room back.
+                let permit = match self.reserve() {
+                    Ok(permit) => permit,
+                    Err(_) => return Err(SendError(f)),
+                };
+                permit.send(f())
+            }
+            ChannelFlavor::List(chan) => {
+                if chan.is_disconnected() {
+                    return Err(SendError(f));
+                }
+                let msg = f();
+                self.inner.intercept_send(&msg);
+                chan.send(msg, None).map_err(|err| SendError(err.into_inner()))
+            }
+            ChannelFlavor::Zero(chan) => {
+                let mut token = match self.full_policy() {
+                    FullPolicy::Park => self.stats.record(|| select::run_blocking(self)),
+                    FullPolicy::SpinYield => self.spin_select(None).unwrap(),
+                    FullPolicy::Fail => {
+                        let mut token = Token::default();
+                        if !SelectHandle::try(self, &mut token) {
+                            return Err(SendError(f));
+                        }
+                        token
+                    }
+                };
+                if !can_write(self, &token) {
+                    return Err(SendError(f));
+                }
+
+                let msg = {
+                    // If `f` panics, let the receive operation that paired up with this one go.
+                    let guard = AbandonOnDrop { chan, token: &token };
+                    let msg = f();
+                    mem::forget(guard);
+                    msg
+                };
+                unsafe { write(self, &mut token, msg) }.map_err(SendError)
+            }
+        };
+
+        if let Err(SendError(msg)) = res {
+            self.inner.dispose(msg);
+        }
+        Ok(())
+    }
+
+    /// Attempts to send a message into a priority lane without blocking.
+    ///
+    /// This method works like [`try_send`], except that the message goes into lane `lane` of a
+    /// channel created by [`lanes`]. Other channels have a single lane, lane 0.
+    ///
+    /// [`try_send`]: struct.Sender.html#method.try_send
+    /// [`lanes`]: fn.lanes.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane` is not less than the number of lanes in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{lanes, TrySendError};
+    ///
+    /// let (s, r) = lanes(1, 2);
+    ///
+    /// assert_eq!(s.try_send_with_priority(1, 1), Ok(()));
+    /// assert_eq!(s.try_send_with_priority(2, 0), Err(TrySendError::Full(2)));
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn try_send_with_priority(&self, msg: T, lane: usize) -> Result<(), TrySendError<T>> {
+        self.check_lane(lane);
+        self.inner.intercept_send(&msg);
+        match &self.inner.flavor {
+            ChannelFlavor::Lanes(chan) => chan.try_send(msg, lane),
+            _ => self.try_send_unhooked(msg),
+        }
+    }
+
+    /// Blocks the current thread until a message is sent into a priority lane or the channel is
+    /// disconnected.
+    ///
+    /// This method works like [`send`], except that the message goes into lane `lane` of a channel
+    /// created by [`lanes`]. Receive operations always take the oldest message from the highest
+    /// non-empty lane. Other channels have a single lane, lane 0.
+    ///
+    /// [`send`]: struct.Sender.html#method.send
+    /// [`lanes`]: fn.lanes.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane` is not less than the number of lanes in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::lanes;
+    ///
+    /// let (s, r) = lanes(4, 2);
+    ///
+    /// s.send_with_priority(1, 0).unwrap();
+    /// s.send_with_priority(2, 0).unwrap();
+    /// s.send_with_priority(3, 1).unwrap();
+    ///
+    /// assert_eq!(r.try_iter().collect::<Vec<_>>(), [3, 1, 2]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_with_priority(&self, msg: T, lane: usize) -> Result<(), SendError<T>> {
+        self.check_lane(lane);
+        self.inner.intercept_send(&msg);
+        let res = match &self.inner.flavor {
+            ChannelFlavor::Lanes(chan) => self.send_when_full(
+                self.full_policy(),
+                msg,
+                None,
+                |msg| chan.try_send(msg, lane),
+                |msg| chan.send(msg, lane, None),
+            ),
+            _ => self.send_unhooked(msg, None, self.full_policy()),
+        };
+        res.map_err(|err| SendError(err.into_inner()))
+    }
+
+    /// Waits for a message to be sent into a priority lane, but only for a limited time.
+    ///
+    /// This method works like [`send_timeout`], except that the message goes into lane `lane` of a
+    /// channel created by [`lanes`]. Other channels have a single lane, lane 0.
+    ///
+    /// [`send_timeout`]: struct.Sender.html#method.send_timeout
+    /// [`lanes`]: fn.lanes.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane` is not less than the number of lanes in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{lanes, SendTimeoutError};
+    ///
+    /// let (s, r) = lanes(1, 2);
+    /// s.send(1).unwrap();
+    ///
+    /// // The lanes share the capacity, so a full channel blocks urgent messages too.
+    /// assert_eq!(
+    ///     s.send_timeout_with_priority(2, 1, Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout(2)),
+    /// );
+    /// # drop(r);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout_with_priority(
+        &self,
+        msg: T,
+        lane: usize,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<T>> {
+        self.check_lane(lane);
+        let deadline = Instant::now() + timeout;
+        self.inner.intercept_send(&msg);
+        match &self.inner.flavor {
+            ChannelFlavor::Lanes(chan) => self.send_when_full(
+                self.full_policy(),
+                msg,
+                Some(deadline),
+                |msg| chan.try_send(msg, lane),
+                |msg| chan.send(msg, lane, Some(deadline)),
+            ),
+            _ => self.send_unhooked(msg, Some(deadline), self.full_policy()),
+        }
+    }
+
+    /// Panics if `lane` is not a lane of the channel.
+    fn check_lane(&self, lane: usize) {
+        let lanes = match &self.inner.flavor {
+            ChannelFlavor::Lanes(chan) => chan.lanes(),
+            _ => 1,
+        };
+        assert!(
+            lane < lanes,
+            "lane {} is out of range for a channel with {} lanes",
+            lane,
+            lanes
+        );
+    }
+
+    /// Sends a message into the channel without blocking, displacing a queued message if the
+    /// channel is full.
+    ///
+    /// If the channel is not full, the message is simply sent and `Ok(None)` is returned. If the
+    /// channel is full, a queued message is replaced by the new one in a single step, and the
+    /// displaced message is returned as `Ok(Some(msg))`. Which message gets displaced is chosen
+    /// with [`Builder::displace`]: by default it's the newest one, which gives sample-and-hold
+    /// semantics, while [`Displace::Oldest`] turns the channel into a ring buffer.
+    ///
+    /// In a channel with priority lanes, the message is sent into the lowest lane and the displaced
+    /// message is taken from the lowest non-empty lane.
+    ///
+    /// Space reserved by a permit holds no message yet, so there is nothing in it to displace. If
+    /// the channel is full only because of permits, the new message itself is displaced and
+    /// returned back. In a channel with priority lanes, space reserved by permits counts as free
+    /// space.
+    ///
+    /// A zero-capacity channel cannot hold any messages, so if no receive operation is waiting on
+    /// the other side, the new message itself is displaced and returned back. Unbounded channels
+    /// are never full.
+    ///
+    /// If the channel is disconnected, the message is returned back inside the error.
+    ///
+    /// [`Builder::displace`]: struct.Builder.html#method.displace
+    /// [`Displace::Oldest`]: enum.Displace.html#variant.Oldest
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(2);
+    ///
+    /// assert_eq!(s.send_lossy(1), Ok(None));
+    /// assert_eq!(s.send_lossy(2), Ok(None));
+    /// assert_eq!(s.send_lossy(3), Ok(Some(2)));
+    /// assert_eq!(s.send_lossy(4), Ok(Some(3)));
+    ///
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Ok(4));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send_lossy(5), Err(SendError(5)));
+    /// ```
+    pub fn send_lossy(&self, msg: T) -> Result<Option<T>, SendError<T>> {
+        self.inner.intercept_send(&msg);
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan
+                .send_lossy(msg, self.inner.config.displace)
+                .map_err(SendError),
+            ChannelFlavor::Lanes(chan) => chan
+                .send_lossy(msg, 0, self.inner.config.displace)
+                .map_err(SendError),
+            ChannelFlavor::List(chan) => chan.try_send(msg).map(|()| None).map_err(|err| {
+                SendError(err.into_inner())
+            }),
+            ChannelFlavor::Zero(chan) => match chan.try_send(msg) {
+                Ok(()) => Ok(None),
+                Err(TrySendError::Full(msg)) => Ok(Some(msg)),
+                Err(TrySendError::Disconnected(msg)) => Err(SendError(msg)),
+            },
+        }
+    }
+
+    /// Attempts to reserve space for a message in the channel without blocking.
+    ///
+    /// This method will either reserve space in the channel immediately or return an error if the
+    /// channel is full or disconnected. On success, it returns a [`Permit`] that can send one
+    /// message without failing because the channel is full. Space held by a permit is not
+    /// available to other senders until the permit is used or dropped.
+    ///
+    /// A permit doesn't take a place in the queue. Its message is queued when it is sent, and
+    /// messages sent by other senders in the meantime are not held up.
+    ///
+    /// Unbounded and zero-capacity channels have no space to reserve, so this method succeeds
+    /// unless the channel is disconnected.
+    ///
+    /// [`Permit`]: struct.Permit.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, TrySendError};
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// let permit = s.try_reserve().unwrap();
+    /// assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Full(()));
+    /// assert_eq!(s.try_send(1), Err(TrySendError::Full(1)));
+    ///
+    /// permit.send(2).unwrap();
+    /// assert_eq!(r.recv(), Ok(2));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Disconnected(()));
+    /// ```
+    pub fn try_reserve(&self) -> Result<Permit<T>, TrySendError<()>> {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.try_reserve()?,
+            ChannelFlavor::Lanes(chan) => chan.try_reserve()?,
+            ChannelFlavor::List(chan) => {
+                if chan.is_disconnected() {
+                    return Err(TrySendError::Disconnected(()));
+                }
+            }
+            ChannelFlavor::Zero(chan) => {
+                if chan.is_disconnected() {
+                    return Err(TrySendError::Disconnected(()));
+                }
+            }
+        }
+        Ok(Permit { sender: self })
+    }
+
+    /// Blocks the current thread until space for a message is reserved or the channel is
+    /// disconnected.
+    ///
+    /// If the channel is full and not disconnected, this call will block until space becomes
+    /// available. On success, it returns a [`Permit`] that can send one message without failing
+    /// because the channel is full. This way backpressure can be applied before doing the work of
+    /// constructing a message.
+    ///
+    /// Unbounded and zero-capacity channels have no space to reserve, so this method returns
+    /// immediately. A permit for a zero-capacity channel will wait for a receive operation to
+    /// appear on the other side of the channel when sending.
+    ///
+    /// [`Permit`]: struct.Permit.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     assert_eq!(r.recv(), Ok(1));
+    ///     assert_eq!(r.recv(), Ok(2));
+    /// });
+    ///
+    /// // Blocks until the first message is received.
+    /// let permit = s.reserve().unwrap();
+    /// permit.send(2).unwrap();
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn reserve(&self) -> Result<Permit<T>, SendError<()>> {
+        if self.full_policy() == FullPolicy::Fail {
+            return self.try_reserve().map_err(|_| SendError(()));
+        }
+        if self.full_policy() == FullPolicy::SpinYield {
+            let mut backoff = Backoff::new();
+            loop {
+                match self.try_reserve() {
+                    Ok(permit) => return Ok(permit),
+                    Err(TrySendError::Disconnected(())) => return Err(SendError(())),
+                    Err(TrySendError::Full(())) => backoff.snooze(),
+                };
+            }
+        }
+
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => {
+                let res = self.stats.record(|| chan.reserve(None));
+                res.map_err(|err| match err {
+                    SendTimeoutError::Disconnected(()) => SendError(()),
+                    SendTimeoutError::Timeout(()) => unreachable!(),
+                })?
+            }
+            ChannelFlavor::Lanes(chan) => {
+                let res = self.stats.record(|| chan.reserve(None));
+                res.map_err(|err| match err {
+                    SendTimeoutError::Disconnected(()) => SendError(()),
+                    SendTimeoutError::Timeout(()) => unreachable!(),
+                })?
+            }
+            ChannelFlavor::List(chan) => {
+                if chan.is_disconnected() {
+                    return Err(SendError(()));
+                }
+            }
+            ChannelFlavor::Zero(chan) => {
+                if chan.is_disconnected() {
+                    return Err(SendError(()));
+                }
+            }
+        }
+        Ok(Permit { sender: self })
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Note: Zero-capacity channels are always empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.is_empty(),
+            ChannelFlavor::Lanes(chan) => chan.is_empty(),
+            ChannelFlavor::List(chan) => chan.is_empty(),
+            ChannelFlavor::Zero(chan) => chan.is_empty(),
+        }
+    }
+
+    /// Returns `true` if the channel is full.
+    ///
+    /// Note: Zero-capacity channels are always full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// assert!(!s.is_full());
+    /// s.send(0).unwrap();
+    /// assert!(s.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.is_full(),
+            ChannelFlavor::Lanes(chan) => chan.is_full(),
+            ChannelFlavor::List(chan) => chan.is_full(),
+            ChannelFlavor::Zero(chan) => chan.is_full(),
+        }
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// This can be more than the capacity of a bounded channel. A message put back by a
+    /// [`RecvGuard`] doesn't hold capacity, so senders may have taken the room it left behind by
+    /// the time it's back, and the same goes for messages restored from a snapshot.
+    ///
+    /// [`RecvGuard`]: struct.RecvGuard.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(s.len(), 0);
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.len(),
+            ChannelFlavor::Lanes(chan) => chan.len(),
+            ChannelFlavor::List(chan) => chan.len(),
+            ChannelFlavor::Zero(chan) => chan.len(),
+        }
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, _) = unbounded::<i32>();
+    /// assert_eq!(s.capacity(), None);
+    ///
+    /// let (s, _) = bounded::<i32>(5);
+    /// assert_eq!(s.capacity(), Some(5));
+    ///
+    /// let (s, _) = bounded::<i32>(0);
+    /// assert_eq!(s.capacity(), Some(0));
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.capacity(),
+            ChannelFlavor::Lanes(chan) => chan.capacity(),
+            ChannelFlavor::List(chan) => chan.capacity(),
+            ChannelFlavor::Zero(chan) => chan.capacity(),
+        }
+    }
+
+    /// Returns an estimate of the number of bytes currently held by the channel.
+    ///
+    /// See [`Receiver::memory_usage`] for details.
+    ///
+    /// [`Receiver::memory_usage`]: struct.Receiver.html#method.memory_usage
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let empty = s.memory_usage();
+    ///
+    /// for i in 0..1000 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// assert!(s.memory_usage() >= empty + 1000 * 4);
+    /// # drop(r);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        self.inner.memory_usage()
+    }
+
+    /// Returns the number of messages that were left in the channel when all receivers got
+    /// dropped.
+    ///
+    /// Such messages can never be received, so they are disposed of according to the channel's
+    /// [`DropPolicy`]. This method can be used to find out how many messages were lost after a
+    /// send operation fails because the channel is disconnected. If there are still receivers
+    /// associated with the channel, `None` is returned.
+    ///
+    /// Messages kept in the channel by [`DropPolicy::Keep`] or [`DropPolicy::Discard`] are counted
+    /// until they are reclaimed. This method only reads counters and never runs the policy itself.
+    ///
+    /// [`DropPolicy`]: enum.DropPolicy.html
+    /// [`DropPolicy::Keep`]: enum.DropPolicy.html#variant.Keep
+    /// [`DropPolicy::Discard`]: enum.DropPolicy.html#variant.Discard
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// s.send(3).unwrap();
+    /// assert_eq!(s.pending_on_disconnect(), None);
+    ///
+    /// assert_eq!(r.recv(), Ok(1));
+    /// drop(r);
+    ///
+    /// assert_eq!(s.send(4), Err(SendError(4)));
+    /// assert_eq!(s.pending_on_disconnect(), Some(2));
+    /// ```
+    pub fn pending_on_disconnect(&self) -> Option<usize> {
+        if self.inner.receivers.load(Ordering::SeqCst) != 0 {
+            return None;
+        }
+
+        // A send operation that was in progress during disconnection might have left one last
+        // message behind. It stays in the channel until the policy gets to it when the channel is
+        // destroyed, so count it together with the ones that were already disposed of.
+        Some(self.inner.pending_on_disconnect.load(Ordering::SeqCst) + self.len())
+    }
+
+    /// Returns the number of messages that [`DropPolicy::Forward`] failed to forward into the
+    /// dead-letter channel.
+    ///
+    /// Messages are forwarded without blocking, so they get dropped if the dead-letter channel is
+    /// full or disconnected. This method reports how many messages were lost that way, which is
+    /// always zero with any other policy.
+    ///
+    /// The count includes the messages counted by [`pending_on_disconnect`], so the difference
+    /// between the two is the number of messages that made it into the dead-letter channel.
+    ///
+    /// [`DropPolicy::Forward`]: enum.DropPolicy.html#variant.Forward
+    /// [`pending_on_disconnect`]: struct.Sender.html#method.pending_on_disconnect
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, Builder, DropPolicy};
+    ///
+    /// let (dead_s, dead_r) = bounded(1);
+    /// let (s, r) = Builder::new()
+    ///     .drop_policy(DropPolicy::Forward(dead_s))
+    ///     .unbounded();
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// s.send(3).unwrap();
+    /// drop(r);
+    ///
+    /// assert_eq!(s.pending_on_disconnect(), Some(3));
+    /// assert_eq!(s.lost_on_disconnect(), 2);
+    /// assert_eq!(dead_r.try_recv(), Ok(1));
+    /// ```
+    pub fn lost_on_disconnect(&self) -> usize {
+        self.inner.lost_on_disconnect.load(Ordering::SeqCst)
+    }
+
+    /// Takes back the messages that were left in the channel when all receivers got dropped.
+    ///
+    /// A [`DropPolicy`] that disposes of messages takes them out of the channel as soon as the
+    /// last receiver is dropped, so this method only returns the ones that were sent while the
+    /// channel was being disconnected. With [`DropPolicy::Keep`] or the default
+    /// [`DropPolicy::Discard`], however, all of them stay in the channel and can be taken back,
+    /// e.g. to hand the jobs a dead worker never got to over to another worker. The messages are
+    /// returned in the order they would have been received.
+    ///
+    /// If there are still receivers associated with the channel, `None` is returned.
+    ///
+    /// [`DropPolicy`]: enum.DropPolicy.html
+    /// [`DropPolicy::Keep`]: enum.DropPolicy.html#variant.Keep
+    /// [`DropPolicy::Discard`]: enum.DropPolicy.html#variant.Discard
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, DropPolicy};
+    ///
+    /// let (s, r) = Builder::new().drop_policy(DropPolicy::Keep).unbounded();
+    /// s.send("job 1").unwrap();
+    /// s.send("job 2").unwrap();
+    /// s.send("job 3").unwrap();
+    /// assert_eq!(s.reclaim_unconsumed(), None);
+    ///
+    /// // The worker dies after receiving one job.
+    /// assert_eq!(r.recv(), Ok("job 1"));
+    /// drop(r);
+    ///
+    /// assert_eq!(s.reclaim_unconsumed(), Some(vec!["job 2", "job 3"]));
+    /// assert_eq!(s.reclaim_unconsumed(), Some(vec![]));
+    /// ```
+    pub fn reclaim_unconsumed(&self) -> Option<Vec<T>> {
+        if self.inner.receivers.load(Ordering::SeqCst) != 0 {
+            return None;
+        }
+
+        let mut msgs = Vec::new();
+        while let Ok(msg) = self.inner.try_take() {
+            msgs.push(msg);
+        }
+        Some(msgs)
+    }
+
+    /// Returns statistics about send operations on this sender that blocked.
+    ///
+    /// The statistics count how many operations had to park the thread because the channel was
+    /// full, and how long they stayed parked, which measures the backpressure this sender
+    /// experiences. Each sender keeps its own statistics, and a clone starts from zero. See
+    /// [`BlockingStats`] for details.
+    ///
+    /// [`BlockingStats`]: struct.BlockingStats.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
+    /// assert_eq!(s.stats().blocked, 0);
+    ///
+    /// let t = thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     r.iter().count()
+    /// });
+    ///
+    /// // Blocks until the first message is received.
+    /// s.send(2).unwrap();
+    ///
+    /// let stats = s.stats();
+    /// assert_eq!(stats.blocked, 1);
+    /// assert!(stats.blocked_time > Duration::from_millis(50));
+    ///
+    /// drop(s);
+    /// assert_eq!(t.join().unwrap(), 2);
+    /// ```
+    pub fn stats(&self) -> BlockingStats {
+        self.stats.stats()
+    }
+
+    /// Puts this sender back together with the receiver of the same channel.
+    ///
+    /// This succeeds only if `r` belongs to the same channel and the two are its last remaining
+    /// handles, in which case nobody else can touch the channel anymore. The reunited channel
+    /// keeps its queued messages and can be split up again or converted into a channel of
+    /// another flavor. Otherwise, both handles are given back inside the error. This also happens
+    /// if the channel's shutdown group is inspecting the channel at the same moment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, r) = bounded(2);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    ///
+    /// // Now that both halves are local, lift the capacity limit.
+    /// let (s, r) = s.reunite(r).unwrap().into_unbounded();
+    /// for i in 3..10 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// assert_eq!(r.try_iter().collect::<Vec<_>>(), (1..10).collect::<Vec<_>>());
+    ///
+    /// // Halves of different channels can't be reunited.
+    /// let (_, other) = unbounded();
+    /// let (s, other) = s.reunite(other).unwrap_err().into_inner();
+    /// # drop((s, r, other));
+    /// ```
+    pub fn reunite(self, r: Receiver<T>) -> Result<Reunited<T>, ReuniteError<T>> {
+        // Whether these are the last handles is decided by `Arc::try_unwrap` below, since other
+        // handles may be created or dropped at any moment until then.
+        let is_pair = match &r.flavor {
+            ReceiverFlavor::Channel(arc) => Arc::ptr_eq(arc, &self.inner),
+            _ => false,
+        };
+
+        if !is_pair {
+            return Err(ReuniteError::new(self, r));
+        }
+
+        // Make sure dropping the handles doesn't disconnect the channel or apply the drop policy.
+        let chan = self.inner.clone();
+        chan.senders.fetch_add(1, Ordering::SeqCst);
+        chan.receivers.fetch_add(1, Ordering::SeqCst);
+        drop(self);
+        drop(r);
+
+        // The channel is about to be moved out of the `Arc`, so it must no longer be reachable
+        // through the registry or its shutdown group.
+        #[cfg(feature = "registry")]
+        registry::unregister(&*chan);
+        chan.detach();
+
+        match Arc::try_unwrap(chan) {
+            Ok(mut chan) => {
+                chan.senders = AtomicUsize::new(0);
+                chan.receivers = AtomicUsize::new(0);
+                Ok(Reunited { chan })
+            }
+            Err(chan) => {
+                #[cfg(feature = "registry")]
+                registry::register(&*chan);
+                Channel::attach(&chan);
+
+                chan.senders.fetch_sub(1, Ordering::SeqCst);
+                chan.receivers.fetch_sub(1, Ordering::SeqCst);
+                let s = Sender::new(chan.clone());
+                let r = Receiver::new(chan);
+                Err(ReuniteError::new(s, r))
+            }
+        }
+    }
+
+    /// Converts this sender into a handle specialized for the array flavor.
+    ///
+    /// Channels created by [`bounded`] with a non-zero capacity use the array flavor. Operations
+    /// on the returned [`ArraySender`] go straight to the flavor without matching on it first. If
+    /// the channel has a different flavor, the sender is returned unchanged.
+    ///
+    /// [`bounded`]: fn.bounded.html
+    /// [`ArraySender`]: struct.ArraySender.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let (s, _r) = unbounded::<i32>();
+    /// assert!(s.into_array().is_err());
+    /// ```
+    pub fn into_array(self) -> Result<ArraySender<T>, Sender<T>> {
+        let chan = match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan as *const flavors::array::Channel<T>,
+            _ => return Err(self),
+        };
+        Ok(ArraySender::new(self, chan))
+    }
+
+    /// Converts this sender into a handle specialized for the list flavor.
+    ///
+    /// Channels created by [`unbounded`] use the list flavor. Operations on the returned
+    /// [`ListSender`] go straight to the flavor without matching on it first. If the channel has a
+    /// different flavor, the sender is returned unchanged.
+    ///
+    /// [`unbounded`]: fn.unbounded.html
+    /// [`ListSender`]: struct.ListSender.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, r) = unbounded();
+    /// let s = s.into_list().unwrap();
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let (s, _r) = bounded::<i32>(1);
+    /// assert!(s.into_list().is_err());
+    /// ```
+    pub fn into_list(self) -> Result<ListSender<T>, Sender<T>> {
+        let chan = match &self.inner.flavor {
+            ChannelFlavor::List(chan) => chan as *const flavors::list::Channel<T>,
+            _ => return Err(self),
+        };
+        Ok(ListSender::new(self, chan))
+    }
+
+    /// Shows a message about to be sent to the send hook.
+    #[inline]
+    pub(crate) fn intercept(&self, msg: &T) {
+        self.inner.intercept_send(msg);
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.inner.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.disconnect();
+
+            if self.inner.config.drain_on_sender_drop {
+                self.inner.drain();
+                self.inner.notify_drained();
+            }
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender::new(self.inner.clone())
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender").finish()
+    }
+}
+
+/// Space reserved for one message in a channel.
+///
+/// Permits are created by [`Sender::reserve`] and [`Sender::try_reserve`]. Sending a message
+/// through a permit never fails because the channel is full. If the permit is dropped without
+/// sending, the reserved space is given back to the channel.
+///
+/// A permit holds capacity, not a position in the queue. Its message is queued behind the
+/// messages sent before it, and messages sent by other senders in the meantime can be received
+/// while the permit is still held.
+///
+/// [`Sender::reserve`]: struct.Sender.html#method.reserve
+/// [`Sender::try_reserve`]: struct.Sender.html#method.try_reserve
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{bounded, TrySendError};
+///
+/// let (s, r) = bounded(1);
+///
+/// let permit = s.try_reserve().unwrap();
+/// assert!(s.is_full());
+///
+/// drop(permit);
+/// assert!(!s.is_full());
+/// assert_eq!(s.try_send(1), Ok(()));
+/// # drop(r);
+/// ```
+pub struct Permit<'a, T: 'a> {
+    sender: &'a Sender<T>,
+}
+
+impl<'a, T> Permit<'a, T> {
+    /// Sends a message into the reserved space.
+    ///
+    /// The message can't be rejected because the channel is full, but if the channel becomes
+    /// disconnected in the meantime, an error is returned that contains the original message.
+    ///
+    /// If another sender raced with the reservation and took the last free slot of a bounded
+    /// channel, this method blocks the current thread until a receiver frees one up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// let permit = s.reserve().unwrap();
+    /// permit.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let permit = s.reserve().unwrap();
+    /// drop(r);
+    /// assert_eq!(permit.send(2), Err(SendError(2)));
+    /// ```
+    pub fn send(self, msg: T) -> Result<(), SendError<T>> {
+        self.sender.inner.intercept_send(&msg);
+        let res = match &self.sender.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.send_reserved(msg),
+            ChannelFlavor::Lanes(chan) => chan.send_reserved(msg),
+            ChannelFlavor::List(chan) => chan.send(msg, None).map_err(|err| err.into_inner()),
+            ChannelFlavor::Zero(chan) => chan.send(msg, None).map_err(|err| err.into_inner()),
+        }.map_err(SendError);
+
+        // The reservation has been used up, so don't release it again.
+        mem::forget(self);
+        res
+    }
+}
+
+impl<'a, T> Drop for Permit<'a, T> {
+    fn drop(&mut self) {
+        match &self.sender.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.release(),
+            ChannelFlavor::Lanes(chan) => chan.release(),
+            ChannelFlavor::List(_) | ChannelFlavor::Zero(_) => {}
+        }
+    }
+}
+
+impl<'a, T> fmt::Debug for Permit<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Permit").finish()
+    }
+}
+
+/// A received message that is put back into the channel unless it is committed.
+///
+/// Guards are created by [`Receiver::recv_guard`] and [`SelectedOperation::recv_guard`]. A guard
+/// dereferences to the message it holds. Dropping it without calling [`commit`], which also
+/// happens when the thread panics while holding it, puts the message back so that it is the next
+/// one to be received from the channel.
+///
+/// [`Receiver::recv_guard`]: struct.Receiver.html#method.recv_guard
+/// [`SelectedOperation::recv_guard`]: struct.SelectedOperation.html#method.recv_guard
+/// [`commit`]: struct.RecvGuard.html#method.commit
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// s.send(String::from("hello")).unwrap();
+///
+/// let mut msg = r.recv_guard().unwrap();
+/// msg.push_str(", world");
+/// drop(msg);
+///
+/// assert_eq!(r.recv().unwrap(), "hello, world");
+/// ```
+pub struct RecvGuard<'a, T: 'a> {
+    receiver: &'a Receiver<T>,
+    msg: Option<T>,
+}
+
+impl<'a, T> RecvGuard<'a, T> {
+    /// Takes the message out of the guard so that it is not put back into the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// let msg = r.recv_guard().unwrap();
+    /// assert_eq!(msg.commit(), 1);
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// ```
+    pub fn commit(mut self) -> T {
+        self.msg.take().unwrap()
+    }
+}
+
+impl<'a, T> Deref for RecvGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.msg.as_ref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for RecvGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.msg.as_mut().unwrap()
+    }
+}
+
+impl<'a, T> Drop for RecvGuard<'a, T> {
+    fn drop(&mut self) {
+        if let Some(msg) = self.msg.take() {
+            self.receiver.put_back(msg);
+        }
+    }
+}
+
+impl<'a, T> fmt::Debug for RecvGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RecvGuard").finish()
+    }
+}
+
+/// A channel whose sender and receiver have been put back together.
+///
+/// Reunited channels are created by [`Sender::reunite`]. Since the two halves were the last
+/// handles to the channel, nothing can send into or receive from it until it is split up again.
+/// This makes it possible to convert the channel into another flavor while keeping its queued
+/// messages in order.
+///
+/// Channels created by a converting method keep the settings of the original channel, like its
+/// name, drop policy, and hooks, but not its lanes or its weigher.
+///
+/// Dropping a reunited channel destroys it, and its queued messages are disposed of according to
+/// its drop policy.
+///
+/// [`Sender::reunite`]: struct.Sender.html#method.reunite
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// s.send("a").unwrap();
+///
+/// let chan = s.reunite(r).unwrap();
+/// assert_eq!(chan.len(), 1);
+///
+/// let (s, r) = chan.into_bounded(1).unwrap();
+/// assert_eq!(r.recv(), Ok("a"));
+/// # drop(s);
+/// ```
+pub struct Reunited<T> {
+    /// The channel, which has no handles.
+    chan: Channel<T>,
+}
+
+impl<T> Reunited<T> {
+    /// Splits the channel into its sender and receiver again, without converting it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
+    ///
+    /// let (s, r) = s.reunite(r).unwrap().split();
+    /// assert_eq!(s.capacity(), Some(1));
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn split(self) -> (Sender<T>, Receiver<T>) {
+        share(self.chan)
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    ///
+    /// assert_eq!(s.reunite(r).unwrap().len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        match &self.chan.flavor {
+            ChannelFlavor::Array(chan) => chan.len(),
+            ChannelFlavor::Lanes(chan) => chan.len(),
+            ChannelFlavor::List(chan) => chan.len(),
+            ChannelFlavor::Zero(chan) => chan.len(),
+        }
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// assert!(s.reunite(r).unwrap().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, r) = bounded::<i32>(5);
+    /// assert_eq!(s.reunite(r).unwrap().capacity(), Some(5));
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// assert_eq!(s.reunite(r).unwrap().capacity(), None);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.chan.flavor {
+            ChannelFlavor::Array(chan) => chan.capacity(),
+            ChannelFlavor::Lanes(chan) => chan.capacity(),
+            ChannelFlavor::List(chan) => chan.capacity(),
+            ChannelFlavor::Zero(chan) => chan.capacity(),
+        }
+    }
+
+    /// Converts the channel into a channel of unbounded capacity.
+    ///
+    /// All queued messages are moved into the new channel in the order they would have been
+    /// received from the old one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
+    ///
+    /// let (s, r) = s.reunite(r).unwrap().into_unbounded();
+    /// assert_eq!(s.capacity(), None);
+    ///
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    pub fn into_unbounded(self) -> (Sender<T>, Receiver<T>) {
+        let (chan, config) = self.into_inner();
+        let (s, r) = unbounded_with(config);
+        move_messages(&chan, &s);
+        (s, r)
+    }
+
+    /// Converts the channel into a channel of bounded capacity.
+    ///
+    /// All queued messages are moved into the new channel in the order they would have been
+    /// received from the old one. If they don't fit into `cap`, the channel is given back
+    /// unchanged and boxed in the error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is greater than `usize::max_value() / 4`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// for i in 0..3 {
+    ///     s.send(i).unwrap();
+    /// }
+    ///
+    /// // Three messages don't fit into a capacity of two.
+    /// let chan = s.reunite(r).unwrap().into_bounded(2).unwrap_err();
+    ///
+    /// let (s, r) = chan.into_bounded(3).unwrap();
+    /// assert!(s.is_full());
+    /// assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 1, 2]);
+    /// ```
+    pub fn into_bounded(self, cap: usize) -> Result<(Sender<T>, Receiver<T>), Box<Reunited<T>>> {
+        if self.len() > cap {
+            return Err(Box::new(self));
+        }
+
+        let (chan, config) = self.into_inner();
+        let (s, r) = bounded_with(cap, config);
+        move_messages(&chan, &s);
+        Ok((s, r))
+    }
+
+    /// Takes the settings out of the channel.
+    fn into_inner(self) -> (Channel<T>, Config<T>) {
+        let mut chan = self.chan;
+        let config = mem::replace(&mut chan.config, Config::default());
+        (chan, config)
+    }
+}
+
+impl<T> fmt::Debug for Reunited<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Reunited").finish()
+    }
+}
+
+/// Moves all messages from `chan` into the channel `s` belongs to, bypassing hooks.
+///
+/// The channel `s` belongs to must have enough space for all of the messages.
+fn move_messages<T>(chan: &Channel<T>, s: &Sender<T>) {
+    while let Ok(msg) = chan.try_take() {
+        if s.try_send_unhooked(msg).is_err() {
+            unreachable!("no space for a moved message");
+        }
+    }
+}
+
+/// The receiving side of a channel.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+///
+/// thread::spawn(move || {
+///     s.send(1);
+///     thread::sleep(Duration::from_secs(1));
+///     s.send(2);
+/// });
+///
+/// assert_eq!(r.recv(), Ok(1)); // Received immediately.
+/// assert_eq!(r.recv(), Ok(2)); // Received after 1 second.
+/// ```
+pub struct Receiver<T> {
+    flavor: ReceiverFlavor<T>,
+    stats: Recorder,
+}
+
+/// Receiver flavors.
+pub enum ReceiverFlavor<T> {
+    /// A regular channel (array, list, or zero flavor).
+    Channel(Arc<Channel<T>>),
+
+    /// The after flavor.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    After(flavors::after::Channel),
+
+    /// The tick flavor.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    Tick(flavors::tick::Channel),
+
+    /// The never flavor.
+    Never(flavors::never::Channel<T>),
+}
+
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> UnwindSafe for Receiver<T> {}
+impl<T> RefUnwindSafe for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    /// Creates a receiver handle for the channel and increments the receiver count.
+    fn new(chan: Arc<Channel<T>>) -> Self {
+        let old_count = chan.receivers.fetch_add(1, Ordering::SeqCst);
+
+        // Cloning receivers and calling `mem::forget` on the clones could potentially overflow the
+        // counter. It's very difficult to recover sensibly from such degenerate scenarios so we
+        // just abort when the count becomes very large.
+        if old_count > isize::MAX as usize {
+            process::abort();
+        }
+
+        Receiver {
+            stats: Recorder::with_spin(chan.spin.clone()),
+            flavor: ReceiverFlavor::Channel(chan),
+        }
+    }
+
+    /// Attempts to receive a message from the channel without blocking.
+    ///
+    /// This method will either receive a message from the channel immediately or return an error
+    /// if the channel is empty.
+    ///
+    /// If called on a zero-capacity channel, this method will receive a message only if there
+    /// happens to be a send operation on the other side of the channel at the same time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => self.intercept(match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.try_recv(),
+                ChannelFlavor::Lanes(chan) => chan.try_recv(),
+                ChannelFlavor::List(chan) => chan.try_recv(),
+                ChannelFlavor::Zero(chan) => chan.try_recv(),
+            }),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => {
+                let msg = chan.try_recv();
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, TryRecvError>,
+                        Result<T, TryRecvError>
+                    >(&msg)
+                }
+            },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => {
+                let msg = chan.try_recv();
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, TryRecvError>,
+                        Result<T, TryRecvError>
+                    >(&msg)
+                }
+            },
+            ReceiverFlavor::Never(chan) => chan.try_recv(),
+        }
+    }
+
+    /// Attempts to receive a message from the channel without blocking, returning `None` if there
+    /// is none.
+    ///
+    /// This is a shorthand for `try_recv().ok()`, for callers that treat an empty channel and a
+    /// disconnected channel the same way. Use [`try_recv`] to tell them apart.
+    ///
+    /// [`try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_recv_opt(), None);
+    ///
+    /// s.send(5).unwrap();
+    /// assert_eq!(r.try_recv_opt(), Some(5));
+    /// ```
+    pub fn try_recv_opt(&self) -> Option<T> {
+        self.try_recv().ok()
+    }
+
+    /// Attempts to receive a message from the channel without blocking, returning `default` if
+    /// there is none.
+    ///
+    /// The default is returned if the channel is empty, whether or not it is disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_recv_or(0), 0);
+    ///
+    /// s.send(5).unwrap();
+    /// assert_eq!(r.try_recv_or(0), 5);
+    /// ```
+    pub fn try_recv_or(&self, default: T) -> T {
+        self.try_recv().unwrap_or(default)
+    }
+
+    /// Attempts to receive a message, spinning for at most `duration` but never blocking.
+    ///
+    /// This method repeatedly checks whether a message is ready and receives it as soon as one is,
+    /// for up to `duration`. Unlike [`recv_timeout`], it never parks the thread, so it doesn't pay
+    /// for being woken up, at the expense of burning CPU time while it waits. This makes it
+    /// suitable for consumers with tight latency requirements that can afford a few microseconds of
+    /// spinning, while longer waits are better handled by [`recv_timeout`].
+    ///
+    /// While waiting, only the readiness of the channel is checked, which doesn't contend with
+    /// other threads the way repeated calls to [`try_recv`] do.
+    ///
+    /// If no message arrives in time, an [`Empty`] error is returned. If the channel is empty and
+    /// disconnected, a [`Disconnected`] error is returned right away. With a zero duration, this
+    /// method behaves just like [`try_recv`].
+    ///
+    /// [`recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    /// [`try_recv`]: struct.Receiver.html#method.try_recv
+    /// [`Empty`]: enum.TryRecvError.html#variant.Empty
+    /// [`Disconnected`]: enum.TryRecvError.html#variant.Disconnected
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_recv_for(Duration::from_micros(10)), Err(TryRecvError::Empty));
+    ///
+    /// thread::spawn(move || s.send(1).unwrap());
+    /// assert_eq!(r.try_recv_for(Duration::from_secs(1)), Ok(1));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_recv_for(&self, duration: Duration) -> Result<T, TryRecvError> {
+        let start = Instant::now();
+        let mut backoff = Backoff::new();
+
+        loop {
+            match self.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                res => return res,
+            }
+
+            // Spin until the channel looks ready or the time is up.
+            loop {
+                if start.elapsed() >= duration {
+                    return Err(TryRecvError::Empty);
+                }
+                backoff.spin();
+                if self.is_ready() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Attempts to take a message from a send operation that is already blocked waiting to send
+    /// one.
+    ///
+    /// Unlike [`try_recv`], this method doesn't drain messages that are simply sitting in the
+    /// buffer: a message is received only if some sender is currently blocked on the channel, and
+    /// otherwise an [`Empty`] error is returned. Senders of a bounded channel block only while it
+    /// is full, and senders of an unbounded channel never block, so there a message is never
+    /// received this way. If the channel is empty and disconnected, a [`Disconnected`] error is
+    /// returned.
+    ///
+    /// On a zero-capacity channel, this method behaves exactly like [`try_recv`]. Channels created
+    /// by [`after`], [`tick`], and [`never`] have no senders, so receiving from them with this
+    /// method always fails with [`Empty`].
+    ///
+    /// [`try_recv`]: struct.Receiver.html#method.try_recv
+    /// [`Empty`]: enum.TryRecvError.html#variant.Empty
+    /// [`Disconnected`]: enum.TryRecvError.html#variant.Disconnected
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
+    /// [`never`]: fn.never.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, TryRecvError};
+    ///
+    /// let (s, r) = bounded(0);
+    ///
+    /// // Nobody is sending.
+    /// assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+    ///
+    /// let t = thread::spawn(move || s.send(1));
+    /// thread::sleep(Duration::from_millis(100));
+    ///
+    /// // The sender is blocked waiting for a receiver.
+    /// assert_eq!(r.recv_now(), Ok(1));
+    /// assert!(t.join().unwrap().is_ok());
+    /// ```
+    pub fn recv_now(&self) -> Result<T, TryRecvError> {
+        let (has_senders, is_disconnected) = match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::Array(chan) => (
+                    chan.waiting().0 > 0,
+                    chan.is_disconnected() && chan.is_empty(),
+                ),
+                ChannelFlavor::Lanes(chan) => (
+                    chan.waiting().0 > 0,
+                    chan.is_disconnected() && chan.is_empty(),
+                ),
+                ChannelFlavor::List(chan) => (
+                    chan.waiting().0 > 0,
+                    chan.is_disconnected() && chan.is_empty(),
+                ),
+                ChannelFlavor::Zero(chan) => return self.intercept(chan.try_recv()),
+            },
+            _ => {
+                return Err(TryRecvError::Empty);
+            }
+        };
+
+        if has_senders {
+            self.try_recv()
+        } else if is_disconnected {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Waits until a message could be received without blocking, but only for a limited time.
+    ///
+    /// This method blocks until a message is available, or a send operation is waiting on the
+    /// other side in the case of a zero-capacity channel, without receiving it. This is useful
+    /// when some work has to be done after the message arrives but before it is taken out of the
+    /// channel. Another receiver might take the message before the current thread gets to it, so
+    /// a following [`try_recv`] can still fail.
+    ///
+    /// If the channel is empty and disconnected, a [`Disconnected`] error is returned. If the
+    /// timeout elapses first, a [`Timeout`] error is returned.
+    ///
+    /// [`try_recv`]: struct.Receiver.html#method.try_recv
+    /// [`Disconnected`]: enum.RecvTimeoutError.html#variant.Disconnected
+    /// [`Timeout`]: enum.RecvTimeoutError.html#variant.Timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(1).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.wait_ready(Duration::from_secs(1)), Ok(()));
+    ///
+    /// // The message is still in the channel.
+    /// assert_eq!(r.len(), 1);
+    /// assert_eq!(r.try_recv(), Ok(1));
+    ///
+    /// // The sender is gone.
+    /// assert_eq!(
+    ///     r.wait_ready(Duration::from_secs(1)),
+    ///     Err(RecvTimeoutError::Disconnected),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait_ready(&self, timeout: Duration) -> Result<(), RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        if !self.stats.record(|| select::wait_ready(self, Some(deadline))) {
+            return Err(RecvTimeoutError::Timeout);
+        }
+
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) if arc.is_disconnected() && self.is_empty() => {
+                Err(RecvTimeoutError::Disconnected)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until the receive
+    /// operation can proceed. If the channel is empty and becomes disconnected, this call will
+    /// wake up and return an error.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
+    /// on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(5).unwrap();
+    ///     drop(s);
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.stats.record(|| match &self.flavor {
+            ReceiverFlavor::Channel(arc) => self.intercept(match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.recv(None),
+                ChannelFlavor::Lanes(chan) => chan.recv(None),
+                ChannelFlavor::List(chan) => chan.recv(None),
+                ChannelFlavor::Zero(chan) => chan.recv(None),
+            }),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => {
+                let msg = chan.recv(None);
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, RecvTimeoutError>,
+                        Result<T, RecvTimeoutError>,
+                    >(&msg)
+                }
+            },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => {
+                let msg = chan.recv(None);
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, RecvTimeoutError>,
+                        Result<T, RecvTimeoutError>,
+                    >(&msg)
+                }
+            },
+            ReceiverFlavor::Never(chan) => chan.recv(None),
+        }).map_err(|_| RecvError)
+    }
+
+    /// Blocks the current thread until a message is received, returning `None` once the channel
+    /// is empty and disconnected.
+    ///
+    /// This is a shorthand for `recv().ok()`, and makes it easy to consume a channel in a
+    /// `while let` loop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(1).unwrap();
+    ///     s.send(2).unwrap();
+    /// });
+    ///
+    /// let mut sum = 0;
+    /// while let Some(msg) = r.recv_opt() {
+    ///     sum += msg;
+    /// }
+    /// assert_eq!(sum, 3);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_opt(&self) -> Option<T> {
+        self.recv().ok()
+    }
+
+    /// Blocks the current thread until a message is received, returning `default` if the channel
+    /// is empty and disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.recv_or(0), 5);
+    /// assert_eq!(r.recv_or(0), 0);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_or(&self, default: T) -> T {
+        self.recv().unwrap_or(default)
+    }
+
+    /// Blocks the current thread until a message is received, returning `T::default()` if the
+    /// channel is empty and disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<String>();
+    /// s.send("hello".to_string()).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.recv_or_default(), "hello");
+    /// assert_eq!(r.recv_or_default(), "");
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_or_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.recv().unwrap_or_default()
+    }
+
+    /// Busy-waits for a message to be received from the channel, and then blocks if necessary.
+    ///
+    /// This method repeatedly attempts to receive a message without blocking, spinning for up to
+    /// `max_spins` iterations. If no message has been received by then, it falls back to
+    /// [`recv`]. Spinning avoids the cost of parking and unparking the thread when a message is
+    /// expected to arrive very soon, at the expense of burning CPU time.
+    ///
+    /// [`recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(5).unwrap();
+    ///     drop(s);
+    /// });
+    ///
+    /// assert_eq!(r.spin_recv(1000), Ok(5));
+    /// assert_eq!(r.spin_recv(1000), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn spin_recv(&self, max_spins: usize) -> Result<T, RecvError> {
+        let mut backoff = Backoff::new();
+        for _ in 0..max_spins {
+            match self.try_recv() {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => {}
+            }
+            backoff.spin();
+        }
+        self.recv()
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until the receive
+    /// operation can proceed or the operation times out. If the channel is empty and becomes
+    /// disconnected, this call will wake up and return an error.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
+    /// on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(5).unwrap();
+    ///     drop(s);
+    /// });
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(500)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_secs(1)),
+    ///     Ok(5),
+    /// );
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_secs(1)),
+    ///     Err(RecvTimeoutError::Disconnected),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now() + timeout)
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time, and
+    /// returns the message together with how much of the time budget is left.
+    ///
+    /// This behaves like [`recv_timeout`], except that on success it also returns the time
+    /// remaining until the deadline the operation was waiting for. When several blocking steps
+    /// share one deadline, the result can be passed on as the budget of the next step.
+    ///
+    /// [`recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// let budget = Duration::from_millis(500);
+    /// let (msg, left) = r.recv_timeout_remaining(budget).unwrap();
+    /// assert_eq!(msg, 1);
+    /// assert!(left <= budget);
+    ///
+    /// // The channel is empty, so the remaining budget runs out.
+    /// assert_eq!(r.recv_timeout_remaining(left), Err(RecvTimeoutError::Timeout));
+    /// # drop(s);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout_remaining(
+        &self,
+        budget: Duration,
+    ) -> Result<(T, Duration), RecvTimeoutError> {
+        let deadline = Instant::now() + budget;
+        let msg = self.recv_until(deadline)?;
+        Ok((msg, utils::remaining(deadline)))
+    }
+
+    /// Blocks the current thread until a message is received, the channel is empty and
+    /// disconnected, or the deadline is reached.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn recv_until(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.stats.record(|| match &self.flavor {
+            ReceiverFlavor::Channel(arc) => self.intercept(match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.recv(Some(deadline)),
+                ChannelFlavor::Lanes(chan) => chan.recv(Some(deadline)),
+                ChannelFlavor::List(chan) => chan.recv(Some(deadline)),
+                ChannelFlavor::Zero(chan) => chan.recv(Some(deadline)),
+            }),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => {
+                let msg = chan.recv(Some(deadline));
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, RecvTimeoutError>,
+                        Result<T, RecvTimeoutError>,
+                    >(&msg)
+                }
+            },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => {
+                let msg = chan.recv(Some(deadline));
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, RecvTimeoutError>,
+                        Result<T, RecvTimeoutError>,
+                    >(&msg)
+                }
+            },
+            ReceiverFlavor::Never(chan) => chan.recv(Some(deadline)),
+        })
+    }
+
+    /// Receives messages until the timeout elapses or the channel becomes empty and disconnected,
+    /// and collects them.
+    ///
+    /// Messages that are already queued are taken out of the channel in whole chunks, like with
+    /// [`chunks`], and the call blocks only while the channel is empty. Messages that keep arriving
+    /// are received until the timeout elapses, even if the channel never becomes empty.
+    ///
+    /// [`chunks`]: struct.Receiver.html#method.chunks
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(2).unwrap();
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(3).unwrap();
+    /// });
+    ///
+    /// let v = r.collect_timeout::<Vec<_>>(Duration::from_millis(500));
+    /// assert_eq!(v, [1, 2]);
+    ///
+    /// // The sender gets dropped after sending the last message.
+    /// let v = r.collect_timeout::<Vec<_>>(Duration::from_secs(5));
+    /// assert_eq!(v, [3]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn collect_timeout<C>(&self, timeout: Duration) -> C
+    where
+        C: FromIterator<T>,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut buf = Vec::new();
+
+        loop {
+            let now = Instant::now();
+            if self.try_recv_many(&mut buf, usize::max_value()) == 0 {
+                // The channel is empty, so block until the next message arrives.
+                if now >= deadline {
+                    break;
+                }
+                match self.recv_timeout(deadline - now) {
+                    Ok(msg) => buf.push(msg),
+                    Err(_) => break,
+                }
+            } else if now >= deadline {
+                break;
+            }
+        }
+        buf.into_iter().collect()
+    }
+
+    /// Blocks the current thread until a message is received or the operation is cancelled.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until the receive
+    /// operation can proceed or the token is cancelled. If the token is already cancelled, an
+    /// error is returned immediately, even if a message could have been received. If the channel
+    /// is empty and becomes disconnected, this call will wake up and return an error.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
+    /// on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, CancelToken, RecvCancelError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let token = CancelToken::new();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv_cancellable(&token), Ok(1));
+    ///
+    /// let t = token.clone();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     t.cancel();
+    /// });
+    ///
+    /// assert_eq!(r.recv_cancellable(&token), Err(RecvCancelError::Cancelled));
+    ///
+    /// drop(s);
+    /// assert_eq!(r.recv_cancellable(&CancelToken::new()), Err(RecvCancelError::Disconnected));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_cancellable(&self, cancel: &CancelToken) -> Result<T, RecvCancelError> {
+        match self.stats.record(|| select::run_cancellable(self, cancel)) {
+            None => Err(RecvCancelError::Cancelled),
+            Some(mut token) => unsafe {
+                read(self, &mut token).map_err(|_| RecvCancelError::Disconnected)
+            },
+        }
+    }
+
+    /// Blocks the current thread until a message satisfying `pred` is received or the channel is
+    /// empty and disconnected.
+    ///
+    /// Messages are examined in the order they were sent, and the first one satisfying `pred` is
+    /// received. Messages that don't satisfy it are set aside and stay in the channel: they will
+    /// be received by subsequent receive operations, including ones in [`select!`], before any
+    /// message sent later. This is similar to selective receive in Erlang.
+    ///
+    /// An error is returned if the channel becomes empty and disconnected, or if it is
+    /// disconnected and none of the remaining messages satisfy `pred`.
+    ///
+    /// Messages that were set aside still count against the capacity of a bounded channel until
+    /// they are received. In a channel with priority lanes, messages are examined in priority
+    /// order and the ones that don't satisfy `pred` stay in their lanes. Channels created by
+    /// [`after`] and [`tick`] generate messages on the fly, so messages not satisfying `pred` are
+    /// simply skipped.
+    ///
+    /// A zero-capacity channel has no room for messages set aside. Instead, `pred` examines the
+    /// messages of blocked send operations, and the ones that don't satisfy it stay with their
+    /// senders, which keep waiting. Send operations in [`select!`] offer their message only once
+    /// they are paired up, so they are never examined.
+    ///
+    /// The predicate must not perform operations on this channel.
+    ///
+    /// [`select!`]: macro.select.html
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// s.send(3).unwrap();
+    /// s.send(4).unwrap();
+    ///
+    /// assert_eq!(r.recv_where(|&x| x % 2 == 0), Ok(2));
+    /// assert_eq!(r.recv_where(|&x| x > 2), Ok(3));
+    ///
+    /// // Skipped messages are still in the channel, in their original order.
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Ok(4));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    /// assert_eq!(r.recv_where(|&x| x > 5), Err(RecvError));
+    /// assert_eq!(r.recv(), Ok(5));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_where<F>(&self, mut pred: F) -> Result<T, RecvError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => {
+                let res = self.stats.record(|| match &arc.flavor {
+                    ChannelFlavor::Array(chan) => chan.recv_where(&mut pred, None),
+                    ChannelFlavor::Lanes(chan) => chan.recv_where(&mut pred, None),
+                    ChannelFlavor::List(chan) => chan.recv_where(&mut pred, None),
+                    ChannelFlavor::Zero(chan) => chan.recv_where(&mut pred, None),
+                });
+                self.intercept(res).map_err(|_| RecvError)
+            }
+            _ => loop {
+                // Generated messages cannot be set aside, so skip the ones that don't match.
+                let msg = self.recv()?;
+                if pred(&msg) {
+                    return Ok(msg);
+                }
+            },
+        }
+    }
+
+    /// Waits for a message satisfying `pred` to be received from the channel, but only for a
+    /// limited time.
+    ///
+    /// Messages that don't satisfy `pred` are set aside and stay in the channel. See
+    /// [`recv_where`] for details.
+    ///
+    /// [`recv_where`]: struct.Receiver.html#method.recv_where
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send("ping").unwrap();
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout_where(|&m| m == "pong", Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// assert_eq!(r.try_recv(), Ok("ping"));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout_where<F>(
+        &self,
+        mut pred: F,
+        timeout: Duration,
+    ) -> Result<T, RecvTimeoutError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => {
+                let res = self.stats.record(|| match &arc.flavor {
+                    ChannelFlavor::Array(chan) => chan.recv_where(&mut pred, Some(deadline)),
+                    ChannelFlavor::Lanes(chan) => chan.recv_where(&mut pred, Some(deadline)),
+                    ChannelFlavor::List(chan) => chan.recv_where(&mut pred, Some(deadline)),
+                    ChannelFlavor::Zero(chan) => chan.recv_where(&mut pred, Some(deadline)),
+                });
+                self.intercept(res)
+            }
+            _ => loop {
+                // Generated messages cannot be set aside, so skip the ones that don't match.
+                let now = Instant::now();
+                let timeout = if now < deadline {
+                    deadline - now
+                } else {
+                    Duration::from_secs(0)
+                };
+
+                let msg = self.recv_timeout(timeout)?;
+                if pred(&msg) {
+                    return Ok(msg);
+                }
+            },
+        }
+    }
+
+    /// Splits the receiver into one receiving messages that satisfy `pred` and one receiving the
+    /// rest.
+    ///
+    /// No thread is spawned to sort the messages. Instead, whichever side receives takes messages
+    /// out of the channel, and hands over the ones belonging to the other side. Each side receives
+    /// its messages in the order they were sent. Once all receivers of one side are dropped, the
+    /// other side discards the messages that would have gone to it.
+    ///
+    /// If the channel is bounded, each side holds at most as many handed over messages as the
+    /// channel's capacity. Once one side lags that far behind, the other side stops taking
+    /// messages out of the channel and waits for it to catch up, so senders see a full channel
+    /// instead of messages piling up outside of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let (even, odd) = r.split_by(|&x| x % 2 == 0);
+    ///
+    /// for i in 0..6 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// drop(s);
+    ///
+    /// assert_eq!(odd.recv(), Ok(1));
+    /// assert_eq!(even.recv(), Ok(0));
+    /// assert_eq!(odd.recv(), Ok(3));
+    ///
+    /// let rest: Vec<_> = (0..2).map(|_| even.recv().unwrap()).collect();
+    /// assert_eq!(rest, [2, 4]);
+    /// ```
+    pub fn split_by<F>(self, pred: F) -> (SplitReceiver<T>, SplitReceiver<T>)
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        split::split(self, pred)
+    }
+
+    /// Duplicates the receiver into `n` receivers that each receive every message.
+    ///
+    /// A background thread takes messages out of the channel and sends a clone of each one into
+    /// every new receiver. Each new receiver has its own buffer of capacity `cap`, which lets it
+    /// fall behind the others by up to `cap` messages. When the buffer of the slowest receiver is
+    /// full, the thread waits for it to catch up, so no messages are lost and the other receivers
+    /// stop getting new messages in the meantime. Messages that haven't been taken out yet stay in
+    /// the original channel, which keeps applying backpressure to its senders.
+    ///
+    /// Dropping one of the new receivers removes it from the tee. The new receivers become
+    /// disconnected once the original channel is disconnected and empty. The thread stops at that
+    /// point, or as soon as all new receivers are dropped, even if no messages arrive anymore. A
+    /// message that none of the new receivers got is put back into the original channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::bounded;
+    /// use crossbeam_channel::{unbounded, RecvError};
     ///
-    /// let (s, r) = bounded(1);
+    /// let (s, r) = unbounded();
+    /// let branches = r.tee(2, 10);
     ///
-    /// assert!(!s.is_full());
-    /// s.send(0).unwrap();
-    /// assert!(s.is_full());
+    /// s.send("hello").unwrap();
+    /// drop(s);
+    ///
+    /// for r in &branches {
+    ///     assert_eq!(r.recv(), Ok("hello"));
+    ///     assert_eq!(r.recv(), Err(RecvError));
+    /// }
     /// ```
-    pub fn is_full(&self) -> bool {
-        match &self.inner.flavor {
-            ChannelFlavor::Array(chan) => chan.is_full(),
-            ChannelFlavor::List(chan) => chan.is_full(),
-            ChannelFlavor::Zero(chan) => chan.is_full(),
-        }
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn tee(self, n: usize, cap: usize) -> Vec<Receiver<T>>
+    where
+        T: Clone + Send + 'static,
+    {
+        tee::tee(self, n, cap)
     }
 
-    /// Returns the number of messages in the channel.
+    /// Wraps the receiver into one that yields `Err(Elapsed)` whenever no message arrives within
+    /// `timeout` of the previous one.
+    ///
+    /// Received messages are wrapped in `Ok`. Each message, as well as each reported timeout,
+    /// starts the timeout over, so an idle channel yields `Err(Elapsed)` once per `timeout`. See
+    /// [`TimeoutReceiver`] for details.
+    ///
+    /// [`TimeoutReceiver`]: struct.TimeoutReceiver.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::unbounded;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Elapsed};
     ///
     /// let (s, r) = unbounded();
-    /// assert_eq!(s.len(), 0);
+    /// let r = r.with_timeout(Duration::from_millis(100));
     ///
-    /// s.send(1).unwrap();
-    /// s.send(2).unwrap();
-    /// assert_eq!(s.len(), 2);
+    /// thread::spawn(move || {
+    ///     s.send("heartbeat").unwrap();
+    ///     thread::sleep(Duration::from_millis(150));
+    ///     s.send("heartbeat").unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(Ok("heartbeat")));
+    /// assert_eq!(r.recv(), Ok(Err(Elapsed)));
+    /// assert_eq!(r.recv(), Ok(Ok("heartbeat")));
     /// ```
-    pub fn len(&self) -> usize {
-        match &self.inner.flavor {
-            ChannelFlavor::Array(chan) => chan.len(),
-            ChannelFlavor::List(chan) => chan.len(),
-            ChannelFlavor::Zero(chan) => chan.len(),
-        }
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn with_timeout(self, timeout: Duration) -> TimeoutReceiver<T> {
+        timeout::timeout(self, timeout)
     }
 
-    /// If the channel is bounded, returns its capacity.
+    /// Attempts to receive the newest message in the channel without blocking, discarding all
+    /// messages queued before it.
+    ///
+    /// On success, the newest message is returned together with the number of older messages that
+    /// were discarded. Only messages already in the channel when the first one is received get
+    /// discarded, so a fast sender cannot keep this method from returning.
+    ///
+    /// If there is no message ready to be received, an error is returned, just like with
+    /// [`try_recv`].
+    ///
+    /// [`try_recv`]: struct.Receiver.html#method.try_recv
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::{bounded, unbounded};
+    /// use crossbeam_channel::{unbounded, TryRecvError};
     ///
-    /// let (s, _) = unbounded::<i32>();
-    /// assert_eq!(s.capacity(), None);
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_recv_latest(), Err(TryRecvError::Empty));
     ///
-    /// let (s, _) = bounded::<i32>(5);
-    /// assert_eq!(s.capacity(), Some(5));
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// s.send(3).unwrap();
     ///
-    /// let (s, _) = bounded::<i32>(0);
-    /// assert_eq!(s.capacity(), Some(0));
+    /// assert_eq!(r.try_recv_latest(), Ok((3, 2)));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
     /// ```
-    pub fn capacity(&self) -> Option<usize> {
-        match &self.inner.flavor {
-            ChannelFlavor::Array(chan) => chan.capacity(),
-            ChannelFlavor::List(chan) => chan.capacity(),
-            ChannelFlavor::Zero(chan) => chan.capacity(),
-        }
-    }
-}
-
-impl<T> Drop for Sender<T> {
-    fn drop(&mut self) {
-        if self.inner.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
-            match &self.inner.flavor {
-                ChannelFlavor::Array(chan) => chan.disconnect(),
-                ChannelFlavor::List(chan) => chan.disconnect(),
-                ChannelFlavor::Zero(chan) => chan.disconnect(),
-            };
-        }
-    }
-}
-
-impl<T> Clone for Sender<T> {
-    fn clone(&self) -> Self {
-        Sender::new(self.inner.clone())
+    pub fn try_recv_latest(&self) -> Result<(T, usize), TryRecvError> {
+        let msg = self.try_recv()?;
+        Ok(self.skip_to_latest(msg))
     }
-}
 
-impl<T> fmt::Debug for Sender<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Sender").finish()
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected, then discards all but the newest message in the channel and returns it.
+    ///
+    /// The newest message is returned together with the number of older messages that were
+    /// discarded. See [`try_recv_latest`] for details.
+    ///
+    /// [`try_recv_latest`]: struct.Receiver.html#method.try_recv_latest
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send("stale").unwrap();
+    ///     s.send("fresh").unwrap();
+    /// });
+    ///
+    /// thread::sleep(Duration::from_millis(500));
+    /// assert_eq!(r.recv_latest(), Ok(("fresh", 1)));
+    /// assert_eq!(r.recv_latest(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_latest(&self) -> Result<(T, usize), RecvError> {
+        let msg = self.recv()?;
+        Ok(self.skip_to_latest(msg))
     }
-}
-
-/// The receiving side of a channel.
-///
-/// # Examples
-///
-/// ```
-/// use std::thread;
-/// use std::time::Duration;
-/// use crossbeam_channel::unbounded;
-///
-/// let (s, r) = unbounded();
-///
-/// thread::spawn(move || {
-///     s.send(1);
-///     thread::sleep(Duration::from_secs(1));
-///     s.send(2);
-/// });
-///
-/// assert_eq!(r.recv(), Ok(1)); // Received immediately.
-/// assert_eq!(r.recv(), Ok(2)); // Received after 1 second.
-/// ```
-pub struct Receiver<T> {
-    flavor: ReceiverFlavor<T>
-}
-
-/// Receiver flavors.
-pub enum ReceiverFlavor<T> {
-    /// A regular channel (array, list, or zero flavor).
-    Channel(Arc<Channel<T>>),
-
-    /// The after flavor.
-    After(flavors::after::Channel),
-
-    /// The tick flavor.
-    Tick(flavors::tick::Channel),
-
-    /// The never flavor.
-    Never(flavors::never::Channel<T>),
-}
-
-unsafe impl<T: Send> Send for Receiver<T> {}
-unsafe impl<T: Send> Sync for Receiver<T> {}
-
-impl<T> UnwindSafe for Receiver<T> {}
-impl<T> RefUnwindSafe for Receiver<T> {}
-
-impl<T> Receiver<T> {
-    /// Creates a receiver handle for the channel and increments the receiver count.
-    fn new(chan: Arc<Channel<T>>) -> Self {
-        let old_count = chan.receivers.fetch_add(1, Ordering::SeqCst);
 
-        // Cloning receivers and calling `mem::forget` on the clones could potentially overflow the
-        // counter. It's very difficult to recover sensibly from such degenerate scenarios so we
-        // just abort when the count becomes very large.
-        if old_count > isize::MAX as usize {
-            process::abort();
-        }
+    /// Replaces `msg` with newer messages until the messages currently in the channel run out.
+    ///
+    /// Returns the newest message and the number of discarded ones.
+    fn skip_to_latest(&self, mut msg: T) -> (T, usize) {
+        let mut discarded = 0;
 
-        Receiver {
-            flavor: ReceiverFlavor::Channel(chan),
+        // Only look at messages that are already in the channel.
+        for _ in 0..self.len() {
+            match self.try_recv() {
+                Ok(newer) => {
+                    msg = newer;
+                    discarded += 1;
+                }
+                Err(_) => break,
+            }
         }
+        (msg, discarded)
     }
 
-    /// Attempts to receive a message from the channel without blocking.
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected, and returns a guard holding the message.
     ///
-    /// This method will either receive a message from the channel immediately or return an error
-    /// if the channel is empty.
+    /// The guard dereferences to the message. Calling [`commit`] on the guard takes the message
+    /// out of it for good. If the guard is dropped without being committed, for example because
+    /// processing the message failed partway through, the message is put back so that it is the
+    /// next one to be received from the channel. This gives consumers at-least-once semantics
+    /// without losing the order of messages.
     ///
-    /// If called on a zero-capacity channel, this method will receive a message only if there
-    /// happens to be a send operation on the other side of the channel at the same time.
+    /// Note that a message is put back even if the channel has become full in the meantime. In a
+    /// channel with priority lanes, messages are put back into the highest lane. A receive
+    /// operation might report the channel as disconnected while a guard still holds a message, but
+    /// the message can be received once it is put back. Channels created by
+    /// [`after`] and [`tick`] generate messages on the fly, so their messages are simply discarded
+    /// instead of being put back.
+    ///
+    /// [`commit`]: struct.RecvGuard.html#method.commit
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::{unbounded, TryRecvError};
+    /// use crossbeam_channel::bounded;
     ///
-    /// let (s, r) = unbounded();
-    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// let (s, r) = bounded(2);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
     ///
-    /// s.send(5).unwrap();
-    /// drop(s);
+    /// // Processing fails, so the message is put back.
+    /// let msg = r.recv_guard().unwrap();
+    /// assert_eq!(*msg, 1);
+    /// drop(msg);
     ///
-    /// assert_eq!(r.try_recv(), Ok(5));
-    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// // Processing succeeds this time.
+    /// let msg = r.recv_guard().unwrap();
+    /// assert_eq!(msg.commit(), 1);
+    /// assert_eq!(r.recv(), Ok(2));
     /// ```
-    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_guard(&self) -> Result<RecvGuard<T>, RecvError> {
+        let msg = self.recv()?;
+        Ok(guard(self, msg))
+    }
+
+    /// Puts a received message back so that it is the next one to be received.
+    pub(crate) fn put_back(&self, msg: T) {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
-                ChannelFlavor::Array(chan) => chan.try_recv(),
-                ChannelFlavor::List(chan) => chan.try_recv(),
-                ChannelFlavor::Zero(chan) => chan.try_recv(),
-            },
-            ReceiverFlavor::After(chan) => {
-                let msg = chan.try_recv();
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, TryRecvError>,
-                        Result<T, TryRecvError>
-                    >(&msg)
-                }
-            },
-            ReceiverFlavor::Tick(chan) => {
-                let msg = chan.try_recv();
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, TryRecvError>,
-                        Result<T, TryRecvError>
-                    >(&msg)
-                }
+                ChannelFlavor::Array(chan) => chan.put_back(msg),
+                ChannelFlavor::Lanes(chan) => chan.put_back(msg),
+                ChannelFlavor::List(chan) => chan.put_back(msg),
+                ChannelFlavor::Zero(chan) => chan.put_back(msg),
             },
-            ReceiverFlavor::Never(chan) => chan.try_recv(),
+            // Generated messages cannot be put back.
+            _ => drop(msg),
         }
     }
 
-    /// Blocks the current thread until a message is received or the channel is empty and
-    /// disconnected.
+    /// Returns clones of the messages currently queued in the channel, in the order they are going
+    /// to be received.
     ///
-    /// If the channel is empty and not disconnected, this call will block until the receive
-    /// operation can proceed. If the channel is empty and becomes disconnected, this call will
-    /// wake up and return an error.
+    /// This is meant for checkpointing the state of a pipeline: the snapshot can be serialized,
+    /// and later put into a new channel with [`restore`]. The channel keeps working as usual while
+    /// the snapshot is taken and no messages are lost, although a receive operation running at the
+    /// same time might receive a message ahead of one that is being moved aside.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
-    /// on the other side of the channel.
+    /// In a bounded or unbounded channel, the queued messages are moved aside so that they can be
+    /// cloned safely, much like messages skipped by [`recv_where`]. They are still received next,
+    /// and in a bounded channel they keep taking up room in the buffer until then. Messages sent
+    /// while the snapshot is being taken might not be included, and neither are messages queued
+    /// behind a permit that hasn't been used yet. In a zero-capacity channel, only messages put
+    /// back or restored are included, and channels created by [`after`], [`tick`], and [`never`]
+    /// always return an empty snapshot.
+    ///
+    /// This method is available only if the `serde` feature is enabled.
+    ///
+    /// [`restore`]: struct.Receiver.html#method.restore
+    /// [`recv_where`]: struct.Receiver.html#method.recv_where
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
+    /// [`never`]: fn.never.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
-    /// use std::time::Duration;
-    /// use crossbeam_channel::{unbounded, RecvError};
+    /// use crossbeam_channel::bounded;
     ///
-    /// let (s, r) = unbounded();
+    /// let (s, r) = bounded(4);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
     ///
-    /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(5).unwrap();
-    ///     drop(s);
-    /// });
+    /// assert_eq!(r.snapshot(), [1, 2]);
     ///
-    /// assert_eq!(r.recv(), Ok(5));
-    /// assert_eq!(r.recv(), Err(RecvError));
+    /// // The messages are still in the channel.
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Ok(2));
     /// ```
-    pub fn recv(&self) -> Result<T, RecvError> {
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone + Serialize,
+    {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
-                ChannelFlavor::Array(chan) => chan.recv(None),
-                ChannelFlavor::List(chan) => chan.recv(None),
-                ChannelFlavor::Zero(chan) => chan.recv(None),
-            },
-            ReceiverFlavor::After(chan) => {
-                let msg = chan.recv(None);
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
-            },
-            ReceiverFlavor::Tick(chan) => {
-                let msg = chan.recv(None);
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
+                ChannelFlavor::Array(chan) => chan.snapshot(),
+                ChannelFlavor::Lanes(chan) => chan.snapshot(),
+                ChannelFlavor::List(chan) => chan.snapshot(),
+                ChannelFlavor::Zero(chan) => chan.snapshot(),
             },
-            ReceiverFlavor::Never(chan) => chan.recv(None),
-        }.map_err(|_| RecvError)
+            _ => {
+                Vec::new()
+            }
+        }
     }
 
-    /// Waits for a message to be received from the channel, but only for a limited time.
+    /// Puts messages taken with [`snapshot`] into the channel, so that they are the next ones to
+    /// be received, in order.
     ///
-    /// If the channel is empty and not disconnected, this call will block until the receive
-    /// operation can proceed or the operation times out. If the channel is empty and becomes
-    /// disconnected, this call will wake up and return an error.
+    /// The messages are put in front of any other messages in the channel, even if the channel
+    /// is full, so this is best called on a newly created channel before anything is sent into
+    /// it. In a prioritized channel, they are put into the highest lane. Channels created by
+    /// [`after`], [`tick`], and [`never`] cannot hold messages, so the messages are dropped.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
-    /// on the other side of the channel.
+    /// This method is available only if the `serde` feature is enabled.
+    ///
+    /// [`snapshot`]: struct.Receiver.html#method.snapshot
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
+    /// [`never`]: fn.never.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
-    /// use std::time::Duration;
-    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    /// use crossbeam_channel::{bounded, unbounded};
     ///
-    /// let (s, r) = unbounded();
+    /// let (s1, r1) = unbounded();
+    /// s1.send("a").unwrap();
+    /// s1.send("b").unwrap();
+    /// let snapshot = r1.snapshot();
     ///
-    /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(5).unwrap();
-    ///     drop(s);
-    /// });
+    /// let (s2, r2) = bounded(1);
+    /// r2.restore(snapshot);
+    /// assert_eq!(r2.len(), 2);
     ///
-    /// assert_eq!(
-    ///     r.recv_timeout(Duration::from_millis(500)),
-    ///     Err(RecvTimeoutError::Timeout),
-    /// );
-    /// assert_eq!(
-    ///     r.recv_timeout(Duration::from_secs(1)),
-    ///     Ok(5),
-    /// );
-    /// assert_eq!(
-    ///     r.recv_timeout(Duration::from_secs(1)),
-    ///     Err(RecvTimeoutError::Disconnected),
-    /// );
+    /// s2.send("c").unwrap();
+    /// assert_eq!(r2.iter().take(3).collect::<Vec<_>>(), ["a", "b", "c"]);
     /// ```
-    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
-        let deadline = Instant::now() + timeout;
-
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, msgs: Vec<T>) {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
-                ChannelFlavor::Array(chan) => chan.recv(Some(deadline)),
-                ChannelFlavor::List(chan) => chan.recv(Some(deadline)),
-                ChannelFlavor::Zero(chan) => chan.recv(Some(deadline)),
-            },
-            ReceiverFlavor::After(chan) => {
-                let msg = chan.recv(Some(deadline));
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
-            },
-            ReceiverFlavor::Tick(chan) => {
-                let msg = chan.recv(Some(deadline));
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
+                ChannelFlavor::Array(chan) => chan.restore(msgs),
+                ChannelFlavor::Lanes(chan) => chan.restore(msgs),
+                ChannelFlavor::List(chan) => chan.restore(msgs),
+                ChannelFlavor::Zero(chan) => chan.restore(msgs),
             },
-            ReceiverFlavor::Never(chan) => chan.recv(Some(deadline)),
+            // Generated messages cannot be put back.
+            _ => drop(msgs),
         }
     }
 
@@ -844,10 +4092,13 @@ impl<T> Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.is_empty(),
+                ChannelFlavor::Lanes(chan) => chan.is_empty(),
                 ChannelFlavor::List(chan) => chan.is_empty(),
                 ChannelFlavor::Zero(chan) => chan.is_empty(),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.is_empty(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.is_empty(),
             ReceiverFlavor::Never(chan) => chan.is_empty(),
         }
@@ -872,10 +4123,13 @@ impl<T> Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.is_full(),
+                ChannelFlavor::Lanes(chan) => chan.is_full(),
                 ChannelFlavor::List(chan) => chan.is_full(),
                 ChannelFlavor::Zero(chan) => chan.is_full(),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.is_full(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.is_full(),
             ReceiverFlavor::Never(chan) => chan.is_full(),
         }
@@ -883,6 +4137,12 @@ impl<T> Receiver<T> {
 
     /// Returns the number of messages in the channel.
     ///
+    /// This can be more than the capacity of a bounded channel. A message put back by a
+    /// [`RecvGuard`] doesn't hold capacity, so senders may have taken the room it left behind by
+    /// the time it's back, and the same goes for messages restored from a snapshot.
+    ///
+    /// [`RecvGuard`]: struct.RecvGuard.html
+    ///
     /// # Examples
     ///
     /// ```
@@ -899,10 +4159,13 @@ impl<T> Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.len(),
+                ChannelFlavor::Lanes(chan) => chan.len(),
                 ChannelFlavor::List(chan) => chan.len(),
                 ChannelFlavor::Zero(chan) => chan.len(),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.len(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.len(),
             ReceiverFlavor::Never(chan) => chan.len(),
         }
@@ -928,82 +4191,480 @@ impl<T> Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.capacity(),
+                ChannelFlavor::Lanes(chan) => chan.capacity(),
                 ChannelFlavor::List(chan) => chan.capacity(),
                 ChannelFlavor::Zero(chan) => chan.capacity(),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.capacity(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.capacity(),
             ReceiverFlavor::Never(chan) => chan.capacity(),
         }
     }
 
-    /// A blocking iterator over messages in the channel.
+    /// Returns an estimate of the number of bytes currently held by the channel.
+    ///
+    /// The estimate covers the channel itself, its buffer or the blocks of its linked list, and
+    /// messages set aside by selective receive operations. Messages are counted by their
+    /// `size_of`, so memory they own on the heap, like the contents of a `Vec`, is not included.
+    /// Neither is the bookkeeping of operations blocked on the channel.
+    ///
+    /// Bounded channels allocate their whole buffer upfront, so their memory usage stays the same
+    /// as messages come and go. Unbounded channels grow and shrink in blocks of messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, r) = bounded::<u64>(100);
+    /// assert!(r.memory_usage() >= 100 * 8);
+    ///
+    /// let (s, r) = unbounded();
+    /// let empty = r.memory_usage();
+    ///
+    /// for i in 0..1000u64 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// assert!(r.memory_usage() >= empty + 1000 * 8);
+    ///
+    /// while r.try_recv().is_ok() {}
+    /// assert!(r.memory_usage() < empty + 1000 * 8);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => arc.memory_usage(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => mem::size_of_val(chan),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => mem::size_of_val(chan),
+            ReceiverFlavor::Never(chan) => mem::size_of_val(chan),
+        }
+    }
+
+    /// Returns statistics about receive operations on this receiver that blocked.
+    ///
+    /// The statistics count how many operations had to park the thread because the channel was
+    /// empty, and how long they stayed parked. Each receiver keeps its own statistics, and a clone
+    /// starts from zero. See [`BlockingStats`] for details.
+    ///
+    /// [`BlockingStats`]: struct.BlockingStats.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(1).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let stats = r.stats();
+    /// assert_eq!(stats.blocked, 1);
+    /// assert!(stats.blocked_time > Duration::from_millis(50));
+    /// ```
+    pub fn stats(&self) -> BlockingStats {
+        self.stats.stats()
+    }
+
+    /// Returns statistics about how long received messages have waited inside the channel.
+    ///
+    /// Every message carries the time it was sent, and the time from then until it was taken out
+    /// of the channel is recorded into a histogram shared by all receivers. See [`LatencyStats`]
+    /// for details. Channels created by [`after`], [`tick`], and [`never`] return empty
+    /// statistics.
+    ///
+    /// This method is available only if the `latency` feature is enabled.
+    ///
+    /// [`LatencyStats`]: struct.LatencyStats.html
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
+    /// [`never`]: fn.never.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(10);
+    /// s.send(1).unwrap();
+    ///
+    /// thread::sleep(Duration::from_millis(100));
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let stats = r.latency_stats();
+    /// assert_eq!(stats.count(), 1);
+    /// assert!(stats.min().unwrap() >= Duration::from_millis(90));
+    /// ```
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.latency_stats(),
+                ChannelFlavor::Lanes(chan) => chan.latency_stats(),
+                ChannelFlavor::List(chan) => chan.latency_stats(),
+                ChannelFlavor::Zero(chan) => chan.latency_stats(),
+            },
+            _ => LatencyStats::default(),
+        }
+    }
+
+    /// A blocking iterator over messages in the channel.
+    ///
+    /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if
+    /// the channel becomes empty and disconnected, it returns [`None`] without blocking.
+    ///
+    /// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(1).unwrap();
+    ///     s.send(2).unwrap();
+    ///     s.send(3).unwrap();
+    ///     drop(s); // Disconnect the channel.
+    /// });
+    ///
+    /// // Collect all messages from the channel.
+    /// // Note that the call to `collect` blocks until the sender is dropped.
+    /// let v: Vec<_> = r.iter().collect();
+    ///
+    /// assert_eq!(v, [1, 2, 3]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn iter(&self) -> Iter<T> {
+        Iter { receiver: self }
+    }
+
+    /// A non-blocking iterator over messages in the channel.
+    ///
+    /// Each call to [`next`] returns a message if there is one ready to be received. The iterator
+    /// never blocks waiting for the next message.
+    ///
+    /// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(1).unwrap();
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(2).unwrap();
+    ///     thread::sleep(Duration::from_secs(2));
+    ///     s.send(3).unwrap();
+    /// });
+    ///
+    /// thread::sleep(Duration::from_secs(2));
+    ///
+    /// // Collect all messages from the channel without blocking.
+    /// // The third message hasn't been sent yet so we'll collect only the first two.
+    /// let v: Vec<_> = r.try_iter().collect();
+    ///
+    /// assert_eq!(v, [1, 2]);
+    /// ```
+    pub fn try_iter(&self) -> TryIter<T> {
+        TryIter { receiver: self }
+    }
+
+    /// A non-blocking iterator over messages in the channel that reports why it stopped.
+    ///
+    /// This iterator works like [`try_iter`], except that once it returns [`None`], its
+    /// [`status`] method tells whether the channel was merely empty or also disconnected. The
+    /// error is the one returned by the very receive operation that ended the iteration, so
+    /// there is no window for the channel to change in between, as there would be if a separate
+    /// [`try_recv`] followed [`try_iter`].
+    ///
+    /// [`try_iter`]: struct.Receiver.html#method.try_iter
+    /// [`try_recv`]: struct.Receiver.html#method.try_recv
+    /// [`status`]: struct.TryIterStatus.html#method.status
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    ///
+    /// let mut iter = r.try_iter_status();
+    /// assert_eq!(iter.by_ref().collect::<Vec<_>>(), [1, 2]);
+    /// assert_eq!(iter.status(), Some(TryRecvError::Empty));
+    ///
+    /// s.send(3).unwrap();
+    /// drop(s);
+    ///
+    /// let mut iter = r.try_iter_status();
+    /// assert_eq!(iter.by_ref().collect::<Vec<_>>(), [3]);
+    /// assert_eq!(iter.status(), Some(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_iter_status(&self) -> TryIterStatus<T> {
+        TryIterStatus {
+            receiver: self,
+            status: None,
+        }
+    }
+
+    /// A blocking iterator over chunks of messages in the channel.
+    ///
+    /// Each call to [`next`] blocks until at least one message is available and then returns a
+    /// vector of up to `size` messages. Messages that are already queued are taken out of the
+    /// channel as a whole chunk instead of one by one, which makes receiving in batches cheaper.
+    /// If the channel becomes empty and disconnected, the iterator returns [`None`].
+    ///
+    /// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// for i in 0..5 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// drop(s); // Disconnect the channel.
+    ///
+    /// let v: Vec<_> = r.chunks(2).collect();
+    /// assert_eq!(v, [vec![0, 1], vec![2, 3], vec![4]]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn chunks(&self, size: usize) -> Chunks<T> {
+        assert!(size != 0, "chunk size must be positive");
+        Chunks {
+            receiver: self,
+            size,
+        }
+    }
+
+    /// Disconnects the channel and returns an iterator over the messages that were queued.
+    ///
+    /// The channel is disconnected right away, as if all receivers were dropped: blocked send
+    /// operations wake up, and all further send operations fail and hand back their messages. Every
+    /// message that was accepted before the cut-off stays in the channel and is yielded by the
+    /// iterator, which ends once the channel is empty. No message is accepted after the cut-off,
+    /// and no accepted message is lost.
+    ///
+    /// Other receivers of the same channel can still receive the remaining messages, competing with
+    /// the iterator. Messages that are left when the iterator is dropped are disposed of according
+    /// to the drop policy, just like when the last receiver is dropped.
+    ///
+    /// Channels created by [`after`] and [`tick`] can't be disconnected, so the iterator only
+    /// yields a message that is ready right away, if there is one.
+    ///
+    /// [`after`]: fn.after.html
+    /// [`tick`]: fn.tick.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    ///
+    /// let drain = r.into_drain();
+    /// assert_eq!(s.send(3), Err(SendError(3)));
+    ///
+    /// assert_eq!(drain.collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    pub fn into_drain(self) -> Drain<T> {
+        if let ReceiverFlavor::Channel(chan) = &self.flavor {
+            chan.disconnect();
+        }
+        Drain { receiver: self }
+    }
+
+    /// Drops the receiver and returns the messages that were still queued in the channel.
+    ///
+    /// This makes it possible to requeue messages a receiver never got to, e.g. the jobs of a
+    /// worker that is shutting down. The messages are returned in the order they would have been
+    /// received.
+    ///
+    /// If this is the last receiver, the channel is disconnected first, just like with
+    /// [`into_drain`], so a message sent concurrently is either returned or handed back to its
+    /// sender, but never lost. Otherwise, the channel stays connected, and messages sent after
+    /// this call go to the remaining receivers.
+    ///
+    /// [`into_drain`]: struct.Receiver.html#method.into_drain
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// s.send(3).unwrap();
+    ///
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.into_remaining(), [2, 3]);
+    /// assert_eq!(s.send(4), Err(SendError(4)));
+    /// ```
+    pub fn into_remaining(self) -> Vec<T> {
+        let is_last = match &self.flavor {
+            ReceiverFlavor::Channel(chan) => chan.receivers.load(Ordering::SeqCst) == 1,
+            _ => true,
+        };
+
+        if is_last {
+            self.into_drain().collect()
+        } else {
+            self.try_iter().collect()
+        }
+    }
+
+    /// Attempts to receive up to `limit` messages without blocking.
+    ///
+    /// Received messages are pushed into `buf`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn try_recv_many(&self, buf: &mut Vec<T>, limit: usize) -> usize {
+        if let ReceiverFlavor::Channel(arc) = &self.flavor {
+            let start = buf.len();
+            let count = match &arc.flavor {
+                ChannelFlavor::Array(chan) => Some(chan.try_recv_many(buf, limit)),
+                ChannelFlavor::Lanes(chan) => Some(chan.try_recv_many(buf, limit)),
+                ChannelFlavor::List(chan) => Some(chan.try_recv_many(buf, limit)),
+                ChannelFlavor::Zero(_) => None,
+            };
+
+            if let Some(count) = count {
+                for msg in &buf[start..] {
+                    arc.intercept_recv(msg);
+                }
+                if count > 0 {
+                    arc.notify_drained();
+                }
+                return count;
+            }
+        }
+
+        // Other flavors don't hold queued messages, so just receive them one by one.
+        let mut count = 0;
+        while count < limit {
+            match self.try_recv() {
+                Ok(msg) => buf.push(msg),
+                Err(_) => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Converts this receiver into a handle specialized for the array flavor.
     ///
-    /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if
-    /// the channel becomes empty and disconnected, it returns [`None`] without blocking.
+    /// Channels created by [`bounded`] with a non-zero capacity use the array flavor. Operations
+    /// on the returned [`ArrayReceiver`] go straight to the flavor without matching on it first.
+    /// If the channel has a different flavor, the receiver is returned unchanged.
     ///
-    /// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
-    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`bounded`]: fn.bounded.html
+    /// [`ArrayReceiver`]: struct.ArrayReceiver.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
-    /// use crossbeam_channel::unbounded;
-    ///
-    /// let (s, r) = unbounded();
-    ///
-    /// thread::spawn(move || {
-    ///     s.send(1).unwrap();
-    ///     s.send(2).unwrap();
-    ///     s.send(3).unwrap();
-    ///     drop(s); // Disconnect the channel.
-    /// });
+    /// use crossbeam_channel::{after, bounded};
+    /// use std::time::Duration;
     ///
-    /// // Collect all messages from the channel.
-    /// // Note that the call to `collect` blocks until the sender is dropped.
-    /// let v: Vec<_> = r.iter().collect();
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap();
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
     ///
-    /// assert_eq!(v, [1, 2, 3]);
+    /// let r = after(Duration::from_secs(1));
+    /// assert!(r.into_array().is_err());
     /// ```
-    pub fn iter(&self) -> Iter<T> {
-        Iter { receiver: self }
+    pub fn into_array(self) -> Result<ArrayReceiver<T>, Receiver<T>> {
+        let chan = match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan as *const flavors::array::Channel<T>,
+                _ => return Err(self),
+            },
+            _ => return Err(self),
+        };
+        Ok(ArrayReceiver::new(self, chan))
     }
 
-    /// A non-blocking iterator over messages in the channel.
+    /// Converts this receiver into a handle specialized for the list flavor.
     ///
-    /// Each call to [`next`] returns a message if there is one ready to be received. The iterator
-    /// never blocks waiting for the next message.
+    /// Channels created by [`unbounded`] use the list flavor. Operations on the returned
+    /// [`ListReceiver`] go straight to the flavor without matching on it first. If the channel has
+    /// a different flavor, the receiver is returned unchanged.
     ///
-    /// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+    /// [`unbounded`]: fn.unbounded.html
+    /// [`ListReceiver`]: struct.ListReceiver.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
-    /// use std::time::Duration;
-    /// use crossbeam_channel::unbounded;
-    ///
-    /// let (s, r) = unbounded::<i32>();
-    ///
-    /// thread::spawn(move || {
-    ///     s.send(1).unwrap();
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(2).unwrap();
-    ///     thread::sleep(Duration::from_secs(2));
-    ///     s.send(3).unwrap();
-    /// });
-    ///
-    /// thread::sleep(Duration::from_secs(2));
+    /// use crossbeam_channel::{bounded, unbounded};
     ///
-    /// // Collect all messages from the channel without blocking.
-    /// // The third message hasn't been sent yet so we'll collect only the first two.
-    /// let v: Vec<_> = r.try_iter().collect();
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap();
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
     ///
-    /// assert_eq!(v, [1, 2]);
+    /// let (_s, r) = bounded::<i32>(0);
+    /// assert!(r.into_list().is_err());
     /// ```
-    pub fn try_iter(&self) -> TryIter<T> {
-        TryIter { receiver: self }
+    pub fn into_list(self) -> Result<ListReceiver<T>, Receiver<T>> {
+        let chan = match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::List(chan) => chan as *const flavors::list::Channel<T>,
+                _ => return Err(self),
+            },
+            _ => return Err(self),
+        };
+        Ok(ListReceiver::new(self, chan))
+    }
+
+    /// Returns the recorder of blocking statistics for this handle.
+    #[inline]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn recorder(&self) -> &Recorder {
+        &self.stats
+    }
+
+    /// Shows a message received from a regular channel to the receive hook.
+    #[inline]
+    pub(crate) fn intercept<E>(&self, res: Result<T, E>) -> Result<T, E> {
+        if let (ReceiverFlavor::Channel(arc), Ok(msg)) = (&self.flavor, &res) {
+            arc.intercept_recv(msg);
+            arc.notify_drained();
+        }
+        res
     }
 }
 
@@ -1011,11 +4672,15 @@ impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
         if let ReceiverFlavor::Channel(chan) = &self.flavor {
             if chan.receivers.fetch_sub(1, Ordering::SeqCst) == 1 {
-                match &chan.flavor {
-                    ChannelFlavor::Array(chan) => chan.disconnect(),
-                    ChannelFlavor::List(chan) => chan.disconnect(),
-                    ChannelFlavor::Zero(chan) => chan.disconnect(),
+                chan.disconnect();
+
+                // With the default policy, remaining messages are dropped together with the
+                // channel.
+                match &chan.config.drop_policy {
+                    DropPolicy::Discard => {}
+                    _ => chan.drain(),
                 }
+                chan.notify_drained();
             }
         }
     }
@@ -1025,14 +4690,19 @@ impl<T> Clone for Receiver<T> {
     fn clone(&self) -> Self {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => Receiver::new(arc.clone()),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => Receiver {
                 flavor: ReceiverFlavor::After(chan.clone()),
+                stats: Recorder::new(),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => Receiver {
                 flavor: ReceiverFlavor::Tick(chan.clone()),
+                stats: Recorder::new(),
             },
             ReceiverFlavor::Never(chan) => Receiver {
                 flavor: ReceiverFlavor::Never(chan.clone()),
+                stats: Recorder::new(),
             },
         }
     }
@@ -1044,6 +4714,7 @@ impl<T> fmt::Debug for Receiver<T> {
     }
 }
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<'a, T> IntoIterator for &'a Receiver<T> {
     type Item = T;
     type IntoIter = Iter<'a, T>;
@@ -1053,6 +4724,7 @@ impl<'a, T> IntoIterator for &'a Receiver<T> {
     }
 }
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<T> IntoIterator for Receiver<T> {
     type Item = T;
     type IntoIter = IntoIter<T>;
@@ -1091,12 +4763,15 @@ impl<T> IntoIterator for Receiver<T> {
 ///
 /// assert_eq!(v, [1, 2, 3]);
 /// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub struct Iter<'a, T: 'a> {
     receiver: &'a Receiver<T>,
 }
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<'a, T> FusedIterator for Iter<'a, T> {}
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = T;
 
@@ -1105,6 +4780,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<'a, T> fmt::Debug for Iter<'a, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Iter").finish()
@@ -1161,6 +4837,187 @@ impl<'a, T> fmt::Debug for TryIter<'a, T> {
     }
 }
 
+/// A non-blocking iterator over messages in a channel that reports why it stopped.
+///
+/// Each call to [`next`] returns a message if there is one ready to be received. Once the channel
+/// is empty, the iterator returns [`None`] for good, and [`status`] tells whether the channel was
+/// also disconnected at that moment.
+///
+/// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+/// [`status`]: struct.TryIterStatus.html#method.status
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{unbounded, TryRecvError};
+///
+/// let (s, r) = unbounded();
+///
+/// thread::spawn(move || {
+///     for i in 0..3 {
+///         s.send(i).unwrap();
+///         thread::sleep(Duration::from_millis(100));
+///     }
+/// });
+///
+/// let mut received = Vec::new();
+/// loop {
+///     let mut iter = r.try_iter_status();
+///     received.extend(iter.by_ref());
+///
+///     match iter.status() {
+///         Some(TryRecvError::Disconnected) => break,
+///         _ => thread::sleep(Duration::from_millis(10)),
+///     }
+/// }
+///
+/// assert_eq!(received, [0, 1, 2]);
+/// ```
+pub struct TryIterStatus<'a, T: 'a> {
+    receiver: &'a Receiver<T>,
+
+    /// The error that ended the iteration, if it has ended.
+    status: Option<TryRecvError>,
+}
+
+impl<'a, T> TryIterStatus<'a, T> {
+    /// Returns the reason the iteration ended, or `None` if it hasn't ended yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// let mut iter = r.try_iter_status();
+    /// assert_eq!(iter.next(), Some(1));
+    /// assert_eq!(iter.status(), None);
+    ///
+    /// assert_eq!(iter.next(), None);
+    /// assert_eq!(iter.status(), Some(TryRecvError::Empty));
+    /// # drop(s);
+    /// ```
+    pub fn status(&self) -> Option<TryRecvError> {
+        self.status
+    }
+
+    /// Returns `true` if the iteration ended because the channel is empty and disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// drop(s);
+    ///
+    /// let mut iter = r.try_iter_status();
+    /// assert_eq!(iter.next(), None);
+    /// assert!(iter.is_disconnected());
+    /// ```
+    pub fn is_disconnected(&self) -> bool {
+        self.status == Some(TryRecvError::Disconnected)
+    }
+}
+
+impl<'a, T> FusedIterator for TryIterStatus<'a, T> {}
+
+impl<'a, T> Iterator for TryIterStatus<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.status.is_some() {
+            return None;
+        }
+        match self.receiver.try_recv() {
+            Ok(msg) => Some(msg),
+            Err(err) => {
+                self.status = Some(err);
+                None
+            }
+        }
+    }
+}
+
+impl<'a, T> fmt::Debug for TryIterStatus<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TryIterStatus")
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+/// A blocking iterator over chunks of messages in a channel.
+///
+/// Each call to [`next`] blocks until at least one message is available and then returns a vector
+/// of up to `size` messages. However, if the channel becomes empty and disconnected, it returns
+/// [`None`] without blocking.
+///
+/// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+///
+/// thread::spawn(move || {
+///     for i in 0..10 {
+///         s.send(i).unwrap();
+///     }
+///     drop(s); // Disconnect the channel.
+/// });
+///
+/// // Receive all messages in chunks of at most 4 messages.
+/// let mut received = Vec::new();
+/// for chunk in r.chunks(4) {
+///     assert!(chunk.len() <= 4);
+///     received.extend(chunk);
+/// }
+///
+/// assert_eq!(received, (0..10).collect::<Vec<_>>());
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct Chunks<'a, T: 'a> {
+    receiver: &'a Receiver<T>,
+    size: usize,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> FusedIterator for Chunks<'a, T> {}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> Iterator for Chunks<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = Vec::new();
+
+        // If messages are already queued, take the whole chunk at once. Otherwise, block until
+        // the first message arrives and then take whatever follows it.
+        if self.receiver.try_recv_many(&mut chunk, self.size) == 0 {
+            chunk.push(self.receiver.recv().ok()?);
+            self.receiver.try_recv_many(&mut chunk, self.size - 1);
+        }
+        Some(chunk)
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> fmt::Debug for Chunks<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Chunks").finish()
+    }
+}
+
 /// A blocking iterator over messages in a channel.
 ///
 /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if the
@@ -1190,12 +5047,15 @@ impl<'a, T> fmt::Debug for TryIter<'a, T> {
 ///
 /// assert_eq!(v, [1, 2, 3]);
 /// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub struct IntoIter<T> {
     receiver: Receiver<T>,
 }
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<T> FusedIterator for IntoIter<T> {}
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
@@ -1204,16 +5064,68 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 impl<T> fmt::Debug for IntoIter<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("IntoIter").finish()
     }
 }
 
+/// An iterator over the messages that were queued in a channel when it was disconnected.
+///
+/// This iterator is created by [`Receiver::into_drain`]. Each call to [`next`] receives a message
+/// without blocking, and [`None`] is returned once the channel is empty.
+///
+/// [`Receiver::into_drain`]: struct.Receiver.html#method.into_drain
+/// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::bounded;
+///
+/// let (s, r) = bounded(10);
+///
+/// let producer = thread::spawn(move || {
+///     let mut sent = 0;
+///     while s.send(sent).is_ok() {
+///         sent += 1;
+///     }
+///     sent
+/// });
+///
+/// // Exactly the messages that were sent successfully are drained.
+/// let drained = r.into_drain().count();
+/// let sent = producer.join().unwrap();
+/// assert_eq!(drained, sent);
+/// ```
+pub struct Drain<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> FusedIterator for Drain<T> {}
+
+impl<T> Iterator for Drain<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl<T> fmt::Debug for Drain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Drain").finish()
+    }
+}
+
 impl<T> SelectHandle for Sender<T> {
     fn try(&self, token: &mut Token) -> bool {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.sender().try(token),
+            ChannelFlavor::Lanes(chan) => chan.sender().try(token),
             ChannelFlavor::List(chan) => chan.sender().try(token),
             ChannelFlavor::Zero(chan) => chan.sender().try(token),
         }
@@ -1222,6 +5134,7 @@ impl<T> SelectHandle for Sender<T> {
     fn retry(&self, token: &mut Token) -> bool {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.sender().retry(token),
+            ChannelFlavor::Lanes(chan) => chan.sender().retry(token),
             ChannelFlavor::List(chan) => chan.sender().retry(token),
             ChannelFlavor::Zero(chan) => chan.sender().retry(token),
         }
@@ -1234,6 +5147,7 @@ impl<T> SelectHandle for Sender<T> {
     fn register(&self, token: &mut Token, oper: Operation, cx: &Context) -> bool {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.sender().register(token, oper, cx),
+            ChannelFlavor::Lanes(chan) => chan.sender().register(token, oper, cx),
             ChannelFlavor::List(chan) => chan.sender().register(token, oper, cx),
             ChannelFlavor::Zero(chan) => chan.sender().register(token, oper, cx),
         }
@@ -1242,6 +5156,7 @@ impl<T> SelectHandle for Sender<T> {
     fn unregister(&self, oper: Operation) {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.sender().unregister(oper),
+            ChannelFlavor::Lanes(chan) => chan.sender().unregister(oper),
             ChannelFlavor::List(chan) => chan.sender().unregister(oper),
             ChannelFlavor::Zero(chan) => chan.sender().unregister(oper),
         }
@@ -1250,6 +5165,7 @@ impl<T> SelectHandle for Sender<T> {
     fn accept(&self, token: &mut Token, cx: &Context) -> bool {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.sender().accept(token, cx),
+            ChannelFlavor::Lanes(chan) => chan.sender().accept(token, cx),
             ChannelFlavor::List(chan) => chan.sender().accept(token, cx),
             ChannelFlavor::Zero(chan) => chan.sender().accept(token, cx),
         }
@@ -1258,10 +5174,38 @@ impl<T> SelectHandle for Sender<T> {
     fn state(&self) -> usize {
         match &self.inner.flavor {
             ChannelFlavor::Array(chan) => chan.sender().state(),
+            ChannelFlavor::Lanes(chan) => chan.sender().state(),
             ChannelFlavor::List(chan) => chan.sender().state(),
             ChannelFlavor::Zero(chan) => chan.sender().state(),
         }
     }
+
+    fn is_ready(&self) -> bool {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.sender().is_ready(),
+            ChannelFlavor::Lanes(chan) => chan.sender().is_ready(),
+            ChannelFlavor::List(chan) => chan.sender().is_ready(),
+            ChannelFlavor::Zero(chan) => chan.sender().is_ready(),
+        }
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.sender().watch(oper, cx),
+            ChannelFlavor::Lanes(chan) => chan.sender().watch(oper, cx),
+            ChannelFlavor::List(chan) => chan.sender().watch(oper, cx),
+            ChannelFlavor::Zero(chan) => chan.sender().watch(oper, cx),
+        }
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        match &self.inner.flavor {
+            ChannelFlavor::Array(chan) => chan.sender().unwatch(oper),
+            ChannelFlavor::Lanes(chan) => chan.sender().unwatch(oper),
+            ChannelFlavor::List(chan) => chan.sender().unwatch(oper),
+            ChannelFlavor::Zero(chan) => chan.sender().unwatch(oper),
+        }
+    }
 }
 
 impl<T> SelectHandle for Receiver<T> {
@@ -1269,10 +5213,13 @@ impl<T> SelectHandle for Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.receiver().try(token),
+                ChannelFlavor::Lanes(chan) => chan.receiver().try(token),
                 ChannelFlavor::List(chan) => chan.receiver().try(token),
                 ChannelFlavor::Zero(chan) => chan.receiver().try(token),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.try(token),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.try(token),
             ReceiverFlavor::Never(chan) => chan.try(token),
         }
@@ -1282,10 +5229,13 @@ impl<T> SelectHandle for Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.receiver().retry(token),
+                ChannelFlavor::Lanes(chan) => chan.receiver().retry(token),
                 ChannelFlavor::List(chan) => chan.receiver().retry(token),
                 ChannelFlavor::Zero(chan) => chan.receiver().retry(token),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.retry(token),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.retry(token),
             ReceiverFlavor::Never(chan) => chan.retry(token),
         }
@@ -1294,7 +5244,9 @@ impl<T> SelectHandle for Receiver<T> {
     fn deadline(&self) -> Option<Instant> {
         match &self.flavor {
             ReceiverFlavor::Channel(_) => None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.deadline(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.deadline(),
             ReceiverFlavor::Never(chan) => chan.deadline(),
         }
@@ -1304,10 +5256,13 @@ impl<T> SelectHandle for Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.receiver().register(token, oper, cx),
+                ChannelFlavor::Lanes(chan) => chan.receiver().register(token, oper, cx),
                 ChannelFlavor::List(chan) => chan.receiver().register(token, oper, cx),
                 ChannelFlavor::Zero(chan) => chan.receiver().register(token, oper, cx),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.register(token, oper, cx),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.register(token, oper, cx),
             ReceiverFlavor::Never(chan) => chan.register(token, oper, cx),
         }
@@ -1317,10 +5272,13 @@ impl<T> SelectHandle for Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.receiver().unregister(oper),
+                ChannelFlavor::Lanes(chan) => chan.receiver().unregister(oper),
                 ChannelFlavor::List(chan) => chan.receiver().unregister(oper),
                 ChannelFlavor::Zero(chan) => chan.receiver().unregister(oper),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.unregister(oper),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.unregister(oper),
             ReceiverFlavor::Never(chan) => chan.unregister(oper),
         }
@@ -1330,10 +5288,13 @@ impl<T> SelectHandle for Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.receiver().accept(token, cx),
+                ChannelFlavor::Lanes(chan) => chan.receiver().accept(token, cx),
                 ChannelFlavor::List(chan) => chan.receiver().accept(token, cx),
                 ChannelFlavor::Zero(chan) => chan.receiver().accept(token, cx),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.accept(token, cx),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.accept(token, cx),
             ReceiverFlavor::Never(chan) => chan.accept(token, cx),
         }
@@ -1343,20 +5304,125 @@ impl<T> SelectHandle for Receiver<T> {
         match &self.flavor {
             ReceiverFlavor::Channel(arc) => match &arc.flavor {
                 ChannelFlavor::Array(chan) => chan.receiver().state(),
+                ChannelFlavor::Lanes(chan) => chan.receiver().state(),
                 ChannelFlavor::List(chan) => chan.receiver().state(),
                 ChannelFlavor::Zero(chan) => chan.receiver().state(),
             },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::After(chan) => chan.state(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             ReceiverFlavor::Tick(chan) => chan.state(),
             ReceiverFlavor::Never(chan) => chan.state(),
         }
     }
+
+    fn is_ready(&self) -> bool {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.receiver().is_ready(),
+                ChannelFlavor::Lanes(chan) => chan.receiver().is_ready(),
+                ChannelFlavor::List(chan) => chan.receiver().is_ready(),
+                ChannelFlavor::Zero(chan) => chan.receiver().is_ready(),
+            },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => chan.is_ready(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => chan.is_ready(),
+            ReceiverFlavor::Never(chan) => chan.is_ready(),
+        }
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.receiver().watch(oper, cx),
+                ChannelFlavor::Lanes(chan) => chan.receiver().watch(oper, cx),
+                ChannelFlavor::List(chan) => chan.receiver().watch(oper, cx),
+                ChannelFlavor::Zero(chan) => chan.receiver().watch(oper, cx),
+            },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => chan.watch(oper, cx),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => chan.watch(oper, cx),
+            ReceiverFlavor::Never(chan) => chan.watch(oper, cx),
+        }
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        match &self.flavor {
+            ReceiverFlavor::Channel(arc) => match &arc.flavor {
+                ChannelFlavor::Array(chan) => chan.receiver().unwatch(oper),
+                ChannelFlavor::Lanes(chan) => chan.receiver().unwatch(oper),
+                ChannelFlavor::List(chan) => chan.receiver().unwatch(oper),
+                ChannelFlavor::Zero(chan) => chan.receiver().unwatch(oper),
+            },
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::After(chan) => chan.unwatch(oper),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            ReceiverFlavor::Tick(chan) => chan.unwatch(oper),
+            ReceiverFlavor::Never(chan) => chan.unwatch(oper),
+        }
+    }
+}
+
+/// Abandons a paired up send operation on a zero-capacity channel when dropped.
+///
+/// Makes sure the receive operation on the other side doesn't wait forever for the message.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct AbandonOnDrop<'a, T: 'a> {
+    chan: &'a flavors::zero::Channel<T>,
+    token: &'a Token,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> Drop for AbandonOnDrop<'a, T> {
+    fn drop(&mut self) {
+        unsafe { self.chan.abandon(self.token) }
+    }
+}
+
+/// Returns `true` if a selected send operation can write its message.
+///
+/// Otherwise, the channel is disconnected and writing would fail.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn can_write<T>(s: &Sender<T>, token: &Token) -> bool {
+    match &s.inner.flavor {
+        ChannelFlavor::Array(_) => token.array.has_slot(),
+        ChannelFlavor::Lanes(_) => token.lanes,
+        ChannelFlavor::List(_) => token.list.has_slot(),
+        ChannelFlavor::Zero(_) => token.zero.has_packet(),
+    }
+}
+
+/// Returns `true` if the channel the sender belongs to is disconnected.
+pub fn is_disconnected<T>(s: &Sender<T>) -> bool {
+    s.inner.is_disconnected()
+}
+
+/// Returns `true` if the channel the receiver belongs to is disconnected and empty.
+pub fn is_closed<T>(r: &Receiver<T>) -> bool {
+    match &r.flavor {
+        ReceiverFlavor::Channel(arc) => arc.is_disconnected() && r.is_empty(),
+        _ => false,
+    }
+}
+
+/// Returns `true` if both receivers belong to the same channel.
+pub fn same_channel<T, U>(a: &Receiver<T>, b: &Receiver<U>) -> bool {
+    match (&a.flavor, &b.flavor) {
+        (ReceiverFlavor::Channel(x), ReceiverFlavor::Channel(y)) => {
+            &**x as *const Channel<T> as *const u8 == &**y as *const Channel<U> as *const u8
+        }
+        _ => a as *const Receiver<T> as *const u8 == b as *const Receiver<U> as *const u8,
+    }
 }
 
 /// Writes a message into the channel.
 pub unsafe fn write<T>(s: &Sender<T>, token: &mut Token, msg: T) -> Result<(), T> {
+    s.inner.intercept_send(&msg);
     match &s.inner.flavor {
         ChannelFlavor::Array(chan) => chan.write(token, msg),
+        ChannelFlavor::Lanes(chan) => chan.write(token, msg),
         ChannelFlavor::List(chan) => chan.write(token, msg),
         ChannelFlavor::Zero(chan) => chan.write(token, msg),
     }
@@ -1365,17 +5431,28 @@ pub unsafe fn write<T>(s: &Sender<T>, token: &mut Token, msg: T) -> Result<(), T
 /// Reads a message from the channel.
 pub unsafe fn read<T>(r: &Receiver<T>, token: &mut Token) -> Result<T, ()> {
     match &r.flavor {
-        ReceiverFlavor::Channel(arc) => match &arc.flavor {
+        ReceiverFlavor::Channel(arc) => r.intercept(match &arc.flavor {
             ChannelFlavor::Array(chan) => chan.read(token),
+            ChannelFlavor::Lanes(chan) => chan.read(token),
             ChannelFlavor::List(chan) => chan.read(token),
             ChannelFlavor::Zero(chan) => chan.read(token),
-        },
+        }),
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
         ReceiverFlavor::After(chan) => {
             mem::transmute_copy::<Result<Instant, ()>, Result<T, ()>>(&chan.read(token))
         }
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
         ReceiverFlavor::Tick(chan) => {
             mem::transmute_copy::<Result<Instant, ()>, Result<T, ()>>(&chan.read(token))
         }
         ReceiverFlavor::Never(chan) => chan.read(token),
     }
 }
+
+/// Wraps a message received from the channel into a guard that puts it back unless committed.
+pub fn guard<T>(r: &Receiver<T>, msg: T) -> RecvGuard<T> {
+    RecvGuard {
+        receiver: r,
+        msg: Some(msg),
+    }
+}
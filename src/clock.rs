@@ -0,0 +1,235 @@
+//! Time sources for timer channels.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use selectable::Notifier;
+
+/// A source of time for [`after_with_clock`] and [`tick_with_clock`] channels.
+///
+/// Timer channels created with [`after`] and [`tick`] use the [`SystemClock`]. Plugging in a
+/// different clock, most notably a [`ManualClock`], makes timer-heavy code testable without
+/// waiting for real time to pass.
+///
+/// A clock either advances on its own, in which case threads waiting for it sleep for the
+/// remaining time, or it only jumps when told to, in which case it must provide a [`Notifier`]
+/// and notify it whenever the time changes.
+///
+/// [`after_with_clock`]: fn.after_with_clock.html
+/// [`tick_with_clock`]: fn.tick_with_clock.html
+/// [`after`]: fn.after.html
+/// [`tick`]: fn.tick.html
+/// [`SystemClock`]: struct.SystemClock.html
+/// [`ManualClock`]: struct.ManualClock.html
+/// [`Notifier`]: struct.Notifier.html
+///
+/// # Examples
+///
+/// A clock that runs a fixed amount of time ahead:
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use crossbeam_channel::{after_with_clock, Clock};
+///
+/// struct Ahead(Duration);
+///
+/// impl Clock for Ahead {
+///     fn now(&self) -> Instant {
+///         Instant::now() + self.0
+///     }
+/// }
+///
+/// let r = after_with_clock(Duration::from_millis(100), Ahead(Duration::from_secs(1)));
+/// assert!(r.recv().is_ok());
+/// ```
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns the notifier that is notified whenever the time jumps.
+    ///
+    /// Clocks that advance on their own return `None`, which is the default.
+    fn notifier(&self) -> Option<&Notifier> {
+        None
+    }
+}
+
+/// The clock of the operating system, as reported by `Instant::now()`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{after_with_clock, SystemClock};
+///
+/// let r = after_with_clock(Duration::from_millis(100), SystemClock);
+/// assert!(r.recv().is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only advances when told to, for testing.
+///
+/// The clock starts at the time of its creation. Cloning it creates a new handle to the same
+/// clock.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{tick_with_clock, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let r = tick_with_clock(Duration::from_secs(60), clock.clone());
+/// assert!(r.try_recv().is_err());
+///
+/// // An hour passes in an instant.
+/// for _ in 0..60 {
+///     clock.advance(Duration::from_secs(60));
+///     assert!(r.try_recv().is_ok());
+/// }
+/// ```
+pub struct ManualClock {
+    inner: Arc<Inner>,
+}
+
+/// Inner representation of `ManualClock`.
+struct Inner {
+    /// The current time.
+    now: Mutex<Instant>,
+
+    /// Wakes up threads waiting for the time to change.
+    notifier: Notifier,
+}
+
+impl ManualClock {
+    /// Creates a new clock set to the current time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Clock, ManualClock};
+    ///
+    /// let clock = ManualClock::new();
+    /// let now = clock.now();
+    /// assert_eq!(clock.now(), now);
+    /// ```
+    pub fn new() -> ManualClock {
+        ManualClock {
+            inner: Arc::new(Inner {
+                now: Mutex::new(Instant::now()),
+                notifier: Notifier::new(),
+            }),
+        }
+    }
+
+    /// Moves the clock forward and wakes up threads waiting for timer channels using it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{after_with_clock, ManualClock};
+    ///
+    /// let clock = ManualClock::new();
+    /// let r = after_with_clock(Duration::from_secs(3600), clock.clone());
+    ///
+    /// let c = clock.clone();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     c.advance(Duration::from_secs(3600));
+    /// });
+    ///
+    /// // Blocks only until the clock is moved forward.
+    /// assert!(r.recv().is_ok());
+    /// ```
+    pub fn advance(&self, dur: Duration) {
+        *self.inner.now.lock() += dur;
+        self.inner.notifier.notify();
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.inner.now.lock()
+    }
+
+    fn notifier(&self) -> Option<&Notifier> {
+        Some(&self.inner.notifier)
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> ManualClock {
+        ManualClock::new()
+    }
+}
+
+impl Clone for ManualClock {
+    fn clone(&self) -> ManualClock {
+        ManualClock {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ManualClock")
+            .field("now", &self.now())
+            .finish()
+    }
+}
+
+/// The time source of a timer channel.
+///
+/// `None` stands for the system clock, which is by far the most common case and is read directly.
+pub type Time = Option<Arc<Clock>>;
+
+/// Returns the current time.
+#[inline]
+pub fn now(time: &Time) -> Instant {
+    match time {
+        None => Instant::now(),
+        Some(clock) => clock.now(),
+    }
+}
+
+/// Converts an instant on the clock into a deadline on the system clock.
+///
+/// Returns `None` if the clock only advances when notified, since no amount of real waiting is
+/// guaranteed to reach the instant.
+#[inline]
+pub fn deadline(time: &Time, at: Instant) -> Option<Instant> {
+    match time {
+        None => Some(at),
+        Some(clock) => {
+            if clock.notifier().is_some() {
+                return None;
+            }
+            let now = clock.now();
+            let real = Instant::now();
+            if at > now {
+                Some(real + (at - now))
+            } else {
+                Some(real)
+            }
+        }
+    }
+}
+
+/// Returns the notifier of a clock that only advances when notified.
+#[inline]
+pub fn notifier(time: &Time) -> Option<&Notifier> {
+    time.as_ref().and_then(|clock| clock.notifier())
+}
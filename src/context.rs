@@ -1,14 +1,54 @@
 //! Thread-local context used in select.
 
 use std::cell::Cell;
+use std::fmt;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 use std::thread::{self, Thread, ThreadId};
 use std::time::Instant;
 
+use park::{self, Parker};
 use select::Selected;
+use stats;
 use utils::Backoff;
 
+/// Identifies the execution context a `Context` belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Id {
+    /// A thread that blocks by parking.
+    Thread(ThreadId),
+
+    /// An execution context that blocks using a custom parker, identified by its address.
+    Custom(usize),
+}
+
+impl Id {
+    /// Returns the id of the current execution context.
+    #[inline]
+    pub fn current() -> Id {
+        thread_local! {
+            /// Cached thread-local id.
+            static THREAD_ID: ThreadId = thread::current().id();
+        }
+
+        match park::current() {
+            Some(parker) => Id::of(&parker),
+            None => Id::Thread(
+                THREAD_ID
+                    .try_with(|id| *id)
+                    .unwrap_or_else(|_| thread::current().id()),
+            ),
+        }
+    }
+
+    /// Returns the id of the execution context owning a custom parker.
+    #[inline]
+    fn of(parker: &Arc<Parker>) -> Id {
+        Id::Custom(&**parker as *const Parker as *const u8 as usize)
+    }
+}
+
 /// Thread-local context used in select.
 #[derive(Clone)]
 pub struct Context {
@@ -21,13 +61,16 @@ struct Inner {
     select: AtomicUsize,
 
     /// A slot into which another thread may store a pointer to its `Packet`.
-    packet: AtomicUsize,
+    packet: AtomicPtr<u8>,
 
     /// Thread handle.
     thread: Thread,
 
-    /// Thread id.
-    thread_id: ThreadId,
+    /// Custom parker, if the execution context doesn't block by parking the thread.
+    parker: Option<Arc<Parker>>,
+
+    /// Execution context id.
+    id: Id,
 }
 
 impl Context {
@@ -42,6 +85,12 @@ impl Context {
             static CONTEXT: Cell<Option<Context>> = Cell::new(Some(Context::new()));
         }
 
+        // Contexts with custom parkers belong to execution contexts that may share a thread, so
+        // they can't be cached in a thread-local.
+        if park::is_custom() {
+            return f(&Context::new());
+        }
+
         let mut f = Some(f);
         let mut f = move |cx: &Context| -> R {
             let f = f.take().unwrap();
@@ -66,21 +115,44 @@ impl Context {
     /// Creates a new `Context`.
     #[cold]
     fn new() -> Context {
+        let parker = park::current();
+        let id = match &parker {
+            Some(parker) => Id::of(parker),
+            None => Id::Thread(thread::current().id()),
+        };
+
         Context {
             inner: Arc::new(Inner {
                 select: AtomicUsize::new(Selected::Waiting.into()),
-                packet: AtomicUsize::new(0),
+                packet: AtomicPtr::new(ptr::null_mut()),
                 thread: thread::current(),
-                thread_id: thread::current().id(),
+                parker,
+                id,
+            }),
+        }
+    }
+
+    /// Creates a new `Context` that is woken up through `parker`.
+    ///
+    /// The context is used to get notified about operations becoming ready rather than to block,
+    /// so it must never be waited on.
+    pub fn with_parker(parker: Arc<Parker>) -> Context {
+        Context {
+            inner: Arc::new(Inner {
+                select: AtomicUsize::new(Selected::Waiting.into()),
+                packet: AtomicPtr::new(ptr::null_mut()),
+                thread: thread::current(),
+                id: Id::of(&parker),
+                parker: Some(parker),
             }),
         }
     }
 
     /// Resets `select` and `packet`.
     #[inline]
-    fn reset(&self) {
+    pub fn reset(&self) {
         self.inner.select.store(Selected::Waiting.into(), Ordering::Release);
-        self.inner.packet.store(0, Ordering::Release);
+        self.inner.packet.store(ptr::null_mut(), Ordering::Release);
     }
 
     /// Attempts to select an operation.
@@ -110,19 +182,19 @@ impl Context {
     ///
     /// This method must be called after `try_select` succeeds and there is a packet to provide.
     #[inline]
-    pub fn store_packet(&self, packet: usize) {
-        if packet != 0 {
-            self.inner.packet.store(packet, Ordering::Release);
+    pub fn store_packet(&self, packet: *const u8) {
+        if !packet.is_null() {
+            self.inner.packet.store(packet as *mut u8, Ordering::Release);
         }
     }
 
     /// Waits until a packet is provided and returns it.
     #[inline]
-    pub fn wait_packet(&self) -> usize {
+    pub fn wait_packet(&self) -> *const u8 {
         let mut backoff = Backoff::new();
         loop {
             let packet = self.inner.packet.load(Ordering::Acquire);
-            if packet != 0 {
+            if !packet.is_null() {
                 return packet;
             }
             backoff.snooze();
@@ -134,19 +206,47 @@ impl Context {
     /// If the deadline is reached, `Selected::Aborted` will be selected.
     #[inline]
     pub fn wait_until(&self, deadline: Option<Instant>) -> Selected {
-        // Spin for a short time, waiting until an operation is selected.
-        let mut backoff = Backoff::new();
-        loop {
-            let sel = Selected::from(self.inner.select.load(Ordering::Acquire));
-            if sel != Selected::Waiting {
-                return sel;
+        // Adaptive spinning learns from how long operations on the channel wait.
+        match unsafe { stats::spin() } {
+            Some(spin) if spin.is_adaptive() => {
+                let start = Instant::now();
+                let sel = self.spin_and_park_until(deadline, spin.should_spin());
+                spin.observe(start.elapsed());
+                sel
             }
+            _ => self.spin_and_park_until(deadline, true),
+        }
+    }
 
-            if !backoff.snooze() {
-                break;
+    /// Spins for a short time if `spin` is `true`, and then parks the current thread until an
+    /// operation is selected.
+    fn spin_and_park_until(&self, deadline: Option<Instant>, spin: bool) -> Selected {
+        if spin {
+            // Spin for a short time, waiting until an operation is selected.
+            let mut backoff = Backoff::new();
+            loop {
+                let sel = Selected::from(self.inner.select.load(Ordering::Acquire));
+                if sel != Selected::Waiting {
+                    return sel;
+                }
+
+                if !backoff.snooze() {
+                    break;
+                }
             }
         }
 
+        let op = stats::take();
+        let start = Instant::now();
+        let sel = self.park_until(deadline);
+        stats::parked(op, start.elapsed());
+        sel
+    }
+
+    /// Parks the current thread until an operation is selected and returns it.
+    ///
+    /// If the deadline is reached, `Selected::Aborted` will be selected.
+    fn park_until(&self, deadline: Option<Instant>) -> Selected {
         loop {
             // Check whether an operation has been selected.
             let sel = Selected::from(self.inner.select.load(Ordering::Acquire));
@@ -159,7 +259,10 @@ impl Context {
                 let now = Instant::now();
 
                 if now < end {
-                    thread::park_timeout(end - now);
+                    match &self.inner.parker {
+                        Some(parker) => parker.park(Some(end)),
+                        None => thread::park_timeout(end - now),
+                    }
                 } else {
                     // The deadline has been reached. Try aborting select.
                     return match self.try_select(Selected::Aborted) {
@@ -168,20 +271,32 @@ impl Context {
                     };
                 }
             } else {
-                thread::park();
+                match &self.inner.parker {
+                    Some(parker) => parker.park(None),
+                    None => thread::park(),
+                }
             }
         }
     }
 
-    /// Unparks the thread this context belongs to.
+    /// Wakes up the execution context this context belongs to.
     #[inline]
     pub fn unpark(&self) {
-        self.inner.thread.unpark();
+        match &self.inner.parker {
+            Some(parker) => parker.unpark(),
+            None => self.inner.thread.unpark(),
+        }
     }
 
-    /// Returns the id of the thread this context belongs to.
+    /// Returns the id of the execution context this context belongs to.
     #[inline]
-    pub fn thread_id(&self) -> ThreadId {
-        self.inner.thread_id
+    pub fn id(&self) -> Id {
+        self.inner.id
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Context").finish()
     }
 }
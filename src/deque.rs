@@ -0,0 +1,485 @@
+//! Work-stealing deques that can participate in select.
+//!
+//! The deques of [`crossbeam-deque`] don't tell anyone when a task is pushed into them, so a
+//! worker that waits for tasks and for messages on a channel at the same time would have to poll
+//! the deque in a loop. The types in this module wrap those deques and wake up blocked selections
+//! whenever a task is pushed. [`Injector`] and [`Stealer`] implement [`Selectable`], so they can
+//! be used in the `custom` case of [`select!`] or added to a [`Select`] with [`Select::custom`].
+//! Such an operation is ready while the deque is not empty.
+//!
+//! Selecting a deque takes no task out of it. Another worker may steal the task first, in which
+//! case stealing after the selection comes up empty and the worker simply selects again.
+//!
+//! This module is available only if the `deque` feature is enabled.
+//!
+//! [`crossbeam-deque`]: https://docs.rs/crossbeam-deque
+//! [`Injector`]: struct.Injector.html
+//! [`Stealer`]: struct.Stealer.html
+//! [`Selectable`]: ../trait.Selectable.html
+//! [`select!`]: ../macro.select.html
+//! [`Select`]: ../struct.Select.html
+//! [`Select::custom`]: ../struct.Select.html#method.custom
+//!
+//! # Examples
+//!
+//! A worker that takes tasks from a global queue until it is told to quit:
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate crossbeam_channel;
+//! # fn main() {
+//! use std::sync::Arc;
+//! use std::thread;
+//! use crossbeam_channel::deque::Injector;
+//! use crossbeam_channel::unbounded;
+//!
+//! let injector = Arc::new(Injector::new());
+//! let (quit_s, quit_r) = unbounded::<()>();
+//! let (done_s, done_r) = unbounded();
+//!
+//! let inj = injector.clone();
+//! let worker = thread::spawn(move || loop {
+//!     select! {
+//!         custom(*inj) => {
+//!             if let Some(task) = inj.steal().success() {
+//!                 done_s.send(task * 2).unwrap();
+//!             }
+//!         }
+//!         recv(quit_r) -> _ => break,
+//!     }
+//! });
+//!
+//! for task in 0..3 {
+//!     injector.push(task);
+//! }
+//! assert_eq!(done_r.iter().take(3).collect::<Vec<_>>(), [0, 2, 4]);
+//!
+//! drop(quit_s);
+//! worker.join().unwrap();
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use crossbeam_deque;
+
+use selectable::{Notifier, Selectable};
+
+pub use crossbeam_deque::Steal;
+
+/// A FIFO queue shared among workers, typically the global queue of a scheduler.
+///
+/// Pushing a task wakes up selections waiting for the injector to become non-empty.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::deque::{Injector, Steal};
+///
+/// let q = Injector::new();
+/// q.push(1);
+/// q.push(2);
+///
+/// assert_eq!(q.steal(), Steal::Success(1));
+/// assert_eq!(q.steal(), Steal::Success(2));
+/// assert_eq!(q.steal(), Steal::Empty);
+/// ```
+pub struct Injector<T> {
+    /// The underlying queue.
+    inner: crossbeam_deque::Injector<T>,
+
+    /// Wakes up selections waiting for a task.
+    notifier: Notifier,
+}
+
+impl<T> Injector<T> {
+    /// Creates a new empty injector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Injector;
+    ///
+    /// let q = Injector::<i32>::new();
+    /// assert!(q.is_empty());
+    /// ```
+    pub fn new() -> Injector<T> {
+        Injector {
+            inner: crossbeam_deque::Injector::new(),
+            notifier: Notifier::new(),
+        }
+    }
+
+    /// Pushes a task into the queue and wakes up selections waiting for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Injector;
+    ///
+    /// let q = Injector::new();
+    /// q.push(1);
+    /// assert!(!q.is_empty());
+    /// ```
+    pub fn push(&self, task: T) {
+        self.inner.push(task);
+        self.notifier.notify();
+    }
+
+    /// Steals a task from the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::{Injector, Steal};
+    ///
+    /// let q = Injector::new();
+    /// q.push(1);
+    ///
+    /// assert_eq!(q.steal(), Steal::Success(1));
+    /// assert_eq!(q.steal(), Steal::Empty);
+    /// ```
+    pub fn steal(&self) -> Steal<T> {
+        self.inner.steal()
+    }
+
+    /// Steals a batch of tasks, pushes them into a worker, and pops a task from that worker.
+    ///
+    /// Selections waiting on stealers of the worker are woken up if tasks are left in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::{Injector, Steal, Worker};
+    ///
+    /// let q = Injector::new();
+    /// for i in 0..4 {
+    ///     q.push(i);
+    /// }
+    ///
+    /// let w = Worker::new_fifo();
+    /// assert_eq!(q.steal_batch_and_pop(&w), Steal::Success(0));
+    /// assert!(!w.is_empty());
+    /// ```
+    pub fn steal_batch_and_pop(&self, dest: &Worker<T>) -> Steal<T> {
+        let steal = self.inner.steal_batch_and_pop(&dest.inner);
+        dest.notify_if_not_empty();
+        steal
+    }
+
+    /// Returns `true` if the queue is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Injector;
+    ///
+    /// let q = Injector::new();
+    /// assert!(q.is_empty());
+    /// q.push(1);
+    /// assert!(!q.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<T: Send> Selectable for Injector<T> {
+    fn is_ready(&self) -> bool {
+        !self.inner.is_empty()
+    }
+
+    fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+}
+
+impl<T> Default for Injector<T> {
+    fn default() -> Injector<T> {
+        Injector::new()
+    }
+}
+
+impl<T> fmt::Debug for Injector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Injector { .. }")
+    }
+}
+
+/// The deque of a single worker thread, whose tasks can be stolen by other workers.
+///
+/// Pushing a task wakes up selections waiting on the worker's [`Stealer`]s. The worker itself
+/// can't be selected over because it can't be shared between threads.
+///
+/// [`Stealer`]: struct.Stealer.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::deque::{Steal, Worker};
+///
+/// let w = Worker::new_lifo();
+/// let s = w.stealer();
+///
+/// w.push(1);
+/// w.push(2);
+/// w.push(3);
+///
+/// assert_eq!(s.steal(), Steal::Success(1));
+/// assert_eq!(w.pop(), Some(3));
+/// assert_eq!(w.pop(), Some(2));
+/// ```
+pub struct Worker<T> {
+    /// The underlying deque.
+    inner: crossbeam_deque::Worker<T>,
+
+    /// Wakes up selections waiting on stealers of this worker.
+    notifier: Arc<Notifier>,
+}
+
+impl<T> Worker<T> {
+    /// Creates a worker whose tasks are popped in FIFO order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Worker;
+    ///
+    /// let w = Worker::new_fifo();
+    /// w.push(1);
+    /// w.push(2);
+    /// assert_eq!(w.pop(), Some(1));
+    /// ```
+    pub fn new_fifo() -> Worker<T> {
+        Worker {
+            inner: crossbeam_deque::Worker::new_fifo(),
+            notifier: Arc::new(Notifier::new()),
+        }
+    }
+
+    /// Creates a worker whose tasks are popped in LIFO order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Worker;
+    ///
+    /// let w = Worker::new_lifo();
+    /// w.push(1);
+    /// w.push(2);
+    /// assert_eq!(w.pop(), Some(2));
+    /// ```
+    pub fn new_lifo() -> Worker<T> {
+        Worker {
+            inner: crossbeam_deque::Worker::new_lifo(),
+            notifier: Arc::new(Notifier::new()),
+        }
+    }
+
+    /// Creates a stealer for this worker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::{Steal, Worker};
+    ///
+    /// let w = Worker::new_fifo();
+    /// w.push(1);
+    ///
+    /// let s = w.stealer();
+    /// assert_eq!(s.steal(), Steal::Success(1));
+    /// ```
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.stealer(),
+            notifier: self.notifier.clone(),
+        }
+    }
+
+    /// Pushes a task into the deque and wakes up selections waiting on its stealers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Worker;
+    ///
+    /// let w = Worker::new_fifo();
+    /// w.push(1);
+    /// assert!(!w.is_empty());
+    /// ```
+    pub fn push(&self, task: T) {
+        self.inner.push(task);
+        self.notifier.notify();
+    }
+
+    /// Pops a task from the deque.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Worker;
+    ///
+    /// let w = Worker::new_fifo();
+    /// w.push(1);
+    /// assert_eq!(w.pop(), Some(1));
+    /// assert_eq!(w.pop(), None);
+    /// ```
+    pub fn pop(&self) -> Option<T> {
+        self.inner.pop()
+    }
+
+    /// Returns `true` if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Worker;
+    ///
+    /// let w = Worker::new_lifo();
+    /// assert!(w.is_empty());
+    /// w.push(1);
+    /// assert!(!w.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Wakes up selections waiting on stealers if there are tasks to steal.
+    fn notify_if_not_empty(&self) {
+        if !self.inner.is_empty() {
+            self.notifier.notify();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Worker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Worker { .. }")
+    }
+}
+
+/// A stealer that takes tasks from the deque of a [`Worker`].
+///
+/// Cloning a stealer creates a new handle to the same deque.
+///
+/// [`Worker`]: struct.Worker.html
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::deque::{Steal, Worker};
+/// use crossbeam_channel::Select;
+///
+/// let w = Worker::new_fifo();
+/// let s = w.stealer();
+///
+/// thread::spawn(move || w.push(1));
+///
+/// // Wait until there is a task to steal.
+/// let mut sel = Select::new();
+/// let oper1 = sel.custom(&s);
+/// let oper = sel.select();
+/// assert_eq!(oper.index(), oper1);
+/// oper.custom(&s);
+///
+/// assert_eq!(s.steal(), Steal::Success(1));
+/// ```
+pub struct Stealer<T> {
+    /// The underlying stealer.
+    inner: crossbeam_deque::Stealer<T>,
+
+    /// Wakes up selections waiting for a task.
+    notifier: Arc<Notifier>,
+}
+
+impl<T> Stealer<T> {
+    /// Steals a task from the deque.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::{Steal, Worker};
+    ///
+    /// let w = Worker::new_fifo();
+    /// w.push(1);
+    /// w.push(2);
+    ///
+    /// let s = w.stealer();
+    /// assert_eq!(s.steal(), Steal::Success(1));
+    /// assert_eq!(s.steal(), Steal::Success(2));
+    /// assert_eq!(s.steal(), Steal::Empty);
+    /// ```
+    pub fn steal(&self) -> Steal<T> {
+        self.inner.steal()
+    }
+
+    /// Steals a batch of tasks, pushes them into another worker, and pops a task from that
+    /// worker.
+    ///
+    /// Selections waiting on stealers of the other worker are woken up if tasks are left in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::{Steal, Worker};
+    ///
+    /// let w1 = Worker::new_fifo();
+    /// for i in 0..4 {
+    ///     w1.push(i);
+    /// }
+    ///
+    /// let s = w1.stealer();
+    /// let w2 = Worker::new_fifo();
+    /// assert_eq!(s.steal_batch_and_pop(&w2), Steal::Success(0));
+    /// assert!(!w2.is_empty());
+    /// ```
+    pub fn steal_batch_and_pop(&self, dest: &Worker<T>) -> Steal<T> {
+        let steal = self.inner.steal_batch_and_pop(&dest.inner);
+        dest.notify_if_not_empty();
+        steal
+    }
+
+    /// Returns `true` if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::deque::Worker;
+    ///
+    /// let w = Worker::new_lifo();
+    /// let s = w.stealer();
+    /// assert!(s.is_empty());
+    /// w.push(1);
+    /// assert!(!s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<T: Send> Selectable for Stealer<T> {
+    fn is_ready(&self) -> bool {
+        !self.inner.is_empty()
+    }
+
+    fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.clone(),
+            notifier: self.notifier.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Stealer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Stealer { .. }")
+    }
+}
@@ -0,0 +1,249 @@
+//! Bidirectional channels.
+
+use std::fmt;
+use std::ops::Deref;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+use channel::{bounded, Receiver, Sender};
+use err::TrySendError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{SendError, SendTimeoutError};
+
+/// Creates a pair of connected endpoints for bidirectional communication.
+///
+/// The first endpoint sends messages of type `A` and receives messages of type `B`, while the
+/// second endpoint does the opposite. Messages of type `A` travel through a channel of capacity
+/// `cap_a`, and messages of type `B` travel through a channel of capacity `cap_b`. Both channels
+/// behave exactly like ones created by [`bounded`].
+///
+/// Each endpoint dereferences to its receiving side, which means it can be passed directly to
+/// [`select!`] and [`Select::recv`].
+///
+/// # Panics
+///
+/// Panics if either capacity is greater than `usize::max_value() / 4`.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::duplex;
+///
+/// let (client, server) = duplex::<i32, String>(1, 1);
+///
+/// thread::spawn(move || {
+///     for n in server.iter() {
+///         server.send(n.to_string()).unwrap();
+///     }
+/// });
+///
+/// client.send(42).unwrap();
+/// assert_eq!(client.recv(), Ok("42".to_string()));
+/// ```
+///
+/// [`bounded`]: fn.bounded.html
+/// [`select!`]: macro.select.html
+/// [`Select::recv`]: struct.Select.html#method.recv
+pub fn duplex<A, B>(cap_a: usize, cap_b: usize) -> (Duplex<A, B>, Duplex<B, A>) {
+    let (sa, ra) = bounded(cap_a);
+    let (sb, rb) = bounded(cap_b);
+
+    let first = Duplex {
+        sender: sa,
+        receiver: rb,
+    };
+    let second = Duplex {
+        sender: sb,
+        receiver: ra,
+    };
+    (first, second)
+}
+
+/// One endpoint of a bidirectional channel.
+///
+/// The endpoint sends messages of type `S` and receives messages of type `R`. It dereferences to
+/// its [`Receiver`], so all receiving methods are available on the endpoint directly, and it can
+/// be used as a receive operation in [`select!`] and [`Select`].
+///
+/// Endpoints are created by the [`duplex`] function.
+///
+/// [`Receiver`]: struct.Receiver.html
+/// [`select!`]: macro.select.html
+/// [`Select`]: struct.Select.html
+/// [`duplex`]: fn.duplex.html
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::{duplex, unbounded};
+///
+/// let (a, b) = duplex::<(), i32>(1, 1);
+/// let (s, r) = unbounded::<i32>();
+///
+/// b.send(10).unwrap();
+///
+/// select! {
+///     recv(a) -> msg => assert_eq!(msg, Ok(10)),
+///     recv(r) -> _ => panic!(),
+/// }
+/// # drop(s);
+/// # }
+/// ```
+pub struct Duplex<S, R> {
+    /// The sending side.
+    sender: Sender<S>,
+
+    /// The receiving side.
+    receiver: Receiver<R>,
+}
+
+impl<S, R> Duplex<S, R> {
+    /// Attempts to send a message to the other endpoint without blocking.
+    ///
+    /// See [`Sender::try_send`] for details.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{duplex, TrySendError};
+    ///
+    /// let (a, b) = duplex::<i32, ()>(1, 1);
+    ///
+    /// assert_eq!(a.try_send(1), Ok(()));
+    /// assert_eq!(a.try_send(2), Err(TrySendError::Full(2)));
+    ///
+    /// drop(b);
+    /// assert_eq!(a.try_send(3), Err(TrySendError::Disconnected(3)));
+    /// ```
+    pub fn try_send(&self, msg: S) -> Result<(), TrySendError<S>> {
+        self.sender.try_send(msg)
+    }
+
+    /// Blocks the current thread until a message is sent to the other endpoint or it gets
+    /// disconnected.
+    ///
+    /// See [`Sender::send`] for details.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{duplex, SendError};
+    ///
+    /// let (a, b) = duplex::<i32, ()>(1, 1);
+    /// assert_eq!(a.send(1), Ok(()));
+    /// assert_eq!(b.recv(), Ok(1));
+    ///
+    /// drop(b);
+    /// assert_eq!(a.send(2), Err(SendError(2)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: S) -> Result<(), SendError<S>> {
+        self.sender.send(msg)
+    }
+
+    /// Waits for a message to be sent to the other endpoint, but only for a limited time.
+    ///
+    /// See [`Sender::send_timeout`] for details.
+    ///
+    /// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{duplex, SendTimeoutError};
+    ///
+    /// let (a, b) = duplex::<i32, ()>(0, 0);
+    ///
+    /// assert_eq!(
+    ///     a.send_timeout(1, Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout(1)),
+    /// );
+    /// # drop(b);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout(&self, msg: S, timeout: Duration) -> Result<(), SendTimeoutError<S>> {
+        self.sender.send_timeout(msg, timeout)
+    }
+
+    /// Returns the sending side of the endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::duplex;
+    ///
+    /// let (a, b) = duplex::<i32, ()>(5, 1);
+    /// assert_eq!(a.sender().capacity(), Some(5));
+    /// # drop(b);
+    /// ```
+    pub fn sender(&self) -> &Sender<S> {
+        &self.sender
+    }
+
+    /// Returns the receiving side of the endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::duplex;
+    ///
+    /// let (a, b) = duplex::<i32, ()>(5, 1);
+    /// assert_eq!(a.receiver().capacity(), Some(1));
+    /// # drop(b);
+    /// ```
+    pub fn receiver(&self) -> &Receiver<R> {
+        &self.receiver
+    }
+
+    /// Splits the endpoint into its sending and receiving side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::duplex;
+    ///
+    /// let (a, b) = duplex(1, 1);
+    /// let (s, r) = a.into_inner();
+    ///
+    /// s.send(1).unwrap();
+    /// b.send("foo").unwrap();
+    ///
+    /// assert_eq!(b.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Ok("foo"));
+    /// ```
+    pub fn into_inner(self) -> (Sender<S>, Receiver<R>) {
+        (self.sender, self.receiver)
+    }
+}
+
+impl<S, R> Deref for Duplex<S, R> {
+    type Target = Receiver<R>;
+
+    fn deref(&self) -> &Receiver<R> {
+        &self.receiver
+    }
+}
+
+impl<S, R> Clone for Duplex<S, R> {
+    fn clone(&self) -> Self {
+        Duplex {
+            sender: self.sender.clone(),
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+impl<S, R> fmt::Debug for Duplex<S, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Duplex").finish()
+    }
+}
@@ -1,13 +1,19 @@
 use std::error;
 use std::fmt;
+use std::io;
+
+use channel::{Receiver, Sender};
 
 /// An error returned from the [`send`] method.
 ///
-/// The message could not be sent because the channel is disconnected.
+/// The message could not be sent because the channel is disconnected, or because it is full and
+/// its [`FullPolicy`] is [`FullPolicy::Fail`].
 ///
 /// The error contains the message so it can be recovered.
 ///
 /// [`send`]: struct.Sender.html#method.send
+/// [`FullPolicy`]: enum.FullPolicy.html
+/// [`FullPolicy::Fail`]: enum.FullPolicy.html#variant.Fail
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct SendError<T>(pub T);
 
@@ -45,6 +51,20 @@ pub enum SendTimeoutError<T> {
     Disconnected(T),
 }
 
+/// An error returned from the [`send_cancellable`] method.
+///
+/// The error contains the message being sent so it can be recovered.
+///
+/// [`send_cancellable`]: struct.Sender.html#method.send_cancellable
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum SendCancelError<T> {
+    /// The message could not be sent because the operation was cancelled.
+    Cancelled(T),
+
+    /// The message could not be sent because the channel is disconnected.
+    Disconnected(T),
+}
+
 /// An error returned from the [`recv`] method.
 ///
 /// A message could not be received because the channel is empty and disconnected.
@@ -83,6 +103,18 @@ pub enum RecvTimeoutError {
     Disconnected,
 }
 
+/// An error returned from the [`recv_cancellable`] method.
+///
+/// [`recv_cancellable`]: struct.Receiver.html#method.recv_cancellable
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RecvCancelError {
+    /// A message could not be received because the operation was cancelled.
+    Cancelled,
+
+    /// The message could not be received because the channel is empty and disconnected.
+    Disconnected,
+}
+
 /// An error returned from the [`try_select`] method.
 ///
 /// Failed because none of the channel operations were ready.
@@ -99,6 +131,28 @@ pub struct TrySelectError;
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub struct SelectTimeoutError;
 
+/// A message yielded by a [`TimeoutReceiver`] when no message arrived in time.
+///
+/// No message was received within the timeout since the previous message, or since the previous
+/// `Elapsed` if messages stopped arriving.
+///
+/// [`TimeoutReceiver`]: struct.TimeoutReceiver.html
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Elapsed;
+
+/// An error returned from the [`reunite`] method.
+///
+/// The sender and the receiver could not be reunited because they belong to different channels
+/// or because other handles to the channel still exist.
+///
+/// The error contains both handles so they can be recovered.
+///
+/// [`reunite`]: struct.Sender.html#method.reunite
+pub struct ReuniteError<T> {
+    /// The handles that could not be reunited.
+    halves: Box<(Sender<T>, Receiver<T>)>,
+}
+
 impl<T> fmt::Debug for SendError<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         "SendError(..)".fmt(f)
@@ -258,6 +312,70 @@ impl<T> SendTimeoutError<T> {
     }
 }
 
+impl<T> fmt::Debug for SendCancelError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendCancelError::Cancelled(..) => "Cancelled(..)".fmt(f),
+            SendCancelError::Disconnected(..) => "Disconnected(..)".fmt(f),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendCancelError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendCancelError::Cancelled(..) => "send operation was cancelled".fmt(f),
+            SendCancelError::Disconnected(..) => "sending on a disconnected channel".fmt(f),
+        }
+    }
+}
+
+impl<T: Send> error::Error for SendCancelError<T> {
+    fn description(&self) -> &str {
+        match *self {
+            SendCancelError::Cancelled(..) => "send operation was cancelled",
+            SendCancelError::Disconnected(..) => "sending on a disconnected channel",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        None
+    }
+}
+
+impl<T> From<SendError<T>> for SendCancelError<T> {
+    fn from(err: SendError<T>) -> SendCancelError<T> {
+        match err {
+            SendError(e) => SendCancelError::Disconnected(e),
+        }
+    }
+}
+
+impl<T> SendCancelError<T> {
+    /// Unwraps the message.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use crossbeam_channel::{bounded, CancelToken};
+    ///
+    /// let (s, r) = bounded(0);
+    /// let token = CancelToken::new();
+    /// token.cancel();
+    ///
+    /// if let Err(err) = s.send_cancellable("foo", &token) {
+    ///     assert_eq!(err.into_inner(), "foo");
+    /// }
+    /// # drop(r);
+    /// ```
+    pub fn into_inner(self) -> T {
+        match self {
+            SendCancelError::Cancelled(v) => v,
+            SendCancelError::Disconnected(v) => v,
+        }
+    }
+}
+
 impl fmt::Display for RecvError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         "receiving on an empty and disconnected channel".fmt(f)
@@ -334,6 +452,36 @@ impl From<RecvError> for RecvTimeoutError {
     }
 }
 
+impl fmt::Display for RecvCancelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecvCancelError::Cancelled => "receive operation was cancelled".fmt(f),
+            RecvCancelError::Disconnected => "channel is empty and disconnected".fmt(f),
+        }
+    }
+}
+
+impl error::Error for RecvCancelError {
+    fn description(&self) -> &str {
+        match *self {
+            RecvCancelError::Cancelled => "receive operation was cancelled",
+            RecvCancelError::Disconnected => "channel is empty and disconnected",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        None
+    }
+}
+
+impl From<RecvError> for RecvCancelError {
+    fn from(err: RecvError) -> RecvCancelError {
+        match err {
+            RecvError => RecvCancelError::Disconnected,
+        }
+    }
+}
+
 impl fmt::Display for TrySelectError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         "all operations in select would block".fmt(f)
@@ -365,3 +513,213 @@ impl error::Error for SelectTimeoutError {
         None
     }
 }
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "no message received within the timeout".fmt(f)
+    }
+}
+
+impl error::Error for Elapsed {
+    fn description(&self) -> &str {
+        "no message received within the timeout"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        None
+    }
+}
+
+impl<T> ReuniteError<T> {
+    /// Creates an error containing the handles that could not be reunited.
+    pub(crate) fn new(s: Sender<T>, r: Receiver<T>) -> ReuniteError<T> {
+        ReuniteError {
+            halves: Box::new((s, r)),
+        }
+    }
+
+    /// Unwraps the sender and the receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    ///
+    /// let (s1, r2) = s1.reunite(r2).unwrap_err().into_inner();
+    /// # drop((s1, r1, s2, r2));
+    /// ```
+    pub fn into_inner(self) -> (Sender<T>, Receiver<T>) {
+        *self.halves
+    }
+}
+
+impl<T> fmt::Debug for ReuniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "ReuniteError(..)".fmt(f)
+    }
+}
+
+impl<T> fmt::Display for ReuniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "tried to reunite handles that are not the last pair of one channel".fmt(f)
+    }
+}
+
+impl<T: Send> error::Error for ReuniteError<T> {
+    fn description(&self) -> &str {
+        "tried to reunite handles that are not the last pair of one channel"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        None
+    }
+}
+
+/// Converts a channel error into an I/O error, dropping the message.
+///
+/// The error kind is [`BrokenPipe`].
+///
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+impl<T> From<SendError<T>> for io::Error {
+    fn from(_: SendError<T>) -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "sending on a disconnected channel")
+    }
+}
+
+/// Converts a channel error into an I/O error, dropping the message.
+///
+/// The error kind is [`WouldBlock`] if the channel is full and [`BrokenPipe`] if it is
+/// disconnected.
+///
+/// [`WouldBlock`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.WouldBlock
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+impl<T> From<TrySendError<T>> for io::Error {
+    fn from(err: TrySendError<T>) -> io::Error {
+        match err {
+            TrySendError::Full(..) => {
+                io::Error::new(io::ErrorKind::WouldBlock, "sending on a full channel")
+            }
+            TrySendError::Disconnected(msg) => SendError(msg).into(),
+        }
+    }
+}
+
+/// Converts a channel error into an I/O error, dropping the message.
+///
+/// The error kind is [`TimedOut`] if the operation timed out and [`BrokenPipe`] if the channel is
+/// disconnected.
+///
+/// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+impl<T> From<SendTimeoutError<T>> for io::Error {
+    fn from(err: SendTimeoutError<T>) -> io::Error {
+        match err {
+            SendTimeoutError::Timeout(..) => {
+                io::Error::new(io::ErrorKind::TimedOut, "timed out waiting on send operation")
+            }
+            SendTimeoutError::Disconnected(msg) => SendError(msg).into(),
+        }
+    }
+}
+
+/// Converts a channel error into an I/O error, dropping the message.
+///
+/// The error kind is [`Interrupted`] if the operation was cancelled and [`BrokenPipe`] if the
+/// channel is disconnected.
+///
+/// [`Interrupted`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.Interrupted
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+impl<T> From<SendCancelError<T>> for io::Error {
+    fn from(err: SendCancelError<T>) -> io::Error {
+        match err {
+            SendCancelError::Cancelled(..) => {
+                io::Error::new(io::ErrorKind::Interrupted, "send operation was cancelled")
+            }
+            SendCancelError::Disconnected(msg) => SendError(msg).into(),
+        }
+    }
+}
+
+/// Converts a channel error into an I/O error.
+///
+/// The error kind is [`UnexpectedEof`].
+///
+/// [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+impl From<RecvError> for io::Error {
+    fn from(err: RecvError) -> io::Error {
+        io::Error::new(io::ErrorKind::UnexpectedEof, err)
+    }
+}
+
+/// Converts a channel error into an I/O error.
+///
+/// The error kind is [`WouldBlock`] if the channel is empty and [`UnexpectedEof`] if it is
+/// disconnected.
+///
+/// [`WouldBlock`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.WouldBlock
+/// [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+impl From<TryRecvError> for io::Error {
+    fn from(err: TryRecvError) -> io::Error {
+        match err {
+            TryRecvError::Empty => io::Error::new(io::ErrorKind::WouldBlock, err),
+            TryRecvError::Disconnected => io::Error::new(io::ErrorKind::UnexpectedEof, err),
+        }
+    }
+}
+
+/// Converts a channel error into an I/O error.
+///
+/// The error kind is [`TimedOut`] if the operation timed out and [`UnexpectedEof`] if the channel
+/// is disconnected.
+///
+/// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+/// [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+impl From<RecvTimeoutError> for io::Error {
+    fn from(err: RecvTimeoutError) -> io::Error {
+        match err {
+            RecvTimeoutError::Timeout => io::Error::new(io::ErrorKind::TimedOut, err),
+            RecvTimeoutError::Disconnected => io::Error::new(io::ErrorKind::UnexpectedEof, err),
+        }
+    }
+}
+
+/// Converts a channel error into an I/O error.
+///
+/// The error kind is [`Interrupted`] if the operation was cancelled and [`UnexpectedEof`] if the
+/// channel is disconnected.
+///
+/// [`Interrupted`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.Interrupted
+/// [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+impl From<RecvCancelError> for io::Error {
+    fn from(err: RecvCancelError) -> io::Error {
+        match err {
+            RecvCancelError::Cancelled => io::Error::new(io::ErrorKind::Interrupted, err),
+            RecvCancelError::Disconnected => io::Error::new(io::ErrorKind::UnexpectedEof, err),
+        }
+    }
+}
+
+/// Converts a selection error into an I/O error.
+///
+/// The error kind is [`WouldBlock`].
+///
+/// [`WouldBlock`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.WouldBlock
+impl From<TrySelectError> for io::Error {
+    fn from(err: TrySelectError) -> io::Error {
+        io::Error::new(io::ErrorKind::WouldBlock, err)
+    }
+}
+
+/// Converts a selection error into an I/O error.
+///
+/// The error kind is [`TimedOut`].
+///
+/// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+impl From<SelectTimeoutError> for io::Error {
+    fn from(err: SelectTimeoutError) -> io::Error {
+        io::Error::new(io::ErrorKind::TimedOut, err)
+    }
+}
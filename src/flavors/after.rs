@@ -7,10 +7,12 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 
+use clock::{self, Time};
 use context::Context;
 use err::{RecvTimeoutError, TryRecvError};
-use select::{Operation, SelectHandle, Token};
+use select::{self, Operation, SelectHandle, Token};
 use utils;
+use wheel;
 
 /// Result of a receive operation.
 pub type AfterToken = Option<Instant>;
@@ -22,15 +24,19 @@ pub struct Channel {
 
     /// `true` if the message has been received.
     received: Arc<AtomicBool>,
+
+    /// The clock measuring time.
+    time: Time,
 }
 
 impl Channel {
     /// Creates a channel that delivers a message after a certain duration of time.
     #[inline]
-    pub fn new(dur: Duration) -> Self {
+    pub fn new(dur: Duration, time: Time) -> Self {
         Channel {
-            delivery_time: Instant::now() + dur,
+            delivery_time: clock::now(&time) + dur,
             received: Arc::new(AtomicBool::new(false)),
+            time,
         }
     }
 
@@ -43,7 +49,7 @@ impl Channel {
             return Err(TryRecvError::Empty);
         }
 
-        if Instant::now() < self.delivery_time {
+        if clock::now(&self.time) < self.delivery_time {
             // The message was not delivered yet.
             return Err(TryRecvError::Empty);
         }
@@ -61,6 +67,10 @@ impl Channel {
     /// Receives a message from the channel.
     #[inline]
     pub fn recv(&self, deadline: Option<Instant>) -> Result<Instant, RecvTimeoutError> {
+        if self.time.is_some() {
+            return self.recv_with_clock(deadline);
+        }
+
         // We use relaxed ordering because this is just an optional optimistic check.
         if self.received.load(Ordering::Relaxed) {
             // The message has already been received.
@@ -100,6 +110,25 @@ impl Channel {
         }
     }
 
+    /// Receives a message from the channel, measuring time with a custom clock.
+    fn recv_with_clock(&self, deadline: Option<Instant>) -> Result<Instant, RecvTimeoutError> {
+        loop {
+            if let Ok(msg) = self.try_recv() {
+                return Ok(msg);
+            }
+
+            // Check if the deadline has been reached.
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+
+            // Wait until the clock advances far enough or the deadline is reached.
+            select::wait_ready(self, deadline);
+        }
+    }
+
     /// Reads a message from the channel.
     #[inline]
     pub unsafe fn read(&self, token: &mut Token) -> Result<Instant, ()> {
@@ -115,7 +144,7 @@ impl Channel {
         }
 
         // If the delivery time hasn't been reached yet, the channel is empty.
-        if Instant::now() < self.delivery_time {
+        if clock::now(&self.time) < self.delivery_time {
             return true;
         }
 
@@ -153,6 +182,7 @@ impl Clone for Channel {
         Channel {
             delivery_time: self.delivery_time,
             received: self.received.clone(),
+            time: self.time.clone(),
         }
     }
 }
@@ -182,16 +212,32 @@ impl SelectHandle for Channel {
 
     #[inline]
     fn deadline(&self) -> Option<Instant> {
-        Some(self.delivery_time)
+        // Timers on the system clock are fired by the timer wheel instead.
+        self.time.as_ref()?;
+        clock::deadline(&self.time, self.delivery_time)
     }
 
     #[inline]
-    fn register(&self, _token: &mut Token, _oper: Operation, _cx: &Context) -> bool {
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        if self.time.is_none() {
+            return self.received.load(Ordering::SeqCst)
+                || wheel::register(self.delivery_time, oper, cx);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().register(oper, cx);
+        }
         true
     }
 
     #[inline]
-    fn unregister(&self, _oper: Operation) {}
+    fn unregister(&self, oper: Operation) {
+        if self.time.is_none() {
+            wheel::unregister(oper);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().unregister(oper);
+        }
+    }
 
     #[inline]
     fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
@@ -203,10 +249,35 @@ impl SelectHandle for Channel {
         // Return 1 if the deadline has been reached and 0 otherwise.
         if self.received.load(Ordering::SeqCst) {
             1
-        } else if Instant::now() < self.delivery_time {
+        } else if clock::now(&self.time) < self.delivery_time {
             0
         } else {
             1
         }
     }
+
+    #[inline]
+    fn is_ready(&self) -> bool {
+        !self.is_empty()
+    }
+
+    #[inline]
+    fn watch(&self, oper: Operation, cx: &Context) {
+        if self.time.is_none() {
+            wheel::watch(self.delivery_time, oper, cx);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().watch(oper, cx);
+        }
+    }
+
+    #[inline]
+    fn unwatch(&self, oper: Operation) {
+        if self.time.is_none() {
+            wheel::unregister(oper);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().unwatch(oper);
+        }
+    }
 }
@@ -40,6 +40,8 @@
 //! ```
 
 use std::cell::UnsafeCell;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::mem;
 use std::ptr;
@@ -48,9 +50,20 @@ use std::time::Instant;
 
 use crossbeam_utils::CachePadded;
 
+use builder::{Displace, WakePolicy};
 use context::Context;
-use err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
-use select::{Operation, SelectHandle, Selected, Token};
+use err::{TryRecvError, TrySendError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvTimeoutError, SendTimeoutError};
+use latency::Histogram;
+#[cfg(feature = "latency")]
+use latency::{LatencyStats, Stamp};
+use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select::Selected;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use spin;
+use stash::Stash;
 use utils::Backoff;
 use waker::SyncWaker;
 
@@ -66,9 +79,14 @@ struct Slot<T> {
     ///
     /// If the lap in the stamp is odd, this value contains a message. Otherwise, it is empty.
     msg: UnsafeCell<T>,
+
+    /// The time the message in this slot was sent.
+    #[cfg(feature = "latency")]
+    sent: UnsafeCell<Stamp>,
 }
 
 /// The token type for the array flavor.
+#[derive(Debug)]
 pub struct ArrayToken {
     /// Slot to read from or write to.
     slot: *const u8,
@@ -87,6 +105,14 @@ impl Default for ArrayToken {
     }
 }
 
+impl ArrayToken {
+    /// Returns `true` if the token holds a slot, i.e. the channel was not disconnected.
+    #[inline]
+    pub fn has_slot(&self) -> bool {
+        !self.slot.is_null()
+    }
+}
+
 /// Bounded channel based on a preallocated array.
 pub struct Channel<T> {
     /// The head of the channel.
@@ -102,7 +128,8 @@ pub struct Channel<T> {
     ///
     /// This value is a "stamp" consisting of an index into the buffer and a lap, but packed into a
     /// single `usize`. The lower bits represent the index, while the upper bits represent the lap.
-    /// The lap in the tail is always an even number.
+    /// The lap in the tail is always an even number. The mark bit in the tail indicates that the
+    /// channel is disconnected.
     ///
     /// Messages are pushed into the tail of the channel.
     tail: CachePadded<AtomicUsize>,
@@ -113,11 +140,26 @@ pub struct Channel<T> {
     /// The channel capacity.
     cap: usize,
 
-    /// A stamp with the value of `{ lap: 1, index: 0 }`.
+    /// A stamp with the value of `{ lap: 1, mark: 0, index: 0 }`.
     one_lap: usize,
 
-    /// Equals `true` when the channel is disconnected.
-    is_disconnected: AtomicBool,
+    /// If this bit is set in the tail, that means the channel is disconnected.
+    mark_bit: usize,
+
+    /// Equals `true` once capacity has been held by a permit or a selective receive operation.
+    ///
+    /// Until then, send operations don't look at the counters below, so channels that never hold
+    /// capacity don't pay for them.
+    holds_capacity: AtomicBool,
+
+    /// Capacity held by permits and by messages set aside by selective receive operations.
+    ///
+    /// It sits on its own cache line, so holding capacity doesn't disturb the fields every
+    /// operation reads.
+    reserved: CachePadded<AtomicUsize>,
+
+    /// Number of messages set aside by selective receive operations that still hold capacity.
+    aside: AtomicUsize,
 
     /// Senders waiting while the channel is full.
     senders: SyncWaker,
@@ -125,6 +167,12 @@ pub struct Channel<T> {
     /// Receivers waiting while the channel is empty and not disconnected.
     receivers: SyncWaker,
 
+    /// Messages set aside by selective receive operations or put back by receivers.
+    stash: Stash<T>,
+
+    /// How long received messages have waited inside the channel.
+    latency: Histogram,
+
     /// Indicates that dropping a `Channel<T>` may drop values of type `T`.
     _marker: PhantomData<T>,
 }
@@ -134,14 +182,14 @@ impl<T> Channel<T> {
     ///
     /// # Panics
     ///
-    /// Panics if the capacity is not in the range `1 ..= usize::max_value() / 4`.
+    /// Panics if the capacity is not in the range `1 ..= usize::max_value() / 8`.
     pub fn with_capacity(cap: usize) -> Self {
         assert!(cap > 0, "capacity must be positive");
 
-        // Make sure there are at least two most significant bits to encode laps. If we can't
-        // reserve two bits, then panic. In that case, the buffer is likely too large to allocate
-        // anyway.
-        let cap_limit = usize::max_value() / 4;
+        // Make sure there are at least two most significant bits to encode laps, in addition to
+        // the mark bit. If we can't reserve three bits, then panic. In that case, the buffer is
+        // likely too large to allocate anyway.
+        let cap_limit = usize::max_value() / 8;
         assert!(
             cap <= cap_limit,
             "channel capacity is too large: {} > {}",
@@ -149,11 +197,14 @@ impl<T> Channel<T> {
             cap_limit
         );
 
-        // One lap is the smallest power of two greater than or equal to `cap`.
-        let one_lap = cap.next_power_of_two();
+        // The mark bit is the smallest power of two greater than or equal to `cap`.
+        let mark_bit = cap.next_power_of_two();
 
-        // Head is initialized to `{ lap: 1, index: 0 }`.
-        // Tail is initialized to `{ lap: 0, index: 0 }`.
+        // One lap is the next power of two after the mark bit.
+        let one_lap = mark_bit * 2;
+
+        // Head is initialized to `{ lap: 1, mark: 0, index: 0 }`.
+        // Tail is initialized to `{ lap: 0, mark: 0, index: 0 }`.
         let head = one_lap;
         let tail = 0;
 
@@ -178,15 +229,27 @@ impl<T> Channel<T> {
             buffer,
             cap,
             one_lap,
-            is_disconnected: AtomicBool::new(false),
+            mark_bit,
+            holds_capacity: AtomicBool::new(false),
+            reserved: CachePadded::new(AtomicUsize::new(0)),
+            aside: AtomicUsize::new(0),
             head: CachePadded::new(AtomicUsize::new(head)),
             tail: CachePadded::new(AtomicUsize::new(tail)),
             senders: SyncWaker::new(),
             receivers: SyncWaker::new(),
+            stash: Stash::new(),
+            latency: Histogram::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Sets which blocked thread an operation wakes up when several are waiting.
+    pub fn with_wake_policy(mut self, policy: WakePolicy) -> Self {
+        self.senders = SyncWaker::with_policy(policy);
+        self.receivers = SyncWaker::with_policy(policy);
+        self
+    }
+
     /// Returns a receiver handle to the channel.
     pub fn receiver(&self) -> Receiver<T> {
         Receiver(self)
@@ -199,18 +262,29 @@ impl<T> Channel<T> {
 
     /// Attempts to reserve a slot for sending a message.
     fn start_send(&self, token: &mut Token) -> bool {
-        // If the channel is disconnected, return early.
-        if self.is_disconnected() {
-            token.array.slot = ptr::null();
-            token.array.stamp = 0;
-            return true;
-        }
+        self.claim_slot(token, false)
+    }
 
+    /// Attempts to claim the slot at the tail for writing a message.
+    ///
+    /// Messages sent through permits (`reserved` is `true`) already hold capacity. Other messages
+    /// must leave room for the capacity held by permits and set-aside messages.
+    fn claim_slot(&self, token: &mut Token, reserved: bool) -> bool {
         let mut backoff = Backoff::new();
 
         loop {
-            // Load the tail and deconstruct it.
+            // Load the tail.
             let tail = self.tail.load(Ordering::SeqCst);
+
+            // If the channel is disconnected, return early. The mark bit is checked together with
+            // the position of the tail, so no message gets in once the channel is disconnected.
+            if tail & self.mark_bit != 0 {
+                token.array.slot = ptr::null();
+                token.array.stamp = 0;
+                return true;
+            }
+
+            // Deconstruct the tail.
             let index = tail & (self.one_lap - 1);
             let lap = tail & !(self.one_lap - 1);
 
@@ -220,26 +294,33 @@ impl<T> Channel<T> {
 
             // If the tail and the stamp match, we may attempt to push.
             if tail == stamp {
-                let new_tail = if index + 1 < self.cap {
-                    // Same lap, incremented index.
-                    // Set to `{ lap: lap, index: index + 1 }`.
-                    tail + 1
-                } else {
-                    // Two laps forward, index wraps around to zero.
-                    // Set to `{ lap: lap.wrapping_add(2), index: 0 }`.
-                    lap.wrapping_add(self.one_lap.wrapping_mul(2))
-                };
-
-                // Try moving the tail.
-                if self
-                    .tail
-                    .compare_exchange_weak(tail, new_tail, Ordering::SeqCst, Ordering::Relaxed)
-                    .is_ok()
-                {
-                    // Prepare the token for the follow-up call to `write`.
-                    token.array.slot = slot as *const Slot<T> as *const u8;
-                    token.array.stamp = stamp.wrapping_add(self.one_lap);
-                    return true;
+                // The slot is free, so unless some capacity is held, there's room for the message.
+                if reserved || self.held() == 0 || self.has_room(tail) {
+                    let new_tail = if index + 1 < self.cap {
+                        // Same lap, incremented index.
+                        // Set to `{ lap: lap, index: index + 1 }`.
+                        tail + 1
+                    } else {
+                        // Two laps forward, index wraps around to zero.
+                        // Set to `{ lap: lap.wrapping_add(2), index: 0 }`.
+                        lap.wrapping_add(self.one_lap.wrapping_mul(2))
+                    };
+
+                    // Try moving the tail.
+                    if self
+                        .tail
+                        .compare_exchange_weak(tail, new_tail, Ordering::SeqCst, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        // Prepare the token for the follow-up call to `write`.
+                        token.array.slot = slot as *const Slot<T> as *const u8;
+                        token.array.stamp = stamp.wrapping_add(self.one_lap);
+                        return true;
+                    }
+                // If there's no room and the tail hasn't moved in the meantime, the channel is
+                // full.
+                } else if self.tail.load(Ordering::SeqCst) == tail {
+                    return false;
                 }
             // But if the slot lags one lap behind the tail...
             } else if stamp.wrapping_add(self.one_lap) == tail {
@@ -256,6 +337,39 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Returns the capacity held by permits and set-aside messages.
+    #[inline]
+    fn held(&self) -> usize {
+        if self.holds_capacity.load(Ordering::SeqCst) {
+            self.reserved.load(Ordering::SeqCst)
+        } else {
+            0
+        }
+    }
+
+    /// Holds capacity for one more message and returns the capacity held now.
+    fn hold(&self) -> usize {
+        // Send operations that don't see the flag yet have to be ordered before the capacity is
+        // held, just like ones that see the counter at zero.
+        if !self.holds_capacity.load(Ordering::Relaxed) {
+            self.holds_capacity.store(true, Ordering::SeqCst);
+        }
+        self.reserved.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Returns `true` if a message can be pushed at `tail` without taking the capacity held by
+    /// permits and set-aside messages.
+    ///
+    /// This is only called while some capacity is held, which ordinary send operations don't do.
+    #[cold]
+    fn has_room(&self, tail: usize) -> bool {
+        // Capacity for a message is held before the head moves past it, so load the head first in
+        // order to count every message at least once.
+        let head = self.head.load(Ordering::SeqCst);
+        let reserved = self.reserved.load(Ordering::SeqCst);
+        self.distance(head, tail) + reserved < self.cap
+    }
+
     /// Writes a message into the channel.
     pub unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
         // If there is no slot, the channel is disconnected.
@@ -267,6 +381,8 @@ impl<T> Channel<T> {
 
         // Write the message into the slot and update the stamp.
         slot.msg.get().write(msg);
+        #[cfg(feature = "latency")]
+        slot.sent.get().write(Stamp::now());
         slot.stamp.store(token.array.stamp, Ordering::Release);
 
         // Wake a sleeping receiver.
@@ -274,8 +390,28 @@ impl<T> Channel<T> {
         Ok(())
     }
 
-    /// Attempts to reserve a slot for receiving a message.
+    /// Attempts to reserve a message for receiving, looking into the stash first.
     fn start_recv(&self, token: &mut Token) -> bool {
+        if self.stash.start_recv(token) {
+            return true;
+        }
+        if !self.start_recv_buffer(token) {
+            return false;
+        }
+
+        // If the channel is disconnected, a selective receive operation might be about to set a
+        // message aside, so the operation is not ready until it's done.
+        if token.array.slot.is_null() {
+            if self.stash.is_held() {
+                return false;
+            }
+            self.stash.start_recv(token);
+        }
+        true
+    }
+
+    /// Attempts to reserve a slot in the buffer for receiving a message.
+    fn start_recv_buffer(&self, token: &mut Token) -> bool {
         let mut backoff = Backoff::new();
 
         loop {
@@ -317,16 +453,13 @@ impl<T> Channel<T> {
 
                 // ...and if the tail lags one lap behind the head as well, that means the channel
                 // is empty.
-                if tail.wrapping_add(self.one_lap) == head {
+                if (tail & !self.mark_bit).wrapping_add(self.one_lap) == head {
                     // If the channel is disconnected...
-                    if self.is_disconnected() {
-                        // ...and still empty...
-                        if self.tail.load(Ordering::SeqCst) == tail {
-                            // ...then receive an error.
-                            token.array.slot = ptr::null();
-                            token.array.stamp = 0;
-                            return true;
-                        }
+                    if tail & self.mark_bit != 0 {
+                        // ...then receive an error.
+                        token.array.slot = ptr::null();
+                        token.array.stamp = 0;
+                        return true;
                     } else {
                         // Otherwise, the receive operation is not ready.
                         return false;
@@ -338,22 +471,106 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Attempts to reserve a slot in the buffer for receiving a message, holding capacity for the
+    /// message in case it is set aside or replaced.
+    ///
+    /// The capacity is held before the head moves past the message, so that senders never see its
+    /// slot free without seeing the capacity taken. Unless a message is claimed, the capacity is
+    /// given back.
+    fn start_recv_held(&self, token: &mut Token) -> bool {
+        self.hold();
+
+        let ready = self.start_recv_buffer(token);
+        if !ready || !token.array.has_slot() {
+            self.give_back(1);
+        }
+        ready
+    }
+
+    /// Gives back capacity held for `count` messages and wakes up as many sleeping senders.
+    fn give_back(&self, count: usize) {
+        self.reserved.fetch_sub(count, Ordering::SeqCst);
+        for _ in 0..count {
+            self.senders.wake_one();
+        }
+    }
+
     /// Reads a message from the channel.
     pub unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
+        if let Some(msg) = self.stash.read(token) {
+            if self.release_aside() {
+                self.give_back(1);
+            }
+            return Ok(msg);
+        }
+
         if token.array.slot.is_null() {
             // The channel is disconnected.
             return Err(());
         }
 
-        let slot: &Slot<T> = &*(token.array.slot as *const Slot<T>);
-
         // Read the message from the slot and update the stamp.
+        let msg = self.take(token);
+        self.free_slot(&token.array);
+        Ok(msg)
+    }
+
+    /// Takes the message out of a slot claimed by `start_recv_buffer`, without making the slot
+    /// available to senders.
+    unsafe fn take(&self, token: &Token) -> T {
+        let slot: &Slot<T> = &*(token.array.slot as *const Slot<T>);
         let msg = slot.msg.get().read();
-        slot.stamp.store(token.array.stamp, Ordering::Release);
+        #[cfg(feature = "latency")]
+        self.latency.record(slot.sent.get().read());
+        msg
+    }
+
+    /// Makes a slot available to senders once its message has been taken out.
+    fn free_slot(&self, token: &ArrayToken) {
+        let slot: &Slot<T> = unsafe { &*(token.slot as *const Slot<T>) };
+        slot.stamp.store(token.stamp, Ordering::Release);
 
         // Wake a sleeping sender.
         self.senders.wake_one();
-        Ok(msg)
+    }
+
+    /// Sets aside a message taken out of a slot claimed by `start_recv_held`, and wakes up blocked
+    /// receivers so that one of them can take it.
+    ///
+    /// The slot is freed, but the message keeps holding capacity until it leaves the stash.
+    #[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn set_aside(&self, token: &Token, msg: T) {
+        self.aside.fetch_add(1, Ordering::SeqCst);
+        self.stash.push(msg);
+
+        // No capacity is given back, so there's no need to wake senders.
+        let slot: &Slot<T> = unsafe { &*(token.array.slot as *const Slot<T>) };
+        slot.stamp.store(token.array.stamp, Ordering::Release);
+
+        self.receivers.notify_all();
+    }
+
+    /// Hands over capacity held by a set-aside message after a message leaves the stash.
+    ///
+    /// Messages put back or restored share the stash but don't hold capacity, so capacity is
+    /// handed over only while more set-aside messages hold it than there are messages left.
+    /// Returns `true` if the caller now holds the capacity.
+    fn release_aside(&self) -> bool {
+        let mut aside = self.aside.load(Ordering::SeqCst);
+        loop {
+            if aside <= self.stash.len() {
+                return false;
+            }
+            match self.aside.compare_exchange_weak(
+                aside,
+                aside - 1,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return true,
+                Err(a) => aside = a,
+            }
+        }
     }
 
     /// Attempts to send a message into the channel.
@@ -368,7 +585,103 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Attempts to send messages from the front of `msgs` at once without blocking.
+    ///
+    /// Consecutive free slots are claimed by moving the tail only once. Sent messages are removed
+    /// from `msgs`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_send_many(&self, msgs: &mut VecDeque<T>) -> usize {
+        // Capacity held by permits and set-aside messages has to be checked for every message.
+        if self.held() != 0 {
+            return self.try_send_first(msgs);
+        }
+
+        let mut backoff = Backoff::new();
+
+        loop {
+            // Load the tail. If the channel is disconnected, nothing can be sent.
+            let tail = self.tail.load(Ordering::SeqCst);
+            if tail & self.mark_bit != 0 {
+                return 0;
+            }
+
+            // Deconstruct the tail.
+            let index = tail & (self.one_lap - 1);
+            let lap = tail & !(self.one_lap - 1);
+
+            // Count the consecutive free slots, up to the end of the buffer.
+            let mut count = 0;
+            while count < msgs.len() && index + count < self.cap {
+                let slot = unsafe { &*self.buffer.add(index + count) };
+                if slot.stamp.load(Ordering::Acquire) != tail + count {
+                    break;
+                }
+                count += 1;
+            }
+
+            // If the slot at the tail is not free, let `claim_slot` find out whether the channel is
+            // full.
+            if count == 0 {
+                return self.try_send_first(msgs);
+            }
+
+            let new_tail = if index + count < self.cap {
+                // Same lap, index moved forward.
+                // Set to `{ lap: lap, mark: 0, index: index + count }`.
+                tail + count
+            } else {
+                // Two laps forward, index wraps around to zero.
+                // Set to `{ lap: lap.wrapping_add(2), mark: 0, index: 0 }`.
+                lap.wrapping_add(self.one_lap.wrapping_mul(2))
+            };
+
+            // Try moving the tail past all counted slots.
+            if self
+                .tail
+                .compare_exchange_weak(tail, new_tail, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                for (i, msg) in msgs.drain(..count).enumerate() {
+                    let slot = unsafe { &*self.buffer.add(index + i) };
+
+                    // Write the message into the slot and update the stamp.
+                    unsafe { slot.msg.get().write(msg) };
+                    #[cfg(feature = "latency")]
+                    unsafe { slot.sent.get().write(Stamp::now()) };
+                    slot.stamp
+                        .store((tail + i).wrapping_add(self.one_lap), Ordering::Release);
+
+                    // Wake a sleeping receiver.
+                    self.receivers.wake_one();
+                }
+
+                return count;
+            }
+
+            backoff.spin();
+        }
+    }
+
+    /// Attempts to send the first message in `msgs` without blocking.
+    ///
+    /// If the message is sent, it is removed from `msgs` and 1 is returned. Otherwise, 0 is
+    /// returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn try_send_first(&self, msgs: &mut VecDeque<T>) -> usize {
+        let token = &mut Token::default();
+        if msgs.is_empty() || !self.start_send(token) || !token.array.has_slot() {
+            return 0;
+        }
+
+        let msg = msgs.pop_front().unwrap();
+        unsafe {
+            let _ = self.write(token, msg);
+        }
+        1
+    }
+
     /// Sends a message into the channel.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub fn send(&self, msg: T, deadline: Option<Instant>) -> Result<(), SendTimeoutError<T>> {
         let token = &mut Token::default();
         loop {
@@ -379,7 +692,7 @@ impl<T> Channel<T> {
                     let res = unsafe { self.write(token, msg) };
                     return res.map_err(SendTimeoutError::Disconnected);
                 }
-                if !backoff.snooze() {
+                if !spin::should_spin() || !backoff.snooze() {
                     break;
                 }
             }
@@ -414,6 +727,299 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Sends a message into the channel, displacing a queued message if the channel is full.
+    ///
+    /// Capacity held by permits can't be displaced. If the channel is full only because of
+    /// permits, the new message itself is displaced. On success, the displaced message is
+    /// returned, if there is one.
+    pub fn send_lossy(&self, msg: T, displace: Displace) -> Result<Option<T>, T> {
+        let token = &mut Token::default();
+        let mut msg = msg;
+        let mut backoff = Backoff::new();
+
+        loop {
+            if self.start_send(token) {
+                return unsafe { self.write(token, msg).map(|()| None) };
+            }
+
+            // The channel is full, so try replacing a message in it.
+            let res = match displace {
+                Displace::Newest => self.displace_newest(msg),
+                Displace::Oldest => self.displace_oldest(msg),
+            };
+            match res {
+                Ok(old) => return Ok(old),
+                Err(m) => msg = m,
+            }
+
+            backoff.spin();
+        }
+    }
+
+    /// Attempts to replace the oldest message in a full channel.
+    ///
+    /// Messages in the stash are received first, so the oldest message is taken from there if
+    /// there is one. Otherwise, it is popped from the buffer.
+    fn displace_oldest(&self, msg: T) -> Result<Option<T>, T> {
+        if !self.is_full() {
+            return Err(msg);
+        }
+
+        match self.stash.pop() {
+            Some(old) => {
+                if self.release_aside() {
+                    return self.replace(old, msg);
+                }
+
+                // The message doesn't hold capacity, so room has to be made in the buffer.
+                self.stash.push_front(old);
+                self.displace_head(msg)
+            }
+            None => self.displace_head(msg),
+        }
+    }
+
+    /// Attempts to replace the message at the head of the buffer in a full channel.
+    ///
+    /// If the buffer is empty, the channel is full only because of capacity held by permits, and
+    /// the new message is displaced instead.
+    fn displace_head(&self, msg: T) -> Result<Option<T>, T> {
+        let token = &mut Token::default();
+        if !self.start_recv_held(token) {
+            return if self.is_full() { Ok(Some(msg)) } else { Err(msg) };
+        }
+        if !token.array.has_slot() {
+            return Err(msg);
+        }
+
+        // Free the slot, but keep holding the capacity for the new message.
+        let old = unsafe { self.take(token) };
+        let slot: &Slot<T> = unsafe { &*(token.array.slot as *const Slot<T>) };
+        slot.stamp.store(token.array.stamp, Ordering::Release);
+
+        self.replace(old, msg)
+    }
+
+    /// Sends `msg` using the capacity held by `old`, a message that was just taken out of the
+    /// channel.
+    ///
+    /// If the channel is disconnected in the meantime, `old` is put back and `msg` is returned.
+    fn replace(&self, old: T, msg: T) -> Result<Option<T>, T> {
+        match self.send_reserved(msg) {
+            Ok(()) => Ok(Some(old)),
+            Err(msg) => {
+                self.put_back(old);
+                Err(msg)
+            }
+        }
+    }
+
+    /// Attempts to replace the newest message in a full channel.
+    ///
+    /// The slot holding the newest message is locked by rolling its stamp back to the value it
+    /// had before the message was written, which makes receivers wait. The message is replaced
+    /// only if no receiver could have claimed the slot before it was locked.
+    ///
+    /// If the buffer is empty, the newest message is the last one in the stash.
+    fn displace_newest(&self, msg: T) -> Result<Option<T>, T> {
+        if !self.is_full() {
+            return Err(msg);
+        }
+
+        let tail = self.tail.load(Ordering::SeqCst) & !self.mark_bit;
+        let head = self.head.load(Ordering::SeqCst);
+
+        if tail.wrapping_add(self.one_lap) == head {
+            return match self.stash.replace_newest(msg) {
+                Ok(old) => {
+                    self.receivers.notify_all();
+                    Ok(Some(old))
+                }
+                // Only permits hold capacity, so there is nothing to displace.
+                Err(msg) => if self.is_full() { Ok(Some(msg)) } else { Err(msg) },
+            };
+        }
+
+        // Find the position the newest message was written to.
+        let index = tail & (self.one_lap - 1);
+        let lap = tail & !(self.one_lap - 1);
+        let prev = if index > 0 {
+            tail - 1
+        } else {
+            lap.wrapping_sub(self.one_lap.wrapping_mul(2)) + self.cap - 1
+        };
+        let written = prev.wrapping_add(self.one_lap);
+
+        // If the newest message is also the oldest one in the buffer, it's at the head.
+        if written == head {
+            return self.displace_head(msg);
+        }
+
+        // Lock the slot.
+        let slot = unsafe { &*self.buffer.add(prev & (self.one_lap - 1)) };
+        if slot
+            .stamp
+            .compare_exchange(written, prev, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(msg);
+        }
+
+        // Receivers that get to the slot from now on will wait until it's unlocked. But if the
+        // head has already reached the slot, a receiver might be about to take the message.
+        let head = self.head.load(Ordering::SeqCst);
+        let head_lap = head & !(self.one_lap - 1);
+        let written_lap = written & !(self.one_lap - 1);
+        let behind = match head_lap.wrapping_sub(written_lap) as isize {
+            0 => head & (self.one_lap - 1) < prev & (self.one_lap - 1),
+            d => d < 0,
+        };
+
+        if !behind {
+            // Unlock the slot, unless a receiver has already taken the message.
+            let _ = slot.stamp.compare_exchange(
+                prev,
+                written,
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            );
+            return Err(msg);
+        }
+
+        // Swap the messages and unlock the slot.
+        let old = unsafe { slot.msg.get().replace(msg) };
+        #[cfg(feature = "latency")]
+        unsafe { slot.sent.get().write(Stamp::now()) };
+        slot.stamp.store(written, Ordering::Release);
+
+        Ok(Some(old))
+    }
+
+    /// Attempts to reserve capacity for a future message without blocking.
+    ///
+    /// The capacity is held by a counter, so messages sent in the meantime are not held up. The
+    /// counter is incremented before checking that there was room, so that senders that see the
+    /// channel fuller than it is don't exceed the capacity. A sender that has already checked
+    /// the counter might still take the last free slot, in which case the reserved message blocks
+    /// in `send_reserved` until a receiver frees one.
+    pub fn try_reserve(&self) -> Result<(), TrySendError<()>> {
+        if self.is_disconnected() {
+            return Err(TrySendError::Disconnected(()));
+        }
+
+        let reserved = self.hold();
+        if self.buffer_len() + reserved > self.cap {
+            self.give_back(1);
+            return Err(TrySendError::Full(()));
+        }
+        Ok(())
+    }
+
+    /// Reserves capacity for a future message.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn reserve(&self, deadline: Option<Instant>) -> Result<(), SendTimeoutError<()>> {
+        let token = &mut Token::default();
+        loop {
+            // Try reserving capacity several times.
+            let mut backoff = Backoff::new();
+            loop {
+                match self.try_reserve() {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Disconnected(())) => {
+                        return Err(SendTimeoutError::Disconnected(()))
+                    }
+                    Err(TrySendError::Full(())) => {}
+                }
+                if !spin::should_spin() || !backoff.snooze() {
+                    break;
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.senders.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if !self.is_full() || self.is_disconnected() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.senders.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(SendTimeoutError::Timeout(()));
+                }
+            }
+        }
+    }
+
+    /// Sends a message using capacity held by `try_reserve` or `reserve`.
+    ///
+    /// The message doesn't have to leave room for held capacity, so it fits in unless a receiver
+    /// is just reading from the slot or a sender raced with the reservation and took the last
+    /// free slot. In the latter case, the current thread blocks until a receiver frees a slot. If
+    /// the channel is disconnected, the capacity is given up and the message is returned back.
+    pub fn send_reserved(&self, msg: T) -> Result<(), T> {
+        let token = &mut Token::default();
+        let mut backoff = Backoff::new();
+
+        while !self.claim_slot(token, true) {
+            if backoff.snooze() {
+                continue;
+            }
+
+            // Receivers wake up watching senders whenever they free a slot.
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            self.senders
+                .wait_until(|| self.buffer_len() < self.cap || self.is_disconnected(), true);
+        }
+
+        // The message now takes the slot instead of the held capacity.
+        self.reserved.fetch_sub(1, Ordering::SeqCst);
+        unsafe { self.write(token, msg) }
+    }
+
+    /// Gives up capacity held by `try_reserve` or `reserve`.
+    pub fn release(&self) {
+        self.give_back(1);
+    }
+
+    /// Blocks until all messages sent so far have left the buffer.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn flush(&self, park: bool) {
+        // Once every message before the current tail is received, the head reaches the same
+        // position, which is represented by a stamp one lap ahead.
+        let tail = self.tail.load(Ordering::SeqCst) & !self.mark_bit;
+        let target = tail.wrapping_add(self.one_lap);
+
+        let is_flushed = || {
+            if self.is_disconnected() {
+                return true;
+            }
+
+            let head = self.head.load(Ordering::SeqCst);
+            let head_lap = head & !(self.one_lap - 1);
+            let target_lap = target & !(self.one_lap - 1);
+            match head_lap.wrapping_sub(target_lap) as isize {
+                0 => head & (self.one_lap - 1) >= target & (self.one_lap - 1),
+                d => d > 0,
+            }
+        };
+        self.senders.wait_until(is_flushed, park);
+    }
+
     /// Attempts to receive a message without blocking.
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         let token = &mut Token::default();
@@ -427,7 +1033,83 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Attempts to receive up to `limit` messages at once without blocking.
+    ///
+    /// Consecutive messages are claimed by moving the head only once. Received messages are pushed
+    /// into `buf`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_recv_many(&self, buf: &mut Vec<T>, limit: usize) -> usize {
+        // Messages set aside by selective receive operations come first.
+        let stashed = self.stash.pop_many(buf, limit);
+        let released = (0..stashed).filter(|_| self.release_aside()).count();
+        if released > 0 {
+            self.give_back(released);
+        }
+        let limit = limit - stashed;
+        let mut backoff = Backoff::new();
+
+        loop {
+            // Load the head and deconstruct it.
+            let head = self.head.load(Ordering::SeqCst);
+            let index = head & (self.one_lap - 1);
+            let lap = head & !(self.one_lap - 1);
+
+            // Count the consecutive slots holding a message, up to the end of the buffer.
+            let mut count = 0;
+            while count < limit && index + count < self.cap {
+                let slot = unsafe { &*self.buffer.add(index + count) };
+                if slot.stamp.load(Ordering::Acquire) != head + count {
+                    break;
+                }
+                count += 1;
+            }
+
+            if count == 0 {
+                return stashed;
+            }
+
+            let new = if index + count < self.cap {
+                // Same lap, index moved forward.
+                // Set to `{ lap: lap, index: index + count }`.
+                head + count
+            } else {
+                // Two laps forward, index wraps around to zero.
+                // Set to `{ lap: lap.wrapping_add(2), index: 0 }`.
+                lap.wrapping_add(self.one_lap.wrapping_mul(2))
+            };
+
+            // Try moving the head past all counted slots.
+            if self
+                .head
+                .compare_exchange_weak(head, new, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                buf.reserve(count);
+
+                for i in 0..count {
+                    let slot = unsafe { &*self.buffer.add(index + i) };
+
+                    // Read the message from the slot and update the stamp.
+                    let msg = unsafe { slot.msg.get().read() };
+                    #[cfg(feature = "latency")]
+                    self.latency.record(unsafe { slot.sent.get().read() });
+                    slot.stamp
+                        .store((head + i).wrapping_add(self.one_lap), Ordering::Release);
+                    buf.push(msg);
+
+                    // Wake a sleeping sender.
+                    self.senders.wake_one();
+                }
+
+                return stashed + count;
+            }
+
+            backoff.spin();
+        }
+    }
+
     /// Receives a message from the channel.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
         let token = &mut Token::default();
         loop {
@@ -438,7 +1120,7 @@ impl<T> Channel<T> {
                     let res = unsafe { self.read(token) };
                     return res.map_err(|_| RecvTimeoutError::Disconnected);
                 }
-                if !backoff.snooze() {
+                if !spin::should_spin() || !backoff.snooze() {
                     break;
                 }
             }
@@ -449,7 +1131,7 @@ impl<T> Channel<T> {
                 self.receivers.register(oper, cx);
 
                 // Has the channel become ready just now?
-                if !self.is_empty() || self.is_disconnected() {
+                if self.can_recv() {
                     let _ = cx.try_select(Selected::Aborted);
                 }
 
@@ -475,8 +1157,133 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Receives the oldest message satisfying `pred`, setting aside messages that don't.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_where<F>(
+        &self,
+        pred: &mut F,
+        deadline: Option<Instant>,
+    ) -> Result<T, RecvTimeoutError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let token = &mut Token::default();
+        let wake = || self.receivers.notify_all();
+        loop {
+            // Look for the message among the ones that were set aside earlier.
+            let mut stamp = self.stash.stamp();
+            if let Some(msg) = self.stash.take_where(pred, &mut stamp, wake) {
+                if self.release_aside() {
+                    self.give_back(1);
+                }
+                return Ok(msg);
+            }
+
+            // Take messages out of the buffer until one satisfies the predicate.
+            let mut backoff = Backoff::new();
+            loop {
+                let hold = self.stash.hold(&self.receivers);
+                if self.start_recv_held(token) {
+                    if token.array.has_slot() {
+                        let msg = unsafe { self.take(token) };
+                        if pred(&msg) {
+                            self.free_slot(&token.array);
+                            self.give_back(1);
+                            return Ok(msg);
+                        }
+                        self.set_aside(token, msg);
+                        continue;
+                    }
+
+                    // The channel is disconnected, but another thread might be setting aside a
+                    // message just now. If so, wait until it is done.
+                    drop(hold);
+                    let held = self.stash.is_held();
+                    if let Some(msg) = self.stash.take_where(pred, &mut stamp, wake) {
+                        if self.release_aside() {
+                            self.give_back(1);
+                        }
+                        return Ok(msg);
+                    }
+                    if !held {
+                        return Err(RecvTimeoutError::Disconnected);
+                    }
+                    break;
+                }
+                drop(hold);
+                if !spin::should_spin() || !backoff.snooze() {
+                    break;
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender or another receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.receivers.register(oper, cx);
+
+                // Has the channel become ready or has a message been set aside just now?
+                if !self.is_buffer_empty() || self.is_drained() || self.stash.stamp() != stamp {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.receivers.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+        }
+    }
+
+    /// Puts a received message back so that it is the next one to be received, and wakes up
+    /// blocked receivers.
+    pub fn put_back(&self, msg: T) {
+        self.stash.push_front(msg);
+        self.receivers.notify_all();
+    }
+
+    /// Sets aside all messages in the buffer and returns clones of all messages set aside, in the
+    /// order they are going to be received.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let token = &mut Token::default();
+        let hold = self.stash.hold(&self.receivers);
+        while self.start_recv_held(token) && token.array.has_slot() {
+            let msg = unsafe { self.take(token) };
+            self.set_aside(token, msg);
+        }
+        drop(hold);
+        self.stash.cloned()
+    }
+
+    /// Puts messages in front of all others, in order, and wakes up blocked receivers.
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, msgs: Vec<T>) {
+        self.stash.push_front_many(msgs);
+        self.receivers.notify_all();
+    }
+
     /// Returns the current number of messages inside the channel.
     pub fn len(&self) -> usize {
+        self.stash.len() + self.buffer_len()
+    }
+
+    /// Returns the current number of messages inside the buffer.
+    fn buffer_len(&self) -> usize {
         loop {
             // Load the tail, then load the head.
             let tail = self.tail.load(Ordering::SeqCst);
@@ -484,30 +1291,50 @@ impl<T> Channel<T> {
 
             // If the tail didn't change, we've got consistent values to work with.
             if self.tail.load(Ordering::SeqCst) == tail {
-                let hix = head & (self.one_lap - 1);
-                let tix = tail & (self.one_lap - 1);
-
-                return if hix < tix {
-                    tix - hix
-                } else if hix > tix {
-                    self.cap - hix + tix
-                } else if tail.wrapping_add(self.one_lap) == head {
-                    0
-                } else {
-                    self.cap
-                };
+                return self.distance(head, tail & !self.mark_bit);
             }
         }
     }
 
+    /// Returns the number of slots from `head` up to `tail`.
+    fn distance(&self, head: usize, tail: usize) -> usize {
+        let hix = head & (self.one_lap - 1);
+        let tix = tail & (self.one_lap - 1);
+
+        if hix < tix {
+            tix - hix
+        } else if hix > tix {
+            self.cap - hix + tix
+        } else if tail.wrapping_add(self.one_lap) == head {
+            0
+        } else {
+            self.cap
+        }
+    }
+
     /// Returns the capacity of the channel.
     pub fn capacity(&self) -> Option<usize> {
         Some(self.cap)
     }
 
+    /// Returns the number of bytes the channel has allocated on the heap.
+    pub fn memory_usage(&self) -> usize {
+        self.cap * mem::size_of::<Slot<T>>()
+            + self.stash.memory_usage()
+            + self.latency.memory_usage()
+    }
+
+    /// Returns statistics about how long received messages have waited inside the channel.
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+
     /// Disconnects the channel and wakes up all blocked receivers.
     pub fn disconnect(&self) {
-        if !self.is_disconnected.swap(true, Ordering::SeqCst) {
+        let tail = self.tail.fetch_or(self.mark_bit, Ordering::SeqCst);
+
+        if tail & self.mark_bit == 0 {
             self.senders.disconnect();
             self.receivers.disconnect();
         }
@@ -515,13 +1342,23 @@ impl<T> Channel<T> {
 
     /// Returns `true` if the channel is disconnected.
     pub fn is_disconnected(&self) -> bool {
-        self.is_disconnected.load(Ordering::SeqCst)
+        self.tail.load(Ordering::SeqCst) & self.mark_bit != 0
+    }
+
+    /// Returns the number of blocked send and receive operations.
+    pub fn waiting(&self) -> (usize, usize) {
+        (self.senders.len(), self.receivers.len())
     }
 
     /// Returns `true` if the channel is empty.
     pub fn is_empty(&self) -> bool {
+        self.stash.is_empty() && self.is_buffer_empty()
+    }
+
+    /// Returns `true` if the buffer is empty.
+    fn is_buffer_empty(&self) -> bool {
         let head = self.head.load(Ordering::SeqCst);
-        let tail = self.tail.load(Ordering::SeqCst);
+        let tail = self.tail.load(Ordering::SeqCst) & !self.mark_bit;
 
         // Is the tail lagging one lap behind head?
         //
@@ -532,14 +1369,18 @@ impl<T> Channel<T> {
 
     /// Returns `true` if the channel is full.
     pub fn is_full(&self) -> bool {
-        let tail = self.tail.load(Ordering::SeqCst);
-        let head = self.head.load(Ordering::SeqCst);
+        // Capacity held by permits and messages in the stash counts as taken.
+        self.buffer_len() + self.held() >= self.cap
+    }
 
-        // Is the head lagging one lap behind tail?
-        //
-        // Note: If the tail changes just before we load the head, that means there was a moment
-        // when the channel was not full, so it is safe to just return `false`.
-        head.wrapping_add(self.one_lap) == tail
+    /// Returns `true` if the channel is disconnected and no message is being set aside.
+    fn is_drained(&self) -> bool {
+        self.is_disconnected() && !self.stash.is_held()
+    }
+
+    /// Returns `true` if a receive operation would not block.
+    fn can_recv(&self) -> bool {
+        !self.stash.is_empty() || !self.is_buffer_empty() || self.is_drained()
     }
 }
 
@@ -549,7 +1390,7 @@ impl<T> Drop for Channel<T> {
         let hix = self.head.load(Ordering::Relaxed) & (self.one_lap - 1);
 
         // Loop over all slots that hold a message and drop them.
-        for i in 0..self.len() {
+        for i in 0..self.buffer_len() {
             // Compute the index of the next slot holding a message.
             let index = if hix + i < self.cap {
                 hix + i
@@ -590,7 +1431,7 @@ impl<'a, T> SelectHandle for Receiver<'a, T> {
 
     fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
         self.0.receivers.register(oper, cx);
-        self.0.is_empty() && !self.0.is_disconnected()
+        !self.0.can_recv()
     }
 
     fn unregister(&self, oper: Operation) {
@@ -604,6 +1445,18 @@ impl<'a, T> SelectHandle for Receiver<'a, T> {
     fn state(&self) -> usize {
         self.0.tail.load(Ordering::SeqCst)
     }
+
+    fn is_ready(&self) -> bool {
+        self.0.can_recv()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.receivers.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.receivers.unwatch(oper);
+    }
 }
 
 impl<'a, T> SelectHandle for Sender<'a, T> {
@@ -635,4 +1488,16 @@ impl<'a, T> SelectHandle for Sender<'a, T> {
     fn state(&self) -> usize {
         self.0.head.load(Ordering::SeqCst)
     }
+
+    fn is_ready(&self) -> bool {
+        !self.0.is_full() || self.0.is_disconnected()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.senders.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.senders.unwatch(oper);
+    }
 }
@@ -0,0 +1,970 @@
+//! Bounded channel with several priority lanes.
+//!
+//! Messages are queued in lanes, and receive operations always take the oldest message from the
+//! highest non-empty lane. All lanes share the capacity of the channel, so a full channel blocks
+//! senders no matter which lane they are sending into.
+//!
+//! The same flavor with a single lane implements channels bounded by the total weight of queued
+//! messages rather than their number. Every message then takes up as many units of capacity as it
+//! weighs.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+use builder::{Displace, WakePolicy};
+use context::Context;
+use err::{TryRecvError, TrySendError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvTimeoutError, SendTimeoutError};
+#[cfg(feature = "latency")]
+use latency::LatencyStats;
+use latency::{Histogram, Stamp};
+use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select::Selected;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use spin;
+use utils::Backoff;
+use waker::SyncWaker;
+
+/// A function computing the weight of a message.
+pub type Weigher<T> = Box<Fn(&T) -> usize + Send + Sync>;
+
+/// The token type for the lanes flavor.
+///
+/// Equals `true` if a slot or a message was claimed, and `false` if the channel is disconnected.
+pub type LanesToken = bool;
+
+/// Inner representation of a channel with priority lanes.
+struct Inner<T> {
+    /// Queued messages and the times they were sent, one queue per lane. The last lane has the
+    /// highest priority.
+    lanes: Box<[VecDeque<(T, Stamp)>]>,
+
+    /// The number of queued messages in all lanes.
+    len: usize,
+
+    /// The total weight of queued messages in all lanes.
+    ///
+    /// Equals `len` unless the channel has a weigher.
+    weight: usize,
+
+    /// Computes the weight of a message, or `None` if every message weighs 1.
+    weigher: Option<Weigher<T>>,
+
+    /// Sequence number of the message at the front of each lane.
+    heads: Box<[usize]>,
+
+    /// Sequence number following the message at the back of each lane.
+    ///
+    /// When a message is removed from behind the front of a lane, the messages after it move one
+    /// position forward and take over its sequence number and the following ones.
+    tails: Box<[usize]>,
+
+    /// The number of queued messages claimed by receive operations that haven't read them yet.
+    claimed: usize,
+
+    /// The number of slots claimed by send operations that haven't written into them yet.
+    writing: usize,
+
+    /// The number of slots reserved by permits that haven't been used yet.
+    reserved: usize,
+}
+
+impl<T> Inner<T> {
+    /// Returns the number of queued messages not yet claimed by receive operations.
+    fn available(&self) -> usize {
+        self.len - self.claimed
+    }
+
+    /// Returns the number of slots not yet taken by messages, send operations or permits.
+    ///
+    /// Send operations and permits take up one slot each until their messages are weighed.
+    fn free(&self, cap: usize) -> usize {
+        cap.saturating_sub(self.weight + self.writing + self.reserved)
+    }
+
+    /// Returns the weight of a message.
+    fn weigh(&self, msg: &T) -> usize {
+        match &self.weigher {
+            None => 1,
+            Some(weigher) => weigher(msg),
+        }
+    }
+
+    /// Pushes a message into a lane.
+    fn push(&mut self, lane: usize, msg: T) {
+        self.weight += self.weigh(&msg);
+        self.lanes[lane].push_back((msg, Stamp::now()));
+        self.tails[lane] = self.tails[lane].wrapping_add(1);
+        self.len += 1;
+    }
+
+    /// Pushes a message that was put back in front of the highest lane.
+    fn push_front(&mut self, msg: T) {
+        let lane = self.lanes.len() - 1;
+        self.weight += self.weigh(&msg);
+        self.lanes[lane].push_front((msg, Stamp::none()));
+        self.heads[lane] = self.heads[lane].wrapping_sub(1);
+        self.len += 1;
+    }
+
+    /// Pops the oldest message from the highest non-empty lane.
+    fn pop(&mut self) -> Option<(T, Stamp)> {
+        match self.lanes.iter().rposition(|queue| !queue.is_empty()) {
+            None => None,
+            Some(lane) => self.remove(lane, 0),
+        }
+    }
+
+    /// Removes the message at `index` in a lane.
+    fn remove(&mut self, lane: usize, index: usize) -> Option<(T, Stamp)> {
+        let msg = self.lanes[lane].remove(index);
+        if let Some((msg, _)) = &msg {
+            self.weight -= self.weigh(msg);
+            if index == 0 {
+                self.heads[lane] = self.heads[lane].wrapping_add(1);
+            } else {
+                self.tails[lane] = self.tails[lane].wrapping_sub(1);
+            }
+            self.len -= 1;
+        }
+        msg
+    }
+
+    /// Returns `true` if all messages preceding the sequence numbers in `targets` have left their
+    /// lanes.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn has_passed(&self, targets: &[usize]) -> bool {
+        self.heads
+            .iter()
+            .zip(self.tails.iter())
+            .zip(targets)
+            .all(|((&head, &tail), &target)| {
+                // Messages might have been removed from behind the front since.
+                let target = if (tail.wrapping_sub(target) as isize) < 0 {
+                    tail
+                } else {
+                    target
+                };
+                head.wrapping_sub(target) as isize >= 0
+            })
+    }
+}
+
+/// Bounded channel with several priority lanes.
+pub struct Channel<T> {
+    /// Inner representation of the channel.
+    inner: Mutex<Inner<T>>,
+
+    /// The channel capacity, shared by all lanes.
+    cap: usize,
+
+    /// Incremented every time a message is pushed into or popped from the channel.
+    stamp: AtomicUsize,
+
+    /// Equals `true` when the channel is disconnected.
+    ///
+    /// This flag is only set while holding the lock, so send operations checking it under the lock
+    /// either get their messages in before the channel is disconnected or fail.
+    is_disconnected: AtomicBool,
+
+    /// Senders waiting while the channel is full.
+    senders: SyncWaker,
+
+    /// Receivers waiting while the channel is empty and not disconnected.
+    receivers: SyncWaker,
+
+    /// How long received messages have waited inside the channel.
+    latency: Histogram,
+}
+
+impl<T> Channel<T> {
+    /// Creates a channel of capacity `cap` with `lanes` priority lanes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity or the number of lanes is zero.
+    pub fn new(cap: usize, lanes: usize) -> Self {
+        Channel::with_weigher(cap, lanes, None)
+    }
+
+    /// Creates a channel with `lanes` priority lanes that holds messages of total weight `cap`,
+    /// weighing them with `weigher`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity or the number of lanes is zero.
+    pub fn with_weigher(cap: usize, lanes: usize, weigher: Option<Weigher<T>>) -> Self {
+        assert!(cap > 0, "capacity must be positive");
+        assert!(lanes > 0, "number of lanes must be positive");
+
+        Channel {
+            inner: Mutex::new(Inner {
+                lanes: (0..lanes).map(|_| VecDeque::new()).collect::<Vec<_>>().into_boxed_slice(),
+                len: 0,
+                weight: 0,
+                weigher,
+                heads: vec![0; lanes].into_boxed_slice(),
+                tails: vec![0; lanes].into_boxed_slice(),
+                claimed: 0,
+                writing: 0,
+                reserved: 0,
+            }),
+            cap,
+            stamp: AtomicUsize::new(0),
+            is_disconnected: AtomicBool::new(false),
+            senders: SyncWaker::new(),
+            receivers: SyncWaker::new(),
+            latency: Histogram::new(),
+        }
+    }
+
+    /// Sets which blocked thread an operation wakes up when several are waiting.
+    pub fn with_wake_policy(mut self, policy: WakePolicy) -> Self {
+        self.senders = SyncWaker::with_policy(policy);
+        self.receivers = SyncWaker::with_policy(policy);
+        self
+    }
+
+    /// Returns a receiver handle to the channel.
+    pub fn receiver(&self) -> Receiver<T> {
+        Receiver(self)
+    }
+
+    /// Returns a sender handle to the channel.
+    pub fn sender(&self) -> Sender<T> {
+        Sender(self)
+    }
+
+    /// Returns the number of lanes.
+    pub fn lanes(&self) -> usize {
+        self.inner.lock().lanes.len()
+    }
+
+    /// Attempts to claim a slot for sending a message.
+    fn start_send(&self, token: &mut Token) -> bool {
+        let mut inner = self.inner.lock();
+
+        // If the channel is disconnected, return early.
+        if self.is_disconnected() {
+            token.lanes = false;
+            return true;
+        }
+
+        if inner.free(self.cap) == 0 {
+            return false;
+        }
+        inner.writing += 1;
+        token.lanes = true;
+        true
+    }
+
+    /// Writes a message into the lowest lane.
+    pub unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
+        self.write_lane(token, msg, 0)
+    }
+
+    /// Writes a message into a lane.
+    unsafe fn write_lane(&self, token: &mut Token, msg: T, lane: usize) -> Result<(), T> {
+        // If no slot was claimed, the channel is disconnected.
+        if !token.lanes {
+            return Err(msg);
+        }
+
+        {
+            let mut inner = self.inner.lock();
+            inner.writing -= 1;
+            inner.push(lane, msg);
+            self.stamp.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // Wake a sleeping receiver.
+        self.receivers.wake_one();
+        Ok(())
+    }
+
+    /// Attempts to claim a message for receiving.
+    fn start_recv(&self, token: &mut Token) -> bool {
+        let mut backoff = Backoff::new();
+
+        loop {
+            {
+                let mut inner = self.inner.lock();
+                if inner.available() > 0 {
+                    inner.claimed += 1;
+                    token.lanes = true;
+                    return true;
+                }
+                if !self.is_disconnected() {
+                    return false;
+                }
+
+                // Send operations that claimed a slot before the channel was disconnected are
+                // about to write into it, so their messages have to be waited for.
+                if inner.writing == 0 {
+                    token.lanes = false;
+                    return true;
+                }
+            }
+
+            backoff.snooze();
+        }
+    }
+
+    /// Reads a message from the channel.
+    ///
+    /// The message read is the one with the highest priority at the moment of reading, which is
+    /// not necessarily the one that was queued when the message was claimed.
+    pub unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
+        // If no message was claimed, the channel is disconnected.
+        if !token.lanes {
+            return Err(());
+        }
+
+        let msg = {
+            let mut inner = self.inner.lock();
+            inner.claimed -= 1;
+            let (msg, sent) = inner.pop().unwrap();
+            self.stamp.fetch_add(1, Ordering::SeqCst);
+            self.latency.record(sent);
+            msg
+        };
+
+        // Wake a sleeping sender.
+        self.senders.wake_one();
+        Ok(msg)
+    }
+
+    /// Attempts to send a message into a lane without blocking.
+    pub fn try_send(&self, msg: T, lane: usize) -> Result<(), TrySendError<T>> {
+        let token = &mut Token::default();
+        if self.start_send(token) {
+            unsafe {
+                self.write_lane(token, msg, lane)
+                    .map_err(TrySendError::Disconnected)
+            }
+        } else {
+            Err(TrySendError::Full(msg))
+        }
+    }
+
+    /// Attempts to send messages from the front of `msgs` into a lane at once without blocking.
+    ///
+    /// The messages are pushed while holding the lock only once. Sent messages are removed from
+    /// `msgs`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_send_many(&self, msgs: &mut VecDeque<T>, lane: usize) -> usize {
+        let count = {
+            let mut inner = self.inner.lock();
+            if self.is_disconnected() {
+                return 0;
+            }
+
+            // Every message fits in as long as there's a free slot before it is pushed.
+            let free = inner.free(self.cap);
+            let mut weight = 0;
+            let count = msgs
+                .iter()
+                .take_while(|msg| {
+                    let fits = weight < free;
+                    weight += inner.weigh(msg);
+                    fits
+                }).count();
+
+            for msg in msgs.drain(..count) {
+                inner.push(lane, msg);
+            }
+            if count > 0 {
+                self.stamp.fetch_add(1, Ordering::SeqCst);
+            }
+            count
+        };
+
+        // Wake a sleeping receiver for each message.
+        for _ in 0..count {
+            self.receivers.wake_one();
+        }
+        count
+    }
+
+    /// Sends a message into a lane.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(
+        &self,
+        msg: T,
+        lane: usize,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<T>> {
+        let token = &mut Token::default();
+        loop {
+            // Try sending a message several times.
+            let mut backoff = Backoff::new();
+            loop {
+                if self.start_send(token) {
+                    let res = unsafe { self.write_lane(token, msg, lane) };
+                    return res.map_err(SendTimeoutError::Disconnected);
+                }
+                if !spin::should_spin() || !backoff.snooze() {
+                    break;
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.senders.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if !self.is_full() || self.is_disconnected() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.senders.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(SendTimeoutError::Timeout(msg));
+                }
+            }
+        }
+    }
+
+    /// Sends a message into a lane, displacing a queued message if the channel is full.
+    ///
+    /// Slots reserved by permits are considered available. The displaced message is taken from
+    /// the lowest non-empty lane. On success, the displaced message is returned, if there is one.
+    pub fn send_lossy(&self, msg: T, lane: usize, displace: Displace) -> Result<Option<T>, T> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            {
+                let mut inner = self.inner.lock();
+
+                if self.is_disconnected() {
+                    return Err(msg);
+                }
+
+                if inner.weight + inner.writing < self.cap {
+                    inner.push(lane, msg);
+                    self.stamp.fetch_add(1, Ordering::SeqCst);
+                    drop(inner);
+                    self.receivers.wake_one();
+                    return Ok(None);
+                }
+
+                // The channel is full, so try replacing a message in it. Claimed messages don't
+                // need special care because the number of queued messages stays the same.
+                let old = match inner.lanes.iter().position(|q| !q.is_empty()) {
+                    None => None,
+                    Some(i) => {
+                        let index = match displace {
+                            Displace::Newest => inner.lanes[i].len() - 1,
+                            Displace::Oldest => 0,
+                        };
+                        inner.remove(i, index).map(|(old, _)| old)
+                    }
+                };
+                if let Some(old) = old {
+                    inner.push(lane, msg);
+                    self.stamp.fetch_add(1, Ordering::SeqCst);
+                    drop(inner);
+
+                    // Wake up senders waiting for the channel to be flushed.
+                    self.senders.notify();
+                    return Ok(Some(old));
+                }
+            }
+
+            // Every slot is claimed by a send operation that hasn't written into it yet.
+            backoff.spin();
+        }
+    }
+
+    /// Attempts to reserve a slot for a future message without blocking.
+    pub fn try_reserve(&self) -> Result<(), TrySendError<()>> {
+        if self.is_disconnected() {
+            return Err(TrySendError::Disconnected(()));
+        }
+
+        let mut inner = self.inner.lock();
+        if inner.free(self.cap) == 0 {
+            return Err(TrySendError::Full(()));
+        }
+        inner.reserved += 1;
+        Ok(())
+    }
+
+    /// Reserves a slot for a future message.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn reserve(&self, deadline: Option<Instant>) -> Result<(), SendTimeoutError<()>> {
+        let token = &mut Token::default();
+        loop {
+            // Try reserving a slot several times.
+            let mut backoff = Backoff::new();
+            loop {
+                match self.try_reserve() {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Disconnected(())) => {
+                        return Err(SendTimeoutError::Disconnected(()))
+                    }
+                    Err(TrySendError::Full(())) => {}
+                }
+                if !spin::should_spin() || !backoff.snooze() {
+                    break;
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.senders.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if !self.is_full() || self.is_disconnected() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.senders.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(SendTimeoutError::Timeout(()));
+                }
+            }
+        }
+    }
+
+    /// Sends a message into a previously reserved slot in the lowest lane.
+    pub fn send_reserved(&self, msg: T) -> Result<(), T> {
+        {
+            let mut inner = self.inner.lock();
+            inner.reserved -= 1;
+
+            if self.is_disconnected() {
+                drop(inner);
+                self.senders.wake_one();
+                return Err(msg);
+            }
+
+            inner.push(0, msg);
+            self.stamp.fetch_add(1, Ordering::SeqCst);
+        }
+
+        self.receivers.wake_one();
+        Ok(())
+    }
+
+    /// Releases a reserved slot.
+    pub fn release(&self) {
+        self.inner.lock().reserved -= 1;
+
+        // Wake a sleeping sender because a slot has just become available.
+        self.senders.wake_one();
+    }
+
+    /// Attempts to receive a message without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let token = &mut Token::default();
+
+        if self.start_recv(token) {
+            unsafe { self.read(token).map_err(|_| TryRecvError::Disconnected) }
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Attempts to receive up to `limit` messages at once without blocking.
+    ///
+    /// Messages are taken in priority order. Received messages are pushed into `buf`, and their
+    /// number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_recv_many(&self, buf: &mut Vec<T>, limit: usize) -> usize {
+        let count = {
+            let mut inner = self.inner.lock();
+            let count = limit.min(inner.available());
+
+            buf.reserve(count);
+            for _ in 0..count {
+                let (msg, sent) = inner.pop().unwrap();
+                self.latency.record(sent);
+                buf.push(msg);
+            }
+            if count > 0 {
+                self.stamp.fetch_add(1, Ordering::SeqCst);
+            }
+            count
+        };
+
+        // Wake a sleeping sender for each slot that has become available.
+        for _ in 0..count {
+            self.senders.wake_one();
+        }
+        count
+    }
+
+    /// Receives a message from the channel.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let token = &mut Token::default();
+        loop {
+            // Try receiving a message several times.
+            let mut backoff = Backoff::new();
+            loop {
+                if self.start_recv(token) {
+                    let res = unsafe { self.read(token) };
+                    return res.map_err(|_| RecvTimeoutError::Disconnected);
+                }
+                if !spin::should_spin() || !backoff.snooze() {
+                    break;
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender wakes us up.
+                let oper = Operation::hook(token);
+                self.receivers.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if !self.is_empty() || self.is_disconnected() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.receivers.unregister(oper).unwrap();
+                        // If the channel was disconnected, we still have to check for remaining
+                        // messages.
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+        }
+    }
+
+    /// Receives the first message satisfying `pred` in priority order, leaving messages that
+    /// don't in their lanes.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_where<F>(
+        &self,
+        pred: &mut F,
+        deadline: Option<Instant>,
+    ) -> Result<T, RecvTimeoutError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let token = &mut Token::default();
+        let mut last_stamp = None;
+
+        loop {
+            let stamp = {
+                let mut inner = self.inner.lock();
+                let stamp = self.stamp.load(Ordering::SeqCst);
+
+                // Messages claimed by other receive operations must stay in the channel.
+                if inner.available() > 0 {
+                    let mut found = None;
+                    for (lane, queue) in inner.lanes.iter().enumerate().rev() {
+                        if let Some(index) = queue.iter().position(|(msg, _)| pred(msg)) {
+                            found = Some((lane, index));
+                            break;
+                        }
+                    }
+
+                    if let Some((lane, index)) = found {
+                        let (msg, sent) = inner.remove(lane, index).unwrap();
+                        self.stamp.fetch_add(1, Ordering::SeqCst);
+                        self.latency.record(sent);
+                        drop(inner);
+
+                        self.senders.wake_one();
+                        return Ok(msg);
+                    }
+                }
+
+                if self.is_disconnected() {
+                    return Err(RecvTimeoutError::Disconnected);
+                }
+                stamp
+            };
+
+            // A sender might have woken this operation up instead of another receive operation
+            // waiting for the message that just arrived, so let the others have a look too.
+            if last_stamp.is_some() && last_stamp != Some(stamp) {
+                self.receivers.notify_all();
+            }
+            last_stamp = Some(stamp);
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender wakes us up.
+                let oper = Operation::hook(token);
+                self.receivers.register(oper, cx);
+
+                // Has a message arrived just now?
+                if self.stamp.load(Ordering::SeqCst) != stamp || self.is_disconnected() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.receivers.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+        }
+    }
+
+    /// Puts a received message back in front of the highest lane so that it is the next one to be
+    /// received, and wakes up blocked receivers.
+    ///
+    /// The message is put back even if the channel is full.
+    pub fn put_back(&self, msg: T) {
+        {
+            let mut inner = self.inner.lock();
+            inner.push_front(msg);
+            self.stamp.fetch_add(1, Ordering::SeqCst);
+        }
+        self.receivers.notify_all();
+    }
+
+    /// Returns clones of all queued messages not yet claimed by receive operations, in the order
+    /// they are going to be received.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let inner = self.inner.lock();
+        inner
+            .lanes
+            .iter()
+            .rev()
+            .flat_map(|queue| queue.iter())
+            .skip(inner.claimed)
+            .map(|(msg, _)| msg.clone())
+            .collect()
+    }
+
+    /// Puts messages in front of the highest lane, in order, and wakes up blocked receivers.
+    ///
+    /// The messages are put back even if the channel is full.
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, msgs: Vec<T>) {
+        {
+            let mut inner = self.inner.lock();
+            for msg in msgs.into_iter().rev() {
+                inner.push_front(msg);
+                self.stamp.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        self.receivers.notify_all();
+    }
+
+    /// Returns the current number of messages inside the channel.
+    pub fn len(&self) -> usize {
+        self.inner.lock().available()
+    }
+
+    /// Returns the capacity of the channel.
+    pub fn capacity(&self) -> Option<usize> {
+        Some(self.cap)
+    }
+
+    /// Returns the number of bytes the channel has allocated on the heap.
+    pub fn memory_usage(&self) -> usize {
+        let inner = self.inner.lock();
+        let queues: usize = inner
+            .lanes
+            .iter()
+            .map(|queue| queue.capacity() * mem::size_of::<(T, Stamp)>())
+            .sum();
+        let per_lane = mem::size_of::<VecDeque<(T, Stamp)>>() + 2 * mem::size_of::<usize>();
+        queues + inner.lanes.len() * per_lane + self.latency.memory_usage()
+    }
+
+    /// Returns statistics about how long received messages have waited inside the channel.
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+
+    /// Disconnects the channel and wakes up all blocked operations.
+    pub fn disconnect(&self) {
+        let was_disconnected = {
+            let _inner = self.inner.lock();
+            self.is_disconnected.swap(true, Ordering::SeqCst)
+        };
+
+        if !was_disconnected {
+            self.senders.disconnect();
+            self.receivers.disconnect();
+        }
+    }
+
+    /// Blocks until all messages sent so far have left their lanes.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn flush(&self, park: bool) {
+        let targets = self.inner.lock().tails.clone();
+        self.senders.wait_until(
+            || self.inner.lock().has_passed(&targets) || self.is_disconnected(),
+            park,
+        );
+    }
+
+    /// Returns `true` if the channel is disconnected.
+    pub fn is_disconnected(&self) -> bool {
+        self.is_disconnected.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of blocked send and receive operations.
+    pub fn waiting(&self) -> (usize, usize) {
+        (self.senders.len(), self.receivers.len())
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().available() == 0
+    }
+
+    /// Returns `true` if the channel is full.
+    pub fn is_full(&self) -> bool {
+        self.inner.lock().free(self.cap) == 0
+    }
+}
+
+/// Receiver handle to a channel.
+pub struct Receiver<'a, T: 'a>(&'a Channel<T>);
+
+/// Sender handle to a channel.
+pub struct Sender<'a, T: 'a>(&'a Channel<T>);
+
+impl<'a, T> SelectHandle for Receiver<'a, T> {
+    fn try(&self, token: &mut Token) -> bool {
+        self.0.start_recv(token)
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        self.0.start_recv(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.0.receivers.register(oper, cx);
+        self.0.is_empty() && !self.0.is_disconnected()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.receivers.unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.0.start_recv(token)
+    }
+
+    fn state(&self) -> usize {
+        self.0.stamp.load(Ordering::SeqCst)
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.0.is_empty() || self.0.is_disconnected()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.receivers.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.receivers.unwatch(oper);
+    }
+}
+
+impl<'a, T> SelectHandle for Sender<'a, T> {
+    fn try(&self, token: &mut Token) -> bool {
+        self.0.start_send(token)
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        self.0.start_send(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.0.senders.register(oper, cx);
+        self.0.is_full() && !self.0.is_disconnected()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.senders.unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.0.start_send(token)
+    }
+
+    fn state(&self) -> usize {
+        self.0.stamp.load(Ordering::SeqCst)
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.0.is_full() || self.0.is_disconnected()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.senders.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.senders.unwatch(oper);
+    }
+}
@@ -1,18 +1,36 @@
 //! Unbounded channel implemented as a linked list.
 
 use std::cell::UnsafeCell;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::mem::{self, ManuallyDrop};
 use std::ptr;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Instant;
 
+#[cfg(not(any(miri, feature = "miri")))]
 use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+#[cfg(any(miri, feature = "miri"))]
+use reclaim::{self as epoch, Atomic, Guard, Owned, Shared};
 use crossbeam_utils::CachePadded;
+use parking_lot::Mutex;
 
+use builder::WakePolicy;
 use context::Context;
-use err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
-use select::{Operation, SelectHandle, Selected, Token};
+use err::{SendTimeoutError, TryRecvError, TrySendError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::RecvTimeoutError;
+use latency::Histogram;
+#[cfg(feature = "latency")]
+use latency::{LatencyStats, Stamp};
+use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select::Selected;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use spin;
+use stash::Stash;
 use utils::Backoff;
 use waker::SyncWaker;
 
@@ -25,16 +43,27 @@ use waker::SyncWaker;
 /// The maximum number of messages a block can hold.
 const BLOCK_CAP: usize = 32;
 
+/// Indices are shifted left by this many bits to make room for the mark bit.
+const SHIFT: usize = 1;
+
+/// If this bit is set in the tail index, that means the channel is disconnected.
+const MARK_BIT: usize = 1;
+
 /// A slot in a block.
 struct Slot<T> {
     /// The message.
     msg: UnsafeCell<ManuallyDrop<T>>,
 
+    /// The time the message was sent.
+    #[cfg(feature = "latency")]
+    sent: UnsafeCell<Stamp>,
+
     /// Equals `true` if the message is ready for reading.
     ready: AtomicBool,
 }
 
 /// The token type for the list flavor.
+#[derive(Debug)]
 pub struct ListToken {
     /// Slot to read from or write to.
     slot: *const u8,
@@ -53,13 +82,21 @@ impl Default for ListToken {
     }
 }
 
+impl ListToken {
+    /// Returns `true` if the token holds a slot, i.e. the channel was not disconnected.
+    #[inline]
+    pub fn has_slot(&self) -> bool {
+        !self.slot.is_null()
+    }
+}
+
 /// A block in a linked list.
 ///
 /// Each block in the list can hold up to `BLOCK_CAP` messages.
 struct Block<T> {
     /// The start index of this block.
     ///
-    /// Slots in this block have indices in `start_index .. start_index + BLOCK_CAP`.
+    /// Slots in this block have indices in `start_index .. start_index + (BLOCK_CAP << SHIFT)`.
     start_index: usize,
 
     /// The next block in the linked list.
@@ -78,13 +115,55 @@ impl<T> Block<T> {
             next: Atomic::null(),
         }
     }
+
+    /// Resets a block whose messages have all been read so that it starts at `start_index`.
+    fn reset(&mut self, start_index: usize) {
+        self.start_index = start_index;
+        self.next = Atomic::null();
+        for slot in self.slots.iter() {
+            unsafe {
+                (*slot.get()).ready.store(false, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Spare blocks kept for reuse.
+///
+/// Blocks whose messages have all been read are put here instead of being deallocated, and new
+/// blocks are taken from here before allocating. The pool is shared with the garbage collector,
+/// which hands back retired blocks once no thread is accessing them anymore.
+struct Pool<T> {
+    /// The spare blocks.
+    blocks: Mutex<Vec<Owned<Block<T>>>>,
+
+    /// The maximum number of spare blocks.
+    cap: usize,
+}
+
+impl<T> Pool<T> {
+    /// Takes a spare block out of the pool, if there is one.
+    fn take(&self) -> Option<Owned<Block<T>>> {
+        self.blocks.lock().pop()
+    }
+
+    /// Puts a block whose messages have all been read into the pool, or destroys it if the pool
+    /// is full.
+    fn put(&self, block: Owned<Block<T>>) {
+        let mut blocks = self.blocks.lock();
+        if blocks.len() < self.cap {
+            blocks.push(block);
+        }
+    }
 }
 
 /// Position in the channel (index and block).
 ///
 /// This struct describes the current position of the head or the tail in a linked list.
 struct Position<T> {
-    /// The index in the channel.
+    /// The index in the channel, shifted left by `SHIFT` bits.
+    ///
+    /// In the tail, the lowest bit is the mark bit, which is set once the channel is disconnected.
     index: AtomicUsize,
 
     /// The block in the linked list.
@@ -105,19 +184,30 @@ pub struct Channel<T> {
     /// The tail of the channel.
     tail: CachePadded<Position<T>>,
 
-    /// Equals `true` when the channel is disconnected.
-    is_disconnected: AtomicBool,
-
     /// Receivers waiting while the channel is empty and not disconnected.
     receivers: SyncWaker,
 
+    /// Senders waiting for messages to be received.
+    ///
+    /// Send operations never block, so only senders flushing the channel wait here.
+    senders: SyncWaker,
+
+    /// Messages set aside by selective receive operations.
+    stash: Stash<T>,
+
+    /// Spare blocks kept for reuse, if pooling is enabled.
+    pool: Option<Arc<Pool<T>>>,
+
+    /// How long received messages have waited inside the channel.
+    latency: Histogram,
+
     /// Indicates that dropping a `Channel<T>` may drop values of type `T`.
     _marker: PhantomData<T>,
 }
 
 impl<T> Channel<T> {
-    /// Creates a new unbounded channel.
-    pub fn new() -> Self {
+    /// Creates a new unbounded channel that keeps up to `pool_cap` spare blocks for reuse.
+    pub fn new(pool_cap: usize) -> Self {
         let channel = Channel {
             head: CachePadded::new(Position {
                 index: AtomicUsize::new(0),
@@ -127,8 +217,18 @@ impl<T> Channel<T> {
                 index: AtomicUsize::new(0),
                 block: Atomic::null(),
             }),
-            is_disconnected: AtomicBool::new(false),
             receivers: SyncWaker::new(),
+            senders: SyncWaker::new(),
+            stash: Stash::new(),
+            pool: if pool_cap == 0 {
+                None
+            } else {
+                Some(Arc::new(Pool {
+                    blocks: Mutex::new(Vec::new()),
+                    cap: pool_cap,
+                }))
+            },
+            latency: Histogram::new(),
             _marker: PhantomData,
         };
 
@@ -140,6 +240,45 @@ impl<T> Channel<T> {
         channel
     }
 
+    /// Returns an empty block that starts at `start_index`, reusing a spare block if possible.
+    fn new_block(&self, start_index: usize) -> Owned<Block<T>> {
+        if let Some(pool) = &self.pool {
+            if let Some(mut block) = pool.take() {
+                block.reset(start_index);
+                return block;
+            }
+        }
+        Owned::new(Block::new(start_index))
+    }
+
+    /// Hands a block that failed to get installed back to the pool.
+    fn discard_block(&self, block: Owned<Block<T>>) {
+        if let Some(pool) = &self.pool {
+            pool.put(block);
+        }
+    }
+
+    /// Retires a block whose messages have all been claimed.
+    ///
+    /// The block is destroyed or put into the pool once no thread is accessing it anymore.
+    unsafe fn retire_block(&self, block: Shared<Block<T>>, guard: &Guard) {
+        match &self.pool {
+            None => guard.defer_destroy(block),
+            Some(pool) => {
+                let pool = pool.clone();
+                let block = block.as_raw() as *mut Block<T>;
+                guard.defer_unchecked(move || pool.put(Owned::from_raw(block)));
+            }
+        }
+    }
+
+    /// Sets which blocked thread an operation wakes up when several are waiting.
+    pub fn with_wake_policy(mut self, policy: WakePolicy) -> Self {
+        self.senders = SyncWaker::with_policy(policy);
+        self.receivers = SyncWaker::with_policy(policy);
+        self
+    }
+
     /// Returns a receiver handle to the channel.
     pub fn receiver(&self) -> Receiver<T> {
         Receiver(self)
@@ -152,12 +291,6 @@ impl<T> Channel<T> {
 
     /// Attempts to reserve a slot for sending a message.
     fn start_send(&self, token: &mut Token) -> bool {
-        // If the channel is disconnected, return early.
-        if self.is_disconnected() {
-            token.list.slot = ptr::null();
-            return true;
-        }
-
         let guard = epoch::pin();
         let mut backoff = Backoff::new();
 
@@ -168,27 +301,41 @@ impl<T> Channel<T> {
             let tail = unsafe { tail_ptr.deref() };
             let tail_index = self.tail.index.load(Ordering::Relaxed);
 
+            // If the channel is disconnected, return early. The mark bit is part of the tail
+            // index, so no slot can be claimed once it is set.
+            if tail_index & MARK_BIT != 0 {
+                token.list.slot = ptr::null();
+                return true;
+            }
+
             // Calculate the index of the corresponding slot in the block.
-            let offset = tail_index.wrapping_sub(tail.start_index);
+            let offset = tail_index.wrapping_sub(tail.start_index) >> SHIFT;
 
             // Advance the current index one slot forward.
-            let new_index = tail_index.wrapping_add(1);
+            let new_index = tail_index.wrapping_add(1 << SHIFT);
 
             // A closure that installs a block following `tail` in case it hasn't been yet.
             let install_next_block = || {
-                let current = tail
-                    .next
-                    .compare_and_set(
-                        Shared::null(),
-                        Owned::new(Block::new(tail.start_index.wrapping_add(BLOCK_CAP))),
-                        Ordering::AcqRel,
-                        &guard,
-                    ).unwrap_or_else(|err| err.current);
+                // Allocate a new block only if the next one is missing.
+                let mut next = tail.next.load(Ordering::Acquire, &guard);
+                if next.is_null() {
+                    next = tail
+                        .next
+                        .compare_and_set(
+                            Shared::null(),
+                            self.new_block(tail.start_index.wrapping_add(BLOCK_CAP << SHIFT)),
+                            Ordering::AcqRel,
+                            &guard,
+                        ).unwrap_or_else(|err| {
+                            self.discard_block(err.new);
+                            err.current
+                        });
+                }
 
                 let _ =
                     self.tail
                         .block
-                        .compare_and_set(tail_ptr, current, Ordering::Release, &guard);
+                        .compare_and_set(tail_ptr, next, Ordering::Release, &guard);
             };
 
             // If `tail_index` is pointing into `tail`...
@@ -239,6 +386,8 @@ impl<T> Channel<T> {
 
         // Write the message into the slot.
         (*slot).msg.get().write(ManuallyDrop::new(msg));
+        #[cfg(feature = "latency")]
+        (*slot).sent.get().write(Stamp::now());
         (*slot).ready.store(true, Ordering::Release);
 
         // Wake a sleeping receiver.
@@ -246,8 +395,28 @@ impl<T> Channel<T> {
         Ok(())
     }
 
-    /// Attempts to reserve a slot for receiving a message.
+    /// Attempts to reserve a message for receiving, looking into the stash first.
     fn start_recv(&self, token: &mut Token) -> bool {
+        if self.stash.start_recv(token) {
+            return true;
+        }
+        if !self.start_recv_queue(token) {
+            return false;
+        }
+
+        // If the channel is disconnected, a selective receive operation might be about to set a
+        // message aside, so the operation is not ready until it's done.
+        if token.list.slot.is_null() {
+            if self.stash.is_held() {
+                return false;
+            }
+            self.stash.start_recv(token);
+        }
+        true
+    }
+
+    /// Attempts to reserve a slot in the linked list for receiving a message.
+    fn start_recv_queue(&self, token: &mut Token) -> bool {
         let guard = epoch::pin();
         let mut backoff = Backoff::new();
 
@@ -261,26 +430,33 @@ impl<T> Channel<T> {
             let head_index = self.head.index.load(Ordering::SeqCst);
 
             // Calculate the index of the corresponding slot in the block.
-            let offset = head_index.wrapping_sub(head.start_index);
+            let offset = head_index.wrapping_sub(head.start_index) >> SHIFT;
 
             // Advance the current index one slot forward.
-            let new_index = head_index.wrapping_add(1);
+            let new_index = head_index.wrapping_add(1 << SHIFT);
 
             // A closure that installs a block following `head` in case it hasn't been yet.
             let install_next_block = || {
-                let current = head
-                    .next
-                    .compare_and_set(
-                        Shared::null(),
-                        Owned::new(Block::new(head.start_index.wrapping_add(BLOCK_CAP))),
-                        Ordering::AcqRel,
-                        &guard,
-                    ).unwrap_or_else(|err| err.current);
+                // Allocate a new block only if the next one is missing.
+                let mut next = head.next.load(Ordering::Acquire, &guard);
+                if next.is_null() {
+                    next = head
+                        .next
+                        .compare_and_set(
+                            Shared::null(),
+                            self.new_block(head.start_index.wrapping_add(BLOCK_CAP << SHIFT)),
+                            Ordering::AcqRel,
+                            &guard,
+                        ).unwrap_or_else(|err| {
+                            self.discard_block(err.new);
+                            err.current
+                        });
+                }
 
                 let _ =
                     self.head
                         .block
-                        .compare_and_set(head_ptr, current, Ordering::Release, &guard);
+                        .compare_and_set(head_ptr, next, Ordering::Release, &guard);
             };
 
             // If `head_index` is pointing into `head`...
@@ -292,15 +468,12 @@ impl<T> Channel<T> {
                     let tail_index = self.tail.index.load(Ordering::SeqCst);
 
                     // If the tail equals the head, that means the channel is empty.
-                    if tail_index == head_index {
+                    if tail_index & !MARK_BIT == head_index {
                         // If the channel is disconnected...
-                        if self.is_disconnected() {
-                            // ...and still empty...
-                            if self.tail.index.load(Ordering::SeqCst) == tail_index {
-                                // ...then receive an error.
-                                token.list.slot = ptr::null();
-                                return true;
-                            }
+                        if tail_index & MARK_BIT != 0 {
+                            // ...then receive an error.
+                            token.list.slot = ptr::null();
+                            return true;
                         } else {
                             // Otherwise, the receive operation is not ready.
                             return false;
@@ -324,7 +497,7 @@ impl<T> Channel<T> {
                     if offset + 1 == BLOCK_CAP {
                         install_next_block();
                         unsafe {
-                            guard.defer_destroy(head_ptr);
+                            self.retire_block(head_ptr, &guard);
                         }
                     }
 
@@ -345,6 +518,10 @@ impl<T> Channel<T> {
 
     /// Reads a message from the channel.
     pub unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
+        if let Some(msg) = self.stash.read(token) {
+            return Ok(msg);
+        }
+
         if token.list.slot.is_null() {
             // The channel is disconnected.
             return Err(());
@@ -362,6 +539,11 @@ impl<T> Channel<T> {
         // Read the message.
         let m = slot.msg.get().read();
         let msg = ManuallyDrop::into_inner(m);
+        #[cfg(feature = "latency")]
+        self.latency.record(slot.sent.get().read());
+
+        // Wake up senders waiting for the channel to be flushed.
+        self.senders.notify();
         Ok(msg)
     }
 
@@ -384,6 +566,102 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Attempts to send messages from the front of `msgs` at once without blocking.
+    ///
+    /// Consecutive slots in the tail block are claimed by moving the tail index only once. Sent
+    /// messages are removed from `msgs`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_send_many(&self, msgs: &mut VecDeque<T>) -> usize {
+        if msgs.is_empty() {
+            return 0;
+        }
+
+        let guard = epoch::pin();
+        let mut backoff = Backoff::new();
+
+        loop {
+            // These two load operations don't have to be `SeqCst`. If they happen to retrieve
+            // stale values, the following CAS will fail or won't even be attempted.
+            let tail_ptr = self.tail.block.load(Ordering::Acquire, &guard);
+            let tail = unsafe { tail_ptr.deref() };
+            let tail_index = self.tail.index.load(Ordering::Relaxed);
+
+            // If the channel is disconnected, nothing can be sent.
+            if tail_index & MARK_BIT != 0 {
+                return 0;
+            }
+
+            // Calculate the index of the corresponding slot in the block.
+            let offset = tail_index.wrapping_sub(tail.start_index) >> SHIFT;
+
+            // A closure that installs a block following `tail` in case it hasn't been yet.
+            let install_next_block = || {
+                // Allocate a new block only if the next one is missing.
+                let mut next = tail.next.load(Ordering::Acquire, &guard);
+                if next.is_null() {
+                    next = tail
+                        .next
+                        .compare_and_set(
+                            Shared::null(),
+                            self.new_block(tail.start_index.wrapping_add(BLOCK_CAP << SHIFT)),
+                            Ordering::AcqRel,
+                            &guard,
+                        ).unwrap_or_else(|err| {
+                            self.discard_block(err.new);
+                            err.current
+                        });
+                }
+
+                let _ =
+                    self.tail
+                        .block
+                        .compare_and_set(tail_ptr, next, Ordering::Release, &guard);
+            };
+
+            if offset < BLOCK_CAP {
+                // Claim as many slots as there are messages, but stay inside the block.
+                let count = msgs.len().min(BLOCK_CAP - offset);
+
+                // Try moving the tail index forward.
+                if self
+                    .tail
+                    .index
+                    .compare_exchange_weak(
+                        tail_index,
+                        tail_index.wrapping_add(count << SHIFT),
+                        Ordering::SeqCst,
+                        Ordering::Relaxed,
+                    ).is_ok()
+                {
+                    // If the last slot in the block was claimed, install a new block.
+                    if offset + count == BLOCK_CAP {
+                        install_next_block();
+                    }
+
+                    for (i, msg) in msgs.drain(..count).enumerate() {
+                        let slot = unsafe { &*tail.slots.get_unchecked(offset + i).get() };
+
+                        // Write the message into the slot.
+                        unsafe { slot.msg.get().write(ManuallyDrop::new(msg)) };
+                        #[cfg(feature = "latency")]
+                        unsafe { slot.sent.get().write(Stamp::now()) };
+                        slot.ready.store(true, Ordering::Release);
+
+                        // Wake a sleeping receiver.
+                        self.receivers.wake_one();
+                    }
+
+                    return count;
+                }
+
+                backoff.spin();
+            } else if offset == BLOCK_CAP {
+                // Help install the next block.
+                install_next_block();
+            }
+        }
+    }
+
     /// Attempts to receive a message without blocking.
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         let token = &mut Token::default();
@@ -397,7 +675,114 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Attempts to receive up to `limit` messages at once without blocking.
+    ///
+    /// Consecutive messages in the head block are claimed by moving the head index only once.
+    /// Received messages are pushed into `buf`, and their number is returned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn try_recv_many(&self, buf: &mut Vec<T>, limit: usize) -> usize {
+        // Messages set aside by selective receive operations come first.
+        let stashed = self.stash.pop_many(buf, limit);
+        let limit = limit - stashed;
+        let guard = epoch::pin();
+        let mut backoff = Backoff::new();
+
+        loop {
+            let head_ptr = self.head.block.load(Ordering::Acquire, &guard);
+            let head = unsafe { head_ptr.deref() };
+            let head_index = self.head.index.load(Ordering::SeqCst);
+            let tail_index = self.tail.index.load(Ordering::SeqCst) & !MARK_BIT;
+
+            // Calculate the index of the corresponding slot in the block.
+            let offset = head_index.wrapping_sub(head.start_index) >> SHIFT;
+
+            // A closure that installs a block following `head` in case it hasn't been yet.
+            let install_next_block = || {
+                // Allocate a new block only if the next one is missing.
+                let mut next = head.next.load(Ordering::Acquire, &guard);
+                if next.is_null() {
+                    next = head
+                        .next
+                        .compare_and_set(
+                            Shared::null(),
+                            self.new_block(head.start_index.wrapping_add(BLOCK_CAP << SHIFT)),
+                            Ordering::AcqRel,
+                            &guard,
+                        ).unwrap_or_else(|err| {
+                            self.discard_block(err.new);
+                            err.current
+                        });
+                }
+
+                let _ =
+                    self.head
+                        .block
+                        .compare_and_set(head_ptr, next, Ordering::Release, &guard);
+            };
+
+            if offset < BLOCK_CAP {
+                // Claim the messages between the head and the tail, but stay inside the block.
+                let count = (tail_index.wrapping_sub(head_index) >> SHIFT)
+                    .min(BLOCK_CAP - offset)
+                    .min(limit);
+
+                if count == 0 {
+                    return stashed;
+                }
+
+                // Try moving the head index forward.
+                if self
+                    .head
+                    .index
+                    .compare_exchange_weak(
+                        head_index,
+                        head_index.wrapping_add(count << SHIFT),
+                        Ordering::SeqCst,
+                        Ordering::Relaxed,
+                    ).is_ok()
+                {
+                    // If the last slot in the block was claimed, install a new block and destroy
+                    // the old one.
+                    if offset + count == BLOCK_CAP {
+                        install_next_block();
+                        unsafe {
+                            self.retire_block(head_ptr, &guard);
+                        }
+                    }
+
+                    buf.reserve(count);
+
+                    for i in offset..offset + count {
+                        let slot = unsafe { &*head.slots.get_unchecked(i).get() };
+
+                        // Wait until the message becomes ready.
+                        let mut backoff = Backoff::new();
+                        while !slot.ready.load(Ordering::Acquire) {
+                            backoff.snooze();
+                        }
+
+                        // Read the message.
+                        let m = unsafe { slot.msg.get().read() };
+                        buf.push(ManuallyDrop::into_inner(m));
+                        #[cfg(feature = "latency")]
+                        self.latency.record(unsafe { slot.sent.get().read() });
+                    }
+
+                    // Wake up senders waiting for the channel to be flushed.
+                    self.senders.notify();
+                    return stashed + count;
+                }
+
+                backoff.spin();
+            } else if offset == BLOCK_CAP {
+                // Help install the next block.
+                install_next_block();
+            }
+        }
+    }
+
     /// Receives a message from the channel.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
         let token = &mut Token::default();
         loop {
@@ -409,7 +794,7 @@ impl<T> Channel<T> {
                         return self.read(token).map_err(|_| RecvTimeoutError::Disconnected);
                     }
                 }
-                if !backoff.snooze() {
+                if !spin::should_spin() || !backoff.snooze() {
                     break;
                 }
             }
@@ -420,7 +805,7 @@ impl<T> Channel<T> {
                 self.receivers.register(oper, cx);
 
                 // Has the channel become ready just now?
-                if !self.is_empty() || self.is_disconnected() {
+                if !self.is_empty() || self.is_drained() {
                     let _ = cx.try_select(Selected::Aborted);
                 }
 
@@ -446,8 +831,149 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Receives the oldest message satisfying `pred`, setting aside messages that don't.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_where<F>(
+        &self,
+        pred: &mut F,
+        deadline: Option<Instant>,
+    ) -> Result<T, RecvTimeoutError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let token = &mut Token::default();
+        let wake = || self.receivers.notify_all();
+        loop {
+            // Look for the message among the ones that were set aside earlier.
+            let mut stamp = self.stash.stamp();
+            if let Some(msg) = self.stash.take_where(pred, &mut stamp, wake) {
+                return Ok(msg);
+            }
+
+            // Take messages out of the linked list until one satisfies the predicate.
+            let mut backoff = Backoff::new();
+            loop {
+                let hold = self.stash.hold(&self.receivers);
+                if self.start_recv_queue(token) {
+                    match unsafe { self.read(token) } {
+                        Ok(msg) => {
+                            if pred(&msg) {
+                                return Ok(msg);
+                            }
+                            self.set_aside(msg);
+                            continue;
+                        }
+                        Err(()) => {
+                            // The channel is disconnected, but another thread might be setting
+                            // aside a message just now. If so, wait until it is done.
+                            drop(hold);
+                            let held = self.stash.is_held();
+                            if let Some(msg) = self.stash.take_where(pred, &mut stamp, wake) {
+                                return Ok(msg);
+                            }
+                            if !held {
+                                return Err(RecvTimeoutError::Disconnected);
+                            }
+                            break;
+                        }
+                    }
+                }
+                drop(hold);
+                if !spin::should_spin() || !backoff.snooze() {
+                    break;
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender or another receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.receivers.register(oper, cx);
+
+                // Has the channel become ready or has a message been set aside just now?
+                if !self.is_queue_empty() || self.is_drained() || self.stash.stamp() != stamp
+                {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.receivers.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+        }
+    }
+
+    /// Puts a received message back so that it is the next one to be received, and wakes up
+    /// blocked receivers.
+    pub fn put_back(&self, msg: T) {
+        self.stash.push_front(msg);
+        self.receivers.notify_all();
+    }
+
+    /// Sets aside all messages in the linked list and returns clones of all messages set aside, in
+    /// the order they are going to be received.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let token = &mut Token::default();
+        let hold = self.stash.hold(&self.receivers);
+        while self.start_recv_queue(token) {
+            match unsafe { self.read(token) } {
+                Ok(msg) => self.set_aside(msg),
+                Err(()) => break,
+            }
+        }
+        drop(hold);
+        self.stash.cloned()
+    }
+
+    /// Puts messages in front of all others, in order, and wakes up blocked receivers.
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, msgs: Vec<T>) {
+        self.stash.push_front_many(msgs);
+        self.receivers.notify_all();
+    }
+
+    /// Sets a message aside and wakes up blocked receivers so that one of them can take it.
+    #[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn set_aside(&self, msg: T) {
+        self.stash.push(msg);
+        self.receivers.notify_all();
+    }
+
+    /// Blocks until all messages sent so far have left the linked list.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn flush(&self, park: bool) {
+        let target = self.tail.index.load(Ordering::SeqCst) & !MARK_BIT;
+
+        let is_flushed = || {
+            let head_index = self.head.index.load(Ordering::SeqCst);
+            head_index.wrapping_sub(target) as isize >= 0 || self.is_disconnected()
+        };
+        self.senders.wait_until(is_flushed, park);
+    }
+
     /// Returns the current number of messages inside the channel.
     pub fn len(&self) -> usize {
+        self.stash.len() + self.queue_len()
+    }
+
+    /// Returns the current number of messages inside the linked list.
+    fn queue_len(&self) -> usize {
         loop {
             // Load the tail index, then load the head index.
             let tail_index = self.tail.index.load(Ordering::SeqCst);
@@ -455,7 +981,7 @@ impl<T> Channel<T> {
 
             // If the tail index didn't change, we've got consistent indices to work with.
             if self.tail.index.load(Ordering::SeqCst) == tail_index {
-                return tail_index.wrapping_sub(head_index);
+                return (tail_index & !MARK_BIT).wrapping_sub(head_index) >> SHIFT;
             }
         }
     }
@@ -465,23 +991,77 @@ impl<T> Channel<T> {
         None
     }
 
+    /// Returns the number of bytes the channel has allocated on the heap.
+    ///
+    /// Blocks that have been unlinked from the list but not yet reclaimed by the garbage collector
+    /// are not counted.
+    pub fn memory_usage(&self) -> usize {
+        let (head_index, tail_index) = loop {
+            // Load the tail index, then load the head index.
+            let tail_index = self.tail.index.load(Ordering::SeqCst);
+            let head_index = self.head.index.load(Ordering::SeqCst);
+
+            // If the tail index didn't change, we've got consistent indices to work with.
+            if self.tail.index.load(Ordering::SeqCst) == tail_index {
+                break (head_index, tail_index);
+            }
+        };
+
+        // The list spans the blocks from the head block to the tail block.
+        let linked = (tail_index >> SHIFT) / BLOCK_CAP;
+        let linked = linked.wrapping_sub((head_index >> SHIFT) / BLOCK_CAP) + 1;
+        let spare = match &self.pool {
+            None => 0,
+            Some(pool) => pool.blocks.lock().len(),
+        };
+
+        (linked + spare) * mem::size_of::<Block<T>>()
+            + self.stash.memory_usage()
+            + self.latency.memory_usage()
+    }
+
+    /// Returns statistics about how long received messages have waited inside the channel.
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+
     /// Disconnects the channel and wakes up all blocked receivers.
     pub fn disconnect(&self) {
-        if !self.is_disconnected.swap(true, Ordering::SeqCst) {
+        let tail_index = self.tail.index.fetch_or(MARK_BIT, Ordering::SeqCst);
+
+        if tail_index & MARK_BIT == 0 {
             self.receivers.disconnect();
         }
     }
 
     /// Returns `true` if the channel is disconnected.
     pub fn is_disconnected(&self) -> bool {
-        self.is_disconnected.load(Ordering::SeqCst)
+        self.tail.index.load(Ordering::SeqCst) & MARK_BIT != 0
+    }
+
+    /// Returns `true` if the channel is disconnected and no message is being set aside.
+    fn is_drained(&self) -> bool {
+        self.is_disconnected() && !self.stash.is_held()
+    }
+
+    /// Returns the number of blocked send and receive operations.
+    ///
+    /// Send operations never block.
+    pub fn waiting(&self) -> (usize, usize) {
+        (0, self.receivers.len())
     }
 
     /// Returns `true` if the channel is empty.
     pub fn is_empty(&self) -> bool {
+        self.stash.is_empty() && self.is_queue_empty()
+    }
+
+    /// Returns `true` if the linked list is empty.
+    fn is_queue_empty(&self) -> bool {
         let head_index = self.head.index.load(Ordering::SeqCst);
         let tail_index = self.tail.index.load(Ordering::SeqCst);
-        head_index == tail_index
+        head_index == tail_index & !MARK_BIT
     }
 
     /// Returns `true` if the channel is full.
@@ -493,7 +1073,7 @@ impl<T> Channel<T> {
 impl<T> Drop for Channel<T> {
     fn drop(&mut self) {
         // Get the tail and head indices.
-        let tail_index = self.tail.index.load(Ordering::Relaxed);
+        let tail_index = self.tail.index.load(Ordering::Relaxed) & !MARK_BIT;
         let mut head_index = self.head.index.load(Ordering::Relaxed);
 
         unsafe {
@@ -506,7 +1086,7 @@ impl<T> Drop for Channel<T> {
             // heap-allocated nodes along the way.
             while head_index != tail_index {
                 let head = head_ptr.deref();
-                let offset = head_index.wrapping_sub(head.start_index);
+                let offset = head_index.wrapping_sub(head.start_index) >> SHIFT;
 
                 let slot = &mut *head.slots.get_unchecked(offset).get();
                 ManuallyDrop::drop(&mut (*slot).msg.get().read());
@@ -517,7 +1097,7 @@ impl<T> Drop for Channel<T> {
                     head_ptr = next;
                 }
 
-                head_index = head_index.wrapping_add(1);
+                head_index = head_index.wrapping_add(1 << SHIFT);
             }
 
             // If there is one last remaining block in the end, destroy it.
@@ -549,7 +1129,7 @@ impl<'a, T> SelectHandle for Receiver<'a, T> {
 
     fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
         self.0.receivers.register(oper, cx);
-        self.0.is_empty() && !self.0.is_disconnected()
+        self.0.is_empty() && !self.0.is_drained()
     }
 
     fn unregister(&self, oper: Operation) {
@@ -563,6 +1143,18 @@ impl<'a, T> SelectHandle for Receiver<'a, T> {
     fn state(&self) -> usize {
         self.0.tail.index.load(Ordering::SeqCst)
     }
+
+    fn is_ready(&self) -> bool {
+        !self.0.is_empty() || self.0.is_drained()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.receivers.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.receivers.unwatch(oper);
+    }
 }
 
 impl<'a, T> SelectHandle for Sender<'a, T> {
@@ -591,4 +1183,12 @@ impl<'a, T> SelectHandle for Sender<'a, T> {
     fn state(&self) -> usize {
         self.0.head.index.load(Ordering::SeqCst)
     }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    fn watch(&self, _oper: Operation, _cx: &Context) {}
+
+    fn unwatch(&self, _oper: Operation) {}
 }
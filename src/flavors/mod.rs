@@ -1,17 +1,21 @@
 //! Channel flavors.
 //!
-//! There are six flavors:
+//! There are seven flavors:
 //!
 //! 1. `after` - Channel that delivers a message after a certain amount of time.
 //! 2. `array` - Bounded channel based on a preallocated array.
-//! 3. `list` - Unbounded channel implemented as a linked list.
-//! 4. `never` - Channel that never delivers messages.
-//! 5. `tick` - Channel that delivers messages periodically.
-//! 6. `zero` - Zero-capacity channel.
+//! 3. `lanes` - Bounded channel with several priority lanes.
+//! 4. `list` - Unbounded channel implemented as a linked list.
+//! 5. `never` - Channel that never delivers messages.
+//! 6. `tick` - Channel that delivers messages periodically.
+//! 7. `zero` - Zero-capacity channel.
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub mod after;
 pub mod array;
+pub mod lanes;
 pub mod list;
 pub mod never;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub mod tick;
 pub mod zero;
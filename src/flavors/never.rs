@@ -6,8 +6,11 @@ use std::marker::PhantomData;
 use std::time::Instant;
 
 use context::Context;
-use err::{RecvTimeoutError, TryRecvError};
+use err::TryRecvError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::RecvTimeoutError;
 use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 use utils;
 
 /// This flavor doesn't need a token.
@@ -35,6 +38,7 @@ impl<T> Channel<T> {
 
     /// Receives a message from the channel.
     #[inline]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
         utils::sleep_until(deadline);
         Err(RecvTimeoutError::Timeout)
@@ -111,4 +115,15 @@ impl<T> SelectHandle for Channel<T> {
     fn state(&self) -> usize {
         0
     }
+
+    #[inline]
+    fn is_ready(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn watch(&self, _oper: Operation, _cx: &Context) {}
+
+    #[inline]
+    fn unwatch(&self, _oper: Operation) {}
 }
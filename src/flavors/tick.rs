@@ -5,22 +5,39 @@
 use std::num::Wrapping;
 use std::sync::Arc;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use parking_lot::Mutex;
 
+use clock::{self, Time};
 use context::Context;
 use err::{RecvTimeoutError, TryRecvError};
-use select::{Operation, SelectHandle, Token};
+use select::{self, Operation, SelectHandle, Token};
+use utils;
+use wheel;
 
 /// Result of a receive operation.
 pub type TickToken = Option<Instant>;
 
+/// Determines when messages are delivered, beyond the fixed interval between them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Schedule {
+    /// If set, messages are delivered at multiples of the interval since the Unix epoch, shifted
+    /// by this offset.
+    pub align: Option<Duration>,
+
+    /// The maximum random delay added to each message.
+    pub jitter: Duration,
+}
+
 /// Channel state.
 struct Inner {
     /// The instant at which the next message will be delivered.
     next_tick: Instant,
 
+    /// The random delay included in `next_tick`.
+    delay: Duration,
+
     /// The index of the next message to be received.
     index: Wrapping<usize>,
 }
@@ -33,33 +50,95 @@ pub struct Channel {
 
     /// The time interval in which messages get delivered.
     duration: Duration,
+
+    /// The clock measuring time.
+    time: Time,
+
+    /// Alignment and jitter of messages.
+    schedule: Schedule,
 }
 
 impl Channel {
     /// Creates a channel that delivers messages periodically.
     #[inline]
-    pub fn new(dur: Duration) -> Self {
-        Channel {
+    pub fn new(dur: Duration, time: Time) -> Self {
+        Channel::with_schedule(dur, time, Schedule::default())
+    }
+
+    /// Creates a channel that delivers messages periodically, aligned and jittered as scheduled.
+    pub fn with_schedule(dur: Duration, time: Time, schedule: Schedule) -> Self {
+        let now = clock::now(&time);
+        let chan = Channel {
             inner: Arc::new(Mutex::new(Inner {
-                next_tick: Instant::now() + dur,
+                next_tick: now,
+                delay: Duration::from_secs(0),
                 index: Wrapping(0),
             })),
             duration: dur,
-        }
+            time,
+            schedule,
+        };
+
+        // The first message is due one interval from now, or at the first aligned instant.
+        chan.schedule_next(&mut chan.inner.lock(), now);
+        chan
+    }
+
+    /// Receives the next message at `now` and schedules the one following it.
+    fn advance(&self, inner: &mut Inner, now: Instant) -> Instant {
+        let msg = inner.next_tick;
+        self.schedule_next(inner, now);
+        inner.index += Wrapping(1);
+        msg
+    }
+
+    /// Schedules the next message after one was received at `now`.
+    fn schedule_next(&self, inner: &mut Inner, now: Instant) {
+        // Random delays don't add up: the next message is due one interval after the time the
+        // current one was due without its delay, unless it was received late.
+        let base = now - inner.delay;
+        let nominal = match self.schedule.align {
+            None => base + self.duration,
+            Some(offset) => base + self.until_aligned(base, offset),
+        };
+
+        inner.delay = utils::random_duration(self.schedule.jitter);
+        inner.next_tick = nominal + inner.delay;
+    }
+
+    /// Returns the time from `base` until the next multiple of the interval since the Unix epoch,
+    /// shifted by `offset`.
+    fn until_aligned(&self, base: Instant, offset: Duration) -> Duration {
+        // Find out what the wall clock shows at `base`.
+        let now = clock::now(&self.time);
+        let wall = if base >= now {
+            SystemTime::now() + (base - now)
+        } else {
+            SystemTime::now() - (now - base)
+        };
+        let wall = wall.duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+
+        let nanos = |d: Duration| {
+            u128::from(d.as_secs()) * 1_000_000_000 + u128::from(d.subsec_nanos())
+        };
+        let period = nanos(self.duration);
+        let past = (nanos(wall) + period - nanos(offset) % period) % period;
+        let until = period - past;
+        Duration::new(
+            (until / 1_000_000_000) as u64,
+            (until % 1_000_000_000) as u32,
+        )
     }
 
     /// Attempts to receive a message without blocking.
     #[inline]
     pub fn try_recv(&self) -> Result<Instant, TryRecvError> {
         let mut inner = self.inner.lock();
-        let now = Instant::now();
+        let now = clock::now(&self.time);
 
         // If the next tick time has been reached, we can receive the next message.
         if now >= inner.next_tick {
-            let msg = inner.next_tick;
-            inner.next_tick = now + self.duration;
-            inner.index += Wrapping(1);
-            Ok(msg)
+            Ok(self.advance(&mut inner, now))
         } else {
             Err(TryRecvError::Empty)
         }
@@ -68,6 +147,10 @@ impl Channel {
     /// Receives a message from the channel.
     #[inline]
     pub fn recv(&self, deadline: Option<Instant>) -> Result<Instant, RecvTimeoutError> {
+        if self.time.is_some() {
+            return self.recv_with_clock(deadline);
+        }
+
         loop {
             // Compute the time to sleep until the next message or the deadline.
             let offset = {
@@ -76,10 +159,7 @@ impl Channel {
 
                 // Check if we can receive the next message.
                 if now >= inner.next_tick {
-                    let msg = inner.next_tick;
-                    inner.next_tick = now + self.duration;
-                    inner.index += Wrapping(1);
-                    return Ok(msg);
+                    return Ok(self.advance(&mut inner, now));
                 }
 
                 // Check if the operation deadline has been reached.
@@ -98,6 +178,25 @@ impl Channel {
         }
     }
 
+    /// Receives a message from the channel, measuring time with a custom clock.
+    fn recv_with_clock(&self, deadline: Option<Instant>) -> Result<Instant, RecvTimeoutError> {
+        loop {
+            if let Ok(msg) = self.try_recv() {
+                return Ok(msg);
+            }
+
+            // Check if the deadline has been reached.
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+
+            // Wait until the clock advances far enough or the deadline is reached.
+            select::wait_ready(self, deadline);
+        }
+    }
+
     /// Reads a message from the channel.
     #[inline]
     pub unsafe fn read(&self, token: &mut Token) -> Result<Instant, ()> {
@@ -108,7 +207,7 @@ impl Channel {
     #[inline]
     pub fn is_empty(&self) -> bool {
         let inner = self.inner.lock();
-        Instant::now() < inner.next_tick
+        clock::now(&self.time) < inner.next_tick
     }
 
     /// Returns `true` if the channel is full.
@@ -140,6 +239,8 @@ impl Clone for Channel {
         Channel {
             inner: self.inner.clone(),
             duration: self.duration,
+            time: self.time.clone(),
+            schedule: self.schedule,
         }
     }
 }
@@ -169,16 +270,31 @@ impl SelectHandle for Channel {
 
     #[inline]
     fn deadline(&self) -> Option<Instant> {
-        Some(self.inner.lock().next_tick)
+        // Timers on the system clock are fired by the timer wheel instead.
+        self.time.as_ref()?;
+        clock::deadline(&self.time, self.inner.lock().next_tick)
     }
 
     #[inline]
-    fn register(&self, _token: &mut Token, _oper: Operation, _cx: &Context) -> bool {
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        if self.time.is_none() {
+            return wheel::register(self.inner.lock().next_tick, oper, cx);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().register(oper, cx);
+        }
         true
     }
 
     #[inline]
-    fn unregister(&self, _oper: Operation) {}
+    fn unregister(&self, oper: Operation) {
+        if self.time.is_none() {
+            wheel::unregister(oper);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().unregister(oper);
+        }
+    }
 
     #[inline]
     fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
@@ -189,11 +305,36 @@ impl SelectHandle for Channel {
     fn state(&self) -> usize {
         // Return the index of the next message to be delivered to the channel.
         let inner = self.inner.lock();
-        let index = if Instant::now() < inner.next_tick {
+        let index = if clock::now(&self.time) < inner.next_tick {
             inner.index
         } else {
             inner.index + Wrapping(1)
         };
         index.0
     }
+
+    #[inline]
+    fn is_ready(&self) -> bool {
+        !self.is_empty()
+    }
+
+    #[inline]
+    fn watch(&self, oper: Operation, cx: &Context) {
+        if self.time.is_none() {
+            wheel::watch(self.inner.lock().next_tick, oper, cx);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().watch(oper, cx);
+        }
+    }
+
+    #[inline]
+    fn unwatch(&self, oper: Operation) {
+        if self.time.is_none() {
+            wheel::unregister(oper);
+        }
+        if let Some(n) = clock::notifier(&self.time) {
+            n.waiters().unwatch(oper);
+        }
+    }
 }
@@ -4,20 +4,49 @@
 
 use std::cell::UnsafeCell;
 use std::marker::PhantomData;
+use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::Instant;
 
 use parking_lot::Mutex;
 
+use builder::WakePolicy;
 use context::Context;
-use err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
+use err::{SendTimeoutError, TryRecvError, TrySendError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::RecvTimeoutError;
+use latency::Histogram;
+#[cfg(feature = "latency")]
+use latency::{LatencyStats, Stamp};
 use select::{Operation, SelectHandle, Selected, Token};
+use stash::Stash;
 use utils::Backoff;
 use waker::Waker;
 
-/// A pointer to a packet.
-pub type ZeroToken = usize;
+/// The token type for the zero flavor.
+#[derive(Debug)]
+pub struct ZeroToken {
+    /// A pointer to the packet to read from or write to.
+    packet: *const u8,
+}
+
+impl Default for ZeroToken {
+    #[inline]
+    fn default() -> Self {
+        ZeroToken {
+            packet: ptr::null(),
+        }
+    }
+}
+
+impl ZeroToken {
+    /// Returns `true` if the token holds a packet, i.e. the channel was not disconnected.
+    #[inline]
+    pub fn has_packet(&self) -> bool {
+        !self.packet.is_null()
+    }
+}
 
 /// A slot for passing one message from a sender to a receiver.
 struct Packet<T> {
@@ -29,15 +58,22 @@ struct Packet<T> {
 
     /// The message.
     msg: UnsafeCell<Option<T>>,
+
+    /// The time the message was sent.
+    #[cfg(feature = "latency")]
+    sent: UnsafeCell<Stamp>,
 }
 
 impl<T> Packet<T> {
     /// Creates an empty packet on the stack.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     fn empty_on_stack() -> Packet<T> {
         Packet {
             on_stack: true,
             ready: AtomicBool::new(false),
             msg: UnsafeCell::new(None),
+            #[cfg(feature = "latency")]
+            sent: UnsafeCell::new(Stamp::none()),
         }
     }
 
@@ -47,6 +83,8 @@ impl<T> Packet<T> {
             on_stack: false,
             ready: AtomicBool::new(false),
             msg: UnsafeCell::new(None),
+            #[cfg(feature = "latency")]
+            sent: UnsafeCell::new(Stamp::none()),
         })
     }
 
@@ -56,6 +94,8 @@ impl<T> Packet<T> {
             on_stack: true,
             ready: AtomicBool::new(false),
             msg: UnsafeCell::new(Some(msg)),
+            #[cfg(feature = "latency")]
+            sent: UnsafeCell::new(Stamp::now()),
         }
     }
 
@@ -85,6 +125,12 @@ pub struct Channel<T> {
     /// Inner representation of the channel.
     inner: Mutex<Inner>,
 
+    /// Messages put back or restored by receivers.
+    stash: Stash<T>,
+
+    /// How long received messages have waited for a receiver.
+    latency: Histogram,
+
     /// Indicates that dropping a `Channel<T>` may drop values of type `T`.
     _marker: PhantomData<T>,
 }
@@ -98,10 +144,22 @@ impl<T> Channel<T> {
                 receivers: Waker::new(),
                 is_disconnected: false,
             }),
+            stash: Stash::new(),
+            latency: Histogram::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Sets which blocked thread an operation wakes up when several are waiting.
+    pub fn with_wake_policy(mut self, policy: WakePolicy) -> Self {
+        {
+            let inner = self.inner.get_mut();
+            inner.senders = Waker::with_policy(policy);
+            inner.receivers = Waker::with_policy(policy);
+        }
+        self
+    }
+
     /// Returns a receiver handle to the channel.
     pub fn receiver(&self) -> Receiver<T> {
         Receiver(self)
@@ -118,10 +176,10 @@ impl<T> Channel<T> {
 
         // If there's a waiting receiver, pair up with it.
         if let Some(operation) = inner.receivers.wake_one() {
-            token.zero = operation.packet;
+            token.zero.packet = operation.packet;
             return true;
         } else if inner.is_disconnected {
-            token.zero = 0;
+            token.zero.packet = ptr::null();
             return true;
         }
 
@@ -135,7 +193,8 @@ impl<T> Channel<T> {
             let packet = Box::into_raw(Packet::<T>::empty_on_heap());
             inner
                 .senders
-                .register_with_packet(oper, packet as usize, cx);
+                .register_with_packet(oper, packet as *const u8, cx);
+            inner.receivers.notify();
             drop(inner);
 
             // Yield to give receivers a chance to pair up with this operation.
@@ -156,7 +215,7 @@ impl<T> Channel<T> {
                     }
 
                     // All receivers have just been dropped.
-                    token.zero = 0;
+                    token.zero.packet = ptr::null();
                     true
                 }
                 Selected::Aborted => {
@@ -169,7 +228,7 @@ impl<T> Channel<T> {
                 }
                 Selected::Operation(_) => {
                     // Success! A receiver has paired up with this operation.
-                    token.zero = cx.wait_packet();
+                    token.zero.packet = cx.wait_packet();
                     true
                 }
             }
@@ -179,26 +238,44 @@ impl<T> Channel<T> {
     /// Writes a message into the packet.
     pub unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
         // If there is no packet, the channel is disconnected.
-        if token.zero == 0 {
+        if token.zero.packet.is_null() {
             return Err(msg);
         }
 
-        let packet = &*(token.zero as *const Packet<T>);
+        let packet = &*(token.zero.packet as *const Packet<T>);
         packet.msg.get().write(Some(msg));
+        #[cfg(feature = "latency")]
+        packet.sent.get().write(Stamp::now());
         packet.ready.store(true, Ordering::Release);
         Ok(())
     }
 
-    /// Attempts to pair up with a sender.
+    /// Fails the rendezvous of a paired up send operation that won't provide its message.
+    ///
+    /// The receive operation on the other side finds the packet empty and looks for another
+    /// message instead.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub unsafe fn abandon(&self, token: &Token) {
+        let packet = &*(token.zero.packet as *const Packet<T>);
+        packet.ready.store(true, Ordering::Release);
+    }
+
+    /// Attempts to pair up with a sender, looking into the stash first.
     fn start_recv(&self, token: &mut Token, short_pause: bool) -> bool {
+        if self.stash.start_recv(token) {
+            return true;
+        }
+
         let mut inner = self.inner.lock();
 
         // If there's a waiting sender, pair up with it.
         if let Some(operation) = inner.senders.wake_one() {
-            token.zero = operation.packet;
+            token.zero.packet = operation.packet;
             return true;
         } else if inner.is_disconnected {
-            token.zero = 0;
+            drop(inner);
+            token.zero.packet = ptr::null();
+            self.stash.start_recv(token);
             return true;
         }
 
@@ -212,7 +289,8 @@ impl<T> Channel<T> {
             let packet = Box::into_raw(Packet::<T>::empty_on_heap());
             inner
                 .receivers
-                .register_with_packet(oper, packet as usize, cx);
+                .register_with_packet(oper, packet as *const u8, cx);
+            inner.senders.notify();
             drop(inner);
 
             // Yield to give senders a chance to pair up with this operation.
@@ -241,12 +319,12 @@ impl<T> Channel<T> {
                     }
 
                     // All senders have just been dropped.
-                    token.zero = 0;
+                    token.zero.packet = ptr::null();
                     true
                 }
                 Selected::Operation(_) => {
                     // Success! A sender has paired up with this operation.
-                    token.zero = cx.wait_packet();
+                    token.zero.packet = cx.wait_packet();
                     true
                 }
             }
@@ -255,30 +333,61 @@ impl<T> Channel<T> {
 
     /// Reads a message from the packet.
     pub unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
+        match self.read_packet(token)? {
+            Some(msg) => Ok(msg),
+            // The sender abandoned the rendezvous, so wait for another message.
+            None => self.recv_again(),
+        }
+    }
+
+    /// Reads a message from the packet, or returns `None` if the sender abandoned it.
+    unsafe fn read_packet(&self, token: &mut Token) -> Result<Option<T>, ()> {
+        if let Some(msg) = self.stash.read(token) {
+            return Ok(Some(msg));
+        }
+
         // If there is no packet, the channel is disconnected.
-        if token.zero == 0 {
+        if token.zero.packet.is_null() {
             return Err(());
         }
 
-        let packet = &*(token.zero as *const Packet<T>);
+        let packet = &*(token.zero.packet as *const Packet<T>);
 
         if packet.on_stack {
             // The message has been in the packet from the beginning, so there is no need to wait
             // for it. However, after reading the message, we need to set `ready` to `true` in
             // order to signal that the packet can be destroyed.
             let msg = packet.msg.get().replace(None).unwrap();
+            #[cfg(feature = "latency")]
+            self.latency.record(packet.sent.get().read());
             packet.ready.store(true, Ordering::Release);
-            Ok(msg)
+            Ok(Some(msg))
         } else {
             // Wait until the message becomes available, then read it and destroy the
             // heap-allocated packet.
             packet.wait_ready();
-            let msg = packet.msg.get().replace(None).unwrap();
+            let msg = packet.msg.get().replace(None);
+            #[cfg(feature = "latency")]
+            self.latency.record(packet.sent.get().read());
             drop(Box::from_raw(packet as *const Packet<T> as *mut Packet<T>));
             Ok(msg)
         }
     }
 
+    /// Receives a message after the operation this one paired up with was abandoned.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn recv_again(&self) -> Result<T, ()> {
+        self.recv(None).map_err(|_| ())
+    }
+
+    /// Receives a message after the operation this one paired up with was abandoned.
+    ///
+    /// Only blocking send operations can abandon a rendezvous, so this never happens here.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    fn recv_again(&self) -> Result<T, ()> {
+        unreachable!()
+    }
+
     /// Attempts to send a message into the channel.
     pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
         let token = &mut Token::default();
@@ -286,7 +395,7 @@ impl<T> Channel<T> {
 
         // If there's a waiting receiver, pair up with it.
         if let Some(operation) = inner.receivers.wake_one() {
-            token.zero = operation.packet;
+            token.zero.packet = operation.packet;
             drop(inner);
             unsafe {
                 self.write(token, msg).ok().unwrap();
@@ -306,7 +415,7 @@ impl<T> Channel<T> {
 
         // If there's a waiting receiver, pair up with it.
         if let Some(operation) = inner.receivers.wake_one() {
-            token.zero = operation.packet;
+            token.zero.packet = operation.packet;
             drop(inner);
             unsafe {
                 self.write(token, msg).ok().unwrap();
@@ -324,7 +433,8 @@ impl<T> Channel<T> {
             let packet = Packet::<T>::message_on_stack(msg);
             inner
                 .senders
-                .register_with_packet(oper, &packet as *const Packet<T> as usize, cx);
+                .register_with_packet(oper, &packet as *const Packet<T> as *const u8, cx);
+            inner.receivers.notify();
             drop(inner);
 
             // Block the current thread.
@@ -353,70 +463,210 @@ impl<T> Channel<T> {
 
     /// Attempts to receive a message without blocking.
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        // Messages put back by receivers come first.
+        if let Some(msg) = self.stash.pop() {
+            return Ok(msg);
+        }
+
         let token = &mut Token::default();
         let mut inner = self.inner.lock();
 
         // If there's a waiting sender, pair up with it.
         if let Some(operation) = inner.senders.wake_one() {
-            token.zero = operation.packet;
+            token.zero.packet = operation.packet;
             drop(inner);
-            unsafe {
-                self.read(token).map_err(|_| TryRecvError::Disconnected)
+            match unsafe { self.read_packet(token) } {
+                Ok(Some(msg)) => Ok(msg),
+                // The sender abandoned the rendezvous.
+                Ok(None) => Err(TryRecvError::Empty),
+                Err(()) => Err(TryRecvError::Disconnected),
             }
         } else if inner.is_disconnected {
-            Err(TryRecvError::Disconnected)
+            drop(inner);
+            self.stash.pop().ok_or(TryRecvError::Disconnected)
         } else {
             Err(TryRecvError::Empty)
         }
     }
 
     /// Receives a message from the channel.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
         let token = &mut Token::default();
-        let mut inner = self.inner.lock();
+        loop {
+            // Messages put back by receivers come first.
+            if let Some(msg) = self.stash.pop() {
+                return Ok(msg);
+            }
 
-        // If there's a waiting sender, pair up with it.
-        if let Some(operation) = inner.senders.wake_one() {
-            token.zero = operation.packet;
-            drop(inner);
-            unsafe {
-                return self.read(token).map_err(|_| RecvTimeoutError::Disconnected);
+            let mut inner = self.inner.lock();
+
+            // If there's a waiting sender, pair up with it.
+            if let Some(operation) = inner.senders.wake_one() {
+                token.zero.packet = operation.packet;
+                drop(inner);
+                match unsafe { self.read_packet(token) } {
+                    Ok(Some(msg)) => return Ok(msg),
+                    // The sender abandoned the rendezvous, so look for another message.
+                    Ok(None) => continue,
+                    Err(()) => return Err(RecvTimeoutError::Disconnected),
+                }
             }
-        }
 
-        if inner.is_disconnected {
-            return Err(RecvTimeoutError::Disconnected);
+            if inner.is_disconnected {
+                drop(inner);
+                return self.stash.pop().ok_or(RecvTimeoutError::Disconnected);
+            }
+
+            let msg = Context::with(|cx| {
+                // Prepare for blocking until a sender wakes us up.
+                let oper = Operation::hook(token);
+                let packet = Packet::<T>::empty_on_stack();
+                inner
+                    .receivers
+                    .register_with_packet(oper, &packet as *const Packet<T> as *const u8, cx);
+                inner.senders.notify();
+
+                // Has a message been put back just now?
+                if !self.stash.is_empty() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+                drop(inner);
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.inner.lock().receivers.unregister(oper).unwrap();
+                        // Check the stash and the channel again.
+                        None
+                    }
+                    Selected::Operation(_) => {
+                        // Wait until the message is provided, then read it.
+                        packet.wait_ready();
+                        #[cfg(feature = "latency")]
+                        self.latency.record(unsafe { packet.sent.get().read() });
+                        unsafe { packet.msg.get().replace(None) }
+                    }
+                }
+            });
+
+            if let Some(msg) = msg {
+                return Ok(msg);
+            }
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
         }
+    }
 
-        Context::with(|cx| {
-            // Prepare for blocking until a sender wakes us up.
-            let oper = Operation::hook(token);
-            let packet = Packet::<T>::empty_on_stack();
-            inner
-                .receivers
-                .register_with_packet(oper, &packet as *const Packet<T> as usize, cx);
-            drop(inner);
+    /// Receives the first message offered by a blocked send operation that satisfies `pred`.
+    ///
+    /// Messages that don't satisfy `pred` stay with their senders, which keep waiting. Send
+    /// operations in `select!` don't offer a message until they are paired up, so they are not
+    /// examined.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_where<F>(
+        &self,
+        pred: &mut F,
+        deadline: Option<Instant>,
+    ) -> Result<T, RecvTimeoutError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let token = &mut Token::default();
+        let wake = || self.inner.lock().receivers.notify_all();
+        loop {
+            // Messages put back by receivers come first.
+            let mut stamp = self.stash.stamp();
+            if let Some(msg) = self.stash.take_where(pred, &mut stamp, wake) {
+                return Ok(msg);
+            }
 
-            // Block the current thread.
-            let sel = cx.wait_until(deadline);
+            let mut inner = self.inner.lock();
 
-            match sel {
-                Selected::Waiting => unreachable!(),
-                Selected::Aborted => {
-                    self.inner.lock().receivers.unregister(oper).unwrap();
-                    Err(RecvTimeoutError::Timeout)
+            // If there's a waiting sender offering a suitable message, pair up with it.
+            let operation = inner.senders.wake_where(|packet| {
+                // The packet stays valid while the sender is registered.
+                let packet = unsafe { &*(packet as *const Packet<T>) };
+                match unsafe { &*packet.msg.get() } {
+                    Some(msg) => pred(msg),
+                    None => false,
                 }
-                Selected::Disconnected => {
-                    self.inner.lock().receivers.unregister(oper).unwrap();
-                    Err(RecvTimeoutError::Disconnected)
+            });
+            if let Some(operation) = operation {
+                token.zero.packet = operation.packet;
+                drop(inner);
+                unsafe {
+                    return self.read(token).map_err(|_| RecvTimeoutError::Disconnected);
                 }
-                Selected::Operation(_) => {
-                    // Wait until the message is provided, then read it.
-                    packet.wait_ready();
-                    unsafe { Ok(packet.msg.get().replace(None).unwrap()) }
+            }
+
+            if inner.is_disconnected {
+                drop(inner);
+                return self
+                    .stash
+                    .take_where(pred, &mut stamp, wake)
+                    .ok_or(RecvTimeoutError::Disconnected);
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender arrives or a message is put back.
+                let oper = Operation::hook(token);
+                inner.receivers.watch(oper, cx);
+
+                // Has a message been put back just now?
+                if self.stash.stamp() != stamp {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+                drop(inner);
+
+                // Block the current thread.
+                match cx.wait_until(deadline) {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected | Selected::Operation(_) => {
+                        self.inner.lock().receivers.unwatch(oper);
+                    }
+                }
+            });
+
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
                 }
             }
-        })
+        }
+    }
+
+    /// Puts a received message back so that it is the next one to be received, and wakes up
+    /// blocked receivers.
+    pub fn put_back(&self, msg: T) {
+        self.stash.push_front(msg);
+        self.inner.lock().receivers.notify_all();
+    }
+
+    /// Returns clones of all messages put back or restored, in the order they are going to be
+    /// received.
+    ///
+    /// Messages of blocked send operations are not included.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.stash.cloned()
+    }
+
+    /// Puts messages in front of all others, in order, and wakes up blocked receivers.
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, msgs: Vec<T>) {
+        self.stash.push_front_many(msgs);
+        self.inner.lock().receivers.notify_all();
     }
 
     /// Disconnects the channel and wakes up all blocked receivers.
@@ -431,8 +681,10 @@ impl<T> Channel<T> {
     }
 
     /// Returns the current number of messages inside the channel.
+    ///
+    /// Only messages put back or restored by receivers are counted.
     pub fn len(&self) -> usize {
-        0
+        self.stash.len()
     }
 
     /// Returns the capacity of the channel.
@@ -440,9 +692,34 @@ impl<T> Channel<T> {
         Some(0)
     }
 
+    /// Returns the number of bytes the channel has allocated on the heap.
+    ///
+    /// Messages offered by blocked operations live on their stacks and are not counted.
+    pub fn memory_usage(&self) -> usize {
+        self.stash.memory_usage() + self.latency.memory_usage()
+    }
+
+    /// Returns statistics about how long received messages have waited for a receiver.
+    #[cfg(feature = "latency")]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+
+    /// Returns `true` if the channel is disconnected.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.lock().is_disconnected
+    }
+
+    /// Returns the number of blocked send and receive operations.
+    #[cfg(feature = "registry")]
+    pub fn waiting(&self) -> (usize, usize) {
+        let inner = self.inner.lock();
+        (inner.senders.len(), inner.receivers.len())
+    }
+
     /// Returns `true` if the channel is empty.
     pub fn is_empty(&self) -> bool {
-        true
+        self.stash.is_empty()
     }
 
     /// Returns `true` if the channel is full.
@@ -476,8 +753,9 @@ impl<'a, T> SelectHandle for Receiver<'a, T> {
         let mut inner = self.0.inner.lock();
         inner
             .receivers
-            .register_with_packet(oper, packet as usize, cx);
-        !inner.senders.can_wake_one() && !inner.is_disconnected
+            .register_with_packet(oper, packet as *const u8, cx);
+        inner.senders.notify();
+        !inner.senders.can_wake_one() && !inner.is_disconnected && self.0.stash.is_empty()
     }
 
     fn unregister(&self, oper: Operation) {
@@ -489,13 +767,26 @@ impl<'a, T> SelectHandle for Receiver<'a, T> {
     }
 
     fn accept(&self, token: &mut Token, cx: &Context) -> bool {
-        token.zero = cx.wait_packet();
+        token.zero.packet = cx.wait_packet();
         true
     }
 
     fn state(&self) -> usize {
         self.0.inner.lock().senders.register_count()
     }
+
+    fn is_ready(&self) -> bool {
+        let inner = self.0.inner.lock();
+        inner.senders.can_wake_one() || inner.is_disconnected || !self.0.stash.is_empty()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.inner.lock().receivers.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.inner.lock().receivers.unwatch(oper);
+    }
 }
 
 impl<'a, T> SelectHandle for Sender<'a, T> {
@@ -517,7 +808,8 @@ impl<'a, T> SelectHandle for Sender<'a, T> {
         let mut inner = self.0.inner.lock();
         inner
             .senders
-            .register_with_packet(oper, packet as usize, cx);
+            .register_with_packet(oper, packet as *const u8, cx);
+        inner.receivers.notify();
         !inner.receivers.can_wake_one() && !inner.is_disconnected
     }
 
@@ -530,11 +822,24 @@ impl<'a, T> SelectHandle for Sender<'a, T> {
     }
 
     fn accept(&self, token: &mut Token, cx: &Context) -> bool {
-        token.zero = cx.wait_packet();
+        token.zero.packet = cx.wait_packet();
         true
     }
 
     fn state(&self) -> usize {
         self.0.inner.lock().receivers.register_count()
     }
+
+    fn is_ready(&self) -> bool {
+        let inner = self.0.inner.lock();
+        inner.receivers.can_wake_one() || inner.is_disconnected
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.inner.lock().senders.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.inner.lock().senders.unwatch(oper);
+    }
 }
@@ -0,0 +1,349 @@
+//! Splitting a stream of bytes received through a channel into frames.
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use channel::Receiver;
+
+/// Determines how a stream of bytes is split into frames.
+///
+/// See [`Frames`] for details.
+///
+/// [`Frames`]: struct.Frames.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Every frame ends with a newline.
+    ///
+    /// The newline, and a carriage return right before it, are not part of the frame. The bytes
+    /// following the last newline make up one more frame if the stream ends without a newline.
+    Newline,
+
+    /// Every frame is preceded by its length in bytes, encoded as a 32-bit big-endian integer.
+    ///
+    /// The stream must not end in the middle of a frame.
+    LengthPrefixed,
+}
+
+/// A reader that pulls chunks of bytes out of a channel.
+///
+/// The receiver is treated as a stream of bytes no matter how the bytes were split into
+/// messages. Reading blocks until a message is received, and reaches the end of the stream once
+/// the channel is empty and disconnected. Empty messages are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use crossbeam_channel::unbounded;
+/// use crossbeam_channel::io::ChunkReader;
+///
+/// let (s, r) = unbounded();
+/// s.send(b"hello ".to_vec()).unwrap();
+/// s.send(b"world".to_vec()).unwrap();
+/// drop(s);
+///
+/// let mut text = String::new();
+/// ChunkReader::new(r).read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "hello world");
+/// ```
+pub struct ChunkReader {
+    /// The channel chunks are received from.
+    receiver: Receiver<Vec<u8>>,
+
+    /// The chunk being read.
+    chunk: Vec<u8>,
+
+    /// The number of bytes in `chunk` that were already consumed.
+    pos: usize,
+}
+
+impl ChunkReader {
+    /// Creates a reader that pulls chunks of bytes out of the channel.
+    pub fn new(receiver: Receiver<Vec<u8>>) -> ChunkReader {
+        ChunkReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Returns the receiver, dropping bytes of the current chunk that were not read yet.
+    pub fn into_inner(self) -> Receiver<Vec<u8>> {
+        self.receiver
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = cmp::min(buf.len(), available.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for ChunkReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => break,
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.chunk.len());
+    }
+}
+
+impl fmt::Debug for ChunkReader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChunkReader").finish()
+    }
+}
+
+/// An iterator over frames read from a buffered reader.
+///
+/// Bytes are read from the reader and buffered until a whole frame has arrived, so a frame may be
+/// split across any number of messages, and a message may hold any number of frames. The reader
+/// is typically a [`ByteReceiver`], or a [`ChunkReader`] over a channel of byte chunks.
+///
+/// The iterator yields `Ok(frame)` for each frame and ends with the stream. A frame longer than
+/// the limit set with [`max_len`] fails with [`InvalidData`], and a stream ending in the middle
+/// of a length-prefixed frame fails with [`UnexpectedEof`]. After an error, the iterator ends.
+///
+/// [`ByteReceiver`]: struct.ByteReceiver.html
+/// [`ChunkReader`]: struct.ChunkReader.html
+/// [`max_len`]: struct.Frames.html#method.max_len
+/// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+/// [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use std::thread;
+/// use crossbeam_channel::io::{byte_channel, Framing, Frames};
+///
+/// let (mut s, r) = byte_channel(4);
+///
+/// thread::spawn(move || {
+///     s.write_all(b"GET /\r\nHEAD /index").unwrap();
+///     s.write_all(b".html\n").unwrap();
+/// });
+///
+/// let frames: Vec<_> = Frames::new(r, Framing::Newline).map(|f| f.unwrap()).collect();
+/// assert_eq!(frames, [&b"GET /"[..], &b"HEAD /index.html"[..]]);
+/// ```
+pub struct Frames<R> {
+    /// The reader bytes are read from.
+    reader: R,
+
+    /// How the stream is split into frames.
+    framing: Framing,
+
+    /// The maximum length of a frame.
+    max_len: usize,
+
+    /// Equals `true` once the stream has ended or an error has occurred.
+    done: bool,
+}
+
+impl<R: BufRead> Frames<R> {
+    /// Creates an iterator over frames read from `reader`.
+    ///
+    /// By default, frames may be of any length.
+    pub fn new(reader: R, framing: Framing) -> Frames<R> {
+        Frames {
+            reader,
+            framing,
+            max_len: usize::max_value(),
+            done: false,
+        }
+    }
+
+    /// Sets the maximum length of a frame.
+    ///
+    /// A longer frame fails with [`InvalidData`] instead of being buffered.
+    ///
+    /// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::ErrorKind;
+    /// use crossbeam_channel::unbounded;
+    /// use crossbeam_channel::io::{frames, Framing};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(b"ok\nway too long\n".to_vec()).unwrap();
+    ///
+    /// let mut frames = frames(r, Framing::Newline).max_len(4);
+    /// assert_eq!(frames.next().unwrap().unwrap(), b"ok");
+    /// assert_eq!(frames.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+    /// assert!(frames.next().is_none());
+    /// # drop(s);
+    /// ```
+    pub fn max_len(mut self, max_len: usize) -> Frames<R> {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Returns the reader.
+    ///
+    /// Bytes of a partially read frame are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads a frame ending with a newline.
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut frame = Vec::new();
+        loop {
+            let (found, used) = {
+                let available = self.reader.fill_buf()?;
+                if available.is_empty() {
+                    // The bytes after the last newline make up the last frame.
+                    return Ok(if frame.is_empty() { None } else { Some(frame) });
+                }
+
+                match available.iter().position(|&b| b == b'\n') {
+                    Some(i) => {
+                        frame.extend_from_slice(&available[..i]);
+                        (true, i + 1)
+                    }
+                    None => {
+                        frame.extend_from_slice(available);
+                        (false, available.len())
+                    }
+                }
+            };
+            self.reader.consume(used);
+
+            if found && frame.last() == Some(&b'\r') {
+                frame.pop();
+            }
+            if frame.len() > self.max_len {
+                return Err(too_long(self.max_len));
+            }
+            if found {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    /// Reads a frame preceded by its length.
+    fn read_prefixed(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut header = [0u8; 4];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.reader.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(truncated()),
+                Ok(n) => filled += n,
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let len = header.iter().fold(0u64, |len, &b| len << 8 | u64::from(b));
+        if len > self.max_len as u64 {
+            return Err(too_long(self.max_len));
+        }
+
+        let mut frame = vec![0; len as usize];
+        self.reader.read_exact(&mut frame).map_err(|err| {
+            if err.kind() == ErrorKind::UnexpectedEof {
+                truncated()
+            } else {
+                err
+            }
+        })?;
+        Ok(Some(frame))
+    }
+}
+
+impl<R: BufRead> Iterator for Frames<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = match self.framing {
+            Framing::Newline => self.read_line(),
+            Framing::LengthPrefixed => self.read_prefixed(),
+        };
+        match res {
+            Ok(Some(frame)) => Some(Ok(frame)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<R> fmt::Debug for Frames<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Frames")
+            .field("framing", &self.framing)
+            .field("max_len", &self.max_len)
+            .finish()
+    }
+}
+
+/// Creates an iterator over frames received through a channel of byte chunks.
+///
+/// This is a shorthand for wrapping the receiver into a [`ChunkReader`] and that into
+/// [`Frames`].
+///
+/// [`ChunkReader`]: struct.ChunkReader.html
+/// [`Frames`]: struct.Frames.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded;
+/// use crossbeam_channel::io::{frames, Framing};
+///
+/// let (s, r) = unbounded();
+/// s.send(vec![0, 0, 0, 3, b'a']).unwrap();
+/// s.send(vec![b'b', b'c', 0, 0]).unwrap();
+/// s.send(vec![0, 1, b'd']).unwrap();
+/// drop(s);
+///
+/// let frames: Vec<_> = frames(r, Framing::LengthPrefixed).map(|f| f.unwrap()).collect();
+/// assert_eq!(frames, [&b"abc"[..], &b"d"[..]]);
+/// ```
+pub fn frames(receiver: Receiver<Vec<u8>>, framing: Framing) -> Frames<ChunkReader> {
+    Frames::new(ChunkReader::new(receiver), framing)
+}
+
+/// Returns the error for a frame longer than `max_len`.
+fn too_long(max_len: usize) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("frame longer than {} bytes", max_len),
+    )
+}
+
+/// Returns the error for a stream ending in the middle of a frame.
+fn truncated() -> io::Error {
+    io::Error::new(ErrorKind::UnexpectedEof, "stream ended in the middle of a frame")
+}
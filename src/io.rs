@@ -0,0 +1,233 @@
+//! Helpers for using channels as I/O transports.
+//!
+//! Adapters that implement [`Read`] or [`Write`] on top of a channel need to report failures as
+//! [`io::Error`]s. All channel errors convert into [`io::Error`] through `From`, so the `?`
+//! operator works out of the box, and the error kinds are mapped as follows:
+//!
+//! * A full or empty channel becomes [`WouldBlock`].
+//! * A timed out operation becomes [`TimedOut`].
+//! * Sending into a disconnected channel becomes [`BrokenPipe`].
+//! * Receiving from an empty and disconnected channel becomes [`UnexpectedEof`].
+//!
+//! The functions in this module go one step further and follow the conventions of [`Read`]: a
+//! receive operation on an empty and disconnected channel is not an error but the end of the
+//! stream, which is reported as `Ok(None)`.
+//!
+//! For streams of bytes, [`byte_channel`] creates a channel whose sides implement [`Read`] and
+//! [`Write`] directly. [`Frames`] splits such a stream into newline-delimited or length-prefixed
+//! frames.
+//!
+//! This module is available only if the `io` feature is enabled.
+//!
+//! [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+//! [`WouldBlock`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.WouldBlock
+//! [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+//! [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+//! [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+//! [`byte_channel`]: fn.byte_channel.html
+//! [`Frames`]: struct.Frames.html
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::{self, Read};
+//! use crossbeam_channel::{io as chan_io, unbounded, Receiver};
+//!
+//! // A reader that pulls chunks of bytes out of a channel.
+//! struct ChannelReader {
+//!     receiver: Receiver<Vec<u8>>,
+//!     pending: Vec<u8>,
+//! }
+//!
+//! impl Read for ChannelReader {
+//!     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+//!         if self.pending.is_empty() {
+//!             match chan_io::recv(&self.receiver)? {
+//!                 None => return Ok(0),
+//!                 Some(chunk) => self.pending = chunk,
+//!             }
+//!         }
+//!         let n = buf.len().min(self.pending.len());
+//!         buf[..n].copy_from_slice(&self.pending[..n]);
+//!         self.pending.drain(..n);
+//!         Ok(n)
+//!     }
+//! }
+//!
+//! let (s, r) = unbounded();
+//! s.send(b"hello ".to_vec()).unwrap();
+//! s.send(b"world".to_vec()).unwrap();
+//! drop(s);
+//!
+//! let mut text = String::new();
+//! let mut reader = ChannelReader { receiver: r, pending: Vec::new() };
+//! reader.read_to_string(&mut text).unwrap();
+//! assert_eq!(text, "hello world");
+//! ```
+
+use std::io;
+use std::time::Duration;
+
+use channel::{Receiver, Sender};
+use err::{RecvTimeoutError, TryRecvError};
+
+pub use bytes::{byte_channel, ByteReceiver, ByteSender};
+pub use framing::{frames, ChunkReader, Framing, Frames};
+
+/// Attempts to send a message into the channel without blocking.
+///
+/// Fails with [`WouldBlock`] if the channel is full and with [`BrokenPipe`] if it is
+/// disconnected. In both cases the message is dropped.
+///
+/// [`WouldBlock`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.WouldBlock
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use crossbeam_channel::{bounded, io};
+///
+/// let (s, r) = bounded(1);
+///
+/// assert!(io::try_send(&s, 1).is_ok());
+/// assert_eq!(io::try_send(&s, 2).unwrap_err().kind(), ErrorKind::WouldBlock);
+///
+/// drop(r);
+/// assert_eq!(io::try_send(&s, 3).unwrap_err().kind(), ErrorKind::BrokenPipe);
+/// ```
+pub fn try_send<T>(s: &Sender<T>, msg: T) -> io::Result<()> {
+    s.try_send(msg).map_err(io::Error::from)
+}
+
+/// Blocks the current thread until a message is sent into the channel.
+///
+/// Fails with [`BrokenPipe`] if the channel is disconnected, in which case the message is
+/// dropped.
+///
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use crossbeam_channel::{unbounded, io};
+///
+/// let (s, r) = unbounded();
+/// assert!(io::send(&s, 1).is_ok());
+///
+/// drop(r);
+/// assert_eq!(io::send(&s, 2).unwrap_err().kind(), ErrorKind::BrokenPipe);
+/// ```
+pub fn send<T>(s: &Sender<T>, msg: T) -> io::Result<()> {
+    s.send(msg).map_err(io::Error::from)
+}
+
+/// Waits for a message to be sent into the channel, but only for a limited time.
+///
+/// Fails with [`TimedOut`] if the operation timed out and with [`BrokenPipe`] if the channel is
+/// disconnected. In both cases the message is dropped.
+///
+/// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+/// [`BrokenPipe`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.BrokenPipe
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use std::time::Duration;
+/// use crossbeam_channel::{bounded, io};
+///
+/// let (s, r) = bounded(0);
+///
+/// let err = io::send_timeout(&s, 1, Duration::from_millis(50)).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::TimedOut);
+/// # drop(r);
+/// ```
+pub fn send_timeout<T>(s: &Sender<T>, msg: T, timeout: Duration) -> io::Result<()> {
+    s.send_timeout(msg, timeout).map_err(io::Error::from)
+}
+
+/// Attempts to receive a message from the channel without blocking.
+///
+/// Returns `Ok(None)` if the channel is empty and disconnected, and fails with [`WouldBlock`] if
+/// it is empty but not disconnected.
+///
+/// [`WouldBlock`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.WouldBlock
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use crossbeam_channel::{unbounded, io};
+///
+/// let (s, r) = unbounded();
+/// assert_eq!(io::try_recv(&r).unwrap_err().kind(), ErrorKind::WouldBlock);
+///
+/// s.send(5).unwrap();
+/// drop(s);
+///
+/// assert_eq!(io::try_recv(&r).unwrap(), Some(5));
+/// assert_eq!(io::try_recv(&r).unwrap(), None);
+/// ```
+pub fn try_recv<T>(r: &Receiver<T>) -> io::Result<Option<T>> {
+    match r.try_recv() {
+        Ok(msg) => Ok(Some(msg)),
+        Err(TryRecvError::Disconnected) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Blocks the current thread until a message is received or the channel is empty and
+/// disconnected.
+///
+/// Returns `Ok(None)` if the channel is empty and disconnected. This function never fails, but
+/// returns a `Result` so that it composes with other I/O operations.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{unbounded, io};
+///
+/// let (s, r) = unbounded();
+/// s.send(5).unwrap();
+/// drop(s);
+///
+/// assert_eq!(io::recv(&r).unwrap(), Some(5));
+/// assert_eq!(io::recv(&r).unwrap(), None);
+/// ```
+pub fn recv<T>(r: &Receiver<T>) -> io::Result<Option<T>> {
+    Ok(r.recv().ok())
+}
+
+/// Waits for a message to be received from the channel, but only for a limited time.
+///
+/// Returns `Ok(None)` if the channel is empty and disconnected, and fails with [`TimedOut`] if the
+/// operation timed out.
+///
+/// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use std::time::Duration;
+/// use crossbeam_channel::{unbounded, io};
+///
+/// let (s, r) = unbounded::<i32>();
+///
+/// let err = io::recv_timeout(&r, Duration::from_millis(50)).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::TimedOut);
+///
+/// drop(s);
+/// assert_eq!(io::recv_timeout(&r, Duration::from_millis(50)).unwrap(), None);
+/// ```
+pub fn recv_timeout<T>(r: &Receiver<T>, timeout: Duration) -> io::Result<Option<T>> {
+    match r.recv_timeout(timeout) {
+        Ok(msg) => Ok(Some(msg)),
+        Err(RecvTimeoutError::Disconnected) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,307 @@
+//! Measuring how long messages wait inside channels.
+//!
+//! Every message is stored together with a hidden `Stamp` holding the time it was sent. When the
+//! message is taken out of the channel, the time it spent inside is recorded into the channel's
+//! `Histogram`. Without the `latency` feature, the array, list and zero flavors leave the stamp out
+//! of their slots and packets altogether, both types are empty, and all of this compiles down to
+//! nothing.
+//!
+//! The histogram is HDR-style: the first `SUB_BUCKETS` buckets hold one nanosecond each, and every
+//! following power of two is split into `SUB_BUCKETS / 2` buckets of equal width. That keeps the
+//! relative error of every recorded value below `2 / SUB_BUCKETS` while covering the whole range
+//! of `u64` nanoseconds with under a thousand counters. Counters are `usize`-sized so that the
+//! crate keeps building on targets without `AtomicU64`.
+
+#[cfg(feature = "latency")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "latency")]
+use std::time::{Duration, Instant};
+
+/// The number of bits used to tell values apart within a power of two.
+#[cfg(feature = "latency")]
+const SUB_BITS: u32 = 5;
+
+/// The number of buckets holding the smallest values, one nanosecond each.
+#[cfg(feature = "latency")]
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+
+/// The number of buckets every following power of two is split into.
+#[cfg(feature = "latency")]
+const HALF: usize = SUB_BUCKETS / 2;
+
+/// The total number of buckets needed to cover all `u64` values.
+#[cfg(feature = "latency")]
+const BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BITS as usize) * HALF;
+
+/// The time a message was sent, or `None` if the message was put back into the channel and has
+/// already been counted.
+#[cfg(feature = "latency")]
+#[derive(Clone, Copy)]
+pub struct Stamp(Option<Instant>);
+
+/// The time a message was sent.
+#[cfg(not(feature = "latency"))]
+#[derive(Clone, Copy)]
+pub struct Stamp;
+
+impl Stamp {
+    /// Returns a stamp for a message sent right now.
+    #[cfg(feature = "latency")]
+    #[inline]
+    pub fn now() -> Stamp {
+        Stamp(Some(Instant::now()))
+    }
+
+    /// Returns a stamp for a message sent right now.
+    #[cfg(not(feature = "latency"))]
+    #[inline]
+    pub fn now() -> Stamp {
+        Stamp
+    }
+
+    /// Returns a stamp for a message put back into the channel, which is not counted again.
+    #[cfg(feature = "latency")]
+    #[inline]
+    pub fn none() -> Stamp {
+        Stamp(None)
+    }
+
+    /// Returns a stamp for a message put back into the channel, which is not counted again.
+    #[cfg(not(feature = "latency"))]
+    #[inline]
+    pub fn none() -> Stamp {
+        Stamp
+    }
+}
+
+/// Counts how long messages waited inside a channel.
+#[cfg(feature = "latency")]
+pub struct Histogram {
+    /// The number of recorded values in each bucket.
+    buckets: Box<[AtomicUsize]>,
+}
+
+/// Counts how long messages waited inside a channel.
+#[cfg(not(feature = "latency"))]
+pub struct Histogram;
+
+impl Histogram {
+    /// Creates an empty histogram.
+    #[cfg(feature = "latency")]
+    pub fn new() -> Histogram {
+        Histogram {
+            buckets: (0..BUCKETS).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+
+    /// Creates an empty histogram.
+    #[cfg(not(feature = "latency"))]
+    pub fn new() -> Histogram {
+        Histogram
+    }
+
+    /// Records the time a message with the given stamp has spent inside the channel.
+    #[cfg(feature = "latency")]
+    #[inline]
+    pub fn record(&self, stamp: Stamp) {
+        if let Some(sent) = stamp.0 {
+            let nanos = nanos(sent.elapsed());
+            self.buckets[index(nanos)].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records the time a message with the given stamp has spent inside the channel.
+    #[cfg(not(feature = "latency"))]
+    #[inline]
+    pub fn record(&self, _stamp: Stamp) {}
+
+    /// Returns the statistics collected so far.
+    #[cfg(feature = "latency")]
+    pub fn stats(&self) -> LatencyStats {
+        let mut counts: Vec<usize> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        while counts.last() == Some(&0) {
+            counts.pop();
+        }
+        LatencyStats { counts }
+    }
+
+    /// Returns the number of bytes held by the histogram on the heap.
+    #[cfg(feature = "latency")]
+    pub fn memory_usage(&self) -> usize {
+        BUCKETS * ::std::mem::size_of::<AtomicUsize>()
+    }
+
+    /// Returns the number of bytes held by the histogram on the heap.
+    #[cfg(not(feature = "latency"))]
+    pub fn memory_usage(&self) -> usize {
+        0
+    }
+}
+
+/// Statistics about how long messages waited inside a channel.
+///
+/// The latency of a message is the time from the moment it was sent until it was taken out of the
+/// channel. Messages set aside by [`Receiver::recv_where`] are counted when they are set aside,
+/// and messages put back into the channel, for example by [`RecvGuard`], are not counted again. In
+/// a zero-capacity channel, the latency is the time the sender spent waiting for a receiver.
+///
+/// Latencies are kept in an HDR-style histogram, so all values reported here are accurate to
+/// within about 6%. The statistics are returned by [`Receiver::latency_stats`].
+///
+/// This type is available only if the `latency` feature is enabled.
+///
+/// [`Receiver::recv_where`]: struct.Receiver.html#method.recv_where
+/// [`RecvGuard`]: struct.RecvGuard.html
+/// [`Receiver::latency_stats`]: struct.Receiver.html#method.latency_stats
+#[cfg(feature = "latency")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// The number of recorded values in each bucket, without trailing empty buckets.
+    counts: Vec<usize>,
+}
+
+#[cfg(feature = "latency")]
+impl LatencyStats {
+    /// Returns the number of received messages.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().map(|&c| c as u64).sum()
+    }
+
+    /// Returns the lowest latency, or `None` if no messages were received.
+    pub fn min(&self) -> Option<Duration> {
+        self.counts
+            .iter()
+            .position(|&c| c > 0)
+            .map(|i| duration(lowest(i)))
+    }
+
+    /// Returns the highest latency, or `None` if no messages were received.
+    pub fn max(&self) -> Option<Duration> {
+        match self.counts.len() {
+            0 => None,
+            n => Some(duration(highest(n - 1))),
+        }
+    }
+
+    /// Returns the average latency, or `None` if no messages were received.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let sum: f64 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| c as f64 * (lowest(i) as f64 + highest(i) as f64) / 2.0)
+            .sum();
+        Some(duration((sum / count as f64) as u64))
+    }
+
+    /// Returns the latency below which the given percentage of messages fall, or `None` if no
+    /// messages were received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 100.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// for i in 0..10 {
+    ///     s.send(i).unwrap();
+    /// }
+    ///
+    /// // The last message waits much longer than the others.
+    /// r.iter().take(9).count();
+    /// thread::sleep(Duration::from_millis(100));
+    /// r.recv().unwrap();
+    ///
+    /// let stats = r.latency_stats();
+    /// assert!(stats.percentile(50.0).unwrap() < Duration::from_millis(100));
+    /// assert!(stats.percentile(100.0).unwrap() >= Duration::from_millis(90));
+    /// ```
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        assert!(
+            percentile >= 0.0 && percentile <= 100.0,
+            "percentile must be between 0 and 100",
+        );
+
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((percentile / 100.0 * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &c) in self.counts.iter().enumerate() {
+            seen += c as u64;
+            if seen >= rank {
+                return Some(duration(highest(i)));
+            }
+        }
+        self.max()
+    }
+}
+
+/// Converts nanoseconds into a duration.
+#[cfg(feature = "latency")]
+fn duration(nanos: u64) -> Duration {
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+/// Converts a duration into nanoseconds, saturating at `u64::max_value()`.
+#[cfg(feature = "latency")]
+fn nanos(d: Duration) -> u64 {
+    d.as_secs()
+        .saturating_mul(1_000_000_000)
+        .saturating_add(u64::from(d.subsec_nanos()))
+}
+
+/// Returns the index of the bucket holding `value`.
+#[cfg(feature = "latency")]
+fn index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+
+    // Keep the `SUB_BITS` highest bits of the value. The highest of them is always set.
+    let shift = 64 - value.leading_zeros() - SUB_BITS;
+    let top = (value >> shift) as usize;
+    SUB_BUCKETS + (shift as usize - 1) * HALF + (top - HALF)
+}
+
+/// Returns the lowest value in the bucket at `index`.
+#[cfg(feature = "latency")]
+fn lowest(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+
+    let i = index - SUB_BUCKETS;
+    let shift = i / HALF + 1;
+    let top = (i % HALF + HALF) as u64;
+    top << shift
+}
+
+/// Returns the highest value in the bucket at `index`.
+#[cfg(feature = "latency")]
+fn highest(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+
+    let i = index - SUB_BUCKETS;
+    let shift = i / HALF + 1;
+    lowest(index) + ((1u64 << shift) - 1)
+}
@@ -332,6 +332,40 @@
 //! # }
 //! ```
 //!
+//! # WebAssembly
+//!
+//! The crate compiles for `wasm32-unknown-unknown`, where code usually runs on a single thread
+//! without access to a clock. Non-blocking operations like [`try_send`], [`try_recv`],
+//! [`try_iter`], and [`Select::try_select`] work as usual.
+//!
+//! Operations that may block, operations with timeouts, and the channels created by [`after`] and
+//! [`tick`] need threads or a clock, so they don't exist on this target. Calling `send`, `recv`,
+//! `recv_timeout`, `Select::select`, or a `select!` without a `default` case is a compile error
+//! rather than a panic at runtime. The same goes for the types built on top of them, like
+//! `TickBuilder`, `TimeoutReceiver`, `Mux`, `zip`, and the TTL channels.
+//!
+//! # Targets without 64-bit atomics
+//!
+//! All atomic counters, indices, and stamps used by the channels are `usize`-sized, so the crate
+//! builds on 32-bit targets that lack `AtomicU64`, like `mipsel-unknown-linux-gnu`,
+//! `powerpc-unknown-linux-gnu`, and `armv5te-unknown-linux-gnueabi`. On such targets, the capacity
+//! of bounded channels is limited to `usize::max_value() / 4` like everywhere else. Since nothing
+//! needs 64-bit atomics, there is no lock-based fallback.
+//!
+//! Bare-metal targets like `thumbv7m-none-eabi` (ARM Cortex-M) are not supported. Their problem is
+//! not the width of atomics but the lack of the standard library, which the crate needs for
+//! threads, thread parking, and time. A lock-based fallback wouldn't change that.
+//!
+//! # Miri
+//!
+//! By default, the unbounded flavor reclaims memory with `crossbeam-epoch`, which packs tags into
+//! the low bits of pointers and stores them as integers. Miri can't track the provenance of such
+//! pointers. Enabling the `miri` feature, or building with `cfg(miri)`, swaps epoch reclamation
+//! for a simpler and slower scheme that only ever stores pointers as pointers, so the channels can
+//! be checked with `-Zmiri-strict-provenance`.
+//!
+//! Dependencies such as `parking_lot` are outside the scope of this mode.
+//!
 //! [`std::sync::mpsc`]: https://doc.rust-lang.org/std/sync/mpsc/index.html
 //! [`unbounded`]: fn.unbounded.html
 //! [`bounded`]: fn.bounded.html
@@ -342,33 +376,180 @@
 //! [`recv`]: struct.Receiver.html#method.recv
 //! [`iter`]: struct.Receiver.html#method.iter
 //! [`try_iter`]: struct.Receiver.html#method.try_iter
+//! [`try_send`]: struct.Sender.html#method.try_send
+//! [`try_recv`]: struct.Receiver.html#method.try_recv
+//! [`Select::try_select`]: struct.Select.html#method.try_select
 //! [`select!`]: macro.select.html
 //! [`Select`]: struct.Select.html
 //! [`Sender`]: struct.Sender.html
 //! [`Receiver`]: struct.Receiver.html
 
+#[cfg(feature = "deque")]
+extern crate crossbeam_deque;
 extern crate crossbeam_epoch;
 extern crate crossbeam_utils;
+#[macro_use]
+extern crate lazy_static;
+#[cfg(target_os = "linux")]
+extern crate libc;
 extern crate parking_lot;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
 extern crate smallvec;
 
+mod affinity;
+mod builder;
+#[cfg(feature = "io")]
+mod bytes;
+mod cancel;
 mod channel;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod clock;
 mod context;
+#[cfg(feature = "deque")]
+pub mod deque;
+mod duplex;
 mod err;
 mod flavors;
+#[cfg(feature = "io")]
+mod framing;
+#[cfg(feature = "io")]
+pub mod io;
+mod latency;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod mux;
+mod ordered;
+mod park;
+mod poll;
+#[cfg(any(miri, feature = "miri"))]
+mod reclaim;
+#[cfg(feature = "registry")]
+pub mod registry;
+mod router;
 mod select;
+mod select_loop;
 mod select_macro;
+mod select_pool;
+mod selectable;
+mod sequenced;
+mod sharded;
+mod shutdown;
+#[cfg(feature = "sim")]
+pub mod sim;
+mod specialized;
+mod spin;
+mod split;
+mod stash;
+mod stats;
+mod tagged;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod tee;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod ticker;
+mod timeout;
+mod trigger;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod ttl;
 mod utils;
 mod waker;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod wheel;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod zip;
 
 pub use channel::{Receiver, Sender};
-pub use channel::{bounded, unbounded};
-pub use channel::{after, never, tick};
-pub use channel::{IntoIter, Iter, TryIter};
+pub use channel::{bounded, bounded_by_weight, lanes, unbounded};
+pub use channel::never;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use channel::{after, tick};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use channel::{after_with_clock, tick_with_clock};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use channel::{Chunks, IntoIter, Iter};
+pub use channel::{Drain, TryIter, TryIterStatus};
+pub use channel::{Permit, RecvGuard, Reunited};
+
+pub use builder::{Builder, Displace, DropPolicy, FullPolicy, WakePolicy};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use builder::TickBuilder;
+
+pub use cancel::CancelToken;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use clock::{Clock, ManualClock, SystemClock};
+
+pub use duplex::{duplex, Duplex};
+
+#[cfg(feature = "latency")]
+pub use latency::LatencyStats;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use mux::Mux;
+
+pub use ordered::{bounded_ordered, unbounded_ordered};
+pub use ordered::{OrderedReceiver, OrderedSender};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use ordered::OrderedIter;
 
-pub use select::{Select, SelectedOperation};
+pub use park::{set_parker, Parker};
 
-pub use err::{RecvError, RecvTimeoutError, TryRecvError};
-pub use err::{SendError, SendTimeoutError, TrySendError};
+pub use poll::Poller;
+
+pub use router::Router;
+
+pub use sequenced::{bounded_sequenced, unbounded_sequenced};
+pub use sequenced::{SequenceStats, SequencedReceiver, SequencedSender, SequencedTryIter};
+
+pub use sharded::{unbounded_sharded, ShardedReceiver, ShardedSender};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use sharded::ShardedIter;
+
+pub use shutdown::ShutdownGroup;
+
+pub use specialized::{ArrayReceiver, ArraySender, ListReceiver, ListSender};
+
+pub use split::SplitReceiver;
+
+pub use stats::BlockingStats;
+
+pub use tagged::{bounded_tagged, unbounded_tagged};
+pub use tagged::{SenderId, TaggedReceiver, TaggedSender};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use tagged::TaggedIter;
+
+pub use ticker::{manual_ticker, TickDriver};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use timeout::{TimeoutIter, TimeoutReceiver};
+
+pub use trigger::Trigger;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use ttl::{bounded_ttl, TtlReceiver, TtlSender};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use zip::zip;
+
+pub use select::{Budget, Fairness, Select, SelectSet, SelectedOperation};
+pub use select::set_rng_seed;
+pub use select_pool::SelectPool;
+pub use selectable::{Notifier, Selectable};
+
+pub use err::{RecvCancelError, RecvError, RecvTimeoutError, TryRecvError};
+pub use err::{SendCancelError, SendError, SendTimeoutError, TrySendError};
 pub use err::{SelectTimeoutError, TrySelectError};
+pub use err::{Elapsed, ReuniteError};
+
+/// Crate internals used by the `select!` macro.
+#[doc(hidden)]
+pub mod internal {
+    pub use select::SelectHandle;
+    pub use select::{closed, same_channel};
+    pub use select::try_select;
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub use select::{select, select_timeout};
+    pub use selectable::custom;
+}
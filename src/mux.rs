@@ -0,0 +1,256 @@
+//! Multiplexing channels of different types into a single channel.
+
+use std::fmt;
+use std::thread;
+
+use channel::{bounded, Receiver};
+use err::RecvError;
+use select::{Select, SelectedOperation};
+
+/// A receiving side of a multiplexer, tagging its messages on the way out.
+trait Source<E>: Send {
+    /// Adds the receive operation to `sel` and returns its index.
+    fn register<'a>(&'a self, sel: &mut Select<'a>) -> usize;
+
+    /// Completes the selected receive operation and tags the received message.
+    fn complete(&self, oper: SelectedOperation) -> Result<E, RecvError>;
+}
+
+/// A receiver together with the function tagging its messages.
+struct Tagged<T, F> {
+    receiver: Receiver<T>,
+    tag: F,
+}
+
+impl<T, E, F> Source<E> for Tagged<T, F>
+where
+    T: Send,
+    F: Fn(T) -> E + Send,
+{
+    fn register<'a>(&'a self, sel: &mut Select<'a>) -> usize {
+        sel.recv(&self.receiver)
+    }
+
+    fn complete(&self, oper: SelectedOperation) -> Result<E, RecvError> {
+        oper.recv(&self.receiver).map(&self.tag)
+    }
+}
+
+/// Combines receivers of different types into a single receiver.
+///
+/// Each receiver is added together with a function that wraps its messages into a common type,
+/// typically an enum variant. The combined receiver delivers messages from all added receivers,
+/// tagged by their source.
+///
+/// Messages are moved from the added receivers into the combined one by a background thread. The
+/// thread waits on all receivers at once and forwards one message at a time through a channel of
+/// capacity one. A message is taken out of its original channel only once there is room for it
+/// in the combined one, so at most one message is buffered on the way. Messages that haven't been
+/// forwarded yet stay in their original channels, which keeps applying backpressure to senders.
+///
+/// The combined receiver becomes disconnected when all added receivers are disconnected and
+/// empty. The background thread stops at that point, or as soon as the combined receiver is
+/// dropped, even if no messages arrive anymore.
+///
+/// The [`mux!`] macro is a more concise way of building a multiplexer.
+///
+/// [`mux!`]: macro.mux.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{unbounded, Mux};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Event {
+///     Key(char),
+///     Click(i32, i32),
+/// }
+///
+/// let (s1, r1) = unbounded();
+/// let (s2, r2) = unbounded();
+///
+/// let r = Mux::new()
+///     .recv(r1, Event::Key)
+///     .recv(r2, |(x, y)| Event::Click(x, y))
+///     .build();
+///
+/// s1.send('a').unwrap();
+/// assert_eq!(r.recv(), Ok(Event::Key('a')));
+///
+/// s2.send((1, 2)).unwrap();
+/// assert_eq!(r.recv(), Ok(Event::Click(1, 2)));
+/// ```
+pub struct Mux<E> {
+    /// The receivers to multiplex.
+    sources: Vec<Box<Source<E>>>,
+}
+
+impl<E: Send + 'static> Mux<E> {
+    /// Creates a multiplexer without any receivers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Mux, RecvError};
+    ///
+    /// let r = Mux::<i32>::new().build();
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn new() -> Mux<E> {
+        Mux {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Adds a receiver whose messages are wrapped with `tag`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Mux};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = Mux::new().recv(r, Some).build();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(Some(1)));
+    /// ```
+    pub fn recv<T, F>(mut self, r: Receiver<T>, tag: F) -> Mux<E>
+    where
+        T: Send + 'static,
+        F: Fn(T) -> E + Send + 'static,
+    {
+        self.sources.push(Box::new(Tagged { receiver: r, tag }));
+        self
+    }
+
+    /// Starts the multiplexer and returns the combined receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Mux, RecvError};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<u8>();
+    /// let r = Mux::new()
+    ///     .recv(r1, |n| n)
+    ///     .recv(r2, |n| i32::from(n))
+    ///     .build();
+    ///
+    /// s1.send(-1).unwrap();
+    /// drop(s1);
+    /// drop(s2);
+    ///
+    /// assert_eq!(r.recv(), Ok(-1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn build(self) -> Receiver<E> {
+        let (s, r) = bounded(1);
+        if self.sources.is_empty() {
+            return r;
+        }
+
+        let sources = self.sources;
+        thread::spawn(move || {
+            let mut sel = Select::with_capacity(sources.len() + 1);
+            for source in &sources {
+                source.register(&mut sel);
+            }
+            let abandoned = sel.abandoned(&s);
+
+            let mut remaining = sources.len();
+            let mut permit = None;
+            while remaining > 0 {
+                // Make room for the next message before taking it out of its source.
+                if permit.is_none() {
+                    match s.reserve() {
+                        Ok(p) => permit = Some(p),
+                        Err(_) => break,
+                    }
+                }
+
+                let oper = sel.select();
+                let index = oper.index();
+
+                if index == abandoned {
+                    oper.abandoned(&s);
+                    break;
+                }
+
+                match sources[index].complete(oper) {
+                    Ok(msg) => {
+                        if permit.take().unwrap().send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError) => {
+                        sel.disarm(index);
+                        remaining -= 1;
+                    }
+                }
+            }
+        });
+        r
+    }
+}
+
+impl<E: Send + 'static> Default for Mux<E> {
+    fn default() -> Mux<E> {
+        Mux::new()
+    }
+}
+
+impl<E> fmt::Debug for Mux<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mux")
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
+
+/// Combines receivers of different types into a single receiver.
+///
+/// Every receiver is paired with a function, usually an enum variant, that wraps its messages into
+/// the common message type of the combined receiver. This is a shorthand for building a [`Mux`].
+///
+/// [`Mux`]: struct.Mux.html
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::unbounded;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Msg {
+///     Text(String),
+///     Number(i32),
+/// }
+///
+/// let (s1, r1) = unbounded();
+/// let (s2, r2) = unbounded();
+///
+/// let r = mux! {
+///     Msg::Text = r1,
+///     Msg::Number = r2,
+/// };
+///
+/// s1.send("hello".to_string()).unwrap();
+/// assert_eq!(r.recv(), Ok(Msg::Text("hello".to_string())));
+///
+/// s2.send(7).unwrap();
+/// assert_eq!(r.recv(), Ok(Msg::Number(7)));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mux {
+    ($($tag:path = $r:expr),* $(,)*) => {
+        $crate::Mux::new()
+            $(.recv($r, $tag))*
+            .build()
+    };
+}
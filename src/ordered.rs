@@ -0,0 +1,706 @@
+//! Channels that deliver messages in the order of their sequence numbers.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use channel::{bounded, unbounded, Receiver, Sender};
+use context::Context;
+use err::TryRecvError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvError, RecvTimeoutError};
+use err::TrySendError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{SendError, SendTimeoutError};
+use select::{Operation, Ready, SelectHandle, Token};
+
+/// Creates a channel of bounded capacity that restores the order of messages sent out of order.
+///
+/// Every message is sent together with its sequence number. Messages can be sent in any order,
+/// but the receiver delivers them in strictly increasing order of their sequence numbers,
+/// starting at zero. A message that arrives early is buffered by the receiver until all messages
+/// before it have been delivered. This is useful for parallel pipeline stages that must restore
+/// the order of their input.
+///
+/// The capacity limits the messages in the channel, not the ones buffered by the receiver. Since
+/// the receiver takes messages out of the channel while waiting for a missing one, senders never
+/// get stuck behind a gap.
+///
+/// Sending a message with a sequence number that has already been used or delivered is a logic
+/// error. Such a message is discarded when the receiver comes across it.
+///
+/// # Panics
+///
+/// Panics if the capacity is greater than `usize::max_value() / 4`.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::bounded_ordered;
+///
+/// let (s, r) = bounded_ordered(10);
+///
+/// for i in 0..4 {
+///     let s = s.clone();
+///     thread::spawn(move || s.send(i, i * i).unwrap());
+/// }
+/// drop(s);
+///
+/// let v: Vec<_> = r.iter().collect();
+/// assert_eq!(v, [0, 1, 4, 9]);
+/// ```
+pub fn bounded_ordered<T>(cap: usize) -> (OrderedSender<T>, OrderedReceiver<T>) {
+    let (s, r) = bounded(cap);
+    ordered(s, r)
+}
+
+/// Creates a channel of unbounded capacity that restores the order of messages sent out of order.
+///
+/// See [`bounded_ordered`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{unbounded_ordered, TryRecvError};
+///
+/// let (s, r) = unbounded_ordered();
+///
+/// s.send(1, 'b').unwrap();
+/// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+///
+/// s.send(0, 'a').unwrap();
+/// assert_eq!(r.try_recv(), Ok('a'));
+/// assert_eq!(r.try_recv(), Ok('b'));
+/// ```
+///
+/// [`bounded_ordered`]: fn.bounded_ordered.html
+pub fn unbounded_ordered<T>() -> (OrderedSender<T>, OrderedReceiver<T>) {
+    let (s, r) = unbounded();
+    ordered(s, r)
+}
+
+/// Wraps the two sides of a channel carrying numbered messages.
+fn ordered<T>(
+    s: Sender<(u64, T)>,
+    r: Receiver<(u64, T)>,
+) -> (OrderedSender<T>, OrderedReceiver<T>) {
+    let s = OrderedSender { sender: s };
+    let r = OrderedReceiver {
+        receiver: r,
+        state: Mutex::new(State {
+            next: 0,
+            pending: BinaryHeap::new(),
+        }),
+    };
+    (s, r)
+}
+
+/// A message that arrived before its turn.
+struct Entry<T> {
+    /// The sequence number.
+    seq: u64,
+
+    /// The message.
+    msg: T,
+}
+
+// Entries are ordered so that the one with the lowest sequence number is the greatest, which puts
+// it on top of the heap.
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Entry<T>) -> Ordering {
+        other.seq.cmp(&self.seq)
+    }
+}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Entry<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Entry<T>) -> bool {
+        self.seq == other.seq
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+/// The state of the receiving side.
+struct State<T> {
+    /// The sequence number of the next message to deliver.
+    next: u64,
+
+    /// Messages that arrived before their turn.
+    pending: BinaryHeap<Entry<T>>,
+}
+
+impl<T> State<T> {
+    /// Buffers a message taken out of the channel.
+    fn push(&mut self, (seq, msg): (u64, T)) {
+        // Messages whose turn has already passed are discarded.
+        if seq >= self.next {
+            self.pending.push(Entry { seq, msg });
+        }
+    }
+
+    /// Returns `true` if the next message is in the buffer.
+    fn has_next(&self) -> bool {
+        self.pending
+            .peek()
+            .map_or(false, |entry| entry.seq <= self.next)
+    }
+
+    /// Takes the next message out of the buffer if it has arrived.
+    fn pop_next(&mut self) -> Option<T> {
+        while let Some(seq) = self.pending.peek().map(|entry| entry.seq) {
+            if seq > self.next {
+                return None;
+            }
+
+            let entry = self.pending.pop().unwrap();
+            if seq == self.next {
+                self.next += 1;
+                return Some(entry.msg);
+            }
+            // A duplicate of a message that has already been delivered.
+        }
+        None
+    }
+
+    /// Takes the earliest buffered message, skipping over any missing ones.
+    ///
+    /// This is used once the channel is disconnected and missing messages can never arrive.
+    fn pop_any(&mut self) -> Option<T> {
+        if let Some(entry) = self.pending.peek() {
+            self.next = entry.seq;
+        }
+        self.pop_next()
+    }
+}
+
+/// The sending side of a channel that restores the order of messages.
+///
+/// Senders are created by the [`bounded_ordered`] and [`unbounded_ordered`] functions.
+///
+/// [`bounded_ordered`]: fn.bounded_ordered.html
+/// [`unbounded_ordered`]: fn.unbounded_ordered.html
+pub struct OrderedSender<T> {
+    /// The underlying sender.
+    sender: Sender<(u64, T)>,
+}
+
+impl<T> OrderedSender<T> {
+    /// Attempts to send a message with sequence number `seq` into the channel without blocking.
+    ///
+    /// See [`Sender::try_send`] for details. The error contains the sequence number together with
+    /// the message.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_ordered, TrySendError};
+    ///
+    /// let (s, r) = bounded_ordered(1);
+    ///
+    /// assert_eq!(s.try_send(1, 'b'), Ok(()));
+    /// assert_eq!(s.try_send(0, 'a'), Err(TrySendError::Full((0, 'a'))));
+    /// # drop(r);
+    /// ```
+    pub fn try_send(&self, seq: u64, msg: T) -> Result<(), TrySendError<(u64, T)>> {
+        self.sender.try_send((seq, msg))
+    }
+
+    /// Sends a message with sequence number `seq` into the channel, blocking if the channel is
+    /// full.
+    ///
+    /// See [`Sender::send`] for details. The error contains the sequence number together with the
+    /// message.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_ordered, SendError};
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// assert_eq!(s.send(0, 'a'), Ok(()));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send(1, 'b'), Err(SendError((1, 'b'))));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, seq: u64, msg: T) -> Result<(), SendError<(u64, T)>> {
+        self.sender.send((seq, msg))
+    }
+
+    /// Waits for a message with sequence number `seq` to be sent into the channel, but only for a
+    /// limited time.
+    ///
+    /// See [`Sender::send_timeout`] for details. The error contains the sequence number together
+    /// with the message.
+    ///
+    /// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ordered, SendTimeoutError};
+    ///
+    /// let (s, r) = bounded_ordered(1);
+    /// s.send(0, 'a').unwrap();
+    ///
+    /// assert_eq!(
+    ///     s.send_timeout(1, 'b', Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout((1, 'b'))),
+    /// );
+    /// # drop(r);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout(
+        &self,
+        seq: u64,
+        msg: T,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<(u64, T)>> {
+        self.sender.send_timeout((seq, msg), timeout)
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Messages buffered by the receiver are not in the channel anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0, 'a').unwrap();
+    /// assert!(!s.is_empty());
+    /// # drop(r);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// Messages buffered by the receiver are not in the channel anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// s.send(1, 'b').unwrap();
+    /// s.send(0, 'a').unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// # drop(r);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_ordered, unbounded_ordered};
+    ///
+    /// let (s, _) = bounded_ordered::<i32>(5);
+    /// assert_eq!(s.capacity(), Some(5));
+    ///
+    /// let (s, _) = unbounded_ordered::<i32>();
+    /// assert_eq!(s.capacity(), None);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+}
+
+impl<T> Clone for OrderedSender<T> {
+    fn clone(&self) -> OrderedSender<T> {
+        OrderedSender {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for OrderedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OrderedSender").finish()
+    }
+}
+
+/// The receiving side of a channel that restores the order of messages.
+///
+/// The receiver buffers messages that arrive before their turn. It cannot be cloned, because a
+/// clone would take messages out of the channel that this receiver might be waiting for.
+///
+/// Once all senders are dropped and the channel is empty, messages that are still buffered can't
+/// be preceded by any more messages, so they are delivered in order even if some sequence numbers
+/// before them are missing.
+///
+/// An ordered receiver can be waited on in [`Select`] with [`Select::recv_ready`]. The operation is
+/// ready once the next message is buffered or the channel holds any message, even one that arrived
+/// before its turn. In that case, `try_recv` buffers it and may report an empty channel.
+///
+/// Receivers are created by the [`bounded_ordered`] and [`unbounded_ordered`] functions.
+///
+/// [`bounded_ordered`]: fn.bounded_ordered.html
+/// [`unbounded_ordered`]: fn.unbounded_ordered.html
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+pub struct OrderedReceiver<T> {
+    /// The underlying receiver.
+    receiver: Receiver<(u64, T)>,
+
+    /// Messages that arrived before their turn.
+    state: Mutex<State<T>>,
+}
+
+impl<T> OrderedReceiver<T> {
+    /// Attempts to receive the next message in order without blocking.
+    ///
+    /// Returns an [`Empty`] error if the next message hasn't been sent yet, even if later ones
+    /// have.
+    ///
+    /// [`Empty`]: enum.TryRecvError.html#variant.Empty
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_ordered, TryRecvError};
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// s.send(1, "second").unwrap();
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(0, "first").unwrap();
+    /// assert_eq!(r.try_recv(), Ok("first"));
+    /// assert_eq!(r.try_recv(), Ok("second"));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.state.lock();
+        loop {
+            if let Some(msg) = state.pop_next() {
+                return Ok(msg);
+            }
+
+            match self.receiver.try_recv() {
+                Ok(m) => state.push(m),
+                Err(TryRecvError::Empty) => return Err(TryRecvError::Empty),
+                Err(TryRecvError::Disconnected) => {
+                    return state.pop_any().ok_or(TryRecvError::Disconnected);
+                }
+            }
+        }
+    }
+
+    /// Blocks the current thread until the next message in order is received or the channel is
+    /// empty and disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// s.send(1, "second").unwrap();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(0, "first").unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok("first"));
+    /// assert_eq!(r.recv(), Ok("second"));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.state.lock();
+        loop {
+            if let Some(msg) = state.pop_next() {
+                return Ok(msg);
+            }
+
+            match self.receiver.recv() {
+                Ok(m) => state.push(m),
+                Err(RecvError) => return state.pop_any().ok_or(RecvError),
+            }
+        }
+    }
+
+    /// Waits for the next message in order to be received, but only for a limited time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded_ordered, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// s.send(1, "second").unwrap();
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock();
+        loop {
+            if let Some(msg) = state.pop_next() {
+                return Ok(msg);
+            }
+
+            let now = Instant::now();
+            let timeout = if now < deadline {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            };
+
+            match self.receiver.recv_timeout(timeout) {
+                Ok(m) => state.push(m),
+                Err(RecvTimeoutError::Timeout) => return Err(RecvTimeoutError::Timeout),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return state.pop_any().ok_or(RecvTimeoutError::Disconnected);
+                }
+            }
+        }
+    }
+
+    /// A blocking iterator over messages in order.
+    ///
+    /// Each call to `next` blocks waiting for the next message in order. The iterator returns
+    /// `None` once the channel is empty and disconnected and no buffered messages are left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// for &i in &[2, 0, 1] {
+    ///     s.send(i, i).unwrap();
+    /// }
+    /// drop(s);
+    ///
+    /// let v: Vec<_> = r.iter().collect();
+    /// assert_eq!(v, [0, 1, 2]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn iter(&self) -> OrderedIter<T> {
+        OrderedIter { receiver: self }
+    }
+
+    /// Returns the sequence number of the next message to be received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// assert_eq!(r.next_sequence(), 0);
+    ///
+    /// s.send(0, 'a').unwrap();
+    /// r.recv().unwrap();
+    /// assert_eq!(r.next_sequence(), 1);
+    /// ```
+    pub fn next_sequence(&self) -> u64 {
+        self.state.lock().next
+    }
+
+    /// Returns the number of messages buffered because they arrived before their turn.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_ordered, TryRecvError};
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// s.send(2, 'c').unwrap();
+    /// s.send(1, 'b').unwrap();
+    ///
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// assert_eq!(r.buffered(), 2);
+    /// ```
+    pub fn buffered(&self) -> usize {
+        self.state.lock().pending.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Messages buffered by the receiver are not in the channel anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// assert!(r.is_empty());
+    ///
+    /// s.send(0, 'a').unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// Messages buffered by the receiver are not in the channel anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_ordered;
+    ///
+    /// let (s, r) = unbounded_ordered();
+    /// s.send(1, 'b').unwrap();
+    /// s.send(0, 'a').unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ordered;
+    ///
+    /// let (s, r) = bounded_ordered::<i32>(5);
+    /// assert_eq!(r.capacity(), Some(5));
+    /// # drop(s);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        self.receiver.capacity()
+    }
+}
+
+impl<T> SelectHandle for OrderedReceiver<T> {
+    fn try(&self, token: &mut Token) -> bool {
+        self.state.lock().has_next() || Ready::new(&self.receiver).try(token)
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        self.state.lock().has_next() || Ready::new(&self.receiver).retry(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        let blocked = Ready::new(&self.receiver).register(token, oper, cx);
+        blocked && !self.state.lock().has_next()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        Ready::new(&self.receiver).unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        self.state.lock().has_next() || Ready::new(&self.receiver).accept(token, cx)
+    }
+
+    fn state(&self) -> usize {
+        self.receiver.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.state.lock().has_next() || self.receiver.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.receiver.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.receiver.unwatch(oper);
+    }
+}
+
+impl<T> fmt::Debug for OrderedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OrderedReceiver").finish()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> IntoIterator for &'a OrderedReceiver<T> {
+    type Item = T;
+    type IntoIter = OrderedIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A blocking iterator over messages in a channel that restores their order.
+///
+/// Each call to `next` blocks waiting for the next message in order. The iterator returns `None`
+/// once the channel is empty and disconnected and no buffered messages are left.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::unbounded_ordered;
+///
+/// let (s, r) = unbounded_ordered();
+///
+/// thread::spawn(move || {
+///     s.send(1, 'b').unwrap();
+///     s.send(0, 'a').unwrap();
+/// });
+///
+/// let v: Vec<_> = r.iter().collect();
+/// assert_eq!(v, ['a', 'b']);
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct OrderedIter<'a, T: 'a> {
+    /// The receiver.
+    receiver: &'a OrderedReceiver<T>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> Iterator for OrderedIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> fmt::Debug for OrderedIter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OrderedIter").finish()
+    }
+}
@@ -0,0 +1,150 @@
+//! Pluggable blocking primitive.
+
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A blocking primitive used by channel operations to wait for each other.
+///
+/// By default, blocked operations park the current thread with [`thread::park`] and are woken up
+/// with [`Thread::unpark`]. A custom `Parker` can be installed with [`set_parker`] in order to
+/// block in a different way, e.g. by yielding to the scheduler of a green-thread runtime.
+///
+/// Every parker carries a wakeup token, just like threads do. A call to [`unpark`] makes the
+/// token available if it isn't already, and a call to [`park`] consumes the token, blocking until
+/// it becomes available if necessary. Spurious wakeups are permitted, so [`park`] may return
+/// without consuming the token.
+///
+/// [`thread::park`]: https://doc.rust-lang.org/std/thread/fn.park.html
+/// [`Thread::unpark`]: https://doc.rust-lang.org/std/thread/struct.Thread.html#method.unpark
+/// [`set_parker`]: fn.set_parker.html
+/// [`park`]: trait.Parker.html#tymethod.park
+/// [`unpark`]: trait.Parker.html#tymethod.unpark
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, Condvar, Mutex};
+/// use std::time::Instant;
+/// use crossbeam_channel::Parker;
+///
+/// struct CondvarParker {
+///     token: Mutex<bool>,
+///     cvar: Condvar,
+/// }
+///
+/// impl Parker for CondvarParker {
+///     fn park(&self, deadline: Option<Instant>) {
+///         let mut token = self.token.lock().unwrap();
+///         while !*token {
+///             match deadline {
+///                 None => token = self.cvar.wait(token).unwrap(),
+///                 Some(d) => {
+///                     let now = Instant::now();
+///                     if now >= d {
+///                         return;
+///                     }
+///                     token = self.cvar.wait_timeout(token, d - now).unwrap().0;
+///                 }
+///             }
+///         }
+///         *token = false;
+///     }
+///
+///     fn unpark(&self) {
+///         *self.token.lock().unwrap() = true;
+///         self.cvar.notify_one();
+///     }
+/// }
+/// ```
+pub trait Parker: Send + Sync {
+    /// Blocks the current execution context until the wakeup token becomes available or the
+    /// deadline is reached.
+    fn park(&self, deadline: Option<Instant>);
+
+    /// Makes the wakeup token available and wakes up the execution context owning this parker.
+    fn unpark(&self);
+}
+
+/// A function returning the parker of the current execution context.
+type CurrentFn = fn() -> Arc<Parker>;
+
+thread_local! {
+    /// Returns the parker of the current execution context, if a custom one is installed.
+    static CURRENT: Cell<Option<CurrentFn>> = Cell::new(None);
+}
+
+/// Installs a custom blocking primitive for channel operations on the current thread.
+///
+/// The function `current` is called whenever a channel operation on this thread is about to
+/// block, and must return the [`Parker`] belonging to the execution context that is currently
+/// running, e.g. the current green thread. Calling it again from the same execution context must
+/// return a handle to the same parker because parkers are also used to tell execution contexts
+/// apart.
+///
+/// Passing `None` restores the default behavior, which is to park the thread.
+///
+/// [`Parker`]: trait.Parker.html
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Instant;
+/// use crossbeam_channel::{set_parker, unbounded, Parker};
+///
+/// // A parker that simply delegates to thread parking.
+/// struct ThreadParker(thread::Thread);
+///
+/// impl Parker for ThreadParker {
+///     fn park(&self, deadline: Option<Instant>) {
+///         match deadline {
+///             None => thread::park(),
+///             Some(d) => {
+///                 let now = Instant::now();
+///                 if now < d {
+///                     thread::park_timeout(d - now);
+///                 }
+///             }
+///         }
+///     }
+///
+///     fn unpark(&self) {
+///         self.0.unpark();
+///     }
+/// }
+///
+/// thread_local! {
+///     static PARKER: Arc<ThreadParker> = Arc::new(ThreadParker(thread::current()));
+/// }
+///
+/// fn current() -> Arc<Parker> {
+///     PARKER.with(|p| p.clone())
+/// }
+///
+/// let (s, r) = unbounded();
+///
+/// thread::spawn(move || {
+///     set_parker(Some(current));
+///     assert_eq!(r.recv(), Ok(1));
+/// });
+///
+/// s.send(1).unwrap();
+/// ```
+pub fn set_parker(current: Option<fn() -> Arc<Parker>>) {
+    CURRENT.with(|c| c.set(current));
+}
+
+/// Returns the custom parker of the current execution context, if there is one.
+pub fn current() -> Option<Arc<Parker>> {
+    CURRENT.try_with(|c| c.get()).ok().and_then(|f| f).map(|f| f())
+}
+
+/// Returns `true` if a custom parker is installed on the current thread.
+#[inline]
+pub fn is_custom() -> bool {
+    CURRENT
+        .try_with(|c| c.get().is_some())
+        .unwrap_or(false)
+}
@@ -0,0 +1,534 @@
+//! Waiting for readiness of many operations that are registered once.
+
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+use parking_lot::{Condvar, Mutex};
+
+use channel::{Receiver, Sender};
+use context::Context;
+use park::Parker;
+use select::{Operation, SelectHandle};
+use selectable::{Adapter, Selectable};
+
+/// A set of operations that are registered once and then polled for readiness repeatedly.
+///
+/// [`Select`] is rebuilt or at least scanned in full every time it blocks, which gets expensive
+/// with hundreds of channels of which only a few are active at a time. A `Poller` works like
+/// `epoll`: receive, send, and [custom] operations are registered once, each with a token chosen
+/// by the caller, and [`poll`] then returns the tokens of operations that are ready. Channels
+/// notify the poller about the operations that might have become ready, so the cost of a poll
+/// depends on the number of active operations rather than the number of registered ones.
+///
+/// Polling is level-triggered: an operation is reported on every poll for as long as it is
+/// ready, e.g. a receive operation is reported until its channel becomes empty. The poller only
+/// reports readiness, while performing the operations, e.g. with [`try_recv`], is up to the
+/// caller. As with [`Select`], another thread may perform an operation in the meantime, so an
+/// operation reported as ready might not be ready anymore once the caller gets to it.
+///
+/// Operations on channels created by [`after`] and [`tick`] become ready at a deadline without
+/// a notification, so they are checked on every poll.
+///
+/// [`Select`]: struct.Select.html
+/// [custom]: trait.Selectable.html
+/// [`poll`]: struct.Poller.html#method.poll
+/// [`try_recv`]: struct.Receiver.html#method.try_recv
+/// [`after`]: fn.after.html
+/// [`tick`]: fn.tick.html
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{unbounded, Poller};
+///
+/// let channels: Vec<_> = (0..100).map(|_| unbounded()).collect();
+///
+/// let mut poller = Poller::new();
+/// for (token, (_, r)) in channels.iter().enumerate() {
+///     poller.recv(r, token);
+/// }
+///
+/// let s = channels[42].0.clone();
+/// thread::spawn(move || s.send("hello").unwrap());
+///
+/// let mut events = Vec::new();
+/// assert_eq!(poller.poll(&mut events, Some(Duration::from_secs(1))), 1);
+/// assert_eq!(events, [42]);
+/// assert_eq!(channels[42].1.try_recv(), Ok("hello"));
+/// ```
+pub struct Poller<'a> {
+    /// Registered operations, indexed by slot. Vacant slots are `None`.
+    slots: Vec<Option<Box<Slot<'a>>>>,
+
+    /// Vacant slots that can be reused.
+    vacant: Vec<usize>,
+
+    /// Maps tokens to the slots of their operations.
+    tokens: HashMap<usize, usize>,
+
+    /// Slots of operations that were ready in the last poll and need to be checked again.
+    ready: Vec<usize>,
+
+    /// Slots of operations that become ready at a deadline.
+    timed: Vec<usize>,
+
+    /// Slots of operations to check in the current poll, kept to reuse the allocation.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    check: Vec<usize>,
+
+    /// Slots taken from the shared notifications, kept to reuse the allocation.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    notified: Vec<usize>,
+
+    /// Slots of operations the channels have notified about.
+    shared: Arc<Shared>,
+}
+
+/// A registered operation.
+struct Slot<'a> {
+    /// The operation.
+    handle: &'a (SelectHandle + 'a),
+
+    /// The token the operation was registered with.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    token: usize,
+
+    /// The context through which channels notify the poller.
+    cx: Context,
+
+    /// Receives notifications for this operation.
+    notice: Arc<Notice>,
+}
+
+impl<'a> Slot<'a> {
+    /// Returns the identifier the operation is watched with.
+    fn oper(&mut self) -> Operation {
+        Operation::hook(self)
+    }
+
+    /// Starts watching for the operation to become ready.
+    fn watch(&mut self) {
+        self.cx.reset();
+        self.notice.queued.store(false, Ordering::SeqCst);
+        let oper = self.oper();
+        self.handle.watch(oper, &self.cx);
+    }
+
+    /// Stops watching for the operation to become ready.
+    fn unwatch(&mut self) {
+        let oper = self.oper();
+        self.handle.unwatch(oper);
+    }
+}
+
+/// Notifications shared by the poller and the channels.
+struct Shared {
+    /// Slots of operations the channels have notified about since the last poll.
+    notified: Mutex<Vec<usize>>,
+
+    /// Wakes up the poller when a notification arrives.
+    cvar: Condvar,
+}
+
+/// Receives notifications for a single registered operation.
+///
+/// Channels wake up the contexts watching for an operation by unparking them, so a registered
+/// operation gets its own context whose parker queues up the operation instead.
+struct Notice {
+    /// Notifications shared by the poller and the channels.
+    shared: Arc<Shared>,
+
+    /// The slot of the operation.
+    slot: usize,
+
+    /// Equals `true` if the slot is already queued up for the next poll.
+    queued: AtomicBool,
+}
+
+impl Parker for Notice {
+    fn park(&self, _deadline: Option<Instant>) {
+        // The context of a notice is never waited on. Returning right away is a spurious wakeup.
+    }
+
+    fn unpark(&self) {
+        if !self.queued.swap(true, Ordering::SeqCst) {
+            self.shared.notified.lock().push(self.slot);
+            self.shared.cvar.notify_one();
+        }
+    }
+}
+
+impl<'a> Poller<'a> {
+    /// Creates an empty poller.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Poller;
+    ///
+    /// let poller = Poller::new();
+    /// assert!(poller.is_empty());
+    /// ```
+    pub fn new() -> Poller<'a> {
+        Poller {
+            slots: Vec::new(),
+            vacant: Vec::new(),
+            tokens: HashMap::new(),
+            ready: Vec::new(),
+            timed: Vec::new(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            check: Vec::new(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            notified: Vec::new(),
+            shared: Arc::new(Shared {
+                notified: Mutex::new(Vec::new()),
+                cvar: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Registers a receive operation with a token.
+    ///
+    /// The operation is ready when a message can be received from the channel or the channel is
+    /// disconnected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the token is already registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Poller};
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut poller = Poller::new();
+    /// poller.recv(&r, 7);
+    ///
+    /// s.send(1).unwrap();
+    ///
+    /// let mut events = Vec::new();
+    /// poller.poll(&mut events, None);
+    /// assert_eq!(events, [7]);
+    /// ```
+    pub fn recv<T>(&mut self, r: &'a Receiver<T>, token: usize) {
+        self.register(r, token);
+    }
+
+    /// Registers a send operation with a token.
+    ///
+    /// The operation is ready when a message can be sent into the channel or the channel is
+    /// disconnected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the token is already registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, Poller};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let mut poller = Poller::new();
+    /// poller.send(&s, 3);
+    ///
+    /// let mut events = Vec::new();
+    /// poller.poll(&mut events, None);
+    /// assert_eq!(events, [3]);
+    ///
+    /// // The channel is full, so the send operation is not ready anymore.
+    /// s.send(1).unwrap();
+    /// assert_eq!(poller.poll(&mut events, Some(Duration::from_millis(100))), 0);
+    /// # drop(r);
+    /// ```
+    pub fn send<T>(&mut self, s: &'a Sender<T>, token: usize) {
+        self.register(s, token);
+    }
+
+    /// Registers an operation on a custom [`Selectable`] primitive with a token.
+    ///
+    /// The operation is ready for as long as [`Selectable::is_ready`] returns `true`.
+    ///
+    /// [`Selectable`]: trait.Selectable.html
+    /// [`Selectable::is_ready`]: trait.Selectable.html#tymethod.is_ready
+    ///
+    /// # Panics
+    ///
+    /// Panics if the token is already registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Poller, Trigger};
+    ///
+    /// let trigger = Trigger::new();
+    /// let mut poller = Poller::new();
+    /// poller.custom(&trigger, 0);
+    ///
+    /// trigger.set();
+    ///
+    /// let mut events = Vec::new();
+    /// poller.poll(&mut events, None);
+    /// assert_eq!(events, [0]);
+    /// ```
+    pub fn custom<S: Selectable>(&mut self, s: &'a S, token: usize) {
+        self.register(Adapter::new(s), token);
+    }
+
+    /// Registers an operation with a token and starts watching it.
+    fn register(&mut self, handle: &'a SelectHandle, token: usize) {
+        assert!(
+            !self.tokens.contains_key(&token),
+            "token {} is already registered",
+            token
+        );
+
+        let index = self.vacant.pop().unwrap_or_else(|| {
+            self.slots.push(None);
+            self.slots.len() - 1
+        });
+        let notice = Arc::new(Notice {
+            shared: self.shared.clone(),
+            slot: index,
+            queued: AtomicBool::new(false),
+        });
+        let mut slot = Box::new(Slot {
+            handle,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            token,
+            cx: Context::with_parker(notice.clone()),
+            notice,
+        });
+
+        slot.watch();
+        self.slots[index] = Some(slot);
+        self.tokens.insert(token, index);
+
+        // Check the operation in the next poll.
+        self.ready.push(index);
+        if handle.deadline().is_some() {
+            self.timed.push(index);
+        }
+    }
+
+    /// Deregisters the operation with the given token.
+    ///
+    /// Returns `true` if an operation with the token was registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Poller};
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut poller = Poller::new();
+    /// poller.recv(&r, 1);
+    ///
+    /// assert!(poller.deregister(1));
+    /// assert!(!poller.deregister(1));
+    ///
+    /// s.send(()).unwrap();
+    /// let mut events = Vec::new();
+    /// assert_eq!(poller.poll(&mut events, Some(Duration::from_millis(100))), 0);
+    /// ```
+    pub fn deregister(&mut self, token: usize) -> bool {
+        let index = match self.tokens.remove(&token) {
+            None => return false,
+            Some(index) => index,
+        };
+
+        if let Some(mut slot) = self.slots[index].take() {
+            slot.unwatch();
+        }
+        self.timed.retain(|&i| i != index);
+        self.vacant.push(index);
+        true
+    }
+
+    /// Returns `true` if an operation with the given token is registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{never, Poller};
+    ///
+    /// let r = never::<i32>();
+    /// let mut poller = Poller::new();
+    /// poller.recv(&r, 5);
+    ///
+    /// assert!(poller.contains(5));
+    /// assert!(!poller.contains(6));
+    /// ```
+    pub fn contains(&self, token: usize) -> bool {
+        self.tokens.contains_key(&token)
+    }
+
+    /// Returns the number of registered operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Poller};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let mut poller = Poller::new();
+    /// poller.recv(&r, 0);
+    /// poller.send(&s, 1);
+    ///
+    /// assert_eq!(poller.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns `true` if no operations are registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Poller};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let mut poller = Poller::new();
+    /// assert!(poller.is_empty());
+    ///
+    /// poller.recv(&r, 0);
+    /// assert!(!poller.is_empty());
+    /// # drop(s);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Waits until some of the registered operations are ready and collects their tokens.
+    ///
+    /// The tokens of ready operations replace the contents of `events`, and their number is
+    /// returned. If no operation becomes ready within `timeout`, zero is returned. A timeout of
+    /// `None` waits indefinitely, while a zero timeout only checks which operations are ready
+    /// right now.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Poller};
+    ///
+    /// let (s1, r1) = unbounded::<&str>();
+    /// let (s2, r2) = unbounded();
+    ///
+    /// let mut poller = Poller::new();
+    /// poller.recv(&r1, 1);
+    /// poller.recv(&r2, 2);
+    ///
+    /// let mut events = Vec::new();
+    /// assert_eq!(poller.poll(&mut events, Some(Duration::from_millis(0))), 0);
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s2.send("two").unwrap();
+    /// });
+    ///
+    /// assert_eq!(poller.poll(&mut events, None), 1);
+    /// assert_eq!(events, [2]);
+    ///
+    /// // Ready operations are reported until they are performed.
+    /// assert_eq!(poller.poll(&mut events, None), 1);
+    /// assert_eq!(r2.try_recv(), Ok("two"));
+    /// # drop(s1);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn poll(&mut self, events: &mut Vec<usize>, timeout: Option<Duration>) -> usize {
+        events.clear();
+        let deadline = timeout.map(|t| Instant::now() + t);
+
+        loop {
+            // Operations that were ready, operations that were notified about, and timers.
+            mem::swap(&mut self.check, &mut self.ready);
+            mem::swap(&mut self.notified, &mut *self.shared.notified.lock());
+            for &index in &self.notified {
+                if let Some(slot) = &mut self.slots[index] {
+                    // The notification removed the watching operation from the channel.
+                    slot.watch();
+                }
+            }
+            self.check.extend(self.notified.drain(..));
+            self.check.extend(&self.timed);
+            self.check.sort_unstable();
+            self.check.dedup();
+
+            for index in self.check.drain(..) {
+                if let Some(slot) = &self.slots[index] {
+                    if slot.handle.is_ready() {
+                        events.push(slot.token);
+                        self.ready.push(index);
+                    }
+                }
+            }
+
+            if !events.is_empty() {
+                return events.len();
+            }
+
+            let now = Instant::now();
+            if let Some(d) = deadline {
+                if now >= d {
+                    return 0;
+                }
+            }
+
+            // Wait for a notification, the overall deadline, or the deadline of a timer.
+            let mut wake_at = deadline;
+            for &index in &self.timed {
+                if let Some(slot) = &self.slots[index] {
+                    if let Some(d) = slot.handle.deadline() {
+                        wake_at = Some(wake_at.map_or(d, |w| w.min(d)));
+                    }
+                }
+            }
+
+            let mut notified = self.shared.notified.lock();
+            if notified.is_empty() {
+                match wake_at {
+                    None => self.shared.cvar.wait(&mut notified),
+                    Some(w) if w > now => {
+                        self.shared.cvar.wait_until(&mut notified, w);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Drop for Poller<'a> {
+    fn drop(&mut self) {
+        for slot in self.slots.iter_mut().filter_map(|s| s.as_mut()) {
+            slot.unwatch();
+        }
+    }
+}
+
+impl<'a> Default for Poller<'a> {
+    fn default() -> Poller<'a> {
+        Poller::new()
+    }
+}
+
+impl<'a> fmt::Debug for Poller<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Poller").field("len", &self.len()).finish()
+    }
+}
@@ -0,0 +1,329 @@
+//! Memory reclamation that keeps pointers as pointers.
+//!
+//! This is a replacement for the parts of `crossbeam-epoch` used by the list flavor, for running
+//! under Miri with strict provenance. `crossbeam-epoch` packs tags into the low bits of pointers
+//! and stores them as integers, which loses their provenance. Here, pointers are always stored as
+//! pointers.
+//!
+//! Reclamation is much simpler and slower than with epochs: a global count of pinned guards is
+//! kept, and deferred functions run as soon as no guard is pinned anymore. Since a deferred
+//! function runs only after every guard that was pinned at the time it was deferred has been
+//! dropped, no thread can still be accessing the memory it frees.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use parking_lot::Mutex;
+
+/// A function whose execution is deferred until no guard is pinned.
+struct Deferred {
+    /// Calls the function and deallocates it.
+    call: unsafe fn(*mut u8),
+
+    /// The boxed function.
+    data: *mut u8,
+}
+
+// Deferred functions are only called once, by whichever thread unpins the last guard.
+unsafe impl Send for Deferred {}
+
+impl Deferred {
+    /// Boxes a function for deferred execution.
+    fn new<F: FnOnce()>(f: F) -> Deferred {
+        unsafe fn call<F: FnOnce()>(data: *mut u8) {
+            let f = *Box::from_raw(data as *mut F);
+            f();
+        }
+
+        Deferred {
+            call: call::<F>,
+            data: Box::into_raw(Box::new(f)) as *mut u8,
+        }
+    }
+
+    /// Calls the function.
+    unsafe fn run(self) {
+        (self.call)(self.data)
+    }
+}
+
+/// Global state shared by all guards.
+struct Global {
+    /// The number of pinned guards.
+    pinned: usize,
+
+    /// Functions waiting for all pinned guards to be dropped.
+    garbage: Vec<Deferred>,
+}
+
+lazy_static! {
+    /// The global state.
+    static ref GLOBAL: Mutex<Global> = Mutex::new(Global {
+        pinned: 0,
+        garbage: Vec::new(),
+    });
+}
+
+/// Returns the global state.
+fn global() -> &'static Mutex<Global> {
+    &GLOBAL
+}
+
+/// A guard that keeps the current thread pinned.
+#[derive(Debug)]
+pub struct Guard {
+    /// Equals `false` for the unprotected guard, which doesn't count as pinned.
+    protected: bool,
+}
+
+/// The guard returned by `unprotected`.
+static UNPROTECTED: Guard = Guard { protected: false };
+
+/// Pins the current thread.
+pub fn pin() -> Guard {
+    global().lock().pinned += 1;
+    Guard { protected: true }
+}
+
+/// Returns a guard that doesn't pin anything.
+///
+/// Functions deferred with it are called right away.
+pub unsafe fn unprotected() -> &'static Guard {
+    &UNPROTECTED
+}
+
+impl Guard {
+    /// Defers the execution of `f` until no guard is pinned.
+    pub unsafe fn defer_unchecked<F, R>(&self, f: F)
+    where
+        F: FnOnce() -> R,
+    {
+        if self.protected {
+            global().lock().garbage.push(Deferred::new(move || {
+                f();
+            }));
+        } else {
+            f();
+        }
+    }
+
+    /// Defers the destruction of the object `ptr` points to until no guard is pinned.
+    pub unsafe fn defer_destroy<T>(&self, ptr: Shared<T>) {
+        let raw = ptr.as_raw() as *mut T;
+        self.defer_unchecked(move || drop(Box::from_raw(raw)));
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if !self.protected {
+            return;
+        }
+
+        // Take the garbage while still holding the lock, so that no guard can get pinned in the
+        // meantime and start accessing memory the garbage is about to free.
+        let mut garbage = Vec::new();
+        {
+            let mut global = global().lock();
+            global.pinned -= 1;
+            if global.pinned == 0 {
+                mem::swap(&mut garbage, &mut global.garbage);
+            }
+        }
+
+        for deferred in garbage {
+            unsafe { deferred.run() }
+        }
+    }
+}
+
+/// A pointer that can be installed into an `Atomic`.
+pub trait Pointer<T> {
+    /// Converts the pointer into a raw pointer.
+    fn into_raw(self) -> *mut T;
+
+    /// Converts a raw pointer back.
+    unsafe fn from_raw(raw: *mut T) -> Self;
+}
+
+/// An atomic pointer.
+pub struct Atomic<T> {
+    ptr: AtomicPtr<T>,
+}
+
+impl<T> Atomic<T> {
+    /// Returns a new null atomic pointer.
+    pub fn null() -> Atomic<T> {
+        Atomic {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Loads the pointer.
+    pub fn load<'g>(&self, ord: Ordering, _: &'g Guard) -> Shared<'g, T> {
+        Shared::from_raw(self.ptr.load(ord))
+    }
+
+    /// Stores a pointer.
+    pub fn store<P: Pointer<T>>(&self, new: P, ord: Ordering) {
+        self.ptr.store(new.into_raw(), ord);
+    }
+
+    /// Stores `new` if the current pointer equals `current`.
+    ///
+    /// On success, the new pointer is returned. On failure, the current pointer is returned
+    /// together with `new`.
+    pub fn compare_and_set<'g, P: Pointer<T>>(
+        &self,
+        current: Shared<T>,
+        new: P,
+        ord: Ordering,
+        _: &'g Guard,
+    ) -> Result<Shared<'g, T>, CompareAndSetError<'g, T, P>> {
+        let new = new.into_raw();
+        let failure = match ord {
+            Ordering::Release | Ordering::Relaxed => Ordering::Relaxed,
+            Ordering::SeqCst => Ordering::SeqCst,
+            _ => Ordering::Acquire,
+        };
+
+        match self
+            .ptr
+            .compare_exchange(current.raw as *mut T, new, ord, failure)
+        {
+            Ok(_) => Ok(Shared::from_raw(new)),
+            Err(raw) => Err(CompareAndSetError {
+                current: Shared::from_raw(raw),
+                new: unsafe { P::from_raw(new) },
+            }),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Atomic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Atomic").finish()
+    }
+}
+
+/// The error returned by a failed `compare_and_set`.
+pub struct CompareAndSetError<'g, T: 'g, P: Pointer<T>> {
+    /// The pointer that was found instead of the expected one.
+    pub current: Shared<'g, T>,
+
+    /// The pointer that was to be stored.
+    pub new: P,
+}
+
+/// An owned heap-allocated object.
+pub struct Owned<T> {
+    inner: Box<T>,
+}
+
+impl<T> Owned<T> {
+    /// Allocates `value` on the heap.
+    pub fn new(value: T) -> Owned<T> {
+        Owned {
+            inner: Box::new(value),
+        }
+    }
+
+    /// Takes ownership of the object `raw` points to.
+    pub unsafe fn from_raw(raw: *mut T) -> Owned<T> {
+        Owned {
+            inner: Box::from_raw(raw),
+        }
+    }
+
+    /// Converts the owned object into a shared pointer.
+    pub fn into_shared<'g>(self, _: &'g Guard) -> Shared<'g, T> {
+        Shared::from_raw(Box::into_raw(self.inner))
+    }
+}
+
+impl<T> Pointer<T> for Owned<T> {
+    fn into_raw(self) -> *mut T {
+        Box::into_raw(self.inner)
+    }
+
+    unsafe fn from_raw(raw: *mut T) -> Owned<T> {
+        Owned::from_raw(raw)
+    }
+}
+
+impl<T> Deref for Owned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Owned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+/// A pointer to an object that is valid while the guard `'g` is pinned.
+pub struct Shared<'g, T: 'g> {
+    raw: *const T,
+    _marker: PhantomData<(&'g (), *const T)>,
+}
+
+impl<'g, T> Shared<'g, T> {
+    /// Returns a null pointer.
+    pub fn null() -> Shared<'g, T> {
+        Shared::from_raw(ptr::null_mut())
+    }
+
+    /// Wraps a raw pointer.
+    fn from_raw(raw: *mut T) -> Shared<'g, T> {
+        Shared {
+            raw,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the pointer is null.
+    pub fn is_null(&self) -> bool {
+        self.raw.is_null()
+    }
+
+    /// Returns the raw pointer.
+    pub fn as_raw(&self) -> *const T {
+        self.raw
+    }
+
+    /// Dereferences the pointer.
+    pub unsafe fn deref(&self) -> &'g T {
+        &*self.raw
+    }
+
+    /// Takes ownership of the object the pointer points to.
+    pub unsafe fn into_owned(self) -> Owned<T> {
+        Owned::from_raw(self.raw as *mut T)
+    }
+}
+
+impl<'g, T> Pointer<T> for Shared<'g, T> {
+    fn into_raw(self) -> *mut T {
+        self.raw as *mut T
+    }
+
+    unsafe fn from_raw(raw: *mut T) -> Shared<'g, T> {
+        Shared::from_raw(raw)
+    }
+}
+
+impl<'g, T> Clone for Shared<'g, T> {
+    fn clone(&self) -> Shared<'g, T> {
+        *self
+    }
+}
+
+impl<'g, T> Copy for Shared<'g, T> {}
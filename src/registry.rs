@@ -0,0 +1,203 @@
+//! A process-wide registry of live channels for diagnostics.
+//!
+//! Every channel created by [`bounded`], [`unbounded`], or a [`Builder`] is registered here for as
+//! long as it is alive. A [`snapshot`] describes the current state of each channel, which comes in
+//! handy when a service gets stuck: an admin thread or a panic hook can dump the snapshot and
+//! show which channels are full, which ones have nobody on the other side, and where threads are
+//! blocked.
+//!
+//! Channels created by [`after`], [`tick`], and [`never`] have no shared state and are not
+//! registered. Channels can be given a name with [`Builder::name`] to make them easier to tell
+//! apart.
+//!
+//! Registration takes a global lock whenever a channel is created or destroyed, but channel
+//! operations themselves are not affected.
+//!
+//! This module is available only if the `registry` feature is enabled.
+//!
+//! [`bounded`]: ../fn.bounded.html
+//! [`unbounded`]: ../fn.unbounded.html
+//! [`Builder`]: ../struct.Builder.html
+//! [`Builder::name`]: ../struct.Builder.html#method.name
+//! [`after`]: ../fn.after.html
+//! [`tick`]: ../fn.tick.html
+//! [`never`]: ../fn.never.html
+//! [`snapshot`]: fn.snapshot.html
+//!
+//! # Examples
+//!
+//! ```
+//! use crossbeam_channel::{registry, Builder};
+//!
+//! let (s, r) = Builder::new().name("jobs").bounded(10);
+//! s.send(1).unwrap();
+//!
+//! for info in registry::snapshot() {
+//!     println!("{}", info);
+//! }
+//!
+//! let info = registry::snapshot()
+//!     .into_iter()
+//!     .find(|info| info.name.as_ref().map(|n| n.as_str()) == Some("jobs"))
+//!     .unwrap();
+//! assert_eq!(info.len, 1);
+//! assert_eq!(info.capacity, Some(10));
+//! # drop(r);
+//! ```
+
+use std::fmt;
+
+use parking_lot::Mutex;
+
+/// The kind of a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flavor {
+    /// A bounded channel based on a preallocated array.
+    Array,
+
+    /// An unbounded channel implemented as a linked list.
+    List,
+
+    /// A bounded channel with several priority lanes.
+    Lanes,
+
+    /// A zero-capacity channel.
+    Zero,
+}
+
+/// The state of a channel at the moment a snapshot was taken.
+///
+/// The fields are read one after another while the channel is in use, so they are not guaranteed
+/// to be consistent with each other.
+#[derive(Clone, Debug)]
+pub struct ChannelInfo {
+    /// The name given to the channel with [`Builder::name`].
+    ///
+    /// [`Builder::name`]: ../struct.Builder.html#method.name
+    pub name: Option<String>,
+
+    /// The kind of the channel.
+    pub flavor: Flavor,
+
+    /// The capacity of the channel, or `None` if it is unbounded.
+    pub capacity: Option<usize>,
+
+    /// The number of messages in the channel.
+    pub len: usize,
+
+    /// The number of live senders.
+    pub senders: usize,
+
+    /// The number of live receivers.
+    pub receivers: usize,
+
+    /// The number of send operations blocked on the channel.
+    pub waiting_senders: usize,
+
+    /// The number of receive operations blocked on the channel.
+    pub waiting_receivers: usize,
+
+    /// Equals `true` if the channel is disconnected.
+    pub is_disconnected: bool,
+}
+
+impl fmt::Display for ChannelInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(ref name) => write!(f, "{:?}", name)?,
+            None => write!(f, "<unnamed>")?,
+        }
+        write!(f, " {:?}", self.flavor)?;
+        match self.capacity {
+            Some(cap) => write!(f, " len={}/{}", self.len, cap)?,
+            None => write!(f, " len={}", self.len)?,
+        }
+        write!(
+            f,
+            " senders={} (waiting {}) receivers={} (waiting {})",
+            self.senders, self.waiting_senders, self.receivers, self.waiting_receivers,
+        )?;
+        if self.is_disconnected {
+            write!(f, " disconnected")?;
+        }
+        Ok(())
+    }
+}
+
+/// A channel that can describe its state.
+pub(crate) trait Inspect {
+    /// Returns the current state of the channel.
+    fn info(&self) -> ChannelInfo;
+}
+
+/// A registered channel.
+struct Entry {
+    /// The address of the channel.
+    chan: *const u8,
+
+    /// Describes the channel at `chan`.
+    info: unsafe fn(*const u8) -> ChannelInfo,
+}
+
+// A channel unregisters itself before it is destroyed, and it is only inspected while the registry
+// is locked, so the pointer is valid whenever it is used.
+unsafe impl Send for Entry {}
+
+lazy_static! {
+    /// The registered channels, in the order they were created.
+    static ref REGISTRY: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+}
+
+/// Registers a channel.
+///
+/// The channel must call `unregister` before it is destroyed.
+pub(crate) fn register<C: Inspect>(chan: &C) {
+    unsafe fn info<C: Inspect>(chan: *const u8) -> ChannelInfo {
+        (*(chan as *const C)).info()
+    }
+
+    REGISTRY.lock().push(Entry {
+        chan: chan as *const C as *const u8,
+        info: info::<C>,
+    });
+}
+
+/// Unregisters a channel.
+pub(crate) fn unregister<C: Inspect>(chan: &C) {
+    let addr = chan as *const C as *const u8;
+    let mut entries = REGISTRY.lock();
+    if let Some(pos) = entries.iter().position(|e| e.chan == addr) {
+        entries.remove(pos);
+    }
+}
+
+/// Takes a snapshot of all live channels.
+///
+/// Channels are listed in the order they were created.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{registry, Builder};
+///
+/// let (s, r) = Builder::<i32>::new().name("events").unbounded();
+/// let named = |name: &str| {
+///     registry::snapshot()
+///         .into_iter()
+///         .filter(|info| info.name.as_ref().map(|n| n.as_str()) == Some(name))
+///         .count()
+/// };
+///
+/// assert_eq!(named("events"), 1);
+///
+/// drop(s);
+/// drop(r);
+/// assert_eq!(named("events"), 0);
+/// ```
+pub fn snapshot() -> Vec<ChannelInfo> {
+    REGISTRY
+        .lock()
+        .iter()
+        .map(|e| unsafe { (e.info)(e.chan) })
+        .collect()
+}
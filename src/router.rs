@@ -0,0 +1,274 @@
+//! Topic-based routing of messages.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use channel::{self, unbounded, Receiver, Sender};
+use err::{SendError, TrySendError};
+
+/// Routes messages to subscribers by topic.
+///
+/// Producers send messages to a topic with [`send`], and every subscriber of that topic receives
+/// its own clone of the message. Consumers subscribe to a topic with [`subscribe`], which returns
+/// an ordinary [`Receiver`] that can be used with all receiving methods, [`select!`], and
+/// [`Select`].
+///
+/// Subscriptions can be added and removed at any time. Dropping a subscriber's receiver
+/// unsubscribes it, and [`close`] unsubscribes all subscribers of a topic at once.
+///
+/// Sending takes a shared lock on the routing table just long enough to look up the subscribers
+/// of the topic, and delivers the message after releasing it. Subscribing, closing topics, and
+/// cleaning up after dropped receivers take an exclusive lock, but they copy the subscriber list of
+/// a topic instead of modifying it in place, so they never wait for messages being delivered.
+///
+/// Subscribers are backed by unbounded channels, which means [`send`] never blocks.
+///
+/// Cloning a router creates a new handle to the same routing table.
+///
+/// [`send`]: struct.Router.html#method.send
+/// [`subscribe`]: struct.Router.html#method.subscribe
+/// [`close`]: struct.Router.html#method.close
+/// [`Receiver`]: struct.Receiver.html
+/// [`select!`]: macro.select.html
+/// [`Select`]: struct.Select.html
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::Router;
+///
+/// let router = Router::new();
+/// let logs = router.subscribe("logs");
+/// let metrics = router.subscribe("metrics");
+///
+/// router.send("metrics", 1).unwrap();
+///
+/// select! {
+///     recv(logs) -> _ => panic!(),
+///     recv(metrics) -> msg => assert_eq!(msg, Ok(1)),
+/// }
+/// # }
+/// ```
+pub struct Router<K, T> {
+    /// Subscribers of each topic.
+    topics: Arc<RwLock<HashMap<K, Subscribers<T>>>>,
+}
+
+/// The subscribers of a topic.
+///
+/// The list is shared with send operations in progress and copied whenever it is modified.
+type Subscribers<T> = Arc<Vec<Sender<T>>>;
+
+impl<K: Eq + Hash, T: Clone> Router<K, T> {
+    /// Creates a router without any subscribers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Router;
+    ///
+    /// let router = Router::<&str, i32>::new();
+    /// assert_eq!(router.subscribers("news"), 0);
+    /// ```
+    pub fn new() -> Router<K, T> {
+        Router {
+            topics: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Subscribes to a topic.
+    ///
+    /// The returned receiver gets every message sent to the topic from now on. Dropping it
+    /// unsubscribes from the topic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Router;
+    ///
+    /// let router = Router::new();
+    /// let r1 = router.subscribe("news");
+    /// let r2 = router.subscribe("news");
+    ///
+    /// router.send("news", "hello").unwrap();
+    /// assert_eq!(r1.recv(), Ok("hello"));
+    /// assert_eq!(r2.recv(), Ok("hello"));
+    /// ```
+    pub fn subscribe(&self, topic: K) -> Receiver<T> {
+        let (s, r) = unbounded();
+        let mut topics = self.topics.write();
+        if let Some(senders) = topics.get_mut(&topic) {
+            Arc::make_mut(senders).push(s);
+            return r;
+        }
+        topics.insert(topic, Arc::new(vec![s]));
+        r
+    }
+
+    /// Sends a message to all subscribers of a topic.
+    ///
+    /// Each subscriber receives a clone of the message, except for the last one, which receives
+    /// the message itself. On success, the number of subscribers the message was delivered to is
+    /// returned. If the message couldn't be delivered to anyone because the topic has no
+    /// subscribers or all of them are gone, it is returned back inside the error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Router, SendError};
+    ///
+    /// let router = Router::new();
+    /// assert_eq!(router.send("news", 1), Err(SendError(1)));
+    ///
+    /// let r = router.subscribe("news");
+    /// assert_eq!(router.send("news", 2), Ok(1));
+    ///
+    /// drop(r);
+    /// assert_eq!(router.send("news", 3), Err(SendError(3)));
+    /// ```
+    pub fn send<Q>(&self, topic: &Q, msg: T) -> Result<usize, SendError<T>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        // Deliver the message without holding the lock.
+        let senders = match self.topics.read().get(topic) {
+            None => return Err(SendError(msg)),
+            Some(senders) => senders.clone(),
+        };
+        let (last, rest) = match senders.split_last() {
+            None => return Err(SendError(msg)),
+            Some(split) => split,
+        };
+
+        let mut delivered = 0;
+        let mut disconnected = false;
+        let mut deliver = |s: &Sender<T>, msg: T| match s.try_send(msg) {
+            Ok(()) => {
+                delivered += 1;
+                None
+            }
+            Err(TrySendError::Disconnected(msg)) => {
+                disconnected = true;
+                Some(msg)
+            }
+            Err(TrySendError::Full(msg)) => Some(msg),
+        };
+
+        for s in rest {
+            deliver(s, msg.clone());
+        }
+        let undelivered = deliver(last, msg);
+
+        if disconnected {
+            self.prune(topic);
+        }
+
+        match undelivered {
+            Some(msg) if delivered == 0 => Err(SendError(msg)),
+            _ => Ok(delivered),
+        }
+    }
+
+    /// Unsubscribes all subscribers of a topic.
+    ///
+    /// The receivers of all subscribers become disconnected, but messages already sent to them
+    /// can still be received. Returns `true` if the topic had any subscribers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{RecvError, Router};
+    ///
+    /// let router = Router::new();
+    /// let r = router.subscribe("news");
+    /// router.send("news", 1).unwrap();
+    ///
+    /// assert!(router.close("news"));
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn close<Q>(&self, topic: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        self.topics.write().remove(topic).is_some()
+    }
+
+    /// Returns the number of subscribers of a topic.
+    ///
+    /// Subscribers whose receivers were dropped might still be counted until the next message is
+    /// sent to the topic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Router;
+    ///
+    /// let router = Router::new();
+    /// let r = router.subscribe("news");
+    /// assert_eq!(router.subscribers("news"), 1);
+    ///
+    /// drop(r);
+    /// let _ = router.send("news", ());
+    /// assert_eq!(router.subscribers("news"), 0);
+    /// ```
+    pub fn subscribers<Q>(&self, topic: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        match self.topics.read().get(topic) {
+            None => 0,
+            Some(senders) => senders.len(),
+        }
+    }
+
+    /// Removes subscribers whose receivers were dropped.
+    fn prune<Q>(&self, topic: &Q)
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + Hash,
+    {
+        let mut topics = self.topics.write();
+        let empty = match topics.get_mut(topic) {
+            None => false,
+            Some(senders) => {
+                Arc::make_mut(senders).retain(|s| !channel::is_disconnected(s));
+                senders.is_empty()
+            }
+        };
+        if empty {
+            topics.remove(topic);
+        }
+    }
+}
+
+impl<K: Eq + Hash, T: Clone> Default for Router<K, T> {
+    fn default() -> Router<K, T> {
+        Router::new()
+    }
+}
+
+impl<K, T> Clone for Router<K, T> {
+    fn clone(&self) -> Router<K, T> {
+        Router {
+            topics: self.topics.clone(),
+        }
+    }
+}
+
+impl<K, T> fmt::Debug for Router<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Router { .. }")
+    }
+}
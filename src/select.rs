@@ -1,34 +1,54 @@
 //! Interface to the select mechanism.
 
+use std::any::Any;
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
+use std::num::Wrapping;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
-use channel::{self, Receiver, Sender};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use cancel::CancelToken;
+use channel::{self, Receiver, RecvGuard, Sender};
 use context::Context;
-use err::{RecvError, SelectTimeoutError, SendError, TrySelectError};
-use smallvec::SmallVec;
+use err::{RecvError, SendError, TrySelectError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{Elapsed, SelectTimeoutError};
+use smallvec::{Array, SmallVec};
+use stash;
+use timeout;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use timeout::TimeoutReceiver;
+use trigger::Trigger;
 use utils;
 
 use flavors;
+use selectable::{Adapter, Selectable};
 
 /// Temporary data that gets initialized during select or a blocking operation, and is consumed by
 /// `read` or `write`.
 ///
 /// Each field contains data associated with a specific channel flavor.
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct Token {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub after: flavors::after::AfterToken,
     pub array: flavors::array::ArrayToken,
+    pub lanes: flavors::lanes::LanesToken,
     pub list: flavors::list::ListToken,
     pub never: flavors::never::NeverToken,
+    pub stash: stash::StashToken,
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub tick: flavors::tick::TickToken,
+    pub timeout: timeout::TimeoutToken,
     pub zero: flavors::zero::ZeroToken,
 }
 
 /// Identifier associated with an operation by a specific thread on a specific channel.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Operation(usize);
 
 impl Operation {
@@ -48,7 +68,7 @@ impl Operation {
 }
 
 /// Current state of a select or a blocking operation.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Selected {
     /// Still waiting for an operation.
     Waiting,
@@ -120,6 +140,21 @@ pub trait SelectHandle {
     /// For example, by calling `state()`, the receiving side can check how much activity the
     /// sending side has had and viceversa.
     fn state(&self) -> usize;
+
+    /// Returns `true` if the operation would not block, either because it would succeed or
+    /// because the channel is disconnected.
+    ///
+    /// Unlike `try`, this doesn't perform the operation.
+    fn is_ready(&self) -> bool;
+
+    /// Registers the current thread to be woken up when the operation might become ready.
+    ///
+    /// The operation is not performed by the other side, the thread is selected and woken up only
+    /// so that it can check `is_ready` again.
+    fn watch(&self, oper: Operation, cx: &Context);
+
+    /// Unregisters an operation previously watched by the current thread.
+    fn unwatch(&self, oper: Operation);
 }
 
 impl<'a, T: SelectHandle> SelectHandle for &'a T {
@@ -150,18 +185,225 @@ impl<'a, T: SelectHandle> SelectHandle for &'a T {
     fn state(&self) -> usize {
         (**self).state()
     }
+
+    fn is_ready(&self) -> bool {
+        (**self).is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        (**self).watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        (**self).unwatch(oper);
+    }
+}
+
+/// Adapts a handle so that its operation is only waited on, but never performed.
+///
+/// Selecting the adapted operation just reports that the operation would not block.
+#[repr(transparent)]
+pub struct Ready<H>(H);
+
+impl<H: SelectHandle> Ready<H> {
+    /// Wraps a reference to a handle.
+    #[inline]
+    pub fn new(handle: &H) -> &Ready<H> {
+        // The adapter has the same layout as the wrapped handle.
+        unsafe { &*(handle as *const H as *const Ready<H>) }
+    }
+}
+
+impl<H: SelectHandle> SelectHandle for Ready<H> {
+    fn try(&self, _token: &mut Token) -> bool {
+        self.0.is_ready()
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        self.0.is_ready()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.0.deadline()
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.0.watch(oper, cx);
+        !self.0.is_ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.unwatch(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        self.0.is_ready()
+    }
+
+    fn state(&self) -> usize {
+        self.0.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.0.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.unwatch(oper);
+    }
+}
+
+/// Adapts a receiver so that its operation is ready only once the channel is disconnected and
+/// empty.
+///
+/// Selecting the adapted operation receives nothing.
+#[repr(transparent)]
+pub struct Closed<T>(Receiver<T>);
+
+impl<T> Closed<T> {
+    /// Wraps a reference to a receiver.
+    #[inline]
+    pub fn new(r: &Receiver<T>) -> &Closed<T> {
+        // The adapter has the same layout as the wrapped receiver.
+        unsafe { &*(r as *const Receiver<T> as *const Closed<T>) }
+    }
+}
+
+impl<T> SelectHandle for Closed<T> {
+    fn try(&self, _token: &mut Token) -> bool {
+        channel::is_closed(&self.0)
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        channel::is_closed(&self.0)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.0.watch(oper, cx);
+        !channel::is_closed(&self.0)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.unwatch(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        channel::is_closed(&self.0)
+    }
+
+    fn state(&self) -> usize {
+        self.0.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        channel::is_closed(&self.0)
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.unwatch(oper);
+    }
+}
+
+/// Adapts a sender so that its operation is ready only once the channel is disconnected.
+///
+/// Selecting the adapted operation sends nothing. Background threads forwarding messages into a
+/// channel use it to notice that nobody is receiving anymore while their sources are idle.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[repr(transparent)]
+pub struct Abandoned<T>(Sender<T>);
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T> Abandoned<T> {
+    /// Wraps a reference to a sender.
+    #[inline]
+    pub fn new(s: &Sender<T>) -> &Abandoned<T> {
+        // The adapter has the same layout as the wrapped sender.
+        unsafe { &*(s as *const Sender<T> as *const Abandoned<T>) }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T> SelectHandle for Abandoned<T> {
+    fn try(&self, _token: &mut Token) -> bool {
+        channel::is_disconnected(&self.0)
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        channel::is_disconnected(&self.0)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.0.watch(oper, cx);
+        !channel::is_disconnected(&self.0)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.unwatch(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        channel::is_disconnected(&self.0)
+    }
+
+    fn state(&self) -> usize {
+        self.0.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        channel::is_disconnected(&self.0)
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.unwatch(oper);
+    }
+}
+
+/// Turns a reference to a receiver into a handle for the `closed` case of the `select!` macro.
+pub fn closed<'a, T>(r: &'a Receiver<T>) -> &'a (SelectHandle + 'a) {
+    Closed::new(r)
+}
+
+/// Returns `true` if both receivers belong to the same channel.
+///
+/// This is used by the `select!` macro to route disconnection of a receive case to the `closed`
+/// case for the same channel. The check happens at run time, unlike the token-based checks of a
+/// strict `select!` block.
+pub fn same_channel<T, U>(a: &Receiver<T>, b: &Receiver<U>) -> bool {
+    channel::same_channel(a, b)
 }
 
 /// Determines when a select operation should time out.
 #[derive(Clone, Copy, Eq, PartialEq)]
-enum Timeout {
+pub enum Timeout {
     /// Try firing operations without blocking.
     Now,
 
     /// Block forever.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     Never,
 
     /// Time out after an instant in time.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     At(Instant),
 }
 
@@ -169,21 +411,32 @@ enum Timeout {
 ///
 /// Successful receive operations will have to be followed up by `channel::read()` and successful
 /// send operations by `channel::write()`.
-fn run_select<S>(
+///
+/// The `states` buffer is used as scratch space, which lets callers reuse its allocation.
+///
+/// If `priorities` is not empty, it holds the priority of every send operation, indexed by the
+/// operation index, and `None` for other operations. See `order_by_priority()`.
+fn run_select<S, A>(
     handles: &mut [(&S, usize, *const u8)],
+    states: &mut SmallVec<A>,
     timeout: Timeout,
+    fairness: Fairness,
+    priorities: &[Option<i32>],
 ) -> Option<(Token, usize, *const u8)>
 where
     S: SelectHandle + ?Sized,
+    A: Array<Item = usize>,
 {
     if handles.is_empty() {
         // Wait until the timeout and return.
         match timeout {
             Timeout::Now => return None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             Timeout::Never => {
                 utils::sleep_until(None);
                 unreachable!();
             }
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             Timeout::At(when) => {
                 utils::sleep_until(Some(when));
                 return None;
@@ -210,9 +463,12 @@ where
         }
 
         // Shuffle the operations for fairness.
-        utils::shuffle(handles);
+        if fairness == Fairness::Random {
+            utils::shuffle(handles);
+        }
+        order_by_priority(handles, priorities);
 
-        let mut states = SmallVec::<[usize; 4]>::with_capacity(handles.len());
+        states.clear();
 
         // Snapshot the channel states of all operations.
         for &(handle, _, _) in handles.iter() {
@@ -248,9 +504,10 @@ where
 
     loop {
         // Shuffle the operations for fairness.
-        if handles.len() >= 2 {
+        if handles.len() >= 2 && fairness == Fairness::Random {
             utils::shuffle(handles);
         }
+        order_by_priority(handles, priorities);
 
         // Try firing the operations without blocking.
         for &(handle, i, ptr) in handles.iter() {
@@ -297,9 +554,9 @@ where
                 // Check with each operation for how long we're allowed to block, and compute the
                 // earliest deadline.
                 let mut deadline: Option<Instant> = match timeout {
-                    Timeout::Now => unreachable!(),
-                    Timeout::Never => None,
+                    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
                     Timeout::At(when) => Some(when),
+                    _ => None,
                 };
                 for &(handle, _, _) in handles.iter() {
                     if let Some(x) = handle.deadline() {
@@ -344,18 +601,282 @@ where
         // Check for timeout.
         match timeout {
             Timeout::Now => unreachable!(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             Timeout::Never => {},
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             Timeout::At(when) => {
                 if Instant::now() >= when {
                     // Fall back to one final non-blocking select. This is needed to make the whole
                     // select invocation appear from the outside as a single operation.
-                    return run_select(handles, Timeout::Now);
+                    return run_select(handles, states, Timeout::Now, fairness, priorities);
                 }
             }
         };
     }
 }
 
+/// Reorders send operations so that the ones with higher priorities are tried first.
+///
+/// Send operations only trade places among themselves, so other operations stay where shuffling
+/// put them and keep their random fairness. Send operations with equal priorities keep their
+/// relative order. Nothing is reordered if all send operations have the same priority.
+fn order_by_priority<S: ?Sized>(
+    handles: &mut [(&S, usize, *const u8)],
+    priorities: &[Option<i32>],
+) {
+    let mut sends = priorities.iter().filter_map(|p| *p);
+    let first = match sends.next() {
+        None => return,
+        Some(p) => p,
+    };
+    if sends.all(|p| p == first) {
+        return;
+    }
+
+    // Insertion sort over the positions of send operations.
+    for j in 0..handles.len() {
+        let p = match priorities[handles[j].1] {
+            None => continue,
+            Some(p) => p,
+        };
+
+        let mut k = j;
+        while let Some(prev) = (0..k).rev().find(|&x| priorities[handles[x].1].is_some()) {
+            if priorities[handles[prev].1] >= Some(p) {
+                break;
+            }
+            handles.swap(prev, k);
+            k = prev;
+        }
+    }
+}
+
+/// Blocks until the operation fires or the token is cancelled.
+///
+/// Returns `None` if the token was cancelled. Cancellation takes precedence over the operation if
+/// both are ready at the same time.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn run_cancellable(handle: &SelectHandle, cancel: &CancelToken) -> Option<Token> {
+    let mut handles: [(&SelectHandle, usize, *const u8); 2] = [
+        (cancel, 0, ptr::null()),
+        (handle, 1, ptr::null()),
+    ];
+
+    // Fixed order makes sure the token is always checked first.
+    let mut states = SmallVec::<[usize; 2]>::new();
+    match run_select(&mut handles, &mut states, Timeout::Never, Fairness::RoundRobin, &[]) {
+        Some((token, 1, _)) => Some(token),
+        _ => None,
+    }
+}
+
+/// Blocks until the operation fires.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn run_blocking(handle: &SelectHandle) -> Token {
+    let mut handles: [(&SelectHandle, usize, *const u8); 1] = [(handle, 0, ptr::null())];
+
+    let mut states = SmallVec::<[usize; 1]>::new();
+    match run_select(&mut handles, &mut states, Timeout::Never, Fairness::RoundRobin, &[]) {
+        Some((token, _, _)) => token,
+        None => unreachable!(),
+    }
+}
+
+/// Blocks until the operation becomes ready or the deadline is reached, without performing it.
+///
+/// Returns `true` if the operation is ready.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn wait_ready(handle: &SelectHandle, deadline: Option<Instant>) -> bool {
+    let token = &mut Token::default();
+    loop {
+        if handle.is_ready() {
+            return true;
+        }
+        if let Some(d) = deadline {
+            if Instant::now() >= d {
+                return false;
+            }
+        }
+
+        Context::with(|cx| {
+            let oper = Operation::hook(token);
+            handle.watch(oper, cx);
+
+            // Has the operation become ready just now?
+            if handle.is_ready() {
+                let _ = cx.try_select(Selected::Aborted);
+            }
+
+            // Operations such as receiving from `after` or `tick` become ready at a deadline
+            // rather than being woken up.
+            let deadline = match (deadline, handle.deadline()) {
+                (Some(x), Some(y)) => Some(x.min(y)),
+                (x, y) => x.or(y),
+            };
+            cx.wait_until(deadline);
+            handle.unwatch(oper);
+        });
+    }
+}
+
+/// Seeds the random number generator used for fairness in selection on the current thread.
+///
+/// When multiple operations are ready at the same time, [`select!`] and [`Select`] choose one of
+/// them at random, using a random number generator that belongs to the current thread. It is
+/// seeded randomly by default. Seeding it explicitly makes the sequence of choices on this
+/// thread reproducible, given the same sequence of ready operations. A [`Select`] created with
+/// [`Select::with_rng_seed`] uses its own generator instead.
+///
+/// [`select!`]: macro.select.html
+/// [`Select`]: struct.Select.html
+/// [`Select::with_rng_seed`]: struct.Select.html#method.with_rng_seed
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::{set_rng_seed, unbounded};
+///
+/// let (s1, r1) = unbounded();
+/// let (s2, r2) = unbounded();
+///
+/// let mut picks = |seed| {
+///     set_rng_seed(seed);
+///     (0..10)
+///         .map(|_| {
+///             s1.send(()).unwrap();
+///             s2.send(()).unwrap();
+///             let pick = select! {
+///                 recv(r1) -> _ => r2.recv().map(|_| 1),
+///                 recv(r2) -> _ => r1.recv().map(|_| 2),
+///             };
+///             pick.unwrap()
+///         })
+///         .collect::<Vec<_>>()
+/// };
+///
+/// assert_eq!(picks(7), picks(7));
+/// # }
+/// ```
+pub fn set_rng_seed(seed: u64) {
+    utils::set_seed(seed);
+}
+
+/// Runs selection over operations stored in a slice, without allocating.
+///
+/// The `select!` macro supports at most 32 operations, so the states always fit on the stack.
+fn run_slice(
+    handles: &mut [(&SelectHandle, usize, *const u8)],
+    priorities: &[Option<i32>],
+    timeout: Timeout,
+) -> Option<(Token, usize, *const u8)> {
+    let mut states = SmallVec::<[usize; 32]>::new();
+    run_select(handles, &mut states, timeout, Fairness::Random, priorities)
+}
+
+/// Attempts to select one of the operations without blocking.
+///
+/// This is used by the `select!` macro, which stores operations in an array on the stack.
+#[inline]
+pub fn try_select<'a>(
+    handles: &mut [(&'a SelectHandle, usize, *const u8)],
+    priorities: &[Option<i32>],
+) -> Result<SelectedOperation<'a>, TrySelectError> {
+    match run_slice(handles, priorities, Timeout::Now) {
+        None => Err(TrySelectError),
+        Some((token, index, ptr)) => Ok(SelectedOperation {
+            token,
+            index,
+            ptr,
+            _marker: PhantomData,
+        }),
+    }
+}
+
+/// Blocks until one of the operations becomes ready and selects it.
+///
+/// This is used by the `select!` macro, which stores operations in an array on the stack.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[inline]
+pub fn select<'a>(
+    handles: &mut [(&'a SelectHandle, usize, *const u8)],
+    priorities: &[Option<i32>],
+) -> SelectedOperation<'a> {
+    if handles.is_empty() {
+        panic!("no operations have been added to `Select`");
+    }
+
+    let (token, index, ptr) = run_slice(handles, priorities, Timeout::Never).unwrap();
+    SelectedOperation {
+        token,
+        index,
+        ptr,
+        _marker: PhantomData,
+    }
+}
+
+/// Waits until one of the operations becomes ready and selects it, but only for a limited time.
+///
+/// This is used by the `select!` macro, which stores operations in an array on the stack.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[inline]
+pub fn select_timeout<'a>(
+    handles: &mut [(&'a SelectHandle, usize, *const u8)],
+    priorities: &[Option<i32>],
+    timeout: Duration,
+) -> Result<SelectedOperation<'a>, SelectTimeoutError> {
+    let timeout = Timeout::At(Instant::now() + timeout);
+
+    match run_slice(handles, priorities, timeout) {
+        None => Err(SelectTimeoutError),
+        Some((token, index, ptr)) => Ok(SelectedOperation {
+            token,
+            index,
+            ptr,
+            _marker: PhantomData,
+        }),
+    }
+}
+
+/// The strategy for choosing among multiple ready operations in [`Select`].
+///
+/// [`Select`]: struct.Select.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fairness {
+    /// A random ready operation is selected.
+    ///
+    /// This is the default.
+    Random,
+
+    /// Ready operations are selected in strict rotation.
+    ///
+    /// Operations are tried in the order of their indices, starting with the one following the
+    /// previously selected operation. An operation that stays ready is therefore bypassed at most
+    /// once by each other operation.
+    RoundRobin,
+}
+
+/// The budget left over after [`Select::select_budgeted`] returns.
+///
+/// [`Select::select_budgeted`]: struct.Select.html#method.select_budgeted
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Budget {
+    /// The number of operations that could still have been performed.
+    pub ops: usize,
+
+    /// The time that was left until the deadline.
+    pub time: Duration,
+}
+
+impl Budget {
+    /// Returns `true` if either the operations or the time ran out.
+    pub fn is_exhausted(&self) -> bool {
+        self.ops == 0 || self.time == Duration::from_secs(0)
+    }
+}
+
 /// Selects from a set of channel operations.
 ///
 /// `Select` allows you to define a set of channel operations, wait until any one of them becomes
@@ -368,7 +889,13 @@ where
 /// The [`select!`] macro is a convenience wrapper around `Select`. However, it cannot select over a
 /// dynamically created list of channel operations.
 ///
+/// Operations stay in the list after one of them is selected and completed, so the same `Select`
+/// can be used over and over again in an event loop. Individual operations can be temporarily
+/// excluded with [`disarm`] and included again with [`rearm`] without changing their indices.
+///
 /// [`select!`]: macro.select.html
+/// [`disarm`]: struct.Select.html#method.disarm
+/// [`rearm`]: struct.Select.html#method.rearm
 ///
 /// # Examples
 ///
@@ -392,11 +919,44 @@ where
 ///     _ => unreachable!(),
 /// }
 /// ```
+///
+/// A `Select` can be sent to and shared between threads, so it only accepts channels whose
+/// messages are `Send`:
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use crossbeam_channel::{unbounded, Select};
+///
+/// let (s, r) = unbounded::<Rc<i32>>();
+/// let mut sel = Select::new();
+/// sel.recv(&r);
+/// ```
 pub struct Select<'a> {
     /// A list of senders and receivers participating in selection.
     handles: SmallVec<[(&'a SelectHandle, usize, *const u8); 4]>,
+
+    /// A list of senders and receivers that are temporarily excluded from selection.
+    disarmed: SmallVec<[(&'a SelectHandle, usize, *const u8); 4]>,
+
+    /// Scratch space for the channel states of operations during selection.
+    states: SmallVec<[usize; 4]>,
+
+    /// The strategy for choosing among multiple ready operations.
+    fairness: Fairness,
+
+    /// The index of the operation to try first when selecting in rotation.
+    next: usize,
+
+    /// The random number generator used for random fairness, if it was seeded explicitly.
+    rng: Option<Wrapping<u32>>,
 }
 
+// A `Select` holds shared references to senders, receivers and custom primitives, plus their
+// addresses, which are only compared and never dereferenced. Every method that adds an operation
+// requires the referenced object to be `Sync`, either directly or by requiring messages to be
+// `Send`, which makes senders and receivers `Sync`. The references may therefore be used from any
+// thread. The only exception is the crate-internal `recv_local`, whose callers never let the
+// `Select` leave the current thread.
 unsafe impl<'a> Send for Select<'a> {}
 unsafe impl<'a> Sync for Select<'a> {}
 
@@ -416,79 +976,787 @@ impl<'a> Select<'a> {
     pub fn new() -> Select<'a> {
         Select {
             handles: SmallVec::new(),
+            disarmed: SmallVec::new(),
+            states: SmallVec::new(),
+            fairness: Fairness::Random,
+            next: 0,
+            rng: None,
         }
     }
 
-    /// Adds a send operation.
+    /// Creates an empty list of channel operations with space for at least `capacity` operations.
     ///
-    /// Returns the index of the added operation.
+    /// Up to four operations are stored inline, so selecting over a small set of operations never
+    /// allocates. A larger set allocates space for its operations once, and a `Select` that is
+    /// reused with [`clear`] keeps that space, so selecting in a loop doesn't allocate after the
+    /// first iteration. Creating the `Select` with enough capacity upfront avoids even that.
+    ///
+    /// [`clear`]: struct.Select.html#method.clear
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
     /// use crossbeam_channel::{unbounded, Select};
     ///
-    /// let (s1, r1) = unbounded::<i32>();
-    /// let (s2, r2) = unbounded::<i32>();
-    /// let (s3, r3) = unbounded::<i32>();
+    /// let channels: Vec<_> = (0..8).map(|_| unbounded::<i32>()).collect();
+    /// channels[5].0.send(5).unwrap();
     ///
-    /// let mut sel = Select::new();
-    /// let oper1 = sel.send(&s1);
-    /// let oper2 = sel.send(&s2);
-    /// let oper3 = sel.send(&s3);
+    /// let mut sel = Select::with_capacity(channels.len());
+    /// for (_, r) in &channels {
+    ///     sel.recv(r);
+    /// }
     ///
-    /// assert_eq!(oper1, 0);
-    /// assert_eq!(oper2, 1);
-    /// assert_eq!(oper3, 2);
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), 5);
+    /// assert_eq!(oper.recv(&channels[5].1), Ok(5));
     /// ```
-    pub fn send<T>(&mut self, s: &'a Sender<T>) -> usize {
-        let i = self.handles.len();
-        let ptr = s as *const Sender<_> as *const u8;
-        self.handles.push((s, i, ptr));
-        i
+    pub fn with_capacity(capacity: usize) -> Select<'a> {
+        Select {
+            handles: SmallVec::with_capacity(capacity),
+            disarmed: SmallVec::new(),
+            states: SmallVec::with_capacity(capacity),
+            fairness: Fairness::Random,
+            next: 0,
+            rng: None,
+        }
     }
 
-    /// Adds a receive operation.
+    /// Creates an empty list of channel operations that chooses among ready operations using a
+    /// random number generator seeded with `seed`.
     ///
-    /// Returns the index of the added operation.
+    /// By default, random choices are made by a hidden thread-local generator, which makes them
+    /// irreproducible. Two `Select`s with the same operations and the same seed make the same
+    /// sequence of choices when they see the same ready operations, so failing property tests and
+    /// fuzz runs can be replayed. See [`set_rng_seed`] for seeding the generator used by
+    /// [`select!`].
+    ///
+    /// [`set_rng_seed`]: fn.set_rng_seed.html
+    /// [`select!`]: macro.select.html
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
     /// use crossbeam_channel::{unbounded, Select};
     ///
-    /// let (s1, r1) = unbounded::<i32>();
-    /// let (s2, r2) = unbounded::<i32>();
-    /// let (s3, r3) = unbounded::<i32>();
-    ///
-    /// let mut sel = Select::new();
-    /// let oper1 = sel.recv(&r1);
-    /// let oper2 = sel.recv(&r2);
-    /// let oper3 = sel.recv(&r3);
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
     ///
-    /// assert_eq!(oper1, 0);
-    /// assert_eq!(oper2, 1);
-    /// assert_eq!(oper3, 2);
+    /// // Records which channels a seeded `Select` picks when both are always ready.
+    /// let picks = |seed| {
+    ///     let mut sel = Select::with_rng_seed(seed);
+    ///     sel.recv(&r1);
+    ///     sel.recv(&r2);
+    ///
+    ///     (0..10)
+    ///         .map(|_| {
+    ///             s1.send(()).unwrap();
+    ///             s2.send(()).unwrap();
+    ///
+    ///             let oper = sel.select();
+    ///             let index = oper.index();
+    ///             oper.recv([&r1, &r2][index]).unwrap();
+    ///             [&r1, &r2][1 - index].recv().unwrap();
+    ///             index
+    ///         })
+    ///         .collect::<Vec<_>>()
+    /// };
+    ///
+    /// assert_eq!(picks(7), picks(7));
     /// ```
-    pub fn recv<T>(&mut self, r: &'a Receiver<T>) -> usize {
-        let i = self.handles.len();
-        let ptr = r as *const Receiver<_> as *const u8;
-        self.handles.push((r, i, ptr));
-        i
+    pub fn with_rng_seed(seed: u64) -> Select<'a> {
+        let mut sel = Select::new();
+        sel.rng = Some(utils::rng_from_seed(seed));
+        sel
     }
 
-    /// Attempts to execute one of the operations without blocking.
+    /// Removes all operations, keeping the allocated space for reuse.
     ///
-    /// If an operation is ready, it is selected and returned. If multiple operations are ready at
-    /// the same time, a random one among them is selected. If none of the operations are ready, an
-    /// error is returned.
+    /// Indices of operations added afterwards start from zero again. The fairness strategy is
+    /// kept.
     ///
-    /// An operation is considered to be ready if it doesn't have to block. Note that it is ready
-    /// even when it will simply return an error because the channel is disconnected.
+    /// # Examples
     ///
-    /// The selected operation must be completed with [`SelectedOperation::send`]
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded();
+    /// s2.send("hello").unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// sel.recv(&r1);
+    /// assert!(sel.try_select().is_err());
+    ///
+    /// // Reuse the same `Select` for a different set of operations.
+    /// sel.clear();
+    /// let oper2 = sel.recv(&r2);
+    /// assert_eq!(oper2, 0);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.recv(&r2), Ok("hello"));
+    /// # drop(s1);
+    /// ```
+    pub fn clear(&mut self) {
+        self.handles.clear();
+        self.disarmed.clear();
+        self.next = 0;
+    }
+
+    /// Adds a send operation.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    /// let (s3, r3) = unbounded::<i32>();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.send(&s1);
+    /// let oper2 = sel.send(&s2);
+    /// let oper3 = sel.send(&s3);
+    ///
+    /// assert_eq!(oper1, 0);
+    /// assert_eq!(oper2, 1);
+    /// assert_eq!(oper3, 2);
+    /// ```
+    pub fn send<T: Send>(&mut self, s: &'a Sender<T>) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = s as *const Sender<_> as *const u8;
+        self.handles.push((s, i, ptr));
+        i
+    }
+
+    /// Adds a receive operation.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    /// let (s3, r3) = unbounded::<i32>();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r1);
+    /// let oper2 = sel.recv(&r2);
+    /// let oper3 = sel.recv(&r3);
+    ///
+    /// assert_eq!(oper1, 0);
+    /// assert_eq!(oper2, 1);
+    /// assert_eq!(oper3, 2);
+    /// ```
+    pub fn recv<T: Send>(&mut self, r: &'a Receiver<T>) -> usize {
+        self.recv_local(r)
+    }
+
+    /// Adds a receive operation without requiring messages to be `Send`.
+    ///
+    /// The `Select` must not be sent to or shared with another thread.
+    pub(crate) fn recv_local<T>(&mut self, r: &'a Receiver<T>) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = r as *const Receiver<_> as *const u8;
+        self.handles.push((r, i, ptr));
+        i
+    }
+
+    /// Adds a receive operation on a receiver with a timeout.
+    ///
+    /// The operation is ready when a message can be received or the timeout of the receiver has
+    /// elapsed. Once selected, it must be completed with [`SelectedOperation::recv_with_timeout`].
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// [`SelectedOperation::recv_with_timeout`]: struct.SelectedOperation.html#method.recv_with_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Elapsed, Select};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    /// let r1 = r1.with_timeout(Duration::from_millis(100));
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv_with_timeout(&r1);
+    /// let oper2 = sel.recv(&r2);
+    ///
+    /// // Neither channel receives a message, so the first one reports a timeout.
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv_with_timeout(&r1), Ok(Err(Elapsed)));
+    /// # drop((s1, s2, oper2));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_with_timeout<T: Send>(&mut self, r: &'a TimeoutReceiver<T>) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = r as *const TimeoutReceiver<_> as *const u8;
+        self.handles.push((r, i, ptr));
+        i
+    }
+
+    /// Adds an operation waiting for a channel to be closed.
+    ///
+    /// The operation is ready once the channel is disconnected and empty, i.e. once a receive
+    /// operation would fail. Once selected, it must be completed with
+    /// [`SelectedOperation::closed`].
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// [`SelectedOperation::closed`]: struct.SelectedOperation.html#method.closed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    /// drop(s);
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.closed(&r);
+    ///
+    /// // The channel still holds a message, so it is not closed yet.
+    /// assert!(sel.try_select().is_err());
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// oper.closed(&r);
+    /// ```
+    pub fn closed<T: Send>(&mut self, r: &'a Receiver<T>) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = r as *const Receiver<_> as *const u8;
+        self.handles.push((Closed::new(r), i, ptr));
+        i
+    }
+
+    /// Adds an operation waiting until the channel is disconnected because all receivers were
+    /// dropped.
+    ///
+    /// Once selected, the operation must be completed with `SelectedOperation::abandoned`.
+    ///
+    /// Returns the index of the added operation.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn abandoned<T>(&mut self, s: &'a Sender<T>) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = s as *const Sender<_> as *const u8;
+        self.handles.push((Abandoned::new(s), i, ptr));
+        i
+    }
+
+    /// Adds an operation waiting until a message could be sent without blocking.
+    ///
+    /// Unlike [`send`], this operation doesn't need the message upfront. It is ready when the
+    /// channel has room for a message, or a receive operation is waiting on the other side in the
+    /// case of a zero-capacity channel, or the channel is disconnected. Once selected, it must be
+    /// completed with [`SelectedOperation::send_ready`], after which the message can be
+    /// constructed and sent with [`Sender::try_send`]. Another sender might take the room in the
+    /// meantime, so the send can still fail because the channel is full.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// [`send`]: struct.Select.html#method.send
+    /// [`SelectedOperation::send_ready`]: struct.SelectedOperation.html#method.send_ready
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, Select};
+    ///
+    /// let (s1, r1) = bounded(1);
+    /// let (s2, r2) = bounded(1);
+    /// s1.send(vec![0; 1024]).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.send_ready(&s1);
+    /// let oper2 = sel.send_ready(&s2);
+    ///
+    /// // Only the second channel has room, so the payload is built just for it.
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper2);
+    /// oper.send_ready(&s2).unwrap();
+    /// s2.try_send(vec![1; 1024]).unwrap();
+    ///
+    /// assert_eq!(r2.recv().unwrap().len(), 1024);
+    /// # drop(r1);
+    /// ```
+    pub fn send_ready<T: Send>(&mut self, s: &'a Sender<T>) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = s as *const Sender<_> as *const u8;
+        self.handles.push((Ready::new(s), i, ptr));
+        i
+    }
+
+    /// Adds an operation waiting until a receive operation would not block.
+    ///
+    /// This works with [`Receiver`] as well as receivers that aren't one: [`SplitReceiver`],
+    /// [`ShardedReceiver`], [`SequencedReceiver`], [`TtlReceiver`], [`TaggedReceiver`], and
+    /// [`OrderedReceiver`]. The operation is ready once the receiver holds a message or is
+    /// disconnected. Once selected, it must be completed with [`SelectedOperation::recv_ready`],
+    /// after which the message can be received with the receiver's own `try_recv`. If other threads
+    /// receive from the same channel, they may take the message first, in which case `try_recv`
+    /// reports an empty channel.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// [`Receiver`]: struct.Receiver.html
+    /// [`SplitReceiver`]: struct.SplitReceiver.html
+    /// [`ShardedReceiver`]: struct.ShardedReceiver.html
+    /// [`SequencedReceiver`]: struct.SequencedReceiver.html
+    /// [`TtlReceiver`]: struct.TtlReceiver.html
+    /// [`TaggedReceiver`]: struct.TaggedReceiver.html
+    /// [`OrderedReceiver`]: struct.OrderedReceiver.html
+    /// [`SelectedOperation::recv_ready`]: struct.SelectedOperation.html#method.recv_ready
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded();
+    /// let (even, odd) = r2.split_by(|&x| x % 2 == 0);
+    /// s2.send(3).unwrap();
+    ///
+    /// // Receiving on the even side pulls the odd message out of the channel and hands it over.
+    /// assert!(even.try_recv().is_err());
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r1);
+    /// let oper2 = sel.recv_ready(&even);
+    /// let oper3 = sel.recv_ready(&odd);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper3);
+    /// oper.recv_ready(&odd);
+    /// assert_eq!(odd.try_recv(), Ok(3));
+    /// # drop((s1, oper1, oper2));
+    /// ```
+    ///
+    /// A [`Select`] can be sent to and shared between threads, so receivers that can't be shared
+    /// between threads are rejected:
+    ///
+    /// ```compile_fail
+    /// use std::rc::Rc;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded::<Rc<i32>>();
+    /// let mut sel = Select::new();
+    /// sel.recv_ready(&r);
+    /// ```
+    ///
+    /// [`Select`]: struct.Select.html
+    pub fn recv_ready<R: SelectHandle + Sync>(&mut self, r: &'a R) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = r as *const R as *const u8;
+        self.handles.push((Ready::new(r), i, ptr));
+        i
+    }
+
+    /// Adds an operation waiting for a trigger to be set.
+    ///
+    /// The operation is ready for as long as the trigger is set. Once selected, it must be
+    /// completed with [`SelectedOperation::trigger`].
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// [`SelectedOperation::trigger`]: struct.SelectedOperation.html#method.trigger
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Select, Trigger};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let shutdown = Trigger::new();
+    ///
+    /// let t = shutdown.clone();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     t.set();
+    /// });
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r);
+    /// let oper2 = sel.trigger(&shutdown);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper2);
+    /// oper.trigger(&shutdown);
+    /// # drop(s);
+    /// ```
+    pub fn trigger(&mut self, t: &'a Trigger) -> usize {
+        self.custom(t)
+    }
+
+    /// Adds an operation waiting for a custom [`Selectable`] primitive to become ready.
+    ///
+    /// Once selected, the operation must be completed with [`SelectedOperation::custom`].
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// [`Selectable`]: trait.Selectable.html
+    /// [`SelectedOperation::custom`]: struct.SelectedOperation.html#method.custom
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::{unbounded, Notifier, Select, Selectable};
+    ///
+    /// // A primitive that becomes ready at a fixed instant.
+    /// struct Alarm(Instant, Notifier);
+    ///
+    /// impl Selectable for Alarm {
+    ///     fn is_ready(&self) -> bool {
+    ///         Instant::now() >= self.0
+    ///     }
+    ///
+    ///     fn deadline(&self) -> Option<Instant> {
+    ///         Some(self.0)
+    ///     }
+    ///
+    ///     fn notifier(&self) -> &Notifier {
+    ///         &self.1
+    ///     }
+    /// }
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let alarm = Alarm(Instant::now() + Duration::from_millis(100), Notifier::new());
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r);
+    /// let oper2 = sel.custom(&alarm);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper2);
+    /// oper.custom(&alarm);
+    /// # drop(s);
+    /// ```
+    pub fn custom<S: Selectable + Sync>(&mut self, s: &'a S) -> usize {
+        let i = self.handles.len() + self.disarmed.len();
+        let ptr = s as *const S as *const u8;
+        self.handles.push((Adapter::new(s), i, ptr));
+        i
+    }
+
+    /// Temporarily excludes an operation from selection.
+    ///
+    /// The operation keeps its index and stays in the list, but it won't be selected until it is
+    /// armed again with [`rearm`]. This is useful in event loops that reuse the same `Select` on
+    /// every iteration, e.g. to stop receiving from a channel that got disconnected or to stop
+    /// sending while there is nothing to send. Disarming an operation that is already disarmed
+    /// has no effect.
+    ///
+    /// [`rearm`]: struct.Select.html#method.rearm
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operation with the given index has been added to `Select`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    /// s1.send(10).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r1);
+    /// let oper2 = sel.recv(&r2);
+    ///
+    /// // Only the second operation can be selected now.
+    /// sel.disarm(oper1);
+    /// assert!(sel.try_select().is_err());
+    ///
+    /// s2.send(20).unwrap();
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper2);
+    /// assert_eq!(oper.recv(&r2), Ok(20));
+    /// ```
+    pub fn disarm(&mut self, index: usize) {
+        if let Some(pos) = self.handles.iter().position(|&(_, i, _)| i == index) {
+            let handle = self.handles.remove(pos);
+            self.disarmed.push(handle);
+        } else {
+            assert!(
+                self.disarmed.iter().any(|&(_, i, _)| i == index),
+                "no operation with index {}",
+                index,
+            );
+        }
+    }
+
+    /// Includes a previously disarmed operation in selection again.
+    ///
+    /// The operation is restored with its original index, so nothing has to be rebuilt after it
+    /// was temporarily disabled with [`disarm`]. Rearming an operation that is already armed has no
+    /// effect.
+    ///
+    /// [`disarm`]: struct.Select.html#method.disarm
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operation with the given index has been added to `Select`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r);
+    ///
+    /// sel.disarm(oper1);
+    /// assert!(sel.try_select().is_err());
+    ///
+    /// sel.rearm(oper1);
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv(&r), Ok(1));
+    /// ```
+    pub fn rearm(&mut self, index: usize) {
+        if let Some(pos) = self.disarmed.iter().position(|&(_, i, _)| i == index) {
+            let handle = self.disarmed.remove(pos);
+            self.handles.push(handle);
+        } else {
+            assert!(
+                self.handles.iter().any(|&(_, i, _)| i == index),
+                "no operation with index {}",
+                index,
+            );
+        }
+    }
+
+    /// Sets the strategy for choosing among multiple ready operations.
+    ///
+    /// By default, a random ready operation is selected. With [`Fairness::RoundRobin`], ready
+    /// operations are instead selected in strict rotation, which makes selection deterministic
+    /// and guarantees that no ready operation is bypassed more than once by any other operation.
+    ///
+    /// [`Fairness::RoundRobin`]: enum.Fairness.html#variant.RoundRobin
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Fairness, Select};
+    ///
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
+    ///
+    /// for i in 0..2 {
+    ///     s1.send(i).unwrap();
+    ///     s2.send(i).unwrap();
+    /// }
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r1);
+    /// let oper2 = sel.recv(&r2);
+    /// sel.set_fairness(Fairness::RoundRobin);
+    ///
+    /// // Both operations are always ready, so they take turns.
+    /// for &expected in &[oper1, oper2, oper1, oper2] {
+    ///     let oper = sel.select();
+    ///     assert_eq!(oper.index(), expected);
+    ///     match oper.index() {
+    ///         i if i == oper1 => oper.recv(&r1).unwrap(),
+    ///         i if i == oper2 => oper.recv(&r2).unwrap(),
+    ///         _ => unreachable!(),
+    ///     };
+    /// }
+    /// ```
+    pub fn set_fairness(&mut self, fairness: Fairness) {
+        self.fairness = fairness;
+    }
+
+    /// Runs a selection with the given timeout and returns the selected operation, if any.
+    pub(crate) fn select_with(&mut self, timeout: Timeout) -> Option<SelectedOperation<'_>> {
+        self.run(timeout).map(|(token, index, ptr)| SelectedOperation {
+            token,
+            index,
+            ptr,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Orders the operations for the next selection and runs it.
+    fn run(&mut self, timeout: Timeout) -> Option<(Token, usize, *const u8)> {
+        if self.fairness == Fairness::RoundRobin {
+            // Order the operations by index, starting with the one that should be tried first.
+            self.handles.sort_by_key(|&(_, i, _)| i);
+            let next = self.next;
+            let start = self
+                .handles
+                .iter()
+                .position(|&(_, i, _)| i >= next)
+                .unwrap_or(0);
+            self.handles.rotate_left(start);
+        }
+
+        let fairness = self.fairness;
+        let handles = &mut self.handles;
+        let states = &mut self.states;
+        let res = match &mut self.rng {
+            None => run_select(handles, states, timeout, fairness, &[]),
+            Some(rng) => {
+                utils::with_rng(rng, || run_select(handles, states, timeout, fairness, &[]))
+            }
+        };
+        if let Some((_, index, _)) = res {
+            self.next = index + 1;
+        }
+        res
+    }
+
+    /// Attempts to execute one of the operations without blocking.
+    ///
+    /// If an operation is ready, it is selected and returned. If multiple operations are ready at
+    /// the same time, a random one among them is selected. If none of the operations are ready, an
+    /// error is returned.
+    ///
+    /// An operation is considered to be ready if it doesn't have to block. Note that it is ready
+    /// even when it will simply return an error because the channel is disconnected.
+    ///
+    /// The selected operation must be completed with [`SelectedOperation::send`]
+    /// or [`SelectedOperation::recv`].
+    ///
+    /// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
+    /// [`SelectedOperation::recv`]: struct.SelectedOperation.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
+    ///
+    /// s1.send(10).unwrap();
+    /// s2.send(20).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r1);
+    /// let oper2 = sel.recv(&r2);
+    ///
+    /// // Both operations are initially ready, so a random one will be executed.
+    /// let oper = sel.try_select();
+    /// match oper {
+    ///     Err(_) => panic!("both operations should be ready"),
+    ///     Ok(oper) => match oper.index() {
+    ///         i if i == oper1 => assert_eq!(oper.recv(&r1), Ok(10)),
+    ///         i if i == oper2 => assert_eq!(oper.recv(&r2), Ok(20)),
+    ///         _ => unreachable!(),
+    ///     }
+    /// }
+    /// ```
+    pub fn try_select(&mut self) -> Result<SelectedOperation<'_>, TrySelectError> {
+        match self.run(Timeout::Now) {
+            None => Err(TrySelectError),
+            Some((token, index, ptr)) => Ok(SelectedOperation {
+                token,
+                index,
+                ptr,
+                _marker: PhantomData,
+            }),
+        }
+    }
+
+    /// Blocks until one of the operations becomes ready.
+    ///
+    /// Once an operation becomes ready, it is selected and returned.
+    ///
+    /// An operation is considered to be ready if it doesn't have to block. Note that it is ready
+    /// even when it will simply return an error because the channel is disconnected.
+    ///
+    /// The selected operation must be completed with [`SelectedOperation::send`]
+    /// or [`SelectedOperation::recv`].
+    ///
+    /// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
+    /// [`SelectedOperation::recv`]: struct.SelectedOperation.html#method.recv
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operations have been added to `Select` or all of them are disarmed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s1.send(10).unwrap();
+    /// });
+    /// thread::spawn(move || s2.send(20).unwrap());
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv(&r1);
+    /// let oper2 = sel.recv(&r2);
+    ///
+    /// // The second operation will be selected because it becomes ready first.
+    /// let oper = sel.select();
+    /// match oper.index() {
+    ///     i if i == oper1 => assert_eq!(oper.recv(&r1), Ok(10)),
+    ///     i if i == oper2 => assert_eq!(oper.recv(&r2), Ok(20)),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn select(&mut self) -> SelectedOperation<'_> {
+        if self.handles.is_empty() {
+            if self.disarmed.is_empty() {
+                panic!("no operations have been added to `Select`");
+            } else {
+                panic!("all operations in `Select` are disarmed");
+            }
+        }
+
+        let (token, index, ptr) = self.run(Timeout::Never).unwrap();
+        SelectedOperation {
+            token,
+            index,
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Waits until one of the operations becomes ready, but only for a limited time.
+    ///
+    /// If an operation becomes ready, it is selected and returned. If multiple operations are
+    /// ready at the same time, a random one among them is selected. If none of the operations
+    /// become ready for the specified duration, an error is returned.
+    ///
+    /// An operation is considered to be ready if it doesn't have to block. Note that it is ready
+    /// even when it will simply return an error because the channel is disconnected.
+    ///
+    /// The selected operation must be completed with [`SelectedOperation::send`]
     /// or [`SelectedOperation::recv`].
     ///
     /// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
@@ -498,22 +1766,26 @@ impl<'a> Select<'a> {
     ///
     /// ```
     /// use std::thread;
+    /// use std::time::Duration;
     /// use crossbeam_channel::{unbounded, Select};
     ///
     /// let (s1, r1) = unbounded();
     /// let (s2, r2) = unbounded();
     ///
-    /// s1.send(10).unwrap();
-    /// s2.send(20).unwrap();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s1.send(10).unwrap();
+    /// });
+    /// thread::spawn(move || s2.send(20).unwrap());
     ///
     /// let mut sel = Select::new();
     /// let oper1 = sel.recv(&r1);
     /// let oper2 = sel.recv(&r2);
     ///
-    /// // Both operations are initially ready, so a random one will be executed.
-    /// let oper = sel.try_select();
+    /// // The second operation will be selected because it becomes ready first.
+    /// let oper = sel.select_timeout(Duration::from_millis(500));
     /// match oper {
-    ///     Err(_) => panic!("both operations should be ready"),
+    ///     Err(_) => panic!("should not have timed out"),
     ///     Ok(oper) => match oper.index() {
     ///         i if i == oper1 => assert_eq!(oper.recv(&r1), Ok(10)),
     ///         i if i == oper2 => assert_eq!(oper.recv(&r2), Ok(20)),
@@ -521,9 +1793,15 @@ impl<'a> Select<'a> {
     ///     }
     /// }
     /// ```
-    pub fn try_select(&mut self) -> Result<SelectedOperation<'_>, TrySelectError> {
-        match run_select(&mut self.handles, Timeout::Now) {
-            None => Err(TrySelectError),
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn select_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<SelectedOperation<'_>, SelectTimeoutError> {
+        let timeout = Timeout::At(Instant::now() + timeout);
+
+        match self.run(timeout) {
+            None => Err(SelectTimeoutError),
             Some((token, index, ptr)) => Ok(SelectedOperation {
                 token,
                 index,
@@ -533,57 +1811,539 @@ impl<'a> Select<'a> {
         }
     }
 
-    /// Blocks until one of the operations becomes ready.
+    /// Selects and completes operations one after another until a budget runs out.
     ///
-    /// Once an operation becomes ready, it is selected and returned.
+    /// Each selected operation is passed to `f`, which must complete it with
+    /// [`SelectedOperation::send`] or [`SelectedOperation::recv`]. At most `ops` operations are
+    /// selected, and selection stops once `time` has elapsed, waiting for operations to become
+    /// ready in the meantime. Operations that are ready when the time runs out are still checked
+    /// for once, so a zero `time` completes a single ready operation, if there is one. The
+    /// remaining budget is returned.
+    ///
+    /// This lets a cooperative scheduler service channels for a bounded amount of work before
+    /// running other tasks, even when the channels never run dry.
+    ///
+    /// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
+    /// [`SelectedOperation::recv`]: struct.SelectedOperation.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// for i in 0..10 {
+    ///     s.send(i).unwrap();
+    /// }
+    ///
+    /// let mut sel = Select::new();
+    /// sel.recv(&r);
+    ///
+    /// // Only four messages are received, leaving the rest for the next turn.
+    /// let mut received = Vec::new();
+    /// let left = sel.select_budgeted(4, Duration::from_secs(1), |oper| {
+    ///     received.push(oper.recv(&r).unwrap());
+    /// });
+    /// assert_eq!(received, [0, 1, 2, 3]);
+    /// assert_eq!(left.ops, 0);
+    /// assert!(left.is_exhausted());
+    /// assert_eq!(r.len(), 6);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn select_budgeted<F>(&mut self, ops: usize, time: Duration, mut f: F) -> Budget
+    where
+        F: FnMut(SelectedOperation<'_>),
+    {
+        let deadline = Instant::now() + time;
+        let mut left = ops;
+
+        while left > 0 {
+            match self.run(Timeout::At(deadline)) {
+                None => break,
+                Some((token, index, ptr)) => f(SelectedOperation {
+                    token,
+                    index,
+                    ptr,
+                    _marker: PhantomData,
+                }),
+            }
+            left -= 1;
+
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        let now = Instant::now();
+        Budget {
+            ops: left,
+            time: if now < deadline {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            },
+        }
+    }
+
+    /// Finds all operations that are ready right now, without selecting any of them.
+    ///
+    /// The indices of the ready operations are written into `ready` in increasing order, replacing
+    /// its previous contents, and their number is returned. Disarmed operations are skipped. All
+    /// operations are checked in a single pass, so a poller can service every ready channel after
+    /// a wakeup instead of selecting them one at a time.
     ///
     /// An operation is considered to be ready if it doesn't have to block. Note that it is ready
-    /// even when it will simply return an error because the channel is disconnected.
+    /// even when it will simply return an error because the channel is disconnected. Since other
+    /// threads may be operating on the same channels, an operation found to be ready can stop
+    /// being ready before it is performed, so it should be performed with a non-blocking method
+    /// like [`Receiver::try_recv`].
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let channels: Vec<_> = (0..4).map(|_| unbounded()).collect();
+    /// channels[1].0.send(1).unwrap();
+    /// channels[3].0.send(3).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// for (_, r) in &channels {
+    ///     sel.recv(r);
+    /// }
+    ///
+    /// let mut ready = Vec::new();
+    /// assert_eq!(sel.ready_all(&mut ready), 2);
+    /// assert_eq!(ready, [1, 3]);
+    ///
+    /// for &i in &ready {
+    ///     assert_eq!(channels[i].1.try_recv(), Ok(i));
+    /// }
+    /// assert_eq!(sel.ready_all(&mut ready), 0);
+    /// ```
+    pub fn ready_all(&self, ready: &mut Vec<usize>) -> usize {
+        ready.clear();
+        for &(handle, index, _) in &self.handles {
+            if handle.is_ready() {
+                ready.push(index);
+            }
+        }
+
+        // Rearmed operations are moved to the end of the list, so restore the order of indices.
+        ready.sort_unstable();
+        ready.len()
+    }
+}
+
+impl<'a> Clone for Select<'a> {
+    fn clone(&self) -> Select<'a> {
+        Select {
+            handles: self.handles.clone(),
+            disarmed: self.disarmed.clone(),
+            states: SmallVec::new(),
+            fairness: self.fairness,
+            next: self.next,
+            rng: self.rng,
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Select<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Select").finish()
+    }
+}
+
+/// A sender or a receiver owned by a `SelectSet`.
+trait Owned: Send + Sync {
+    /// Returns the handle that participates in selection.
+    fn handle(&self) -> &SelectHandle;
+
+    /// Returns the sender or receiver so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &Any;
+}
+
+impl<T: Send + 'static> Owned for Sender<T> {
+    fn handle(&self) -> &SelectHandle {
+        self
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+}
+
+impl<T: Send + 'static> Owned for Receiver<T> {
+    fn handle(&self) -> &SelectHandle {
+        self
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+}
+
+/// A set of channel operations that owns its senders and receivers.
+///
+/// `SelectSet` works like [`Select`], except that it takes ownership of the added senders and
+/// receivers instead of borrowing them. This makes it easy to store in a struct and move to
+/// another thread: a set can be built on one thread and then selected from on another, or even on
+/// several threads at the same time.
+///
+/// To complete a selected operation, pass the sender or receiver returned by [`sender`] or
+/// [`receiver`] to [`SelectedOperation::send`] or [`SelectedOperation::recv`].
+///
+/// [`Select`]: struct.Select.html
+/// [`sender`]: struct.SelectSet.html#method.sender
+/// [`receiver`]: struct.SelectSet.html#method.receiver
+/// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
+/// [`SelectedOperation::recv`]: struct.SelectedOperation.html#method.recv
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::{unbounded, SelectSet};
+///
+/// let (s1, r1) = unbounded::<i32>();
+/// let (s2, r2) = unbounded::<&str>();
+///
+/// let mut set = SelectSet::new();
+/// let oper1 = set.recv(r1);
+/// let oper2 = set.recv(r2);
+///
+/// s1.send(10).unwrap();
+///
+/// // Select on a different thread than the one that built the set.
+/// let msg = thread::spawn(move || {
+///     let oper = set.select();
+///     match oper.index() {
+///         i if i == oper1 => oper.recv(set.receiver::<i32>(i)).unwrap(),
+///         i if i == oper2 => panic!(),
+///         _ => unreachable!(),
+///     }
+/// }).join().unwrap();
+///
+/// assert_eq!(msg, 10);
+/// # drop(s2);
+/// ```
+pub struct SelectSet {
+    /// Senders and receivers participating in selection, in the order of their indices.
+    opers: Vec<Box<Owned>>,
+
+    /// The strategy for choosing among multiple ready operations.
+    fairness: Fairness,
+
+    /// The index of the operation to try first when selecting in rotation.
+    next: AtomicUsize,
+}
+
+impl SelectSet {
+    /// Creates an empty set of channel operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::SelectSet;
+    ///
+    /// let set = SelectSet::new();
+    ///
+    /// // The set is empty, which means no operation can be selected.
+    /// assert!(set.try_select().is_err());
+    /// ```
+    pub fn new() -> SelectSet {
+        SelectSet {
+            opers: Vec::new(),
+            fairness: Fairness::Random,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds a send operation and takes ownership of the sender.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// assert_eq!(set.send(s1), 0);
+    /// assert_eq!(set.send(s2), 1);
+    /// # drop((r1, r2));
+    /// ```
+    pub fn send<T: Send + 'static>(&mut self, s: Sender<T>) -> usize {
+        self.opers.push(Box::new(s));
+        self.opers.len() - 1
+    }
+
+    /// Adds a receive operation and takes ownership of the receiver.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// assert_eq!(set.recv(r1), 0);
+    /// assert_eq!(set.recv(r2), 1);
+    /// # drop((s1, s2));
+    /// ```
+    pub fn recv<T: Send + 'static>(&mut self, r: Receiver<T>) -> usize {
+        self.opers.push(Box::new(r));
+        self.opers.len() - 1
+    }
+
+    /// Returns the sender of the send operation with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation with the given index is not a send operation on a `Sender<T>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SelectSet};
+    ///
+    /// let (s, r) = bounded::<i32>(1);
+    ///
+    /// let mut set = SelectSet::new();
+    /// let oper1 = set.send(s);
+    ///
+    /// let oper = set.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.send(set.sender(oper1), 10), Ok(()));
+    /// assert_eq!(r.recv(), Ok(10));
+    /// ```
+    pub fn sender<T: 'static>(&self, index: usize) -> &Sender<T> {
+        match self.get(index).as_any().downcast_ref() {
+            Some(s) => s,
+            None => panic!("operation {} is not a send operation of this type", index),
+        }
+    }
+
+    /// Returns the receiver of the receive operation with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation with the given index is not a receive operation on a
+    /// `Receiver<T>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// let mut set = SelectSet::new();
+    /// let oper1 = set.recv(r);
+    ///
+    /// s.send("hello").unwrap();
+    /// let oper = set.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv(set.receiver(oper1)), Ok("hello"));
+    /// ```
+    pub fn receiver<T: 'static>(&self, index: usize) -> &Receiver<T> {
+        match self.get(index).as_any().downcast_ref() {
+            Some(r) => r,
+            None => panic!("operation {} is not a receive operation of this type", index),
+        }
+    }
+
+    /// Returns the number of operations in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// assert_eq!(set.len(), 0);
+    ///
+    /// set.send(s);
+    /// set.recv(r);
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.opers.len()
+    }
+
+    /// Returns `true` if the set contains no operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// assert!(set.is_empty());
+    ///
+    /// set.recv(r);
+    /// assert!(!set.is_empty());
+    /// # drop(s);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.opers.is_empty()
+    }
+
+    /// Sets the strategy for choosing among multiple ready operations.
+    ///
+    /// See [`Select::set_fairness`] for details.
+    ///
+    /// [`Select::set_fairness`]: struct.Select.html#method.set_fairness
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Fairness, SelectSet};
+    ///
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.set_fairness(Fairness::RoundRobin);
+    /// let oper1 = set.recv(r1);
+    /// let oper2 = set.recv(r2);
+    ///
+    /// for i in 0..4 {
+    ///     s1.send(i).unwrap();
+    ///     s2.send(i).unwrap();
+    /// }
+    ///
+    /// // Both operations are always ready, so they take turns.
+    /// for &expected in &[oper1, oper2, oper1, oper2] {
+    ///     let oper = set.select();
+    ///     assert_eq!(oper.index(), expected);
+    ///     oper.recv(set.receiver::<i32>(expected)).unwrap();
+    /// }
+    /// ```
+    pub fn set_fairness(&mut self, fairness: Fairness) {
+        self.fairness = fairness;
+    }
+
+    /// Returns the sender or receiver of the operation with the given index.
+    fn get(&self, index: usize) -> &Owned {
+        match self.opers.get(index) {
+            Some(oper) => &**oper,
+            None => panic!("no operation with index {} has been added to `SelectSet`", index),
+        }
+    }
+
+    /// Orders the operations for the next selection and runs it.
+    fn run(&self, timeout: Timeout) -> Option<(Token, usize, *const u8)> {
+        let mut handles: SmallVec<[(&SelectHandle, usize, *const u8); 4]> = self
+            .opers
+            .iter()
+            .enumerate()
+            .map(|(i, oper)| {
+                let ptr = &**oper as *const Owned as *const u8;
+                (oper.handle(), i, ptr)
+            })
+            .collect();
+
+        if self.fairness == Fairness::RoundRobin && !handles.is_empty() {
+            // Start with the operation that should be tried first.
+            let start = self.next.load(Ordering::Relaxed) % handles.len();
+            handles.rotate_left(start);
+        }
+
+        let mut states = SmallVec::<[usize; 4]>::new();
+        let res = run_select(&mut handles, &mut states, timeout, self.fairness, &[]);
+        if let Some((_, index, _)) = res {
+            self.next.store(index + 1, Ordering::Relaxed);
+        }
+        res
+    }
+
+    /// Attempts to execute one of the operations without blocking.
+    ///
+    /// See [`Select::try_select`] for details.
+    ///
+    /// [`Select::try_select`]: struct.Select.html#method.try_select
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// let mut set = SelectSet::new();
+    /// let oper1 = set.recv(r);
+    /// assert!(set.try_select().is_err());
+    ///
+    /// s.send(1).unwrap();
+    /// let oper = set.try_select().unwrap();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv(set.receiver(oper1)), Ok(1));
+    /// ```
+    pub fn try_select(&self) -> Result<SelectedOperation<'_>, TrySelectError> {
+        match self.run(Timeout::Now) {
+            None => Err(TrySelectError),
+            Some((token, index, ptr)) => Ok(SelectedOperation {
+                token,
+                index,
+                ptr,
+                _marker: PhantomData,
+            }),
+        }
+    }
+
+    /// Blocks until one of the operations becomes ready.
     ///
-    /// The selected operation must be completed with [`SelectedOperation::send`]
-    /// or [`SelectedOperation::recv`].
+    /// See [`Select::select`] for details.
     ///
-    /// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
-    /// [`SelectedOperation::recv`]: struct.SelectedOperation.html#method.recv
+    /// [`Select::select`]: struct.Select.html#method.select
     ///
     /// # Panics
     ///
-    /// Panics if no operations have been added to `Select`.
+    /// Panics if no operations have been added to the set.
     ///
     /// # Examples
     ///
     /// ```
     /// use std::thread;
     /// use std::time::Duration;
-    /// use crossbeam_channel::{unbounded, Select};
+    /// use crossbeam_channel::{unbounded, SelectSet};
     ///
-    /// let (s1, r1) = unbounded();
-    /// let (s2, r2) = unbounded();
+    /// let (s, r) = unbounded();
+    ///
+    /// let mut set = SelectSet::new();
+    /// let oper1 = set.recv(r);
     ///
     /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s1.send(10).unwrap();
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(1).unwrap();
     /// });
-    /// thread::spawn(move || s2.send(20).unwrap());
-    ///
-    /// let mut sel = Select::new();
-    /// let oper1 = sel.recv(&r1);
-    /// let oper2 = sel.recv(&r2);
     ///
-    /// // The second operation will be selected because it becomes ready first.
-    /// let oper = sel.select();
-    /// match oper.index() {
-    ///     i if i == oper1 => assert_eq!(oper.recv(&r1), Ok(10)),
-    ///     i if i == oper2 => assert_eq!(oper.recv(&r2), Ok(20)),
-    ///     _ => unreachable!(),
-    /// }
+    /// let oper = set.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv(set.receiver(oper1)), Ok(1));
     /// ```
-    pub fn select(&mut self) -> SelectedOperation<'_> {
-        if self.handles.is_empty() {
-            panic!("no operations have been added to `Select`");
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn select(&self) -> SelectedOperation<'_> {
+        if self.opers.is_empty() {
+            panic!("no operations have been added to `SelectSet`");
         }
 
-        let (token, index, ptr) = run_select(&mut self.handles, Timeout::Never).unwrap();
+        let (token, index, ptr) = self.run(Timeout::Never).unwrap();
         SelectedOperation {
             token,
             index,
@@ -594,57 +2354,32 @@ impl<'a> Select<'a> {
 
     /// Waits until one of the operations becomes ready, but only for a limited time.
     ///
-    /// If an operation becomes ready, it is selected and returned. If multiple operations are
-    /// ready at the same time, a random one among them is selected. If none of the operations
-    /// become ready for the specified duration, an error is returned.
-    ///
-    /// An operation is considered to be ready if it doesn't have to block. Note that it is ready
-    /// even when it will simply return an error because the channel is disconnected.
-    ///
-    /// The selected operation must be completed with [`SelectedOperation::send`]
-    /// or [`SelectedOperation::recv`].
+    /// See [`Select::select_timeout`] for details.
     ///
-    /// [`SelectedOperation::send`]: struct.SelectedOperation.html#method.send
-    /// [`SelectedOperation::recv`]: struct.SelectedOperation.html#method.recv
+    /// [`Select::select_timeout`]: struct.Select.html#method.select_timeout
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
     /// use std::time::Duration;
-    /// use crossbeam_channel::{unbounded, Select};
-    ///
-    /// let (s1, r1) = unbounded();
-    /// let (s2, r2) = unbounded();
+    /// use crossbeam_channel::{unbounded, SelectSet};
     ///
-    /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s1.send(10).unwrap();
-    /// });
-    /// thread::spawn(move || s2.send(20).unwrap());
+    /// let (s, r) = unbounded::<i32>();
     ///
-    /// let mut sel = Select::new();
-    /// let oper1 = sel.recv(&r1);
-    /// let oper2 = sel.recv(&r2);
+    /// let mut set = SelectSet::new();
+    /// set.recv(r);
     ///
-    /// // The second operation will be selected because it becomes ready first.
-    /// let oper = sel.select_timeout(Duration::from_millis(500));
-    /// match oper {
-    ///     Err(_) => panic!("should not have timed out"),
-    ///     Ok(oper) => match oper.index() {
-    ///         i if i == oper1 => assert_eq!(oper.recv(&r1), Ok(10)),
-    ///         i if i == oper2 => assert_eq!(oper.recv(&r2), Ok(20)),
-    ///         _ => unreachable!(),
-    ///     }
-    /// }
+    /// assert!(set.select_timeout(Duration::from_millis(100)).is_err());
+    /// # drop(s);
     /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
     pub fn select_timeout(
-        &mut self,
+        &self,
         timeout: Duration,
     ) -> Result<SelectedOperation<'_>, SelectTimeoutError> {
         let timeout = Timeout::At(Instant::now() + timeout);
 
-        match run_select(&mut self.handles, timeout) {
+        match self.run(timeout) {
             None => Err(SelectTimeoutError),
             Some((token, index, ptr)) => Ok(SelectedOperation {
                 token,
@@ -656,17 +2391,17 @@ impl<'a> Select<'a> {
     }
 }
 
-impl<'a> Clone for Select<'a> {
-    fn clone(&self) -> Select<'a> {
-        Select {
-            handles: self.handles.clone(),
-        }
+impl Default for SelectSet {
+    fn default() -> SelectSet {
+        SelectSet::new()
     }
 }
 
-impl<'a> fmt::Debug for Select<'a> {
+impl fmt::Debug for SelectSet {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Select").finish()
+        f.debug_struct("SelectSet")
+            .field("len", &self.len())
+            .finish()
     }
 }
 
@@ -689,7 +2424,7 @@ pub struct SelectedOperation<'a> {
     /// The index of the selected operation.
     index: usize,
 
-    /// The address of the selected `Sender` or `Receiver`.
+    /// The address of the selected `Sender`, `Receiver`, or custom primitive.
     ptr: *const u8,
 
     /// Indicates that a `Select<'a>` is mutably borrowed.
@@ -798,6 +2533,312 @@ impl<'a> SelectedOperation<'a> {
         mem::forget(self);
         res.map_err(|_| RecvError)
     }
+
+    /// Completes the receive operation and returns a guard holding the message.
+    ///
+    /// This is a panic-safe alternative to [`recv`]. The message stays in the guard while it is
+    /// being handled, and is committed only when [`RecvGuard::commit`] is called. If the handler
+    /// panics or the guard is dropped for any other reason, the operation is re-armed: the message
+    /// is put back so that it is the next one to be received from the channel, and selecting over
+    /// the channel again picks it up. See [`Receiver::recv_guard`] for details.
+    ///
+    /// In a zero-capacity channel, the sender has already returned by the time the message is put
+    /// back, so the rendezvous is not undone. Instead, the message is kept inside the channel
+    /// until another receive operation takes it.
+    ///
+    /// The passed [`Receiver`] reference must be the same one that was used in [`Select::recv`]
+    /// when the operation was added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect [`Receiver`] reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::panic;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(String::from("poison")).unwrap();
+    ///
+    /// let res = panic::catch_unwind(|| {
+    ///     let mut sel = Select::new();
+    ///     sel.recv(&r);
+    ///
+    ///     let msg = sel.select().recv_guard(&r).unwrap();
+    ///     if *msg == "poison" {
+    ///         panic!("failed to handle the message");
+    ///     }
+    ///     msg.commit();
+    /// });
+    ///
+    /// // The handler panicked, so the message is still in the channel.
+    /// assert!(res.is_err());
+    /// assert_eq!(r.recv().unwrap(), "poison");
+    /// ```
+    ///
+    /// [`recv`]: struct.SelectedOperation.html#method.recv
+    /// [`RecvGuard::commit`]: struct.RecvGuard.html#method.commit
+    /// [`Receiver::recv_guard`]: struct.Receiver.html#method.recv_guard
+    /// [`Receiver`]: struct.Receiver.html
+    /// [`Select::recv`]: struct.Select.html#method.recv
+    pub fn recv_guard<'r, T>(self, r: &'r Receiver<T>) -> Result<RecvGuard<'r, T>, RecvError> {
+        let msg = self.recv(r)?;
+        Ok(channel::guard(r, msg))
+    }
+
+    /// Completes the receive operation on a receiver with a timeout.
+    ///
+    /// The passed [`TimeoutReceiver`] reference must be the same one that was used in
+    /// [`Select::recv_with_timeout`] when the operation was added. Returns `Ok(Err(Elapsed))` if
+    /// the operation was selected because the timeout elapsed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect [`TimeoutReceiver`] reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.with_timeout(Duration::from_secs(1));
+    /// s.send(1).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv_with_timeout(&r);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv_with_timeout(&r), Ok(Ok(1)));
+    /// ```
+    ///
+    /// [`TimeoutReceiver`]: struct.TimeoutReceiver.html
+    /// [`Select::recv_with_timeout`]: struct.Select.html#method.recv_with_timeout
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_with_timeout<T>(
+        mut self,
+        r: &TimeoutReceiver<T>,
+    ) -> Result<Result<T, Elapsed>, RecvError> {
+        assert!(
+            r as *const TimeoutReceiver<T> as *const u8 == self.ptr,
+            "passed a receiver that wasn't selected",
+        );
+        let res = unsafe { timeout::read(r, &mut self.token) };
+        mem::forget(self);
+        res.map_err(|_| RecvError)
+    }
+
+    /// Completes the operation waiting for a channel to be closed.
+    ///
+    /// The passed [`Receiver`] reference must be the same one that was used in
+    /// [`Select::closed`] when the operation was added. Nothing is received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect [`Receiver`] reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// drop(s);
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.closed(&r);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// oper.closed(&r);
+    /// ```
+    ///
+    /// [`Receiver`]: struct.Receiver.html
+    /// [`Select::closed`]: struct.Select.html#method.closed
+    pub fn closed<T>(self, r: &Receiver<T>) {
+        assert!(
+            r as *const Receiver<T> as *const u8 == self.ptr,
+            "passed a receiver that wasn't selected",
+        );
+        mem::forget(self);
+    }
+
+    /// Completes the operation waiting until all receivers are dropped.
+    ///
+    /// The passed `Sender` reference must be the same one that was used in `Select::abandoned`
+    /// when the operation was added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect `Sender` reference is passed.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn abandoned<T>(self, s: &Sender<T>) {
+        assert!(
+            s as *const Sender<T> as *const u8 == self.ptr,
+            "passed a sender that wasn't selected",
+        );
+        mem::forget(self);
+    }
+
+    /// Completes the operation waiting until a message could be sent.
+    ///
+    /// The passed [`Sender`] reference must be the same one that was used in
+    /// [`Select::send_ready`] when the operation was added. Nothing is sent. If the channel is
+    /// disconnected, an error is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect [`Sender`] reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, Select, SendError};
+    ///
+    /// let (s, r) = bounded::<i32>(1);
+    /// drop(r);
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.send_ready(&s);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.send_ready(&s), Err(SendError(())));
+    /// ```
+    ///
+    /// [`Sender`]: struct.Sender.html
+    /// [`Select::send_ready`]: struct.Select.html#method.send_ready
+    pub fn send_ready<T>(self, s: &Sender<T>) -> Result<(), SendError<()>> {
+        assert!(
+            s as *const Sender<T> as *const u8 == self.ptr,
+            "passed a sender that wasn't selected",
+        );
+        mem::forget(self);
+        if channel::is_disconnected(s) {
+            Err(SendError(()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Completes the operation waiting until a receive operation would not block.
+    ///
+    /// The passed receiver reference must be the same one that was used in
+    /// [`Select::recv_ready`] when the operation was added. Nothing is received, so the message
+    /// still has to be taken out with the receiver's own `try_recv`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect receiver reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, Select};
+    ///
+    /// let (s, r) = bounded(1);
+    /// s.send("hello").unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv_ready(&r);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// oper.recv_ready(&r);
+    /// assert_eq!(r.try_recv(), Ok("hello"));
+    /// ```
+    ///
+    /// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+    pub fn recv_ready<R: SelectHandle>(self, r: &R) {
+        assert!(
+            r as *const R as *const u8 == self.ptr,
+            "passed a receiver that wasn't selected",
+        );
+        mem::forget(self);
+    }
+
+    /// Completes the operation waiting for a trigger.
+    ///
+    /// The passed [`Trigger`] reference must be the same one that was used in
+    /// [`Select::trigger`] when the operation was added. Completing the operation doesn't reset
+    /// the trigger.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect [`Trigger`] reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Select, Trigger};
+    ///
+    /// let trigger = Trigger::new();
+    /// trigger.set();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.trigger(&trigger);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// oper.trigger(&trigger);
+    /// assert!(trigger.is_set());
+    /// ```
+    ///
+    /// [`Trigger`]: struct.Trigger.html
+    /// [`Select::trigger`]: struct.Select.html#method.trigger
+    pub fn trigger(self, t: &Trigger) {
+        self.custom(t)
+    }
+
+    /// Completes the operation waiting for a custom [`Selectable`] primitive.
+    ///
+    /// The passed reference must be the same one that was used in [`Select::custom`] when the
+    /// operation was added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Notifier, Select, Selectable};
+    ///
+    /// struct Always(Notifier);
+    ///
+    /// impl Selectable for Always {
+    ///     fn is_ready(&self) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn notifier(&self) -> &Notifier {
+    ///         &self.0
+    ///     }
+    /// }
+    ///
+    /// let always = Always(Notifier::new());
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.custom(&always);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// oper.custom(&always);
+    /// ```
+    ///
+    /// [`Selectable`]: trait.Selectable.html
+    /// [`Select::custom`]: struct.Select.html#method.custom
+    pub fn custom<S: Selectable>(self, s: &S) {
+        assert!(
+            s as *const S as *const u8 == self.ptr,
+            "passed a primitive that wasn't selected",
+        );
+        mem::forget(self);
+    }
 }
 
 impl<'a> fmt::Debug for SelectedOperation<'a> {
@@ -0,0 +1,392 @@
+//! The `select_loop!` macro.
+
+/// A helper macro for `select_loop!` to hide the list of macro patterns from the documentation.
+///
+/// The macro consists of these stages:
+/// 1. `@list`: Turns a list of tokens into a list of receive cases and an optional close case.
+/// 2. `@add`: Binds each receiver to a variable.
+/// 3. `@run`: Registers the receivers with a `Select` and runs the loop.
+/// 4. `@close`: Runs the close case, if there is one.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! crossbeam_channel_select_loop {
+    // The list is empty. Now bind the receivers.
+    (@list
+        ()
+        ($($cases:tt)*)
+        $close:tt
+    ) => {
+        crossbeam_channel_select_loop!(
+            @add
+            ($($cases)*)
+            (
+                (0usize _oper0)
+                (1usize _oper1)
+                (2usize _oper2)
+                (3usize _oper3)
+                (4usize _oper4)
+                (5usize _oper5)
+                (6usize _oper6)
+                (7usize _oper7)
+                (8usize _oper8)
+                (9usize _oper9)
+                (10usize _oper10)
+                (11usize _oper11)
+                (12usize _oper12)
+                (13usize _oper13)
+                (14usize _oper14)
+                (15usize _oper15)
+                (16usize _oper16)
+                (17usize _oper17)
+                (18usize _oper18)
+                (19usize _oper19)
+                (20usize _oper20)
+                (21usize _oper21)
+                (22usize _oper22)
+                (23usize _oper23)
+                (24usize _oper24)
+                (25usize _oper25)
+                (26usize _oper26)
+                (27usize _oper27)
+                (28usize _oper28)
+                (29usize _oper29)
+                (30usize _oper30)
+                (31usize _oper31)
+            )
+            ()
+            $close
+        )
+    };
+    // Print an error if there is a missing result in a recv case.
+    (@list
+        (recv($($args:tt)*) => $($tail:tt)*)
+        $cases:tt
+        $close:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            "expected `->` after `recv` case, found `=>`"
+        ))
+    };
+    // A receive case with a block, followed by a comma.
+    (@list
+        (recv($r:expr) -> $res:pat => $body:block, $($tail:tt)*)
+        ($($cases:tt)*)
+        $close:tt
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tail)*)
+            ($($cases)* [recv($r) -> $res => $body])
+            $close
+        )
+    };
+    // A receive case with a block, without a comma.
+    (@list
+        (recv($r:expr) -> $res:pat => $body:block $($tail:tt)*)
+        ($($cases:tt)*)
+        $close:tt
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tail)*)
+            ($($cases)* [recv($r) -> $res => $body])
+            $close
+        )
+    };
+    // A receive case with an expression, followed by a comma.
+    (@list
+        (recv($r:expr) -> $res:pat => $body:expr, $($tail:tt)*)
+        ($($cases:tt)*)
+        $close:tt
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tail)*)
+            ($($cases)* [recv($r) -> $res => { $body }])
+            $close
+        )
+    };
+    // The last receive case with an expression.
+    (@list
+        (recv($r:expr) -> $res:pat => $body:expr)
+        ($($cases:tt)*)
+        $close:tt
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ()
+            ($($cases)* [recv($r) -> $res => { $body }])
+            $close
+        )
+    };
+    // Only one close case is allowed.
+    (@list
+        (on_close $($tail:tt)*)
+        $cases:tt
+        ($($close:tt)+)
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            "there can be only one `on_close` case in a `select_loop!` block"
+        ))
+    };
+    // A close case with a block, followed by a comma.
+    (@list
+        (on_close($i:pat) => $body:block, $($tail:tt)*)
+        $cases:tt
+        ()
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tail)*)
+            $cases
+            (($i) $body)
+        )
+    };
+    // A close case with a block, without a comma.
+    (@list
+        (on_close($i:pat) => $body:block $($tail:tt)*)
+        $cases:tt
+        ()
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tail)*)
+            $cases
+            (($i) $body)
+        )
+    };
+    // A close case with an expression, followed by a comma.
+    (@list
+        (on_close($i:pat) => $body:expr, $($tail:tt)*)
+        $cases:tt
+        ()
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tail)*)
+            $cases
+            (($i) { $body })
+        )
+    };
+    // The last close case with an expression.
+    (@list
+        (on_close($i:pat) => $body:expr)
+        $cases:tt
+        ()
+    ) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ()
+            $cases
+            (($i) { $body })
+        )
+    };
+    // Print an error on an unknown case.
+    (@list
+        ($case:ident $($tail:tt)*)
+        $cases:tt
+        $close:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "expected one of `recv` or `on_close`, found `",
+                crossbeam_channel_delegate!(stringify($case)),
+                "`",
+            ))
+        ))
+    };
+    // Print an error on anything else.
+    (@list
+        ($($tail:tt)*)
+        $cases:tt
+        $close:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "invalid syntax in `select_loop!`: ",
+                crossbeam_channel_delegate!(stringify($($tail)*)),
+            ))
+        ))
+    };
+
+    // All receivers are bound. Now run the loop.
+    (@add
+        ()
+        $labels:tt
+        $cases:tt
+        $close:tt
+    ) => {
+        crossbeam_channel_select_loop!(@run $cases $close)
+    };
+    // Print an error if there are too many cases.
+    (@add
+        $input:tt
+        ()
+        $cases:tt
+        $close:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error("too many operations in a `select_loop!` block"))
+    };
+    // Bind a receiver to a variable so that it is evaluated only once.
+    (@add
+        ([recv($r:expr) -> $res:pat => $body:tt] $($tail:tt)*)
+        (($i:tt $var:ident) $($labels:tt)*)
+        ($($cases:tt)*)
+        $close:tt
+    ) => {
+        match $r {
+            ref _r => {
+                let $var: &$crate::Receiver<_> = _r;
+                crossbeam_channel_select_loop!(
+                    @add
+                    ($($tail)*)
+                    ($($labels)*)
+                    ($($cases)* [$i] recv($var) -> $res => $body,)
+                    $close
+                )
+            }
+        }
+    };
+
+    // There is nothing to receive from.
+    (@run
+        ()
+        $close:tt
+    ) => {{}};
+    // Select over the open receivers until all of them are closed.
+    (@run
+        ($([$i:tt] recv($var:ident) -> $res:pat => $body:tt,)*)
+        $close:tt
+    ) => {{
+        let mut _sel = $crate::Select::new();
+        let mut _open = 0usize;
+        $(
+            _sel.recv($var);
+            _open += 1;
+        )*
+
+        while _open > 0 {
+            #[allow(unreachable_code)]
+            let _closed: ::std::option::Option<usize> = {
+                let _oper = _sel.select();
+                let _index = _oper.index();
+                $(
+                    if _index == $i {
+                        match _oper.recv($var) {
+                            ::std::result::Result::Ok($res) => {
+                                $body;
+                                ::std::option::Option::None
+                            }
+                            ::std::result::Result::Err(_) => ::std::option::Option::Some($i),
+                        }
+                    } else
+                )* {
+                    crossbeam_channel_delegate!(unreachable(
+                        "selected an operation that wasn't added to `select_loop!`"
+                    ))
+                }
+            };
+
+            if let ::std::option::Option::Some(_index) = _closed {
+                _sel.disarm(_index);
+                _open -= 1;
+                crossbeam_channel_select_loop!(@close _index $close);
+            }
+        }
+    }};
+
+    // There is no close case.
+    (@close $index:ident ()) => {};
+    // Run the close case with the index of the closed receiver.
+    (@close $index:ident (($i:pat) $body:tt)) => {{
+        let $i = $index;
+        $body;
+    }};
+}
+
+/// Receives messages from a set of channels in a loop until all of them are disconnected.
+///
+/// Each `recv(r) -> msg => body` case runs its body with every message received from `r`. Once a
+/// receiver is disconnected and empty, its case is removed from the loop and the optional
+/// `on_close(i) => body` case runs with `i` bound to the position of the removed case, counting
+/// `recv` cases from zero. The loop ends when every receiver has been closed.
+///
+/// This replaces the usual hand-written [`select!`] loop that keeps a flag per receiver to skip
+/// arms of disconnected channels. Bodies may use `break` to leave the loop early and `continue`
+/// to go on to the next message.
+///
+/// Receivers are evaluated once, before the loop starts. At most 32 `recv` cases are supported.
+///
+/// [`select!`]: macro.select.html
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use std::thread;
+/// use crossbeam_channel::unbounded;
+///
+/// let (s1, r1) = unbounded();
+/// let (s2, r2) = unbounded();
+///
+/// thread::spawn(move || {
+///     for i in 0..3 {
+///         s1.send(i).unwrap();
+///     }
+/// });
+/// thread::spawn(move || s2.send("done").unwrap());
+///
+/// let mut sum = 0;
+/// let mut closed = Vec::new();
+///
+/// select_loop! {
+///     recv(r1) -> n => sum += n,
+///     recv(r2) -> msg => assert_eq!(msg, "done"),
+///     on_close(i) => closed.push(i),
+/// }
+///
+/// assert_eq!(sum, 3);
+/// closed.sort();
+/// assert_eq!(closed, [0, 1]);
+/// # }
+/// ```
+///
+/// Stop early with `break`:
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// for i in 0..10 {
+///     s.send(i).unwrap();
+/// }
+///
+/// let mut last = 0;
+/// select_loop! {
+///     recv(r) -> n => {
+///         last = n;
+///         if n == 4 {
+///             break;
+///         }
+///     }
+/// }
+///
+/// assert_eq!(last, 4);
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! select_loop {
+    ($($tokens:tt)*) => {
+        crossbeam_channel_select_loop!(
+            @list
+            ($($tokens)*)
+            ()
+            ()
+        )
+    };
+}
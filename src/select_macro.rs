@@ -24,6 +24,36 @@ macro_rules! crossbeam_channel_delegate {
     (compile_error($($args:tt)*)) => {
         compile_error!($($args)*)
     };
+    (strict_dup(($case:ident ($($ep:tt)*)) ($($other:tt)*) $d:tt)) => {{
+        #[allow(unused_macros)]
+        macro_rules! crossbeam_channel_duplicate {
+            ($case ($($ep)*)) => {
+                compile_error!(concat!(
+                    "duplicate `",
+                    stringify!($case),
+                    "(",
+                    stringify!($($ep)*),
+                    ")` case in a strict `select!` block",
+                ));
+            };
+            ($d($d t:tt)*) => {};
+        }
+        $(crossbeam_channel_duplicate! $other;)*
+    }};
+    (strict_closed(($(($($closed:tt)*))*) ($($recv:tt)*) $d:tt)) => {{
+        #[allow(unused_macros)]
+        macro_rules! crossbeam_channel_unclosed {
+            $(($($closed)*) => {};)*
+            ($d($d t:tt)*) => {
+                compile_error!(concat!(
+                    "`recv(",
+                    stringify!($d($d t)*),
+                    ")` case has no matching `closed` case in a strict `select!` block",
+                ));
+            };
+        }
+        $(crossbeam_channel_unclosed! $recv;)*
+    }};
 }
 
 /// A helper macro for `select!` to hide the long list of macro patterns from the documentation.
@@ -38,9 +68,12 @@ macro_rules! crossbeam_channel_delegate {
 /// 3. `@case`: Parses a single case and verifies its argument list.
 ///
 /// The codegen stage consists of these subparts:
-/// 1. `@init`: Attempts to optimize `select!` away and initializes a `Select`.
-/// 2. `@add`: Adds send/receive operations to the `Select` and starts selection.
-/// 3. `@complete`: Completes the selected send/receive operation.
+/// 1. `@init`: Attempts to optimize `select!` away and initializes the list of handles.
+/// 2. `@count`: Counts the listed cases.
+/// 3. `@add`: Adds send/receive/custom operations to the list of handles and starts selection.
+/// 4. `@complete`: Completes the selected send/receive/custom operation.
+///
+/// The list of handles is an array on the stack, so selection doesn't allocate.
 ///
 /// If the parsing stage encounters a syntax error or the codegen stage ends up with too many
 /// cases to process, the macro fails with a compile-time error.
@@ -180,13 +213,19 @@ macro_rules! crossbeam_channel_internal {
     (@list_error1 send $($tail:tt)*) => {
         crossbeam_channel_internal!(@list_error2 send $($tail)*)
     };
+    (@list_error1 custom $($tail:tt)*) => {
+        crossbeam_channel_internal!(@list_error2 custom $($tail)*)
+    };
+    (@list_error1 closed $($tail:tt)*) => {
+        crossbeam_channel_internal!(@list_error2 closed $($tail)*)
+    };
     (@list_error1 default $($tail:tt)*) => {
         crossbeam_channel_internal!(@list_error2 default $($tail)*)
     };
     (@list_error1 $t:tt $($tail:tt)*) => {
         crossbeam_channel_delegate!(compile_error(
             crossbeam_channel_delegate!(concat(
-                "expected one of `recv`, `send`, or `default`, found `",
+                "expected one of `recv`, `send`, `custom`, `closed`, or `default`, found `",
                 crossbeam_channel_delegate!(stringify($t)),
                 "`",
             ))
@@ -251,6 +290,16 @@ macro_rules! crossbeam_channel_internal {
             "expected an expression after `=>`"
         ))
     };
+    (@list_error3 $case:ident($($args:tt)*) $(-> $r:pat)* => custom($($a:tt)*) $($tail:tt)*) => {
+        crossbeam_channel_delegate!(compile_error(
+            "expected an expression after `=>`"
+        ))
+    };
+    (@list_error3 $case:ident($($args:tt)*) $(-> $r:pat)* => closed($($a:tt)*) $($tail:tt)*) => {
+        crossbeam_channel_delegate!(compile_error(
+            "expected an expression after `=>`"
+        ))
+    };
     (@list_error3 $case:ident($($args:tt)*) $(-> $r:pat)* => default($($a:tt)*) $($tail:tt)*) => {
         crossbeam_channel_delegate!(compile_error(
             "expected an expression after `=>`"
@@ -366,6 +415,24 @@ macro_rules! crossbeam_channel_internal {
             ))
         ))
     };
+    (@list_error3 custom $args:tt $($tail:tt)*) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "expected an argument list after `custom`, found `",
+                crossbeam_channel_delegate!(stringify($args)),
+                "`",
+            ))
+        ))
+    };
+    (@list_error3 closed $args:tt $($tail:tt)*) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "expected an argument list after `closed`, found `",
+                crossbeam_channel_delegate!(stringify($args)),
+                "`",
+            ))
+        ))
+    };
     (@list_error3 default $args:tt $($tail:tt)*) => {
         crossbeam_channel_delegate!(compile_error(
             crossbeam_channel_delegate!(concat(
@@ -383,6 +450,21 @@ macro_rules! crossbeam_channel_internal {
         crossbeam_channel_delegate!(compile_error("invalid syntax"))
     };
 
+    // Check the cases of a strict block before parsing them.
+    (@case
+        (@strict $d:tt $($tail:tt)*)
+        $cases:tt
+        $default:tt
+    ) => {{
+        crossbeam_channel_internal!(@strict $d () ($($tail)*));
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            $cases
+            $default
+        )
+    }};
+
     // Success! All cases were parsed.
     (@case
         ()
@@ -460,7 +542,7 @@ macro_rules! crossbeam_channel_internal {
         crossbeam_channel_internal!(
             @case
             ($($tail)*)
-            ($($cases)* send($s, $m) -> $res => $body,)
+            ($($cases)* send($s, $m, 0) -> $res => $body,)
             $default
         )
     };
@@ -473,7 +555,33 @@ macro_rules! crossbeam_channel_internal {
         crossbeam_channel_internal!(
             @case
             ($($tail)*)
-            ($($cases)* send($s, $m) -> $res => $body,)
+            ($($cases)* send($s, $m, 0) -> $res => $body,)
+            $default
+        )
+    };
+    // Check the format of a send case with a priority.
+    (@case
+        (send($s:expr, $m:expr, priority = $p:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            ($($cases)* send($s, $m, $p) -> $res => $body,)
+            $default
+        )
+    };
+    // Allow trailing comma...
+    (@case
+        (send($s:expr, $m:expr, priority = $p:expr,) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            ($($cases)* send($s, $m, $p) -> $res => $body,)
             $default
         )
     };
@@ -506,6 +614,136 @@ macro_rules! crossbeam_channel_internal {
         ))
     };
 
+    // Check the format of a custom case.
+    (@case
+        (custom($r:expr) => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            ($($cases)* custom($r) -> _ => $body,)
+            $default
+        )
+    };
+    // Allow trailing comma...
+    (@case
+        (custom($r:expr,) => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            ($($cases)* custom($r) -> _ => $body,)
+            $default
+        )
+    };
+    // Print an error if there is a result.
+    (@case
+        (custom($($args:tt)*) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            "expected `=>` after `custom` case, found `->`"
+        ))
+    };
+    // Print an error if the argument list is invalid.
+    (@case
+        (custom($($args:tt)*) => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "invalid argument list in `custom(",
+                crossbeam_channel_delegate!(stringify($($args)*)),
+                ")`",
+            ))
+        ))
+    };
+    // Print an error if there is no argument list.
+    (@case
+        (custom $t:tt $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "expected an argument list after `custom`, found `",
+                crossbeam_channel_delegate!(stringify($t)),
+                "`",
+            ))
+        ))
+    };
+
+    // Check the format of a closed case.
+    (@case
+        (closed($r:expr) => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            ($($cases)* closed($r) -> _ => $body,)
+            $default
+        )
+    };
+    // Allow trailing comma...
+    (@case
+        (closed($r:expr,) => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_internal!(
+            @case
+            ($($tail)*)
+            ($($cases)* closed($r) -> _ => $body,)
+            $default
+        )
+    };
+    // Print an error if there is a result.
+    (@case
+        (closed($($args:tt)*) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            "expected `=>` after `closed` case, found `->`"
+        ))
+    };
+    // Print an error if the argument list is invalid.
+    (@case
+        (closed($($args:tt)*) => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "invalid argument list in `closed(",
+                crossbeam_channel_delegate!(stringify($($args)*)),
+                ")`",
+            ))
+        ))
+    };
+    // Print an error if there is no argument list.
+    (@case
+        (closed $t:tt $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        crossbeam_channel_delegate!(compile_error(
+            crossbeam_channel_delegate!(concat(
+                "expected an argument list after `closed`, found `",
+                crossbeam_channel_delegate!(stringify($t)),
+                "`",
+            ))
+        ))
+    };
+
     // Check the format of a default case.
     (@case
         (default() => $body:tt, $($tail:tt)*)
@@ -592,7 +830,7 @@ macro_rules! crossbeam_channel_internal {
     ) => {
         crossbeam_channel_delegate!(compile_error(
             crossbeam_channel_delegate!(concat(
-                "expected one of `recv`, `send`, or `default`, found `",
+                "expected one of `recv`, `send`, `custom`, `closed`, or `default`, found `",
                 crossbeam_channel_delegate!(stringify($case)),
                 "`",
             ))
@@ -754,16 +992,22 @@ macro_rules! crossbeam_channel_internal {
     //     }
     // }};
 
-    // Create a `Select` and add operations to it.
+    // Create the list of handles and add operations to it.
     (@init
         ($($cases:tt)*)
         $default:tt
     ) => {{
+        const _LEN: usize = crossbeam_channel_internal!(@count ($($cases)*));
+        let _handle: &$crate::internal::SelectHandle = &$crate::never::<()>();
+
+        #[allow(unused_mut)]
+        let mut _sel = [(_handle, 0, ::std::ptr::null()); _LEN];
         #[allow(unused_mut)]
-        let mut _sel = $crate::Select::new();
+        let mut _prio: [::std::option::Option<i32>; _LEN] = [::std::option::Option::None; _LEN];
         crossbeam_channel_internal!(
             @add
             _sel
+            _prio
             ($($cases)*)
             $default
             (
@@ -785,7 +1029,7 @@ macro_rules! crossbeam_channel_internal {
                 (15usize _oper15)
                 (16usize _oper16)
                 (17usize _oper17)
-                (20usize _oper18)
+                (18usize _oper18)
                 (19usize _oper19)
                 (20usize _oper20)
                 (21usize _oper21)
@@ -804,16 +1048,25 @@ macro_rules! crossbeam_channel_internal {
         )
     }};
 
+    // Count the listed cases.
+    (@count ()) => {
+        0
+    };
+    (@count ($oper:ident $args:tt -> $res:pat => $body:tt, $($cases:tt)*)) => {
+        1 + crossbeam_channel_internal!(@count ($($cases)*))
+    };
+
     // Run blocking selection.
     (@add
         $sel:ident
+        $prio:ident
         ()
         ()
         $labels:tt
         $cases:tt
     ) => {{
         let _oper: $crate::SelectedOperation<'_> = {
-            let _oper = $sel.select();
+            let _oper = $crate::internal::select(&mut $sel, &$prio);
 
             // Erase the lifetime so that `sel` can be dropped early even without NLL.
             #[allow(unsafe_code)]
@@ -825,18 +1078,20 @@ macro_rules! crossbeam_channel_internal {
             $sel
             _oper
             $cases
+            $cases
         }
     }};
     // Run non-blocking selection.
     (@add
         $sel:ident
+        $prio:ident
         ()
         (default() => $body:tt,)
         $labels:tt
         $cases:tt
     ) => {{
         let _oper: ::std::option::Option<$crate::SelectedOperation<'_>> = {
-            let _oper = $sel.try_select();
+            let _oper = $crate::internal::try_select(&mut $sel, &$prio);
 
             // Erase the lifetime so that `sel` can be dropped early even without NLL.
             #[allow(unsafe_code)]
@@ -854,6 +1109,7 @@ macro_rules! crossbeam_channel_internal {
                     $sel
                     _oper
                     $cases
+                    $cases
                 }
             }
         }
@@ -861,13 +1117,14 @@ macro_rules! crossbeam_channel_internal {
     // Run selection with a timeout.
     (@add
         $sel:ident
+        $prio:ident
         ()
         (default($timeout:expr) => $body:tt,)
         $labels:tt
         $cases:tt
     ) => {{
         let _oper: ::std::option::Option<$crate::SelectedOperation<'_>> = {
-            let _oper = $sel.select_timeout($timeout);
+            let _oper = $crate::internal::select_timeout(&mut $sel, &$prio, $timeout);
 
             // Erase the lifetime so that `sel` can be dropped early even without NLL.
             #[allow(unsafe_code)]
@@ -885,6 +1142,7 @@ macro_rules! crossbeam_channel_internal {
                     $sel
                     _oper
                     $cases
+                    $cases
                 }
             }
         }
@@ -892,6 +1150,7 @@ macro_rules! crossbeam_channel_internal {
     // Have we used up all labels?
     (@add
         $sel:ident
+        $prio:ident
         $input:tt
         $default:tt
         ()
@@ -899,9 +1158,10 @@ macro_rules! crossbeam_channel_internal {
     ) => {
         crossbeam_channel_delegate!(compile_error("too many operations in a `select!` block"))
     };
-    // Add a receive operation to `sel`.
+    // Add a receive operation to the list of handles.
     (@add
         $sel:ident
+        $prio:ident
         (recv($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
         $default:tt
         (($i:tt $var:ident) $($labels:tt)*)
@@ -919,11 +1179,12 @@ macro_rules! crossbeam_channel_internal {
                     }
                     unbind(_r)
                 };
-                $sel.recv($var);
+                $sel[$i] = ($var, $i, $var as *const $crate::Receiver<_> as *const u8);
 
                 crossbeam_channel_internal!(
                     @add
                     $sel
+                    $prio
                     ($($tail)*)
                     $default
                     ($($labels)*)
@@ -932,10 +1193,11 @@ macro_rules! crossbeam_channel_internal {
             }
         }
     }};
-    // Add a send operation to `sel`.
+    // Add a send operation to the list of handles.
     (@add
         $sel:ident
-        (send($s:expr, $m:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        $prio:ident
+        (send($s:expr, $m:expr, $p:expr) -> $res:pat => $body:tt, $($tail:tt)*)
         $default:tt
         (($i:tt $var:ident) $($labels:tt)*)
         ($($cases:tt)*)
@@ -952,11 +1214,13 @@ macro_rules! crossbeam_channel_internal {
                     }
                     unbind(_s)
                 };
-                $sel.send($var);
+                $sel[$i] = ($var, $i, $var as *const $crate::Sender<_> as *const u8);
+                $prio[$i] = ::std::option::Option::Some($p);
 
                 crossbeam_channel_internal!(
                     @add
                     $sel
+                    $prio
                     ($($tail)*)
                     $default
                     ($($labels)*)
@@ -965,25 +1229,106 @@ macro_rules! crossbeam_channel_internal {
             }
         }
     }};
+    // Add an operation on a custom primitive to the list of handles.
+    (@add
+        $sel:ident
+        $prio:ident
+        (custom($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        $default:tt
+        (($i:tt $var:ident) $($labels:tt)*)
+        ($($cases:tt)*)
+    ) => {{
+        match $r {
+            ref _r => {
+                #[allow(unsafe_code)]
+                let $var = unsafe {
+                    // Erase the lifetime so that `sel` can be dropped early even without NLL.
+                    unsafe fn unbind<'a, T>(x: &T) -> &'a T {
+                        ::std::mem::transmute(x)
+                    }
+                    unbind(_r)
+                };
+                $sel[$i] = ($crate::internal::custom($var), $i, $var as *const _ as *const u8);
+
+                crossbeam_channel_internal!(
+                    @add
+                    $sel
+                    $prio
+                    ($($tail)*)
+                    $default
+                    ($($labels)*)
+                    ($($cases)* [$i] custom($var) -> _ => $body,)
+                )
+            }
+        }
+    }};
+
+    // Add an operation waiting for a channel to be closed to the list of handles.
+    (@add
+        $sel:ident
+        $prio:ident
+        (closed($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        $default:tt
+        (($i:tt $var:ident) $($labels:tt)*)
+        ($($cases:tt)*)
+    ) => {{
+        match $r {
+            ref _r => {
+                #[allow(unsafe_code)]
+                let $var: &$crate::Receiver<_> = unsafe {
+                    let _r: &$crate::Receiver<_> = _r;
+
+                    // Erase the lifetime so that `sel` can be dropped early even without NLL.
+                    unsafe fn unbind<'a, T>(x: &T) -> &'a T {
+                        ::std::mem::transmute(x)
+                    }
+                    unbind(_r)
+                };
+                $sel[$i] = (
+                    $crate::internal::closed($var),
+                    $i,
+                    $var as *const $crate::Receiver<_> as *const u8,
+                );
+
+                crossbeam_channel_internal!(
+                    @add
+                    $sel
+                    $prio
+                    ($($tail)*)
+                    $default
+                    ($($labels)*)
+                    ($($cases)* [$i] closed($var) -> _ => $body,)
+                )
+            }
+        }
+    }};
 
     // Complete a receive operation.
     (@complete
         $sel:ident
         $oper:ident
         ([$i:tt] recv($r:ident) -> $res:pat => $body:tt, $($tail:tt)*)
+        $all:tt
     ) => {{
         if $oper.index() == $i {
             let _res = $oper.recv($r);
             ::std::mem::drop($sel);
 
-            let $res = _res;
-            $body
+            crossbeam_channel_internal! {
+                @closed
+                $r
+                _res
+                ($res)
+                $body
+                $all
+            }
         } else {
             crossbeam_channel_internal! {
                 @complete
                 $sel
                 $oper
                 ($($tail)*)
+                $all
             }
         }
     }};
@@ -992,6 +1337,7 @@ macro_rules! crossbeam_channel_internal {
         $sel:ident
         $oper:ident
         ([$i:tt] send($s:ident, $m:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        $all:tt
     ) => {{
         if $oper.index() == $i {
             let _res = $oper.send($s, $m);
@@ -1005,6 +1351,51 @@ macro_rules! crossbeam_channel_internal {
                 $sel
                 $oper
                 ($($tail)*)
+                $all
+            }
+        }
+    }};
+    // Complete an operation on a custom primitive.
+    (@complete
+        $sel:ident
+        $oper:ident
+        ([$i:tt] custom($r:ident) -> $res:pat => $body:tt, $($tail:tt)*)
+        $all:tt
+    ) => {{
+        if $oper.index() == $i {
+            $oper.custom($r);
+            ::std::mem::drop($sel);
+
+            $body
+        } else {
+            crossbeam_channel_internal! {
+                @complete
+                $sel
+                $oper
+                ($($tail)*)
+                $all
+            }
+        }
+    }};
+    // Complete an operation waiting for a channel to be closed.
+    (@complete
+        $sel:ident
+        $oper:ident
+        ([$i:tt] closed($r:ident) -> $res:pat => $body:tt, $($tail:tt)*)
+        $all:tt
+    ) => {{
+        if $oper.index() == $i {
+            $oper.closed($r);
+            ::std::mem::drop($sel);
+
+            $body
+        } else {
+            crossbeam_channel_internal! {
+                @complete
+                $sel
+                $oper
+                ($($tail)*)
+                $all
             }
         }
     }};
@@ -1013,12 +1404,143 @@ macro_rules! crossbeam_channel_internal {
         $sel:ident
         $oper:ident
         ()
+        $all:tt
     ) => {{
         crossbeam_channel_delegate!(unreachable(
             "internal error in crossbeam-channel: invalid case"
         ))
     }};
 
+    // No `closed` case is left, so run the receive case.
+    (@closed
+        $r:ident
+        $v:ident
+        ($res:pat)
+        $body:tt
+        ()
+    ) => {{
+        let $res = $v;
+        $body
+    }};
+    // Run the `closed` case instead if the receive operation failed on its channel.
+    (@closed
+        $r:ident
+        $v:ident
+        $res:tt
+        $body:tt
+        ([$i:tt] closed($c:ident) -> _ => $closed_body:tt, $($tail:tt)*)
+    ) => {{
+        if $v.is_err() && $crate::internal::same_channel($r, $c) {
+            $closed_body
+        } else {
+            crossbeam_channel_internal! {
+                @closed
+                $r
+                $v
+                $res
+                $body
+                ($($tail)*)
+            }
+        }
+    }};
+    // Skip other cases.
+    (@closed
+        $r:ident
+        $v:ident
+        $res:tt
+        $body:tt
+        ([$i:tt] $case:ident $args:tt -> $p:pat => $b:tt, $($tail:tt)*)
+    ) => {
+        crossbeam_channel_internal! {
+            @closed
+            $r
+            $v
+            $res
+            $body
+            ($($tail)*)
+        }
+    };
+
+    // All cases of a strict block were collected, so check them.
+    (@strict
+        $d:tt
+        ($($ops:tt)*)
+        ()
+    ) => {
+        crossbeam_channel_internal!(@strict_dup $d ($($ops)*));
+        crossbeam_channel_internal!(@strict_closed $d () () ($($ops)*));
+    };
+    // Skip the default case, which doesn't operate on a channel.
+    (@strict
+        $d:tt
+        $ops:tt
+        (default $args:tt $(-> $res:pat)* => $body:tt, $($tail:tt)*)
+    ) => {
+        crossbeam_channel_internal!(@strict $d $ops ($($tail)*))
+    };
+    // Collect the operation and the channel endpoint of a case.
+    (@strict
+        $d:tt
+        $ops:tt
+        ($case:ident ($($args:tt)*) $(-> $res:pat)* => $body:tt, $($tail:tt)*)
+    ) => {
+        crossbeam_channel_internal!(@strict_endpoint $d $ops $case () ($($args)*) ($($tail)*))
+    };
+
+    // The endpoint is the first argument, so stop at the first comma.
+    (@strict_endpoint
+        $d:tt
+        ($($ops:tt)*)
+        $case:ident
+        $ep:tt
+        ()
+        $tail:tt
+    ) => {
+        crossbeam_channel_internal!(@strict $d ($($ops)* ($case $ep)) $tail)
+    };
+    (@strict_endpoint
+        $d:tt
+        ($($ops:tt)*)
+        $case:ident
+        $ep:tt
+        (, $($args:tt)*)
+        $tail:tt
+    ) => {
+        crossbeam_channel_internal!(@strict $d ($($ops)* ($case $ep)) $tail)
+    };
+    (@strict_endpoint
+        $d:tt
+        $ops:tt
+        $case:ident
+        ($($ep:tt)*)
+        ($t:tt $($args:tt)*)
+        $tail:tt
+    ) => {
+        crossbeam_channel_internal!(@strict_endpoint $d $ops $case ($($ep)* $t) ($($args)*) $tail)
+    };
+
+    // Reject operations that appear twice on the same endpoint.
+    (@strict_dup $d:tt ()) => {};
+    (@strict_dup $d:tt ($op:tt)) => {};
+    (@strict_dup $d:tt ($op:tt $($ops:tt)*)) => {
+        crossbeam_channel_delegate!(strict_dup($op ($($ops)*) $d));
+        crossbeam_channel_internal!(@strict_dup $d ($($ops)*));
+    };
+
+    // Reject receive operations without a closed case on the same endpoint.
+    (@strict_closed $d:tt $closed:tt $recv:tt ()) => {
+        crossbeam_channel_delegate!(strict_closed($closed $recv $d));
+    };
+    (@strict_closed $d:tt ($($closed:tt)*) $recv:tt ((closed $ep:tt) $($ops:tt)*)) => {
+        crossbeam_channel_internal!(@strict_closed $d ($($closed)* $ep) $recv ($($ops)*));
+    };
+    (@strict_closed $d:tt $closed:tt ($($recv:tt)*) ((recv $ep:tt) $($ops:tt)*)) => {
+        crossbeam_channel_internal!(@strict_closed $d $closed ($($recv)* $ep) ($($ops)*));
+    };
+    (@strict_closed $d:tt $closed:tt $recv:tt ($op:tt $($ops:tt)*)) => {
+        crossbeam_channel_internal!(@strict_closed $d $closed $recv ($($ops)*));
+    };
+
     // Catches a bug within this macro (should not happen).
     (@$($tokens:tt)*) => {
         crossbeam_channel_delegate!(compile_error(
@@ -1033,6 +1555,16 @@ macro_rules! crossbeam_channel_internal {
     () => {
         crossbeam_channel_delegate!(compile_error("empty `select!` block"))
     };
+    (strict;) => {
+        crossbeam_channel_delegate!(compile_error("empty `select!` block"))
+    };
+    (strict; $($tokens:tt)*) => {
+        crossbeam_channel_internal!(
+            @list
+            ($($tokens)*)
+            (@strict $)
+        )
+    };
     ($($case:ident $(($($args:tt)*))* => $body:expr $(,)*)*) => {
         crossbeam_channel_internal!(
             @list
@@ -1061,10 +1593,34 @@ macro_rules! crossbeam_channel_internal {
 /// An operation is considered to be ready if it doesn't have to block. Note that it is ready even
 /// when it will simply return an error because the channel is disconnected.
 ///
+/// Besides channel operations, a `custom(x) => ...` case waits for any primitive implementing
+/// [`Selectable`] to become ready, e.g. a [`Trigger`].
+///
+/// A send case can be given a priority as in `send(s, msg, priority = 2) -> res => ...`, where
+/// the priority is an `i32` and defaults to zero. When multiple send operations are ready at the
+/// same time, the one with the highest priority is selected rather than a random one. Receive and
+/// custom operations are not affected by priorities and are still selected at random, so a ready
+/// receive operation may be selected before a ready send operation of any priority.
+///
+/// A `closed(r) => ...` case becomes ready once the channel is disconnected and empty, i.e. when
+/// no more messages can ever be received from it. If the same block also has a `recv(r)` case on
+/// that channel, the receive case only handles messages and the `closed` case handles the
+/// disconnection. Which `closed` case a failed receive operation is routed to is decided at run
+/// time by comparing the channels the receivers belong to, so this also works for clones of the
+/// same receiver.
+///
+/// A block starting with `strict;` is additionally checked at compile time: the same operation may
+/// not appear twice, and every `recv(r)` case must be paired with a `closed(r)` case. This check is
+/// purely syntactic and compares operations by their tokens, so `recv(r)` and `recv(r.clone())`
+/// count as different operations, and two different expressions referring to the same channel are
+/// not detected as duplicates.
+///
 /// The `select` macro is a convenience wrapper around [`Select`]. However, it cannot select over a
 /// dynamically created list of channel operations.
 ///
 /// [`Select`]: struct.Select.html
+/// [`Selectable`]: trait.Selectable.html
+/// [`Trigger`]: struct.Trigger.html
 ///
 /// # Examples
 ///
@@ -1153,6 +1709,72 @@ macro_rules! crossbeam_channel_internal {
 /// }
 /// # }
 /// ```
+///
+/// Prefer one output over another when both have room:
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::bounded;
+///
+/// let (fast, fast_r) = bounded(1);
+/// let (slow, slow_r) = bounded(1);
+///
+/// // The fast output is always chosen while it has room.
+/// for msg in 0..2 {
+///     select! {
+///         send(fast, msg, priority = 1) -> res => res.unwrap(),
+///         send(slow, msg) -> res => res.unwrap(),
+///     }
+/// }
+///
+/// assert_eq!(fast_r.try_recv(), Ok(0));
+/// assert_eq!(slow_r.try_recv(), Ok(1));
+/// # }
+/// ```
+///
+/// Drain a channel and handle its disconnection in a separate case:
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// s.send(1).unwrap();
+/// s.send(2).unwrap();
+/// drop(s);
+///
+/// let mut received = Vec::new();
+/// loop {
+///     select! {
+///         strict;
+///         recv(r) -> msg => received.push(msg.unwrap()),
+///         closed(r) => break,
+///     }
+/// }
+/// assert_eq!(received, [1, 2]);
+/// # }
+/// ```
+///
+/// A strict block that doesn't handle disconnection fails to compile:
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded::<i32>();
+/// select! {
+///     strict;
+///     recv(r) -> msg => {}
+///     send(s, 1) -> res => {}
+/// }
+/// # }
+/// ```
 #[macro_export(local_inner_macros)]
 macro_rules! select {
     ($($tokens:tt)*) => {
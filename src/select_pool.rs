@@ -0,0 +1,363 @@
+//! A changing collection of receivers that are waited on together.
+
+use std::fmt;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+use channel::Receiver;
+use err::{RecvError, RecvTimeoutError, TryRecvError};
+use select::{Select, Timeout};
+
+/// A changing collection of receivers that are waited on together.
+///
+/// Receivers are added with [`insert`], which returns a key identifying the receiver, and can be
+/// taken out again with [`remove`]. Receiving from the pool waits for a message from any of its
+/// receivers and returns it together with the key of the receiver it came from.
+///
+/// Receivers that become disconnected and empty are dropped from the pool automatically, so their
+/// keys are no longer [contained] in it. Once the pool has no receivers left, receiving from it
+/// fails as if it was a disconnected channel.
+///
+/// Keys are assigned in increasing order and never reused, so a stale key can't refer to a
+/// receiver that was inserted later.
+///
+/// [`insert`]: struct.SelectPool.html#method.insert
+/// [`remove`]: struct.SelectPool.html#method.remove
+/// [contained]: struct.SelectPool.html#method.contains
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::{unbounded, SelectPool};
+///
+/// let mut pool = SelectPool::new();
+/// let mut senders = Vec::new();
+///
+/// // One channel per connection.
+/// for id in 0..3 {
+///     let (s, r) = unbounded();
+///     let key = pool.insert(r);
+///     senders.push(s);
+///     assert_eq!(key, id);
+/// }
+///
+/// for (id, s) in senders.into_iter().enumerate() {
+///     thread::spawn(move || {
+///         s.send(id * 10).unwrap();
+///     });
+/// }
+///
+/// let mut received = Vec::new();
+/// while let Ok((key, msg)) = pool.recv() {
+///     assert_eq!(key * 10, msg);
+///     received.push(key);
+/// }
+///
+/// // All connections are closed.
+/// received.sort();
+/// assert_eq!(received, [0, 1, 2]);
+/// assert!(pool.is_empty());
+/// ```
+pub struct SelectPool<T> {
+    /// Receivers in the pool, with their keys, in the order they were inserted.
+    entries: Vec<(usize, Receiver<T>)>,
+
+    /// The key of the next inserted receiver.
+    next_key: usize,
+}
+
+impl<T> SelectPool<T> {
+    /// Creates an empty pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{SelectPool, TryRecvError};
+    ///
+    /// let mut pool = SelectPool::<i32>::new();
+    /// assert!(pool.is_empty());
+    /// assert_eq!(pool.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn new() -> SelectPool<T> {
+        SelectPool {
+            entries: Vec::new(),
+            next_key: 0,
+        }
+    }
+
+    /// Adds a receiver to the pool and returns its key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectPool};
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut pool = SelectPool::new();
+    /// let key = pool.insert(r);
+    ///
+    /// s.send("hello").unwrap();
+    /// assert_eq!(pool.recv(), Ok((key, "hello")));
+    /// ```
+    pub fn insert(&mut self, r: Receiver<T>) -> usize {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.entries.push((key, r));
+        key
+    }
+
+    /// Removes the receiver with the given key from the pool and returns it.
+    ///
+    /// If there is no such receiver, `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectPool, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut pool = SelectPool::new();
+    /// let key = pool.insert(r);
+    ///
+    /// s.send(1).unwrap();
+    /// let r = pool.remove(key).unwrap();
+    /// assert!(pool.remove(key).is_none());
+    ///
+    /// // The message stays in the channel.
+    /// assert_eq!(pool.try_recv(), Err(TryRecvError::Disconnected));
+    /// assert_eq!(r.try_recv(), Ok(1));
+    /// ```
+    pub fn remove(&mut self, key: usize) -> Option<Receiver<T>> {
+        match self.entries.iter().position(|&(k, _)| k == key) {
+            None => None,
+            Some(index) => Some(self.entries.remove(index).1),
+        }
+    }
+
+    /// Returns the receiver with the given key.
+    ///
+    /// If there is no such receiver, `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectPool};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let mut pool = SelectPool::new();
+    /// let key = pool.insert(r);
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(pool.get(key).map(|r| r.len()), Some(1));
+    /// assert!(pool.get(key + 1).is_none());
+    /// ```
+    pub fn get(&self, key: usize) -> Option<&Receiver<T>> {
+        self.entries
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|entry| &entry.1)
+    }
+
+    /// Returns `true` if the pool contains a receiver with the given key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, RecvError, SelectPool};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let mut pool = SelectPool::new();
+    /// let key = pool.insert(r);
+    /// assert!(pool.contains(key));
+    ///
+    /// // Disconnected receivers are dropped from the pool while receiving.
+    /// drop(s);
+    /// assert_eq!(pool.recv(), Err(RecvError));
+    /// assert!(!pool.contains(key));
+    /// ```
+    pub fn contains(&self, key: usize) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of receivers in the pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectPool};
+    ///
+    /// let mut pool = SelectPool::new();
+    /// pool.insert(unbounded::<i32>().1);
+    /// pool.insert(unbounded::<i32>().1);
+    /// assert_eq!(pool.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no receivers in the pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectPool};
+    ///
+    /// let mut pool = SelectPool::new();
+    /// assert!(pool.is_empty());
+    ///
+    /// pool.insert(unbounded::<i32>().1);
+    /// assert!(!pool.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Attempts to receive a message from any receiver in the pool without blocking.
+    ///
+    /// On success, the message is returned together with the key of its receiver. Receivers found
+    /// to be disconnected and empty are dropped from the pool. If there are no receivers left, a
+    /// [`Disconnected`] error is returned.
+    ///
+    /// [`Disconnected`]: enum.TryRecvError.html#variant.Disconnected
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectPool, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut pool = SelectPool::new();
+    /// let key = pool.insert(r);
+    ///
+    /// assert_eq!(pool.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(pool.try_recv(), Ok((key, 5)));
+    /// assert_eq!(pool.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&mut self) -> Result<(usize, T), TryRecvError> {
+        match self.run(Timeout::Now) {
+            Ok(res) => Ok(res),
+            Err(RecvTimeoutError::Timeout) => Err(TryRecvError::Empty),
+            Err(RecvTimeoutError::Disconnected) => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// Blocks the current thread until a message is received from any receiver in the pool.
+    ///
+    /// On success, the message is returned together with the key of its receiver. Receivers that
+    /// become disconnected and empty while waiting are dropped from the pool. If there are no
+    /// receivers left, an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvError, SelectPool};
+    ///
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
+    ///
+    /// let mut pool = SelectPool::new();
+    /// pool.insert(r1);
+    /// let key2 = pool.insert(r2);
+    ///
+    /// thread::spawn(move || {
+    ///     drop(s1);
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s2.send(2).unwrap();
+    /// });
+    ///
+    /// assert_eq!(pool.recv(), Ok((key2, 2)));
+    /// assert_eq!(pool.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&mut self) -> Result<(usize, T), RecvError> {
+        self.run(Timeout::Never).map_err(|_| RecvError)
+    }
+
+    /// Waits for a message to be received from any receiver in the pool, but only for a limited
+    /// time.
+    ///
+    /// On success, the message is returned together with the key of its receiver. Receivers that
+    /// become disconnected and empty while waiting are dropped from the pool. If there are no
+    /// receivers left, a [`Disconnected`] error is returned.
+    ///
+    /// [`Disconnected`]: enum.RecvTimeoutError.html#variant.Disconnected
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError, SelectPool};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let mut pool = SelectPool::new();
+    /// pool.insert(r);
+    ///
+    /// assert_eq!(
+    ///     pool.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    ///
+    /// drop(s);
+    /// assert_eq!(
+    ///     pool.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Disconnected),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<(usize, T), RecvTimeoutError> {
+        self.run(Timeout::At(Instant::now() + timeout))
+    }
+
+    /// Receives a message from any receiver in the pool, dropping receivers that turn out to be
+    /// disconnected.
+    fn run(&mut self, timeout: Timeout) -> Result<(usize, T), RecvTimeoutError> {
+        loop {
+            if self.entries.is_empty() {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            let (index, res) = {
+                let mut sel = Select::with_capacity(self.entries.len());
+                for entry in &self.entries {
+                    sel.recv_local(&entry.1);
+                }
+
+                let oper = match sel.select_with(timeout) {
+                    None => return Err(RecvTimeoutError::Timeout),
+                    Some(oper) => oper,
+                };
+
+                let index = oper.index();
+                (index, oper.recv(&self.entries[index].1))
+            };
+
+            match res {
+                Ok(msg) => return Ok((self.entries[index].0, msg)),
+                Err(RecvError) => {
+                    self.entries.remove(index);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Default for SelectPool<T> {
+    fn default() -> SelectPool<T> {
+        SelectPool::new()
+    }
+}
+
+impl<T> fmt::Debug for SelectPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SelectPool")
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
@@ -0,0 +1,300 @@
+//! Custom primitives that can participate in select.
+
+use std::fmt;
+use std::time::Instant;
+
+use context::Context;
+use select::{Operation, SelectHandle, Token};
+use waker::SyncWaker;
+
+/// A primitive that can be waited on in [`Select`] and [`select!`] alongside channels.
+///
+/// Implementing this trait turns any condition into a select operation: a timer, a bridge from
+/// an async runtime, readiness of a socket, and so on. An operation on a selectable primitive
+/// carries no data. It is ready for as long as [`is_ready`] returns `true`, and selecting it just
+/// reports that it was ready. Acting on the readiness, e.g. reading from the socket, is up to the
+/// caller.
+///
+/// Blocked selections are woken up through the primitive's [`Notifier`], so whenever
+/// [`is_ready`] may have changed from `false` to `true`, the primitive must call
+/// [`Notifier::notify`].
+///
+/// Operations are added to [`Select`] with [`Select::custom`] and completed with
+/// [`SelectedOperation::custom`], or used directly with the `custom` case of [`select!`].
+///
+/// A [`Select`] can be sent to and shared between threads, so [`Select::custom`] only accepts
+/// primitives that are `Sync`. The `custom` case of [`select!`] accepts any primitive.
+///
+/// [`Select`]: struct.Select.html
+/// [`select!`]: macro.select.html
+/// [`is_ready`]: trait.Selectable.html#tymethod.is_ready
+/// [`Notifier`]: struct.Notifier.html
+/// [`Notifier::notify`]: struct.Notifier.html#method.notify
+/// [`Select::custom`]: struct.Select.html#method.custom
+/// [`SelectedOperation::custom`]: struct.SelectedOperation.html#method.custom
+///
+/// # Examples
+///
+/// A counting semaphore whose acquisition can be selected over:
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use crossbeam_channel::{unbounded, Notifier, Selectable};
+///
+/// struct Permits {
+///     count: AtomicUsize,
+///     notifier: Notifier,
+/// }
+///
+/// impl Permits {
+///     fn release(&self) {
+///         self.count.fetch_add(1, Ordering::SeqCst);
+///         self.notifier.notify();
+///     }
+/// }
+///
+/// impl Selectable for Permits {
+///     fn is_ready(&self) -> bool {
+///         self.count.load(Ordering::SeqCst) > 0
+///     }
+///
+///     fn notifier(&self) -> &Notifier {
+///         &self.notifier
+///     }
+/// }
+///
+/// let permits = Permits { count: AtomicUsize::new(0), notifier: Notifier::new() };
+/// let (s, r) = unbounded::<i32>();
+/// permits.release();
+///
+/// select! {
+///     recv(r) -> msg => panic!(),
+///     custom(permits) => permits.count.fetch_sub(1, Ordering::SeqCst),
+/// };
+/// # drop(s);
+/// # }
+/// ```
+///
+/// Primitives that can't be shared between threads are rejected by [`Select::custom`]:
+///
+/// ```compile_fail
+/// use std::cell::Cell;
+/// use crossbeam_channel::{Notifier, Select, Selectable};
+///
+/// struct Flag {
+///     set: Cell<bool>,
+///     notifier: Notifier,
+/// }
+///
+/// impl Selectable for Flag {
+///     fn is_ready(&self) -> bool {
+///         self.set.get()
+///     }
+///
+///     fn notifier(&self) -> &Notifier {
+///         &self.notifier
+///     }
+/// }
+///
+/// let flag = Flag { set: Cell::new(true), notifier: Notifier::new() };
+/// let mut sel = Select::new();
+/// sel.custom(&flag);
+/// ```
+pub trait Selectable {
+    /// Returns `true` if the operation is ready.
+    fn is_ready(&self) -> bool;
+
+    /// Returns the deadline at which the operation becomes ready, if there is one.
+    ///
+    /// Selection blocks at most until the deadline and then checks [`is_ready`] again, so a
+    /// primitive that becomes ready at a known instant doesn't have to notify anyone. The default
+    /// implementation returns `None`.
+    ///
+    /// [`is_ready`]: trait.Selectable.html#tymethod.is_ready
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Returns the notifier through which blocked selections are woken up.
+    fn notifier(&self) -> &Notifier;
+}
+
+impl<'a, S: Selectable + ?Sized> Selectable for &'a S {
+    fn is_ready(&self) -> bool {
+        (**self).is_ready()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        (**self).deadline()
+    }
+
+    fn notifier(&self) -> &Notifier {
+        (**self).notifier()
+    }
+}
+
+/// Wakes up selections blocked on a [`Selectable`] primitive.
+///
+/// [`Selectable`]: trait.Selectable.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::Notifier;
+///
+/// let notifier = Notifier::new();
+///
+/// // Nobody is waiting, so this does nothing.
+/// notifier.notify();
+/// ```
+pub struct Notifier {
+    /// Operations waiting for the primitive to become ready.
+    waiters: SyncWaker,
+}
+
+impl Notifier {
+    /// Creates a new notifier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Notifier;
+    ///
+    /// let notifier = Notifier::new();
+    /// ```
+    pub fn new() -> Notifier {
+        Notifier {
+            waiters: SyncWaker::new(),
+        }
+    }
+
+    /// Wakes up all selections blocked on the primitive.
+    ///
+    /// Woken up selections check [`Selectable::is_ready`] again, so a spurious notification is
+    /// harmless.
+    ///
+    /// [`Selectable::is_ready`]: trait.Selectable.html#tymethod.is_ready
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{Notifier, Select, Selectable};
+    ///
+    /// struct Flag(AtomicBool, Notifier);
+    ///
+    /// impl Selectable for Flag {
+    ///     fn is_ready(&self) -> bool {
+    ///         self.0.load(Ordering::SeqCst)
+    ///     }
+    ///
+    ///     fn notifier(&self) -> &Notifier {
+    ///         &self.1
+    ///     }
+    /// }
+    ///
+    /// let flag = Arc::new(Flag(AtomicBool::new(false), Notifier::new()));
+    ///
+    /// let f = flag.clone();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     f.0.store(true, Ordering::SeqCst);
+    ///     f.1.notify();
+    /// });
+    ///
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.custom(&*flag);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// oper.custom(&*flag);
+    /// ```
+    pub fn notify(&self) {
+        self.waiters.notify_all();
+    }
+
+    /// Returns the list of waiting operations.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn waiters(&self) -> &SyncWaker {
+        &self.waiters
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Notifier {
+        Notifier::new()
+    }
+}
+
+impl fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Notifier").finish()
+    }
+}
+
+/// Turns a reference to a `Selectable` primitive into a handle for the `select!` macro.
+pub fn custom<'a, S: Selectable + 'a>(s: &'a S) -> &'a (SelectHandle + 'a) {
+    Adapter::new(s)
+}
+
+/// Adapts a `Selectable` primitive to the select machinery.
+#[repr(transparent)]
+pub struct Adapter<S>(S);
+
+impl<S: Selectable> Adapter<S> {
+    /// Wraps a reference to a selectable primitive.
+    #[inline]
+    pub fn new(s: &S) -> &Adapter<S> {
+        // The adapter has the same layout as the wrapped primitive.
+        unsafe { &*(s as *const S as *const Adapter<S>) }
+    }
+}
+
+impl<S: Selectable> SelectHandle for Adapter<S> {
+    fn try(&self, _token: &mut Token) -> bool {
+        self.0.is_ready()
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        self.0.is_ready()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.0.deadline()
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.0.notifier().waiters.register(oper, cx);
+        !self.0.is_ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.notifier().waiters.unregister(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        self.0.is_ready()
+    }
+
+    fn state(&self) -> usize {
+        self.0.is_ready() as usize
+    }
+
+    fn is_ready(&self) -> bool {
+        self.0.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.0.notifier().waiters.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.notifier().waiters.unwatch(oper);
+    }
+}
@@ -0,0 +1,693 @@
+//! Channels with sequence-stamped messages.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use channel::{bounded, unbounded, Receiver, Sender};
+use context::Context;
+use err::{TryRecvError, TrySendError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvError, RecvTimeoutError, SendError};
+use select::{Operation, Ready, SelectHandle, Token};
+
+/// Creates a channel of bounded capacity whose messages are stamped with sequence numbers.
+///
+/// Every message sent into the channel is stamped with the next number of a monotonically
+/// increasing sequence, starting at zero. The receiving side keeps track of the numbers it has
+/// seen, which makes it possible to prove that messages arrive in order and to detect messages
+/// that went missing. See [`SequencedReceiver::stats`] for details.
+///
+/// Apart from stamping, the channel behaves like one created by [`bounded`].
+///
+/// # Panics
+///
+/// Panics if the capacity is zero or greater than `usize::max_value() / 4`.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::bounded_sequenced;
+///
+/// let (s, r) = bounded_sequenced(10);
+/// s.send("a").unwrap();
+/// s.send("b").unwrap();
+///
+/// assert_eq!(r.recv_sequenced(), Ok((0, "a")));
+/// assert_eq!(r.recv_sequenced(), Ok((1, "b")));
+/// assert_eq!(r.last_sequence(), Some(1));
+/// ```
+///
+/// [`bounded`]: fn.bounded.html
+/// [`SequencedReceiver::stats`]: struct.SequencedReceiver.html#method.stats
+pub fn bounded_sequenced<T>(cap: usize) -> (SequencedSender<T>, SequencedReceiver<T>) {
+    // A zero-capacity channel would have to hold the stamping lock while waiting for a receiver.
+    assert!(cap > 0, "capacity must be positive");
+    let (s, r) = bounded(cap);
+    sequenced(s, r)
+}
+
+/// Creates a channel of unbounded capacity whose messages are stamped with sequence numbers.
+///
+/// See [`bounded_sequenced`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded_sequenced;
+///
+/// let (s, r) = unbounded_sequenced();
+/// for i in 0..3 {
+///     s.send(i).unwrap();
+/// }
+///
+/// let v: Vec<_> = r.try_iter().collect();
+/// assert_eq!(v, [0, 1, 2]);
+/// assert_eq!(r.last_sequence(), Some(2));
+/// ```
+///
+/// [`bounded_sequenced`]: fn.bounded_sequenced.html
+pub fn unbounded_sequenced<T>() -> (SequencedSender<T>, SequencedReceiver<T>) {
+    let (s, r) = unbounded();
+    sequenced(s, r)
+}
+
+/// Wraps the two sides of a channel carrying stamped messages.
+fn sequenced<T>(
+    s: Sender<(u64, T)>,
+    r: Receiver<(u64, T)>,
+) -> (SequencedSender<T>, SequencedReceiver<T>) {
+    let s = SequencedSender {
+        sender: s,
+        next: Arc::new(Mutex::new(0)),
+    };
+    let r = SequencedReceiver {
+        receiver: r,
+        tracker: Arc::new(Mutex::new(Tracker {
+            last: None,
+            stats: SequenceStats::default(),
+        })),
+    };
+    (s, r)
+}
+
+/// Statistics about sequence numbers seen by the receiving side of a channel.
+///
+/// A gap is a jump in the sequence between two consecutively received messages. Sequence numbers
+/// skipped by gaps are counted as missing. A message whose sequence number is not greater than the
+/// one received before it is counted as out of order.
+///
+/// Messages never get lost or reordered inside a channel, so with a single receiver all counters
+/// except `received` stay at zero. Gaps do appear when messages are removed from the channel by
+/// other means, e.g. when a [`Receiver`] obtained with [`SequencedReceiver::into_inner`] takes
+/// some of them. If several threads receive from the channel at the same time, the order in which
+/// their receive operations get recorded may differ from the order of messages in the channel,
+/// which shows up as out-of-order messages.
+///
+/// [`Receiver`]: struct.Receiver.html
+/// [`SequencedReceiver::into_inner`]: struct.SequencedReceiver.html#method.into_inner
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SequenceStats {
+    /// The number of received messages.
+    pub received: u64,
+
+    /// The number of jumps in the sequence.
+    pub gaps: u64,
+
+    /// The number of sequence numbers skipped by jumps.
+    pub missing: u64,
+
+    /// The number of messages received after a message with a greater or equal sequence number.
+    pub out_of_order: u64,
+}
+
+/// The state of the receiving side.
+struct Tracker {
+    /// The sequence number of the last received message.
+    last: Option<u64>,
+
+    /// Statistics about received sequence numbers.
+    stats: SequenceStats,
+}
+
+impl Tracker {
+    /// Records a received sequence number.
+    fn record(&mut self, seq: u64) {
+        self.stats.received += 1;
+        if let Some(last) = self.last {
+            if seq <= last {
+                self.stats.out_of_order += 1;
+                return;
+            }
+            if seq > last + 1 {
+                self.stats.gaps += 1;
+                self.stats.missing += seq - last - 1;
+            }
+        } else if seq > 0 {
+            self.stats.gaps += 1;
+            self.stats.missing += seq;
+        }
+        self.last = Some(seq);
+    }
+}
+
+/// The sending side of a channel with sequence-stamped messages.
+///
+/// Senders are created by the [`bounded_sequenced`] and [`unbounded_sequenced`] functions. All
+/// clones of a sender share the same sequence.
+///
+/// [`bounded_sequenced`]: fn.bounded_sequenced.html
+/// [`unbounded_sequenced`]: fn.unbounded_sequenced.html
+pub struct SequencedSender<T> {
+    /// The underlying sender.
+    sender: Sender<(u64, T)>,
+
+    /// The sequence number of the next message.
+    next: Arc<Mutex<u64>>,
+}
+
+impl<T> SequencedSender<T> {
+    /// Attempts to stamp a message and send it into the channel without blocking.
+    ///
+    /// A sequence number is used up only if the message is sent. See [`Sender::try_send`] for
+    /// details.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_sequenced, TrySendError};
+    ///
+    /// let (s, r) = bounded_sequenced(1);
+    ///
+    /// assert_eq!(s.try_send(1), Ok(()));
+    /// assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    ///
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(s.try_send(3), Ok(()));
+    /// assert_eq!(r.recv_sequenced(), Ok((1, 3)));
+    /// ```
+    pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        let mut next = self.next.lock();
+        match self.sender.try_send((*next, msg)) {
+            Ok(()) => {
+                *next += 1;
+                Ok(())
+            }
+            Err(TrySendError::Full((_, msg))) => Err(TrySendError::Full(msg)),
+            Err(TrySendError::Disconnected((_, msg))) => Err(TrySendError::Disconnected(msg)),
+        }
+    }
+
+    /// Stamps a message and sends it into the channel, blocking if the channel is full.
+    ///
+    /// Waiting for space happens before the message is stamped, so blocked senders don't hold
+    /// up each other. See [`Sender::send`] for details.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::bounded_sequenced;
+    ///
+    /// let (s, r) = bounded_sequenced(1);
+    /// s.send(1).unwrap();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     assert_eq!(r.recv_sequenced(), Ok((0, 1)));
+    ///     assert_eq!(r.recv_sequenced(), Ok((1, 2)));
+    /// });
+    ///
+    /// s.send(2).unwrap();
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        let permit = match self.sender.reserve() {
+            Ok(permit) => permit,
+            Err(SendError(())) => return Err(SendError(msg)),
+        };
+
+        let mut next = self.next.lock();
+        match permit.send((*next, msg)) {
+            Ok(()) => {
+                *next += 1;
+                Ok(())
+            }
+            Err(SendError((_, msg))) => Err(SendError(msg)),
+        }
+    }
+
+    /// Returns the sequence number of the last message sent into the channel.
+    ///
+    /// The number covers messages sent through any clone of this sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// assert_eq!(s.last_sequence(), None);
+    ///
+    /// s.send('a').unwrap();
+    /// s.clone().send('b').unwrap();
+    /// assert_eq!(s.last_sequence(), Some(1));
+    /// # drop(r);
+    /// ```
+    pub fn last_sequence(&self) -> Option<u64> {
+        match *self.next.lock() {
+            0 => None,
+            next => Some(next - 1),
+        }
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// # drop(r);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// # drop(r);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_sequenced, unbounded_sequenced};
+    ///
+    /// let (s, _) = bounded_sequenced::<i32>(5);
+    /// assert_eq!(s.capacity(), Some(5));
+    ///
+    /// let (s, _) = unbounded_sequenced::<i32>();
+    /// assert_eq!(s.capacity(), None);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+}
+
+impl<T> Clone for SequencedSender<T> {
+    fn clone(&self) -> SequencedSender<T> {
+        SequencedSender {
+            sender: self.sender.clone(),
+            next: self.next.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for SequencedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SequencedSender").finish()
+    }
+}
+
+/// The receiving side of a channel with sequence-stamped messages.
+///
+/// The receiving side records the sequence number of every message it receives. All clones of a
+/// receiver share the same records.
+///
+/// A sequenced receiver can be waited on in [`Select`] with [`Select::recv_ready`].
+///
+/// Receivers are created by the [`bounded_sequenced`] and [`unbounded_sequenced`] functions.
+///
+/// [`bounded_sequenced`]: fn.bounded_sequenced.html
+/// [`unbounded_sequenced`]: fn.unbounded_sequenced.html
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+pub struct SequencedReceiver<T> {
+    /// The underlying receiver.
+    receiver: Receiver<(u64, T)>,
+
+    /// Records of received sequence numbers.
+    tracker: Arc<Mutex<Tracker>>,
+}
+
+impl<T> SequencedReceiver<T> {
+    /// Attempts to receive a message from the channel without blocking.
+    ///
+    /// See [`Receiver::try_recv`] for details.
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_sequenced, TryRecvError};
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.receiver.try_recv().map(|m| self.record(m).1)
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected.
+    ///
+    /// See [`Receiver::recv`] for details.
+    ///
+    /// [`Receiver::recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_sequenced, RecvError};
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_sequenced().map(|(_, msg)| msg)
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected, and returns the message together with its sequence number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// s.send('a').unwrap();
+    /// s.send('b').unwrap();
+    ///
+    /// assert_eq!(r.recv_sequenced(), Ok((0, 'a')));
+    /// assert_eq!(r.recv_sequenced(), Ok((1, 'b')));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_sequenced(&self) -> Result<(u64, T), RecvError> {
+        self.receiver.recv().map(|m| self.record(m))
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time.
+    ///
+    /// See [`Receiver::recv_timeout`] for details.
+    ///
+    /// [`Receiver::recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded_sequenced, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded_sequenced::<i32>();
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// # drop(s);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.receiver
+            .recv_timeout(timeout)
+            .map(|m| self.record(m).1)
+    }
+
+    /// A non-blocking iterator over messages in the channel.
+    ///
+    /// Each call to `next` returns a message if there is one ready to be received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    ///
+    /// let v: Vec<_> = r.try_iter().collect();
+    /// assert_eq!(v, [1, 2]);
+    /// ```
+    pub fn try_iter(&self) -> SequencedTryIter<T> {
+        SequencedTryIter { receiver: self }
+    }
+
+    /// Returns the sequence number of the last received message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// assert_eq!(r.last_sequence(), None);
+    ///
+    /// s.send(()).unwrap();
+    /// r.recv().unwrap();
+    /// assert_eq!(r.last_sequence(), Some(0));
+    /// ```
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.tracker.lock().last
+    }
+
+    /// Returns statistics about the sequence numbers received so far.
+    ///
+    /// See [`SequenceStats`] for the meaning of each counter.
+    ///
+    /// [`SequenceStats`]: struct.SequenceStats.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_sequenced, SequenceStats};
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// for i in 0..3 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// for _ in 0..3 {
+    ///     r.recv().unwrap();
+    /// }
+    ///
+    /// let stats = r.stats();
+    /// assert_eq!(stats.received, 3);
+    /// assert_eq!(stats.gaps, 0);
+    /// assert_eq!(stats.missing, 0);
+    /// assert_eq!(stats.out_of_order, 0);
+    /// ```
+    pub fn stats(&self) -> SequenceStats {
+        self.tracker.lock().stats
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// assert!(r.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_sequenced;
+    ///
+    /// let (s, r) = bounded_sequenced::<i32>(5);
+    /// assert_eq!(r.capacity(), Some(5));
+    /// # drop(s);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        self.receiver.capacity()
+    }
+
+    /// Unwraps the underlying receiver of stamped messages.
+    ///
+    /// The returned receiver can be used with [`select!`] and [`Select`]. Messages it receives
+    /// are not recorded by the other clones of this receiver.
+    ///
+    /// [`select!`]: macro.select.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sequenced;
+    ///
+    /// let (s, r) = unbounded_sequenced();
+    /// s.send("foo").unwrap();
+    ///
+    /// let r = r.into_inner();
+    /// assert_eq!(r.recv(), Ok((0, "foo")));
+    /// ```
+    pub fn into_inner(self) -> Receiver<(u64, T)> {
+        self.receiver
+    }
+
+    /// Records the sequence number of a received message.
+    fn record(&self, (seq, msg): (u64, T)) -> (u64, T) {
+        self.tracker.lock().record(seq);
+        (seq, msg)
+    }
+}
+
+impl<T> Clone for SequencedReceiver<T> {
+    fn clone(&self) -> SequencedReceiver<T> {
+        SequencedReceiver {
+            receiver: self.receiver.clone(),
+            tracker: self.tracker.clone(),
+        }
+    }
+}
+
+impl<T> SelectHandle for SequencedReceiver<T> {
+    fn try(&self, token: &mut Token) -> bool {
+        Ready::new(&self.receiver).try(token)
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        Ready::new(&self.receiver).retry(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        Ready::new(&self.receiver).register(token, oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        Ready::new(&self.receiver).unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        Ready::new(&self.receiver).accept(token, cx)
+    }
+
+    fn state(&self) -> usize {
+        self.receiver.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.receiver.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.receiver.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.receiver.unwatch(oper);
+    }
+}
+
+impl<T> fmt::Debug for SequencedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SequencedReceiver").finish()
+    }
+}
+
+/// A non-blocking iterator over messages in a channel with sequence-stamped messages.
+///
+/// Each call to `next` returns a message if there is one ready to be received. The iterator
+/// never blocks waiting for the next message.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded_sequenced;
+///
+/// let (s, r) = unbounded_sequenced();
+/// s.send(1).unwrap();
+///
+/// assert_eq!(r.try_iter().next(), Some(1));
+/// assert_eq!(r.try_iter().next(), None);
+/// ```
+pub struct SequencedTryIter<'a, T: 'a> {
+    /// The receiver.
+    receiver: &'a SequencedReceiver<T>,
+}
+
+impl<'a, T> Iterator for SequencedTryIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl<'a, T> fmt::Debug for SequencedTryIter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SequencedTryIter").finish()
+    }
+}
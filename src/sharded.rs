@@ -0,0 +1,531 @@
+//! Unbounded channels split into shards.
+
+use std::fmt;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::iter::FusedIterator;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+use channel::{unbounded, Receiver, Sender};
+use context::Context;
+use err::TryRecvError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvError, RecvTimeoutError, SendError};
+use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select::Select;
+
+/// The number of threads that have sent a message into a sharded channel so far.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+thread_local! {
+    /// A number identifying the current thread, used to pick the shard its messages go into.
+    static THREAD: usize = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Creates a channel of unbounded capacity split into a number of shards.
+///
+/// A sharded channel is made of several independent unbounded queues. Every thread sending into
+/// the channel is assigned one of the shards, so producers running on different threads mostly
+/// contend on different queues. Receive operations look into all shards in rotation.
+///
+/// The price for lower contention is ordering: messages sent by the same thread are received in
+/// the order they were sent, but there is no global order among messages sent by different
+/// threads.
+///
+/// # Panics
+///
+/// Panics if `shards` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::unbounded_sharded;
+///
+/// let (s, r) = unbounded_sharded(4);
+///
+/// for i in 0..8 {
+///     let s = s.clone();
+///     thread::spawn(move || s.send(i).unwrap());
+/// }
+/// drop(s);
+///
+/// let mut v: Vec<_> = r.iter().collect();
+/// v.sort();
+/// assert_eq!(v, [0, 1, 2, 3, 4, 5, 6, 7]);
+/// ```
+pub fn unbounded_sharded<T>(shards: usize) -> (ShardedSender<T>, ShardedReceiver<T>) {
+    assert!(shards > 0, "number of shards must be positive");
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..shards).map(|_| unbounded()).unzip();
+    let s = ShardedSender {
+        senders: Arc::new(senders),
+    };
+    let r = ShardedReceiver {
+        inner: Arc::new(Shards {
+            receivers,
+            next: AtomicUsize::new(0),
+        }),
+    };
+    (s, r)
+}
+
+/// The sending side of a sharded channel.
+///
+/// Senders are created by the [`unbounded_sharded`] function.
+///
+/// [`unbounded_sharded`]: fn.unbounded_sharded.html
+pub struct ShardedSender<T> {
+    /// Senders of all shards.
+    senders: Arc<Vec<Sender<T>>>,
+}
+
+impl<T> ShardedSender<T> {
+    /// Sends a message into the shard assigned to the current thread.
+    ///
+    /// This call never blocks. If the channel is disconnected, the message is returned back inside
+    /// the error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_sharded, SendError};
+    ///
+    /// let (s, r) = unbounded_sharded(2);
+    /// assert_eq!(s.send(1), Ok(()));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        let index = match THREAD.try_with(|t| *t) {
+            Ok(t) => t % self.senders.len(),
+            Err(_) => 0,
+        };
+        self.senders[index].send(msg)
+    }
+
+    /// Returns `true` if all shards are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded(2);
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// # drop(r);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.senders.iter().all(|s| s.is_empty())
+    }
+
+    /// Returns the total number of messages in all shards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded(2);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// # drop(r);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.senders.iter().map(|s| s.len()).sum()
+    }
+
+    /// Returns the number of shards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded::<i32>(8);
+    /// assert_eq!(s.shards(), 8);
+    /// # drop(r);
+    /// ```
+    pub fn shards(&self) -> usize {
+        self.senders.len()
+    }
+}
+
+impl<T> Clone for ShardedSender<T> {
+    fn clone(&self) -> ShardedSender<T> {
+        ShardedSender {
+            senders: self.senders.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ShardedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShardedSender").finish()
+    }
+}
+
+/// Receivers of all shards, shared among the receiving side.
+struct Shards<T> {
+    /// Receivers of all shards.
+    receivers: Vec<Receiver<T>>,
+
+    /// The shard the next receive operation starts looking into.
+    next: AtomicUsize,
+}
+
+/// The receiving side of a sharded channel.
+///
+/// Receive operations take a message from any shard that has one, starting with a different
+/// shard every time so that no shard gets starved.
+///
+/// A sharded receiver can be waited on in [`Select`] with [`Select::recv_ready`]. The operation is
+/// ready once any shard holds a message or the channel is disconnected.
+///
+/// Receivers are created by the [`unbounded_sharded`] function.
+///
+/// [`unbounded_sharded`]: fn.unbounded_sharded.html
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+pub struct ShardedReceiver<T> {
+    /// The shards.
+    inner: Arc<Shards<T>>,
+}
+
+impl<T> ShardedReceiver<T> {
+    /// Attempts to receive a message from any shard without blocking.
+    ///
+    /// If all shards are empty, an error is returned. The error says the channel is disconnected
+    /// only if it is also disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_sharded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded_sharded(4);
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let receivers = &self.inner.receivers;
+        let start = self.inner.next.fetch_add(1, Ordering::Relaxed);
+        let mut disconnected = 0;
+
+        for i in 0..receivers.len() {
+            match receivers[(start + i) % receivers.len()].try_recv() {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => disconnected += 1,
+            }
+        }
+
+        if disconnected == receivers.len() {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Blocks the current thread until a message is received or all shards are empty and the
+    /// channel is disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded_sharded, RecvError};
+    ///
+    /// let (s, r) = unbounded_sharded(4);
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(5).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        match self.recv_until(None) {
+            Ok(msg) => Ok(msg),
+            Err(RecvTimeoutError::Disconnected) => Err(RecvError),
+            Err(RecvTimeoutError::Timeout) => unreachable!(),
+        }
+    }
+
+    /// Waits for a message to be received from any shard, but only for a limited time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded_sharded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded_sharded::<i32>(4);
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// # drop(s);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(Instant::now() + timeout))
+    }
+
+    /// Returns `true` if all shards are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded(2);
+    /// assert!(r.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.receivers.iter().all(|r| r.is_empty())
+    }
+
+    /// Returns the total number of messages in all shards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded(2);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.receivers.iter().map(|r| r.len()).sum()
+    }
+
+    /// Returns the number of shards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded::<i32>(8);
+    /// assert_eq!(r.shards(), 8);
+    /// # drop(s);
+    /// ```
+    pub fn shards(&self) -> usize {
+        self.inner.receivers.len()
+    }
+
+    /// A blocking iterator over messages in the channel.
+    ///
+    /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if
+    /// the channel becomes empty and disconnected, it returns [`None`] without blocking.
+    ///
+    /// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_sharded;
+    ///
+    /// let (s, r) = unbounded_sharded(2);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// drop(s);
+    ///
+    /// let v: Vec<_> = r.iter().collect();
+    /// assert_eq!(v, [1, 2]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn iter(&self) -> ShardedIter<T> {
+        ShardedIter { receiver: self }
+    }
+
+    /// Receives a message, blocking until the deadline if there is one.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let receivers = &self.inner.receivers;
+
+        loop {
+            match self.try_recv() {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            let mut sel = Select::new();
+            for r in receivers {
+                sel.recv_local(r);
+            }
+
+            let oper = match deadline {
+                None => sel.select(),
+                Some(d) => {
+                    let now = Instant::now();
+                    if now >= d {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    match sel.select_timeout(d - now) {
+                        Ok(oper) => oper,
+                        Err(_) => return Err(RecvTimeoutError::Timeout),
+                    }
+                }
+            };
+
+            // A disconnected shard is ready as well, so check all shards again if it is empty.
+            let index = oper.index();
+            if let Ok(msg) = oper.recv(&receivers[index]) {
+                return Ok(msg);
+            }
+        }
+    }
+}
+
+impl<T> Clone for ShardedReceiver<T> {
+    fn clone(&self) -> ShardedReceiver<T> {
+        ShardedReceiver {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> SelectHandle for ShardedReceiver<T> {
+    fn try(&self, _token: &mut Token) -> bool {
+        self.is_ready()
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        self.is_ready()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.watch(oper, cx);
+        !self.is_ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.unwatch(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        self.is_ready()
+    }
+
+    fn state(&self) -> usize {
+        self.inner
+            .receivers
+            .iter()
+            .fold(0, |state, r| state.wrapping_add(r.state()))
+    }
+
+    fn is_ready(&self) -> bool {
+        // All shards are disconnected together, so a disconnected shard means a disconnected
+        // channel.
+        self.inner.receivers.iter().any(|r| r.is_ready())
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        for r in &self.inner.receivers {
+            r.watch(oper, cx);
+        }
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        for r in &self.inner.receivers {
+            r.unwatch(oper);
+        }
+    }
+}
+
+impl<T> fmt::Debug for ShardedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShardedReceiver").finish()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> IntoIterator for &'a ShardedReceiver<T> {
+    type Item = T;
+    type IntoIter = ShardedIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A blocking iterator over messages in a sharded channel.
+///
+/// Each call to [`next`] blocks waiting for the next message and then returns it. However, if the
+/// channel becomes empty and disconnected, it returns [`None`] without blocking.
+///
+/// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded_sharded;
+///
+/// let (s, r) = unbounded_sharded(4);
+/// s.send(1).unwrap();
+/// drop(s);
+///
+/// for msg in &r {
+///     assert_eq!(msg, 1);
+/// }
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct ShardedIter<'a, T: 'a> {
+    /// The receiver.
+    receiver: &'a ShardedReceiver<T>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> FusedIterator for ShardedIter<'a, T> {}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> Iterator for ShardedIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> fmt::Debug for ShardedIter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShardedIter").finish()
+    }
+}
@@ -0,0 +1,459 @@
+//! Groups of channels that are shut down together.
+
+use std::fmt;
+use std::sync::atomic::{self, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+/// A group of channels that are shut down together.
+///
+/// Channels are attached to a group when they are created with [`Builder::shutdown_group`].
+/// Calling [`shutdown`] disconnects every attached channel at once, as if all of its senders had
+/// been dropped: blocked operations wake up, send operations fail from then on, and receive
+/// operations still get the messages that are left in each channel before failing too. A channel
+/// attached to a group that is already shut down is disconnected right away.
+///
+/// [`wait_drained`] then blocks until the receivers have emptied every attached channel, which
+/// makes it possible to tear down a whole graph of channels and threads without having to drop
+/// senders in the right order.
+///
+/// Cloning a group creates a new handle to the same group. The group doesn't keep its channels
+/// alive: a channel leaves its group when it is destroyed.
+///
+/// [`Builder::shutdown_group`]: struct.Builder.html#method.shutdown_group
+/// [`shutdown`]: struct.ShutdownGroup.html#method.shutdown
+/// [`wait_drained`]: struct.ShutdownGroup.html#method.wait_drained
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::{Builder, ShutdownGroup};
+///
+/// let group = ShutdownGroup::new();
+/// let (s1, r1) = Builder::new().shutdown_group(&group).bounded(10);
+/// let (s2, r2) = Builder::new().shutdown_group(&group).unbounded();
+///
+/// // A stage that doubles numbers.
+/// let stage = thread::spawn(move || {
+///     for n in r1 {
+///         if s2.send(n * 2).is_err() {
+///             break;
+///         }
+///     }
+/// });
+///
+/// // A sink that prints them.
+/// let sink = thread::spawn(move || {
+///     for n in r2 {
+///         println!("{}", n);
+///     }
+/// });
+///
+/// for i in 0..5 {
+///     s1.send(i).unwrap();
+/// }
+///
+/// // Disconnect both channels even though their senders are still alive.
+/// group.shutdown();
+/// assert!(s1.send(5).is_err());
+///
+/// group.wait_drained();
+/// stage.join().unwrap();
+/// sink.join().unwrap();
+/// ```
+pub struct ShutdownGroup {
+    inner: Arc<Inner>,
+}
+
+/// Inner representation of `ShutdownGroup`.
+struct Inner {
+    /// The state of the group.
+    group: Mutex<Group>,
+
+    /// Signaled when an attached channel gets drained or detached while threads are waiting.
+    drained: Condvar,
+
+    /// The number of threads waiting for the group to get drained.
+    waiting: AtomicUsize,
+}
+
+/// The state of a group.
+struct Group {
+    /// Equals `true` if the group has been shut down.
+    is_shut_down: bool,
+
+    /// The attached channels, in the order they were attached.
+    members: Vec<Entry>,
+
+    /// Incremented every time waiting threads are notified.
+    epoch: usize,
+}
+
+/// A channel that can be attached to a group.
+pub(crate) trait Member {
+    /// Disconnects the channel and wakes up all blocked operations.
+    fn shut_down(&self);
+
+    /// Returns `true` if the channel is empty or its messages can never be received.
+    fn is_drained(&self) -> bool;
+}
+
+/// An attached channel.
+struct Entry {
+    /// The address of the channel, which identifies it when it gets detached.
+    addr: usize,
+
+    /// The channel, which may already be in the middle of getting destroyed.
+    member: Weak<Member + Send + Sync>,
+}
+
+impl Group {
+    /// Returns the attached channels that are still alive.
+    ///
+    /// The returned channels must not be dropped while the group is locked because dropping the
+    /// last reference to a channel detaches it from the group.
+    fn members(&self) -> Vec<Arc<Member + Send + Sync>> {
+        self.members.iter().filter_map(|e| e.member.upgrade()).collect()
+    }
+}
+
+/// A shutdown group a channel is going to be attached to once it is created.
+pub(crate) struct Attachment<M> {
+    /// The group.
+    group: ShutdownGroup,
+
+    /// Turns a reference to the channel into a member of the group.
+    member: fn(&Arc<M>) -> Weak<Member + Send + Sync>,
+}
+
+impl<M: Member + Send + Sync + 'static> Attachment<M> {
+    /// Creates an attachment to `group`.
+    pub(crate) fn new(group: &ShutdownGroup) -> Attachment<M> {
+        Attachment {
+            group: group.clone(),
+            member: downgrade::<M>,
+        }
+    }
+}
+
+impl<M: Member> Attachment<M> {
+    /// Attaches a channel to the group.
+    ///
+    /// If the group has already been shut down, the channel is disconnected right away.
+    pub(crate) fn attach(&self, chan: &Arc<M>) {
+        let mut group = self.group.inner.group.lock();
+        if group.is_shut_down {
+            chan.shut_down();
+        }
+        group.members.push(Entry {
+            addr: address(&**chan),
+            member: (self.member)(chan),
+        });
+    }
+
+    /// Detaches a channel from the group.
+    pub(crate) fn detach(&self, chan: &M) {
+        let addr = address(chan);
+        let inner = &*self.group.inner;
+        let mut group = inner.group.lock();
+        if let Some(pos) = group.members.iter().position(|e| e.addr == addr) {
+            group.members.remove(pos);
+
+            // The channel no longer counts, so the group may be drained now.
+            if inner.waiting.load(Ordering::SeqCst) > 0 {
+                group.epoch = group.epoch.wrapping_add(1);
+                inner.drained.notify_all();
+            }
+        }
+    }
+
+    /// Wakes up threads waiting for the group to get drained if the channel just got drained.
+    ///
+    /// This must be called whenever a message is received from the channel or its last receiver
+    /// is dropped.
+    #[inline]
+    pub(crate) fn notify(&self, chan: &M) {
+        let inner = &*self.group.inner;
+
+        // Pairs with the fence in `wait_until`, so that either the waiting thread sees the channel
+        // drained, or this thread sees the waiting thread.
+        atomic::fence(Ordering::SeqCst);
+        if inner.waiting.load(Ordering::Relaxed) > 0 && chan.is_drained() {
+            let mut group = inner.group.lock();
+            group.epoch = group.epoch.wrapping_add(1);
+            inner.drained.notify_all();
+        }
+    }
+}
+
+impl<M> fmt::Debug for Attachment<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.group.fmt(f)
+    }
+}
+
+/// Turns a reference to a channel into a member of a group.
+fn downgrade<M: Member + Send + Sync + 'static>(chan: &Arc<M>) -> Weak<Member + Send + Sync> {
+    let member: Weak<M> = Arc::downgrade(chan);
+    member
+}
+
+/// Returns the address of a channel.
+fn address<M>(chan: &M) -> usize {
+    chan as *const M as usize
+}
+
+impl ShutdownGroup {
+    /// Creates a new group with no channels attached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::ShutdownGroup;
+    ///
+    /// let group = ShutdownGroup::new();
+    /// assert!(!group.is_shut_down());
+    /// ```
+    pub fn new() -> ShutdownGroup {
+        ShutdownGroup {
+            inner: Arc::new(Inner {
+                group: Mutex::new(Group {
+                    is_shut_down: false,
+                    members: Vec::new(),
+                    epoch: 0,
+                }),
+                drained: Condvar::new(),
+                waiting: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Disconnects every attached channel.
+    ///
+    /// All channels are disconnected while the group is locked, so no channel can be attached or
+    /// detached in the meantime. Channels attached later are disconnected as soon as they are
+    /// created. Calling this method again has no effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, RecvError, SendError, ShutdownGroup};
+    ///
+    /// let group = ShutdownGroup::new();
+    /// let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// group.shutdown();
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn shutdown(&self) {
+        let mut group = self.inner.group.lock();
+        group.is_shut_down = true;
+        let members = group.members();
+        for m in &members {
+            m.shut_down();
+        }
+        drop(group);
+    }
+
+    /// Returns `true` if the group has been shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::ShutdownGroup;
+    ///
+    /// let group = ShutdownGroup::new();
+    /// assert!(!group.is_shut_down());
+    ///
+    /// group.shutdown();
+    /// assert!(group.is_shut_down());
+    /// ```
+    pub fn is_shut_down(&self) -> bool {
+        self.inner.group.lock().is_shut_down
+    }
+
+    /// Returns the number of channels attached to the group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, ShutdownGroup};
+    ///
+    /// let group = ShutdownGroup::new();
+    /// let (s, r) = Builder::<i32>::new().shutdown_group(&group).bounded(1);
+    /// assert_eq!(group.len(), 1);
+    ///
+    /// drop(s);
+    /// drop(r);
+    /// assert_eq!(group.len(), 0);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.group.lock().members.len()
+    }
+
+    /// Returns `true` if no channels are attached to the group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::ShutdownGroup;
+    ///
+    /// let group = ShutdownGroup::new();
+    /// assert!(group.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.group.lock().members.is_empty()
+    }
+
+    /// Returns `true` if every attached channel is empty or has no receivers left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Builder, ShutdownGroup};
+    ///
+    /// let group = ShutdownGroup::new();
+    /// let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    ///
+    /// s.send(1).unwrap();
+    /// assert!(!group.is_drained());
+    ///
+    /// r.recv().unwrap();
+    /// assert!(group.is_drained());
+    /// ```
+    pub fn is_drained(&self) -> bool {
+        let members = self.inner.group.lock().members();
+        members.iter().all(|m| m.is_drained())
+    }
+
+    /// Blocks the current thread until every attached channel is empty.
+    ///
+    /// This is typically called after [`shutdown`] to wait for the receivers to consume the
+    /// messages that were still in flight. Channels that are destroyed or lose all their receivers
+    /// in the meantime no longer count. If messages are never received, this method blocks
+    /// forever.
+    ///
+    /// [`shutdown`]: struct.ShutdownGroup.html#method.shutdown
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{Builder, ShutdownGroup};
+    ///
+    /// let group = ShutdownGroup::new();
+    /// let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    /// for i in 0..100 {
+    ///     s.send(i).unwrap();
+    /// }
+    ///
+    /// thread::spawn(move || for _ in r {});
+    ///
+    /// group.shutdown();
+    /// group.wait_drained();
+    /// assert!(group.is_drained());
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait_drained(&self) {
+        self.wait_until(None);
+    }
+
+    /// Waits for every attached channel to become empty, but only for a limited time.
+    ///
+    /// Returns `true` if all channels were drained before the timeout elapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{Builder, ShutdownGroup};
+    ///
+    /// let group = ShutdownGroup::new();
+    /// let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// group.shutdown();
+    /// assert!(!group.wait_drained_timeout(Duration::from_millis(100)));
+    ///
+    /// r.recv().unwrap();
+    /// assert!(group.wait_drained_timeout(Duration::from_millis(100)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait_drained_timeout(&self, timeout: Duration) -> bool {
+        self.wait_until(Some(Instant::now() + timeout))
+    }
+
+    /// Waits until every attached channel is empty or the deadline is reached.
+    ///
+    /// Channels notify the group whenever they might have become drained while a thread is
+    /// waiting here.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn wait_until(&self, deadline: Option<Instant>) -> bool {
+        let inner = &*self.inner;
+        inner.waiting.fetch_add(1, Ordering::SeqCst);
+
+        // Pairs with the fence in `Attachment::notify`.
+        atomic::fence(Ordering::SeqCst);
+
+        let mut group = inner.group.lock();
+        let res = loop {
+            // The channels are inspected with the group unlocked, so the epoch tells whether a
+            // notification was missed in the meantime.
+            let epoch = group.epoch;
+            let members = group.members();
+            drop(group);
+            let is_drained = members.iter().all(|m| m.is_drained());
+            drop(members);
+            if is_drained {
+                break true;
+            }
+
+            group = inner.group.lock();
+            if group.epoch == epoch {
+                match deadline {
+                    None => inner.drained.wait(&mut group),
+                    Some(d) => {
+                        if inner.drained.wait_until(&mut group, d).timed_out() {
+                            drop(group);
+                            break self.is_drained();
+                        }
+                    }
+                }
+            }
+        };
+
+        inner.waiting.fetch_sub(1, Ordering::SeqCst);
+        res
+    }
+}
+
+impl Clone for ShutdownGroup {
+    fn clone(&self) -> ShutdownGroup {
+        ShutdownGroup {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl Default for ShutdownGroup {
+    fn default() -> ShutdownGroup {
+        ShutdownGroup::new()
+    }
+}
+
+impl fmt::Debug for ShutdownGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let group = self.inner.group.lock();
+        f.debug_struct("ShutdownGroup")
+            .field("is_shut_down", &group.is_shut_down)
+            .field("len", &group.members.len())
+            .finish()
+    }
+}
@@ -0,0 +1,437 @@
+//! Deterministic simulation of concurrent tasks communicating over channels.
+//!
+//! A [`Simulation`] runs a set of tasks such that the way they interleave is decided by a seeded
+//! random number generator rather than by the operating system. Each task runs on its own thread,
+//! but only one of them runs at any time. Whenever the running task blocks on a channel
+//! operation, the scheduler picks the next task to run among those that are ready, and every task
+//! gets its own deterministically seeded random number generator for fairness in [`Select`] and
+//! [`select!`]. Running the same tasks with the same seed therefore replays the exact same
+//! interleaving, which makes rare races reproducible, while trying many seeds explores many
+//! different interleavings.
+//!
+//! Tasks only switch at blocking operations. Everything a task does between two blocking
+//! operations happens atomically with respect to the other tasks. Blocking operations with a
+//! timeout wait for real time to pass, but a task is woken up by a timeout only when every other
+//! task is blocked too.
+//!
+//! The interleaving is reproducible only if the tasks themselves are deterministic and
+//! communicate solely with each other: tasks must not share channels with threads outside the
+//! simulation, nor block on anything else than channels.
+//!
+//! This module is available only if the `sim` feature is enabled.
+//!
+//! [`Simulation`]: struct.Simulation.html
+//! [`Select`]: ../struct.Select.html
+//! [`select!`]: ../macro.select.html
+//!
+//! # Examples
+//!
+//! ```
+//! use crossbeam_channel::sim::Simulation;
+//! use crossbeam_channel::unbounded;
+//!
+//! fn run(seed: u64) -> Vec<i32> {
+//!     let (s, r) = unbounded();
+//!     let mut sim = Simulation::new(seed);
+//!
+//!     for i in 0..3 {
+//!         let s = s.clone();
+//!         sim.spawn(move || s.send(i).unwrap());
+//!     }
+//!     drop(s);
+//!
+//!     sim.run();
+//!     r.try_iter().collect()
+//! }
+//!
+//! // The same seed always results in the same order of messages.
+//! assert_eq!(run(7), run(7));
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use parking_lot::{Condvar, Mutex};
+
+use park::{self, Parker};
+use utils::{self, Rng};
+
+/// A set of tasks that run in a deterministic interleaving.
+///
+/// See the [module-level documentation](index.html) for more.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::bounded;
+/// use crossbeam_channel::sim::Simulation;
+///
+/// let (s, r) = bounded(0);
+/// let mut sim = Simulation::new(42);
+///
+/// sim.spawn(move || {
+///     for i in 0..10 {
+///         s.send(i).unwrap();
+///     }
+/// });
+/// sim.spawn(move || {
+///     for i in 0..10 {
+///         assert_eq!(r.recv(), Ok(i));
+///     }
+/// });
+///
+/// sim.run();
+/// ```
+pub struct Simulation {
+    /// State shared with the tasks.
+    shared: Arc<Shared>,
+
+    /// Threads running the tasks.
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// State shared by the simulation and its tasks.
+struct Shared {
+    /// The state of the scheduler.
+    state: Mutex<State>,
+
+    /// Signaled whenever a different task gets to run.
+    cvar: Condvar,
+}
+
+/// The state of the scheduler.
+struct State {
+    /// All spawned tasks.
+    tasks: Vec<Task>,
+
+    /// The index of the running task.
+    current: Option<usize>,
+
+    /// Decides which task runs next.
+    rng: Rng,
+
+    /// Indices of tasks in the order they were scheduled.
+    trace: Vec<usize>,
+
+    /// Equals `true` if the simulation is dropped without being run.
+    cancelled: bool,
+
+    /// Equals `true` if every unfinished task is blocked forever.
+    deadlocked: bool,
+
+    /// The payload of the first task that panicked.
+    panic: Option<Box<Any + Send>>,
+}
+
+/// The state of a single task.
+struct Task {
+    /// What the task is doing.
+    status: Status,
+
+    /// The wakeup token of the task's parker.
+    token: bool,
+}
+
+/// What a task is doing.
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    /// Waiting to be scheduled.
+    Ready,
+
+    /// Running.
+    Running,
+
+    /// Blocked until unparked or until the deadline, if there is one.
+    Blocked(Option<Instant>),
+
+    /// Done.
+    Finished,
+}
+
+/// The parker of a task.
+struct TaskParker {
+    /// The simulation the task belongs to.
+    shared: Arc<Shared>,
+
+    /// The index of the task.
+    index: usize,
+}
+
+thread_local! {
+    /// The parker of the task running on the current thread.
+    static PARKER: RefCell<Option<Arc<TaskParker>>> = RefCell::new(None);
+}
+
+/// Returns the parker of the task running on the current thread.
+fn current() -> Arc<Parker> {
+    PARKER.with(|p| p.borrow().clone().expect("not a simulated task"))
+}
+
+impl Simulation {
+    /// Creates a new simulation whose interleaving is determined by `seed`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::sim::Simulation;
+    ///
+    /// let sim = Simulation::new(0);
+    /// sim.run();
+    /// ```
+    pub fn new(seed: u64) -> Simulation {
+        Simulation {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    tasks: Vec::new(),
+                    current: None,
+                    rng: Rng(seed),
+                    trace: Vec::new(),
+                    cancelled: false,
+                    deadlocked: false,
+                    panic: None,
+                }),
+                cvar: Condvar::new(),
+            }),
+            threads: Vec::new(),
+        }
+    }
+
+    /// Adds a task to the simulation.
+    ///
+    /// The task doesn't start running before [`run`] is called.
+    ///
+    /// [`run`]: struct.Simulation.html#method.run
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::sim::Simulation;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut sim = Simulation::new(0);
+    /// sim.spawn(move || s.send("hello").unwrap());
+    ///
+    /// assert!(r.try_recv().is_err());
+    /// sim.run();
+    /// assert_eq!(r.try_recv(), Ok("hello"));
+    /// ```
+    pub fn spawn<F>(&mut self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let (index, seed) = {
+            let mut state = self.shared.state.lock();
+            state.tasks.push(Task {
+                status: Status::Ready,
+                token: false,
+            });
+            (state.tasks.len() - 1, state.rng.next())
+        };
+
+        let shared = self.shared.clone();
+        self.threads.push(thread::spawn(move || {
+            let parker = Arc::new(TaskParker {
+                shared: shared.clone(),
+                index,
+            });
+            PARKER.with(|p| *p.borrow_mut() = Some(parker));
+            park::set_parker(Some(current));
+            utils::set_seed(seed);
+
+            if shared.wait_turn(index) {
+                let res = panic::catch_unwind(AssertUnwindSafe(f));
+                shared.finish(index, res.err());
+            }
+        }));
+    }
+
+    /// Runs all tasks to completion and returns the order in which they were scheduled.
+    ///
+    /// Each element of the returned trace is the index of a task, counting from zero in the order
+    /// of spawning. A new element is added whenever a task starts running or resumes running
+    /// after being blocked.
+    ///
+    /// # Panics
+    ///
+    /// If a task panics, the panic is propagated once all other tasks finish.
+    ///
+    /// Panics if every unfinished task is blocked with no way of being woken up. In that case,
+    /// the threads of the blocked tasks are leaked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    /// use crossbeam_channel::sim::Simulation;
+    ///
+    /// let run = |seed| {
+    ///     let (s, r) = bounded(0);
+    ///     let mut sim = Simulation::new(seed);
+    ///     sim.spawn(move || s.send(1).unwrap());
+    ///     sim.spawn(move || assert_eq!(r.recv(), Ok(1)));
+    ///     sim.run()
+    /// };
+    ///
+    /// assert_eq!(run(1), run(1));
+    /// ```
+    pub fn run(mut self) -> Vec<usize> {
+        let deadlocked = {
+            let mut state = self.shared.state.lock();
+            self.shared.schedule(&mut state);
+
+            while !state.deadlocked && state.tasks.iter().any(|t| t.status != Status::Finished) {
+                self.shared.cvar.wait(&mut state);
+            }
+            state.deadlocked
+        };
+
+        if deadlocked {
+            panic!("simulation deadlocked: all tasks are blocked");
+        }
+
+        for t in self.threads.drain(..) {
+            t.join().unwrap();
+        }
+
+        let mut state = self.shared.state.lock();
+        if let Some(payload) = state.panic.take() {
+            drop(state);
+            panic::resume_unwind(payload);
+        }
+        state.trace.clone()
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        if state.current.is_none() && state.trace.is_empty() {
+            // The simulation was never run, so let the threads exit without running tasks.
+            state.cancelled = true;
+            self.shared.cvar.notify_all();
+        }
+    }
+}
+
+impl fmt::Debug for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Simulation").finish()
+    }
+}
+
+impl Shared {
+    /// Picks the next task to run and wakes it up.
+    fn schedule(&self, state: &mut State) {
+        let ready = state
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|&(_, t)| match t.status {
+                Status::Ready => true,
+                Status::Blocked(_) => t.token,
+                Status::Running | Status::Finished => false,
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        let next = if !ready.is_empty() {
+            let i = (state.rng.next() % ready.len() as u64) as usize;
+            Some(ready[i])
+        } else {
+            // Nothing is ready, so let the task with the earliest timeout run.
+            state
+                .tasks
+                .iter()
+                .enumerate()
+                .filter_map(|(i, t)| match t.status {
+                    Status::Blocked(Some(d)) => Some((d, i)),
+                    _ => None,
+                })
+                .min()
+                .map(|(_, i)| i)
+        };
+
+        match next {
+            Some(i) => {
+                state.tasks[i].status = Status::Running;
+                state.trace.push(i);
+            }
+            None => {
+                if state.tasks.iter().any(|t| t.status != Status::Finished) {
+                    state.deadlocked = true;
+                }
+            }
+        }
+        state.current = next;
+        self.cvar.notify_all();
+    }
+
+    /// Waits until the task gets to run.
+    ///
+    /// Returns `false` if the simulation was cancelled.
+    fn wait_turn(&self, index: usize) -> bool {
+        let mut state = self.state.lock();
+        while state.current != Some(index) {
+            if state.cancelled {
+                return false;
+            }
+            self.cvar.wait(&mut state);
+        }
+        true
+    }
+
+    /// Marks the task as finished and lets another task run.
+    fn finish(&self, index: usize, panic: Option<Box<Any + Send>>) {
+        let mut state = self.state.lock();
+        state.tasks[index].status = Status::Finished;
+        if state.panic.is_none() {
+            state.panic = panic;
+        }
+        self.schedule(&mut state);
+    }
+}
+
+impl Parker for TaskParker {
+    fn park(&self, deadline: Option<Instant>) {
+        let timed_out = {
+            let mut state = self.shared.state.lock();
+            if state.tasks[self.index].token {
+                state.tasks[self.index].token = false;
+                return;
+            }
+
+            // Block and let another task run.
+            state.tasks[self.index].status = Status::Blocked(deadline);
+            self.shared.schedule(&mut state);
+            while state.current != Some(self.index) {
+                self.shared.cvar.wait(&mut state);
+            }
+
+            let task = &mut state.tasks[self.index];
+            let timed_out = !task.token;
+            task.token = false;
+            timed_out
+        };
+
+        // The task was scheduled only because everything else is blocked, so wait out the
+        // timeout.
+        if timed_out {
+            if let Some(d) = deadline {
+                let now = Instant::now();
+                if now < d {
+                    thread::sleep(d - now);
+                }
+            }
+        }
+    }
+
+    fn unpark(&self) {
+        self.shared.state.lock().tasks[self.index].token = true;
+    }
+}
@@ -0,0 +1,966 @@
+//! Handles specialized for a single channel flavor.
+//!
+//! A [`Sender`] or [`Receiver`] matches on the channel flavor in every operation. The handles in
+//! this module are checked once, on conversion, and then call into the flavor directly.
+//!
+//! Each specialized handle owns the generic handle it was converted from, which keeps the channel
+//! alive and maintains the sender and receiver counts, along with a pointer to the flavor inside
+//! that channel.
+//!
+//! [`Sender`]: struct.Sender.html
+//! [`Receiver`]: struct.Receiver.html
+
+use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+use channel::{Receiver, Sender};
+use err::TryRecvError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvError, RecvTimeoutError};
+use err::TrySendError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{SendError, SendTimeoutError};
+use flavors::{array, list};
+
+/// The sending side of a bounded channel, specialized for the array flavor.
+///
+/// Created by [`Sender::into_array`].
+///
+/// [`Sender::into_array`]: struct.Sender.html#method.into_array
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::bounded;
+///
+/// let (s, r) = bounded(10);
+/// let s = s.into_array().unwrap();
+///
+/// thread::spawn(move || {
+///     for i in 0..100 {
+///         s.send(i).unwrap();
+///     }
+/// });
+///
+/// assert_eq!(r.iter().sum::<i32>(), 4950);
+/// ```
+pub struct ArraySender<T> {
+    /// The generic sender.
+    sender: Sender<T>,
+
+    /// The flavor inside the channel `sender` points to.
+    chan: *const array::Channel<T>,
+}
+
+unsafe impl<T: Send> Send for ArraySender<T> {}
+unsafe impl<T: Send> Sync for ArraySender<T> {}
+
+impl<T> UnwindSafe for ArraySender<T> {}
+impl<T> RefUnwindSafe for ArraySender<T> {}
+
+impl<T> ArraySender<T> {
+    /// Creates a specialized sender from a generic one and its array flavor.
+    pub(crate) fn new(sender: Sender<T>, chan: *const array::Channel<T>) -> ArraySender<T> {
+        ArraySender { sender, chan }
+    }
+
+    /// Returns the array flavor of the channel.
+    #[inline]
+    fn chan(&self) -> &array::Channel<T> {
+        // The flavor lives as long as the channel, which `self.sender` keeps alive.
+        unsafe { &*self.chan }
+    }
+
+    /// Attempts to send a message into the channel without blocking.
+    ///
+    /// See [`Sender::try_send`] for details.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, TrySendError};
+    ///
+    /// let (s, _r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// assert_eq!(s.try_send(1), Ok(()));
+    /// assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    /// ```
+    pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.sender.intercept(&msg);
+        self.chan().try_send(msg)
+    }
+
+    /// Blocks the current thread until a message is sent or the channel is disconnected.
+    ///
+    /// See [`Sender::send`] for details.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// assert_eq!(s.send(1), Ok(()));
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.sender.intercept(&msg);
+        self.sender
+            .send_when_full(
+                self.sender.full_policy(),
+                msg,
+                None,
+                |msg| self.chan().try_send(msg),
+                |msg| self.chan().send(msg, None),
+            )
+            .map_err(|err| SendError(err.into_inner()))
+    }
+
+    /// Waits for a message to be sent into the channel, but only for a limited time.
+    ///
+    /// See [`Sender::send_timeout`] for details.
+    ///
+    /// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendTimeoutError};
+    ///
+    /// let (s, _r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// assert_eq!(s.send_timeout(1, Duration::from_millis(100)), Ok(()));
+    /// assert_eq!(
+    ///     s.send_timeout(2, Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout(2)),
+    /// );
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now() + timeout;
+        self.sender.intercept(&msg);
+        self.sender.send_when_full(
+            self.sender.full_policy(),
+            msg,
+            Some(deadline),
+            |msg| self.chan().try_send(msg),
+            |msg| self.chan().send(msg, Some(deadline)),
+        )
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, _r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// assert!(s.is_empty());
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.chan().is_empty()
+    }
+
+    /// Returns `true` if the channel is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, _r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// assert!(!s.is_full());
+    /// s.send(0).unwrap();
+    /// assert!(s.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.chan().is_full()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, _r) = bounded(3);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.chan().len()
+    }
+
+    /// Returns the capacity of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, _r) = bounded::<i32>(7);
+    /// assert_eq!(s.into_array().unwrap().capacity(), 7);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.chan().capacity().unwrap()
+    }
+
+    /// Returns the generic sender this handle was converted from.
+    ///
+    /// This is useful for passing the sender to APIs that take a [`Sender`], like [`Select`].
+    ///
+    /// [`Sender`]: struct.Sender.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, Select};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let s = s.into_array().unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper = sel.send(s.as_sender());
+    /// let op = sel.select();
+    /// assert_eq!(op.index(), oper);
+    /// op.send(s.as_sender(), 1).unwrap();
+    /// # drop(r);
+    /// ```
+    pub fn as_sender(&self) -> &Sender<T> {
+        &self.sender
+    }
+
+    /// Converts this handle back into a generic sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// let s = s.into_array().unwrap().into_sender();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn into_sender(self) -> Sender<T> {
+        self.sender
+    }
+}
+
+impl<T> Clone for ArraySender<T> {
+    fn clone(&self) -> ArraySender<T> {
+        ArraySender::new(self.sender.clone(), self.chan)
+    }
+}
+
+impl<T> fmt::Debug for ArraySender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArraySender").finish()
+    }
+}
+
+/// The receiving side of a bounded channel, specialized for the array flavor.
+///
+/// Created by [`Receiver::into_array`].
+///
+/// [`Receiver::into_array`]: struct.Receiver.html#method.into_array
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::bounded;
+///
+/// let (s, r) = bounded(10);
+/// let r = r.into_array().unwrap();
+///
+/// thread::spawn(move || {
+///     for i in 0..100 {
+///         s.send(i).unwrap();
+///     }
+/// });
+///
+/// let mut sum = 0;
+/// while let Ok(i) = r.recv() {
+///     sum += i;
+/// }
+/// assert_eq!(sum, 4950);
+/// ```
+pub struct ArrayReceiver<T> {
+    /// The generic receiver.
+    receiver: Receiver<T>,
+
+    /// The flavor inside the channel `receiver` points to.
+    chan: *const array::Channel<T>,
+}
+
+unsafe impl<T: Send> Send for ArrayReceiver<T> {}
+unsafe impl<T: Send> Sync for ArrayReceiver<T> {}
+
+impl<T> UnwindSafe for ArrayReceiver<T> {}
+impl<T> RefUnwindSafe for ArrayReceiver<T> {}
+
+impl<T> ArrayReceiver<T> {
+    /// Creates a specialized receiver from a generic one and its array flavor.
+    pub(crate) fn new(receiver: Receiver<T>, chan: *const array::Channel<T>) -> ArrayReceiver<T> {
+        ArrayReceiver { receiver, chan }
+    }
+
+    /// Returns the array flavor of the channel.
+    #[inline]
+    fn chan(&self) -> &array::Channel<T> {
+        // The flavor lives as long as the channel, which `self.receiver` keeps alive.
+        unsafe { &*self.chan }
+    }
+
+    /// Attempts to receive a message from the channel without blocking.
+    ///
+    /// See [`Receiver::try_recv`] for details.
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, TryRecvError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap();
+    ///
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// s.send(5).unwrap();
+    /// drop(s);
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.receiver.intercept(self.chan().try_recv())
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected.
+    ///
+    /// See [`Receiver::recv`] for details.
+    ///
+    /// [`Receiver::recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, RecvError};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap();
+    ///
+    /// s.send(1).unwrap();
+    /// drop(s);
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.receiver
+            .recorder()
+            .record(|| self.receiver.intercept(self.chan().recv(None)))
+            .map_err(|_| RecvError)
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time.
+    ///
+    /// See [`Receiver::recv_timeout`] for details.
+    ///
+    /// [`Receiver::recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = bounded::<i32>(1);
+    /// let r = r.into_array().unwrap();
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// # drop(s);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        self.receiver
+            .recorder()
+            .record(|| self.receiver.intercept(self.chan().recv(Some(deadline))))
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap();
+    ///
+    /// assert!(r.is_empty());
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.chan().is_empty()
+    }
+
+    /// Returns `true` if the channel is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap();
+    ///
+    /// assert!(!r.is_full());
+    /// s.send(0).unwrap();
+    /// assert!(r.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.chan().is_full()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(3);
+    /// let r = r.into_array().unwrap();
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.chan().len()
+    }
+
+    /// Returns the capacity of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (_s, r) = bounded::<i32>(7);
+    /// assert_eq!(r.into_array().unwrap().capacity(), 7);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.chan().capacity().unwrap()
+    }
+
+    /// Returns the generic receiver this handle was converted from.
+    ///
+    /// This is useful for passing the receiver to APIs that take a [`Receiver`], like [`Select`].
+    ///
+    /// [`Receiver`]: struct.Receiver.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, Select};
+    ///
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap();
+    /// s.send(1).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper = sel.recv(r.as_receiver());
+    /// let op = sel.select();
+    /// assert_eq!(op.index(), oper);
+    /// assert_eq!(op.recv(r.as_receiver()), Ok(1));
+    /// ```
+    pub fn as_receiver(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Converts this handle back into a generic receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    /// let r = r.into_array().unwrap().into_receiver();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn into_receiver(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Clone for ArrayReceiver<T> {
+    fn clone(&self) -> ArrayReceiver<T> {
+        ArrayReceiver::new(self.receiver.clone(), self.chan)
+    }
+}
+
+impl<T> fmt::Debug for ArrayReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArrayReceiver").finish()
+    }
+}
+
+/// The sending side of an unbounded channel, specialized for the list flavor.
+///
+/// Created by [`Sender::into_list`].
+///
+/// [`Sender::into_list`]: struct.Sender.html#method.into_list
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// let s = s.into_list().unwrap();
+///
+/// thread::spawn(move || {
+///     for i in 0..100 {
+///         s.send(i).unwrap();
+///     }
+/// });
+///
+/// assert_eq!(r.iter().sum::<i32>(), 4950);
+/// ```
+pub struct ListSender<T> {
+    /// The generic sender.
+    sender: Sender<T>,
+
+    /// The flavor inside the channel `sender` points to.
+    chan: *const list::Channel<T>,
+}
+
+unsafe impl<T: Send> Send for ListSender<T> {}
+unsafe impl<T: Send> Sync for ListSender<T> {}
+
+impl<T> UnwindSafe for ListSender<T> {}
+impl<T> RefUnwindSafe for ListSender<T> {}
+
+impl<T> ListSender<T> {
+    /// Creates a specialized sender from a generic one and its list flavor.
+    pub(crate) fn new(sender: Sender<T>, chan: *const list::Channel<T>) -> ListSender<T> {
+        ListSender { sender, chan }
+    }
+
+    /// Returns the list flavor of the channel.
+    #[inline]
+    fn chan(&self) -> &list::Channel<T> {
+        // The flavor lives as long as the channel, which `self.sender` keeps alive.
+        unsafe { &*self.chan }
+    }
+
+    /// Attempts to send a message into the channel without blocking.
+    ///
+    /// See [`Sender::try_send`] for details.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TrySendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let s = s.into_list().unwrap();
+    ///
+    /// assert_eq!(s.try_send(1), Ok(()));
+    /// drop(r);
+    /// assert_eq!(s.try_send(2), Err(TrySendError::Disconnected(2)));
+    /// ```
+    pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.sender.intercept(&msg);
+        self.chan().try_send(msg)
+    }
+
+    /// Sends a message into the channel.
+    ///
+    /// Since the channel is unbounded, this method never blocks. See [`Sender::send`] for
+    /// details.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let s = s.into_list().unwrap();
+    ///
+    /// assert_eq!(s.send(1), Ok(()));
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.sender.intercept(&msg);
+        self.chan().send(msg, None).map_err(|err| match err {
+            SendTimeoutError::Disconnected(msg) => SendError(msg),
+            SendTimeoutError::Timeout(_) => unreachable!(),
+        })
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, _r) = unbounded();
+    /// let s = s.into_list().unwrap();
+    ///
+    /// assert!(s.is_empty());
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.chan().is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, _r) = unbounded();
+    /// let s = s.into_list().unwrap();
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.chan().len()
+    }
+
+    /// Returns the generic sender this handle was converted from.
+    ///
+    /// This is useful for passing the sender to APIs that take a [`Sender`], like [`Select`].
+    ///
+    /// [`Sender`]: struct.Sender.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// let s = s.into_list().unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper = sel.send(s.as_sender());
+    /// let op = sel.select();
+    /// assert_eq!(op.index(), oper);
+    /// op.send(s.as_sender(), 1).unwrap();
+    /// # drop(r);
+    /// ```
+    pub fn as_sender(&self) -> &Sender<T> {
+        &self.sender
+    }
+
+    /// Converts this handle back into a generic sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let s = s.into_list().unwrap().into_sender();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn into_sender(self) -> Sender<T> {
+        self.sender
+    }
+}
+
+impl<T> Clone for ListSender<T> {
+    fn clone(&self) -> ListSender<T> {
+        ListSender::new(self.sender.clone(), self.chan)
+    }
+}
+
+impl<T> fmt::Debug for ListSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ListSender").finish()
+    }
+}
+
+/// The receiving side of an unbounded channel, specialized for the list flavor.
+///
+/// Created by [`Receiver::into_list`].
+///
+/// [`Receiver::into_list`]: struct.Receiver.html#method.into_list
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// let r = r.into_list().unwrap();
+///
+/// thread::spawn(move || {
+///     for i in 0..100 {
+///         s.send(i).unwrap();
+///     }
+/// });
+///
+/// let mut sum = 0;
+/// while let Ok(i) = r.recv() {
+///     sum += i;
+/// }
+/// assert_eq!(sum, 4950);
+/// ```
+pub struct ListReceiver<T> {
+    /// The generic receiver.
+    receiver: Receiver<T>,
+
+    /// The flavor inside the channel `receiver` points to.
+    chan: *const list::Channel<T>,
+}
+
+unsafe impl<T: Send> Send for ListReceiver<T> {}
+unsafe impl<T: Send> Sync for ListReceiver<T> {}
+
+impl<T> UnwindSafe for ListReceiver<T> {}
+impl<T> RefUnwindSafe for ListReceiver<T> {}
+
+impl<T> ListReceiver<T> {
+    /// Creates a specialized receiver from a generic one and its list flavor.
+    pub(crate) fn new(receiver: Receiver<T>, chan: *const list::Channel<T>) -> ListReceiver<T> {
+        ListReceiver { receiver, chan }
+    }
+
+    /// Returns the list flavor of the channel.
+    #[inline]
+    fn chan(&self) -> &list::Channel<T> {
+        // The flavor lives as long as the channel, which `self.receiver` keeps alive.
+        unsafe { &*self.chan }
+    }
+
+    /// Attempts to receive a message from the channel without blocking.
+    ///
+    /// See [`Receiver::try_recv`] for details.
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap();
+    ///
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// s.send(5).unwrap();
+    /// drop(s);
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.receiver.intercept(self.chan().try_recv())
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected.
+    ///
+    /// See [`Receiver::recv`] for details.
+    ///
+    /// [`Receiver::recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap();
+    ///
+    /// s.send(1).unwrap();
+    /// drop(s);
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.receiver
+            .recorder()
+            .record(|| self.receiver.intercept(self.chan().recv(None)))
+            .map_err(|_| RecvError)
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time.
+    ///
+    /// See [`Receiver::recv_timeout`] for details.
+    ///
+    /// [`Receiver::recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let r = r.into_list().unwrap();
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// # drop(s);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        self.receiver
+            .recorder()
+            .record(|| self.receiver.intercept(self.chan().recv(Some(deadline))))
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap();
+    ///
+    /// assert!(r.is_empty());
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.chan().is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap();
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.chan().len()
+    }
+
+    /// Returns the generic receiver this handle was converted from.
+    ///
+    /// This is useful for passing the receiver to APIs that take a [`Receiver`], like [`Select`].
+    ///
+    /// [`Receiver`]: struct.Receiver.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap();
+    /// s.send(1).unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let oper = sel.recv(r.as_receiver());
+    /// let op = sel.select();
+    /// assert_eq!(op.index(), oper);
+    /// assert_eq!(op.recv(r.as_receiver()), Ok(1));
+    /// ```
+    pub fn as_receiver(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Converts this handle back into a generic receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.into_list().unwrap().into_receiver();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
+    /// ```
+    pub fn into_receiver(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Clone for ListReceiver<T> {
+    fn clone(&self) -> ListReceiver<T> {
+        ListReceiver::new(self.receiver.clone(), self.chan)
+    }
+}
+
+impl<T> fmt::Debug for ListReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ListReceiver").finish()
+    }
+}
@@ -0,0 +1,91 @@
+//! Adaptive spinning before parking.
+//!
+//! A blocking operation that can't complete right away spins for a little while before parking
+//! the thread, hoping that the other side of the channel shows up in the meantime, because parking
+//! and unparking a thread is comparatively expensive. On a channel where the other side typically
+//! takes much longer than that, spinning only burns CPU time.
+//!
+//! Each channel therefore keeps a running average of how long its blocking operations wait, and
+//! operations skip spinning while the average is well beyond what spinning could cover. Every now
+//! and then an operation spins anyway, although the average keeps track of how long operations
+//! wait whether they spin or not.
+
+use std::cmp;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use stats;
+
+/// Operations spin only if the average wait is at most this many nanoseconds.
+const THRESHOLD: usize = 50_000;
+
+/// Every this many waits, an operation spins regardless of the average. Must be a power of two.
+const PROBE_INTERVAL: usize = 16;
+
+/// The spinning policy of a channel.
+pub struct Spin {
+    /// Equals `true` if spinning depends on the average wait.
+    adaptive: bool,
+
+    /// The exponential moving average of wait times, in nanoseconds.
+    average: AtomicUsize,
+
+    /// The number of waits observed so far.
+    waits: AtomicUsize,
+}
+
+impl Spin {
+    /// Creates a new spinning policy.
+    ///
+    /// If `adaptive` is `false`, operations always spin before parking.
+    pub fn new(adaptive: bool) -> Spin {
+        Spin {
+            // Measuring time is not supported on this target.
+            adaptive: adaptive && !cfg!(all(target_arch = "wasm32", target_os = "unknown")),
+            average: AtomicUsize::new(0),
+            waits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns `true` if operations are expected to be better off spinning before parking.
+    #[inline]
+    pub fn should_spin(&self) -> bool {
+        !self.adaptive
+            || self.average.load(Ordering::Relaxed) <= THRESHOLD
+            || self.waits.load(Ordering::Relaxed) & (PROBE_INTERVAL - 1) == 0
+    }
+
+    /// Returns `true` if the policy learns from observed waits.
+    #[inline]
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// Takes into account how long an operation has waited.
+    pub fn observe(&self, wait: Duration) {
+        let nanos = wait
+            .as_secs()
+            .saturating_mul(1_000_000_000)
+            .saturating_add(u64::from(wait.subsec_nanos()));
+        let sample = cmp::min(nanos, usize::max_value() as u64 / 8) as usize;
+
+        // Races between concurrent updates may lose a sample, which doesn't matter for an average.
+        let average = self.average.load(Ordering::Relaxed);
+        let average = average - average / 8 + sample / 8;
+        self.average.store(average, Ordering::Relaxed);
+        self.waits.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns `true` if the operation the current thread is performing should spin before parking.
+///
+/// Operations that don't belong to any channel, like selection over several channels, always spin.
+#[inline]
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn should_spin() -> bool {
+    match unsafe { stats::spin() } {
+        None => true,
+        Some(spin) => spin.should_spin(),
+    }
+}
@@ -0,0 +1,409 @@
+//! Splitting a channel into two receivers by a predicate.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+use parking_lot::{Condvar, Mutex};
+
+use channel::Receiver;
+use context::Context;
+use err::TryRecvError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvError, RecvTimeoutError};
+use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use utils;
+use waker::SyncWaker;
+
+/// Messages handed over between the two sides of a split.
+struct Inner<T> {
+    /// Messages taken out of the channel for each side: matching messages first, then the rest.
+    queues: [VecDeque<T>; 2],
+
+    /// The number of receivers on each side.
+    receivers: [usize; 2],
+
+    /// Equals `true` while a receiver is blocked on the channel.
+    ///
+    /// Only one receiver at a time takes messages out of the channel so that each side receives
+    /// its messages in the order they were sent.
+    pulling: bool,
+}
+
+/// State shared by both sides of a split.
+struct Split<T> {
+    /// The channel that was split.
+    receiver: Receiver<T>,
+
+    /// Decides which side a message belongs to.
+    pred: Box<Fn(&T) -> bool + Send + Sync>,
+
+    /// Messages handed over between the sides.
+    inner: Mutex<Inner<T>>,
+
+    /// Signaled when a message is handed over, taken by its side, or when the channel is no
+    /// longer being pulled from.
+    cond: Condvar,
+
+    /// The most messages handed over to a side and not yet received by it.
+    limit: usize,
+
+    /// Selections waiting for a receive operation on either side to become ready.
+    watchers: SyncWaker,
+}
+
+impl<T> Split<T> {
+    /// Returns the side a message belongs to.
+    fn side_of(&self, msg: &T) -> usize {
+        if (self.pred)(msg) {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Hands a message over to the side it belongs to, or drops it if that side is gone.
+    fn hand_over(&self, inner: &mut Inner<T>, side: usize, msg: T) {
+        if inner.receivers[side] > 0 {
+            inner.queues[side].push_back(msg);
+            self.wake();
+        }
+    }
+
+    /// Takes a message handed over to a side.
+    fn take(&self, inner: &mut Inner<T>, side: usize) -> Option<T> {
+        let msg = inner.queues[side].pop_front();
+
+        // The other side may be waiting for room to pull from the channel.
+        if msg.is_some() && inner.queues[side].len() + 1 == self.limit {
+            self.wake();
+        }
+        msg
+    }
+
+    /// Returns `true` if a receiver of `side` may take a message out of the channel.
+    ///
+    /// Messages belonging to the other side stay in the channel while the other side already holds
+    /// `limit` handed over messages, so that a lagging side exerts backpressure on senders.
+    fn can_pull(&self, inner: &Inner<T>, side: usize) -> bool {
+        let other = 1 - side;
+        !inner.pulling && (inner.receivers[other] == 0 || inner.queues[other].len() < self.limit)
+    }
+
+    /// Wakes up blocked receivers and selections so that they check both sides again.
+    fn wake(&self) {
+        self.cond.notify_all();
+        self.watchers.notify();
+    }
+}
+
+/// Splits a receiver into one receiving messages that satisfy `pred` and one receiving the rest.
+pub fn split<T, F>(receiver: Receiver<T>, pred: F) -> (SplitReceiver<T>, SplitReceiver<T>)
+where
+    F: Fn(&T) -> bool + Send + Sync + 'static,
+{
+    let limit = receiver.capacity().map_or(usize::max_value(), |cap| cmp::max(cap, 1));
+    let split = Arc::new(Split {
+        receiver,
+        pred: Box::new(pred),
+        inner: Mutex::new(Inner {
+            queues: [VecDeque::new(), VecDeque::new()],
+            receivers: [1, 1],
+            pulling: false,
+        }),
+        cond: Condvar::new(),
+        limit,
+        watchers: SyncWaker::new(),
+    });
+
+    let matching = SplitReceiver {
+        split: split.clone(),
+        side: 0,
+    };
+    let rest = SplitReceiver { split, side: 1 };
+    (matching, rest)
+}
+
+/// One side of a channel split by a predicate.
+///
+/// Receive operations take messages out of the channel on behalf of both sides. Messages
+/// belonging to the other side are handed over to it, so no thread is needed to move messages
+/// around. Each side receives its messages in the order they were sent. Once all receivers of the
+/// other side are dropped, its messages are discarded instead.
+///
+/// Messages handed over to a side wait for it outside the channel. If the channel is bounded, a
+/// side holds at most as many of them as the channel's capacity. Once the limit is reached, the
+/// other side stops taking messages out of the channel until the lagging side catches up, so the
+/// channel fills up and senders block as they would without the split.
+///
+/// A split receiver can be waited on in [`Select`] with [`Select::recv_ready`]. The operation is
+/// ready once a receive operation on this side would not block.
+///
+/// Receivers are created by [`Receiver::split_by`].
+///
+/// [`Receiver::split_by`]: struct.Receiver.html#method.split_by
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+pub struct SplitReceiver<T> {
+    /// State shared by both sides.
+    split: Arc<Split<T>>,
+
+    /// Index of this side: 0 for matching messages, 1 for the rest.
+    side: usize,
+}
+
+impl<T> SplitReceiver<T> {
+    /// Attempts to receive a message belonging to this side without blocking.
+    ///
+    /// See [`Receiver::try_recv`] for details.
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let (even, odd) = r.split_by(|&x| x % 2 == 0);
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(even.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// drop(s);
+    /// assert_eq!(odd.try_recv(), Ok(1));
+    /// assert_eq!(odd.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let split = &*self.split;
+        let mut inner = split.inner.lock();
+
+        loop {
+            if let Some(msg) = split.take(&mut inner, self.side) {
+                return Ok(msg);
+            }
+
+            // Either another receiver is blocked on the channel, so it must be empty, or the other
+            // side has to catch up first.
+            if !split.can_pull(&inner, self.side) {
+                return Err(TryRecvError::Empty);
+            }
+
+            let msg = split.receiver.try_recv()?;
+            let side = split.side_of(&msg);
+            if side == self.side {
+                return Ok(msg);
+            }
+            split.hand_over(&mut inner, side, msg);
+        }
+    }
+
+    /// Blocks the current thread until a message belonging to this side is received or the
+    /// channel is disconnected and holds no such messages.
+    ///
+    /// See [`Receiver::recv`] for details.
+    ///
+    /// [`Receiver::recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Msg {
+    ///     Data(i32),
+    ///     Stop,
+    /// }
+    ///
+    /// let (s, r) = unbounded();
+    /// let (control, data) = r.split_by(|msg| *msg == Msg::Stop);
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(Msg::Data(1)).unwrap();
+    ///     s.send(Msg::Stop).unwrap();
+    ///     s.send(Msg::Data(2)).unwrap();
+    /// });
+    ///
+    /// assert_eq!(control.recv(), Ok(Msg::Stop));
+    /// assert_eq!(control.recv(), Err(RecvError));
+    ///
+    /// assert_eq!(data.recv(), Ok(Msg::Data(1)));
+    /// assert_eq!(data.recv(), Ok(Msg::Data(2)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Waits for a message belonging to this side to be received, but only for a limited time.
+    ///
+    /// See [`Receiver::recv_timeout`] for details.
+    ///
+    /// [`Receiver::recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let (big, small) = r.split_by(|&x| x > 100);
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(
+    ///     big.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// assert_eq!(small.recv_timeout(Duration::from_millis(100)), Ok(1));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(Instant::now() + timeout))
+    }
+
+    /// Receives a message belonging to this side, blocking until the deadline if there is one.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let split = &*self.split;
+        let mut inner = split.inner.lock();
+
+        loop {
+            if let Some(msg) = split.take(&mut inner, self.side) {
+                return Ok(msg);
+            }
+
+            if !split.can_pull(&inner, self.side) {
+                // Wait for the receiver blocked on the channel to hand over a message or give up,
+                // or for the other side to make room.
+                match deadline {
+                    None => split.cond.wait(&mut inner),
+                    Some(d) => {
+                        if split.cond.wait_until(&mut inner, d).timed_out() {
+                            return match split.take(&mut inner, self.side) {
+                                Some(msg) => Ok(msg),
+                                None => Err(RecvTimeoutError::Timeout),
+                            };
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // Block on the channel without holding the lock.
+            inner.pulling = true;
+            let res = {
+                drop(inner);
+                let res = match deadline {
+                    None => split
+                        .receiver
+                        .recv()
+                        .map_err(|_| RecvTimeoutError::Disconnected),
+                    Some(d) => split.receiver.recv_timeout(utils::remaining(d)),
+                };
+                inner = split.inner.lock();
+                res
+            };
+            inner.pulling = false;
+            split.wake();
+
+            let msg = res?;
+            let side = split.side_of(&msg);
+            if side == self.side {
+                return Ok(msg);
+            }
+            split.hand_over(&mut inner, side, msg);
+        }
+    }
+}
+
+impl<T> Drop for SplitReceiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.split.inner.lock();
+        inner.receivers[self.side] -= 1;
+
+        // Nobody is going to receive messages of this side anymore.
+        if inner.receivers[self.side] == 0 {
+            inner.queues[self.side].clear();
+            self.split.wake();
+        }
+    }
+}
+
+impl<T> Clone for SplitReceiver<T> {
+    fn clone(&self) -> SplitReceiver<T> {
+        self.split.inner.lock().receivers[self.side] += 1;
+        SplitReceiver {
+            split: self.split.clone(),
+            side: self.side,
+        }
+    }
+}
+
+impl<T> SplitReceiver<T> {
+    /// Returns `true` if a receive operation on this side would not block.
+    fn ready(&self) -> bool {
+        let split = &*self.split;
+        let inner = split.inner.lock();
+        !inner.queues[self.side].is_empty()
+            || (split.can_pull(&inner, self.side) && split.receiver.is_ready())
+    }
+}
+
+impl<T> SelectHandle for SplitReceiver<T> {
+    fn try(&self, _token: &mut Token) -> bool {
+        self.ready()
+    }
+
+    fn retry(&self, _token: &mut Token) -> bool {
+        self.ready()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.watch(oper, cx);
+        !self.ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.unwatch(oper);
+    }
+
+    fn accept(&self, _token: &mut Token, _cx: &Context) -> bool {
+        self.ready()
+    }
+
+    fn state(&self) -> usize {
+        self.split.receiver.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.split.watchers.watch(oper, cx);
+        self.split.receiver.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.split.receiver.unwatch(oper);
+        self.split.watchers.unwatch(oper);
+    }
+}
+
+impl<T> fmt::Debug for SplitReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SplitReceiver").finish()
+    }
+}
@@ -0,0 +1,613 @@
+//! Messages set aside by selective receive operations.
+//!
+//! A selective receive operation takes messages out of the channel until it finds one it's looking
+//! for. Messages it has no use for cannot be put back into the channel, so they are moved into
+//! the stash instead. Every receive operation looks into the stash before looking into the
+//! channel, which preserves the order of messages.
+//!
+//! A message that was taken out of the channel but hasn't been set aside yet is neither in the
+//! channel nor in the stash. Selective receive operations therefore hold the stash while they
+//! might be carrying such a message. Receive operations finding the channel disconnected while
+//! the stash is held are not ready yet: non-blocking ones report the channel as empty, and
+//! blocking ones sleep until the last holder wakes them up.
+//!
+//! Selective receive operations looking into the stash take one message out at a time and check
+//! it without locking the stash, so that their predicates never run under the lock. While a
+//! message is out for inspection, other receive operations skip it, and the ones that find
+//! nothing else are woken up once it's back.
+//!
+//! The stash is allocated when the first message is put into it or a selective receive operation
+//! first holds it. Until then, a stash is a single null pointer that receive operations check, so
+//! channels that never set messages aside or put them back don't pay for the stash.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+use parking_lot::Mutex;
+
+use select::Token;
+#[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use waker::SyncWaker;
+
+/// The token type for messages taken out of the stash.
+#[derive(Debug)]
+pub struct StashToken {
+    /// The ID of the message claimed for the follow-up call to `read`, if there is one.
+    claimed: Option<usize>,
+}
+
+impl Default for StashToken {
+    #[inline]
+    fn default() -> Self {
+        StashToken { claimed: None }
+    }
+}
+
+/// Set in `Inner::ready` while some message is out for inspection.
+const INSPECTING: usize = 1;
+
+/// A message in the stash.
+struct Entry<T> {
+    /// Identifies the message, so that claims stick to it while other messages come and go.
+    id: usize,
+
+    /// The message, or `None` while it's out for inspection.
+    msg: Option<T>,
+
+    /// Equals `true` if the message was claimed by a receive operation or is out for inspection.
+    claimed: bool,
+}
+
+/// The messages in the stash, in the order they are going to be received.
+struct Queue<T> {
+    /// The messages, including claimed ones that are about to be read.
+    entries: VecDeque<Entry<T>>,
+
+    /// The ID of the next message pushed into the queue.
+    next_id: usize,
+
+    /// The number of messages that are not claimed.
+    unclaimed: usize,
+
+    /// The number of messages out for inspection.
+    inspecting: usize,
+}
+
+impl<T> Queue<T> {
+    /// Creates a new entry for `msg`.
+    fn entry(&mut self, msg: T) -> Entry<T> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.unclaimed += 1;
+        Entry {
+            id,
+            msg: Some(msg),
+            claimed: false,
+        }
+    }
+
+    /// Returns the index of the message with `id`, looking at `hint` first.
+    fn position(&self, id: usize, hint: usize) -> usize {
+        match self.entries.get(hint) {
+            Some(entry) if entry.id == id => hint,
+            _ => self
+                .entries
+                .iter()
+                .position(|entry| entry.id == id)
+                .expect("claimed message is missing from the stash"),
+        }
+    }
+
+    /// Returns the index of the oldest unclaimed message at `from` or later.
+    ///
+    /// If a message out for inspection is skipped, `missed` is set.
+    fn find_unclaimed(&self, from: usize, missed: &AtomicBool) -> Option<usize> {
+        for (i, entry) in self.entries.iter().enumerate().skip(from) {
+            if !entry.claimed {
+                return Some(i);
+            }
+            if entry.msg.is_none() {
+                missed.store(true, Ordering::SeqCst);
+            }
+        }
+        None
+    }
+}
+
+/// The part of the stash that is allocated once the stash is first used.
+struct Inner<T> {
+    /// The messages.
+    queue: Mutex<Queue<T>>,
+
+    /// The number of unclaimed messages, shifted left by one, with the `INSPECTING` bit.
+    ///
+    /// This is only changed while the queue is locked, but receive operations read it without
+    /// locking in order to find out whether there's a message to claim.
+    ready: AtomicUsize,
+
+    /// The number of messages that are not claimed for reading, including ones out for
+    /// inspection.
+    len: AtomicUsize,
+
+    /// The number of messages ever pushed into the queue.
+    stamp: AtomicUsize,
+
+    /// The number of selective receive operations holding the stash.
+    holders: AtomicUsize,
+
+    /// Equals `true` if a receive operation found the stash held or skipped a message out for
+    /// inspection, and must be woken up once the stash is released or the message is back.
+    missed: AtomicBool,
+}
+
+impl<T> Inner<T> {
+    /// Publishes the counts in `queue` to receive operations that don't lock the queue.
+    fn publish(&self, queue: &Queue<T>) {
+        let inspecting = if queue.inspecting > 0 { INSPECTING } else { 0 };
+        self.ready.store(queue.unclaimed << 1 | inspecting, Ordering::SeqCst);
+        self.len.store(queue.unclaimed + queue.inspecting, Ordering::SeqCst);
+    }
+}
+
+/// A queue of messages set aside by selective receive operations.
+pub struct Stash<T> {
+    /// The messages, or null if the stash was never used.
+    inner: AtomicPtr<Inner<T>>,
+
+    /// Indicates that dropping a `Stash<T>` may drop values of type `T`.
+    _marker: PhantomData<T>,
+}
+
+/// A guard that keeps the stash held by a selective receive operation.
+#[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+pub struct Hold<'a> {
+    holders: &'a AtomicUsize,
+
+    /// Set by receive operations that need to be woken up once the stash is released.
+    missed: &'a AtomicBool,
+
+    /// Receivers to wake up once the stash is released.
+    receivers: &'a SyncWaker,
+}
+
+#[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+impl<'a> Drop for Hold<'a> {
+    fn drop(&mut self) {
+        if self.holders.fetch_sub(1, Ordering::SeqCst) == 1
+            && self.missed.swap(false, Ordering::SeqCst)
+        {
+            self.receivers.notify_all();
+        }
+    }
+}
+
+/// A message taken out of the stash for inspection by `take_where`.
+///
+/// Unless the message is taken, it is returned to its place when the inspection is dropped, even
+/// if the predicate panics.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct Inspection<'a, T: 'a, W: Fn() + 'a> {
+    inner: &'a Inner<T>,
+
+    /// The ID of the message.
+    id: usize,
+
+    /// Where the message was in the queue when it was taken out.
+    index: usize,
+
+    /// The message, unless it was taken or returned.
+    msg: Option<T>,
+
+    /// The stamp the selective receive operation compares against, which shouldn't wake it up
+    /// because of its own inspection.
+    stamp: &'a mut usize,
+
+    /// Wakes up blocked receive operations.
+    wake: &'a W,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T, W: Fn()> Inspection<'a, T, W> {
+    /// Takes the message out of the stash for good.
+    fn take(mut self) -> T {
+        let msg = self.msg.take().unwrap();
+        {
+            let mut queue = self.inner.queue.lock();
+            let index = queue.position(self.id, self.index);
+            queue.entries.remove(index);
+            queue.inspecting -= 1;
+            self.inner.publish(&queue);
+        }
+        self.finish(false);
+        msg
+    }
+
+    /// Returns the message to its place in the stash and returns its index.
+    fn restore(&mut self) -> usize {
+        let msg = self.msg.take().unwrap();
+        let index = {
+            let mut queue = self.inner.queue.lock();
+            let index = queue.position(self.id, self.index);
+            {
+                let entry = &mut queue.entries[index];
+                entry.msg = Some(msg);
+                entry.claimed = false;
+            }
+            queue.unclaimed += 1;
+            queue.inspecting -= 1;
+            self.inner.publish(&queue);
+            index
+        };
+        self.finish(true);
+        index
+    }
+
+    /// Wakes up receive operations that missed the message while it was out for inspection.
+    fn finish(&mut self, restored: bool) {
+        if self.inner.missed.swap(false, Ordering::SeqCst) {
+            if restored {
+                // Selective receive operations that skipped the message wait for a new stamp.
+                self.inner.stamp.fetch_add(1, Ordering::SeqCst);
+                *self.stamp = self.stamp.wrapping_add(1);
+            }
+            (self.wake)();
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T, W: Fn()> Drop for Inspection<'a, T, W> {
+    fn drop(&mut self) {
+        if self.msg.is_some() {
+            self.restore();
+        }
+    }
+}
+
+impl<T> Stash<T> {
+    /// Creates an empty stash.
+    pub fn new() -> Self {
+        Stash {
+            inner: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the messages, unless none was ever put into the stash.
+    #[inline]
+    fn inner(&self) -> Option<&Inner<T>> {
+        unsafe { self.inner.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// Returns the messages, allocating them first if needed.
+    fn inner_or_alloc(&self) -> &Inner<T> {
+        if let Some(inner) = self.inner() {
+            return inner;
+        }
+
+        let new = Box::into_raw(Box::new(Inner {
+            queue: Mutex::new(Queue {
+                entries: VecDeque::new(),
+                next_id: 0,
+                unclaimed: 0,
+                inspecting: 0,
+            }),
+            ready: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            stamp: AtomicUsize::new(0),
+            holders: AtomicUsize::new(0),
+            missed: AtomicBool::new(false),
+        }));
+
+        match self.inner.compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => unsafe { &*new },
+            Err(current) => unsafe {
+                // Another thread was faster.
+                drop(Box::from_raw(new));
+                &*current
+            },
+        }
+    }
+
+    /// Returns the number of messages in the stash.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner().map_or(0, |inner| inner.len.load(Ordering::SeqCst))
+    }
+
+    /// Returns the number of bytes the stashed messages take up on the heap.
+    pub fn memory_usage(&self) -> usize {
+        self.len() * mem::size_of::<T>()
+    }
+
+    /// Returns `true` if there's no message in the stash that a receive operation could claim.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner()
+            .map_or(true, |inner| inner.ready.load(Ordering::SeqCst) >> 1 == 0)
+    }
+
+    /// Returns the number of messages ever pushed into the stash.
+    ///
+    /// Selective receive operations use this number to find out whether new messages might have
+    /// been set aside while they were not looking.
+    #[inline]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn stamp(&self) -> usize {
+        self.inner().map_or(0, |inner| inner.stamp.load(Ordering::SeqCst))
+    }
+
+    /// Holds the stash until the returned guard is dropped.
+    ///
+    /// Selective receive operations must hold the stash while they might take a message out of
+    /// the channel and set it aside. When the last guard is dropped, `receivers` are woken up if
+    /// some receive operation found the stash held.
+    #[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    pub fn hold<'a>(&'a self, receivers: &'a SyncWaker) -> Hold<'a> {
+        let inner = self.inner_or_alloc();
+        inner.holders.fetch_add(1, Ordering::SeqCst);
+        Hold {
+            holders: &inner.holders,
+            missed: &inner.missed,
+            receivers,
+        }
+    }
+
+    /// Returns `true` if a selective receive operation holds the stash or has a message out for
+    /// inspection.
+    ///
+    /// Receive operations finding the channel disconnected must check this method before looking
+    /// into the stash for the last time, and must not conclude that the channel is disconnected
+    /// while it returns `true`. They are woken up once that changes.
+    #[inline]
+    pub fn is_held(&self) -> bool {
+        let inner = match self.inner() {
+            None => return false,
+            Some(inner) => inner,
+        };
+        let held = inner.holders.load(Ordering::SeqCst) > 0
+            || inner.ready.load(Ordering::SeqCst) & INSPECTING != 0;
+        if held {
+            inner.missed.store(true, Ordering::SeqCst);
+        }
+        held
+    }
+
+    /// Sets a message aside.
+    ///
+    /// The caller is responsible for waking up blocked receive operations afterwards.
+    #[cfg(any(feature = "serde", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    pub fn push(&self, msg: T) {
+        let inner = self.inner_or_alloc();
+        let mut queue = inner.queue.lock();
+        let entry = queue.entry(msg);
+        queue.entries.push_back(entry);
+        inner.publish(&queue);
+        inner.stamp.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Puts a message back in front of the stash, so that it is the next one to be received.
+    ///
+    /// The caller is responsible for waking up blocked receive operations afterwards.
+    pub fn push_front(&self, msg: T) {
+        let inner = self.inner_or_alloc();
+        let mut queue = inner.queue.lock();
+        let entry = queue.entry(msg);
+        queue.entries.push_front(entry);
+        inner.publish(&queue);
+        inner.stamp.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Puts messages back in front of the stash, in order, so that they are the next ones to be
+    /// received.
+    ///
+    /// The caller is responsible for waking up blocked receive operations afterwards.
+    #[cfg(feature = "serde")]
+    pub fn push_front_many(&self, msgs: Vec<T>) {
+        let inner = self.inner_or_alloc();
+        let mut queue = inner.queue.lock();
+        for msg in msgs.into_iter().rev() {
+            let entry = queue.entry(msg);
+            queue.entries.push_front(entry);
+            inner.stamp.fetch_add(1, Ordering::SeqCst);
+        }
+        inner.publish(&queue);
+    }
+
+    /// Returns clones of all unclaimed messages in the stash, oldest first.
+    ///
+    /// Messages out for inspection are not included.
+    #[cfg(feature = "serde")]
+    pub fn cloned(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let inner = match self.inner() {
+            None => return Vec::new(),
+            Some(inner) => inner,
+        };
+        let queue = inner.queue.lock();
+        queue
+            .entries
+            .iter()
+            .filter(|entry| !entry.claimed)
+            .filter_map(|entry| entry.msg.clone())
+            .collect()
+    }
+
+    /// Attempts to claim the oldest unclaimed message in the stash for the follow-up call to
+    /// `read`.
+    #[inline]
+    pub fn start_recv(&self, token: &mut Token) -> bool {
+        let inner = match self.inner() {
+            None => return false,
+            Some(inner) => inner,
+        };
+        if inner.ready.load(Ordering::SeqCst) >> 1 == 0 {
+            self.skip(inner);
+            return false;
+        }
+
+        let mut queue = inner.queue.lock();
+        match queue.find_unclaimed(0, &inner.missed) {
+            None => false,
+            Some(index) => {
+                let id = {
+                    let entry = &mut queue.entries[index];
+                    entry.claimed = true;
+                    entry.id
+                };
+                queue.unclaimed -= 1;
+                inner.publish(&queue);
+                token.stash.claimed = Some(id);
+                true
+            }
+        }
+    }
+
+    /// Notes that a receive operation found nothing to claim, in case it skipped a message out for
+    /// inspection.
+    fn skip(&self, inner: &Inner<T>) {
+        if inner.ready.load(Ordering::SeqCst) & INSPECTING != 0 {
+            inner.missed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Takes the message claimed by `start_recv` out of the stash, if there is one.
+    #[inline]
+    pub fn read(&self, token: &mut Token) -> Option<T> {
+        let id = token.stash.claimed.take()?;
+        let inner = self.inner().unwrap();
+
+        // Claimed messages are usually the oldest ones.
+        let mut queue = inner.queue.lock();
+        let index = queue.position(id, 0);
+        queue.entries.remove(index).and_then(|entry| entry.msg)
+    }
+
+    /// Takes the oldest unclaimed message out of the stash.
+    pub fn pop(&self) -> Option<T> {
+        let token = &mut Token::default();
+        if self.start_recv(token) {
+            self.read(token)
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the newest unclaimed message in the stash with `msg`.
+    ///
+    /// If there's no such message, `msg` is returned back as an error.
+    pub fn replace_newest(&self, msg: T) -> Result<T, T> {
+        let inner = match self.inner() {
+            None => return Err(msg),
+            Some(inner) => inner,
+        };
+        let mut queue = inner.queue.lock();
+        match queue.entries.iter_mut().rev().find(|entry| !entry.claimed) {
+            None => Err(msg),
+            Some(newest) => {
+                let old = mem::replace(&mut newest.msg, Some(msg));
+                inner.stamp.fetch_add(1, Ordering::SeqCst);
+                Ok(old.unwrap())
+            }
+        }
+    }
+
+    /// Takes up to `limit` of the oldest unclaimed messages out of the stash and pushes them into
+    /// `buf`.
+    ///
+    /// Returns the number of messages taken.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn pop_many(&self, buf: &mut Vec<T>, limit: usize) -> usize {
+        let inner = match self.inner() {
+            None => return 0,
+            Some(inner) => inner,
+        };
+        if limit == 0 || inner.ready.load(Ordering::SeqCst) >> 1 == 0 {
+            self.skip(inner);
+            return 0;
+        }
+
+        let mut queue = inner.queue.lock();
+        let mut count = 0;
+        let mut index = 0;
+        while count < limit {
+            index = match queue.find_unclaimed(index, &inner.missed) {
+                None => break,
+                Some(index) => index,
+            };
+            let entry = queue.entries.remove(index).unwrap();
+            buf.push(entry.msg.unwrap());
+            count += 1;
+        }
+        queue.unclaimed -= count;
+        inner.publish(&queue);
+        count
+    }
+
+    /// Takes the oldest message satisfying `pred` out of the stash.
+    ///
+    /// Messages are taken out for inspection one at a time, so `pred` runs without locking the
+    /// stash. If another receive operation skipped a message while it was out, the stamp is
+    /// bumped once the message is back and `wake` is called to wake up blocked receive
+    /// operations. The caller's `stamp` is bumped too, so that it isn't woken up by its own
+    /// inspection.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn take_where<F, W>(&self, pred: &mut F, stamp: &mut usize, wake: W) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+        W: Fn(),
+    {
+        let inner = self.inner()?;
+        let mut from = 0;
+
+        loop {
+            let (index, id, msg) = {
+                let mut queue = inner.queue.lock();
+                let index = queue.find_unclaimed(from, &inner.missed)?;
+                let (id, msg) = {
+                    let entry = &mut queue.entries[index];
+                    entry.claimed = true;
+                    (entry.id, entry.msg.take().unwrap())
+                };
+                queue.unclaimed -= 1;
+                queue.inspecting += 1;
+                inner.publish(&queue);
+                (index, id, msg)
+            };
+
+            let mut inspection = Inspection {
+                inner,
+                id,
+                index,
+                msg: Some(msg),
+                stamp: &mut *stamp,
+                wake: &wake,
+            };
+            if pred(inspection.msg.as_ref().unwrap()) {
+                return Some(inspection.take());
+            }
+            from = inspection.restore() + 1;
+        }
+    }
+}
+
+impl<T> Drop for Stash<T> {
+    fn drop(&mut self) {
+        let inner = *self.inner.get_mut();
+        if !inner.is_null() {
+            unsafe {
+                drop(Box::from_raw(inner));
+            }
+        }
+    }
+}
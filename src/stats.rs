@@ -0,0 +1,184 @@
+//! Accounting of time spent blocked in channel operations.
+//!
+//! Each handle owns a `Recorder` and runs its blocking operations through it. While an operation
+//! is running, a thread-local points to it, so that the parking code in `Context::wait_until` can
+//! attribute the time it spends parked to the right handle without threading anything through the
+//! flavors. The same goes for the spinning policy of the handle's channel.
+
+use std::cell::Cell;
+use std::ptr::NonNull;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use spin::Spin;
+
+/// Statistics about blocking operations on a channel handle.
+///
+/// An operation blocks when it can't complete right away and the thread has to be parked until
+/// the other side of the channel makes progress. Operations that complete while spinning before
+/// parking are not counted. Every handle keeps its own statistics, which start at zero when the
+/// handle is created or cloned.
+///
+/// Waiting for one of several operations in [`select!`] or [`Select`] is not attributed to any
+/// handle and doesn't show up in the statistics. Neither does waiting in the channels created by
+/// [`after`], [`tick`], and [`never`], which sleep instead of waiting for other threads.
+///
+/// The statistics are returned by [`Sender::stats`] and [`Receiver::stats`].
+///
+/// [`select!`]: macro.select.html
+/// [`Select`]: struct.Select.html
+/// [`after`]: fn.after.html
+/// [`tick`]: fn.tick.html
+/// [`never`]: fn.never.html
+/// [`Sender::stats`]: struct.Sender.html#method.stats
+/// [`Receiver::stats`]: struct.Receiver.html#method.stats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockingStats {
+    /// The number of operations that blocked.
+    pub blocked: u64,
+
+    /// The total time spent blocked.
+    pub blocked_time: Duration,
+}
+
+/// A blocking operation in progress.
+pub struct Operation {
+    /// Time the operation has spent parked so far, if it was parked at all.
+    parked: Cell<Option<Duration>>,
+
+    /// The spinning policy of the channel, or null if the handle doesn't belong to a channel.
+    spin: *const Spin,
+}
+
+thread_local! {
+    /// The operation the current thread is performing on behalf of a handle.
+    static CURRENT: Cell<Option<NonNull<Operation>>> = Cell::default();
+}
+
+/// Collects blocking statistics of a single handle.
+pub struct Recorder {
+    stats: Mutex<BlockingStats>,
+
+    /// The spinning policy of the channel the handle belongs to.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    spin: Option<Arc<Spin>>,
+}
+
+impl Recorder {
+    /// Creates a recorder with empty statistics.
+    pub fn new() -> Recorder {
+        Recorder {
+            stats: Mutex::new(BlockingStats::default()),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            spin: None,
+        }
+    }
+
+    /// Creates a recorder with empty statistics for a handle to a channel with a spinning policy.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn with_spin(spin: Arc<Spin>) -> Recorder {
+        Recorder {
+            stats: Mutex::new(BlockingStats::default()),
+            spin: Some(spin),
+        }
+    }
+
+    /// Creates a recorder with empty statistics, ignoring the spinning policy since nothing blocks.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    pub fn with_spin(_spin: Arc<Spin>) -> Recorder {
+        Recorder::new()
+    }
+
+    /// Returns the statistics collected so far.
+    pub fn stats(&self) -> BlockingStats {
+        *self.stats.lock()
+    }
+
+    /// Runs an operation, recording the time it spends parked.
+    #[inline]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn record<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let op = Operation {
+            parked: Cell::new(None),
+            spin: self.spin.as_ref().map_or(ptr::null(), |spin| &**spin),
+        };
+        if CURRENT.try_with(|c| c.set(Some(NonNull::from(&op)))).is_err() {
+            return f();
+        }
+
+        let res = {
+            let _guard = Guard;
+            f()
+        };
+
+        if let Some(time) = op.parked.get() {
+            let mut stats = self.stats.lock();
+            stats.blocked += 1;
+            stats.blocked_time += time;
+        }
+        res
+    }
+}
+
+/// Clears the current operation when dropped, even if the operation panics.
+///
+/// The current operation is cleared rather than restored to the one before it. With a custom
+/// parker, the previous value may belong to another execution context sharing the thread, which
+/// could have finished in the meantime.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct Guard;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|c| c.set(None));
+    }
+}
+
+/// Returns the spinning policy of the channel the current operation belongs to.
+///
+/// # Safety
+///
+/// The returned reference must not be used after the current operation completes.
+#[inline]
+pub unsafe fn spin<'a>() -> Option<&'a Spin> {
+    match CURRENT.try_with(|c| c.get()) {
+        Ok(Some(op)) => op.as_ref().spin.as_ref(),
+        _ => None,
+    }
+}
+
+/// Takes the current operation before parking the current thread.
+///
+/// The current operation is cleared so that other execution contexts sharing the thread don't
+/// attribute their parking to it.
+#[inline]
+pub fn take() -> Option<NonNull<Operation>> {
+    CURRENT.try_with(|c| c.replace(None)).unwrap_or(None)
+}
+
+/// Records time spent parked on behalf of an operation taken with `take`, and makes it the
+/// current operation again.
+#[inline]
+pub fn parked(op: Option<NonNull<Operation>>, time: Duration) {
+    let op = match op {
+        None => return,
+        Some(op) => op,
+    };
+    let _ = CURRENT.try_with(|c| c.set(Some(op)));
+
+    // The operation lives on the stack of `Recorder::record`, which is still running because the
+    // thread has only just returned from parking inside it.
+    let parked = unsafe { &op.as_ref().parked };
+    parked.set(Some(match parked.get() {
+        None => time,
+        Some(t) => t + time,
+    }));
+}
@@ -0,0 +1,714 @@
+//! Channels that tag messages with the sender they came from.
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use channel::{bounded, unbounded, Receiver, Sender};
+use context::Context;
+use err::TryRecvError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{RecvError, RecvTimeoutError};
+use err::TrySendError;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{SendError, SendTimeoutError};
+use select::{Operation, Ready, SelectHandle, Token};
+
+/// Identifies the sender a message came from.
+///
+/// Every [`TaggedSender`] has its own id, which is unique within its channel. The first sender
+/// gets id 0, and each clone gets the next unused number.
+///
+/// [`TaggedSender`]: struct.TaggedSender.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded_tagged;
+///
+/// let (s1, r) = unbounded_tagged();
+/// let s2 = s1.clone();
+///
+/// s2.send("foo").unwrap();
+/// let (id, msg) = r.recv_tagged().unwrap();
+///
+/// assert_eq!(id, s2.id());
+/// assert_ne!(id, s1.id());
+/// assert_eq!(id.as_usize(), 1);
+/// assert_eq!(msg, "foo");
+/// ```
+///
+/// If the `serde` feature is enabled, ids are serialized as plain numbers, so tagged messages can
+/// be serialized as `(SenderId, T)` pairs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SenderId(usize);
+
+impl SenderId {
+    /// Returns the id as a number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged::<i32>();
+    /// assert_eq!(s.id().as_usize(), 0);
+    /// assert_eq!(s.clone().id().as_usize(), 1);
+    /// # drop(r);
+    /// ```
+    pub fn as_usize(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for SenderId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SenderId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SenderId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SenderId, D::Error> {
+        usize::deserialize(deserializer).map(SenderId)
+    }
+}
+
+/// Creates a channel of bounded capacity whose messages are tagged with the id of their sender.
+///
+/// Every clone of the sending side gets its own [`SenderId`], and every message sent through it
+/// is tagged with that id. The receiving side can then tell which sender each message came from
+/// with [`TaggedReceiver::recv_tagged`]. Messages sent through the same sender are received in the
+/// order they were sent.
+///
+/// Apart from tagging, the channel behaves exactly like one created by [`bounded`].
+///
+/// # Panics
+///
+/// Panics if the capacity is greater than `usize::max_value() / 4`.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::bounded_tagged;
+///
+/// let (s, r) = bounded_tagged(10);
+///
+/// for i in 0..3 {
+///     let s = s.clone();
+///     thread::spawn(move || s.send(i * 10).unwrap());
+/// }
+/// drop(s);
+///
+/// for (id, msg) in r.iter() {
+///     // The original sender has id 0, so the clones have ids 1 to 3.
+///     assert_eq!(msg, (id.as_usize() - 1) * 10);
+/// }
+/// ```
+///
+/// [`bounded`]: fn.bounded.html
+/// [`SenderId`]: struct.SenderId.html
+/// [`TaggedReceiver::recv_tagged`]: struct.TaggedReceiver.html#method.recv_tagged
+pub fn bounded_tagged<T>(cap: usize) -> (TaggedSender<T>, TaggedReceiver<T>) {
+    let (s, r) = bounded(cap);
+    tagged(s, r)
+}
+
+/// Creates a channel of unbounded capacity whose messages are tagged with the id of their sender.
+///
+/// See [`bounded_tagged`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded_tagged;
+///
+/// let (s1, r) = unbounded_tagged();
+/// let s2 = s1.clone();
+///
+/// s1.send('a').unwrap();
+/// s2.send('b').unwrap();
+///
+/// assert_eq!(r.recv_tagged(), Ok((s1.id(), 'a')));
+/// assert_eq!(r.recv_tagged(), Ok((s2.id(), 'b')));
+/// ```
+///
+/// [`bounded_tagged`]: fn.bounded_tagged.html
+pub fn unbounded_tagged<T>() -> (TaggedSender<T>, TaggedReceiver<T>) {
+    let (s, r) = unbounded();
+    tagged(s, r)
+}
+
+/// Wraps the two sides of a channel carrying tagged messages.
+fn tagged<T>(
+    s: Sender<(SenderId, T)>,
+    r: Receiver<(SenderId, T)>,
+) -> (TaggedSender<T>, TaggedReceiver<T>) {
+    let s = TaggedSender {
+        sender: s,
+        id: SenderId(0),
+        next_id: Arc::new(AtomicUsize::new(1)),
+    };
+    let r = TaggedReceiver { receiver: r };
+    (s, r)
+}
+
+/// The sending side of a channel with tagged messages.
+///
+/// Senders are created by the [`bounded_tagged`] and [`unbounded_tagged`] functions. Cloning a
+/// sender creates a new handle with a new [`SenderId`].
+///
+/// [`bounded_tagged`]: fn.bounded_tagged.html
+/// [`unbounded_tagged`]: fn.unbounded_tagged.html
+/// [`SenderId`]: struct.SenderId.html
+pub struct TaggedSender<T> {
+    /// The underlying sender.
+    sender: Sender<(SenderId, T)>,
+
+    /// The id messages sent through this handle are tagged with.
+    id: SenderId,
+
+    /// The id of the next clone.
+    next_id: Arc<AtomicUsize>,
+}
+
+impl<T> TaggedSender<T> {
+    /// Returns the id messages sent through this sender are tagged with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// s.send(1).unwrap();
+    ///
+    /// let (id, _) = r.recv_tagged().unwrap();
+    /// assert_eq!(id, s.id());
+    /// ```
+    pub fn id(&self) -> SenderId {
+        self.id
+    }
+
+    /// Attempts to tag a message and send it into the channel without blocking.
+    ///
+    /// See [`Sender::try_send`] for details.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_tagged, TrySendError};
+    ///
+    /// let (s, r) = bounded_tagged(1);
+    ///
+    /// assert_eq!(s.try_send(1), Ok(()));
+    /// assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.try_send(3), Err(TrySendError::Disconnected(3)));
+    /// ```
+    pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.sender.try_send((self.id, msg)).map_err(|err| match err {
+            TrySendError::Full((_, msg)) => TrySendError::Full(msg),
+            TrySendError::Disconnected((_, msg)) => TrySendError::Disconnected(msg),
+        })
+    }
+
+    /// Tags a message and sends it into the channel, blocking if the channel is full.
+    ///
+    /// See [`Sender::send`] for details.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_tagged, SendError};
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// assert_eq!(s.send(1), Ok(()));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.sender
+            .send((self.id, msg))
+            .map_err(|SendError((_, msg))| SendError(msg))
+    }
+
+    /// Waits for a message to be tagged and sent into the channel, but only for a limited time.
+    ///
+    /// See [`Sender::send_timeout`] for details.
+    ///
+    /// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_tagged, SendTimeoutError};
+    ///
+    /// let (s, r) = bounded_tagged(1);
+    /// s.send(1).unwrap();
+    ///
+    /// assert_eq!(
+    ///     s.send_timeout(2, Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout(2)),
+    /// );
+    /// # drop(r);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.sender
+            .send_timeout((self.id, msg), timeout)
+            .map_err(|err| match err {
+                SendTimeoutError::Timeout((_, msg)) => SendTimeoutError::Timeout(msg),
+                SendTimeoutError::Disconnected((_, msg)) => SendTimeoutError::Disconnected(msg),
+            })
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// # drop(r);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// s.send(1).unwrap();
+    /// s.clone().send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// # drop(r);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_tagged, unbounded_tagged};
+    ///
+    /// let (s, _) = bounded_tagged::<i32>(5);
+    /// assert_eq!(s.capacity(), Some(5));
+    ///
+    /// let (s, _) = unbounded_tagged::<i32>();
+    /// assert_eq!(s.capacity(), None);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+}
+
+impl<T> Clone for TaggedSender<T> {
+    fn clone(&self) -> TaggedSender<T> {
+        TaggedSender {
+            sender: self.sender.clone(),
+            id: SenderId(self.next_id.fetch_add(1, Ordering::Relaxed)),
+            next_id: self.next_id.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for TaggedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaggedSender").field("id", &self.id).finish()
+    }
+}
+
+/// The receiving side of a channel with tagged messages.
+///
+/// A tagged receiver can be waited on in [`Select`] with [`Select::recv_ready`].
+///
+/// Receivers are created by the [`bounded_tagged`] and [`unbounded_tagged`] functions.
+///
+/// [`bounded_tagged`]: fn.bounded_tagged.html
+/// [`unbounded_tagged`]: fn.unbounded_tagged.html
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+pub struct TaggedReceiver<T> {
+    /// The underlying receiver.
+    receiver: Receiver<(SenderId, T)>,
+}
+
+impl<T> TaggedReceiver<T> {
+    /// Attempts to receive a message from the channel without blocking, discarding its tag.
+    ///
+    /// See [`Receiver::try_recv`] for details.
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_tagged, TryRecvError};
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.try_recv_tagged().map(|(_, msg)| msg)
+    }
+
+    /// Attempts to receive a message from the channel without blocking, and returns it together
+    /// with the id of its sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_tagged, TryRecvError};
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// assert_eq!(r.try_recv_tagged(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// assert_eq!(r.try_recv_tagged(), Ok((s.id(), 5)));
+    /// ```
+    pub fn try_recv_tagged(&self) -> Result<(SenderId, T), TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected, discarding the tag of the message.
+    ///
+    /// See [`Receiver::recv`] for details.
+    ///
+    /// [`Receiver::recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded_tagged, RecvError};
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_tagged().map(|(_, msg)| msg)
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected, and returns the message together with the id of its sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// let worker = s.clone();
+    /// let id = worker.id();
+    ///
+    /// thread::spawn(move || worker.send("done").unwrap());
+    /// assert_eq!(r.recv_tagged(), Ok((id, "done")));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_tagged(&self) -> Result<(SenderId, T), RecvError> {
+        self.receiver.recv()
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time,
+    /// discarding the tag of the message.
+    ///
+    /// See [`Receiver::recv_timeout`] for details.
+    ///
+    /// [`Receiver::recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded_tagged, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded_tagged::<i32>();
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// # drop(s);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_timeout_tagged(timeout).map(|(_, msg)| msg)
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time, and
+    /// returns it together with the id of its sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// s.send(1).unwrap();
+    ///
+    /// let timeout = Duration::from_millis(100);
+    /// assert_eq!(r.recv_timeout_tagged(timeout), Ok((s.id(), 1)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn recv_timeout_tagged(
+        &self,
+        timeout: Duration,
+    ) -> Result<(SenderId, T), RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    /// A blocking iterator over messages in the channel, tagged with the ids of their senders.
+    ///
+    /// Each call to `next` blocks waiting for the next message and then returns it together with
+    /// the id of its sender. The iterator returns `None` once the channel is empty and
+    /// disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s1, r) = unbounded_tagged();
+    /// let s2 = s1.clone();
+    /// let (id1, id2) = (s1.id(), s2.id());
+    ///
+    /// s1.send(1).unwrap();
+    /// s2.send(2).unwrap();
+    /// drop((s1, s2));
+    ///
+    /// let v: Vec<_> = r.iter().collect();
+    /// assert_eq!(v, [(id1, 1), (id2, 2)]);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn iter(&self) -> TaggedIter<T> {
+        TaggedIter { receiver: self }
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// assert!(r.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_tagged;
+    ///
+    /// let (s, r) = bounded_tagged::<i32>(5);
+    /// assert_eq!(r.capacity(), Some(5));
+    /// # drop(s);
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        self.receiver.capacity()
+    }
+
+    /// Unwraps the underlying receiver of tagged messages.
+    ///
+    /// The returned receiver can be used with [`select!`] and [`Select`].
+    ///
+    /// [`select!`]: macro.select.html
+    /// [`Select`]: struct.Select.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded_tagged;
+    ///
+    /// let (s, r) = unbounded_tagged();
+    /// s.send("foo").unwrap();
+    ///
+    /// let r = r.into_inner();
+    /// assert_eq!(r.recv(), Ok((s.id(), "foo")));
+    /// ```
+    pub fn into_inner(self) -> Receiver<(SenderId, T)> {
+        self.receiver
+    }
+}
+
+impl<T> Clone for TaggedReceiver<T> {
+    fn clone(&self) -> TaggedReceiver<T> {
+        TaggedReceiver {
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+impl<T> SelectHandle for TaggedReceiver<T> {
+    fn try(&self, token: &mut Token) -> bool {
+        Ready::new(&self.receiver).try(token)
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        Ready::new(&self.receiver).retry(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        Ready::new(&self.receiver).register(token, oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        Ready::new(&self.receiver).unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        Ready::new(&self.receiver).accept(token, cx)
+    }
+
+    fn state(&self) -> usize {
+        self.receiver.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.receiver.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.receiver.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.receiver.unwatch(oper);
+    }
+}
+
+impl<T> fmt::Debug for TaggedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaggedReceiver").finish()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> IntoIterator for &'a TaggedReceiver<T> {
+    type Item = (SenderId, T);
+    type IntoIter = TaggedIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A blocking iterator over messages in a channel with tagged messages.
+///
+/// Each call to `next` blocks waiting for the next message and then returns it together with the
+/// id of its sender. The iterator returns `None` once the channel is empty and disconnected.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::unbounded_tagged;
+///
+/// let (s, r) = unbounded_tagged();
+///
+/// thread::spawn(move || {
+///     s.send(1).unwrap();
+///     s.send(2).unwrap();
+/// });
+///
+/// let v: Vec<_> = r.iter().map(|(_, msg)| msg).collect();
+/// assert_eq!(v, [1, 2]);
+/// ```
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct TaggedIter<'a, T: 'a> {
+    /// The receiver.
+    receiver: &'a TaggedReceiver<T>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> Iterator for TaggedIter<'a, T> {
+    type Item = (SenderId, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv_tagged().ok()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> fmt::Debug for TaggedIter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaggedIter").finish()
+    }
+}
@@ -0,0 +1,81 @@
+//! Duplicating messages of a receiver into several receivers.
+
+use std::thread;
+
+use channel::{self, bounded, Receiver, Sender};
+use err::SendError;
+use select::Select;
+
+/// Spawns a thread that clones every message of `receiver` into `n` new receivers, each with a
+/// buffer of capacity `cap`.
+pub fn tee<T>(receiver: Receiver<T>, n: usize, cap: usize) -> Vec<Receiver<T>>
+where
+    T: Clone + Send + 'static,
+{
+    assert!(n != 0, "number of branches must be positive");
+
+    let (mut senders, receivers): (Vec<Sender<T>>, Vec<Receiver<T>>) =
+        (0..n).map(|_| bounded(cap)).unzip();
+
+    thread::spawn(move || loop {
+        {
+            // Wait for the next message, or for a branch to be dropped while the channel is idle.
+            let mut sel = Select::with_capacity(senders.len() + 1);
+            let source = sel.recv(&receiver);
+            for s in &senders {
+                sel.abandoned(s);
+            }
+
+            loop {
+                let oper = sel.select();
+                let index = oper.index();
+
+                if index != source {
+                    oper.abandoned(&senders[index - 1]);
+                    break;
+                }
+
+                match oper.recv(&receiver) {
+                    Ok(msg) => {
+                        if !forward(&senders, &receiver, msg) {
+                            break;
+                        }
+                    }
+                    Err(_) => return,
+                }
+            }
+        }
+
+        // Branches whose receivers were dropped are removed.
+        senders.retain(|s| !channel::is_disconnected(s));
+        if senders.is_empty() {
+            return;
+        }
+    });
+    receivers
+}
+
+/// Sends `msg` into every branch and returns `false` if some of them turned out to be dropped.
+///
+/// Every branch but the last one gets a clone, and the last one gets the original. If no branch
+/// gets the message, it is put back into `receiver`.
+fn forward<T: Clone>(senders: &[Sender<T>], receiver: &Receiver<T>, msg: T) -> bool {
+    let (last, rest) = senders.split_last().unwrap();
+    let mut delivered = false;
+    let mut connected = true;
+
+    for s in rest {
+        match s.send(msg.clone()) {
+            Ok(()) => delivered = true,
+            Err(_) => connected = false,
+        }
+    }
+
+    if let Err(SendError(msg)) = last.send(msg) {
+        if !delivered {
+            receiver.put_back(msg);
+        }
+        connected = false;
+    }
+    connected
+}
@@ -0,0 +1,733 @@
+//! Tools for checking that a channel implementation behaves like a channel.
+//!
+//! A channel is correct if every concurrent execution is *linearizable*: the operations that
+//! threads performed can be put into a single order that respects real time, meaning that an
+//! operation which finished before another one started comes first, and in which every
+//! operation has the outcome it would have had on a simple sequential queue. That queue is the
+//! [`Model`].
+//!
+//! A [`History`] records the operations threads perform on a channel, together with the moments
+//! they started and finished, and [`check`] searches for such an order. A [`Harness`] does all of
+//! that automatically: it hammers a fresh channel from several threads with random operations,
+//! randomly perturbing the schedule of each thread, and checks every history it records. Any type
+//! implementing [`TestChannel`] can be tested this way, including channels implemented outside
+//! this crate. The built-in channels implement it as a `(Sender<u64>, Receiver<u64>)` pair.
+//!
+//! Messages are `u64` numbers, and every message sent within a history must be unique. Channels
+//! are never disconnected while being tested.
+//!
+//! This module is available only if the `testing` feature is enabled.
+//!
+//! [`Model`]: struct.Model.html
+//! [`History`]: struct.History.html
+//! [`check`]: fn.check.html
+//! [`Harness`]: struct.Harness.html
+//! [`TestChannel`]: trait.TestChannel.html
+//!
+//! # Examples
+//!
+//! ```
+//! use crossbeam_channel::bounded;
+//! use crossbeam_channel::testing::Harness;
+//!
+//! Harness::new(7).rounds(20).run(|| bounded(2)).unwrap();
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use channel::{Receiver, Sender};
+use err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
+use utils::{self, Rng};
+
+/// A channel implementation under test.
+///
+/// The methods behave like the methods of [`Sender`] and [`Receiver`] with the same names.
+///
+/// [`Sender`]: ../struct.Sender.html
+/// [`Receiver`]: ../struct.Receiver.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{unbounded, RecvTimeoutError, SendTimeoutError, Receiver, Sender};
+/// use crossbeam_channel::{TryRecvError, TrySendError};
+/// use crossbeam_channel::testing::{Harness, TestChannel};
+///
+/// // A wrapper standing in for a custom channel implementation.
+/// struct Wrapped(Sender<u64>, Receiver<u64>);
+///
+/// impl TestChannel for Wrapped {
+///     fn try_send(&self, msg: u64) -> Result<(), TrySendError<u64>> {
+///         self.0.try_send(msg)
+///     }
+///     fn send_timeout(&self, msg: u64, timeout: Duration) -> Result<(), SendTimeoutError<u64>> {
+///         self.0.send_timeout(msg, timeout)
+///     }
+///     fn try_recv(&self) -> Result<u64, TryRecvError> {
+///         self.1.try_recv()
+///     }
+///     fn recv_timeout(&self, timeout: Duration) -> Result<u64, RecvTimeoutError> {
+///         self.1.recv_timeout(timeout)
+///     }
+///     fn capacity(&self) -> Option<usize> {
+///         self.0.capacity()
+///     }
+/// }
+///
+/// Harness::new(1).rounds(10).run(|| {
+///     let (s, r) = unbounded();
+///     Wrapped(s, r)
+/// }).unwrap();
+/// ```
+pub trait TestChannel: Send + Sync {
+    /// Attempts to send a message without blocking.
+    fn try_send(&self, msg: u64) -> Result<(), TrySendError<u64>>;
+
+    /// Sends a message, blocking for at most `timeout` while the channel is full.
+    fn send_timeout(&self, msg: u64, timeout: Duration) -> Result<(), SendTimeoutError<u64>>;
+
+    /// Attempts to receive a message without blocking.
+    fn try_recv(&self) -> Result<u64, TryRecvError>;
+
+    /// Receives a message, blocking for at most `timeout` while the channel is empty.
+    fn recv_timeout(&self, timeout: Duration) -> Result<u64, RecvTimeoutError>;
+
+    /// Returns the capacity of the channel, or `None` if it is unbounded.
+    fn capacity(&self) -> Option<usize>;
+}
+
+impl TestChannel for (Sender<u64>, Receiver<u64>) {
+    fn try_send(&self, msg: u64) -> Result<(), TrySendError<u64>> {
+        self.0.try_send(msg)
+    }
+
+    fn send_timeout(&self, msg: u64, timeout: Duration) -> Result<(), SendTimeoutError<u64>> {
+        self.0.send_timeout(msg, timeout)
+    }
+
+    fn try_recv(&self) -> Result<u64, TryRecvError> {
+        self.1.try_recv()
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<u64, RecvTimeoutError> {
+        self.1.recv_timeout(timeout)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.0.capacity()
+    }
+}
+
+/// An operation performed on a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    /// Sending a message.
+    Send(u64),
+
+    /// Receiving a message.
+    Recv,
+}
+
+/// The outcome of an operation performed on a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The message was sent.
+    Sent,
+
+    /// The message could not be sent because the channel was full.
+    Full,
+
+    /// A message was received.
+    Received(u64),
+
+    /// No message could be received because the channel was empty.
+    Empty,
+
+    /// The operation failed because the channel was disconnected.
+    Disconnected,
+}
+
+impl<T> From<TrySendError<T>> for Outcome {
+    fn from(err: TrySendError<T>) -> Outcome {
+        match err {
+            TrySendError::Full(_) => Outcome::Full,
+            TrySendError::Disconnected(_) => Outcome::Disconnected,
+        }
+    }
+}
+
+impl<T> From<SendTimeoutError<T>> for Outcome {
+    fn from(err: SendTimeoutError<T>) -> Outcome {
+        match err {
+            SendTimeoutError::Timeout(_) => Outcome::Full,
+            SendTimeoutError::Disconnected(_) => Outcome::Disconnected,
+        }
+    }
+}
+
+impl From<TryRecvError> for Outcome {
+    fn from(err: TryRecvError) -> Outcome {
+        match err {
+            TryRecvError::Empty => Outcome::Empty,
+            TryRecvError::Disconnected => Outcome::Disconnected,
+        }
+    }
+}
+
+impl From<RecvTimeoutError> for Outcome {
+    fn from(err: RecvTimeoutError) -> Outcome {
+        match err {
+            RecvTimeoutError::Timeout => Outcome::Empty,
+            RecvTimeoutError::Disconnected => Outcome::Disconnected,
+        }
+    }
+}
+
+/// Returns the outcome of a send operation.
+fn sent<E: Into<Outcome>>(res: Result<(), E>) -> Outcome {
+    res.map(|()| Outcome::Sent).unwrap_or_else(Into::into)
+}
+
+/// Returns the outcome of a receive operation.
+fn received<E: Into<Outcome>>(res: Result<u64, E>) -> Outcome {
+    res.map(Outcome::Received).unwrap_or_else(Into::into)
+}
+
+/// An operation recorded in a history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// The thread that performed the operation.
+    pub thread: usize,
+
+    /// The operation.
+    pub op: Op,
+
+    /// The outcome of the operation.
+    pub outcome: Outcome,
+
+    /// The logical time at which the operation started.
+    pub start: usize,
+
+    /// The logical time at which the operation finished.
+    pub end: usize,
+}
+
+/// A sequential queue that serves as the reference every channel is compared against.
+///
+/// A zero-capacity channel is modeled as a queue that a sent message passes through right away:
+/// the message is received by the operation it was handed off to, which the checker places right
+/// after the send operation.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::testing::{Model, Op, Outcome};
+///
+/// let mut model = Model::new(Some(1));
+/// assert!(model.apply(Op::Send(1), Outcome::Sent));
+/// assert!(model.apply(Op::Send(2), Outcome::Full));
+///
+/// // The channel holds a message, so it cannot be empty.
+/// assert!(!model.apply(Op::Recv, Outcome::Empty));
+/// assert!(model.apply(Op::Recv, Outcome::Received(1)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Model {
+    /// The capacity, or `None` if the queue is unbounded.
+    capacity: Option<usize>,
+
+    /// The messages in the queue, oldest first.
+    queue: VecDeque<u64>,
+}
+
+impl Model {
+    /// Creates an empty model of a channel with the given capacity.
+    pub fn new(capacity: Option<usize>) -> Model {
+        Model {
+            capacity,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Returns the number of messages in the queue.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Returns `true` if an operation could have had the given outcome, and if so, applies it.
+    ///
+    /// If the outcome is not possible, the model is left unchanged.
+    pub fn apply(&mut self, op: Op, outcome: Outcome) -> bool {
+        match (op, outcome) {
+            (Op::Send(msg), Outcome::Sent) => {
+                // A zero-capacity channel still hands off one message at a time.
+                if self.queue.len() < self.capacity.unwrap_or(usize::max_value()).max(1) {
+                    self.queue.push_back(msg);
+                    true
+                } else {
+                    false
+                }
+            }
+            (Op::Send(_), Outcome::Full) => match self.capacity {
+                Some(cap) => self.queue.len() >= cap,
+                None => false,
+            },
+            (Op::Recv, Outcome::Received(msg)) => {
+                if self.queue.front() == Some(&msg) {
+                    self.queue.pop_front();
+                    true
+                } else {
+                    false
+                }
+            }
+            (Op::Recv, Outcome::Empty) => self.queue.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// A history of operations performed on a channel by several threads.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded;
+/// use crossbeam_channel::testing::{History, Op, Outcome};
+///
+/// let (s, r) = unbounded();
+/// let history = History::new();
+///
+/// history.record(0, Op::Send(1), || match s.try_send(1) {
+///     Ok(()) => Outcome::Sent,
+///     Err(_) => Outcome::Full,
+/// });
+/// history.record(0, Op::Recv, || match r.try_recv() {
+///     Ok(msg) => Outcome::Received(msg),
+///     Err(_) => Outcome::Empty,
+/// });
+///
+/// assert!(history.check(None).is_ok());
+/// ```
+pub struct History {
+    /// The logical clock timestamping operations.
+    clock: AtomicUsize,
+
+    /// The recorded operations.
+    events: Mutex<Vec<Event>>,
+}
+
+impl History {
+    /// Creates an empty history.
+    pub fn new() -> History {
+        History {
+            clock: AtomicUsize::new(0),
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Performs an operation by calling `f` and records it, returning its outcome.
+    pub fn record<F>(&self, thread: usize, op: Op, f: F) -> Outcome
+    where
+        F: FnOnce() -> Outcome,
+    {
+        let start = self.clock.fetch_add(1, Ordering::SeqCst);
+        let outcome = f();
+        let end = self.clock.fetch_add(1, Ordering::SeqCst);
+
+        self.events.lock().push(Event {
+            thread,
+            op,
+            outcome,
+            start,
+            end,
+        });
+        outcome
+    }
+
+    /// Returns the recorded operations.
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().clone()
+    }
+
+    /// Checks whether the recorded operations are linearizable with respect to a channel of the
+    /// given capacity.
+    pub fn check(&self, capacity: Option<usize>) -> Result<(), Violation> {
+        check(&self.events.lock(), capacity)
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new()
+    }
+}
+
+impl fmt::Debug for History {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("History")
+            .field("events", &*self.events.lock())
+            .finish()
+    }
+}
+
+/// Checks whether a history is linearizable with respect to a channel of the given capacity.
+///
+/// The search backtracks through all orders consistent with real time, so it is meant for
+/// histories of at most a few hundred operations performed by a handful of threads.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::testing::{check, Event, Op, Outcome};
+///
+/// // Thread 1 received a message that thread 0 started sending only afterwards.
+/// let history = [
+///     Event { thread: 1, op: Op::Recv, outcome: Outcome::Received(7), start: 0, end: 1 },
+///     Event { thread: 0, op: Op::Send(7), outcome: Outcome::Sent, start: 2, end: 3 },
+/// ];
+/// assert!(check(&history, None).is_err());
+///
+/// // If the operations overlap, the message could have been sent first.
+/// let history = [
+///     Event { thread: 1, op: Op::Recv, outcome: Outcome::Received(7), start: 0, end: 3 },
+///     Event { thread: 0, op: Op::Send(7), outcome: Outcome::Sent, start: 1, end: 2 },
+/// ];
+/// assert!(check(&history, None).is_ok());
+/// ```
+pub fn check(events: &[Event], capacity: Option<usize>) -> Result<(), Violation> {
+    let mut search = Search {
+        events,
+        done: vec![false; events.len()],
+        model: Model::new(capacity),
+        seen: HashSet::new(),
+    };
+
+    if search.run(events.len()) {
+        Ok(())
+    } else {
+        Err(Violation {
+            events: events.to_vec(),
+            seed: None,
+        })
+    }
+}
+
+/// The state of a search for a linearization.
+struct Search<'a> {
+    /// The operations to put into order.
+    events: &'a [Event],
+
+    /// Equals `true` for each operation that has already been put into order.
+    done: Vec<bool>,
+
+    /// The state of the channel after the operations put into order so far.
+    model: Model,
+
+    /// Combinations of operations put into order and channel states that lead nowhere.
+    seen: HashSet<(Vec<bool>, Model)>,
+}
+
+impl<'a> Search<'a> {
+    /// Returns `true` if the `remaining` operations can be put into order.
+    fn run(&mut self, remaining: usize) -> bool {
+        if remaining == 0 {
+            return true;
+        }
+        if !self.seen.insert((self.done.clone(), self.model.clone())) {
+            return false;
+        }
+
+        // Only operations that started before any of the remaining ones finished can come next.
+        let first_end = self
+            .events
+            .iter()
+            .zip(&self.done)
+            .filter(|&(_, &done)| !done)
+            .map(|(e, _)| e.end)
+            .min()
+            .unwrap();
+
+        for i in 0..self.events.len() {
+            let e = self.events[i];
+            if self.done[i] || e.start > first_end {
+                continue;
+            }
+
+            let model = self.model.clone();
+            if self.model.apply(e.op, e.outcome) {
+                self.done[i] = true;
+                if self.run(remaining - 1) {
+                    return true;
+                }
+                self.done[i] = false;
+                self.model = model;
+            }
+        }
+        false
+    }
+}
+
+/// An error returned when a history is not linearizable.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The operations in the history.
+    events: Vec<Event>,
+
+    /// The seed of the round of the harness that recorded the history.
+    seed: Option<u64>,
+}
+
+impl Violation {
+    /// Returns the operations in the history, in the order they finished.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Returns the seed that reproduces the schedule of the round in which the history was
+    /// recorded, if it was recorded by a [`Harness`].
+    ///
+    /// [`Harness`]: struct.Harness.html
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl fmt::Debug for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}:", self)?;
+        let mut events = self.events.clone();
+        events.sort_by_key(|e| e.start);
+        for e in &events {
+            writeln!(
+                f,
+                "  [{:>4}, {:>4}] thread {}: {:?} -> {:?}",
+                e.start, e.end, e.thread, e.op, e.outcome
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "history of {} operations is not linearizable",
+            self.events.len()
+        )?;
+        if let Some(seed) = self.seed {
+            write!(f, " (seed {})", seed)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for Violation {
+    fn description(&self) -> &str {
+        "history is not linearizable"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        None
+    }
+}
+
+/// Runs randomized concurrent operations on channels and checks that they are linearizable.
+///
+/// In every round, a fresh channel is created and several threads perform a random mix of
+/// sending and receiving operations on it, with and without blocking. The threads start at the
+/// same moment and randomly yield, spin, or sleep between operations, so that different rounds
+/// explore different interleavings. Once all threads are done, the remaining messages are
+/// received, and the recorded history is checked.
+///
+/// The operations are chosen by a random number generator seeded from the seed of the harness,
+/// but the interleaving is ultimately decided by the operating system, so a failing round cannot
+/// always be replayed exactly.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{bounded, lanes, unbounded};
+/// use crossbeam_channel::testing::Harness;
+///
+/// let harness = Harness::new(42).rounds(10).threads(4).ops(15);
+/// harness.run(|| bounded(0)).unwrap();
+/// harness.run(|| bounded(3)).unwrap();
+/// harness.run(|| unbounded()).unwrap();
+/// harness.run(|| lanes(3, 1)).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Harness {
+    /// The seed of the random number generator.
+    seed: u64,
+
+    /// The number of rounds.
+    rounds: usize,
+
+    /// The number of threads in each round.
+    threads: usize,
+
+    /// The number of operations each thread performs in each round.
+    ops: usize,
+}
+
+impl Harness {
+    /// Creates a harness with the given seed.
+    ///
+    /// By default, it runs 100 rounds of 3 threads performing 10 operations each.
+    pub fn new(seed: u64) -> Harness {
+        Harness {
+            seed,
+            rounds: 100,
+            threads: 3,
+            ops: 10,
+        }
+    }
+
+    /// Sets the number of rounds.
+    pub fn rounds(mut self, rounds: usize) -> Harness {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Sets the number of threads in each round.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn threads(mut self, threads: usize) -> Harness {
+        assert!(threads > 0, "a harness needs at least one thread");
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the number of operations each thread performs in each round.
+    pub fn ops(mut self, ops: usize) -> Harness {
+        self.ops = ops;
+        self
+    }
+
+    /// Runs all rounds, creating a channel for each with `new`.
+    ///
+    /// Returns the first history that is not linearizable. If a thread panics, the panic is
+    /// propagated.
+    pub fn run<C, F>(&self, mut new: F) -> Result<(), Violation>
+    where
+        C: TestChannel + 'static,
+        F: FnMut() -> C,
+    {
+        let mut rng = Rng(self.seed);
+        for _ in 0..self.rounds {
+            let seed = rng.next();
+            self.round(new(), seed).map_err(|mut v| {
+                v.seed = Some(seed);
+                v
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs a single round on `chan`.
+    fn round<C>(&self, chan: C, seed: u64) -> Result<(), Violation>
+    where
+        C: TestChannel + 'static,
+    {
+        let capacity = chan.capacity();
+        let chan = Arc::new(chan);
+        let history = Arc::new(History::new());
+        let barrier = Arc::new(Barrier::new(self.threads));
+
+        let handles: Vec<_> = (0..self.threads)
+            .map(|thread| {
+                let chan = chan.clone();
+                let history = history.clone();
+                let barrier = barrier.clone();
+                let mut rng = Rng(seed ^ (thread as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                let ops = self.ops;
+
+                thread::spawn(move || {
+                    barrier.wait();
+                    for i in 0..ops {
+                        perturb(&mut rng);
+                        let msg = (thread as u64) << 32 | i as u64;
+                        step(&*chan, &history, thread, msg, &mut rng);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            if let Err(payload) = handle.join() {
+                panic::resume_unwind(payload);
+            }
+        }
+
+        // Receive the remaining messages, which also checks that none were lost.
+        loop {
+            let outcome = history.record(self.threads, Op::Recv, || {
+                received(chan.try_recv())
+            });
+            if !is_received(outcome) {
+                break;
+            }
+        }
+
+        history.check(capacity)
+    }
+}
+
+/// Performs a random operation on `chan` and records it.
+fn step<C: TestChannel>(chan: &C, history: &History, thread: usize, msg: u64, rng: &mut Rng) {
+    let timeout = Duration::new(0, (rng.next() % 500) as u32 * 1000);
+    match rng.next() % 4 {
+        0 => history.record(thread, Op::Send(msg), || {
+            sent(chan.try_send(msg))
+        }),
+        1 => history.record(thread, Op::Send(msg), || {
+            sent(chan.send_timeout(msg, timeout))
+        }),
+        2 => history.record(thread, Op::Recv, || {
+            received(chan.try_recv())
+        }),
+        _ => history.record(thread, Op::Recv, || {
+            received(chan.recv_timeout(timeout))
+        }),
+    };
+}
+
+/// Randomly delays the current thread to shake up the schedule.
+fn perturb(rng: &mut Rng) {
+    match rng.next() % 4 {
+        0 => {}
+        1 => thread::yield_now(),
+        2 => {
+            for _ in 0..rng.next() % 1000 {
+                utils::spin_loop();
+            }
+        }
+        _ => thread::sleep(Duration::new(0, (rng.next() % 50) as u32 * 1000)),
+    }
+}
+
+/// Returns `true` if the outcome is a received message.
+fn is_received(outcome: Outcome) -> bool {
+    match outcome {
+        Outcome::Received(_) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,157 @@
+//! Intervals driven by an external source.
+
+use std::fmt;
+use std::time::Instant;
+
+use channel::{bounded, Receiver, Sender};
+
+/// Creates a receiver that delivers a message whenever the returned driver fires.
+///
+/// This is the counterpart of [`tick`] for intervals that come from outside the program, like
+/// display refreshes, hardware interrupts, or the steps of a simulation. The receiver behaves just
+/// like one created by [`tick`]: it can be used in [`select!`] and [`Select`], and each message is
+/// the instant at which the tick happened.
+///
+/// Like [`tick`], the receiver holds at most one message. Ticks that fire while an earlier one is
+/// still waiting to be received are coalesced into it. The receiver becomes disconnected once all
+/// drivers are dropped and the last tick has been received.
+///
+/// [`tick`]: fn.tick.html
+/// [`select!`]: macro.select.html
+/// [`Select`]: struct.Select.html
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate crossbeam_channel;
+/// # fn main() {
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{manual_ticker, unbounded};
+///
+/// let (driver, ticker) = manual_ticker();
+/// let (s, r) = unbounded::<String>();
+///
+/// thread::spawn(move || {
+///     // Pretend the display just finished a refresh.
+///     thread::sleep(Duration::from_millis(16));
+///     driver.fire();
+/// });
+///
+/// select! {
+///     recv(r) -> msg => println!("{:?}", msg),
+///     recv(ticker) -> _ => println!("redraw"),
+/// }
+/// # drop(s);
+/// # }
+/// ```
+pub fn manual_ticker() -> (TickDriver, Receiver<Instant>) {
+    let (s, r) = bounded(1);
+    (TickDriver { sender: s }, r)
+}
+
+/// The driving side of a receiver created by [`manual_ticker`].
+///
+/// Cloning a driver creates a new handle that fires the same receiver.
+///
+/// [`manual_ticker`]: fn.manual_ticker.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::manual_ticker;
+///
+/// let (driver, ticker) = manual_ticker();
+///
+/// driver.fire();
+/// assert!(ticker.recv().is_ok());
+///
+/// drop(driver);
+/// assert!(ticker.recv().is_err());
+/// ```
+pub struct TickDriver {
+    /// The sending side of the ticker.
+    sender: Sender<Instant>,
+}
+
+impl TickDriver {
+    /// Fires a tick at the current instant.
+    ///
+    /// Returns `true` if the tick was delivered, and `false` if it was coalesced into a tick that
+    /// hasn't been received yet or the receiver was dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::manual_ticker;
+    ///
+    /// let (driver, ticker) = manual_ticker();
+    ///
+    /// assert!(driver.fire());
+    /// assert!(!driver.fire());
+    ///
+    /// ticker.recv().unwrap();
+    /// assert!(driver.fire());
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn fire(&self) -> bool {
+        self.fire_at(Instant::now())
+    }
+
+    /// Fires a tick carrying the given instant.
+    ///
+    /// This is useful when the external source reports its own timestamps, or when a simulation
+    /// runs on virtual time. Returns `true` if the tick was delivered, and `false` if it was
+    /// coalesced into a tick that hasn't been received yet or the receiver was dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::manual_ticker;
+    ///
+    /// let (driver, ticker) = manual_ticker();
+    /// let vsync = Instant::now() - Duration::from_millis(2);
+    ///
+    /// driver.fire_at(vsync);
+    /// assert_eq!(ticker.recv(), Ok(vsync));
+    /// ```
+    pub fn fire_at(&self, instant: Instant) -> bool {
+        self.sender.try_send(instant).is_ok()
+    }
+
+    /// Returns `true` if a tick has been fired but not received yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::manual_ticker;
+    ///
+    /// let (driver, ticker) = manual_ticker();
+    /// assert!(!driver.is_pending());
+    ///
+    /// driver.fire();
+    /// assert!(driver.is_pending());
+    ///
+    /// ticker.recv().unwrap();
+    /// assert!(!driver.is_pending());
+    /// ```
+    pub fn is_pending(&self) -> bool {
+        self.sender.is_full()
+    }
+}
+
+impl Clone for TickDriver {
+    fn clone(&self) -> TickDriver {
+        TickDriver {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for TickDriver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("TickDriver { .. }")
+    }
+}
@@ -0,0 +1,331 @@
+//! Receivers that report when no message arrives in time.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::fmt;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use parking_lot::Mutex;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use channel::{self, Receiver};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use context::Context;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use err::{Elapsed, RecvError, RecvTimeoutError, TryRecvError};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select::{Operation, SelectHandle, Token};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use utils;
+
+/// The token type for a receiver with a timeout.
+#[derive(Debug, Default)]
+pub struct TimeoutToken {
+    /// Equals `true` if the timeout elapsed instead of a message being received.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    elapsed: bool,
+}
+
+/// Wraps a receiver into one that reports when no message arrives within `timeout`.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn timeout<T>(receiver: Receiver<T>, timeout: Duration) -> TimeoutReceiver<T> {
+    TimeoutReceiver {
+        receiver,
+        timeout,
+        deadline: Mutex::new(Instant::now() + timeout),
+    }
+}
+
+/// A receiver that yields [`Elapsed`] whenever no message arrives in time.
+///
+/// Every received message is wrapped in `Ok`. If no message arrives within the timeout since the
+/// previous one, an `Err(Elapsed)` is received instead, and the timeout starts over, so a channel
+/// that stays idle yields `Elapsed` once per timeout. The first timeout starts when the receiver
+/// is created. This turns idle detection into part of the stream of messages, without having to
+/// pair the receiver with an [`after`] channel that is reset after every message.
+///
+/// Receivers can be added to [`Select`] with [`Select::recv_with_timeout`]. Once the channel is
+/// disconnected and empty, no more timeouts are reported.
+///
+/// Receivers are created by [`Receiver::with_timeout`].
+///
+/// [`Elapsed`]: struct.Elapsed.html
+/// [`after`]: fn.after.html
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_with_timeout`]: struct.Select.html#method.recv_with_timeout
+/// [`Receiver::with_timeout`]: struct.Receiver.html#method.with_timeout
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct TimeoutReceiver<T> {
+    /// The underlying receiver.
+    receiver: Receiver<T>,
+
+    /// How long to wait for a message before reporting a timeout.
+    timeout: Duration,
+
+    /// The instant at which the next timeout is reported.
+    deadline: Mutex<Instant>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T> TimeoutReceiver<T> {
+    /// Attempts to receive a message or a timeout without blocking.
+    ///
+    /// If the channel is empty and the timeout has elapsed, `Ok(Err(Elapsed))` is returned and
+    /// the timeout starts over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Elapsed, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.with_timeout(Duration::from_millis(100));
+    ///
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.try_recv(), Ok(Ok(1)));
+    ///
+    /// thread::sleep(Duration::from_millis(200));
+    /// assert_eq!(r.try_recv(), Ok(Err(Elapsed)));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// ```
+    pub fn try_recv(&self) -> Result<Result<T, Elapsed>, TryRecvError> {
+        match self.receiver.try_recv() {
+            Ok(msg) => {
+                self.restart();
+                Ok(Ok(msg))
+            }
+            Err(TryRecvError::Empty) if self.elapse() => Ok(Err(Elapsed)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Blocks the current thread until a message is received or the timeout elapses.
+    ///
+    /// If the channel is empty and disconnected, an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, Elapsed, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.with_timeout(Duration::from_millis(100));
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(1).unwrap();
+    ///     thread::sleep(Duration::from_millis(150));
+    ///     s.send(2).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(Ok(1)));
+    /// assert_eq!(r.recv(), Ok(Err(Elapsed)));
+    /// assert_eq!(r.recv(), Ok(Ok(2)));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn recv(&self) -> Result<Result<T, Elapsed>, RecvError> {
+        loop {
+            let deadline = *self.deadline.lock();
+            match self.receiver.recv_timeout(utils::remaining(deadline)) {
+                Ok(msg) => {
+                    self.restart();
+                    return Ok(Ok(msg));
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+                Err(RecvTimeoutError::Timeout) => {
+                    // Another thread may have received a message or a timeout in the meantime.
+                    if self.elapse() {
+                        return Ok(Err(Elapsed));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns a blocking iterator over messages and timeouts.
+    ///
+    /// The iterator ends once the channel is empty and disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.with_timeout(Duration::from_millis(100));
+    ///
+    /// thread::spawn(move || {
+    ///     s.send(1).unwrap();
+    ///     s.send(2).unwrap();
+    ///     thread::sleep(Duration::from_millis(150));
+    ///     s.send(3).unwrap();
+    /// });
+    ///
+    /// let v: Vec<_> = r.iter().map(|res| res.ok()).collect();
+    /// assert_eq!(v, [Some(1), Some(2), None, Some(3)]);
+    /// ```
+    pub fn iter(&self) -> TimeoutIter<T> {
+        TimeoutIter { receiver: self }
+    }
+
+    /// Returns the timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (_s, r) = unbounded::<i32>();
+    /// let r = r.with_timeout(Duration::from_secs(1));
+    /// assert_eq!(r.timeout(), Duration::from_secs(1));
+    /// ```
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Unwraps the underlying receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// let r = r.with_timeout(Duration::from_secs(1));
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(r.into_inner().recv(), Ok(1));
+    /// ```
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+
+    /// Starts the timeout over after a message was received.
+    fn restart(&self) {
+        *self.deadline.lock() = Instant::now() + self.timeout;
+    }
+
+    /// Starts the timeout over and returns `true` if it has elapsed.
+    fn elapse(&self) -> bool {
+        let now = Instant::now();
+        let mut deadline = self.deadline.lock();
+        if now >= *deadline {
+            *deadline = now + self.timeout;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T> SelectHandle for TimeoutReceiver<T> {
+    fn try(&self, token: &mut Token) -> bool {
+        if self.receiver.try(token) {
+            return true;
+        }
+        token.timeout.elapsed = self.elapse();
+        token.timeout.elapsed
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        if self.receiver.retry(token) {
+            return true;
+        }
+        token.timeout.elapsed = self.elapse();
+        token.timeout.elapsed
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        let deadline = *self.deadline.lock();
+        match self.receiver.deadline() {
+            Some(d) if d < deadline => Some(d),
+            _ => Some(deadline),
+        }
+    }
+
+    fn register(&self, token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        self.receiver.register(token, oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.receiver.unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        self.receiver.accept(token, cx)
+    }
+
+    fn state(&self) -> usize {
+        self.receiver.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.receiver.is_ready() || Instant::now() >= *self.deadline.lock()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.receiver.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.receiver.unwatch(oper);
+    }
+}
+
+/// Reads a message or a timeout selected from a receiver with a timeout.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub unsafe fn read<T>(r: &TimeoutReceiver<T>, token: &mut Token) -> Result<Result<T, Elapsed>, ()> {
+    if token.timeout.elapsed {
+        return Ok(Err(Elapsed));
+    }
+    let msg = channel::read(&r.receiver, token)?;
+    r.restart();
+    Ok(Ok(msg))
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T> fmt::Debug for TimeoutReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimeoutReceiver")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// A blocking iterator over messages and timeouts of a [`TimeoutReceiver`].
+///
+/// Each call to [`next`] blocks waiting for the next message or timeout. The iterator ends once
+/// the channel is empty and disconnected.
+///
+/// [`TimeoutReceiver`]: struct.TimeoutReceiver.html
+/// [`next`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct TimeoutIter<'a, T: 'a> {
+    receiver: &'a TimeoutReceiver<T>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> Iterator for TimeoutIter<'a, T> {
+    type Item = Result<T, Elapsed>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a, T> fmt::Debug for TimeoutIter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimeoutIter").finish()
+    }
+}
@@ -0,0 +1,212 @@
+//! Manual-reset events that can participate in select.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select;
+use selectable::{Notifier, Selectable};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use selectable::Adapter;
+
+/// A manual-reset event that can be waited on and selected over.
+///
+/// A trigger is either set or unset. Setting it wakes up all threads waiting on it, and it stays
+/// set until it is explicitly [`reset`]. This turns an arbitrary external condition, like a child
+/// process exiting, a ctrl-c handler firing, or a callback from foreign code, into something that
+/// can be waited on, added to a [`Select`] with [`Select::trigger`], or used in the `custom` case
+/// of [`select!`], without allocating a channel for every event.
+///
+/// Cloning a trigger creates a new handle to the same trigger.
+///
+/// [`reset`]: struct.Trigger.html#method.reset
+/// [`Select`]: struct.Select.html
+/// [`Select::trigger`]: struct.Select.html#method.trigger
+/// [`select!`]: macro.select.html
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::Trigger;
+///
+/// let trigger = Trigger::new();
+///
+/// let t = trigger.clone();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(100));
+///     t.set();
+/// });
+///
+/// trigger.wait();
+/// assert!(trigger.is_set());
+/// ```
+pub struct Trigger {
+    inner: Arc<Inner>,
+}
+
+/// Inner representation of `Trigger`.
+struct Inner {
+    /// Equals `true` if the trigger is set.
+    is_set: AtomicBool,
+
+    /// Wakes up operations waiting for the trigger to be set.
+    notifier: Notifier,
+}
+
+impl Trigger {
+    /// Creates a new trigger that is not set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Trigger;
+    ///
+    /// let trigger = Trigger::new();
+    /// assert!(!trigger.is_set());
+    /// ```
+    pub fn new() -> Trigger {
+        Trigger {
+            inner: Arc::new(Inner {
+                is_set: AtomicBool::new(false),
+                notifier: Notifier::new(),
+            }),
+        }
+    }
+
+    /// Sets the trigger and wakes up all operations waiting on it.
+    ///
+    /// Setting a trigger that is already set has no effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Trigger;
+    ///
+    /// let trigger = Trigger::new();
+    /// trigger.set();
+    /// assert!(trigger.is_set());
+    /// ```
+    pub fn set(&self) {
+        if !self.inner.is_set.swap(true, Ordering::SeqCst) {
+            self.inner.notifier.notify();
+        }
+    }
+
+    /// Resets the trigger so that waiting on it blocks again.
+    ///
+    /// Returns `true` if the trigger was set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Trigger;
+    ///
+    /// let trigger = Trigger::new();
+    /// trigger.set();
+    ///
+    /// assert!(trigger.reset());
+    /// assert!(!trigger.is_set());
+    /// assert!(!trigger.reset());
+    /// ```
+    pub fn reset(&self) -> bool {
+        self.inner.is_set.swap(false, Ordering::SeqCst)
+    }
+
+    /// Returns `true` if the trigger is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Trigger;
+    ///
+    /// let trigger = Trigger::new();
+    /// let t = trigger.clone();
+    ///
+    /// t.set();
+    /// assert!(trigger.is_set());
+    /// ```
+    pub fn is_set(&self) -> bool {
+        self.inner.is_set.load(Ordering::SeqCst)
+    }
+
+    /// Blocks the current thread until the trigger is set.
+    ///
+    /// Returns immediately if the trigger is already set. Waiting doesn't reset the trigger.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::Trigger;
+    ///
+    /// let trigger = Trigger::new();
+    ///
+    /// let t = trigger.clone();
+    /// thread::spawn(move || t.set());
+    ///
+    /// trigger.wait();
+    /// trigger.wait();
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait(&self) {
+        select::wait_ready(Adapter::new(self), None);
+    }
+
+    /// Waits for the trigger to be set for a limited time.
+    ///
+    /// Returns `true` if the trigger was set before the timeout elapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::Trigger;
+    ///
+    /// let trigger = Trigger::new();
+    /// assert!(!trigger.wait_timeout(Duration::from_millis(100)));
+    ///
+    /// trigger.set();
+    /// assert!(trigger.wait_timeout(Duration::from_millis(100)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        select::wait_ready(Adapter::new(self), Some(Instant::now() + timeout))
+    }
+}
+
+impl Selectable for Trigger {
+    fn is_ready(&self) -> bool {
+        self.is_set()
+    }
+
+    fn notifier(&self) -> &Notifier {
+        &self.inner.notifier
+    }
+}
+
+impl Default for Trigger {
+    fn default() -> Trigger {
+        Trigger::new()
+    }
+}
+
+impl Clone for Trigger {
+    fn clone(&self) -> Trigger {
+        Trigger {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Trigger")
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
@@ -0,0 +1,580 @@
+//! Channels with expiring messages.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use channel::{bounded, Receiver, Sender};
+use context::Context;
+use err::{RecvError, RecvTimeoutError, TryRecvError};
+use err::{SendError, SendTimeoutError, TrySendError};
+use select::{Operation, Ready, SelectHandle, Token};
+
+/// A message together with the moment it expires.
+struct Entry<T> {
+    /// The message.
+    msg: T,
+
+    /// The message expires at this instant, or never if `None`.
+    deadline: Option<Instant>,
+}
+
+impl<T> Entry<T> {
+    /// Returns `true` if the message has expired by `now`.
+    fn is_expired(&self, now: Instant) -> bool {
+        match self.deadline {
+            None => false,
+            Some(d) => d <= now,
+        }
+    }
+}
+
+/// Creates a channel of bounded capacity whose messages can expire.
+///
+/// Every message can be given a time-to-live when it is sent. Expired messages still occupy space
+/// in the channel until a receive operation comes across them, at which point they are discarded
+/// and the operation moves on to the next message. A handler can be installed with
+/// [`TtlReceiver::on_expire`] in order to be notified of discarded messages.
+///
+/// Apart from expiration, the channel behaves exactly like one created by [`bounded`].
+///
+/// # Panics
+///
+/// Panics if the capacity is greater than `usize::max_value() / 4`.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::bounded_ttl;
+///
+/// let (s, r) = bounded_ttl(10);
+///
+/// s.send_with_ttl("stale", Duration::from_millis(10)).unwrap();
+/// s.send("fresh").unwrap();
+///
+/// thread::sleep(Duration::from_millis(50));
+/// assert_eq!(r.recv(), Ok("fresh"));
+/// ```
+///
+/// [`bounded`]: fn.bounded.html
+/// [`TtlReceiver::on_expire`]: struct.TtlReceiver.html#method.on_expire
+pub fn bounded_ttl<T>(cap: usize) -> (TtlSender<T>, TtlReceiver<T>) {
+    let (s, r) = bounded(cap);
+    let s = TtlSender { sender: s };
+    let r = TtlReceiver {
+        receiver: r,
+        on_expire: None,
+    };
+    (s, r)
+}
+
+/// The sending side of a channel with expiring messages.
+///
+/// Senders are created by the [`bounded_ttl`] function.
+///
+/// [`bounded_ttl`]: fn.bounded_ttl.html
+pub struct TtlSender<T> {
+    /// The underlying sender.
+    sender: Sender<Entry<T>>,
+}
+
+impl<T> TtlSender<T> {
+    /// Attempts to send a message that never expires without blocking.
+    ///
+    /// See [`Sender::try_send`] for details.
+    ///
+    /// [`Sender::try_send`]: struct.Sender.html#method.try_send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_ttl, TrySendError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    ///
+    /// assert_eq!(s.try_send(1), Ok(()));
+    /// assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    /// # drop(r);
+    /// ```
+    pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        self.try_send_entry(Entry {
+            msg,
+            deadline: None,
+        })
+    }
+
+    /// Attempts to send a message that expires after `ttl` without blocking.
+    ///
+    /// The time-to-live starts counting when the message is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ttl, TrySendError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// let ttl = Duration::from_secs(1);
+    ///
+    /// assert_eq!(s.try_send_with_ttl(1, ttl), Ok(()));
+    /// assert_eq!(s.try_send_with_ttl(2, ttl), Err(TrySendError::Full(2)));
+    /// # drop(r);
+    /// ```
+    pub fn try_send_with_ttl(&self, msg: T, ttl: Duration) -> Result<(), TrySendError<T>> {
+        self.try_send_entry(Entry {
+            msg,
+            deadline: Some(Instant::now() + ttl),
+        })
+    }
+
+    /// Blocks the current thread until a message that never expires is sent.
+    ///
+    /// See [`Sender::send`] for details.
+    ///
+    /// [`Sender::send`]: struct.Sender.html#method.send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_ttl, SendError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// assert_eq!(s.send(1), Ok(()));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// ```
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.send_entry(Entry {
+            msg,
+            deadline: None,
+        })
+    }
+
+    /// Blocks the current thread until a message that expires after `ttl` is sent.
+    ///
+    /// The time-to-live starts counting when the message is sent, not when this method is called,
+    /// so time spent waiting for space in a full channel doesn't count against it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ttl, TryRecvError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// s.send_with_ttl(1, Duration::from_millis(10)).unwrap();
+    ///
+    /// thread::sleep(Duration::from_millis(50));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// ```
+    pub fn send_with_ttl(&self, msg: T, ttl: Duration) -> Result<(), SendError<T>> {
+        // Reserve a slot first so that the deadline is computed only once the message can go in.
+        match self.sender.reserve() {
+            Err(_) => Err(SendError(msg)),
+            Ok(permit) => permit
+                .send(Entry {
+                    msg,
+                    deadline: Some(Instant::now() + ttl),
+                }).map_err(|SendError(entry)| SendError(entry.msg)),
+        }
+    }
+
+    /// Waits for a message that never expires to be sent, but only for a limited time.
+    ///
+    /// See [`Sender::send_timeout`] for details.
+    ///
+    /// [`Sender::send_timeout`]: struct.Sender.html#method.send_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ttl, SendTimeoutError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// s.send(1).unwrap();
+    ///
+    /// assert_eq!(
+    ///     s.send_timeout(2, Duration::from_millis(100)),
+    ///     Err(SendTimeoutError::Timeout(2)),
+    /// );
+    /// # drop(r);
+    /// ```
+    pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let entry = Entry {
+            msg,
+            deadline: None,
+        };
+        self.sender
+            .send_timeout(entry, timeout)
+            .map_err(|err| match err {
+                SendTimeoutError::Timeout(e) => SendTimeoutError::Timeout(e.msg),
+                SendTimeoutError::Disconnected(e) => SendTimeoutError::Disconnected(e.msg),
+            })
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Expired messages that haven't been discarded yet count as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ttl;
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// # drop(r);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// Expired messages that haven't been discarded yet count as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ttl;
+    ///
+    /// let (s, r) = bounded_ttl(5);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// # drop(r);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// Returns the channel capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ttl;
+    ///
+    /// let (s, r) = bounded_ttl::<i32>(5);
+    /// assert_eq!(s.capacity(), 5);
+    /// # drop(r);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.sender.capacity().unwrap()
+    }
+
+    /// Sends an entry without blocking.
+    fn try_send_entry(&self, entry: Entry<T>) -> Result<(), TrySendError<T>> {
+        self.sender.try_send(entry).map_err(|err| match err {
+            TrySendError::Full(e) => TrySendError::Full(e.msg),
+            TrySendError::Disconnected(e) => TrySendError::Disconnected(e.msg),
+        })
+    }
+
+    /// Sends an entry, blocking if the channel is full.
+    fn send_entry(&self, entry: Entry<T>) -> Result<(), SendError<T>> {
+        self.sender
+            .send(entry)
+            .map_err(|SendError(entry)| SendError(entry.msg))
+    }
+}
+
+impl<T> Clone for TtlSender<T> {
+    fn clone(&self) -> TtlSender<T> {
+        TtlSender {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for TtlSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TtlSender").finish()
+    }
+}
+
+/// The receiving side of a channel with expiring messages.
+///
+/// Receive operations skip over expired messages, discarding them or passing them to the handler
+/// installed with [`on_expire`].
+///
+/// A TTL receiver can be waited on in [`Select`] with [`Select::recv_ready`]. The operation is
+/// ready once the channel holds a message, even if the message turns out to have expired. In that
+/// case, `try_recv` discards it and may report an empty channel.
+///
+/// Receivers are created by the [`bounded_ttl`] function.
+///
+/// [`on_expire`]: struct.TtlReceiver.html#method.on_expire
+/// [`bounded_ttl`]: fn.bounded_ttl.html
+/// [`Select`]: struct.Select.html
+/// [`Select::recv_ready`]: struct.Select.html#method.recv_ready
+pub struct TtlReceiver<T> {
+    /// The underlying receiver.
+    receiver: Receiver<Entry<T>>,
+
+    /// Called with every expired message that gets discarded.
+    on_expire: Option<Arc<Fn(T) + Send + Sync>>,
+}
+
+impl<T> TtlReceiver<T> {
+    /// Installs a handler that is called with every expired message this receiver discards.
+    ///
+    /// The handler is shared with clones of the receiver made afterwards. It runs on the thread
+    /// performing the receive operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ttl, unbounded};
+    ///
+    /// let (dead_s, dead_r) = unbounded();
+    /// let (s, r) = bounded_ttl(10);
+    /// let r = r.on_expire(move |msg| dead_s.send(msg).unwrap());
+    ///
+    /// s.send_with_ttl("stale", Duration::from_millis(10)).unwrap();
+    /// s.send("fresh").unwrap();
+    /// thread::sleep(Duration::from_millis(50));
+    ///
+    /// assert_eq!(r.recv(), Ok("fresh"));
+    /// assert_eq!(dead_r.try_recv(), Ok("stale"));
+    /// ```
+    pub fn on_expire<F>(mut self, handler: F) -> TtlReceiver<T>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.on_expire = Some(Arc::new(handler));
+        self
+    }
+
+    /// Attempts to receive an unexpired message from the channel without blocking.
+    ///
+    /// Expired messages found along the way are discarded. See [`Receiver::try_recv`] for details.
+    ///
+    /// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded_ttl, TryRecvError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        loop {
+            let entry = self.receiver.try_recv()?;
+            if let Some(msg) = self.check(entry) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Blocks the current thread until an unexpired message is received or the channel is empty
+    /// and disconnected.
+    ///
+    /// Expired messages found along the way are discarded. See [`Receiver::recv`] for details.
+    ///
+    /// [`Receiver::recv`]: struct.Receiver.html#method.recv
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ttl, RecvError};
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send_with_ttl(5, Duration::from_secs(1)).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            let entry = self.receiver.recv()?;
+            if let Some(msg) = self.check(entry) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Waits for an unexpired message to be received from the channel, but only for a limited
+    /// time.
+    ///
+    /// Expired messages found along the way are discarded. See [`Receiver::recv_timeout`] for
+    /// details.
+    ///
+    /// [`Receiver::recv_timeout`]: struct.Receiver.html#method.recv_timeout
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded_ttl, RecvTimeoutError};
+    ///
+    /// let (s, r) = bounded_ttl::<i32>(1);
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// # drop(s);
+    /// ```
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let now = Instant::now();
+            let timeout = if now < deadline {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            };
+
+            let entry = self.receiver.recv_timeout(timeout)?;
+            if let Some(msg) = self.check(entry) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Expired messages that haven't been discarded yet count as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ttl;
+    ///
+    /// let (s, r) = bounded_ttl(1);
+    /// assert!(r.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// Expired messages that haven't been discarded yet count as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ttl;
+    ///
+    /// let (s, r) = bounded_ttl(5);
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Returns the channel capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded_ttl;
+    ///
+    /// let (s, r) = bounded_ttl::<i32>(5);
+    /// assert_eq!(r.capacity(), 5);
+    /// # drop(s);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.receiver.capacity().unwrap()
+    }
+
+    /// Returns the message in the entry, or disposes of it and returns `None` if it has expired.
+    fn check(&self, entry: Entry<T>) -> Option<T> {
+        if !entry.is_expired(Instant::now()) {
+            return Some(entry.msg);
+        }
+        if let Some(ref handler) = self.on_expire {
+            handler(entry.msg);
+        }
+        None
+    }
+}
+
+impl<T> Clone for TtlReceiver<T> {
+    fn clone(&self) -> TtlReceiver<T> {
+        TtlReceiver {
+            receiver: self.receiver.clone(),
+            on_expire: self.on_expire.clone(),
+        }
+    }
+}
+
+impl<T> SelectHandle for TtlReceiver<T> {
+    fn try(&self, token: &mut Token) -> bool {
+        Ready::new(&self.receiver).try(token)
+    }
+
+    fn retry(&self, token: &mut Token) -> bool {
+        Ready::new(&self.receiver).retry(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, token: &mut Token, oper: Operation, cx: &Context) -> bool {
+        Ready::new(&self.receiver).register(token, oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        Ready::new(&self.receiver).unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        Ready::new(&self.receiver).accept(token, cx)
+    }
+
+    fn state(&self) -> usize {
+        self.receiver.state()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.receiver.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) {
+        self.receiver.watch(oper, cx);
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.receiver.unwatch(oper);
+    }
+}
+
+impl<T> fmt::Debug for TtlReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TtlReceiver").finish()
+    }
+}
@@ -4,10 +4,25 @@ use std::cell::Cell;
 use std::num::Wrapping;
 use std::sync::atomic;
 use std::thread;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 use std::time::{Duration, Instant};
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 use rand;
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use park;
+
+/// Signals the processor that it is inside a busy-wait spin loop.
+///
+/// `std::hint::spin_loop` would be the replacement for the deprecated `spin_loop_hint`, but it
+/// requires a newer compiler than the crate supports.
+#[inline]
+#[allow(deprecated)]
+pub fn spin_loop() {
+    atomic::spin_loop_hint();
+}
+
 /// A counter that performs exponential backoff in spin loops.
 pub struct Backoff(u32);
 
@@ -24,7 +39,7 @@ impl Backoff {
     #[inline]
     pub fn spin(&mut self) {
         for _ in 0..1 << self.0.min(6) {
-            atomic::spin_loop_hint();
+            spin_loop();
         }
         self.0 = self.0.wrapping_add(1);
     }
@@ -40,7 +55,7 @@ impl Backoff {
     pub fn snooze(&mut self) -> bool {
         if self.0 <= 6 {
             for _ in 0..1 << self.0 {
-                atomic::spin_loop_hint();
+                spin_loop();
             }
         } else {
             thread::yield_now();
@@ -51,6 +66,69 @@ impl Backoff {
     }
 }
 
+thread_local! {
+    /// The random number generator used for shuffling.
+    static RNG: Cell<Wrapping<u32>> = {
+        let init = seed() | 1;
+        Cell::new(Wrapping(init))
+    }
+}
+
+/// Turns a seed into a valid state of the random number generator.
+pub fn rng_from_seed(seed: u64) -> Wrapping<u32> {
+    // Scramble the seed with SplitMix64 so that similar seeds give unrelated sequences.
+    //
+    // Source: http://xorshift.di.unimi.it/splitmix64.c
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+
+    // Xorshift gets stuck on zero.
+    Wrapping(z as u32 | 1)
+}
+
+/// Reseeds the random number generator of the current thread.
+pub fn set_seed(seed: u64) {
+    let _ = RNG.try_with(|rng| rng.set(rng_from_seed(seed)));
+}
+
+/// Runs a closure with `rng` standing in for the random number generator of the current thread.
+///
+/// The state of `rng` is updated with any numbers drawn in the meantime.
+pub fn with_rng<F, R>(rng: &mut Wrapping<u32>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    match RNG.try_with(|cell| cell.replace(*rng)) {
+        Err(_) => f(),
+        Ok(prev) => {
+            let res = f();
+            *rng = RNG.with(|cell| cell.replace(prev));
+            res
+        }
+    }
+}
+
+/// A small pseudorandom number generator.
+#[cfg(any(feature = "sim", feature = "testing"))]
+pub struct Rng(pub u64);
+
+#[cfg(any(feature = "sim", feature = "testing"))]
+impl Rng {
+    /// Returns the next number in the sequence.
+    pub fn next(&mut self) -> u64 {
+        // This is SplitMix64.
+        //
+        // Source: http://xorshift.di.unimi.it/splitmix64.c
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
 /// Randomly shuffles a slice.
 pub fn shuffle<T>(v: &mut [T]) {
     let len = v.len();
@@ -58,25 +136,9 @@ pub fn shuffle<T>(v: &mut [T]) {
         return;
     }
 
-    thread_local! {
-        static RNG: Cell<Wrapping<u32>> = {
-            let init = rand::random::<u32>() | 1;
-            Cell::new(Wrapping(init))
-        }
-    }
-
     let _ = RNG.try_with(|rng| {
         for i in 1..len {
-            // This is the 32-bit variant of Xorshift.
-            //
-            // Source: https://en.wikipedia.org/wiki/Xorshift
-            let mut x = rng.get();
-            x ^= x << 13;
-            x ^= x >> 17;
-            x ^= x << 5;
-            rng.set(x);
-
-            let x = x.0;
+            let x = step(rng);
             let n = i + 1;
 
             // This is a fast alternative to `let j = x % n`.
@@ -90,8 +152,67 @@ pub fn shuffle<T>(v: &mut [T]) {
     });
 }
 
+/// Returns a random duration between zero and `max`, inclusive.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn random_duration(max: Duration) -> Duration {
+    if max == Duration::from_secs(0) {
+        return max;
+    }
+    let x = RNG.try_with(step).unwrap_or(0);
+
+    // Scale the maximum by `(x + 1) / 2^32`, which can reach `max` itself.
+    let nanos = u128::from(max.as_secs()) * 1_000_000_000 + u128::from(max.subsec_nanos());
+    let nanos = (nanos * (u128::from(x) + 1)) >> 32;
+    Duration::new(
+        (nanos / 1_000_000_000) as u64,
+        (nanos % 1_000_000_000) as u32,
+    )
+}
+
+/// Advances the random number generator and returns the next number.
+fn step(rng: &Cell<Wrapping<u32>>) -> u32 {
+    // This is the 32-bit variant of Xorshift.
+    //
+    // Source: https://en.wikipedia.org/wiki/Xorshift
+    let mut x = rng.get();
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    rng.set(x);
+    x.0
+}
+
+/// Returns a random seed for the thread-local random number generator.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn seed() -> u32 {
+    rand::random::<u32>()
+}
+
+/// Returns a seed for the thread-local random number generator.
+///
+/// There is no source of entropy on `wasm32-unknown-unknown` without bindings to the host, so a
+/// fixed seed is used instead. Random selection is then still fair, but not unpredictable.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn seed() -> u32 {
+    0x9e37_79b9
+}
+
 /// Sleeps until the deadline, or forever if the deadline isn't specified.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub fn sleep_until(deadline: Option<Instant>) {
+    // If the execution context blocks using a custom parker, park it instead of the thread.
+    if let Some(parker) = park::current() {
+        loop {
+            if let Some(d) = deadline {
+                if Instant::now() >= d {
+                    break;
+                }
+            }
+            parker.park(deadline);
+        }
+        return;
+    }
+
     loop {
         match deadline {
             None => thread::sleep(Duration::from_secs(1000)),
@@ -105,3 +226,14 @@ pub fn sleep_until(deadline: Option<Instant>) {
         }
     }
 }
+
+/// Returns the time left until the deadline, or zero if it has passed.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn remaining(deadline: Instant) -> Duration {
+    let now = Instant::now();
+    if now < deadline {
+        deadline - now
+    } else {
+        Duration::from_secs(0)
+    }
+}
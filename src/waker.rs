@@ -2,13 +2,19 @@
 
 use std::collections::VecDeque;
 use std::num::Wrapping;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::thread::{self, ThreadId};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use parking_lot::Mutex;
 
-use context::Context;
+use affinity::{self, Location};
+use builder::WakePolicy;
+use context::{Context, Id};
 use select::{Operation, Selected};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use select::Token;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use utils::Backoff;
 
 /// Represents a thread blocked on a specific channel operation.
 pub struct Entry {
@@ -19,9 +25,16 @@ pub struct Entry {
     pub oper: Operation,
 
     /// Optional packet.
-    pub packet: usize,
+    pub packet: *const u8,
+
+    /// Where the thread was running when it registered, if the waker keeps track of it.
+    pub location: Option<Location>,
 }
 
+// The packet is only accessed by the thread that gets paired up with the operation, and it stays
+// valid until that thread is done with it.
+unsafe impl Send for Entry {}
+
 /// A queue of threads blocked on channel operations.
 ///
 /// This data structure is used by threads to register blocking operations and get woken up once
@@ -30,33 +43,58 @@ pub struct Waker {
     /// The list of registered blocking operations.
     entries: VecDeque<Entry>,
 
+    /// The list of operations waiting to become ready without being performed.
+    observers: Vec<Entry>,
+
     /// The number of calls to `register` and `register_with_packet`.
     register_count: Wrapping<usize>,
+
+    /// Decides which operation `wake_one` selects.
+    policy: WakePolicy,
 }
 
 impl Waker {
     /// Creates a new `Waker`.
     #[inline]
     pub fn new() -> Self {
+        Waker::with_policy(WakePolicy::Fifo)
+    }
+
+    /// Creates a new `Waker` with a wake policy.
+    #[inline]
+    pub fn with_policy(policy: WakePolicy) -> Self {
         Waker {
             entries: VecDeque::new(),
+            observers: Vec::new(),
             register_count: Wrapping(0),
+            policy,
+        }
+    }
+
+    /// Returns where the current thread is running, if the wake policy needs to know.
+    #[inline]
+    fn locate(&self) -> Option<Location> {
+        match self.policy {
+            WakePolicy::Fifo | WakePolicy::Exclusive => None,
+            WakePolicy::Affinity => affinity::current(),
         }
     }
 
     /// Registers the current thread with an operation.
     #[inline]
     pub fn register(&mut self, oper: Operation, cx: &Context) {
-        self.register_with_packet(oper, 0, cx);
+        self.register_with_packet(oper, ptr::null(), cx);
     }
 
     /// Registers the current thread with an operation and a packet.
     #[inline]
-    pub fn register_with_packet(&mut self, oper: Operation, packet: usize, cx: &Context) {
+    pub fn register_with_packet(&mut self, oper: Operation, packet: *const u8, cx: &Context) {
+        let location = self.locate();
         self.entries.push_back(Entry {
             context: cx.clone(),
             oper,
             packet,
+            location,
         });
         self.register_count += Wrapping(1);
     }
@@ -77,41 +115,164 @@ impl Waker {
         }
     }
 
+    /// Registers the current thread to be notified when an operation might become ready.
+    ///
+    /// Unlike registered operations, watched operations are never performed by the other side of
+    /// the channel. The thread is simply woken up so that it can check the channel again.
+    #[inline]
+    pub fn watch(&mut self, oper: Operation, cx: &Context) {
+        self.observers.push(Entry {
+            context: cx.clone(),
+            oper,
+            packet: ptr::null(),
+            location: None,
+        });
+    }
+
+    /// Unregisters an operation previously watched by the current thread.
+    #[inline]
+    pub fn unwatch(&mut self, oper: Operation) {
+        self.observers.retain(|entry| entry.oper != oper);
+    }
+
+    /// Wakes up all threads watching for operations to become ready.
+    #[inline]
+    pub fn notify(&mut self) {
+        for entry in self.observers.drain(..) {
+            if entry.context.try_select(Selected::Operation(entry.oper)).is_ok() {
+                entry.context.unpark();
+            }
+        }
+    }
+
+    /// Wakes up the first thread watching for an operation to become ready that hasn't been woken
+    /// up by something else yet.
+    #[inline]
+    fn notify_one(&mut self) {
+        while !self.observers.is_empty() {
+            let entry = self.observers.remove(0);
+            if entry.context.try_select(Selected::Operation(entry.oper)).is_ok() {
+                entry.context.unpark();
+                break;
+            }
+        }
+    }
+
     /// Attempts to find one thread (not the current one), select its operation, and wake it up.
+    ///
+    /// Threads watching for operations to become ready are woken up too. With the exclusive
+    /// policy, one of them is woken up only if no operation could be selected.
     #[inline]
     pub fn wake_one(&mut self) -> Option<Entry> {
+        let exclusive = self.policy == WakePolicy::Exclusive;
+        if !exclusive {
+            self.notify();
+        }
+
         if !self.entries.is_empty() {
-            let thread_id = current_thread_id();
+            let id = Id::current();
+
+            // With the affinity policy, try the thread closest to the current one first.
+            if self.entries.len() > 1 {
+                if let Some(i) = self.closest(id) {
+                    if let Some(entry) = self.try_wake(i) {
+                        return Some(entry);
+                    }
+                }
+            }
 
             for i in 0..self.entries.len() {
                 // Does the entry belong to a different thread?
-                if self.entries[i].context.thread_id() != thread_id {
-                    // Try selecting this operation.
-                    let sel = Selected::Operation(self.entries[i].oper);
-                    let res = self.entries[i].context.try_select(sel);
-
-                    if res.is_ok() {
-                        // Provide the packet.
-                        self.entries[i].context.store_packet(self.entries[i].packet);
-                        // Wake the thread up.
-                        self.entries[i].context.unpark();
-
-                        // Remove the entry from the queue to keep it clean and improve
-                        // performance.
-                        let entry = self.entries.remove(i).unwrap();
-                        Self::maybe_shrink(&mut self.entries);
+                if self.entries[i].context.id() != id {
+                    if let Some(entry) = self.try_wake(i) {
                         return Some(entry);
                     }
                 }
             }
         }
 
+        if exclusive {
+            self.notify_one();
+        }
+        None
+    }
+
+    /// Attempts to find one thread (not the current one) whose packet satisfies `pred`, select its
+    /// operation, and wake it up.
+    ///
+    /// Unlike `wake_one`, this method doesn't wake up threads watching for operations to become
+    /// ready.
+    #[inline]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wake_where<F>(&mut self, mut pred: F) -> Option<Entry>
+    where
+        F: FnMut(*const u8) -> bool,
+    {
+        let id = Id::current();
+
+        for i in 0..self.entries.len() {
+            let entry = &self.entries[i];
+            if entry.context.id() != id
+                && entry.context.selected() == Selected::Waiting
+                && pred(entry.packet)
+            {
+                if let Some(entry) = self.try_wake(i) {
+                    return Some(entry);
+                }
+            }
+        }
         None
     }
 
+    /// Returns the index of the earliest entry among the ones whose threads were running closest
+    /// to the current thread, if any of them is closer than the rest.
+    fn closest(&self, id: Id) -> Option<usize> {
+        let here = self.locate()?;
+        let mut best = None;
+        let mut best_closeness = 0;
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            if let Some(location) = &entry.location {
+                let closeness = here.closeness(location);
+                if closeness > best_closeness
+                    && entry.context.id() != id
+                    && entry.context.selected() == Selected::Waiting
+                {
+                    best = Some(i);
+                    best_closeness = closeness;
+                }
+            }
+        }
+        best
+    }
+
+    /// Attempts to select the operation of the entry at index `i` and wake its thread up.
+    #[inline]
+    fn try_wake(&mut self, i: usize) -> Option<Entry> {
+        // Try selecting this operation.
+        let sel = Selected::Operation(self.entries[i].oper);
+        let res = self.entries[i].context.try_select(sel);
+
+        if res.is_ok() {
+            // Provide the packet.
+            self.entries[i].context.store_packet(self.entries[i].packet);
+            // Wake the thread up.
+            self.entries[i].context.unpark();
+
+            // Remove the entry from the queue to keep it clean and improve performance.
+            let entry = self.entries.remove(i).unwrap();
+            Self::maybe_shrink(&mut self.entries);
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
     /// Notifies all threads that the channel is disconnected.
     #[inline]
     pub fn disconnect(&mut self) {
+        self.notify();
+
         for entry in self.entries.iter() {
             if entry.context.try_select(Selected::Disconnected).is_ok() {
                 // Wake the thread up.
@@ -124,16 +285,31 @@ impl Waker {
         }
     }
 
+    /// Wakes up all threads so that they check the channel again.
+    ///
+    /// Like in `disconnect`, the entries are not removed from the queue, and registered threads
+    /// must unregister from the waker by themselves.
+    #[inline]
+    pub fn notify_all(&mut self) {
+        self.notify();
+
+        for entry in self.entries.iter() {
+            if entry.context.try_select(Selected::Aborted).is_ok() {
+                entry.context.unpark();
+            }
+        }
+    }
+
     /// Returns `true` if there is an entry which can be woken up by the current thread.
     #[inline]
     pub fn can_wake_one(&self) -> bool {
         if self.entries.is_empty() {
             false
         } else {
-            let thread_id = current_thread_id();
+            let id = Id::current();
 
             self.entries.iter().any(|entry| {
-                entry.context.thread_id() != thread_id
+                entry.context.id() != id
                     && entry.context.selected() == Selected::Waiting
             })
         }
@@ -145,6 +321,12 @@ impl Waker {
         self.entries.len()
     }
 
+    /// Returns `true` if some thread is watching for an operation to become ready.
+    #[inline]
+    pub fn is_observed(&self) -> bool {
+        !self.observers.is_empty()
+    }
+
     #[inline]
     pub fn register_count(&self) -> usize {
         self.register_count.0
@@ -165,6 +347,7 @@ impl Drop for Waker {
     #[inline]
     fn drop(&mut self) {
         debug_assert!(self.entries.is_empty());
+        debug_assert!(self.observers.is_empty());
     }
 }
 
@@ -177,15 +360,25 @@ pub struct SyncWaker {
 
     /// Number of operations in the waker.
     len: AtomicUsize,
+
+    /// Equals `true` if some thread is watching for an operation to become ready.
+    is_observed: AtomicBool,
 }
 
 impl SyncWaker {
     /// Creates a new `SyncWaker`.
     #[inline]
     pub fn new() -> Self {
+        SyncWaker::with_policy(WakePolicy::Fifo)
+    }
+
+    /// Creates a new `SyncWaker` with a wake policy.
+    #[inline]
+    pub fn with_policy(policy: WakePolicy) -> Self {
         SyncWaker {
-            inner: Mutex::new(Waker::new()),
+            inner: Mutex::new(Waker::with_policy(policy)),
             len: AtomicUsize::new(0),
+            is_observed: AtomicBool::new(false),
         }
     }
 
@@ -210,22 +403,104 @@ impl SyncWaker {
         }
     }
 
+    /// Registers the current thread to be notified when an operation might become ready.
+    #[inline]
+    pub fn watch(&self, oper: Operation, cx: &Context) {
+        let mut inner = self.inner.lock();
+        inner.watch(oper, cx);
+        self.is_observed.store(true, Ordering::SeqCst);
+    }
+
+    /// Unregisters an operation previously watched by the current thread.
+    #[inline]
+    pub fn unwatch(&self, oper: Operation) {
+        let mut inner = self.inner.lock();
+        inner.unwatch(oper);
+        self.is_observed.store(inner.is_observed(), Ordering::SeqCst);
+    }
+
     /// Attempts to find one thread (not the current one), select its operation, and wake it up.
+    ///
+    /// Threads watching for operations to become ready are woken up too.
     #[inline]
     pub fn wake_one(&self) -> Option<Entry> {
-        if self.len.load(Ordering::SeqCst) > 0 {
+        if self.len.load(Ordering::SeqCst) > 0 || self.is_observed.load(Ordering::SeqCst) {
             let mut inner = self.inner.lock();
             let entry = inner.wake_one();
             self.len.store(inner.len(), Ordering::SeqCst);
+            self.is_observed.store(inner.is_observed(), Ordering::SeqCst);
             entry
         } else {
             None
         }
     }
 
+    /// Wakes up all threads watching for operations to become ready.
+    #[inline]
+    pub fn notify(&self) {
+        if self.is_observed.load(Ordering::SeqCst) {
+            let mut inner = self.inner.lock();
+            inner.notify();
+            self.is_observed.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Blocks the current thread until `cond` returns `true`.
+    ///
+    /// The condition is checked again every time the waker wakes up its observers. If `park` is
+    /// `false`, the thread is never parked and keeps checking the condition instead, spinning at
+    /// first and then yielding.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn wait_until<F>(&self, cond: F, park: bool)
+    where
+        F: Fn() -> bool,
+    {
+        if !park {
+            let mut backoff = Backoff::new();
+            while !cond() {
+                backoff.snooze();
+            }
+            return;
+        }
+
+        let token = &mut Token::default();
+        while !cond() {
+            Context::with(|cx| {
+                let oper = Operation::hook(token);
+                self.watch(oper, cx);
+
+                // Has the condition been met just now?
+                if cond() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                cx.wait_until(None);
+                self.unwatch(oper);
+            });
+        }
+    }
+
+    /// Returns the number of operations in the waker.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
     /// Notifies all threads that the channel is disconnected.
     pub fn disconnect(&self) {
-        self.inner.lock().disconnect();
+        let mut inner = self.inner.lock();
+        inner.disconnect();
+        self.is_observed.store(false, Ordering::SeqCst);
+    }
+
+    /// Wakes up all threads so that they check the channel again.
+    #[inline]
+    pub fn notify_all(&self) {
+        if self.len.load(Ordering::SeqCst) > 0 || self.is_observed.load(Ordering::SeqCst) {
+            let mut inner = self.inner.lock();
+            inner.notify_all();
+            self.is_observed.store(false, Ordering::SeqCst);
+        }
     }
 }
 
@@ -234,18 +509,6 @@ impl Drop for SyncWaker {
     fn drop(&mut self) {
         debug_assert_eq!(self.inner.lock().len(), 0);
         debug_assert_eq!(self.len.load(Ordering::SeqCst), 0);
+        debug_assert!(!self.is_observed.load(Ordering::SeqCst));
     }
 }
-
-/// Returns the id of the current thread.
-#[inline]
-fn current_thread_id() -> ThreadId {
-    thread_local! {
-        /// Cached thread-local id.
-        static THREAD_ID: ThreadId = thread::current().id();
-    }
-
-    THREAD_ID
-        .try_with(|id| *id)
-        .unwrap_or_else(|_| thread::current().id())
-}
@@ -0,0 +1,325 @@
+//! A hierarchical timer wheel shared by all timer channels.
+//!
+//! Channels created by [`after`] and [`tick`] on the system clock don't hand out deadlines to the
+//! select operations blocked on them. Instead, they register the blocked operation here, and a
+//! single background thread wakes it up once its timer expires. A blocked select therefore doesn't
+//! have to look at every timer to figure out how long to sleep, and timers expiring at the same
+//! moment are fired together in one pass.
+//!
+//! Time is measured in ticks of one millisecond since the wheel was created. The wheel has
+//! `LEVELS` levels of `SLOTS` slots each: a slot on level 0 covers a single tick, and a slot on
+//! each following level covers all slots of the level below it. A timer is put on the lowest level
+//! whose slots are still coarser than the distance to its expiration, so inserting and removing a
+//! timer takes constant time. As time passes, the slots of higher levels are emptied and their
+//! timers move down to lower levels, until they reach level 0 and fire.
+//!
+//! Timers are rounded up to the next tick, so they never fire early, but they may fire up to a
+//! millisecond late. The background thread is started when the first timer is registered and
+//! keeps running, parked whenever no timers are registered.
+//!
+//! [`after`]: ../fn.after.html
+//! [`tick`]: ../fn.tick.html
+
+use std::collections::HashMap;
+use std::mem;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use context::Context;
+use select::{Operation, Selected};
+
+/// The number of slots on each level.
+const SLOTS: usize = 64;
+
+/// The number of bits selecting a slot on a level.
+const SLOT_BITS: usize = 6;
+
+/// The number of levels, which together cover over two years.
+const LEVELS: usize = 6;
+
+/// The number of ticks covered by all levels together.
+const RANGE: u64 = 1 << (LEVELS * SLOT_BITS);
+
+/// A select operation waiting for a timer.
+struct Timer {
+    /// The tick at which the timer expires.
+    when: u64,
+
+    /// The level holding the timer, or `LEVELS` if it expires beyond the range of the wheel.
+    level: usize,
+
+    /// The slot holding the timer.
+    slot: usize,
+
+    /// Context of the thread owning the operation.
+    context: Context,
+}
+
+/// A level of the wheel.
+struct Level {
+    /// The operations whose timers are in each slot.
+    slots: Vec<Vec<Operation>>,
+
+    /// A bit mask of slots holding at least one timer.
+    occupied: u64,
+}
+
+/// Mutable state of the wheel.
+struct State {
+    /// The tick up to which all timers have been processed.
+    elapsed: u64,
+
+    /// The levels, from the finest to the coarsest.
+    levels: Vec<Level>,
+
+    /// Operations whose timers expire beyond the range of the wheel.
+    overflow: Vec<Operation>,
+
+    /// All registered timers.
+    timers: HashMap<Operation, Timer>,
+
+    /// The tick at which the background thread is going to wake up, or `None` if it is parked
+    /// until a timer is registered.
+    wakeup: Option<u64>,
+}
+
+impl State {
+    /// Puts a timer into the slot it belongs to.
+    fn insert(&mut self, oper: Operation, when: u64, context: Context) {
+        let level = level_for(self.elapsed, when);
+        let slot = ((when >> (level * SLOT_BITS)) as usize) & (SLOTS - 1);
+
+        if level < LEVELS {
+            self.levels[level].slots[slot].push(oper);
+            self.levels[level].occupied |= 1 << slot;
+        } else {
+            self.overflow.push(oper);
+        }
+        self.timers.insert(
+            oper,
+            Timer {
+                when,
+                level,
+                slot,
+                context,
+            },
+        );
+    }
+
+    /// Removes a timer from its slot.
+    fn remove(&mut self, oper: Operation) {
+        if let Some(timer) = self.timers.remove(&oper) {
+            if timer.level == LEVELS {
+                self.overflow.retain(|&o| o != oper);
+                return;
+            }
+
+            let level = &mut self.levels[timer.level];
+            let slot = &mut level.slots[timer.slot];
+            slot.retain(|&o| o != oper);
+            if slot.is_empty() {
+                level.occupied &= !(1 << timer.slot);
+            }
+        }
+    }
+
+    /// Returns the level, slot, and starting tick of the next slot to process.
+    fn next_expiration(&self) -> Option<(usize, usize, u64)> {
+        for (i, level) in self.levels.iter().enumerate() {
+            if level.occupied == 0 {
+                continue;
+            }
+
+            // Timers on lower levels always expire before timers on higher levels, so the first
+            // occupied slot at or after the current position is the next one.
+            let slot_range = 1u64 << (i * SLOT_BITS);
+            let level_range = slot_range << SLOT_BITS;
+            let now_slot = ((self.elapsed >> (i * SLOT_BITS)) as usize) & (SLOTS - 1);
+            let distance = level.occupied.rotate_right(now_slot as u32).trailing_zeros() as usize;
+            let slot = (now_slot + distance) & (SLOTS - 1);
+
+            let start = (self.elapsed & !(level_range - 1)) + slot as u64 * slot_range;
+            return Some((i, slot, start));
+        }
+
+        // Timers beyond the range of the wheel are looked at again once the wheel has turned
+        // completely.
+        if self.overflow.is_empty() {
+            None
+        } else {
+            Some((LEVELS, 0, (self.elapsed | (RANGE - 1)) + 1))
+        }
+    }
+
+    /// Fires all timers expiring up to tick `now` and moves the others closer to level 0.
+    fn process(&mut self, now: u64) {
+        while let Some((level, slot, start)) = self.next_expiration() {
+            if start > now {
+                break;
+            }
+            if start > self.elapsed {
+                self.elapsed = start;
+            }
+
+            let opers = if level < LEVELS {
+                self.levels[level].occupied &= !(1 << slot);
+                mem::replace(&mut self.levels[level].slots[slot], Vec::new())
+            } else {
+                mem::replace(&mut self.overflow, Vec::new())
+            };
+
+            for oper in opers {
+                let timer = self.timers.remove(&oper).unwrap();
+                if timer.when <= now {
+                    if timer.context.try_select(Selected::Operation(oper)).is_ok() {
+                        timer.context.unpark();
+                    }
+                } else {
+                    self.insert(oper, timer.when, timer.context);
+                }
+            }
+        }
+
+        if now > self.elapsed {
+            self.elapsed = now;
+        }
+    }
+}
+
+/// The timer wheel.
+struct Wheel {
+    /// The instant of tick 0.
+    start: Instant,
+
+    /// The state of the wheel.
+    state: Mutex<State>,
+
+    /// The background thread firing timers.
+    driver: Thread,
+}
+
+impl Wheel {
+    /// Returns the first tick at or after `at`.
+    fn tick_after(&self, at: Instant) -> u64 {
+        if at <= self.start {
+            return 0;
+        }
+        let d = at - self.start;
+        let ms = d.as_secs() * 1000 + u64::from(d.subsec_nanos() / 1_000_000);
+        if d.subsec_nanos() % 1_000_000 == 0 {
+            ms
+        } else {
+            ms + 1
+        }
+    }
+
+    /// Returns the last tick at or before `at`.
+    fn tick_before(&self, at: Instant) -> u64 {
+        if at <= self.start {
+            return 0;
+        }
+        let d = at - self.start;
+        d.as_secs() * 1000 + u64::from(d.subsec_nanos() / 1_000_000)
+    }
+
+    /// Runs the background thread, firing timers as they expire.
+    fn run(&self) -> ! {
+        loop {
+            let now = Instant::now();
+            let wakeup = {
+                let mut state = self.state.lock();
+                state.process(self.tick_before(now));
+                state.wakeup = state.next_expiration().map(|(_, _, start)| start);
+                state.wakeup
+            };
+
+            match wakeup {
+                None => thread::park(),
+                Some(tick) => {
+                    let at = self.start + Duration::from_millis(tick);
+                    if at > now {
+                        thread::park_timeout(at - now);
+                    }
+                }
+            }
+        }
+    }
+}
+
+lazy_static! {
+    /// The timer wheel shared by all timer channels.
+    ///
+    /// The background thread is started when the wheel is first used. It waits for the
+    /// initialization to complete before it touches the wheel.
+    static ref WHEEL: Wheel = {
+        let handle = thread::Builder::new()
+            .name("crossbeam-channel-timer".to_string())
+            .spawn(|| WHEEL.run())
+            .expect("failed to spawn the timer thread");
+
+        Wheel {
+            start: Instant::now(),
+            state: Mutex::new(State {
+                elapsed: 0,
+                levels: (0..LEVELS)
+                    .map(|_| Level {
+                        slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+                        occupied: 0,
+                    })
+                    .collect(),
+                overflow: Vec::new(),
+                timers: HashMap::new(),
+                wakeup: None,
+            }),
+            driver: handle.thread().clone(),
+        }
+    };
+}
+
+/// Registers an operation to be selected once the instant `at` is reached.
+///
+/// Returns `false` if the instant has already been reached, in which case nothing is registered.
+pub fn register(at: Instant, oper: Operation, cx: &Context) -> bool {
+    let wheel = &*WHEEL;
+    let when = wheel.tick_after(at);
+
+    let mut state = wheel.state.lock();
+    if when <= wheel.tick_before(Instant::now()) {
+        return false;
+    }
+
+    state.remove(oper);
+    state.insert(oper, when, cx.clone());
+
+    // Wake up the background thread if it would sleep past the new timer.
+    if state.wakeup.map_or(true, |w| when < w) {
+        state.wakeup = Some(when);
+        wheel.driver.unpark();
+    }
+    true
+}
+
+/// Registers an operation to be selected once the instant `at` is reached, selecting it right
+/// away if the instant has already been reached.
+pub fn watch(at: Instant, oper: Operation, cx: &Context) {
+    if !register(at, oper, cx) && cx.try_select(Selected::Operation(oper)).is_ok() {
+        cx.unpark();
+    }
+}
+
+/// Unregisters an operation previously registered by the current thread.
+pub fn unregister(oper: Operation) {
+    WHEEL.state.lock().remove(oper);
+}
+
+/// Returns the level for a timer expiring at tick `when`, which is the lowest level on which
+/// ticks `elapsed` and `when` fall into the same rotation.
+///
+/// Returns `LEVELS` or more if the timer expires beyond the range of the wheel.
+fn level_for(elapsed: u64, when: u64) -> usize {
+    let masked = (elapsed ^ when) | (SLOTS as u64 - 1);
+    let significant = 63 - masked.leading_zeros() as usize;
+    significant / SLOT_BITS
+}
@@ -0,0 +1,111 @@
+//! Pairing up messages of two receivers.
+
+use std::thread;
+
+use channel::{bounded, Receiver};
+use select::Select;
+
+/// Combines two receivers into a single receiver of pairs.
+///
+/// The combined receiver delivers a pair as soon as both receivers have delivered a message,
+/// pairing up messages in the order they arrive. This is the channel equivalent of
+/// [`Iterator::zip`].
+///
+/// Messages are paired up by a background thread. The thread waits on both receivers at once,
+/// so it notices that either of them got disconnected right away, and forwards one pair at a time
+/// through a channel of capacity one. Messages are taken out of their original channels only once
+/// there is room for the pair, so at most one pair is buffered on the way. Messages that haven't
+/// been paired up yet stay in their original channels, which keeps applying backpressure to
+/// senders.
+///
+/// The combined receiver becomes disconnected as soon as either receiver is disconnected and empty,
+/// since no more pairs can be formed. A message that was already received from the other receiver
+/// and has no partner is put back into its channel. The background thread stops at that point, or
+/// as soon as the combined receiver is dropped, even if no messages arrive anymore.
+///
+/// [`Iterator::zip`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.zip
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::{unbounded, zip, RecvError};
+///
+/// let (s1, r1) = unbounded();
+/// let (s2, r2) = unbounded();
+/// let r = zip(r1, r2);
+///
+/// s1.send(1).unwrap();
+/// s1.send(2).unwrap();
+/// s2.send('a').unwrap();
+/// assert_eq!(r.recv(), Ok((1, 'a')));
+///
+/// // The second message has no partner yet.
+/// let s = s2.clone();
+/// thread::spawn(move || s.send('b').unwrap());
+/// assert_eq!(r.recv(), Ok((2, 'b')));
+///
+/// // No more pairs can be formed once a receiver is disconnected and empty.
+/// drop(s1);
+/// assert_eq!(r.recv(), Err(RecvError));
+/// # drop(s2);
+/// ```
+pub fn zip<A, B>(r1: Receiver<A>, r2: Receiver<B>) -> Receiver<(A, B)>
+where
+    A: Send + 'static,
+    B: Send + 'static,
+{
+    let (s, r) = bounded(1);
+
+    thread::spawn(move || {
+        let mut sel = Select::new();
+        let oper1 = sel.recv(&r1);
+        let oper2 = sel.recv(&r2);
+        let abandoned = sel.abandoned(&s);
+
+        loop {
+            // Make room for the next pair before taking messages out of the receivers.
+            let permit = match s.reserve() {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+
+            // Messages are held in guards until the pair is complete, so that they are put back
+            // if it never is.
+            let mut a = None;
+            let mut b = None;
+
+            // Wait for a message from each side, in whichever order they arrive.
+            while a.is_none() || b.is_none() {
+                let oper = sel.select();
+                let index = oper.index();
+
+                if index == abandoned {
+                    oper.abandoned(&s);
+                    return;
+                } else if index == oper1 {
+                    match oper.recv_guard(&r1) {
+                        Ok(msg) => a = Some(msg),
+                        Err(_) => return,
+                    }
+                } else {
+                    match oper.recv_guard(&r2) {
+                        Ok(msg) => b = Some(msg),
+                        Err(_) => return,
+                    }
+                }
+
+                // Don't take a second message from the same side before the pair is complete.
+                sel.disarm(index);
+            }
+
+            let pair = (a.unwrap().commit(), b.unwrap().commit());
+            if permit.send(pair).is_err() {
+                return;
+            }
+            sel.rearm(oper1);
+            sel.rearm(oper2);
+        }
+    });
+    r
+}
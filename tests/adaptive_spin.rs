@@ -0,0 +1,130 @@
+//! Tests for adaptive spinning before parking.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Builder, Receiver, RecvTimeoutError, Sender};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn channels(adaptive: bool) -> Vec<(Sender<usize>, Receiver<usize>)> {
+    vec![
+        Builder::new().adaptive_spin(adaptive).unbounded(),
+        Builder::new().adaptive_spin(adaptive).bounded(1),
+        Builder::new().adaptive_spin(adaptive).bounded(0),
+        Builder::new().adaptive_spin(adaptive).lanes(1, 2),
+    ]
+}
+
+#[test]
+fn ping_pong() {
+    const COUNT: usize = 10_000;
+
+    for &adaptive in &[true, false] {
+        let (s1, r1) = Builder::new().adaptive_spin(adaptive).bounded(1);
+        let (s2, r2) = Builder::new().adaptive_spin(adaptive).bounded(1);
+
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    assert_eq!(r1.recv(), Ok(i));
+                    s2.send(i).unwrap();
+                }
+            });
+
+            for i in 0..COUNT {
+                s1.send(i).unwrap();
+                assert_eq!(r2.recv(), Ok(i));
+            }
+        });
+    }
+}
+
+#[test]
+fn slow_peer() {
+    for &adaptive in &[true, false] {
+        for (s, r) in channels(adaptive) {
+            crossbeam::scope(|scope| {
+                scope.spawn(|| {
+                    for i in 0..20 {
+                        thread::sleep(ms(5));
+                        s.send(i).unwrap();
+                    }
+                });
+
+                for i in 0..20 {
+                    assert_eq!(r.recv(), Ok(i));
+                }
+            });
+        }
+    }
+}
+
+#[test]
+fn fast_after_slow() {
+    const COUNT: usize = 10_000;
+
+    for (s, r) in channels(true) {
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                // Teach the channel that waits are long.
+                for i in 0..20 {
+                    thread::sleep(ms(5));
+                    s.send(i).unwrap();
+                }
+                // Then flood it with messages.
+                for i in 20..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+
+            for i in 0..COUNT {
+                assert_eq!(r.recv(), Ok(i));
+            }
+        });
+    }
+}
+
+#[test]
+fn timeouts() {
+    for &adaptive in &[true, false] {
+        for (_s, r) in channels(adaptive) {
+            for _ in 0..5 {
+                let start = Instant::now();
+                assert_eq!(r.recv_timeout(ms(20)), Err(RecvTimeoutError::Timeout));
+                assert!(start.elapsed() >= ms(20));
+            }
+        }
+    }
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &adaptive in &[true, false] {
+        for (s, r) in channels(adaptive) {
+            crossbeam::scope(|scope| {
+                for _ in 0..THREADS {
+                    scope.spawn(|| {
+                        for i in 0..COUNT {
+                            s.send(i).unwrap();
+                        }
+                    });
+                    scope.spawn(|| {
+                        for _ in 0..COUNT {
+                            r.recv().unwrap();
+                        }
+                    });
+                }
+            });
+            assert!(r.is_empty());
+        }
+    }
+}
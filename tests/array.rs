@@ -10,7 +10,7 @@ use std::sync::atomic::Ordering;
 use std::thread;
 use std::time::Duration;
 
-use crossbeam_channel::{bounded};
+use crossbeam_channel::{bounded, Builder, Displace};
 use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
 use crossbeam_channel::{SendError, SendTimeoutError, TrySendError};
 use rand::{thread_rng, Rng};
@@ -141,6 +141,329 @@ fn recv_timeout() {
     });
 }
 
+#[test]
+fn recv_where() {
+    let (s, r) = bounded(4);
+
+    for i in 0..4 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+    assert_eq!(r.len(), 3);
+
+    // Set-aside messages count against the capacity until they are received.
+    assert_eq!(s.try_send(4), Ok(()));
+    assert_eq!(s.try_send(5), Err(TrySendError::Full(5)));
+    assert_eq!(r.recv(), Ok(0));
+    assert_eq!(s.try_send(5), Ok(()));
+    assert_eq!(s.try_send(6), Err(TrySendError::Full(6)));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.try_send(6), Ok(()));
+    assert_eq!(s.try_send(7), Err(TrySendError::Full(7)));
+    assert_eq!(r.len(), 4);
+
+    let v: Vec<_> = r.try_iter().collect();
+    assert_eq!(v, [3, 4, 5, 6]);
+
+    assert_eq!(
+        r.recv_timeout_where(|&x| x > 10, ms(100)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    s.send(11).unwrap();
+    drop(s);
+    assert_eq!(r.recv_where(|&x| x > 10), Ok(11));
+    assert_eq!(r.recv_where(|&x| x > 10), Err(RecvError));
+}
+
+#[test]
+fn recv_where_backpressure() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    assert_eq!(
+        r.recv_timeout_where(|&x| x == 2, ms(100)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    assert_eq!(s.send_timeout(2, ms(100)), Err(SendTimeoutError::Timeout(2)));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        // Blocks until the set-aside message is received.
+        s.send(2).unwrap();
+    });
+    assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+}
+
+#[test]
+fn put_back_doesnt_hold_capacity() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    drop(r.recv_guard().unwrap());
+    assert_eq!(s.try_send(2), Ok(()));
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+    assert_eq!(r.len(), 2);
+
+    // A set-aside message holds capacity even behind a message that was put back.
+    assert_eq!(
+        r.recv_timeout_where(|&x| x == 3, ms(100)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(s.try_send(3), Ok(()));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [3]);
+}
+
+#[test]
+fn recv_where_select() {
+    let (s, r) = bounded(10);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            select! {
+                recv(r) -> v => assert_eq!(v, Ok(1)),
+            }
+        });
+
+        // Wait for the selecting thread to receive the first message, then set the second one
+        // aside.
+        thread::sleep(ms(500));
+        assert_eq!(
+            r.recv_timeout_where(|&x| x == 3, ms(100)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    });
+
+    let mut chunks = r.chunks(10);
+    assert_eq!(chunks.next(), Some(vec![2]));
+}
+
+
+#[test]
+fn recv_where_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded::<usize>(3);
+    let evens = AtomicUsize::new(0);
+    let others = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            let s = s.clone();
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+            scope.spawn(|| {
+                while let Ok(x) = r.recv_where(|x| x % 2 == 0) {
+                    assert_eq!(x % 2, 0);
+                    evens.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+            scope.spawn(|| {
+                while r.recv().is_ok() {
+                    others.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        drop(s);
+    });
+
+    let total = evens.load(Ordering::SeqCst) + others.load(Ordering::SeqCst);
+    assert_eq!(total, COUNT * THREADS);
+    assert!(r.is_empty());
+}
+
+#[test]
+fn recv_latest() {
+    let (s, r) = bounded(5);
+    assert_eq!(r.try_recv_latest(), Err(TryRecvError::Empty));
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.try_recv_latest(), Ok((4, 4)));
+    assert!(r.is_empty());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv_latest(), Ok((7, 0)));
+            thread::sleep(ms(1000));
+            assert_eq!(r.recv_latest(), Ok((10, 2)));
+            assert_eq!(r.recv_latest(), Err(RecvError));
+        });
+        scope.spawn(move || {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+            thread::sleep(ms(100));
+            s.send(8).unwrap();
+            s.send(9).unwrap();
+            s.send(10).unwrap();
+        });
+    });
+}
+
+#[test]
+fn send_lossy() {
+    let (s, r) = bounded(3);
+    for i in 0..3 {
+        assert_eq!(s.send_lossy(i), Ok(None));
+    }
+    assert_eq!(s.send_lossy(3), Ok(Some(2)));
+    assert_eq!(s.send_lossy(4), Ok(Some(3)));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 1, 4]);
+
+    let (s, r) = Builder::new().displace(Displace::Oldest).bounded(3);
+    for i in 0..3 {
+        assert_eq!(s.send_lossy(i), Ok(None));
+    }
+    for i in 3..10 {
+        assert_eq!(s.send_lossy(i), Ok(Some(i - 3)));
+    }
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [7, 8, 9]);
+
+    // A single slot is both the newest and the oldest.
+    for &displace in &[Displace::Newest, Displace::Oldest] {
+        let (s, r) = Builder::new().displace(displace).bounded(1);
+        assert_eq!(s.send_lossy(1), Ok(None));
+        assert_eq!(s.send_lossy(2), Ok(Some(1)));
+        assert_eq!(r.try_recv(), Ok(2));
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    drop(r);
+    assert_eq!(s.send_lossy(5), Err(SendError(5)));
+}
+
+#[test]
+fn send_lossy_reserved() {
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    let permit = s.reserve().unwrap();
+
+    // Permits hold capacity rather than messages, so the newest queued message is displaced.
+    assert_eq!(s.send_lossy(2), Ok(Some(1)));
+    permit.send(3).unwrap();
+    assert_eq!(s.send_lossy(4), Ok(Some(3)));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(4));
+
+    // If only permits hold capacity, the new message itself is displaced.
+    let p1 = s.reserve().unwrap();
+    let p2 = s.reserve().unwrap();
+    assert_eq!(s.send_lossy(5), Ok(Some(5)));
+    drop(p1);
+    assert_eq!(s.send_lossy(6), Ok(None));
+    p2.send(7).unwrap();
+    assert_eq!(r.recv(), Ok(6));
+    assert_eq!(r.recv(), Ok(7));
+
+    let (s, r) = Builder::new().displace(Displace::Oldest).bounded(2);
+    let permit = s.reserve().unwrap();
+    s.send(1).unwrap();
+
+    // The message sent through the permit is queued after the one that displaced the oldest.
+    assert_eq!(s.send_lossy(2), Ok(Some(1)));
+    permit.send(3).unwrap();
+    assert_eq!(s.send_lossy(4), Ok(Some(2)));
+    assert_eq!(r.recv(), Ok(3));
+    assert_eq!(r.recv(), Ok(4));
+}
+
+#[test]
+fn send_lossy_set_aside() {
+    let (s, r) = bounded(3);
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(
+        r.recv_timeout_where(|_| false, ms(100)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    assert_eq!(s.send_lossy(3), Ok(Some(2)));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 1, 3]);
+
+    // Some messages are set aside and the rest are in the buffer.
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.recv_where(|&x| x == 1), Ok(1));
+    s.send(3).unwrap();
+    assert_eq!(s.send_lossy(4), Ok(Some(3)));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 2, 4]);
+
+    let (s, r) = Builder::new().displace(Displace::Oldest).bounded(2);
+    s.send(0).unwrap();
+    s.send(1).unwrap();
+    assert_eq!(
+        r.recv_timeout_where(|_| false, ms(100)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    assert_eq!(s.send_lossy(2), Ok(Some(0)));
+    assert_eq!(s.send_lossy(3), Ok(Some(1)));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 3]);
+}
+
+#[test]
+fn send_lossy_stress() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &displace in &[Displace::Newest, Displace::Oldest] {
+        for &cap in &[1, 2, 5] {
+            let (s, r) = Builder::new().displace(displace).bounded(cap);
+            let seen = (0..COUNT * THREADS)
+                .map(|_| AtomicUsize::new(0))
+                .collect::<Vec<_>>();
+
+            crossbeam::scope(|scope| {
+                for t in 0..THREADS {
+                    let s = s.clone();
+                    let seen = &seen;
+                    scope.spawn(move || {
+                        for i in 0..COUNT {
+                            if let Some(old) = s.send_lossy(t * COUNT + i).unwrap() {
+                                seen[old].fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                    });
+                }
+                for _ in 0..THREADS {
+                    let r = r.clone();
+                    let seen = &seen;
+                    scope.spawn(move || {
+                        let mut last = vec![None; THREADS];
+                        for msg in r.iter() {
+                            seen[msg].fetch_add(1, Ordering::SeqCst);
+
+                            // Messages from the same sender are received in order.
+                            let t = msg / COUNT;
+                            assert!(last[t].map_or(true, |l| l < msg));
+                            last[t] = Some(msg);
+                        }
+                    });
+                }
+                drop(s);
+            });
+
+            // Every message was either received or displaced, exactly once.
+            for c in &seen {
+                assert_eq!(c.load(Ordering::SeqCst), 1);
+            }
+        }
+    }
+}
+
 #[test]
 fn try_send() {
     let (s, r) = bounded(1);
@@ -213,6 +536,34 @@ fn send_timeout() {
     });
 }
 
+#[test]
+fn spin_recv_send() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.spin_recv(0), Ok(7));
+            assert_eq!(r.spin_recv(1_000_000), Ok(8));
+            thread::sleep(ms(1000));
+            assert_eq!(r.spin_recv(10), Ok(9));
+            assert_eq!(r.spin_recv(10), Ok(10));
+            assert_eq!(r.spin_recv(10), Err(RecvError));
+        });
+        scope.spawn(|| {
+            s.spin_send(7, 0).unwrap();
+            s.spin_send(8, 1_000_000).unwrap();
+            s.spin_send(9, 10).unwrap();
+            // Blocks until the receiver wakes up.
+            s.spin_send(10, 10).unwrap();
+            drop(s);
+        });
+    });
+
+    let (s, r) = bounded(1);
+    drop(r);
+    assert_eq!(s.spin_send(1, 10), Err(SendError(1)));
+}
+
 #[test]
 fn send_after_disconnect() {
     let (s, r) = bounded(100);
@@ -228,6 +579,146 @@ fn send_after_disconnect() {
     assert_eq!(s.send_timeout(6, ms(500)), Err(SendTimeoutError::Disconnected(6)));
 }
 
+#[test]
+fn try_reserve() {
+    let (s, r) = bounded(2);
+
+    let p1 = s.try_reserve().unwrap();
+    s.send(1).unwrap();
+    assert!(s.is_full());
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Full(()));
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+
+    // The permit holds capacity, not a position in the queue.
+    assert_eq!(s.len(), 1);
+    assert_eq!(r.try_recv(), Ok(1));
+    p1.send(3).unwrap();
+    assert_eq!(s.len(), 1);
+    assert_eq!(r.try_recv(), Ok(3));
+
+    let p2 = s.try_reserve().unwrap();
+    drop(p2);
+    assert_eq!(s.try_send(4), Ok(()));
+    assert_eq!(s.try_send(5), Ok(()));
+
+    drop(r);
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Disconnected(()));
+}
+
+#[test]
+fn reserve() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            let p = s.reserve().unwrap();
+            thread::sleep(ms(1000));
+            p.send(7).unwrap();
+            let p = s.reserve().unwrap();
+            p.send(8).unwrap();
+            let p = s.reserve().unwrap();
+            thread::sleep(ms(1000));
+            drop(p);
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            // The permit holds the capacity, so ordinary sends can't take it.
+            assert_eq!(s.try_send(0), Err(TrySendError::Full(0)));
+            assert_eq!(r.recv(), Ok(7));
+            assert_eq!(r.recv(), Ok(8));
+            // Blocks until the permit is dropped.
+            assert_eq!(s.send_timeout(9, ms(2000)), Ok(()));
+            assert_eq!(r.recv(), Ok(9));
+        });
+    });
+}
+
+#[test]
+fn reserve_order() {
+    let (s, r) = bounded(3);
+
+    let p = s.reserve().unwrap();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert!(s.is_full());
+
+    // Messages are queued in the order they are sent, not reserved.
+    assert_eq!(r.recv(), Ok(1));
+    p.send(0).unwrap();
+    s.send(3).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(0));
+    assert_eq!(r.recv(), Ok(3));
+}
+
+#[test]
+fn reserve_doesnt_hold_up_receivers() {
+    let (s, r) = bounded(4);
+    let s2 = s.clone();
+
+    let permit = s.reserve().unwrap();
+    s2.send(1).unwrap();
+    s2.send(2).unwrap();
+
+    assert_eq!(s.len(), 2);
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.recv_timeout(ms(500)), Ok(2));
+
+    permit.send(3).unwrap();
+    assert_eq!(r.try_recv(), Ok(3));
+}
+
+#[test]
+fn reserve_after_disconnect() {
+    let (s, r) = bounded(1);
+
+    let p = s.reserve().unwrap();
+    drop(r);
+
+    assert_eq!(p.send(1), Err(SendError(1)));
+    assert_eq!(s.reserve().unwrap_err(), SendError(()));
+}
+
+#[test]
+fn reserve_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded::<usize>(3);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..COUNT {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for t in 0..THREADS {
+            let s = &s;
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    // Mix reserved and ordinary sends, and sometimes give reservations back.
+                    match (i + t) % 3 {
+                        0 => s.send(i).unwrap(),
+                        1 => s.reserve().unwrap().send(i).unwrap(),
+                        _ => {
+                            drop(s.reserve().unwrap());
+                            s.reserve().unwrap().send(i).unwrap();
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
+
 #[test]
 fn recv_after_disconnect() {
     let (s, r) = bounded(100);
@@ -0,0 +1,370 @@
+//! Tests for the `Builder` struct.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded, Builder, DropPolicy};
+use crossbeam_channel::{RecvError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = Builder::new().bounded(1);
+    s.send(7).unwrap();
+    assert_eq!(r.recv(), Ok(7));
+
+    let (s, r) = Builder::new().bounded(0);
+    thread::spawn(move || s.send(8).unwrap());
+    assert_eq!(r.recv(), Ok(8));
+
+    let (s, r) = Builder::new().unbounded();
+    s.send(9).unwrap();
+    assert_eq!(r.recv(), Ok(9));
+}
+
+#[test]
+fn discard() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    for cap in vec![None, Some(10)] {
+        DROPS.store(0, Ordering::SeqCst);
+
+        let builder = Builder::new().drop_policy(DropPolicy::Discard);
+        let (s, r) = match cap {
+            None => builder.unbounded(),
+            Some(cap) => builder.bounded(cap),
+        };
+
+        for _ in 0..5 {
+            s.send(DropCounter).unwrap();
+        }
+
+        // Messages are dropped together with the channel, once the last sender is gone too.
+        let r2 = r.clone();
+        drop(r);
+        drop(r2);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+        drop(s);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+    }
+}
+
+#[test]
+fn handler() {
+    for cap in vec![None, Some(10)] {
+        let lost = Arc::new(Mutex::new(Vec::new()));
+        let l = lost.clone();
+
+        let builder = Builder::new()
+            .drop_policy(DropPolicy::Handler(Box::new(move |m| l.lock().unwrap().push(m))));
+        let (s, r) = match cap {
+            None => builder.unbounded(),
+            Some(cap) => builder.bounded(cap),
+        };
+
+        for i in 0..5 {
+            s.send(i).unwrap();
+        }
+        assert_eq!(r.recv(), Ok(0));
+
+        drop(r);
+        assert_eq!(*lost.lock().unwrap(), [1, 2, 3, 4]);
+        assert!(s.send(5).is_err());
+
+        drop(s);
+        assert_eq!(*lost.lock().unwrap(), [1, 2, 3, 4]);
+    }
+}
+
+#[test]
+fn forward() {
+    let (dead_s, dead_r) = unbounded();
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Forward(dead_s))
+        .bounded(10);
+
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    drop(r);
+
+    assert_eq!(dead_r.try_iter().collect::<Vec<_>>(), [0, 1, 2]);
+    assert_eq!(dead_r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(s.lost_on_disconnect(), 0);
+
+    // The dead-letter sender is dropped together with the channel.
+    drop(s);
+    assert_eq!(dead_r.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn forward_disconnected() {
+    let (dead_s, dead_r) = bounded(1);
+    drop(dead_r);
+
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Forward(dead_s))
+        .unbounded();
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    drop(r);
+    assert_eq!(s.lost_on_disconnect(), 2);
+    drop(s);
+}
+
+#[test]
+fn forward_full() {
+    let (dead_s, dead_r) = bounded(2);
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Forward(dead_s))
+        .unbounded();
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+
+    // Dropping the receiver doesn't block on the full dead-letter channel.
+    drop(r);
+    assert_eq!(dead_r.try_iter().collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(s.lost_on_disconnect(), 3);
+    drop(s);
+}
+
+#[test]
+fn handler_panic() {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let h = handled.clone();
+
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Handler(Box::new(move |m| {
+            h.fetch_add(1, Ordering::SeqCst);
+            if m == 1 {
+                panic!("handler panicked");
+            }
+        })))
+        .unbounded();
+
+    for i in 0..4 {
+        s.send(i).unwrap();
+    }
+
+    // The panic is swallowed, and every message still reaches the handler.
+    drop(r);
+    assert_eq!(handled.load(Ordering::SeqCst), 4);
+    assert!(s.send(4).is_err());
+}
+
+#[test]
+fn handler_panic_while_unwinding() {
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Handler(Box::new(|_| panic!("handler panicked"))))
+        .unbounded();
+    s.send(1).unwrap();
+
+    // The receiver is dropped during unwinding, so the handler's panic is swallowed rather than
+    // aborting the process.
+    let res = thread::spawn(move || {
+        let _r = r;
+        panic!("thread panicked");
+    })
+    .join();
+    assert!(res.is_err());
+}
+
+#[test]
+fn sender_drop() {
+    let (dead_s, dead_r) = unbounded();
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Forward(dead_s.clone()))
+        .unbounded();
+
+    s.send(1).unwrap();
+    drop(s);
+
+    // Without `drain_on_sender_drop`, remaining messages can still be received.
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(dead_r.try_recv(), Err(TryRecvError::Empty));
+
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Forward(dead_s))
+        .drain_on_sender_drop(true)
+        .unbounded();
+
+    s.send(2).unwrap();
+    s.send(3).unwrap();
+    drop(s);
+
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(dead_r.try_iter().collect::<Vec<_>>(), [2, 3]);
+}
+
+#[test]
+fn concurrent_senders() {
+    const COUNT: usize = 10_000;
+
+    let lost = Arc::new(AtomicUsize::new(0));
+    let l = lost.clone();
+
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Handler(Box::new(move |_| {
+            l.fetch_add(1, Ordering::SeqCst);
+        })))
+        .bounded(100);
+
+    let sent = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..4 {
+            let s = s.clone();
+            let sent = &sent;
+            scope.spawn(move || {
+                for _ in 0..COUNT {
+                    if s.send(()).is_ok() {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        }
+
+        scope.spawn(move || {
+            thread::sleep(ms(10));
+            for _ in 0..COUNT {
+                r.recv().unwrap();
+            }
+        });
+    });
+    drop(s);
+
+    // Every message was either received or passed to the handler.
+    assert_eq!(
+        sent.load(Ordering::SeqCst),
+        COUNT + lost.load(Ordering::SeqCst)
+    );
+}
+
+#[test]
+fn forward_shutdown() {
+    const COUNT: usize = 10_000;
+
+    for &drain_on_sender_drop in &[false, true] {
+        let (dead_s, dead_r) = unbounded();
+        let (s, r) = Builder::new()
+            .drop_policy(DropPolicy::Forward(dead_s))
+            .drain_on_sender_drop(drain_on_sender_drop)
+            .bounded(100);
+
+        let returned = AtomicUsize::new(0);
+        let received = AtomicUsize::new(0);
+
+        crossbeam::scope(|scope| {
+            for _ in 0..4 {
+                let s = s.clone();
+                let returned = &returned;
+                scope.spawn(move || {
+                    for _ in 0..COUNT {
+                        if s.send(()).is_err() {
+                            returned.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                });
+            }
+
+            let received = &received;
+            scope.spawn(move || {
+                for _ in 0..COUNT {
+                    r.recv().unwrap();
+                    received.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        });
+        drop(s);
+
+        // Every message was received, handed back to its sender, or forwarded.
+        assert_eq!(
+            received.load(Ordering::SeqCst)
+                + returned.load(Ordering::SeqCst)
+                + dead_r.try_iter().count(),
+            4 * COUNT
+        );
+    }
+}
+
+#[test]
+fn pending_on_disconnect() {
+    for cap in vec![None, Some(0), Some(10)] {
+        let (s, r) = match cap {
+            None => unbounded(),
+            Some(cap) => bounded(cap),
+        };
+
+        for i in 0..cap.unwrap_or(5) {
+            s.send(i).unwrap();
+        }
+        assert_eq!(s.pending_on_disconnect(), None);
+
+        let r2 = r.clone();
+        drop(r);
+        assert_eq!(s.pending_on_disconnect(), None);
+        drop(r2);
+
+        assert_eq!(s.pending_on_disconnect(), Some(cap.unwrap_or(5)));
+        assert!(s.send(100).is_err());
+        assert_eq!(s.pending_on_disconnect(), Some(cap.unwrap_or(5)));
+    }
+}
+
+#[test]
+fn pending_on_disconnect_concurrent() {
+    const COUNT: usize = 10_000;
+
+    let (s, r) = bounded(100);
+    let sent = AtomicUsize::new(0);
+    let received = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..4 {
+            let s = s.clone();
+            let sent = &sent;
+            scope.spawn(move || {
+                for _ in 0..COUNT {
+                    if s.send(()).is_ok() {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        }
+
+        let received = &received;
+        scope.spawn(move || {
+            for _ in 0..COUNT {
+                r.recv().unwrap();
+                received.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+    });
+
+    // Every sent message was either received or lost on disconnection.
+    assert_eq!(
+        sent.load(Ordering::SeqCst),
+        received.load(Ordering::SeqCst) + s.pending_on_disconnect().unwrap()
+    );
+}
@@ -0,0 +1,232 @@
+//! Tests for `Sender::send_all` and `Receiver::collect_timeout`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::collections::{HashSet, VecDeque};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, bounded_by_weight, lanes, never, unbounded};
+use crossbeam_channel::{SendError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn send_all() {
+    let (s, r) = unbounded();
+    assert_eq!(s.send_all(0..100), Ok(()));
+    assert_eq!(s.send_all(Vec::new()), Ok(()));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+
+    let (s, r) = lanes(10, 2);
+    assert_eq!(s.send_all(vec!["a", "b"]), Ok(()));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), ["a", "b"]);
+}
+
+#[test]
+fn send_all_blocks() {
+    let (s, r) = bounded(2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(r.iter().take(10).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+        });
+
+        let start = Instant::now();
+        assert_eq!(s.send_all(0..10), Ok(()));
+        assert!(start.elapsed() >= ms(100));
+    });
+
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..5 {
+                assert_eq!(r.recv(), Ok(i));
+            }
+        });
+
+        assert_eq!(s.send_all(0..5), Ok(()));
+    });
+}
+
+#[test]
+fn send_all_disconnected() {
+    let (s, r) = bounded(2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv(), Ok(0));
+            thread::sleep(ms(100));
+            drop(r);
+        });
+
+        let mut iter = 0..10;
+        assert_eq!(s.send_all(iter.by_ref()), Err(SendError(vec![3])));
+        assert_eq!(iter.next(), Some(4));
+    });
+}
+
+#[test]
+fn send_all_disconnected_returns_unsent() {
+    const COUNT: usize = 100_000;
+
+    for cap in vec![Some(10), Some(1000), None] {
+        let (s, r) = match cap {
+            Some(cap) => bounded(cap),
+            None => unbounded(),
+        };
+
+        crossbeam::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..1000 {
+                    assert_eq!(r.recv(), Ok(i));
+                }
+            });
+
+            // Every message taken from the iterator is either sent or handed back.
+            let mut iter = 0..COUNT;
+            match s.send_all(iter.by_ref()) {
+                Ok(()) => assert_eq!(iter.next(), None),
+                Err(SendError(unsent)) => {
+                    assert!(!unsent.is_empty());
+                    let first = unsent[0];
+                    assert!(first >= 1000);
+                    assert_eq!(unsent, (first..first + unsent.len()).collect::<Vec<_>>());
+                    let next = first + unsent.len();
+                    assert_eq!(iter.next(), if next < COUNT { Some(next) } else { None });
+                }
+            }
+        });
+    }
+}
+
+#[test]
+fn send_all_batches() {
+    const COUNT: usize = 1000;
+
+    for (s, r) in vec![bounded(7), lanes(5, 2), unbounded()] {
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                assert_eq!(
+                    r.iter().take(COUNT).collect::<Vec<_>>(),
+                    (0..COUNT).collect::<Vec<_>>()
+                );
+            });
+
+            assert_eq!(s.send_all(0..COUNT), Ok(()));
+        });
+    }
+}
+
+#[test]
+fn send_all_leaves_room_for_permits() {
+    let (s, r) = bounded(4);
+    let permit = s.try_reserve().unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(r.recv(), Ok(0));
+        });
+
+        // Only three messages fit in, so the fourth one waits for a receiver.
+        let start = Instant::now();
+        assert_eq!(s.send_all(0..4), Ok(()));
+        assert!(start.elapsed() >= ms(100));
+    });
+
+    assert_eq!(s.try_send(9), Err(TrySendError::Full(9)));
+    assert_eq!(permit.send(4), Ok(()));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn send_all_by_weight() {
+    let (s, r) = bounded_by_weight(10, |v: &Vec<u8>| v.len());
+    assert_eq!(s.send_all(vec![vec![0; 4], vec![1; 4], vec![2; 4]]), Ok(()));
+    assert_eq!(s.len(), 3);
+    assert_eq!(s.try_send(vec![3]), Err(TrySendError::Full(vec![3])));
+    assert_eq!(r.try_iter().count(), 3);
+}
+
+#[test]
+fn collect_timeout_queued() {
+    let (s, r) = unbounded();
+    s.send_all(0..1000).unwrap();
+
+    let start = Instant::now();
+    let v = r.collect_timeout::<Vec<_>>(ms(100));
+    assert_eq!(v, (0..1000).collect::<Vec<_>>());
+    assert!(start.elapsed() >= ms(100));
+
+    s.send_all(0..5).unwrap();
+    drop(s);
+    let set = r.collect_timeout::<HashSet<_>>(ms(1000));
+    assert_eq!(set, (0..5).collect());
+}
+
+#[test]
+fn collect_timeout_disconnected() {
+    let (s, r) = bounded(3);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..10 {
+                thread::sleep(ms(10));
+                s.send(i).unwrap();
+            }
+            drop(s);
+        });
+
+        let start = Instant::now();
+        let v = r.collect_timeout::<VecDeque<_>>(ms(5000));
+        assert_eq!(v, (0..10).collect::<VecDeque<_>>());
+        assert!(start.elapsed() < ms(2000));
+    });
+}
+
+#[test]
+fn collect_timeout_elapses() {
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.send(1).unwrap();
+            thread::sleep(ms(300));
+            s.send(2).unwrap();
+        });
+
+        assert_eq!(r.collect_timeout::<Vec<_>>(ms(150)), [1]);
+        assert_eq!(r.collect_timeout::<Vec<_>>(ms(1000)), [2]);
+    });
+
+    let r = never::<i32>();
+    assert!(r.collect_timeout::<Vec<_>>(ms(50)).is_empty());
+    assert!(r.collect_timeout::<Vec<_>>(ms(0)).is_empty());
+}
+
+#[test]
+fn collect_timeout_busy_channel() {
+    let (s, r) = unbounded();
+    let done = after(ms(500));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            // Keep the channel from ever being empty for long.
+            while done.try_recv().is_err() {
+                if s.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let start = Instant::now();
+        assert!(!r.collect_timeout::<Vec<_>>(ms(100)).is_empty());
+        assert!(start.elapsed() < ms(400));
+    });
+}
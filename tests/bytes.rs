@@ -0,0 +1,157 @@
+//! Tests for byte channels.
+
+#![cfg(feature = "io")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::io::{BufRead, ErrorKind, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::io::byte_channel;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (mut s, mut r) = byte_channel(16);
+    s.write_all(b"hello").unwrap();
+    assert_eq!(s.len(), 5);
+
+    let mut buf = [0; 10];
+    assert_eq!(r.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert!(r.is_empty());
+}
+
+#[test]
+fn partial_write() {
+    let (mut s, mut r) = byte_channel(4);
+    assert_eq!(s.write(b"abcdef").unwrap(), 4);
+    assert!(s.is_full());
+
+    let mut buf = [0; 3];
+    assert_eq!(r.read(&mut buf).unwrap(), 3);
+    assert_eq!(&buf, b"abc");
+    assert_eq!(s.write(b"ef").unwrap(), 2);
+
+    let mut buf = [0; 3];
+    r.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"def");
+}
+
+#[test]
+fn eof() {
+    let (mut s, mut r) = byte_channel(16);
+    s.write_all(b"abc").unwrap();
+    drop(s);
+
+    let mut v = Vec::new();
+    assert_eq!(r.read_to_end(&mut v).unwrap(), 3);
+    assert_eq!(v, b"abc");
+
+    let mut buf = [0; 4];
+    assert_eq!(r.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn broken_pipe() {
+    let (mut s, r) = byte_channel(4);
+    s.write_all(b"ab").unwrap();
+    drop(r);
+
+    assert_eq!(s.write(b"c").unwrap_err().kind(), ErrorKind::BrokenPipe);
+    assert_eq!(s.write(b"").unwrap_err().kind(), ErrorKind::BrokenPipe);
+    assert!(s.is_empty());
+}
+
+#[test]
+fn write_blocks_until_read() {
+    let (mut s, mut r) = byte_channel(2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            let mut buf = [0; 2];
+            r.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"ab");
+        });
+
+        s.write_all(b"abcd").unwrap();
+    });
+    assert_eq!(s.len(), 2);
+}
+
+#[test]
+fn buf_read() {
+    let (mut s, mut r) = byte_channel(64);
+    s.write_all(b"first line\nsecond").unwrap();
+
+    let mut line = String::new();
+    r.read_line(&mut line).unwrap();
+    assert_eq!(line, "first line\n");
+
+    // The rest was taken out of the channel, but can still be read.
+    assert!(r.is_empty());
+    assert_eq!(r.fill_buf().unwrap(), b"second");
+    r.consume(3);
+
+    let mut buf = [0; 8];
+    assert_eq!(r.read(&mut buf).unwrap(), 3);
+    assert_eq!(&buf[..3], b"ond");
+}
+
+#[test]
+fn multiple_senders() {
+    let (s, mut r) = byte_channel(8);
+
+    crossbeam::scope(|scope| {
+        for b in b"abcd" {
+            let mut s = s.clone();
+            scope.spawn(move || {
+                for _ in 0..100 {
+                    s.write_all(&[*b; 3]).unwrap();
+                }
+            });
+        }
+        drop(s);
+
+        let mut v = Vec::new();
+        r.read_to_end(&mut v).unwrap();
+        assert_eq!(v.len(), 1200);
+        for b in b"abcd" {
+            assert_eq!(v.iter().filter(|x| *x == b).count(), 300);
+        }
+    });
+}
+
+#[test]
+fn stream() {
+    const COUNT: usize = 100_000;
+
+    let (mut s, mut r) = byte_channel(100);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s.write_all(&[(i % 251) as u8]).unwrap();
+            }
+        });
+
+        let mut v = Vec::new();
+        r.read_to_end(&mut v).unwrap();
+        assert_eq!(v.len(), COUNT);
+        for (i, b) in v.into_iter().enumerate() {
+            assert_eq!(b, (i % 251) as u8);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "capacity must be positive")]
+fn zero_capacity() {
+    byte_channel(0);
+}
@@ -0,0 +1,230 @@
+//! Tests for cancellable blocking operations.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, never, tick, unbounded};
+use crossbeam_channel::{CancelToken, RecvCancelError, SendCancelError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let token = CancelToken::new();
+    assert!(!token.is_cancelled());
+
+    let (s, r) = unbounded();
+    s.send_cancellable(7, &token).unwrap();
+    assert_eq!(r.recv_cancellable(&token), Ok(7));
+
+    token.cancel();
+    assert!(token.is_cancelled());
+    assert_eq!(s.send_cancellable(8, &token), Err(SendCancelError::Cancelled(8)));
+
+    s.send(9).unwrap();
+    assert_eq!(r.recv_cancellable(&token), Err(RecvCancelError::Cancelled));
+    assert_eq!(r.recv(), Ok(9));
+}
+
+#[test]
+fn cancel_twice() {
+    let token = CancelToken::new();
+    token.cancel();
+    token.cancel();
+    assert!(token.is_cancelled());
+    assert!(token.clone().is_cancelled());
+}
+
+#[test]
+fn wakes_receiver() {
+    for &cap in &[None, Some(0), Some(1)] {
+        let (s, r) = match cap {
+            None => unbounded::<i32>(),
+            Some(cap) => bounded(cap),
+        };
+        let token = CancelToken::new();
+
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                let now = Instant::now();
+                assert_eq!(r.recv_cancellable(&token), Err(RecvCancelError::Cancelled));
+                assert!(now.elapsed() >= ms(100));
+            });
+            scope.spawn(|| {
+                thread::sleep(ms(100));
+                token.cancel();
+            });
+        });
+        drop(s);
+    }
+}
+
+#[test]
+fn wakes_sender() {
+    for &cap in &[0, 1] {
+        let (s, r) = bounded(cap);
+        let token = CancelToken::new();
+        if cap > 0 {
+            s.send(0).unwrap();
+        }
+
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                let now = Instant::now();
+                assert_eq!(s.send_cancellable(1, &token), Err(SendCancelError::Cancelled(1)));
+                assert!(now.elapsed() >= ms(100));
+            });
+            scope.spawn(|| {
+                thread::sleep(ms(100));
+                token.cancel();
+            });
+        });
+        assert_eq!(r.try_iter().count(), cap);
+    }
+}
+
+#[test]
+fn disconnected() {
+    let token = CancelToken::new();
+
+    let (s, r) = bounded::<i32>(0);
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv_cancellable(&token), Err(RecvCancelError::Disconnected));
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            drop(s);
+        });
+    });
+
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(s.send_cancellable(2, &token), Err(SendCancelError::Disconnected(2)));
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            drop(r);
+        });
+    });
+}
+
+#[test]
+fn rendezvous() {
+    let (s, r) = bounded(0);
+    let token = CancelToken::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..100 {
+                s.send_cancellable(i, &token).unwrap();
+            }
+        });
+        scope.spawn(|| {
+            for i in 0..100 {
+                assert_eq!(r.recv_cancellable(&token), Ok(i));
+            }
+        });
+    });
+}
+
+#[test]
+fn timers() {
+    let token = CancelToken::new();
+
+    let r = after(ms(50));
+    assert!(r.recv_cancellable(&token).is_ok());
+
+    let r = tick(ms(50));
+    assert!(r.recv_cancellable(&token).is_ok());
+
+    let r = never::<i32>();
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv_cancellable(&token), Err(RecvCancelError::Cancelled));
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            token.cancel();
+        });
+    });
+}
+
+#[test]
+fn shared_token() {
+    const THREADS: usize = 8;
+
+    let (s, _r) = bounded::<i32>(0);
+    let (_s, r) = bounded::<i32>(0);
+    let token = CancelToken::new();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS / 2 {
+            scope.spawn(|| {
+                assert_eq!(r.recv_cancellable(&token), Err(RecvCancelError::Cancelled));
+            });
+            scope.spawn(|| {
+                assert_eq!(s.send_cancellable(1, &token), Err(SendCancelError::Cancelled(1)));
+            });
+        }
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            token.cancel();
+        });
+    });
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+
+    for &cap in &[0, 1, 10] {
+        let (s, r) = bounded(cap);
+        let token = CancelToken::new();
+        let sent = AtomicUsize::new(0);
+        let received = AtomicUsize::new(0);
+
+        crossbeam::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for i in 0..COUNT {
+                        match s.send_cancellable(i, &token) {
+                            Ok(()) => sent.fetch_add(1, Ordering::SeqCst),
+                            Err(SendCancelError::Cancelled(msg)) => {
+                                assert_eq!(msg, i);
+                                break;
+                            }
+                            Err(SendCancelError::Disconnected(_)) => panic!(),
+                        };
+                    }
+                });
+                scope.spawn(|| loop {
+                    match r.recv_cancellable(&token) {
+                        Ok(_) => received.fetch_add(1, Ordering::SeqCst),
+                        Err(RecvCancelError::Cancelled) => break,
+                        Err(RecvCancelError::Disconnected) => panic!(),
+                    };
+                });
+            }
+            scope.spawn(|| {
+                thread::sleep(ms(50));
+                token.cancel();
+            });
+        });
+
+        // Every message that was sent has either been received or is still in the channel.
+        let left = r.try_iter().count();
+        assert_eq!(
+            sent.load(Ordering::SeqCst),
+            received.load(Ordering::SeqCst) + left,
+        );
+    }
+}
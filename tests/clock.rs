@@ -0,0 +1,171 @@
+//! Tests for timer channels with custom clocks.
+
+#[macro_use]
+extern crate crossbeam_channel;
+extern crate crossbeam;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after_with_clock, tick_with_clock, unbounded};
+use crossbeam_channel::{Clock, ManualClock, Select, SystemClock};
+use crossbeam_channel::{RecvTimeoutError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// A clock that runs a fixed amount of time ahead of the system clock.
+struct Ahead(Duration);
+
+impl Clock for Ahead {
+    fn now(&self) -> Instant {
+        Instant::now() + self.0
+    }
+}
+
+#[test]
+fn manual_after() {
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let r = after_with_clock(Duration::from_secs(3600), clock.clone());
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert!(r.is_empty());
+
+    clock.advance(Duration::from_secs(3599));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(r.len(), 1);
+    assert_eq!(r.try_recv(), Ok(start + Duration::from_secs(3600)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn manual_tick() {
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let r = tick_with_clock(Duration::from_secs(60), clock.clone());
+
+    for i in 1..=10 {
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(r.try_recv(), Ok(start + Duration::from_secs(60 * i)));
+    }
+
+    // Ticks missed while nobody was receiving are not delivered.
+    clock.advance(Duration::from_secs(600));
+    assert!(r.try_recv().is_ok());
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn manual_recv_blocks() {
+    let clock = ManualClock::new();
+    let r = after_with_clock(Duration::from_secs(3600), clock.clone());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            clock.advance(Duration::from_secs(1800));
+            thread::sleep(ms(100));
+            clock.advance(Duration::from_secs(1800));
+        });
+
+        let start = Instant::now();
+        assert!(r.recv().is_ok());
+        assert!(start.elapsed() >= ms(150));
+    });
+}
+
+#[test]
+fn manual_recv_timeout() {
+    let clock = ManualClock::new();
+    let r = after_with_clock(ms(100), clock.clone());
+
+    // Real time passing doesn't move the clock.
+    assert_eq!(r.recv_timeout(ms(200)), Err(RecvTimeoutError::Timeout));
+
+    let r = tick_with_clock(ms(100), clock.clone());
+    assert_eq!(r.recv_timeout(ms(200)), Err(RecvTimeoutError::Timeout));
+
+    clock.advance(ms(100));
+    assert!(r.recv_timeout(ms(200)).is_ok());
+}
+
+#[test]
+fn manual_select() {
+    let clock = ManualClock::new();
+    let (s, r) = unbounded::<i32>();
+    let timeout = after_with_clock(Duration::from_secs(30), clock.clone());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            clock.advance(Duration::from_secs(30));
+        });
+
+        select! {
+            recv(r) -> _ => panic!(),
+            recv(timeout) -> msg => assert!(msg.is_ok()),
+        }
+    });
+
+    let ticker = tick_with_clock(Duration::from_secs(1), clock.clone());
+    let mut sel = Select::new();
+    sel.recv(&r);
+    let oper2 = sel.recv(&ticker);
+    assert!(sel.select_timeout(ms(100)).is_err());
+
+    clock.advance(Duration::from_secs(1));
+    let oper = sel.select_timeout(ms(100)).unwrap();
+    assert_eq!(oper.index(), oper2);
+    assert!(oper.recv(&ticker).is_ok());
+    drop(s);
+}
+
+#[test]
+fn manual_wait_ready() {
+    let clock = ManualClock::new();
+    let r = after_with_clock(Duration::from_secs(1), clock.clone());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            clock.advance(Duration::from_secs(1));
+        });
+
+        assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+        assert!(r.try_recv().is_ok());
+    });
+}
+
+#[test]
+fn system_clock() {
+    let start = Instant::now();
+    let r = after_with_clock(ms(100), SystemClock);
+    assert!(r.recv().is_ok());
+    assert!(start.elapsed() >= ms(100));
+
+    let r = tick_with_clock(ms(100), SystemClock);
+    assert!(r.recv().is_ok());
+    assert!(r.recv().is_ok());
+    assert!(start.elapsed() >= ms(300));
+}
+
+#[test]
+fn advancing_clock() {
+    // The clock is already a second ahead, but the message is still due in 100 ms.
+    let start = Instant::now();
+    let r = after_with_clock(ms(100), Ahead(Duration::from_secs(1)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert!(r.recv().is_ok());
+    assert!(start.elapsed() >= ms(100));
+
+    let r = tick_with_clock(ms(100), Ahead(Duration::from_secs(1)));
+    select! {
+        recv(r) -> msg => assert!(msg.is_ok()),
+        default(ms(1000)) => panic!(),
+    }
+}
@@ -0,0 +1,165 @@
+//! Tests for the `deque` module.
+
+#![cfg(feature = "deque")]
+
+#[macro_use]
+extern crate crossbeam_channel;
+extern crate crossbeam;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::deque::{Injector, Steal, Worker};
+use crossbeam_channel::{unbounded, Select};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn injector_ready() {
+    let q = Injector::new();
+
+    let mut sel = Select::new();
+    let oper1 = sel.custom(&q);
+    assert!(sel.try_select().is_err());
+
+    q.push(1);
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), oper1);
+    oper.custom(&q);
+    assert_eq!(q.steal(), Steal::Success(1));
+
+    assert!(sel.try_select().is_err());
+}
+
+#[test]
+fn injector_push_wakes_up() {
+    let q = Injector::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            q.push(7);
+        });
+
+        let start = Instant::now();
+        select! {
+            custom(q) => {}
+            default(ms(1000)) => panic!(),
+        }
+        assert!(start.elapsed() >= ms(50));
+        assert_eq!(q.steal(), Steal::Success(7));
+    });
+}
+
+#[test]
+fn stealer_push_wakes_up() {
+    let w = Worker::new_lifo();
+    let s = w.stealer();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            w.push(7);
+            thread::sleep(ms(100));
+        });
+
+        let start = Instant::now();
+        select! {
+            custom(s) => {}
+            default(ms(1000)) => panic!(),
+        }
+        assert!(start.elapsed() >= ms(50));
+        assert_eq!(s.steal(), Steal::Success(7));
+    });
+}
+
+#[test]
+fn steal_batch_wakes_up() {
+    let q = Injector::new();
+    for i in 0..10 {
+        q.push(i);
+    }
+
+    let w1 = Worker::new_fifo();
+    let w2 = Worker::new_fifo();
+    let s1 = w1.stealer();
+    let s2 = w2.stealer();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            assert_eq!(q.steal_batch_and_pop(&w1), Steal::Success(0));
+            thread::sleep(ms(100));
+        });
+
+        select! {
+            custom(s1) => {}
+            default(ms(1000)) => panic!(),
+        }
+        assert_eq!(s1.steal_batch_and_pop(&w2), Steal::Success(1));
+
+        // Tasks left in `w2` can be stolen through its stealer.
+        select! {
+            custom(s2) => {}
+            default => panic!(),
+        }
+        assert!(s2.steal().is_success());
+    });
+}
+
+#[test]
+fn select_with_channel() {
+    let q = Injector::<i32>::new();
+    let (s, r) = unbounded::<()>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            drop(s);
+        });
+
+        select! {
+            custom(q) => panic!(),
+            recv(r) -> msg => assert!(msg.is_err()),
+        }
+    });
+}
+
+#[test]
+fn workers() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let q = Injector::new();
+    let (quit_s, quit_r) = unbounded::<()>();
+    let done = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| loop {
+                select! {
+                    custom(q) => {
+                        while let Steal::Success(i) = q.steal() {
+                            done.fetch_add(i, Ordering::SeqCst);
+                        }
+                    }
+                    recv(quit_r) -> _ => break,
+                }
+            });
+        }
+
+        for i in 0..COUNT {
+            q.push(i);
+        }
+
+        while done.load(Ordering::SeqCst) != COUNT * (COUNT - 1) / 2 {
+            thread::sleep(ms(1));
+        }
+        drop(quit_s);
+    });
+
+    assert!(q.is_empty());
+}
@@ -0,0 +1,159 @@
+//! Tests for `Receiver::into_drain`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, lanes, never, tick, unbounded, Builder, DropPolicy};
+use crossbeam_channel::{Receiver, SendError, Sender};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn flavors() -> Vec<(Sender<usize>, Receiver<usize>)> {
+    vec![bounded(100), lanes(100, 3), unbounded()]
+}
+
+#[test]
+fn smoke() {
+    for (s, r) in flavors() {
+        for i in 0..10 {
+            s.send(i).unwrap();
+        }
+
+        let drain = r.into_drain();
+        assert_eq!(s.send(10), Err(SendError(10)));
+        assert_eq!(drain.collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn empty() {
+    let (s, r) = unbounded::<i32>();
+    let mut drain = r.into_drain();
+    assert_eq!(drain.next(), None);
+    assert_eq!(drain.next(), None);
+    assert!(s.send(1).is_err());
+
+    let (s, r) = bounded::<i32>(0);
+    assert_eq!(r.into_drain().count(), 0);
+    assert!(s.send(1).is_err());
+}
+
+#[test]
+fn wakes_blocked_senders() {
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            let start = Instant::now();
+            assert_eq!(s.send(3), Err(SendError(3)));
+            assert!(start.elapsed() >= ms(50));
+        });
+
+        thread::sleep(ms(100));
+        assert_eq!(r.into_drain().collect::<Vec<_>>(), [1, 2]);
+    });
+
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(s.send(1), Err(SendError(1)));
+        });
+
+        thread::sleep(ms(100));
+        assert_eq!(r.into_drain().count(), 0);
+    });
+}
+
+#[test]
+fn other_receivers() {
+    let (s, r1) = unbounded();
+    let r2 = r1.clone();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let mut drain = r1.into_drain();
+    assert!(s.send(3).is_err());
+
+    // Another receiver competes for the remaining messages and then sees the disconnection.
+    assert_eq!(r2.recv(), Ok(1));
+    assert_eq!(drain.next(), Some(2));
+    assert_eq!(drain.next(), None);
+    assert!(r2.recv().is_err());
+}
+
+#[test]
+fn leftovers_follow_drop_policy() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let d = dropped.clone();
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Handler(Box::new(move |_: i32| {
+            d.fetch_add(1, Ordering::SeqCst);
+        }))).unbounded();
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+
+    let mut drain = r.into_drain();
+    assert_eq!(drain.next(), Some(0));
+    assert_eq!(drain.next(), Some(1));
+    drop(drain);
+
+    assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    assert_eq!(s.pending_on_disconnect(), Some(3));
+}
+
+#[test]
+fn timers() {
+    let r = after(ms(0));
+    thread::sleep(ms(10));
+    assert_eq!(r.into_drain().count(), 1);
+
+    let r = after(ms(1000));
+    assert_eq!(r.into_drain().count(), 0);
+
+    let r = tick(ms(10));
+    thread::sleep(ms(50));
+    assert_eq!(r.into_drain().count(), 1);
+
+    assert_eq!(never::<i32>().into_drain().count(), 0);
+}
+
+#[test]
+fn no_message_lost() {
+    const THREADS: usize = 4;
+
+    for (s, r) in flavors() {
+        let sent = AtomicUsize::new(0);
+
+        let received = crossbeam::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    let mut i = 0;
+                    while s.send(i).is_ok() {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                        i += 1;
+                    }
+                });
+            }
+
+            for _ in 0..1000 {
+                r.recv().unwrap();
+            }
+            1000 + r.clone().into_drain().count()
+        });
+
+        // All senders have noticed the disconnection by now.
+        assert_eq!(sent.load(Ordering::SeqCst), received);
+    }
+}
@@ -0,0 +1,128 @@
+//! Tests for duplex channels.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{duplex, unbounded, Select};
+use crossbeam_channel::{RecvError, SendError, TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (a, b) = duplex::<i32, &str>(1, 1);
+
+    a.send(7).unwrap();
+    assert_eq!(b.try_recv(), Ok(7));
+
+    b.send("foo").unwrap();
+    assert_eq!(a.recv(), Ok("foo"));
+
+    assert_eq!(a.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(b.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn capacity() {
+    let (a, b) = duplex::<(), ()>(3, 5);
+    assert_eq!(a.sender().capacity(), Some(3));
+    assert_eq!(b.capacity(), Some(3));
+    assert_eq!(b.sender().capacity(), Some(5));
+    assert_eq!(a.capacity(), Some(5));
+
+    a.try_send(()).unwrap();
+    a.try_send(()).unwrap();
+    a.try_send(()).unwrap();
+    assert_eq!(a.try_send(()), Err(TrySendError::Full(())));
+}
+
+#[test]
+fn disconnect() {
+    let (a, b) = duplex::<i32, i32>(1, 1);
+    let b2 = b.clone();
+
+    b.send(1).unwrap();
+    drop(b);
+    assert_eq!(a.send(2), Ok(()));
+    assert_eq!(b2.recv(), Ok(2));
+
+    drop(b2);
+    assert_eq!(a.send(3), Err(SendError(3)));
+    assert_eq!(a.recv(), Ok(1));
+    assert_eq!(a.recv(), Err(RecvError));
+}
+
+#[test]
+fn ping_pong() {
+    const COUNT: i32 = 10_000;
+
+    let (a, b) = duplex(0, 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for msg in b.iter() {
+                b.send(msg + 1).unwrap();
+            }
+        });
+
+        for i in 0..COUNT {
+            a.send(i).unwrap();
+            assert_eq!(a.recv(), Ok(i + 1));
+        }
+        drop(a);
+    });
+}
+
+#[test]
+fn select_macro() {
+    let (a, b) = duplex::<(), i32>(1, 1);
+    let (_s, r) = unbounded::<i32>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            b.send(5).unwrap();
+        });
+
+        select! {
+            recv(a) -> msg => assert_eq!(msg, Ok(5)),
+            recv(r) -> _ => panic!(),
+            default(ms(1000)) => panic!(),
+        }
+    });
+}
+
+#[test]
+fn select_struct() {
+    let (a, b) = duplex::<(), i32>(1, 1);
+    let (_s, r) = unbounded::<i32>();
+
+    b.send(5).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&a);
+    let oper2 = sel.recv(&r);
+    let oper = sel.select();
+    match oper.index() {
+        i if i == oper1 => assert_eq!(oper.recv(&a), Ok(5)),
+        i if i == oper2 => panic!(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn into_inner() {
+    let (a, b) = duplex(1, 1);
+    let (s, r) = b.into_inner();
+
+    a.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(a.recv(), Ok(2));
+}
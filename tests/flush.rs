@@ -0,0 +1,181 @@
+//! Tests for `Sender::flush`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, lanes, unbounded, Receiver, Sender};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn channels() -> Vec<(Sender<usize>, Receiver<usize>)> {
+    vec![unbounded(), bounded(5), bounded(100), lanes(100, 3)]
+}
+
+#[test]
+fn empty() {
+    for (s, _r) in channels() {
+        s.flush();
+    }
+
+    let (s, _r) = bounded::<i32>(0);
+    s.flush();
+}
+
+#[test]
+fn waits_for_receiver() {
+    const COUNT: usize = 5;
+
+    for (s, r) in channels() {
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    thread::sleep(ms(20));
+                    assert_eq!(r.recv(), Ok(i));
+                }
+            });
+
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+
+            let start = Instant::now();
+            s.flush();
+            assert!(start.elapsed() >= ms(50));
+            assert!(s.is_empty());
+        });
+    }
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..3 {
+                assert_eq!(r.recv(), Ok(i));
+            }
+        });
+
+        for i in 0..3 {
+            s.send(i).unwrap();
+            s.flush();
+        }
+    });
+}
+
+#[test]
+fn later_messages_not_awaited() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    let flushed = AtomicBool::new(false);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.flush();
+            flushed.store(true, Ordering::SeqCst);
+        });
+
+        thread::sleep(ms(100));
+        s.send(2).unwrap();
+        assert!(!flushed.load(Ordering::SeqCst));
+
+        assert_eq!(r.recv(), Ok(1));
+        thread::sleep(ms(100));
+        assert!(flushed.load(Ordering::SeqCst));
+        assert_eq!(r.recv(), Ok(2));
+    });
+}
+
+#[test]
+fn other_senders() {
+    let (s1, r) = bounded(10);
+    let s2 = s1.clone();
+    s2.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        // Messages from other senders are waited for too.
+        let start = Instant::now();
+        s1.flush();
+        assert!(start.elapsed() >= ms(50));
+    });
+}
+
+#[test]
+fn lanes_priority() {
+    let (s, r) = lanes(10, 2);
+    s.send_with_priority(1, 0).unwrap();
+    let flushed = AtomicBool::new(false);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.flush();
+            flushed.store(true, Ordering::SeqCst);
+        });
+
+        thread::sleep(ms(100));
+        s.send_with_priority(2, 1).unwrap();
+
+        // A later message with higher priority overtakes the awaited one.
+        assert_eq!(r.recv(), Ok(2));
+        thread::sleep(ms(100));
+        assert!(!flushed.load(Ordering::SeqCst));
+
+        assert_eq!(r.recv(), Ok(1));
+    });
+    assert!(flushed.load(Ordering::SeqCst));
+}
+
+#[test]
+fn displaced() {
+    for &(cap, lanes_count) in &[(1, 0), (2, 0), (1, 2), (2, 2)] {
+        let (s, r) = if lanes_count == 0 {
+            bounded(cap)
+        } else {
+            lanes(cap, lanes_count)
+        };
+        for i in 0..cap {
+            s.send(i).unwrap();
+        }
+        assert!(s.send_lossy(10).unwrap().is_some());
+
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(ms(100));
+                while r.try_recv().is_ok() {}
+            });
+
+            s.flush();
+        });
+    }
+}
+
+#[test]
+fn receiver_dropped() {
+    for (s, r) in channels() {
+        s.send(1).unwrap();
+        s.send(2).unwrap();
+
+        crossbeam::scope(|scope| {
+            scope.spawn(move || {
+                thread::sleep(ms(100));
+                assert_eq!(r.recv(), Ok(1));
+                drop(r);
+            });
+
+            s.flush();
+        });
+        assert_eq!(s.pending_on_disconnect(), Some(1));
+    }
+}
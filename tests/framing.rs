@@ -0,0 +1,165 @@
+//! Tests for splitting byte streams into frames.
+
+#![cfg(feature = "io")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::io::{ErrorKind, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::io::{byte_channel, frames, ChunkReader, Framing, Frames};
+use crossbeam_channel::unbounded;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn collect<I: Iterator<Item = std::io::Result<Vec<u8>>>>(iter: I) -> Vec<Vec<u8>> {
+    iter.map(|f| f.unwrap()).collect()
+}
+
+#[test]
+fn chunk_reader() {
+    let (s, r) = unbounded();
+    s.send(b"ab".to_vec()).unwrap();
+    s.send(Vec::new()).unwrap();
+    s.send(b"cde".to_vec()).unwrap();
+    drop(s);
+
+    let mut reader = ChunkReader::new(r);
+    let mut buf = [0; 4];
+    assert_eq!(reader.read(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], b"ab");
+    assert_eq!(reader.read(&mut buf).unwrap(), 3);
+    assert_eq!(&buf[..3], b"cde");
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn newline() {
+    let (s, r) = unbounded();
+    s.send(b"one\ntw".to_vec()).unwrap();
+    s.send(b"o\r".to_vec()).unwrap();
+    s.send(b"\n\nthree\nfour".to_vec()).unwrap();
+    drop(s);
+
+    let frames = collect(frames(r, Framing::Newline));
+    assert_eq!(
+        frames,
+        [&b"one"[..], &b"two"[..], &b""[..], &b"three"[..], &b"four"[..]]
+    );
+}
+
+#[test]
+fn newline_empty_stream() {
+    let (s, r) = unbounded::<Vec<u8>>();
+    drop(s);
+    assert!(frames(r, Framing::Newline).next().is_none());
+}
+
+#[test]
+fn length_prefixed() {
+    let (s, r) = unbounded();
+    s.send(vec![0, 0]).unwrap();
+    s.send(vec![0, 2, b'h', b'i', 0, 0, 0, 0, 0]).unwrap();
+    s.send(vec![0, 0, 3, b'a', b'b']).unwrap();
+    s.send(vec![b'c']).unwrap();
+    drop(s);
+
+    let frames = collect(frames(r, Framing::LengthPrefixed));
+    assert_eq!(frames, [&b"hi"[..], &b""[..], &b"abc"[..]]);
+}
+
+#[test]
+fn truncated_header() {
+    let (s, r) = unbounded();
+    s.send(vec![0, 0, 0, 1, b'x', 0, 0]).unwrap();
+    drop(s);
+
+    let mut frames = frames(r, Framing::LengthPrefixed);
+    assert_eq!(frames.next().unwrap().unwrap(), b"x");
+    assert_eq!(
+        frames.next().unwrap().unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert!(frames.next().is_none());
+}
+
+#[test]
+fn truncated_body() {
+    let (s, r) = unbounded();
+    s.send(vec![0, 0, 0, 5, b'x']).unwrap();
+    drop(s);
+
+    let mut frames = frames(r, Framing::LengthPrefixed);
+    assert_eq!(
+        frames.next().unwrap().unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert!(frames.next().is_none());
+}
+
+#[test]
+fn max_len() {
+    let (s, r) = unbounded();
+    s.send(vec![0, 0, 0, 2, b'o', b'k', 0, 0, 1, 0]).unwrap();
+    drop(s);
+
+    let mut iter = frames(r, Framing::LengthPrefixed).max_len(2);
+    assert_eq!(iter.next().unwrap().unwrap(), b"ok");
+    assert_eq!(
+        iter.next().unwrap().unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert!(iter.next().is_none());
+
+    let (s, r) = unbounded();
+    s.send(b"abc".to_vec()).unwrap();
+    s.send(b"def\n".to_vec()).unwrap();
+    drop(s);
+
+    let mut iter = frames(r, Framing::Newline).max_len(5);
+    assert_eq!(
+        iter.next().unwrap().unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn byte_channel_frames() {
+    let (mut s, r) = byte_channel(3);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..100 {
+                write!(s, "line {}\n", i).unwrap();
+            }
+        });
+
+        let frames = collect(Frames::new(r, Framing::Newline));
+        assert_eq!(frames.len(), 100);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(*frame, format!("line {}", i).into_bytes());
+        }
+    });
+}
+
+#[test]
+fn blocks_until_frame_arrives() {
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            s.send(b"hel".to_vec()).unwrap();
+            thread::sleep(ms(100));
+            s.send(b"lo\n".to_vec()).unwrap();
+        });
+
+        let mut frames = frames(r, Framing::Newline);
+        assert_eq!(frames.next().unwrap().unwrap(), b"hello");
+        assert!(frames.next().is_none());
+    });
+}
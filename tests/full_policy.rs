@@ -0,0 +1,333 @@
+//! Tests for the policy of send operations on full channels.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Builder, CancelToken, FullPolicy, SendCancelError, SendError};
+use crossbeam_channel::{SendTimeoutError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn park_by_default() {
+    let (s, r) = Builder::new().bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+            assert_eq!(r.recv(), Ok(2));
+        });
+
+        let start = Instant::now();
+        assert_eq!(s.send(2), Ok(()));
+        assert!(start.elapsed() >= ms(400));
+    });
+
+    assert_eq!(s.stats().blocked, 1);
+}
+
+#[test]
+fn fail() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(2);
+
+    assert_eq!(s.send(1), Ok(()));
+    assert_eq!(s.send(2), Ok(()));
+
+    let start = Instant::now();
+    assert_eq!(s.send_timeout(3, ms(1000)), Err(SendTimeoutError::Timeout(3)));
+    assert!(start.elapsed() < ms(500));
+
+    // The channel is still connected.
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.send(3), Ok(()));
+    assert_eq!(s.try_send(4), Err(TrySendError::Full(4)));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 3]);
+
+    drop(r);
+    assert_eq!(s.send_timeout(4, ms(1000)), Err(SendTimeoutError::Disconnected(4)));
+}
+
+#[test]
+fn fail_send() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+    s.send(1).unwrap();
+
+    // A full channel is reported like a disconnected one, without waiting.
+    let start = Instant::now();
+    assert_eq!(s.send(2), Err(SendError(2)));
+    assert_eq!(s.spin_send(2, 100), Err(SendError(2)));
+    assert_eq!(s.send_with(|| 2).unwrap_err().into_inner()(), 2);
+    assert_eq!(s.send_all(vec![2, 3]), Err(SendError(vec![2])));
+    assert!(s.reserve().is_err());
+    assert!(start.elapsed() < ms(500));
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.send(2), Ok(()));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(s.send_all(vec![3, 4]), Err(SendError(vec![4])));
+    assert_eq!(r.recv(), Ok(3));
+
+    drop(r);
+    assert_eq!(s.send(5), Err(SendError(5)));
+}
+
+#[test]
+fn fail_send_zero_capacity() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(0);
+
+    // Nobody is waiting on the other side.
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert!(s.send_with(|| 1).is_err());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv(), Ok(2));
+            assert_eq!(r.recv(), Ok(3));
+        });
+
+        thread::sleep(ms(500));
+        assert_eq!(s.send(2), Ok(()));
+        thread::sleep(ms(500));
+        assert!(s.send_with(|| 3).is_ok());
+    });
+}
+
+#[test]
+fn fail_send_by_policy() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+
+    assert_eq!(s.send_by_policy(1), Ok(()));
+
+    let start = Instant::now();
+    assert_eq!(s.send_by_policy(2), Err(TrySendError::Full(2)));
+    assert!(start.elapsed() < ms(500));
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.send_by_policy(2), Ok(()));
+    assert_eq!(r.recv(), Ok(2));
+
+    drop(r);
+    assert_eq!(s.send_by_policy(3), Err(TrySendError::Disconnected(3)));
+}
+
+#[test]
+fn fail_wait_capacity() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+    s.send(1).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(s.wait_capacity(ms(1000)), Err(SendTimeoutError::Timeout(())));
+    assert!(start.elapsed() < ms(500));
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.wait_capacity(ms(1000)), Ok(()));
+}
+
+#[test]
+fn park_send_by_policy() {
+    let (s, r) = Builder::new().bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        assert_eq!(s.send_by_policy(2), Ok(()));
+    });
+}
+
+#[test]
+fn fail_zero_capacity() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(0);
+
+    // Nobody is waiting on the other side.
+    assert_eq!(s.send_timeout(1, ms(1000)), Err(SendTimeoutError::Timeout(1)));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| assert_eq!(r.recv(), Ok(2)));
+
+        // Once the receiver is waiting, the message goes through.
+        thread::sleep(ms(500));
+        assert_eq!(s.send(2), Ok(()));
+    });
+}
+
+#[test]
+fn fail_lanes() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).lanes(1, 2);
+
+    assert_eq!(s.send_with_priority(1, 1), Ok(()));
+    assert_eq!(s.send_with_priority(2, 0), Err(SendError(2)));
+    assert_eq!(s.send_by_policy(2), Err(TrySendError::Full(2)));
+    assert_eq!(
+        s.send_timeout_with_priority(2, 1, ms(1000)),
+        Err(SendTimeoutError::Timeout(2)),
+    );
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn fail_specialized() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+    let s = s.into_array().unwrap();
+
+    assert_eq!(s.send(1), Ok(()));
+    assert_eq!(s.send_timeout(2, ms(1000)), Err(SendTimeoutError::Timeout(2)));
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn fail_send_specialized() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).bounded(1);
+    let s = s.into_array().unwrap();
+    s.send(1).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(s.send(2), Err(SendError(2)));
+    assert!(start.elapsed() < ms(500));
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.send(2), Ok(()));
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn spin_yield() {
+    const COUNT: usize = 10_000;
+
+    let (s, r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                assert_eq!(r.recv(), Ok(i));
+            }
+        });
+
+        for i in 0..COUNT {
+            s.send(i).unwrap();
+        }
+    });
+
+    // The sender never parked.
+    assert_eq!(s.stats().blocked, 0);
+}
+
+#[test]
+fn spin_yield_send_with() {
+    const COUNT: usize = 1000;
+
+    let (s, r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(1);
+    let token = CancelToken::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT * 2 {
+                assert_eq!(r.recv(), Ok(i));
+            }
+        });
+
+        for i in 0..COUNT {
+            s.send_with(|| i * 2).unwrap();
+            s.send_cancellable(i * 2 + 1, &token).unwrap();
+        }
+    });
+
+    assert_eq!(s.stats().blocked, 0);
+}
+
+#[test]
+fn spin_yield_cancel() {
+    let (s, _r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(1);
+    let token = CancelToken::new();
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(300));
+            token.cancel();
+        });
+
+        assert_eq!(s.send_cancellable(2, &token), Err(SendCancelError::Cancelled(2)));
+    });
+}
+
+#[test]
+fn spin_yield_flush() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(4);
+    for i in 0..4 {
+        s.send(i).unwrap();
+    }
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(300));
+            assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 1, 2, 3]);
+        });
+
+        s.flush();
+        assert!(s.is_empty());
+    });
+
+    assert_eq!(s.stats().blocked, 0);
+}
+
+#[test]
+fn spin_yield_timeout() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(1);
+    s.send(1).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(s.send_timeout(2, ms(300)), Err(SendTimeoutError::Timeout(2)));
+    assert!(start.elapsed() >= ms(300));
+    assert_eq!(s.stats().blocked, 0);
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(s.send_timeout(2, ms(300)), Ok(()));
+}
+
+#[test]
+fn spin_yield_disconnect() {
+    let (s, r) = Builder::<i32>::new().full_policy(FullPolicy::SpinYield).bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(300));
+            drop(r);
+        });
+
+        assert_eq!(s.send(1), Err(SendError(1)));
+    });
+}
+
+#[test]
+fn unbounded_unaffected() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::Fail).unbounded();
+    for i in 0..1000 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.len(), 1000);
+}
+
+#[test]
+fn non_blocking_unaffected() {
+    let (s, r) = Builder::new().full_policy(FullPolicy::SpinYield).bounded(1);
+    assert_eq!(s.try_send(1), Ok(()));
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn debug() {
+    let b = Builder::<i32>::new().full_policy(FullPolicy::SpinYield);
+    assert!(format!("{:?}", b).contains("full_policy: SpinYield"));
+}
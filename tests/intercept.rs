@@ -0,0 +1,175 @@
+//! Tests for send and receive hooks.
+
+#![cfg(feature = "intercept")]
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{Builder, Receiver, Select, Sender, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Messages seen by a hook.
+type Log = Arc<Mutex<Vec<i32>>>;
+
+/// Creates a channel with `make` whose hooks log every message they see.
+fn logged<F>(make: F) -> (Sender<i32>, Receiver<i32>, Log, Log)
+where
+    F: FnOnce(Builder<i32>) -> (Sender<i32>, Receiver<i32>),
+{
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let (s1, r1) = (sent.clone(), received.clone());
+
+    let (s, r) = make(
+        Builder::new()
+            .on_send(move |&msg| s1.lock().unwrap().push(msg))
+            .on_recv(move |&msg| r1.lock().unwrap().push(msg)),
+    );
+    (s, r, sent, received)
+}
+
+#[test]
+fn smoke() {
+    let (s, r, sent, received) = logged(|b| b.unbounded());
+
+    s.send(1).unwrap();
+    s.try_send(2).unwrap();
+    s.send_timeout(3, ms(100)).unwrap();
+    assert_eq!(*sent.lock().unwrap(), [1, 2, 3]);
+    assert!(received.lock().unwrap().is_empty());
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.recv_timeout(ms(100)), Ok(3));
+    assert_eq!(*received.lock().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn all_flavors() {
+    for cap in 0..3 {
+        let (s, r, sent, received) = logged(|b| b.bounded(cap));
+
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                for i in 0..10 {
+                    s.send(i).unwrap();
+                }
+            });
+            for i in 0..10 {
+                assert_eq!(r.recv(), Ok(i));
+            }
+        });
+
+        assert_eq!(*sent.lock().unwrap(), (0..10).collect::<Vec<_>>());
+        assert_eq!(*received.lock().unwrap(), (0..10).collect::<Vec<_>>());
+    }
+
+    let (s, r, sent, received) = logged(|b| b.lanes(4, 2));
+    s.send_with_priority(1, 0).unwrap();
+    s.send_with_priority(2, 1).unwrap();
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 1]);
+    assert_eq!(*sent.lock().unwrap(), [1, 2]);
+    assert_eq!(*received.lock().unwrap(), [2, 1]);
+}
+
+#[test]
+fn failed_send() {
+    let (s, r, sent, received) = logged(|b| b.bounded(1));
+
+    s.send(1).unwrap();
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(*sent.lock().unwrap(), [1, 2]);
+
+    drop(r);
+    assert!(s.send(3).is_err());
+    assert_eq!(*sent.lock().unwrap(), [1, 2, 3]);
+
+    // Messages dropped with the channel were never received.
+    assert!(received.lock().unwrap().is_empty());
+}
+
+#[test]
+fn spin_send_once() {
+    let (s, r, sent, _) = logged(|b| b.bounded(1));
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            r.recv().unwrap();
+        });
+
+        // Spinning retries internally, but the hook sees the message only once.
+        s.spin_send(2, 1000).unwrap();
+    });
+    assert_eq!(*sent.lock().unwrap(), [1, 2]);
+}
+
+#[test]
+fn select() {
+    let (s1, r1, sent1, received1) = logged(|b| b.bounded(1));
+    let (s2, r2, sent2, received2) = logged(|b| b.bounded(1));
+
+    select! {
+        send(s1, 10) -> res => res.unwrap(),
+    }
+    assert_eq!(*sent1.lock().unwrap(), [10]);
+
+    select! {
+        recv(r1) -> msg => assert_eq!(msg, Ok(10)),
+    }
+    assert_eq!(*received1.lock().unwrap(), [10]);
+
+    s2.send(20).unwrap();
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r2);
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.recv(&r2), Ok(20));
+    assert_eq!(*sent2.lock().unwrap(), [20]);
+    assert_eq!(*received2.lock().unwrap(), [20]);
+}
+
+#[test]
+fn bulk_receive() {
+    let (s, r, _, received) = logged(|b| b.unbounded());
+
+    for i in 0..10 {
+        s.send(i).unwrap();
+    }
+    let v: Vec<_> = r.collect_timeout(ms(0));
+    assert_eq!(v, (0..10).collect::<Vec<_>>());
+    assert_eq!(*received.lock().unwrap(), v);
+}
+
+#[test]
+fn selective_receive() {
+    let (s, r, _, received) = logged(|b| b.unbounded());
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+    assert_eq!(*received.lock().unwrap(), [2]);
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(*received.lock().unwrap(), [2, 1]);
+}
+
+#[test]
+fn assertion_hook() {
+    let (s, r) = Builder::new()
+        .on_send(|&msg: &i32| assert!(msg >= 0, "negative message"))
+        .unbounded();
+
+    let res = thread::spawn(move || s.send(-1)).join();
+    assert!(res.is_err());
+    assert!(r.try_recv().is_err());
+}
@@ -0,0 +1,124 @@
+//! Tests for conversions into I/O errors and the `io` module.
+
+extern crate crossbeam_channel;
+
+use std::io::{self, ErrorKind};
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded, Select};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn send_errors() {
+    let (s, r) = bounded(1);
+
+    s.send(1).unwrap();
+    let err: io::Error = s.try_send(2).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    let err: io::Error = s.send_timeout(2, ms(50)).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+    drop(r);
+    let err: io::Error = s.send(2).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    let err: io::Error = s.try_send(2).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    let err: io::Error = s.send_timeout(2, ms(50)).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn recv_errors() {
+    let (s, r) = unbounded::<i32>();
+
+    let err: io::Error = r.try_recv().unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    let err: io::Error = r.recv_timeout(ms(50)).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+    drop(s);
+    let err: io::Error = r.recv().unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let err: io::Error = r.try_recv().unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let err: io::Error = r.recv_timeout(ms(50)).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn select_errors() {
+    let (s, r) = unbounded::<i32>();
+    let mut sel = Select::new();
+    sel.recv(&r);
+
+    let err: io::Error = sel.try_select().unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    let err: io::Error = sel.select_timeout(ms(50)).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    drop(s);
+}
+
+#[test]
+fn question_mark() {
+    fn forward(r: &crossbeam_channel::Receiver<i32>) -> io::Result<i32> {
+        Ok(r.recv()?)
+    }
+
+    let (s, r) = unbounded();
+    s.send(7).unwrap();
+    drop(s);
+
+    assert_eq!(forward(&r).unwrap(), 7);
+    assert_eq!(forward(&r).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+}
+
+#[cfg(feature = "io")]
+mod helpers {
+    use std::io::ErrorKind;
+
+    use crossbeam_channel::{bounded, io, unbounded};
+
+    use super::ms;
+
+    #[test]
+    fn send() {
+        let (s, r) = bounded(1);
+
+        io::send(&s, 1).unwrap();
+        assert_eq!(io::try_send(&s, 2).unwrap_err().kind(), ErrorKind::WouldBlock);
+        assert_eq!(
+            io::send_timeout(&s, 2, ms(50)).unwrap_err().kind(),
+            ErrorKind::TimedOut
+        );
+
+        drop(r);
+        assert_eq!(io::send(&s, 2).unwrap_err().kind(), ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn recv_eof() {
+        let (s, r) = unbounded();
+
+        assert_eq!(io::try_recv(&r).unwrap_err().kind(), ErrorKind::WouldBlock);
+        assert_eq!(
+            io::recv_timeout(&r, ms(50)).unwrap_err().kind(),
+            ErrorKind::TimedOut
+        );
+
+        s.send(1).unwrap();
+        s.send(2).unwrap();
+        s.send(3).unwrap();
+        drop(s);
+
+        assert_eq!(io::recv(&r).unwrap(), Some(1));
+        assert_eq!(io::try_recv(&r).unwrap(), Some(2));
+        assert_eq!(io::recv_timeout(&r, ms(50)).unwrap(), Some(3));
+
+        assert_eq!(io::recv(&r).unwrap(), None);
+        assert_eq!(io::try_recv(&r).unwrap(), None);
+        assert_eq!(io::recv_timeout(&r, ms(50)).unwrap(), None);
+    }
+}
@@ -3,7 +3,11 @@
 extern crate crossbeam;
 extern crate crossbeam_channel;
 
-use crossbeam_channel::unbounded;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, unbounded};
 
 #[test]
 fn nested_recv_iter() {
@@ -108,3 +112,111 @@ fn recv_into_iter_borrowed() {
     assert_eq!(iter.next().unwrap(), 2);
     assert_eq!(iter.next().is_none(), true);
 }
+
+#[test]
+fn chunks() {
+    for cap in vec![None, Some(3), Some(100)] {
+        let (s, r) = match cap {
+            None => unbounded(),
+            Some(cap) => bounded(cap),
+        };
+
+        for i in 0..3 {
+            s.send(i).unwrap();
+        }
+        let mut iter = r.chunks(2);
+        assert_eq!(iter.next(), Some(vec![0, 1]));
+        assert_eq!(iter.next(), Some(vec![2]));
+
+        s.send(3).unwrap();
+        drop(s);
+        assert_eq!(iter.next(), Some(vec![3]));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+}
+
+#[test]
+fn chunks_wrap_around() {
+    let (s, r) = bounded(4);
+
+    // Move the head into the middle of the buffer so that chunks have to wrap around.
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.chunks(3).next(), Some(vec![0, 1, 2]));
+
+    for i in 0..4 {
+        s.send(i).unwrap();
+    }
+    let mut received = Vec::new();
+    for chunk in r.chunks(4) {
+        received.extend(chunk);
+        if received.len() == 4 {
+            break;
+        }
+    }
+    assert_eq!(received, [0, 1, 2, 3]);
+}
+
+#[test]
+fn chunks_block() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            s.send(1).unwrap();
+            thread::sleep(Duration::from_millis(100));
+            s.send(2).unwrap();
+        });
+
+        let v: Vec<_> = r.chunks(10).collect();
+        assert_eq!(v, [vec![1], vec![2]]);
+    });
+
+    let r = after(Duration::from_millis(100));
+    assert_eq!(r.chunks(10).next().map(|c| c.len()), Some(1));
+}
+
+#[test]
+fn chunks_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for cap in vec![None, Some(3), Some(100)] {
+        let (s, r) = match cap {
+            None => unbounded::<usize>(),
+            Some(cap) => bounded(cap),
+        };
+        let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+        crossbeam::scope(|scope| {
+            for _ in 0..THREADS {
+                let r = r.clone();
+                let v = &v;
+                scope.spawn(move || {
+                    for chunk in r.chunks(7) {
+                        assert!(!chunk.is_empty() && chunk.len() <= 7);
+                        for n in chunk {
+                            v[n].fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                let s = s.clone();
+                scope.spawn(move || {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+            drop(s);
+        });
+
+        for c in v {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+}
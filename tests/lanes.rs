@@ -0,0 +1,300 @@
+//! Tests for the lanes channel flavor.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, lanes, Builder, Displace};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use crossbeam_channel::{SendError, SendTimeoutError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = lanes(1, 2);
+    s.send(7).unwrap();
+    assert_eq!(r.try_recv(), Ok(7));
+
+    s.send_with_priority(8, 1).unwrap();
+    assert_eq!(r.recv(), Ok(8));
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(1000)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn priority_order() {
+    let (s, r) = lanes(100, 3);
+
+    for i in 0..10 {
+        s.send_with_priority(i, i % 3).unwrap();
+    }
+
+    let v: Vec<_> = r.try_iter().collect();
+    assert_eq!(v, [2, 5, 8, 1, 4, 7, 0, 3, 6, 9]);
+}
+
+#[test]
+fn shared_capacity() {
+    let (s, r) = lanes(2, 3);
+    assert_eq!(s.capacity(), Some(2));
+
+    s.send_with_priority(1, 0).unwrap();
+    s.send_with_priority(2, 1).unwrap();
+    assert!(s.is_full());
+    assert_eq!(s.len(), 2);
+
+    for lane in 0..3 {
+        assert_eq!(
+            s.try_send_with_priority(3, lane),
+            Err(TrySendError::Full(3))
+        );
+    }
+    assert_eq!(
+        s.send_timeout_with_priority(3, 2, ms(100)),
+        Err(SendTimeoutError::Timeout(3))
+    );
+
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(s.try_send_with_priority(3, 2), Ok(()));
+    assert_eq!(r.recv(), Ok(3));
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn backpressure() {
+    let (s, r) = lanes(1, 2);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            // Blocks until the first message is received.
+            s.send_with_priority(2, 1).unwrap();
+        });
+
+        thread::sleep(ms(500));
+        assert_eq!(r.len(), 1);
+        assert_eq!(r.recv(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+    });
+}
+
+#[test]
+fn disconnect() {
+    let (s, r) = lanes(3, 2);
+    s.send(1).unwrap();
+    s.send_with_priority(2, 1).unwrap();
+    drop(s);
+
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+
+    let (s, r) = lanes(3, 2);
+    drop(r);
+    assert_eq!(s.send_with_priority(1, 1), Err(SendError(1)));
+    assert_eq!(
+        s.try_send_with_priority(2, 0),
+        Err(TrySendError::Disconnected(2))
+    );
+}
+
+#[test]
+#[should_panic(expected = "lane 3 is out of range for a channel with 3 lanes")]
+fn lane_out_of_range() {
+    let (s, _r) = lanes(1, 3);
+    let _ = s.send_with_priority((), 3);
+}
+
+#[test]
+#[should_panic(expected = "lane 1 is out of range for a channel with 1 lanes")]
+fn lane_of_other_flavor() {
+    let (s, _r) = bounded(1);
+    let _ = s.try_send_with_priority((), 1);
+}
+
+#[test]
+#[should_panic(expected = "number of lanes must be positive")]
+fn zero_lanes() {
+    lanes::<()>(1, 0);
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = lanes(2, 2);
+    let (_s2, r2) = bounded::<i32>(1);
+
+    s1.send(1).unwrap();
+    s1.send_with_priority(2, 1).unwrap();
+
+    for &expected in &[2, 1] {
+        select! {
+            recv(r1) -> msg => assert_eq!(msg, Ok(expected)),
+            recv(r2) -> _ => panic!(),
+        }
+    }
+
+    // Send operations in `select!` go into the lowest lane.
+    select! {
+        send(s1, 3) -> res => res.unwrap(),
+        recv(r2) -> _ => panic!(),
+    }
+    s1.send_with_priority(4, 1).unwrap();
+    assert!(s1.is_full());
+    select! {
+        send(s1, 5) -> _ => panic!(),
+        default => {}
+    }
+    assert_eq!(r1.recv(), Ok(4));
+    assert_eq!(r1.recv(), Ok(3));
+}
+
+#[test]
+fn select_blocking() {
+    let (s, r) = lanes(1, 2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send_with_priority(1, 1).unwrap();
+        });
+
+        select! {
+            recv(r) -> msg => assert_eq!(msg, Ok(1)),
+            default(ms(1000)) => panic!(),
+        }
+    });
+}
+
+#[test]
+fn recv_where() {
+    let (s, r) = lanes(10, 2);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    s.send_with_priority(3, 1).unwrap();
+    s.send_with_priority(4, 1).unwrap();
+
+    assert_eq!(r.recv_where(|&x| x % 2 == 0), Ok(4));
+    assert_eq!(r.recv_where(|&x| x < 3), Ok(1));
+    assert_eq!(
+        r.recv_timeout_where(|&x| x > 10, ms(100)),
+        Err(RecvTimeoutError::Timeout)
+    );
+
+    assert_eq!(r.recv(), Ok(3));
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn recv_where_blocking() {
+    let (s, r) = lanes(10, 2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..5 {
+                thread::sleep(ms(50));
+                s.send_with_priority(i, i % 2).unwrap();
+            }
+        });
+
+        assert_eq!(r.recv_where(|&x| x == 4), Ok(4));
+        assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 3, 0, 2]);
+    });
+}
+
+#[test]
+fn permit() {
+    let (s, r) = lanes(1, 2);
+
+    let permit = s.try_reserve().unwrap();
+    assert_eq!(s.try_send_with_priority(1, 1), Err(TrySendError::Full(1)));
+    drop(permit);
+
+    let permit = s.reserve().unwrap();
+    permit.send(2).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn send_lossy() {
+    let (s, r) = Builder::new().displace(Displace::Oldest).lanes(2, 2);
+
+    s.send_with_priority(1, 1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(s.send_lossy(3), Ok(Some(2)));
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Ok(3));
+}
+
+#[test]
+fn chunks() {
+    let (s, r) = lanes(10, 2);
+    for i in 0..5 {
+        s.send_with_priority(i, i % 2).unwrap();
+    }
+    drop(s);
+
+    let v: Vec<_> = r.chunks(2).collect();
+    assert_eq!(v, [vec![1, 3], vec![0, 2], vec![4]]);
+}
+
+#[test]
+fn drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let (s, r) = lanes(10, 3);
+    for i in 0..6 {
+        s.send_with_priority(DropCounter, i % 3).unwrap();
+    }
+    drop(r.recv().unwrap());
+    drop((s, r));
+
+    assert_eq!(DROPS.load(Ordering::SeqCst), 6);
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = lanes::<usize>(3, 3);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..COUNT {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    s.send_with_priority(i, i % 3).unwrap();
+                }
+            });
+        }
+    });
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
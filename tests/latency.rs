@@ -0,0 +1,193 @@
+//! Tests for latency statistics of channels.
+
+#![cfg(feature = "latency")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, lanes, unbounded, Receiver, Select, Sender};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Sends a message, waits for `wait`, and receives it.
+fn delayed(s: &Sender<i32>, r: &Receiver<i32>, wait: Duration) {
+    s.send(1).unwrap();
+    thread::sleep(wait);
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn empty() {
+    let (_s, r) = unbounded::<i32>();
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 0);
+    assert_eq!(stats.min(), None);
+    assert_eq!(stats.max(), None);
+    assert_eq!(stats.mean(), None);
+    assert_eq!(stats.percentile(50.0), None);
+}
+
+#[test]
+fn array() {
+    let (s, r) = bounded(1);
+    delayed(&s, &r, ms(100));
+
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 1);
+    assert!(stats.min().unwrap() >= ms(90));
+}
+
+#[test]
+fn list() {
+    let (s, r) = unbounded();
+    delayed(&s, &r, ms(100));
+
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 1);
+    assert!(stats.min().unwrap() >= ms(90));
+}
+
+#[test]
+fn lanes_flavor() {
+    let (s, r) = lanes(4, 2);
+    delayed(&s, &r, ms(100));
+
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 1);
+    assert!(stats.min().unwrap() >= ms(90));
+}
+
+#[test]
+fn zero() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(1).unwrap());
+        thread::sleep(ms(100));
+        assert_eq!(r.recv(), Ok(1));
+    });
+
+    // The sender waited for the receiver.
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 1);
+    assert!(stats.min().unwrap() >= ms(90));
+
+    // The receiver waited for the sender, so the message didn't wait at all.
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(2).unwrap();
+        });
+        assert_eq!(r.recv(), Ok(2));
+    });
+
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 2);
+    assert!(stats.min().unwrap() < ms(50));
+}
+
+#[test]
+fn shared_by_receivers() {
+    let (s, r1) = unbounded();
+    let r2 = r1.clone();
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r1.recv(), Ok(1));
+    assert_eq!(r2.recv(), Ok(2));
+
+    assert_eq!(r1.latency_stats().count(), 2);
+    assert_eq!(r1.latency_stats(), r2.latency_stats());
+}
+
+#[test]
+fn percentiles() {
+    let (s, r) = unbounded();
+    for i in 0..100 {
+        s.send(i).unwrap();
+    }
+    for _ in 0..90 {
+        r.recv().unwrap();
+    }
+    thread::sleep(ms(100));
+    for _ in 0..10 {
+        r.recv().unwrap();
+    }
+
+    let stats = r.latency_stats();
+    assert_eq!(stats.count(), 100);
+    assert!(stats.percentile(90.0).unwrap() < ms(50));
+    assert!(stats.percentile(91.0).unwrap() >= ms(90));
+    assert_eq!(stats.percentile(100.0), stats.max());
+
+    let mean = stats.mean().unwrap();
+    assert!(mean >= ms(9) && mean <= ms(50));
+    assert!(stats.min().unwrap() <= mean);
+    assert!(mean <= stats.max().unwrap());
+}
+
+#[test]
+#[should_panic]
+fn invalid_percentile() {
+    let (_s, r) = unbounded::<i32>();
+    r.latency_stats().percentile(101.0);
+}
+
+#[test]
+fn batch() {
+    let (s, r) = bounded(10);
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    drop(s);
+
+    assert_eq!(r.collect_timeout::<Vec<_>>(ms(100)).len(), 5);
+    assert_eq!(r.latency_stats().count(), 5);
+
+    let (s, r) = unbounded();
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    drop(s);
+
+    assert_eq!(r.collect_timeout::<Vec<_>>(ms(100)).len(), 5);
+    assert_eq!(r.latency_stats().count(), 5);
+}
+
+#[test]
+fn put_back_counted_once() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+    drop(r.recv_guard().unwrap());
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.latency_stats().count(), 1);
+
+    let (s, r) = lanes(1, 2);
+    s.send(1).unwrap();
+    drop(r.recv_guard().unwrap());
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.latency_stats().count(), 1);
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+    assert_eq!(sel.select().recv(&r), Ok(1));
+    assert_eq!(r.latency_stats().count(), 1);
+}
+
+#[test]
+fn generated_messages() {
+    let r = after(ms(0));
+    r.recv().unwrap();
+    assert_eq!(r.latency_stats().count(), 0);
+}
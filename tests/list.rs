@@ -10,7 +10,7 @@ use std::sync::atomic::Ordering;
 use std::thread;
 use std::time::Duration;
 
-use crossbeam_channel::{unbounded};
+use crossbeam_channel::{unbounded, Builder};
 use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
 use crossbeam_channel::{SendError, SendTimeoutError, TrySendError};
 use rand::{thread_rng, Rng};
@@ -130,6 +130,151 @@ fn recv_timeout() {
     });
 }
 
+#[test]
+fn recv_where() {
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+            assert_eq!(
+                r.recv_timeout_where(|&x| x == 5, ms(1000)),
+                Err(RecvTimeoutError::Timeout)
+            );
+            assert_eq!(r.recv_timeout_where(|&x| x == 5, ms(1000)), Ok(5));
+        });
+
+        for i in 0..3 {
+            s.send(i).unwrap();
+        }
+        thread::sleep(ms(1500));
+        s.send(5).unwrap();
+    });
+
+    assert_eq!(r.try_recv(), Ok(0));
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    drop(s);
+    assert_eq!(r.recv_where(|_| true), Err(RecvError));
+}
+
+#[test]
+fn recv_where_pred_receives() {
+    let (s, r) = unbounded();
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+
+    // The predicate doesn't run under a lock, so it may use the channel itself.
+    let res = r.recv_where(|&x| {
+        if x == 0 {
+            assert_eq!(r.try_recv(), Ok(1));
+        }
+        x == 0
+    });
+    assert_eq!(res, Ok(0));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn recv_where_wakes_receiver() {
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            // The selective receiver might grab the message first and set it aside, in which case
+            // this receiver must be woken up to take it.
+            assert_eq!(r.recv(), Ok(1));
+        });
+        scope.spawn(|| {
+            assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+        });
+
+        thread::sleep(ms(500));
+        s.send(1).unwrap();
+        thread::sleep(ms(500));
+        s.send(2).unwrap();
+    });
+}
+
+#[test]
+fn recv_where_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded::<usize>();
+    let evens = AtomicUsize::new(0);
+    let others = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            let s = s.clone();
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+            scope.spawn(|| {
+                while let Ok(x) = r.recv_where(|x| x % 2 == 0) {
+                    assert_eq!(x % 2, 0);
+                    evens.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+            scope.spawn(|| {
+                while r.recv().is_ok() {
+                    others.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        drop(s);
+    });
+
+    let total = evens.load(Ordering::SeqCst) + others.load(Ordering::SeqCst);
+    assert_eq!(total, COUNT * THREADS);
+    assert!(r.is_empty());
+}
+
+#[test]
+fn recv_latest() {
+    const COUNT: usize = 100_000;
+
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+        });
+
+        let mut last = None;
+        let mut total = 0;
+        while let Ok((msg, discarded)) = r.recv_latest() {
+            if let Some(last) = last {
+                assert_eq!(msg, last + discarded + 1);
+            }
+            last = Some(msg);
+            total += discarded + 1;
+        }
+        assert_eq!(last, Some(COUNT - 1));
+        assert_eq!(total, COUNT);
+    });
+}
+
+#[test]
+fn send_lossy() {
+    let (s, r) = unbounded();
+    for i in 0..100 {
+        assert_eq!(s.send_lossy(i), Ok(None));
+    }
+    assert_eq!(r.len(), 100);
+
+    drop(r);
+    assert_eq!(s.send_lossy(100), Err(SendError(100)));
+}
+
 #[test]
 fn try_send() {
     let (s, r) = unbounded();
@@ -178,6 +323,27 @@ fn send_after_disconnect() {
     assert_eq!(s.send_timeout(6, ms(0)), Err(SendTimeoutError::Disconnected(6)));
 }
 
+#[test]
+fn reserve() {
+    let (s, r) = unbounded();
+
+    let p1 = s.reserve().unwrap();
+    let p2 = s.try_reserve().unwrap();
+    s.send(1).unwrap();
+    p2.send(2).unwrap();
+    p1.send(3).unwrap();
+
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.try_recv(), Ok(3));
+
+    let p = s.reserve().unwrap();
+    drop(r);
+    assert_eq!(p.send(4), Err(SendError(4)));
+    assert_eq!(s.reserve().unwrap_err(), SendError(()));
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Disconnected(()));
+}
+
 #[test]
 fn recv_after_disconnect() {
     let (s, r) = unbounded();
@@ -363,6 +529,88 @@ fn drops() {
     }
 }
 
+
+#[test]
+fn block_pool_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &cap in &[1, 64] {
+        let (s, r) = Builder::<usize>::new().block_pool(cap).unbounded();
+        let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+        crossbeam::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..COUNT {
+                        let n = r.recv().unwrap();
+                        v[n].fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+        for c in v {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+}
+
+#[test]
+fn block_pool_drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut rng = thread_rng();
+
+    for _ in 0..100 {
+        let steps = rng.gen_range(0, 10_000);
+        let additional = rng.gen_range(0, 1000);
+
+        DROPS.store(0, Ordering::SeqCst);
+        let (s, r) = Builder::new().block_pool(64).unbounded();
+
+        // Receive in bursts so that blocks get recycled.
+        let mut received = 0;
+        while received < steps {
+            let burst = rng.gen_range(1, 200).min(steps - received);
+            for _ in 0..burst {
+                s.send(DropCounter).unwrap();
+            }
+            for _ in 0..burst {
+                r.recv().unwrap();
+            }
+            received += burst;
+        }
+
+        for _ in 0..additional {
+            s.try_send(DropCounter).unwrap();
+        }
+
+        assert_eq!(DROPS.load(Ordering::SeqCst), steps);
+        drop(s);
+        drop(r);
+        assert_eq!(DROPS.load(Ordering::SeqCst), steps + additional);
+    }
+}
+
 #[test]
 fn linearizable() {
     const COUNT: usize = 25_000;
@@ -0,0 +1,167 @@
+//! Tests for `manual_ticker`.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{manual_ticker, unbounded, RecvError, RecvTimeoutError, Select};
+use crossbeam_channel::TryRecvError;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (driver, ticker) = manual_ticker();
+    assert_eq!(ticker.try_recv(), Err(TryRecvError::Empty));
+
+    let before = Instant::now();
+    assert!(driver.fire());
+    let msg = ticker.try_recv().unwrap();
+    assert!(msg >= before);
+    assert!(msg <= Instant::now());
+
+    assert_eq!(ticker.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn fire_at() {
+    let (driver, ticker) = manual_ticker();
+    let instant = Instant::now() + ms(1000);
+
+    assert!(driver.fire_at(instant));
+    assert_eq!(ticker.recv(), Ok(instant));
+}
+
+#[test]
+fn coalesce() {
+    let (driver, ticker) = manual_ticker();
+    let start = Instant::now();
+
+    assert!(driver.fire_at(start));
+    assert!(driver.is_pending());
+    assert!(!driver.fire_at(start + ms(1)));
+    assert!(!driver.fire_at(start + ms(2)));
+
+    // The earliest pending tick is kept.
+    assert_eq!(ticker.len(), 1);
+    assert_eq!(ticker.recv(), Ok(start));
+    assert_eq!(ticker.try_recv(), Err(TryRecvError::Empty));
+    assert!(!driver.is_pending());
+}
+
+#[test]
+fn disconnect() {
+    let (driver, ticker) = manual_ticker();
+    let other = driver.clone();
+
+    driver.fire();
+    drop(driver);
+    assert!(other.is_pending());
+    drop(other);
+
+    // The last tick is still delivered.
+    assert!(ticker.recv().is_ok());
+    assert_eq!(ticker.recv(), Err(RecvError));
+}
+
+#[test]
+fn receiver_dropped() {
+    let (driver, ticker) = manual_ticker();
+    drop(ticker);
+    assert!(!driver.fire());
+}
+
+#[test]
+fn recv_timeout() {
+    let (driver, ticker) = manual_ticker();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            driver.fire();
+        });
+
+        assert_eq!(ticker.recv_timeout(ms(20)), Err(RecvTimeoutError::Timeout));
+        assert!(ticker.recv_timeout(ms(1000)).is_ok());
+    });
+}
+
+#[test]
+fn select_macro() {
+    let (driver, ticker) = manual_ticker();
+    let (s, r) = unbounded::<i32>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..3 {
+                thread::sleep(ms(50));
+                while driver.is_pending() {
+                    thread::sleep(ms(1));
+                }
+                driver.fire();
+            }
+            thread::sleep(ms(50));
+            s.send(7).unwrap();
+        });
+
+        let mut ticks = 0;
+        loop {
+            select! {
+                recv(ticker) -> _ => ticks += 1,
+                recv(r) -> msg => {
+                    assert_eq!(msg, Ok(7));
+                    break;
+                }
+            }
+        }
+        assert_eq!(ticks, 3);
+    });
+}
+
+#[test]
+fn select_object() {
+    let (driver, ticker) = manual_ticker();
+    let (_s, r) = unbounded::<i32>();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r);
+    let oper2 = sel.recv(&ticker);
+    assert!(sel.try_select().is_err());
+
+    driver.fire();
+    let oper = sel.select();
+    assert_ne!(oper.index(), oper1);
+    assert_eq!(oper.index(), oper2);
+    assert!(oper.recv(&ticker).is_ok());
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+
+    let (driver, ticker) = manual_ticker();
+
+    crossbeam::scope(|scope| {
+        let delivered = scope.spawn(|| {
+            let mut delivered = 0;
+            for _ in 0..COUNT {
+                if driver.fire() {
+                    delivered += 1;
+                }
+            }
+            drop(driver);
+            delivered
+        });
+
+        let mut received = 0;
+        while ticker.recv().is_ok() {
+            received += 1;
+        }
+        assert_eq!(received, delivered.join());
+    });
+}
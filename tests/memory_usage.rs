@@ -0,0 +1,109 @@
+//! Tests for `Sender::memory_usage` and `Receiver::memory_usage`.
+
+extern crate crossbeam_channel;
+
+use std::mem;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, lanes, never, tick, unbounded};
+
+#[test]
+fn bounded_is_preallocated() {
+    let (s, r) = bounded::<[u8; 64]>(100);
+    let usage = r.memory_usage();
+    assert!(usage >= 100 * 64);
+    assert_eq!(s.memory_usage(), usage);
+
+    for _ in 0..100 {
+        s.send([0; 64]).unwrap();
+    }
+    assert_eq!(r.memory_usage(), usage);
+
+    for _ in 0..100 {
+        r.recv().unwrap();
+    }
+    assert_eq!(r.memory_usage(), usage);
+}
+
+#[test]
+fn larger_capacity() {
+    let (_s1, r1) = bounded::<u64>(10);
+    let (_s2, r2) = bounded::<u64>(1000);
+    assert!(r2.memory_usage() >= r1.memory_usage() + 990 * 8);
+}
+
+#[test]
+fn unbounded_grows_and_shrinks() {
+    let (s, r) = unbounded::<[u8; 64]>();
+    let empty = r.memory_usage();
+
+    for _ in 0..1000 {
+        s.send([0; 64]).unwrap();
+    }
+    let full = r.memory_usage();
+    assert!(full >= empty + 1000 * 64);
+    assert_eq!(s.memory_usage(), full);
+
+    for _ in 0..500 {
+        r.recv().unwrap();
+    }
+    let half = r.memory_usage();
+    assert!(half < full);
+    assert!(half >= empty + 500 * 64);
+
+    for _ in 0..500 {
+        r.recv().unwrap();
+    }
+    assert!(r.memory_usage() < empty + 64 * 64);
+}
+
+#[test]
+fn lanes_grow() {
+    let (s, r) = lanes::<u64>(1000, 3);
+    let empty = r.memory_usage();
+
+    for i in 0..900 {
+        s.send_with_priority(i, i as usize % 3).unwrap();
+    }
+    assert!(r.memory_usage() >= empty + 900 * 8);
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded::<[u8; 64]>(0);
+    let usage = r.memory_usage();
+    assert!(usage >= mem::size_of::<usize>());
+    // The latency histogram is allocated up front.
+    let histogram = if cfg!(feature = "latency") { 8 * 1024 } else { 0 };
+    assert!(usage < 64 * 64 + histogram);
+    assert_eq!(s.memory_usage(), usage);
+}
+
+#[test]
+fn stashed_messages() {
+    let (s, r) = bounded::<[u8; 64]>(10);
+    let usage = r.memory_usage();
+
+    for i in 0..10 {
+        s.send([i; 64]).unwrap();
+    }
+    assert_eq!(r.recv_where(|msg| msg[0] == 9), Ok([9; 64]));
+
+    // The nine messages set aside are held outside of the buffer.
+    assert!(r.memory_usage() >= usage + 9 * 64);
+
+    while r.try_recv().is_ok() {}
+    assert_eq!(r.memory_usage(), usage);
+}
+
+#[test]
+fn timers() {
+    let r = after(Duration::from_secs(1));
+    assert!(r.memory_usage() > 0);
+
+    let r = tick(Duration::from_secs(1));
+    assert!(r.memory_usage() > 0);
+
+    let r = never::<[u8; 64]>();
+    assert!(r.memory_usage() < 64);
+}
@@ -0,0 +1,90 @@
+//! Tests for the `miri` feature.
+
+#![cfg(feature = "miri")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crossbeam_channel::{bounded, unbounded};
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    for i in 0..100 {
+        s.send(i).unwrap();
+    }
+    for i in 0..100 {
+        assert_eq!(r.recv(), Ok(i));
+    }
+    assert!(r.try_recv().is_err());
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 1000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded::<usize>();
+    let sum = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..COUNT {
+                    sum.fetch_add(r.recv().unwrap(), Ordering::SeqCst);
+                }
+            });
+        }
+    });
+
+    assert_eq!(sum.load(Ordering::SeqCst), THREADS * COUNT * (COUNT - 1) / 2);
+}
+
+#[test]
+fn drops() {
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+    for _ in 0..100 {
+        s.send(DropCounter(drops.clone())).unwrap();
+    }
+    for _ in 0..40 {
+        r.recv().unwrap();
+    }
+    assert_eq!(drops.load(Ordering::SeqCst), 40);
+
+    drop(s);
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), 100);
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..100 {
+                s.send(i).unwrap();
+            }
+        });
+        for i in 0..100 {
+            assert_eq!(r.recv(), Ok(i));
+        }
+    });
+}
@@ -0,0 +1,162 @@
+//! Tests for multiplexers.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded, Mux};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[derive(Debug, PartialEq)]
+enum Msg {
+    A(i32),
+    B(&'static str),
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = mux! { Msg::A = r1, Msg::B = r2 };
+
+    s1.send(1).unwrap();
+    assert_eq!(r.recv(), Ok(Msg::A(1)));
+    s2.send("foo").unwrap();
+    assert_eq!(r.recv(), Ok(Msg::B("foo")));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn empty() {
+    let r = Mux::<Msg>::new().build();
+    assert_eq!(r.recv(), Err(RecvError));
+
+    let r: crossbeam_channel::Receiver<Msg> = mux! {};
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn disconnect() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = mux! { Msg::A = r1, Msg::B = r2, };
+
+    s1.send(1).unwrap();
+    s1.send(2).unwrap();
+    drop(s1);
+    assert_eq!(r.recv(), Ok(Msg::A(1)));
+    assert_eq!(r.recv(), Ok(Msg::A(2)));
+
+    // The other receiver still delivers messages.
+    s2.send("foo").unwrap();
+    assert_eq!(r.recv(), Ok(Msg::B("foo")));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+
+    drop(s2);
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn receiver_dropped() {
+    let (s, r1) = bounded(0);
+    let r = Mux::new().recv(r1, Msg::A).build();
+    drop(r);
+
+    // The background thread stops even though no messages arrive, dropping the original
+    // receiver.
+    thread::sleep(ms(100));
+    assert_eq!(s.send(1), Err(SendError(1)));
+}
+
+#[test]
+fn receiver_dropped_keeps_messages() {
+    let (s, r1) = unbounded();
+    let r2 = r1.clone();
+    let r = Mux::new().recv(r1, Msg::A).build();
+    drop(r);
+
+    // Messages are left to other receivers of the original channel.
+    thread::sleep(ms(100));
+    s.send(1).unwrap();
+    assert_eq!(r2.recv_timeout(ms(100)), Ok(1));
+}
+
+#[test]
+fn no_buffering() {
+    let (s1, r1) = bounded(1);
+    let r = mux! { Msg::A = r1 };
+
+    // One message is buffered in the combined channel and one stays in the original channel.
+    s1.send(1).unwrap();
+    thread::sleep(ms(100));
+    s1.send(2).unwrap();
+    assert!(s1.try_send(3).is_err());
+
+    assert_eq!(r.recv(), Ok(Msg::A(1)));
+    assert_eq!(r.recv(), Ok(Msg::A(2)));
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = mux! { Msg::A = r1, Msg::B = r2 };
+    let (_s3, r3) = unbounded::<()>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            s2.send("bar").unwrap();
+        });
+
+        select! {
+            recv(r) -> msg => assert_eq!(msg, Ok(Msg::B("bar"))),
+            recv(r3) -> _ => panic!(),
+            default(ms(1000)) => panic!(),
+        }
+    });
+    drop(s1);
+}
+
+#[test]
+fn stress() {
+    const COUNT: i32 = 10_000;
+
+    let (s1, r1) = bounded(5);
+    let (s2, r2) = unbounded();
+    let r = mux! { Msg::A = r1, Msg::B = r2 };
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s1.send(i).unwrap();
+            }
+        });
+        scope.spawn(move || {
+            for _ in 0..COUNT {
+                s2.send("x").unwrap();
+            }
+        });
+
+        let mut next = 0;
+        let mut others = 0;
+        for msg in r.iter() {
+            match msg {
+                Msg::A(i) => {
+                    assert_eq!(i, next);
+                    next += 1;
+                }
+                Msg::B(_) => others += 1,
+            }
+        }
+        assert_eq!(next, COUNT);
+        assert_eq!(others, COUNT);
+    });
+}
@@ -0,0 +1,211 @@
+//! Tests for channels that restore the order of messages.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+extern crate rand;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded_ordered, unbounded_ordered, unbounded, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use rand::{thread_rng, Rng};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded_ordered();
+    s.send(0, 'a').unwrap();
+    assert_eq!(r.try_recv(), Ok('a'));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn reorder() {
+    let (s, r) = unbounded_ordered();
+    for &i in &[3, 1, 4, 0, 2] {
+        s.send(i, i * 10).unwrap();
+    }
+
+    assert_eq!(r.len(), 5);
+    assert_eq!(r.recv(), Ok(0));
+    assert_eq!(r.buffered(), 3);
+    assert_eq!(r.len(), 1);
+
+    assert_eq!(r.recv(), Ok(10));
+    assert_eq!(r.recv(), Ok(20));
+    assert_eq!(r.recv(), Ok(30));
+    assert_eq!(r.recv(), Ok(40));
+    assert_eq!(r.next_sequence(), 5);
+    assert_eq!(r.buffered(), 0);
+}
+
+#[test]
+fn waits_for_gap() {
+    let (s, r) = unbounded_ordered();
+    s.send(1, "second").unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(0, "first").unwrap();
+        });
+
+        let start = Instant::now();
+        assert_eq!(r.recv(), Ok("first"));
+        assert!(start.elapsed() >= ms(50));
+        assert_eq!(r.try_recv(), Ok("second"));
+    });
+}
+
+#[test]
+fn recv_timeout() {
+    let (s, r) = unbounded_ordered();
+    s.send(1, 'b').unwrap();
+
+    assert_eq!(r.recv_timeout(ms(50)), Err(RecvTimeoutError::Timeout));
+    assert_eq!(r.buffered(), 1);
+
+    s.send(0, 'a').unwrap();
+    assert_eq!(r.recv_timeout(ms(50)), Ok('a'));
+    assert_eq!(r.recv_timeout(ms(50)), Ok('b'));
+}
+
+#[test]
+fn disconnect_flushes_buffer() {
+    let (s, r) = unbounded_ordered();
+    s.send(5, 'c').unwrap();
+    s.send(2, 'b').unwrap();
+    s.send(0, 'a').unwrap();
+    drop(s);
+
+    // Missing sequence numbers are skipped once no more messages can arrive.
+    assert_eq!(r.recv(), Ok('a'));
+    assert_eq!(r.recv(), Ok('b'));
+    assert_eq!(r.next_sequence(), 3);
+    assert_eq!(r.try_recv(), Ok('c'));
+    assert_eq!(r.next_sequence(), 6);
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r.recv_timeout(ms(10)), Err(RecvTimeoutError::Disconnected));
+}
+
+#[test]
+fn stale_and_duplicate() {
+    let (s, r) = unbounded_ordered();
+    s.send(0, 'a').unwrap();
+    assert_eq!(r.recv(), Ok('a'));
+
+    // Already delivered.
+    s.send(0, 'x').unwrap();
+    // Sent twice.
+    s.send(1, 'b').unwrap();
+    s.send(1, 'y').unwrap();
+    s.send(2, 'c').unwrap();
+    drop(s);
+
+    assert_eq!(r.recv(), Ok('b'));
+    assert_eq!(r.recv(), Ok('c'));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn bounded_does_not_block_on_gap() {
+    let (s, r) = bounded_ordered(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            // The first message is sent last, so every other one has to be buffered.
+            for i in (0..100).rev() {
+                s.send(i, i).unwrap();
+            }
+        });
+
+        for i in 0..100 {
+            assert_eq!(r.recv(), Ok(i));
+        }
+    });
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded_ordered(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.send(1, 'b').unwrap();
+            s.send(0, 'a').unwrap();
+        });
+
+        assert_eq!(r.recv(), Ok('a'));
+        assert_eq!(r.recv(), Ok('b'));
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded_ordered();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.send(1, "second").unwrap();
+            thread::sleep(ms(100));
+            s.send(0, "first").unwrap();
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv_ready(&r);
+
+        // The message that arrived early makes the operation ready, but isn't received yet.
+        let mut v = Vec::new();
+        while v.is_empty() {
+            let oper = sel.select_timeout(ms(1000)).unwrap();
+            assert_eq!(oper.index(), oper1);
+            oper.recv_ready(&r);
+            v.extend(r.try_recv());
+        }
+        assert_eq!(v, ["first"]);
+
+        // The buffered message is ready right away.
+        let oper = sel.select_timeout(ms(0)).unwrap();
+        oper.recv_ready(&r);
+        assert_eq!(r.try_recv(), Ok("second"));
+    });
+}
+
+#[test]
+fn parallel_pipeline() {
+    const COUNT: u64 = 10_000;
+    const WORKERS: usize = 4;
+
+    let (input_s, input_r) = unbounded();
+    let (s, r) = bounded_ordered(16);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..WORKERS {
+            let input_r = input_r.clone();
+            let s = s.clone();
+            scope.spawn(move || {
+                let mut rng = thread_rng();
+                for i in input_r {
+                    if rng.gen_range(0, 100) == 0 {
+                        thread::yield_now();
+                    }
+                    s.send(i, i * 2).unwrap();
+                }
+            });
+        }
+        drop(s);
+
+        for i in 0..COUNT {
+            input_s.send(i).unwrap();
+        }
+        drop(input_s);
+
+        let v: Vec<_> = r.iter().collect();
+        assert_eq!(v, (0..COUNT).map(|i| i * 2).collect::<Vec<_>>());
+    });
+}
@@ -0,0 +1,178 @@
+//! Tests for custom parkers.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, never, set_parker, unbounded, Parker};
+use crossbeam_channel::{RecvTimeoutError, Select};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// A parker based on a condition variable that counts how many times it was used.
+struct CountingParker {
+    token: Mutex<bool>,
+    cvar: Condvar,
+    parks: AtomicUsize,
+    unparks: AtomicUsize,
+}
+
+impl Parker for CountingParker {
+    fn park(&self, deadline: Option<Instant>) {
+        self.parks.fetch_add(1, Ordering::SeqCst);
+
+        let mut token = self.token.lock().unwrap();
+        while !*token {
+            match deadline {
+                None => token = self.cvar.wait(token).unwrap(),
+                Some(d) => {
+                    let now = Instant::now();
+                    if now >= d {
+                        return;
+                    }
+                    token = self.cvar.wait_timeout(token, d - now).unwrap().0;
+                }
+            }
+        }
+        *token = false;
+    }
+
+    fn unpark(&self) {
+        self.unparks.fetch_add(1, Ordering::SeqCst);
+
+        *self.token.lock().unwrap() = true;
+        self.cvar.notify_one();
+    }
+}
+
+thread_local! {
+    static PARKER: Arc<CountingParker> = Arc::new(CountingParker {
+        token: Mutex::new(false),
+        cvar: Condvar::new(),
+        parks: AtomicUsize::new(0),
+        unparks: AtomicUsize::new(0),
+    });
+}
+
+fn current() -> Arc<Parker> {
+    PARKER.with(|p| p.clone())
+}
+
+fn counts() -> (usize, usize) {
+    PARKER.with(|p| {
+        (
+            p.parks.load(Ordering::SeqCst),
+            p.unparks.load(Ordering::SeqCst),
+        )
+    })
+}
+
+#[test]
+fn recv() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            set_parker(Some(current));
+            assert_eq!(r.recv(), Ok(7));
+            let (parks, unparks) = counts();
+            assert!(parks > 0);
+            assert!(unparks > 0);
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+        });
+    });
+}
+
+#[test]
+fn send() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            set_parker(Some(current));
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+            assert!(counts().0 > 0);
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+            assert_eq!(r.recv(), Ok(2));
+        });
+    });
+}
+
+#[test]
+fn timeout() {
+    let (_s, r) = unbounded::<i32>();
+
+    set_parker(Some(current));
+    let start = Instant::now();
+    assert_eq!(r.recv_timeout(ms(200)), Err(RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= ms(200));
+    assert!(counts().0 > 0);
+
+    let (parks, _) = counts();
+    assert_eq!(
+        never::<i32>().recv_timeout(ms(200)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    assert!(counts().0 > parks);
+
+    let (parks, _) = counts();
+    assert!(Select::new().select_timeout(ms(200)).is_err());
+    assert!(counts().0 > parks);
+    set_parker(None);
+}
+
+#[test]
+fn restore() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            set_parker(Some(current));
+            set_parker(None);
+            assert_eq!(r.recv(), Ok(7));
+            assert_eq!(counts(), (0, 0));
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+        });
+    });
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+
+    let (s1, r1) = bounded(0);
+    let (s2, r2) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            set_parker(Some(current));
+            for i in 0..COUNT {
+                s1.send(i).unwrap();
+                assert_eq!(r2.recv(), Ok(i));
+            }
+        });
+        scope.spawn(|| {
+            set_parker(Some(current));
+            for i in 0..COUNT {
+                assert_eq!(r1.recv(), Ok(i));
+                s2.send(i).unwrap();
+            }
+        });
+    });
+}
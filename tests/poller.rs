@@ -0,0 +1,284 @@
+//! Tests for `Poller`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, never, tick, unbounded};
+use crossbeam_channel::{Poller, Trigger};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    let mut poller = Poller::new();
+    poller.recv(&r, 1);
+
+    let mut events = Vec::new();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 0);
+    assert!(events.is_empty());
+
+    s.send(7).unwrap();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 1);
+    assert_eq!(events, [1]);
+    assert_eq!(r.try_recv(), Ok(7));
+
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 0);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn send() {
+    let (s, r) = bounded(1);
+    let mut poller = Poller::new();
+    poller.send(&s, 10);
+
+    let mut events = Vec::new();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 1);
+    assert_eq!(events, [10]);
+
+    s.send(1).unwrap();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            r.recv().unwrap();
+        });
+
+        assert_eq!(poller.poll(&mut events, Some(ms(1000))), 1);
+        assert_eq!(events, [10]);
+    });
+}
+
+#[test]
+fn level_triggered() {
+    let (s, r) = unbounded();
+    let mut poller = Poller::new();
+    poller.recv(&r, 0);
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let mut events = Vec::new();
+    for _ in 0..3 {
+        assert_eq!(poller.poll(&mut events, Some(ms(0))), 1);
+        assert_eq!(events, [0]);
+    }
+
+    r.try_recv().unwrap();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 1);
+    r.try_recv().unwrap();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 0);
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = unbounded::<i32>();
+    let mut poller = Poller::new();
+    poller.recv(&r, 3);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            drop(s);
+        });
+
+        let mut events = Vec::new();
+        assert_eq!(poller.poll(&mut events, None), 1);
+        assert_eq!(events, [3]);
+        assert!(r.try_recv().is_err());
+    });
+}
+
+#[test]
+fn timeout() {
+    let (s, r) = unbounded::<i32>();
+    let mut poller = Poller::new();
+    poller.recv(&r, 0);
+
+    let mut events = vec![1, 2, 3];
+    let start = Instant::now();
+    assert_eq!(poller.poll(&mut events, Some(ms(100))), 0);
+    assert!(events.is_empty());
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= ms(100));
+    assert!(elapsed < ms(1000));
+    drop(s);
+}
+
+#[test]
+fn wake_from_other_thread() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+    let mut poller = Poller::new();
+    poller.recv(&r1, 1);
+    poller.recv(&r2, 2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s1.send(()).unwrap();
+            thread::sleep(ms(100));
+            s2.send(()).unwrap();
+        });
+
+        let mut events = Vec::new();
+        assert_eq!(poller.poll(&mut events, None), 1);
+        assert_eq!(events, [1]);
+        r1.recv().unwrap();
+
+        assert_eq!(poller.poll(&mut events, None), 1);
+        assert_eq!(events, [2]);
+        r2.recv().unwrap();
+    });
+}
+
+#[test]
+fn many_channels() {
+    const COUNT: usize = 200;
+
+    let channels: Vec<_> = (0..COUNT).map(|_| unbounded()).collect();
+    let mut poller = Poller::new();
+    for (i, &(_, ref r)) in channels.iter().enumerate() {
+        poller.recv(r, i * 10);
+    }
+    assert_eq!(poller.len(), COUNT);
+
+    let mut events = Vec::new();
+    for i in (0..COUNT).filter(|i| i % 7 == 3) {
+        channels[i].0.send(i).unwrap();
+    }
+    poller.poll(&mut events, Some(ms(0)));
+    events.sort();
+    let expected: Vec<_> = (0..COUNT).filter(|i| i % 7 == 3).map(|i| i * 10).collect();
+    assert_eq!(events, expected);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                channels[i].1.try_recv().ok();
+            }
+            thread::sleep(ms(100));
+            channels[123].0.send(0).unwrap();
+        });
+
+        thread::sleep(ms(50));
+        assert_eq!(poller.poll(&mut events, Some(ms(1000))), 1);
+        assert_eq!(events, [1230]);
+    });
+}
+
+#[test]
+fn deregister() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let mut poller = Poller::new();
+    poller.recv(&r1, 1);
+    poller.recv(&r2, 2);
+
+    assert!(poller.contains(1));
+    assert!(poller.deregister(1));
+    assert!(!poller.contains(1));
+    assert!(!poller.deregister(1));
+    assert_eq!(poller.len(), 1);
+
+    s1.send(()).unwrap();
+    let mut events = Vec::new();
+    assert_eq!(poller.poll(&mut events, Some(ms(100))), 0);
+
+    // The token can be registered again, reusing the vacant slot.
+    poller.recv(&r1, 1);
+    s2.send(()).unwrap();
+    poller.poll(&mut events, Some(ms(0)));
+    events.sort();
+    assert_eq!(events, [1, 2]);
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn duplicate_token() {
+    let (s, r) = unbounded::<i32>();
+    let mut poller = Poller::new();
+    poller.recv(&r, 0);
+    poller.send(&s, 0);
+}
+
+#[test]
+fn timers() {
+    let start = Instant::now();
+    let a = after(ms(100));
+    let t = tick(ms(150));
+    let n = never::<()>();
+    let mut poller = Poller::new();
+    poller.recv(&a, 0);
+    poller.recv(&t, 1);
+    poller.recv(&n, 2);
+
+    let mut events = Vec::new();
+    assert_eq!(poller.poll(&mut events, None), 1);
+    assert_eq!(events, [0]);
+    assert!(start.elapsed() >= ms(100));
+    a.recv().unwrap();
+
+    assert_eq!(poller.poll(&mut events, None), 1);
+    assert_eq!(events, [1]);
+    assert!(start.elapsed() >= ms(150));
+    t.recv().unwrap();
+}
+
+#[test]
+fn custom() {
+    let trigger = Trigger::new();
+    let mut poller = Poller::new();
+    poller.custom(&trigger, 5);
+
+    let mut events = Vec::new();
+    assert_eq!(poller.poll(&mut events, Some(ms(0))), 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            trigger.set();
+        });
+
+        assert_eq!(poller.poll(&mut events, None), 1);
+        assert_eq!(events, [5]);
+    });
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+
+    let channels: Vec<_> = (0..4).map(|_| bounded(1)).collect();
+    let mut poller = Poller::new();
+    for (i, &(_, ref r)) in channels.iter().enumerate() {
+        poller.recv(r, i);
+    }
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                channels[i % 4].0.send(i).unwrap();
+            }
+        });
+
+        let mut events = Vec::new();
+        let mut received = 0;
+        while received < COUNT {
+            poller.poll(&mut events, None);
+            for &token in &events {
+                if channels[token].1.try_recv().is_ok() {
+                    received += 1;
+                }
+            }
+        }
+    });
+}
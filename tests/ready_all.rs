@@ -0,0 +1,167 @@
+//! Tests for `Select::ready_all`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, never, unbounded, Select, Trigger};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded();
+    let (_s2, r2) = unbounded::<i32>();
+    let (s3, r3) = unbounded();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+    let oper3 = sel.recv(&r3);
+
+    let mut ready = vec![7, 8, 9];
+    assert_eq!(sel.ready_all(&mut ready), 0);
+    assert!(ready.is_empty());
+
+    s1.send(1).unwrap();
+    s3.send(3).unwrap();
+    assert_eq!(sel.ready_all(&mut ready), 2);
+    assert_eq!(ready, [oper1, oper3]);
+    assert!(!ready.contains(&oper2));
+
+    // Checking readiness doesn't receive anything.
+    assert_eq!(r1.try_recv(), Ok(1));
+    assert_eq!(r3.try_recv(), Ok(3));
+}
+
+#[test]
+fn send_operations() {
+    let (s1, _r1) = bounded::<i32>(1);
+    let (s2, _r2) = bounded(1);
+    s2.send(0).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.send(&s1);
+    sel.send(&s2);
+
+    let mut ready = Vec::new();
+    sel.ready_all(&mut ready);
+    assert_eq!(ready, [oper1]);
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = unbounded::<i32>();
+    let (_s2, r2) = unbounded::<i32>();
+    drop(s);
+
+    let mut sel = Select::new();
+    let oper = sel.recv(&r);
+    sel.recv(&r2);
+
+    let mut ready = Vec::new();
+    sel.ready_all(&mut ready);
+    assert_eq!(ready, [oper]);
+}
+
+#[test]
+fn disarmed() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let (s3, r3) = unbounded();
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+    s3.send(3).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+    let oper3 = sel.recv(&r3);
+
+    let mut ready = Vec::new();
+    sel.disarm(oper1);
+    sel.ready_all(&mut ready);
+    assert_eq!(ready, [oper2, oper3]);
+
+    // Rearmed operations are still reported in order.
+    sel.rearm(oper1);
+    sel.ready_all(&mut ready);
+    assert_eq!(ready, [oper1, oper2, oper3]);
+}
+
+#[test]
+fn special_flavors() {
+    let a = after(ms(50));
+    let n = never::<i32>();
+    let t = Trigger::new();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&a);
+    sel.recv(&n);
+    let oper3 = sel.trigger(&t);
+
+    let mut ready = Vec::new();
+    assert_eq!(sel.ready_all(&mut ready), 0);
+
+    t.set();
+    sel.ready_all(&mut ready);
+    assert_eq!(ready, [oper3]);
+
+    thread::sleep(ms(100));
+    sel.ready_all(&mut ready);
+    assert_eq!(ready, [oper1, oper3]);
+}
+
+#[test]
+fn empty() {
+    let sel = Select::new();
+    let mut ready = vec![1];
+    assert_eq!(sel.ready_all(&mut ready), 0);
+    assert!(ready.is_empty());
+}
+
+#[test]
+fn poller() {
+    const COUNT: usize = 1000;
+    const CHANNELS: usize = 8;
+
+    let channels: Vec<_> = (0..CHANNELS).map(|_| unbounded::<usize>()).collect();
+
+    crossbeam::scope(|scope| {
+        for (i, (s, _)) in channels.iter().enumerate() {
+            scope.spawn(move || {
+                for j in 0..COUNT {
+                    s.send(i * COUNT + j).unwrap();
+                }
+            });
+        }
+
+        let mut sel = Select::new();
+        for (_, r) in &channels {
+            sel.recv(r);
+        }
+
+        let mut ready = Vec::new();
+        let mut next = vec![0; CHANNELS];
+        let mut received = 0;
+        while received < COUNT * CHANNELS {
+            // Wait until something is ready, then service every ready channel.
+            if sel.ready_all(&mut ready) == 0 {
+                thread::yield_now();
+                continue;
+            }
+            for &i in &ready {
+                while let Ok(msg) = channels[i].1.try_recv() {
+                    assert_eq!(msg, i * COUNT + next[i]);
+                    next[i] += 1;
+                    received += 1;
+                }
+            }
+        }
+        assert!(next.iter().all(|&n| n == COUNT));
+    });
+}
@@ -0,0 +1,180 @@
+//! Tests for taking back messages that were never received.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, never, unbounded, Builder, DropPolicy, SendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn into_remaining() {
+    let (s, r) = unbounded();
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+
+    assert_eq!(r.recv(), Ok(0));
+    assert_eq!(r.into_remaining(), [1, 2, 3, 4]);
+
+    // The last receiver is gone, so the channel is disconnected.
+    assert_eq!(s.send(5), Err(SendError(5)));
+}
+
+#[test]
+fn into_remaining_other_receivers() {
+    let (s, r1) = bounded(10);
+    let r2 = r1.clone();
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r1.into_remaining(), [1, 2]);
+
+    // The other receiver keeps the channel connected.
+    s.send(3).unwrap();
+    assert_eq!(r2.recv(), Ok(3));
+}
+
+#[test]
+fn into_remaining_empty() {
+    let (_s, r) = unbounded::<i32>();
+    assert!(r.into_remaining().is_empty());
+
+    let (s, r) = bounded::<i32>(0);
+    assert!(r.into_remaining().is_empty());
+    assert_eq!(s.send(1), Err(SendError(1)));
+}
+
+#[test]
+fn into_remaining_special_flavors() {
+    let r = after(ms(0));
+    thread::sleep(ms(10));
+    assert_eq!(r.into_remaining().len(), 1);
+
+    assert!(never::<i32>().into_remaining().is_empty());
+}
+
+#[test]
+fn into_remaining_racing_senders() {
+    const COUNT: usize = 10_000;
+
+    for cap in &[1, 100] {
+        let (s, r) = bounded(*cap);
+
+        crossbeam::scope(|scope| {
+            let sent = scope.spawn(|| {
+                let mut sent = 0;
+                while s.send(()).is_ok() {
+                    sent += 1;
+                }
+                sent
+            });
+
+            let mut received = 0;
+            for _ in 0..COUNT {
+                r.recv().unwrap();
+                received += 1;
+            }
+            received += r.into_remaining().len();
+
+            // Every message that was accepted was either received or returned.
+            assert_eq!(received, sent.join());
+        });
+    }
+}
+
+#[test]
+fn reclaim_unconsumed() {
+    let (s, r) = Builder::new().drop_policy(DropPolicy::Keep).bounded(10);
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(s.reclaim_unconsumed(), None);
+
+    assert_eq!(r.recv(), Ok(0));
+    drop(r);
+    assert_eq!(s.pending_on_disconnect(), Some(4));
+
+    assert_eq!(s.reclaim_unconsumed(), Some(vec![1, 2, 3, 4]));
+    assert_eq!(s.pending_on_disconnect(), Some(0));
+    assert_eq!(s.reclaim_unconsumed(), Some(vec![]));
+}
+
+#[test]
+fn reclaim_from_clone() {
+    let (s, r) = Builder::new().drop_policy(DropPolicy::Keep).unbounded();
+    let s2 = s.clone();
+
+    s.send(1).unwrap();
+    s2.send(2).unwrap();
+    drop(r);
+
+    // Every sender sees the same messages, but each message is reclaimed only once.
+    assert_eq!(s2.reclaim_unconsumed(), Some(vec![1, 2]));
+    assert_eq!(s.reclaim_unconsumed(), Some(vec![]));
+}
+
+#[test]
+fn reclaim_default_policy() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    drop(r);
+
+    // The default policy leaves the message in the channel until it is destroyed.
+    assert_eq!(s.pending_on_disconnect(), Some(1));
+    assert_eq!(s.reclaim_unconsumed(), Some(vec![1]));
+    assert_eq!(s.pending_on_disconnect(), Some(0));
+}
+
+#[test]
+fn requeue() {
+    let (jobs_s, jobs_r) = Builder::new().drop_policy(DropPolicy::Keep).unbounded();
+    let (backup_s, backup_r) = unbounded();
+
+    for i in 0..10 {
+        jobs_s.send(i).unwrap();
+    }
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            // A worker that dies after three jobs.
+            for _ in 0..3 {
+                jobs_r.recv().unwrap();
+            }
+        });
+    });
+
+    for job in jobs_s.reclaim_unconsumed().unwrap() {
+        backup_s.send(job).unwrap();
+    }
+    drop(backup_s);
+    assert_eq!(backup_r.iter().collect::<Vec<_>>(), (3..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn keep_drops_with_channel() {
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = Builder::new().drop_policy(DropPolicy::Keep).unbounded();
+    s.send(Counted(drops.clone())).unwrap();
+    s.send(Counted(drops.clone())).unwrap();
+
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+    drop(s);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,247 @@
+//! Tests for two-phase receive operations.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, lanes, unbounded, RecvError, Select, TryRecvError};
+use crossbeam_channel::TrySendError;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn commit() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let msg = r.recv_guard().unwrap();
+    assert_eq!(*msg, 1);
+    assert_eq!(msg.commit(), 1);
+    assert_eq!(r.len(), 1);
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn put_back_keeps_order() {
+    let (s, r) = bounded(3);
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+
+    for _ in 0..3 {
+        let msg = r.recv_guard().unwrap();
+        assert_eq!(*msg, 0);
+    }
+    assert_eq!(r.len(), 3);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 1, 2]);
+}
+
+#[test]
+fn put_back_mpmc() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded::<usize>();
+    let put_back = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+    let received = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+        });
+
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                while let Ok(msg) = r.recv_guard() {
+                    // Put every message back once while other threads keep receiving.
+                    if put_back[*msg].fetch_add(1, Ordering::SeqCst) > 0 {
+                        received[msg.commit()].fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        }
+    });
+
+    for c in received {
+        assert_eq!(c.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn put_back_into_full_channel() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    let msg = r.recv_guard().unwrap();
+    s.send(2).unwrap();
+    drop(msg);
+
+    // The message put back doesn't hold capacity, so the channel holds more than its capacity
+    // until the receiver catches up.
+    assert_eq!(r.len(), 2);
+    assert_eq!(r.capacity(), Some(1));
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.len(), 1);
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(s.try_send(3), Ok(()));
+}
+
+#[test]
+fn modify() {
+    let (s, r) = unbounded();
+    s.send(vec![1]).unwrap();
+
+    let mut msg = r.recv_guard().unwrap();
+    msg.push(2);
+    drop(msg);
+    assert_eq!(r.recv(), Ok(vec![1, 2]));
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    drop(s);
+
+    drop(r.recv_guard().unwrap());
+    assert_eq!(r.recv_guard().unwrap().commit(), 1);
+    assert_eq!(r.recv_guard().unwrap_err(), RecvError);
+}
+
+#[test]
+fn lanes_put_back_first() {
+    let (s, r) = lanes(4, 2);
+    s.send_with_priority(1, 0).unwrap();
+    s.send_with_priority(2, 1).unwrap();
+
+    let msg = r.recv_guard().unwrap();
+    assert_eq!(*msg, 2);
+    drop(msg);
+
+    let msg = r.recv_guard().unwrap();
+    assert_eq!(*msg, 2);
+    msg.commit();
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn zero() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+        });
+
+        drop(r.recv_guard().unwrap());
+        assert_eq!(r.recv(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+    });
+}
+
+#[test]
+fn wakes_blocked_receiver() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        let msg = r.recv_guard().unwrap();
+
+        scope.spawn(|| {
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        thread::sleep(ms(100));
+        drop(msg);
+    });
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn generated_messages() {
+    let r = after(ms(0));
+    drop(r.recv_guard().unwrap());
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn select_commit() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded();
+    s2.send(2).unwrap();
+
+    let mut sel = Select::new();
+    sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv_guard(&r2).unwrap().commit(), 2);
+    assert!(r2.is_empty());
+    drop(s1);
+}
+
+#[test]
+fn select_panic_rearms() {
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let msg = sel.select().recv_guard(&r).unwrap();
+        assert_eq!(*msg, 1);
+        panic!();
+    }));
+    assert!(res.is_err());
+
+    // The same operation is selected again and gets the message back.
+    let msg = sel.select().recv_guard(&r).unwrap();
+    assert_eq!(msg.commit(), 1);
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn select_panic_zero() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(1).unwrap());
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut sel = Select::new();
+            sel.recv(&r);
+            let _msg = sel.select().recv_guard(&r).unwrap();
+            panic!();
+        }));
+        assert!(res.is_err());
+    });
+
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn select_disconnected() {
+    let (s, r) = unbounded::<i32>();
+    drop(s);
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+    assert_eq!(sel.select().recv_guard(&r).unwrap_err(), RecvError);
+}
@@ -0,0 +1,94 @@
+//! Tests for receiving with `Option` and default results.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, never, unbounded};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn try_recv_opt() {
+    let (s, r) = unbounded();
+    assert_eq!(r.try_recv_opt(), None);
+
+    s.send(1).unwrap();
+    assert_eq!(r.try_recv_opt(), Some(1));
+
+    s.send(2).unwrap();
+    drop(s);
+    assert_eq!(r.try_recv_opt(), Some(2));
+    assert_eq!(r.try_recv_opt(), None);
+}
+
+#[test]
+fn try_recv_or() {
+    let (s, r) = bounded(1);
+    assert_eq!(r.try_recv_or(-1), -1);
+
+    s.send(1).unwrap();
+    assert_eq!(r.try_recv_or(-1), 1);
+
+    drop(s);
+    assert_eq!(r.try_recv_or(-1), -1);
+}
+
+#[test]
+fn recv_opt() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..10 {
+                s.send(i).unwrap();
+            }
+        });
+
+        let mut received = Vec::new();
+        while let Some(msg) = r.recv_opt() {
+            received.push(msg);
+        }
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn recv_or_blocks() {
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+        });
+
+        assert_eq!(r.recv_or(-1), 1);
+        assert_eq!(r.recv_or(-1), -1);
+    });
+}
+
+#[test]
+fn recv_or_default() {
+    let (s, r) = unbounded::<Vec<i32>>();
+    s.send(vec![1, 2]).unwrap();
+    drop(s);
+
+    assert_eq!(r.recv_or_default(), vec![1, 2]);
+    assert_eq!(r.recv_or_default(), Vec::<i32>::new());
+}
+
+#[test]
+fn special_flavors() {
+    let r = after(ms(50));
+    assert_eq!(r.try_recv_opt(), None);
+    assert!(r.recv_opt().is_some());
+
+    let r = never::<i32>();
+    assert_eq!(r.try_recv_opt(), None);
+    assert_eq!(r.try_recv_or(7), 7);
+}
@@ -0,0 +1,177 @@
+//! Tests for the `registry` module.
+
+#![cfg(feature = "registry")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::registry::{self, ChannelInfo, Flavor};
+use crossbeam_channel::{after, bounded, unbounded, Builder};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Returns the snapshots of all live channels with the given name.
+fn named(name: &str) -> Vec<ChannelInfo> {
+    registry::snapshot()
+        .into_iter()
+        .filter(|info| info.name.as_ref().map(|n| n.as_str()) == Some(name))
+        .collect()
+}
+
+#[test]
+fn flavors() {
+    let (_s1, _r1) = Builder::<i32>::new().name("flavors array").bounded(3);
+    let (_s2, _r2) = Builder::<i32>::new().name("flavors list").unbounded();
+    let (_s3, _r3) = Builder::<i32>::new().name("flavors zero").bounded(0);
+
+    let info = &named("flavors array")[0];
+    assert_eq!(info.flavor, Flavor::Array);
+    assert_eq!(info.capacity, Some(3));
+
+    let info = &named("flavors list")[0];
+    assert_eq!(info.flavor, Flavor::List);
+    assert_eq!(info.capacity, None);
+
+    let info = &named("flavors zero")[0];
+    assert_eq!(info.flavor, Flavor::Zero);
+    assert_eq!(info.capacity, Some(0));
+}
+
+#[test]
+fn lifetime() {
+    let (s, r) = Builder::<i32>::new().name("lifetime").unbounded();
+    assert_eq!(named("lifetime").len(), 1);
+
+    drop(s);
+    assert_eq!(named("lifetime").len(), 1);
+    assert!(named("lifetime")[0].is_disconnected);
+
+    drop(r);
+    assert_eq!(named("lifetime").len(), 0);
+}
+
+#[test]
+fn unnamed() {
+    let before = registry::snapshot().len();
+    let (s, r) = bounded::<i32>(1);
+    let (_s, _r) = unbounded::<i32>();
+    let _a = after(ms(1000));
+    assert!(registry::snapshot().len() >= before + 2);
+    assert!(registry::snapshot().iter().any(|info| info.name.is_none()));
+    drop(s);
+    drop(r);
+}
+
+#[test]
+fn counts() {
+    let (s, r) = Builder::new().name("counts").bounded(4);
+    let s2 = s.clone();
+    s.send(1).unwrap();
+    s2.send(2).unwrap();
+
+    let info = &named("counts")[0];
+    assert_eq!(info.len, 2);
+    assert_eq!(info.senders, 2);
+    assert_eq!(info.receivers, 1);
+    assert!(!info.is_disconnected);
+
+    drop(r);
+    let info = &named("counts")[0];
+    assert_eq!(info.receivers, 0);
+    assert!(info.is_disconnected);
+}
+
+#[test]
+fn waiting() {
+    let (s, r) = Builder::new().name("waiting array").bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(2).unwrap());
+        scope.spawn(|| s.send(3).unwrap());
+        thread::sleep(ms(500));
+
+        let info = &named("waiting array")[0];
+        assert_eq!(info.waiting_senders, 2);
+        assert_eq!(info.waiting_receivers, 0);
+
+        for _ in 0..3 {
+            r.recv().unwrap();
+        }
+    });
+    assert_eq!(named("waiting array")[0].waiting_senders, 0);
+
+    let (s, r) = Builder::<i32>::new().name("waiting list").unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| r.recv().unwrap());
+        thread::sleep(ms(500));
+
+        let info = &named("waiting list")[0];
+        assert_eq!(info.waiting_senders, 0);
+        assert_eq!(info.waiting_receivers, 1);
+
+        s.send(1).unwrap();
+    });
+
+    let (s, r) = Builder::new().name("waiting zero").bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(1).unwrap());
+        thread::sleep(ms(500));
+
+        let info = &named("waiting zero")[0];
+        assert_eq!(info.waiting_senders, 1);
+        assert_eq!(info.waiting_receivers, 0);
+
+        r.recv().unwrap();
+    });
+}
+
+#[test]
+fn display() {
+    let (s, r) = Builder::new().name("display").bounded(2);
+    s.send(1).unwrap();
+
+    let info = &named("display")[0];
+    assert_eq!(
+        info.to_string(),
+        "\"display\" Array len=1/2 senders=1 (waiting 0) receivers=1 (waiting 0)",
+    );
+
+    drop(r);
+    let info = &named("display")[0];
+    assert!(info.to_string().ends_with(" disconnected"));
+    drop(s);
+}
+
+#[test]
+fn stress() {
+    const THREADS: usize = 4;
+    const COUNT: usize = 1000;
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    let (s, r) = Builder::new().name("stress").bounded(1);
+                    s.send(i).unwrap();
+                    assert_eq!(r.recv(), Ok(i));
+                }
+            });
+        }
+
+        for _ in 0..COUNT {
+            for info in named("stress") {
+                assert!(info.len <= 1);
+            }
+        }
+    });
+
+    assert_eq!(named("stress").len(), 0);
+}
@@ -0,0 +1,220 @@
+//! Tests for reuniting channel halves.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{
+    bounded, lanes, unbounded, Builder, DropPolicy, RecvError, ShutdownGroup, TryRecvError,
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded(3);
+    s.send(1).unwrap();
+
+    let (s, r) = s.reunite(r).unwrap().split();
+    assert_eq!(s.capacity(), Some(3));
+    s.send(2).unwrap();
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2]);
+}
+
+#[test]
+fn different_channels() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+
+    let (s1, r2) = s1.reunite(r2).unwrap_err().into_inner();
+
+    // The handles still work after a failed attempt.
+    s1.send(1).unwrap();
+    assert_eq!(r1.recv(), Ok(1));
+    s2.send(2).unwrap();
+    assert_eq!(r2.recv(), Ok(2));
+}
+
+#[test]
+fn other_handles() {
+    let (s, r) = unbounded::<i32>();
+
+    let s2 = s.clone();
+    let (s, r) = s.reunite(r).unwrap_err().into_inner();
+    drop(s2);
+
+    let r2 = r.clone();
+    let (s, r) = s.reunite(r).unwrap_err().into_inner();
+    drop(r2);
+
+    assert!(s.reunite(r).is_ok());
+}
+
+#[test]
+fn other_handles_keep_working() {
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+
+    let s2 = s.clone();
+    let (s, r) = s.reunite(r).unwrap_err().into_inner();
+    s2.send(2).unwrap();
+    drop(s);
+
+    // The channel is still connected and keeps its messages.
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    drop(s2);
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn handle_dropped_on_other_thread() {
+    let (s, r) = unbounded::<i32>();
+    let s2 = s.clone();
+
+    thread::spawn(move || drop(s2)).join().unwrap();
+    assert!(s.reunite(r).is_ok());
+}
+
+#[test]
+fn special_flavors() {
+    let (s, _) = unbounded::<i32>();
+    assert!(s.reunite(crossbeam_channel::never()).is_err());
+}
+
+#[test]
+fn into_unbounded() {
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let (s, r) = s.reunite(r).unwrap().into_unbounded();
+    assert_eq!(s.capacity(), None);
+    for i in 3..100 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), (1..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn into_bounded() {
+    let (s, r) = unbounded();
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+
+    let chan = s.reunite(r).unwrap();
+    let chan = chan.into_bounded(4).unwrap_err();
+    assert_eq!(chan.len(), 5);
+
+    let (s, r) = chan.into_bounded(10).unwrap();
+    assert_eq!(s.capacity(), Some(10));
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+
+    // An empty channel can become zero-capacity.
+    let (s, r) = s.reunite(r).unwrap().into_bounded(0).unwrap();
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(7).unwrap());
+        assert_eq!(r.recv(), Ok(7));
+    });
+}
+
+#[test]
+fn lanes_keep_receive_order() {
+    let (s, r) = lanes(10, 2);
+    s.send_with_priority(1, 0).unwrap();
+    s.send_with_priority(2, 1).unwrap();
+    s.send_with_priority(3, 0).unwrap();
+
+    let (_s, r) = s.reunite(r).unwrap().into_unbounded();
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 1, 3]);
+}
+
+#[test]
+fn keeps_settings() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let d = dropped.clone();
+
+    let (s, r) = Builder::new()
+        .name("jobs")
+        .drop_policy(DropPolicy::Handler(Box::new(move |_: i32| {
+            d.fetch_add(1, Ordering::SeqCst);
+        })))
+        .bounded(2);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    // Converting doesn't count as disconnecting.
+    let (s, r) = s.reunite(r).unwrap().into_unbounded();
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    assert_eq!(r.len(), 2);
+
+    // The drop policy carries over to the new channel.
+    drop(r);
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+    drop(s);
+}
+
+#[test]
+fn shutdown_group() {
+    let group = ShutdownGroup::new();
+    let (s, r) = Builder::new().shutdown_group(&group).bounded(1);
+    s.send(1).unwrap();
+
+    // The channel stays in its group when split up again, and so does the converted channel.
+    let (s, r) = s.reunite(r).unwrap().split();
+    assert_eq!(group.len(), 1);
+    let (s, r) = s.reunite(r).unwrap().into_unbounded();
+    assert_eq!(group.len(), 1);
+
+    group.shutdown();
+    assert!(s.send(2).is_err());
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn drop_reunited() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let d = dropped.clone();
+
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Handler(Box::new(move |_: i32| {
+            d.fetch_add(1, Ordering::SeqCst);
+        })))
+        .unbounded();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let chan = s.reunite(r).unwrap();
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    drop(chan);
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn works_across_threads_after_conversion() {
+    let (s, r) = bounded(1);
+    s.send(0).unwrap();
+    let (s, r) = s.reunite(r).unwrap().into_unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 1..1000 {
+                s.send(i).unwrap();
+            }
+            thread::sleep(ms(10));
+        });
+
+        for i in 0..1000 {
+            assert_eq!(r.recv(), Ok(i));
+        }
+        assert_eq!(r.recv(), Err(RecvError));
+    });
+}
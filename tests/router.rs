@@ -0,0 +1,190 @@
+//! Tests for topic routers.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{RecvError, Router, SendError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let router = Router::new();
+    let a = router.subscribe("a");
+    let b = router.subscribe("b");
+
+    assert_eq!(router.send("a", 1), Ok(1));
+    assert_eq!(router.send("b", 2), Ok(1));
+    assert_eq!(router.send("c", 3), Err(SendError(3)));
+
+    assert_eq!(a.try_recv(), Ok(1));
+    assert_eq!(a.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(b.try_recv(), Ok(2));
+    assert_eq!(b.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn fan_out() {
+    let router = Router::new();
+    let rs = (0..4).map(|_| router.subscribe(7)).collect::<Vec<_>>();
+
+    assert_eq!(router.subscribers(&7), 4);
+    assert_eq!(router.send(&7, "x"), Ok(4));
+
+    for r in &rs {
+        assert_eq!(r.try_recv(), Ok("x"));
+    }
+}
+
+#[test]
+fn unsubscribe() {
+    let router = Router::new();
+    let r1 = router.subscribe("a");
+    let r2 = router.subscribe("a");
+
+    drop(r1);
+    assert_eq!(router.subscribers("a"), 2);
+    assert_eq!(router.send("a", 1), Ok(1));
+    assert_eq!(router.subscribers("a"), 1);
+
+    drop(r2);
+    assert_eq!(router.send("a", 2), Err(SendError(2)));
+    assert_eq!(router.subscribers("a"), 0);
+
+    // Subscribing again after everyone left works.
+    let r3 = router.subscribe("a");
+    assert_eq!(router.send("a", 3), Ok(1));
+    assert_eq!(r3.try_recv(), Ok(3));
+}
+
+#[test]
+fn last_subscriber_gone() {
+    let router = Router::new();
+    let r1 = router.subscribe("a");
+    let r2 = router.subscribe("a");
+
+    // The message is still delivered to the others when the last subscriber is gone.
+    drop(r2);
+    assert_eq!(router.send("a", 1), Ok(1));
+    assert_eq!(r1.try_recv(), Ok(1));
+    assert_eq!(router.subscribers("a"), 1);
+
+    drop(r1);
+    assert_eq!(router.send("a", 2), Err(SendError(2)));
+}
+
+#[test]
+fn clones() {
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Msg;
+
+    impl Clone for Msg {
+        fn clone(&self) -> Msg {
+            CLONES.fetch_add(1, Ordering::SeqCst);
+            Msg
+        }
+    }
+
+    let router = Router::new();
+    let rs = (0..3).map(|_| router.subscribe("a")).collect::<Vec<_>>();
+
+    assert_eq!(router.send("a", Msg), Ok(3));
+    assert_eq!(CLONES.load(Ordering::SeqCst), 2);
+    for r in &rs {
+        assert_eq!(r.try_recv(), Ok(Msg));
+    }
+}
+
+#[test]
+fn close() {
+    let router = Router::new();
+    let r = router.subscribe(String::from("a"));
+
+    router.send("a", 1).unwrap();
+    assert!(router.close("a"));
+    assert!(!router.close("a"));
+
+    assert_eq!(router.send("a", 2), Err(SendError(2)));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn select() {
+    let router = Router::new();
+    let a = router.subscribe("a");
+    let b = router.subscribe("b");
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            router.send("b", 1).unwrap();
+        });
+
+        select! {
+            recv(a) -> _ => panic!(),
+            recv(b) -> msg => assert_eq!(msg, Ok(1)),
+            default(ms(1000)) => panic!(),
+        }
+    });
+}
+
+#[test]
+fn clone() {
+    let router = Router::new();
+    let other = router.clone();
+
+    let r = other.subscribe("a");
+    router.send("a", 1).unwrap();
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(format!("{:?}", router), "Router { .. }");
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let router = Router::new();
+    let rs = (0..THREADS).map(|i| router.subscribe(i)).collect::<Vec<_>>();
+    let all = router.subscribe(THREADS);
+    let received = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for i in 0..THREADS {
+            let router = &router;
+            scope.spawn(move || {
+                for j in 0..COUNT {
+                    router.send(&i, j).unwrap();
+                    router.send(&THREADS, j).unwrap();
+                }
+            });
+        }
+
+        for r in &rs {
+            let received = &received;
+            scope.spawn(move || {
+                for j in 0..COUNT {
+                    assert_eq!(r.recv(), Ok(j));
+                    received.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+
+        for _ in 0..COUNT * THREADS {
+            all.recv().unwrap();
+        }
+    });
+
+    assert_eq!(received.load(Ordering::SeqCst), COUNT * THREADS);
+    assert_eq!(all.try_recv(), Err(TryRecvError::Empty));
+}
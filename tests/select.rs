@@ -9,6 +9,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use crossbeam_channel::{after, bounded, unbounded, tick, Receiver, Select, TryRecvError};
+use crossbeam_channel::Fairness;
 
 fn ms(ms: u64) -> Duration {
     Duration::from_millis(ms)
@@ -1285,3 +1286,278 @@ fn reuse() {
         }
     });
 }
+
+#[test]
+fn disarm_rearm() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    sel.disarm(oper1);
+    sel.disarm(oper1);
+
+    // Indices keep counting disarmed operations.
+    let oper2 = sel.recv(&r2);
+    assert_eq!(oper2, 1);
+
+    s1.send(1).unwrap();
+    assert!(sel.try_select().is_err());
+    assert!(sel.select_timeout(ms(100)).is_err());
+
+    s2.send(2).unwrap();
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(&r2), Ok(2));
+
+    sel.rearm(oper1);
+    sel.rearm(oper1);
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.recv(&r1), Ok(1));
+
+    let mut sel2 = sel.clone();
+    sel.disarm(oper2);
+    s2.send(3).unwrap();
+    assert!(sel.try_select().is_err());
+
+    let oper = sel2.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(&r2), Ok(3));
+}
+
+#[test]
+fn disarm_disconnected() {
+    const COUNT: usize = 1000;
+
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s1.send(i).unwrap();
+            }
+        });
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s2.send(i).unwrap();
+            }
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv(&r1);
+        let oper2 = sel.recv(&r2);
+
+        let mut received = 0;
+        let mut open = 2;
+
+        while open > 0 {
+            let oper = sel.select();
+            let index = oper.index();
+            let res = match index {
+                i if i == oper1 => oper.recv(&r1),
+                i if i == oper2 => oper.recv(&r2),
+                _ => unreachable!(),
+            };
+
+            match res {
+                Ok(_) => received += 1,
+                Err(_) => {
+                    sel.disarm(index);
+                    open -= 1;
+                }
+            }
+        }
+
+        assert_eq!(received, 2 * COUNT);
+    });
+}
+
+#[test]
+#[should_panic(expected = "no operation with index 1")]
+fn disarm_invalid() {
+    let (_s, r) = unbounded::<i32>();
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+    sel.disarm(1);
+}
+
+#[test]
+#[should_panic(expected = "all operations in `Select` are disarmed")]
+fn select_all_disarmed() {
+    let (_s, r) = unbounded::<i32>();
+
+    let mut sel = Select::new();
+    let oper = sel.recv(&r);
+    sel.disarm(oper);
+    let _ = sel.select();
+}
+
+#[test]
+fn round_robin() {
+    const COUNT: usize = 1000;
+
+    let (s1, r1) = unbounded::<()>();
+    let (s2, r2) = unbounded::<()>();
+    let (s3, r3) = bounded::<()>(COUNT);
+
+    for _ in 0..COUNT {
+        s1.send(()).unwrap();
+        s2.send(()).unwrap();
+    }
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.send(&s3);
+    let oper3 = sel.recv(&r2);
+    sel.set_fairness(Fairness::RoundRobin);
+
+    // All operations are ready, so they must be selected in strict rotation.
+    for i in 0..COUNT {
+        let oper = sel.select();
+        assert_eq!(oper.index(), i % 3);
+        match oper.index() {
+            ix if ix == oper1 => oper.recv(&r1).unwrap(),
+            ix if ix == oper2 => oper.send(&s3, ()).unwrap(),
+            ix if ix == oper3 => oper.recv(&r2).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    // Operations that aren't ready are skipped, but the rotation continues after the selected one.
+    drop(s2);
+    while r2.try_recv().is_ok() {}
+    let (_s4, r4) = unbounded::<()>();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r4);
+    let oper3 = sel.recv(&r2);
+    sel.set_fairness(Fairness::RoundRobin);
+    for _ in 0..10 {
+        for &expected in &[oper1, oper3] {
+            let oper = sel.try_select().unwrap();
+            assert_eq!(oper.index(), expected);
+            match oper.index() {
+                ix if ix == oper1 => assert!(oper.recv(&r1).is_ok()),
+                ix if ix == oper2 => panic!(),
+                ix if ix == oper3 => assert!(oper.recv(&r2).is_err()),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+#[test]
+fn round_robin_rearm() {
+    let (s1, r1) = unbounded::<()>();
+    let (s2, r2) = unbounded::<()>();
+    let (s3, r3) = unbounded::<()>();
+
+    for _ in 0..10 {
+        s1.send(()).unwrap();
+        s2.send(()).unwrap();
+        s3.send(()).unwrap();
+    }
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+    let oper3 = sel.recv(&r3);
+    sel.set_fairness(Fairness::RoundRobin);
+
+    let mut recv = |sel: &mut Select| {
+        let oper = sel.select_timeout(ms(1000)).unwrap();
+        let index = oper.index();
+        match index {
+            ix if ix == oper1 => oper.recv(&r1).unwrap(),
+            ix if ix == oper2 => oper.recv(&r2).unwrap(),
+            ix if ix == oper3 => oper.recv(&r3).unwrap(),
+            _ => unreachable!(),
+        }
+        index
+    };
+
+    assert_eq!(recv(&mut sel), oper1);
+    sel.disarm(oper2);
+    assert_eq!(recv(&mut sel), oper3);
+    assert_eq!(recv(&mut sel), oper1);
+
+    // Rearmed operations take their original place in the rotation.
+    sel.rearm(oper2);
+    assert_eq!(recv(&mut sel), oper2);
+    assert_eq!(recv(&mut sel), oper3);
+
+    let mut sel2 = sel.clone();
+    assert_eq!(recv(&mut sel2), oper1);
+    assert_eq!(recv(&mut sel), oper1);
+}
+
+#[test]
+fn with_capacity() {
+    const CHANNELS: usize = 20;
+
+    let channels: Vec<_> = (0..CHANNELS).map(|_| unbounded::<usize>()).collect();
+
+    let mut sel = Select::with_capacity(CHANNELS);
+    for (i, &(_, ref r)) in channels.iter().enumerate() {
+        assert_eq!(sel.recv(r), i);
+    }
+    assert!(sel.try_select().is_err());
+
+    for (i, &(ref s, _)) in channels.iter().enumerate().rev() {
+        s.send(i).unwrap();
+    }
+    for _ in 0..CHANNELS {
+        let oper = sel.select();
+        let index = oper.index();
+        assert_eq!(oper.recv(&channels[index].1), Ok(index));
+    }
+    assert!(sel.try_select().is_err());
+}
+
+#[test]
+fn clear() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let (s3, r3) = unbounded::<i32>();
+
+    let mut sel = Select::new();
+    sel.set_fairness(Fairness::RoundRobin);
+
+    for i in 0..100 {
+        s1.send(i).unwrap();
+        s2.send(i).unwrap();
+        s3.send(i).unwrap();
+
+        sel.clear();
+        let oper1 = sel.recv(&r1);
+        let oper2 = sel.recv(&r2);
+        let oper3 = sel.recv(&r3);
+        sel.disarm(oper2);
+        assert_eq!((oper1, oper2, oper3), (0, 1, 2));
+
+        // The rotation starts over with the first operation.
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper1);
+        assert_eq!(oper.recv(&r1), Ok(i));
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper3);
+        assert_eq!(oper.recv(&r3), Ok(i));
+        assert!(sel.try_select().is_err());
+
+        assert_eq!(r2.try_recv(), Ok(i));
+    }
+
+    // Clearing removes disarmed operations too.
+    sel.clear();
+    s2.send(0).unwrap();
+    assert!(sel.try_select().is_err());
+    let oper2 = sel.recv(&r2);
+    assert_eq!(oper2, 0);
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(&r2), Ok(0));
+}
@@ -0,0 +1,145 @@
+//! Tests that the `select!` macro doesn't allocate.
+//!
+//! The global allocator counts allocations made by the whole test binary, so this file must
+//! contain only a single test.
+//!
+//! Installing a global allocator requires Rust 1.28, which is newer than the minimum version the
+//! crate supports, so the test only runs with the `alloc-tests` feature.
+
+#![cfg(feature = "alloc-tests")]
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::bounded;
+
+struct Counter;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counter = Counter;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Returns the number of allocations made while running `f`.
+fn allocations<F: FnMut()>(mut f: F) -> usize {
+    // Run once to initialize thread-locals.
+    f();
+
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    for _ in 0..100 {
+        f();
+    }
+    ALLOCATIONS.load(Ordering::SeqCst) - before
+}
+
+#[test]
+fn select_macro() {
+    let (s1, r1) = bounded::<i32>(1);
+    let (s2, r2) = bounded::<i32>(1);
+    let (s3, r3) = bounded::<i32>(1);
+    let (s4, r4) = bounded::<i32>(1);
+    let (s5, r5) = bounded::<i32>(1);
+    let (s6, r6) = bounded::<i32>(1);
+
+    // Two operations.
+    let n = allocations(|| {
+        s1.send(1).unwrap();
+        select! {
+            recv(r1) -> msg => assert_eq!(msg, Ok(1)),
+            recv(r2) -> _ => panic!(),
+        }
+    });
+    assert_eq!(n, 0);
+
+    // More operations than a `Select` stores inline.
+    let n = allocations(|| {
+        select! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> _ => panic!(),
+            recv(r3) -> _ => panic!(),
+            recv(r4) -> _ => panic!(),
+            recv(r5) -> _ => panic!(),
+            send(s6, 6) -> res => assert_eq!(res, Ok(())),
+        }
+        assert_eq!(r6.try_recv(), Ok(6));
+    });
+    assert_eq!(n, 0);
+
+    // Non-blocking selection.
+    let n = allocations(|| {
+        select! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> _ => panic!(),
+            recv(r3) -> _ => panic!(),
+            recv(r4) -> _ => panic!(),
+            recv(r5) -> _ => panic!(),
+            default => {}
+        }
+    });
+    assert_eq!(n, 0);
+
+    // Selection with a timeout.
+    let n = allocations(|| {
+        select! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> _ => panic!(),
+            recv(r3) -> _ => panic!(),
+            recv(r4) -> _ => panic!(),
+            recv(r5) -> _ => panic!(),
+            default(ms(1)) => {}
+        }
+    });
+    assert_eq!(n, 0);
+
+    // Blocking selection woken up by another thread.
+    let (ready_s, ready_r) = bounded::<()>(0);
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..101 {
+                ready_r.recv().unwrap();
+                thread::sleep(ms(1));
+                s5.send(5).unwrap();
+            }
+        });
+
+        let n = allocations(|| {
+            ready_s.send(()).unwrap();
+            select! {
+                recv(r1) -> _ => panic!(),
+                recv(r2) -> _ => panic!(),
+                recv(r3) -> _ => panic!(),
+                recv(r4) -> _ => panic!(),
+                recv(r5) -> msg => assert_eq!(msg, Ok(5)),
+            }
+        });
+        assert_eq!(n, 0);
+    });
+
+    drop((s2, s3, s4));
+}
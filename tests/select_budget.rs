@@ -0,0 +1,123 @@
+//! Tests for `Select::select_budgeted`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, Fairness, Select};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn ops_limit() {
+    let (s, r) = unbounded();
+    for i in 0..10 {
+        s.send(i).unwrap();
+    }
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+
+    let mut received = Vec::new();
+    let left = sel.select_budgeted(3, ms(1000), |oper| received.push(oper.recv(&r).unwrap()));
+    assert_eq!(received, [0, 1, 2]);
+    assert_eq!(left.ops, 0);
+    assert!(left.time > ms(0));
+    assert!(left.is_exhausted());
+
+    let left = sel.select_budgeted(100, ms(0), |oper| received.push(oper.recv(&r).unwrap()));
+    assert_eq!(received, [0, 1, 2, 3]);
+    assert_eq!(left.ops, 99);
+}
+
+#[test]
+fn time_limit() {
+    let (s, r) = unbounded::<i32>();
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+
+    let start = Instant::now();
+    let left = sel.select_budgeted(10, ms(100), |_| panic!());
+    assert!(start.elapsed() >= ms(100));
+    assert!(start.elapsed() < ms(500));
+    assert_eq!(left.ops, 10);
+    assert_eq!(left.time, ms(0));
+    assert!(left.is_exhausted());
+    drop(s);
+}
+
+#[test]
+fn waits_for_operations() {
+    let (s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..3 {
+                thread::sleep(ms(50));
+                s.send(i).unwrap();
+            }
+        });
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+
+        let mut received = Vec::new();
+        let left = sel.select_budgeted(3, ms(2000), |oper| {
+            received.push(oper.recv(&r).unwrap())
+        });
+        assert_eq!(received, [0, 1, 2]);
+        assert_eq!(left.ops, 0);
+        assert!(left.time > ms(0));
+    });
+}
+
+#[test]
+fn busy_channel_yields() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| while s.send(()).is_ok() {});
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+
+        // The sender never stops, but the time budget still ends the loop.
+        let start = Instant::now();
+        let left = sel.select_budgeted(usize::max_value(), ms(100), |oper| {
+            oper.recv(&r).unwrap();
+        });
+        assert!(start.elapsed() < ms(1000));
+        assert!(left.ops < usize::max_value());
+        assert_eq!(left.time, ms(0));
+        drop(sel);
+        drop(r);
+    });
+}
+
+#[test]
+fn multiple_operations() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    for i in 0..5 {
+        s1.send(i).unwrap();
+        s2.send(i + 10).unwrap();
+    }
+
+    let mut sel = Select::new();
+    sel.set_fairness(Fairness::RoundRobin);
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+
+    let mut received = Vec::new();
+    sel.select_budgeted(4, ms(1000), |oper| match oper.index() {
+        i if i == oper1 => received.push(oper.recv(&r1).unwrap()),
+        i if i == oper2 => received.push(oper.recv(&r2).unwrap()),
+        _ => unreachable!(),
+    });
+    assert_eq!(received, [0, 10, 1, 11]);
+}
@@ -0,0 +1,144 @@
+//! Tests for the `closed` case and strict mode of the `select!` macro.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded, Select};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn fires_after_drain() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    drop(s);
+
+    // The channel still holds a message.
+    select! {
+        closed(r) => panic!(),
+        default => {}
+    }
+    assert_eq!(r.recv(), Ok(1));
+
+    select! {
+        closed(r) => {}
+        default => panic!(),
+    }
+}
+
+#[test]
+fn routes_recv_error() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    drop(s);
+
+    let mut events = Vec::new();
+    loop {
+        select! {
+            recv(r) -> msg => events.push(msg.unwrap()),
+            closed(r) => {
+                events.push(-1);
+                break;
+            }
+        }
+    }
+    assert_eq!(events, [1, 2, -1]);
+}
+
+#[test]
+fn clones() {
+    let (s, r) = unbounded::<i32>();
+    let r2 = r.clone();
+    drop(s);
+
+    // A failed receive on a clone is still routed to the `closed` case of the same channel.
+    select! {
+        recv(r2) -> _ => panic!(),
+        closed(r) => {}
+    }
+}
+
+#[test]
+fn other_channel() {
+    let (s1, r1) = unbounded::<i32>();
+    let (_s2, r2) = unbounded::<i32>();
+    drop(s1);
+
+    // The `closed` case only applies to its own channel.
+    select! {
+        recv(r1) -> msg => assert!(msg.is_err()),
+        closed(r2) => panic!(),
+    }
+}
+
+#[test]
+fn wakes_blocked_select() {
+    let (s, r) = bounded::<i32>(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(s);
+        });
+
+        select! {
+            closed(r) => {}
+            default(ms(1000)) => panic!(),
+        }
+    });
+}
+
+#[test]
+fn strict() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+    s1.send(1).unwrap();
+
+    // Nobody is receiving from `r2`, so only the receive operation is ready.
+    select! {
+        strict;
+        recv(r1) -> msg => assert_eq!(msg, Ok(1)),
+        closed(r1) => panic!(),
+        send(s2, 2) -> res => res.unwrap(),
+        default => panic!(),
+    }
+
+    drop(s1);
+    drop(s2);
+    select! {
+        strict;
+        recv(r1) -> _ => panic!(),
+        recv(r2) -> _ => {}
+        closed(r1) => {}
+        closed(r2) => {}
+    }
+}
+
+#[test]
+fn select_closed() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.closed(&r);
+    assert!(sel.try_select().is_err());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(s);
+        });
+
+        assert_eq!(r.recv(), Ok(1));
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper1);
+        oper.closed(&r);
+    });
+}
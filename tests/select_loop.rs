@@ -0,0 +1,226 @@
+//! Tests for the `select_loop!` macro.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::cell::Cell;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded, Receiver};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    s1.send(1).unwrap();
+    s2.send("a").unwrap();
+    s1.send(2).unwrap();
+    drop(s1);
+    drop(s2);
+
+    let mut nums = Vec::new();
+    let mut strs = Vec::new();
+    select_loop! {
+        recv(r1) -> n => nums.push(n),
+        recv(r2) -> s => strs.push(s),
+    }
+
+    assert_eq!(nums, [1, 2]);
+    assert_eq!(strs, ["a"]);
+}
+
+#[test]
+fn on_close() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let (s3, r3) = bounded::<i32>(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(s2);
+            thread::sleep(ms(100));
+            drop(s3);
+            thread::sleep(ms(100));
+            drop(s1);
+        });
+
+        let mut closed = Vec::new();
+        select_loop! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> _ => panic!(),
+            recv(r3) -> _ => panic!(),
+            on_close(i) => closed.push(i),
+        }
+        assert_eq!(closed, [1, 2, 0]);
+    });
+}
+
+#[test]
+fn drains_before_closing() {
+    let (s, r) = bounded(10);
+    for i in 0..10 {
+        s.send(i).unwrap();
+    }
+    drop(s);
+
+    let mut received = Vec::new();
+    let mut closed = None;
+    select_loop! {
+        recv(r) -> n => received.push(n),
+        on_close(i) => {
+            // Every message arrives before the receiver is closed.
+            assert_eq!(received.len(), 10);
+            closed = Some(i);
+        }
+    }
+
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+    assert_eq!(closed, Some(0));
+}
+
+#[test]
+fn already_disconnected() {
+    let (_, r1) = unbounded::<i32>();
+    let (_, r2) = unbounded::<i32>();
+
+    let mut closed = 0;
+    select_loop! {
+        recv(r1) -> _ => panic!(),
+        recv(r2) -> _ => panic!(),
+        on_close(_) => closed += 1,
+    }
+    assert_eq!(closed, 2);
+}
+
+#[test]
+fn break_and_continue() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded::<i32>();
+    for i in 0..10 {
+        s1.send(i).unwrap();
+    }
+
+    let mut seen = Vec::new();
+    select_loop! {
+        recv(r1) -> n => {
+            if n % 2 == 0 {
+                continue;
+            }
+            seen.push(n);
+            if n == 7 {
+                break;
+            }
+        }
+        recv(r2) -> _ => panic!(),
+    }
+    assert_eq!(seen, [1, 3, 5, 7]);
+
+    // Breaking out of the close case stops the loop even though a receiver is still open.
+    drop(s1);
+    select_loop! {
+        recv(r1) -> _ => {}
+        recv(r2) -> _ => panic!(),
+        on_close(i) => {
+            assert_eq!(i, 0);
+            break;
+        }
+    }
+    drop(s2);
+}
+
+#[test]
+fn patterns() {
+    let (s, r) = unbounded();
+    s.send((1, "one")).unwrap();
+    s.send((2, "two")).unwrap();
+    drop(s);
+
+    let mut sum = 0;
+    let mut names = String::new();
+    select_loop! {
+        recv(r) -> (n, name) => {
+            sum += n;
+            names.push_str(name);
+        }
+    }
+
+    assert_eq!(sum, 3);
+    assert_eq!(names, "onetwo");
+}
+
+#[test]
+fn evaluates_receivers_once() {
+    let (s, r) = unbounded();
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    drop(s);
+
+    let calls = Cell::new(0);
+    let get = |r: &Receiver<i32>| -> Receiver<i32> {
+        calls.set(calls.get() + 1);
+        r.clone()
+    };
+
+    let mut count = 0;
+    select_loop! {
+        recv(get(&r)) -> _ => count += 1,
+    }
+
+    assert_eq!(count, 5);
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn empty() {
+    select_loop! {}
+    select_loop! {
+        on_close(_) => panic!(),
+    }
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let chans = (0..THREADS).map(|_| bounded(10)).collect::<Vec<_>>();
+    let (s0, r0) = chans[0].clone();
+    let (s1, r1) = chans[1].clone();
+    let (s2, r2) = chans[2].clone();
+    let (s3, r3) = chans[3].clone();
+    drop(chans);
+
+    crossbeam::scope(|scope| {
+        for s in vec![s0, s1, s2, s3] {
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+
+        let mut sums = [0; THREADS];
+        let mut closed = Vec::new();
+        select_loop! {
+            recv(r0) -> n => sums[0] += n,
+            recv(r1) -> n => sums[1] += n,
+            recv(r2) -> n => sums[2] += n,
+            recv(r3) -> n => sums[3] += n,
+            on_close(i) => closed.push(i),
+        }
+
+        for &sum in &sums {
+            assert_eq!(sum, COUNT * (COUNT - 1) / 2);
+        }
+        closed.sort();
+        assert_eq!(closed, [0, 1, 2, 3]);
+    });
+}
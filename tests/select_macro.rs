@@ -1413,3 +1413,37 @@ fn disconnect_wakes_receiver() {
         });
     });
 }
+
+#[test]
+fn many_cases() {
+    let r: Vec<_> = (0..20).map(|_| bounded::<usize>(1)).collect();
+
+    for i in 0..20 {
+        r[i].0.send(i).unwrap();
+
+        let (index, msg) = select! {
+            recv(r[0].1) -> msg => (0, msg),
+            recv(r[1].1) -> msg => (1, msg),
+            recv(r[2].1) -> msg => (2, msg),
+            recv(r[3].1) -> msg => (3, msg),
+            recv(r[4].1) -> msg => (4, msg),
+            recv(r[5].1) -> msg => (5, msg),
+            recv(r[6].1) -> msg => (6, msg),
+            recv(r[7].1) -> msg => (7, msg),
+            recv(r[8].1) -> msg => (8, msg),
+            recv(r[9].1) -> msg => (9, msg),
+            recv(r[10].1) -> msg => (10, msg),
+            recv(r[11].1) -> msg => (11, msg),
+            recv(r[12].1) -> msg => (12, msg),
+            recv(r[13].1) -> msg => (13, msg),
+            recv(r[14].1) -> msg => (14, msg),
+            recv(r[15].1) -> msg => (15, msg),
+            recv(r[16].1) -> msg => (16, msg),
+            recv(r[17].1) -> msg => (17, msg),
+            recv(r[18].1) -> msg => (18, msg),
+            recv(r[19].1) -> msg => (19, msg),
+        };
+        assert_eq!(index, i);
+        assert_eq!(msg, Ok(i));
+    }
+}
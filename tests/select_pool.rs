@@ -0,0 +1,175 @@
+//! Tests for the `SelectPool` struct.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, SelectPool};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+
+    let mut pool = SelectPool::new();
+    let key1 = pool.insert(r1);
+    let key2 = pool.insert(r2);
+    assert_eq!(pool.len(), 2);
+
+    s2.send(2).unwrap();
+    assert_eq!(pool.recv(), Ok((key2, 2)));
+    s1.send(1).unwrap();
+    assert_eq!(pool.recv(), Ok((key1, 1)));
+
+    assert_eq!(pool.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn empty() {
+    let mut pool = SelectPool::<i32>::new();
+    assert_eq!(pool.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(pool.recv(), Err(RecvError));
+    assert_eq!(pool.recv_timeout(ms(100)), Err(RecvTimeoutError::Disconnected));
+}
+
+#[test]
+fn keys_not_reused() {
+    let mut pool = SelectPool::new();
+    let key1 = pool.insert(unbounded::<i32>().1);
+    assert!(pool.remove(key1).is_some());
+
+    let key2 = pool.insert(unbounded::<i32>().1);
+    assert!(key2 != key1);
+    assert!(!pool.contains(key1));
+    assert!(pool.remove(key1).is_none());
+    assert!(pool.contains(key2));
+}
+
+#[test]
+fn insert_remove() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+
+    let mut pool = SelectPool::new();
+    let key1 = pool.insert(r1);
+    let key2 = pool.insert(r2);
+
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+
+    let r1 = pool.remove(key1).unwrap();
+    assert_eq!(pool.recv(), Ok((key2, 2)));
+    assert_eq!(pool.try_recv(), Err(TryRecvError::Empty));
+
+    let key1 = pool.insert(r1);
+    assert_eq!(pool.recv(), Ok((key1, 1)));
+}
+
+#[test]
+fn prune_disconnected() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let (s3, r3) = unbounded();
+
+    let mut pool = SelectPool::new();
+    let key1 = pool.insert(r1);
+    let key2 = pool.insert(r2);
+    let key3 = pool.insert(r3);
+
+    // Messages queued before disconnection are still delivered.
+    s1.send(1).unwrap();
+    drop(s1);
+    drop(s2);
+
+    assert_eq!(pool.recv(), Ok((key1, 1)));
+    assert_eq!(pool.try_recv(), Err(TryRecvError::Empty));
+    assert!(!pool.contains(key1));
+    assert!(!pool.contains(key2));
+    assert_eq!(pool.len(), 1);
+
+    s3.send(3).unwrap();
+    assert_eq!(pool.recv(), Ok((key3, 3)));
+    drop(s3);
+    assert_eq!(pool.recv(), Err(RecvError));
+    assert!(pool.is_empty());
+}
+
+#[test]
+fn timeout() {
+    let (s, r) = bounded(0);
+    let mut pool = SelectPool::new();
+    let key = pool.insert(r);
+
+    let now = Instant::now();
+    assert_eq!(pool.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+    assert!(now.elapsed() >= ms(100));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+        });
+        assert_eq!(pool.recv_timeout(ms(1000)), Ok((key, 1)));
+    });
+}
+
+#[test]
+fn blocking_prune() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded();
+
+    let mut pool = SelectPool::new();
+    pool.insert(r1);
+    let key2 = pool.insert(r2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(s1);
+            thread::sleep(ms(100));
+            s2.send(2).unwrap();
+        });
+
+        assert_eq!(pool.recv(), Ok((key2, 2)));
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.recv(), Err(RecvError));
+    });
+}
+
+#[test]
+fn many_connections() {
+    const CONNECTIONS: usize = 50;
+    const COUNT: usize = 100;
+
+    let mut pool = SelectPool::new();
+    let mut senders = Vec::new();
+    for _ in 0..CONNECTIONS {
+        let (s, r) = bounded(1);
+        senders.push((pool.insert(r), s));
+    }
+
+    crossbeam::scope(|scope| {
+        for (key, s) in senders {
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    s.send((key, i)).unwrap();
+                }
+            });
+        }
+
+        let mut next = vec![0; CONNECTIONS];
+        while let Ok((key, (k, i))) = pool.recv() {
+            assert_eq!(key, k);
+            assert_eq!(next[key], i);
+            next[key] += 1;
+        }
+        assert_eq!(next, vec![COUNT; CONNECTIONS]);
+    });
+}
@@ -0,0 +1,151 @@
+//! Tests for priorities of send cases in `select!`.
+
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded};
+
+const ROUNDS: usize = 100;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn higher_priority_wins() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+
+    for i in 0..ROUNDS {
+        select! {
+            send(s1, i) -> res => res.unwrap(),
+            send(s2, i, priority = 1) -> res => res.unwrap(),
+        }
+    }
+    assert_eq!(r1.len(), 0);
+    assert_eq!(r2.len(), ROUNDS);
+}
+
+#[test]
+fn ordering() {
+    let (s1, r1) = bounded(1);
+    let (s2, r2) = bounded(1);
+    let (s3, r3) = bounded(1);
+    let (s4, r4) = bounded(1);
+
+    let mut order = Vec::new();
+    for _ in 0..4 {
+        select! {
+            send(s1, (), priority = -1) -> _ => order.push(1),
+            send(s2, (), priority = 5) -> _ => order.push(2),
+            send(s3, ()) -> _ => order.push(3),
+            send(s4, (), priority = 2,) -> _ => order.push(4),
+        }
+    }
+    assert_eq!(order, [2, 4, 3, 1]);
+
+    drop((r1, r2, r3, r4));
+}
+
+#[test]
+fn equal_priorities_are_fair() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let (s3, r3) = unbounded();
+
+    for _ in 0..ROUNDS {
+        select! {
+            send(s1, (), priority = 3) -> _ => {}
+            send(s2, (), priority = 3) -> _ => {}
+            send(s3, (), priority = 0) -> _ => {}
+        }
+    }
+    assert!(r1.len() > 0);
+    assert!(r2.len() > 0);
+    assert_eq!(r3.len(), 0);
+}
+
+#[test]
+fn receives_stay_random() {
+    let (s, r) = unbounded();
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+
+    let mut received = 0;
+    for _ in 0..ROUNDS {
+        s.send(()).unwrap();
+        select! {
+            recv(r) -> _ => received += 1,
+            send(s1, (), priority = 10) -> _ => {}
+            send(s2, ()) -> _ => {}
+        }
+        while r.try_recv().is_ok() {}
+    }
+    assert!(received > 0);
+    assert!(r1.len() > 0);
+    assert_eq!(r2.len(), 0);
+}
+
+#[test]
+fn only_ready_operations_count() {
+    let (s1, r1) = bounded(0);
+    let (s2, r2) = unbounded();
+
+    for i in 0..ROUNDS {
+        select! {
+            send(s1, i, priority = 100) -> _ => panic!(),
+            send(s2, i) -> res => res.unwrap(),
+        }
+    }
+    assert_eq!(r2.len(), ROUNDS);
+    drop(r1);
+}
+
+#[test]
+fn non_blocking_and_timeout() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+
+    for i in 0..ROUNDS {
+        select! {
+            send(s1, i, priority = 1) -> res => res.unwrap(),
+            send(s2, i) -> res => res.unwrap(),
+            default => panic!(),
+        }
+        select! {
+            send(s1, i) -> res => res.unwrap(),
+            send(s2, i, priority = 1) -> res => res.unwrap(),
+            default(ms(1000)) => panic!(),
+        }
+    }
+    assert_eq!(r1.len(), ROUNDS);
+    assert_eq!(r2.len(), ROUNDS);
+
+    let (s1, _r1) = bounded(0);
+    let (s2, _r2) = bounded(0);
+    let mut timed_out = false;
+    select! {
+        send(s1, 1, priority = 1) -> _ => panic!(),
+        send(s2, 2) -> _ => panic!(),
+        default(ms(50)) => timed_out = true,
+    }
+    assert!(timed_out);
+}
+
+#[test]
+fn disconnected_is_ready() {
+    let (s1, r1) = bounded(0);
+    let (s2, r2) = unbounded();
+    drop(r1);
+
+    // A send into a disconnected channel is ready, so the priority applies to it as well.
+    for i in 0..ROUNDS {
+        select! {
+            send(s1, i, priority = 1) -> res => assert!(res.is_err()),
+            send(s2, i) -> _ => panic!(),
+        }
+    }
+    drop(r2);
+}
@@ -0,0 +1,135 @@
+//! Tests for seeding the random number generator used for fairness in selection.
+
+#[macro_use]
+extern crate crossbeam_channel;
+
+use crossbeam_channel::{set_rng_seed, unbounded, Receiver, Select, Sender};
+
+const ROUNDS: usize = 100;
+
+fn pairs() -> Vec<(Sender<()>, Receiver<()>)> {
+    vec![unbounded(), unbounded(), unbounded()]
+}
+
+/// Records which operations `sel` picks when all of them are always ready.
+fn picks(sel: &mut Select, pairs: &[(Sender<()>, Receiver<()>)]) -> Vec<usize> {
+    (0..ROUNDS)
+        .map(|_| {
+            for &(ref s, _) in pairs {
+                s.send(()).unwrap();
+            }
+            let oper = sel.select();
+            let index = oper.index();
+            oper.recv(&pairs[index].1).unwrap();
+            for (i, &(_, ref r)) in pairs.iter().enumerate() {
+                if i != index {
+                    r.recv().unwrap();
+                }
+            }
+            index
+        })
+        .collect()
+}
+
+/// Records which operations `select!` picks when all of them are always ready.
+fn macro_picks(pairs: &[(Sender<()>, Receiver<()>)]) -> Vec<usize> {
+    let (r0, r1, r2) = (&pairs[0].1, &pairs[1].1, &pairs[2].1);
+    (0..ROUNDS)
+        .map(|_| {
+            for &(ref s, _) in pairs {
+                s.send(()).unwrap();
+            }
+            let index = select! {
+                recv(r0) -> _ => 0,
+                recv(r1) -> _ => 1,
+                recv(r2) -> _ => 2,
+            };
+            for (i, &(_, ref r)) in pairs.iter().enumerate() {
+                if i != index {
+                    r.recv().unwrap();
+                }
+            }
+            index
+        })
+        .collect()
+}
+
+fn seeded_picks(seed: u64) -> Vec<usize> {
+    let pairs = pairs();
+    let mut sel = Select::with_rng_seed(seed);
+    for &(_, ref r) in &pairs {
+        sel.recv(r);
+    }
+    picks(&mut sel, &pairs)
+}
+
+#[test]
+fn same_seed() {
+    for seed in 0..10 {
+        assert_eq!(seeded_picks(seed), seeded_picks(seed));
+    }
+}
+
+#[test]
+fn different_seeds() {
+    let first = seeded_picks(0);
+    assert!((1..10).any(|seed| seeded_picks(seed) != first));
+}
+
+#[test]
+fn still_random() {
+    let v = seeded_picks(42);
+    for i in 0..3 {
+        assert!(v.iter().any(|&x| x == i));
+    }
+}
+
+#[test]
+fn independent_of_thread_rng() {
+    let expected = seeded_picks(3);
+
+    // Unseeded selections in between don't disturb a seeded `Select`.
+    let pairs = pairs();
+    let mut sel = Select::with_rng_seed(3);
+    for &(_, ref r) in &pairs {
+        sel.recv(r);
+    }
+    set_rng_seed(100);
+    let mut v = picks(&mut sel, &pairs);
+    v.truncate(ROUNDS / 2);
+    macro_picks(&pairs);
+    assert_eq!(&v[..], &expected[..ROUNDS / 2]);
+}
+
+#[test]
+fn clone_keeps_state() {
+    let pairs = pairs();
+    let mut sel = Select::with_rng_seed(5);
+    for &(_, ref r) in &pairs {
+        sel.recv(r);
+    }
+    picks(&mut sel, &pairs);
+
+    let mut clone = sel.clone();
+    assert_eq!(picks(&mut sel, &pairs), picks(&mut clone, &pairs));
+}
+
+#[test]
+fn thread_seed() {
+    let pairs = pairs();
+
+    set_rng_seed(11);
+    let first = macro_picks(&pairs);
+    set_rng_seed(11);
+    assert_eq!(macro_picks(&pairs), first);
+
+    let run = || {
+        let mut sel = Select::new();
+        for &(_, ref r) in &pairs {
+            sel.recv(r);
+        }
+        set_rng_seed(11);
+        picks(&mut sel, &pairs)
+    };
+    assert_eq!(run(), run());
+}
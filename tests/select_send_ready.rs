@@ -0,0 +1,129 @@
+//! Tests for selecting over send readiness without a message.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, lanes, unbounded, Select, SendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn ready_right_away() {
+    let (s, r) = bounded(1);
+
+    let mut sel = Select::new();
+    let oper1 = sel.send_ready(&s);
+
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.send_ready(&s), Ok(()));
+
+    // Nothing was sent.
+    assert!(r.is_empty());
+    s.try_send(1).unwrap();
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn full() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    let mut sel = Select::new();
+    sel.send_ready(&s);
+    assert!(sel.try_select().is_err());
+    assert!(sel.select_timeout(ms(100)).is_err());
+    assert_eq!(r.len(), 1);
+}
+
+#[test]
+fn wakes_up_when_room() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(300));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        let start = Instant::now();
+        let mut sel = Select::new();
+        let oper1 = sel.send_ready(&s);
+
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper1);
+        assert_eq!(oper.send_ready(&s), Ok(()));
+        assert!(start.elapsed() >= ms(200));
+        s.try_send(2).unwrap();
+    });
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded(0);
+
+    let mut sel = Select::new();
+    sel.send_ready(&s);
+    assert!(sel.try_select().is_err());
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| assert_eq!(r.recv(), Ok(7)));
+
+        let oper = sel.select();
+        assert_eq!(oper.send_ready(&s), Ok(()));
+        s.send(7).unwrap();
+    });
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = bounded::<i32>(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            drop(r);
+        });
+
+        let mut sel = Select::new();
+        sel.send_ready(&s);
+        let oper = sel.select();
+        assert_eq!(oper.send_ready(&s), Err(SendError(())));
+    });
+}
+
+#[test]
+fn mixed_with_recv() {
+    let (s1, r1) = lanes(1, 2);
+    let (s2, r2) = unbounded();
+    s1.send(0).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s2.send("hello").unwrap();
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.send_ready(&s1);
+        let oper2 = sel.recv(&r2);
+
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper2);
+        assert_eq!(oper.recv(&r2), Ok("hello"));
+
+        assert_eq!(r1.recv(), Ok(0));
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper1);
+        oper.send_ready(&s1).unwrap();
+    });
+}
+
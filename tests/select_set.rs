@@ -0,0 +1,240 @@
+//! Tests for the `SelectSet` struct.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, Fairness, RecvError, SelectSet, SendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<String>();
+
+    let mut set = SelectSet::new();
+    let oper1 = set.recv(r1);
+    let oper2 = set.recv(r2);
+    assert_eq!((oper1, oper2), (0, 1));
+    assert_eq!(set.len(), 2);
+
+    s2.send("hello".to_string()).unwrap();
+    let oper = set.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(set.receiver::<String>(oper2)), Ok("hello".to_string()));
+
+    s1.send(1).unwrap();
+    let oper = set.select();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.recv(set.receiver::<i32>(oper1)), Ok(1));
+
+    assert!(set.try_select().is_err());
+}
+
+#[test]
+fn send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SelectSet>();
+}
+
+#[test]
+fn move_between_threads() {
+    struct EventLoop {
+        set: SelectSet,
+        ticks: usize,
+        quit: usize,
+    }
+
+    let (s1, r1) = unbounded::<()>();
+    let (s2, r2) = bounded::<()>(0);
+
+    let mut set = SelectSet::new();
+    let ticks = set.recv(r1);
+    let quit = set.recv(r2);
+    let mut event_loop = EventLoop { set, ticks, quit };
+
+    let handle = thread::spawn(move || {
+        let mut count = 0;
+        loop {
+            let oper = event_loop.set.select();
+            match oper.index() {
+                i if i == event_loop.ticks => {
+                    oper.recv(event_loop.set.receiver::<()>(i)).unwrap();
+                    count += 1;
+                }
+                i if i == event_loop.quit => {
+                    assert_eq!(oper.recv(event_loop.set.receiver::<()>(i)), Err(RecvError));
+                    break;
+                }
+                _ => unreachable!(),
+            }
+        }
+        count
+    });
+
+    for _ in 0..10 {
+        s1.send(()).unwrap();
+    }
+    thread::sleep(ms(100));
+    drop(s2);
+
+    assert_eq!(handle.join().unwrap(), 10);
+}
+
+#[test]
+fn send_and_recv() {
+    let (s1, r1) = bounded::<i32>(1);
+    let (_s2, r2) = bounded::<i32>(0);
+
+    let mut set = SelectSet::new();
+    let oper1 = set.send(s1);
+    set.recv(r2);
+
+    let oper = set.select();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.send(set.sender(oper1), 7), Ok(()));
+    assert_eq!(r1.recv(), Ok(7));
+
+    drop(r1);
+    let oper = set.select();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.send(set.sender(oper1), 8), Err(SendError(8)));
+}
+
+#[test]
+fn timeout() {
+    let (s, r) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    let oper1 = set.recv(r);
+
+    let now = Instant::now();
+    assert!(set.select_timeout(ms(100)).is_err());
+    assert!(now.elapsed() >= ms(100));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+        });
+
+        let oper = set.select_timeout(ms(1000)).unwrap();
+        assert_eq!(oper.index(), oper1);
+        assert_eq!(oper.recv(set.receiver(oper1)), Ok(1));
+    });
+}
+
+#[test]
+fn empty() {
+    let set = SelectSet::new();
+    assert!(set.is_empty());
+    assert!(set.try_select().is_err());
+    assert!(set.select_timeout(ms(50)).is_err());
+}
+
+#[test]
+#[should_panic(expected = "no operations have been added to `SelectSet`")]
+fn select_empty() {
+    let set = SelectSet::new();
+    let _ = set.select();
+}
+
+#[test]
+#[should_panic(expected = "operation 0 is not a receive operation of this type")]
+fn receiver_wrong_type() {
+    let (_s, r) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.recv(r);
+    set.receiver::<u32>(0);
+}
+
+#[test]
+#[should_panic(expected = "operation 0 is not a send operation of this type")]
+fn sender_of_receive_operation() {
+    let (_s, r) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.recv(r);
+    set.sender::<i32>(0);
+}
+
+#[test]
+#[should_panic(expected = "no operation with index 1 has been added to `SelectSet`")]
+fn receiver_out_of_bounds() {
+    let (_s, r) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.recv(r);
+    set.receiver::<i32>(1);
+}
+
+#[test]
+fn round_robin() {
+    const COUNT: usize = 100;
+
+    let channels: Vec<_> = (0..3).map(|_| unbounded::<usize>()).collect();
+
+    let mut set = SelectSet::new();
+    set.set_fairness(Fairness::RoundRobin);
+    for &(ref s, ref r) in &channels {
+        set.recv(r.clone());
+        for i in 0..COUNT {
+            s.send(i).unwrap();
+        }
+    }
+
+    for i in 0..3 * COUNT {
+        let oper = set.select();
+        assert_eq!(oper.index(), i % 3);
+        assert_eq!(oper.recv(set.receiver::<usize>(i % 3)), Ok(i / 3));
+    }
+}
+
+#[test]
+fn shared_between_threads() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let (s1, r1) = bounded::<usize>(5);
+    let (s2, r2) = unbounded::<usize>();
+
+    let mut set = SelectSet::new();
+    let oper1 = set.recv(r1);
+    let oper2 = set.recv(r2);
+    let received = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                s1.send(i).unwrap();
+                s2.send(i).unwrap();
+            }
+        });
+
+        for _ in 0..THREADS {
+            scope.spawn(|| loop {
+                if received.load(Ordering::SeqCst) == 2 * COUNT {
+                    break;
+                }
+                if let Ok(oper) = set.select_timeout(ms(100)) {
+                    let i = oper.index();
+                    match i {
+                        i if i == oper1 => oper.recv(set.receiver::<usize>(i)).unwrap(),
+                        i if i == oper2 => oper.recv(set.receiver::<usize>(i)).unwrap(),
+                        _ => unreachable!(),
+                    };
+                    received.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+
+    assert_eq!(received.load(Ordering::SeqCst), 2 * COUNT);
+}
@@ -0,0 +1,203 @@
+//! Tests for custom `Selectable` primitives.
+
+#[macro_use]
+extern crate crossbeam_channel;
+extern crate crossbeam;
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, Notifier, Select, Selectable, Trigger};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// A counter that is ready while it is positive.
+struct Counter {
+    count: AtomicUsize,
+    notifier: Notifier,
+}
+
+impl Counter {
+    fn new() -> Counter {
+        Counter {
+            count: AtomicUsize::new(0),
+            notifier: Notifier::new(),
+        }
+    }
+
+    fn add(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        self.notifier.notify();
+    }
+
+    fn take(&self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Selectable for Counter {
+    fn is_ready(&self) -> bool {
+        self.count.load(Ordering::SeqCst) > 0
+    }
+
+    fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+}
+
+/// A primitive that becomes ready at a deadline without notifications.
+struct Alarm(Instant, Notifier);
+
+impl Selectable for Alarm {
+    fn is_ready(&self) -> bool {
+        Instant::now() >= self.0
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        Some(self.0)
+    }
+
+    fn notifier(&self) -> &Notifier {
+        &self.1
+    }
+}
+
+#[test]
+fn ready() {
+    let c = Counter::new();
+    c.add();
+
+    let mut sel = Select::new();
+    let oper1 = sel.custom(&c);
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), oper1);
+    oper.custom(&c);
+    c.take();
+
+    assert!(sel.try_select().is_err());
+    assert!(sel.select_timeout(ms(100)).is_err());
+}
+
+#[test]
+fn notified() {
+    let c = Counter::new();
+    let (s, r) = bounded::<i32>(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            c.add();
+        });
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+        let oper2 = sel.custom(&c);
+
+        let start = Instant::now();
+        let oper = sel.select();
+        assert!(start.elapsed() >= ms(50));
+        assert_eq!(oper.index(), oper2);
+        oper.custom(&c);
+    });
+    drop(s);
+}
+
+#[test]
+fn deadline() {
+    let alarm = Alarm(Instant::now() + ms(100), Notifier::new());
+
+    let start = Instant::now();
+    select! {
+        custom(alarm) => {}
+    }
+    assert!(start.elapsed() >= ms(100));
+}
+
+#[test]
+fn macro_custom() {
+    let c = Counter::new();
+    let (s, r) = unbounded();
+
+    select! {
+        recv(r) -> _ => panic!(),
+        custom(c) => panic!(),
+        default => {}
+    }
+
+    c.add();
+    select! {
+        recv(r) -> _ => panic!(),
+        custom(c) => c.take(),
+    }
+
+    s.send(1).unwrap();
+    select! {
+        recv(r) -> msg => assert_eq!(msg, Ok(1)),
+        custom(&c) => panic!(),
+        default(ms(100)) => panic!(),
+    }
+}
+
+#[test]
+fn macro_trigger() {
+    let t = Trigger::new();
+    let (_s, r) = unbounded::<i32>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            t.set();
+        });
+
+        select! {
+            recv(r) -> _ => panic!(),
+            custom(t) => assert!(t.is_set()),
+        }
+    });
+}
+
+#[test]
+fn many_waiters() {
+    const THREADS: usize = 4;
+
+    let c = Counter::new();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                select! {
+                    custom(c) => {}
+                }
+            });
+        }
+
+        thread::sleep(ms(100));
+        c.add();
+    });
+}
+
+#[test]
+fn macro_custom_not_sync() {
+    struct Flag(Cell<bool>, Notifier);
+
+    impl Selectable for Flag {
+        fn is_ready(&self) -> bool {
+            self.0.get()
+        }
+
+        fn notifier(&self) -> &Notifier {
+            &self.1
+        }
+    }
+
+    let f = Flag(Cell::new(true), Notifier::new());
+    select! {
+        custom(f) => f.0.set(false),
+        default => panic!(),
+    }
+    assert!(!f.0.get());
+}
@@ -0,0 +1,168 @@
+//! Tests for `Sender::send_now` and `Receiver::recv_now`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, lanes, never, tick, unbounded};
+use crossbeam_channel::{TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn send_without_receiver() {
+    let (s, r) = unbounded();
+    assert_eq!(s.send_now(1), Err(TrySendError::Full(1)));
+
+    let (s2, r2) = bounded(5);
+    assert_eq!(s2.send_now(2), Err(TrySendError::Full(2)));
+
+    let (s3, r3) = bounded(0);
+    assert_eq!(s3.send_now(3), Err(TrySendError::Full(3)));
+
+    let (s4, r4) = lanes(5, 2);
+    assert_eq!(s4.send_now(4), Err(TrySendError::Full(4)));
+
+    // Nothing was buffered.
+    assert!(r.is_empty());
+    assert!(r2.is_empty());
+    assert!(r3.is_empty());
+    assert!(r4.is_empty());
+}
+
+#[test]
+fn send_to_waiting_receiver() {
+    let (s, r) = unbounded();
+    let (s2, r2) = bounded(5);
+    let (s3, r3) = bounded(0);
+    let (s4, r4) = lanes(5, 2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv(), Ok(1));
+            assert_eq!(r2.recv(), Ok(2));
+            assert_eq!(r3.recv(), Ok(3));
+            assert_eq!(r4.recv(), Ok(4));
+        });
+
+        thread::sleep(ms(100));
+        assert_eq!(s.send_now(1), Ok(()));
+        thread::sleep(ms(100));
+        assert_eq!(s2.send_now(2), Ok(()));
+        thread::sleep(ms(100));
+        assert_eq!(s3.send_now(3), Ok(()));
+        thread::sleep(ms(100));
+        assert_eq!(s4.send_now(4), Ok(()));
+    });
+}
+
+#[test]
+fn send_disconnected() {
+    for &cap in &[0, 1] {
+        let (s, r) = bounded(cap);
+        drop(r);
+        assert_eq!(s.send_now(1), Err(TrySendError::Disconnected(1)));
+    }
+
+    let (s, r) = unbounded();
+    drop(r);
+    assert_eq!(s.send_now(1), Err(TrySendError::Disconnected(1)));
+}
+
+#[test]
+fn recv_without_sender() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+
+    let (s, r) = bounded(5);
+    s.send(1).unwrap();
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+
+    // Messages sitting in the buffer stay there.
+    assert_eq!(r.try_recv(), Ok(1));
+
+    let (_s, r) = bounded::<i32>(0);
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn recv_from_waiting_sender() {
+    let (s, r) = bounded(1);
+    let (s2, r2) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+            s2.send(3).unwrap();
+        });
+
+        thread::sleep(ms(100));
+        assert_eq!(r.recv_now(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+        thread::sleep(ms(100));
+        assert_eq!(r2.recv_now(), Ok(3));
+    });
+}
+
+#[test]
+fn recv_disconnected() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+    drop(s);
+
+    // The remaining message isn't held by a blocked sender.
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv_now(), Err(TryRecvError::Disconnected));
+
+    let (s, r) = bounded::<i32>(0);
+    drop(s);
+    assert_eq!(r.recv_now(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn special_receivers() {
+    let r = after(ms(0));
+    thread::sleep(ms(50));
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+
+    let r = tick(ms(0));
+    thread::sleep(ms(50));
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+
+    let r = never::<i32>();
+    assert_eq!(r.recv_now(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn handoff() {
+    const COUNT: usize = 1000;
+
+    let (s, r) = bounded::<usize>(0);
+    let offloaded = AtomicUsize::new(0);
+    let mut done_locally = 0;
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for _ in r.iter() {
+                offloaded.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        for i in 0..COUNT {
+            if s.send_now(i).is_err() {
+                done_locally += 1;
+            }
+        }
+        drop(s);
+    });
+
+    assert_eq!(offloaded.load(Ordering::SeqCst) + done_locally, COUNT);
+}
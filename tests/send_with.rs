@@ -0,0 +1,234 @@
+//! Tests for `Sender::send_with`.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, lanes, unbounded, Builder, DropPolicy};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    s.send_with(|| 7).unwrap();
+    assert_eq!(r.recv(), Ok(7));
+
+    let (s, r) = bounded(1);
+    s.send_with(|| 8).unwrap();
+    assert_eq!(r.recv(), Ok(8));
+
+    let (s, r) = lanes(1, 2);
+    s.send_with(|| 9).unwrap();
+    assert_eq!(r.recv(), Ok(9));
+}
+
+#[test]
+fn disconnected() {
+    let calls = AtomicUsize::new(0);
+
+    let (s, r) = bounded::<i32>(1);
+    drop(r);
+    let f = s
+        .send_with(|| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            1
+        })
+        .unwrap_err()
+        .into_inner();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    // The closure is handed back and can still be called.
+    assert_eq!(f(), 1);
+}
+
+#[test]
+fn waits_for_receiver() {
+    let (s, r) = bounded(0);
+    let called = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            assert_eq!(called.load(Ordering::SeqCst), 0);
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        s.send_with(|| {
+            called.fetch_add(1, Ordering::SeqCst);
+            1
+        }).unwrap();
+    });
+}
+
+#[test]
+fn waits_for_room() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+    let called = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            assert_eq!(called.load(Ordering::SeqCst), 0);
+            assert_eq!(r.recv(), Ok(1));
+            assert_eq!(r.recv(), Ok(2));
+        });
+
+        s.send_with(|| {
+            called.fetch_add(1, Ordering::SeqCst);
+            2
+        }).unwrap();
+    });
+}
+
+#[test]
+fn disconnect_while_waiting() {
+    let (s, r) = bounded::<i32>(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(r);
+        });
+
+        assert!(s.send_with(|| panic!()).is_err());
+    });
+}
+
+#[test]
+fn disconnect_while_constructing() {
+    for &cap in &[Some(1), None] {
+        let (dead_s, dead_r) = unbounded();
+        let builder = Builder::new().drop_policy(DropPolicy::Forward(dead_s));
+        let (s, r) = match cap {
+            Some(cap) => builder.bounded(cap),
+            None => builder.unbounded(),
+        };
+
+        // The message can't be sent anymore, so it goes to the drop policy.
+        s.send_with(move || {
+            drop(r);
+            1
+        }).unwrap();
+        assert_eq!(s.pending_on_disconnect(), Some(1));
+        assert_eq!(dead_r.try_recv(), Ok(1));
+    }
+}
+
+#[test]
+fn panic_gives_room_back() {
+    let (s, r) = bounded(1);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| s.send_with(|| panic!())));
+    assert!(res.is_err());
+    assert_eq!(s.try_send(1), Ok(()));
+    assert_eq!(r.recv(), Ok(1));
+
+    let (s, r) = lanes(1, 2);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| s.send_with(|| panic!())));
+    assert!(res.is_err());
+    assert_eq!(s.try_send(2), Ok(()));
+    assert_eq!(r.recv(), Ok(2));
+
+    let (s, r) = unbounded();
+    let res = panic::catch_unwind(AssertUnwindSafe(|| s.send_with(|| panic!())));
+    assert!(res.is_err());
+    assert!(r.is_empty());
+    assert_eq!(s.try_send(3), Ok(()));
+    assert_eq!(r.recv(), Ok(3));
+}
+
+#[test]
+fn panic_lets_receiver_go() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv(), Ok(2));
+            assert_eq!(r.recv_timeout(ms(1000)), Ok(4));
+        });
+
+        thread::sleep(ms(100));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| s.send_with(|| panic!())));
+        assert!(res.is_err());
+        s.send(2).unwrap();
+
+        thread::sleep(ms(100));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| s.send_with(|| panic!())));
+        assert!(res.is_err());
+        s.send(4).unwrap();
+    });
+}
+
+#[test]
+fn panic_lets_select_go() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            select! {
+                recv(r) -> msg => assert_eq!(msg, Ok(2)),
+            }
+        });
+
+        thread::sleep(ms(100));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| s.send_with(|| panic!())));
+        assert!(res.is_err());
+        s.send(2).unwrap();
+    });
+}
+
+#[test]
+fn stats() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            r.recv().unwrap();
+        });
+
+        s.send_with(|| ()).unwrap();
+    });
+    assert_eq!(s.stats().blocked, 1);
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    for &cap in &[0, 1, 100] {
+        let (s, r) = bounded::<usize>(cap);
+        let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+        crossbeam::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..COUNT {
+                        let n = r.recv().unwrap();
+                        v[n].fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for i in 0..COUNT {
+                        s.send_with(|| i).unwrap();
+                    }
+                });
+            }
+        });
+
+        for c in &v {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+}
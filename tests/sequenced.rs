@@ -0,0 +1,174 @@
+//! Tests for channels with sequence-stamped messages.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded_sequenced, unbounded, unbounded_sequenced, Select, SequenceStats};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded_sequenced(2);
+    s.send(7).unwrap();
+    s.send(8).unwrap();
+
+    assert_eq!(r.recv_sequenced(), Ok((0, 7)));
+    assert_eq!(r.try_recv(), Ok(8));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+
+    assert_eq!(s.last_sequence(), Some(1));
+    assert_eq!(r.last_sequence(), Some(1));
+}
+
+#[test]
+#[should_panic]
+fn zero_capacity() {
+    bounded_sequenced::<()>(0);
+}
+
+#[test]
+fn capacity() {
+    let (s, r) = bounded_sequenced::<()>(3);
+    assert_eq!(s.capacity(), Some(3));
+    assert_eq!(r.capacity(), Some(3));
+
+    let (s, r) = unbounded_sequenced::<()>();
+    assert_eq!(s.capacity(), None);
+    assert_eq!(r.capacity(), None);
+}
+
+#[test]
+fn full_keeps_sequence() {
+    let (s, r) = bounded_sequenced(1);
+    s.try_send(1).unwrap();
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(s.last_sequence(), Some(0));
+
+    r.recv().unwrap();
+    s.try_send(3).unwrap();
+    assert_eq!(r.recv_sequenced(), Ok((1, 3)));
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = unbounded_sequenced();
+    s.send(1).unwrap();
+    drop(s);
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+
+    let (s, r) = bounded_sequenced(1);
+    drop(r);
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert_eq!(s.try_send(2), Err(TrySendError::Disconnected(2)));
+    assert_eq!(s.last_sequence(), None);
+}
+
+#[test]
+fn gaps() {
+    let (s, r) = unbounded_sequenced();
+    let other = r.clone();
+    for i in 0..10 {
+        s.send(i).unwrap();
+    }
+
+    let inner = other.into_inner();
+    assert_eq!(inner.recv(), Ok((0, 0)));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(inner.recv(), Ok((2, 2)));
+    assert_eq!(inner.recv(), Ok((3, 3)));
+    assert_eq!(r.recv(), Ok(4));
+    assert_eq!(r.recv(), Ok(5));
+
+    assert_eq!(r.last_sequence(), Some(5));
+    assert_eq!(
+        r.stats(),
+        SequenceStats {
+            received: 3,
+            gaps: 2,
+            missing: 3,
+            out_of_order: 0,
+        }
+    );
+}
+
+#[test]
+fn blocked_send() {
+    let (s, r) = bounded_sequenced(1);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..3 {
+            scope.spawn(|| s.send(()).unwrap());
+        }
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            for i in 0..3 {
+                assert_eq!(r.recv_sequenced(), Ok((i, ())));
+            }
+        });
+    });
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded_sequenced();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s2.send("foo").unwrap();
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv(&r1);
+        let oper2 = sel.recv_ready(&r2);
+
+        let oper = sel.select_timeout(ms(1000)).unwrap();
+        assert_eq!(oper.index(), oper2);
+        oper.recv_ready(&r2);
+        assert_eq!(r2.try_recv(), Ok("foo"));
+        assert_eq!(r2.last_sequence(), Some(0));
+
+        assert!(sel.select_timeout(ms(100)).is_err());
+        drop(s1);
+        let oper = sel.select_timeout(ms(100)).unwrap();
+        assert_eq!(oper.index(), oper1);
+        assert_eq!(oper.recv(&r1), Err(RecvError));
+    });
+}
+
+#[test]
+fn mpsc_in_order() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded_sequenced(3);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+
+        for i in 0..COUNT * THREADS {
+            assert_eq!(r.recv_sequenced().unwrap().0, i as u64);
+        }
+    });
+
+    let stats = r.stats();
+    assert_eq!(stats.received, (COUNT * THREADS) as u64);
+    assert_eq!(stats.gaps, 0);
+    assert_eq!(stats.out_of_order, 0);
+}
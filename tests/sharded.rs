@@ -0,0 +1,216 @@
+//! Tests for sharded channels.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded_sharded, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded_sharded(4);
+    s.send(7).unwrap();
+    assert_eq!(r.try_recv(), Ok(7));
+
+    s.send(8).unwrap();
+    assert_eq!(r.recv(), Ok(8));
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+#[should_panic]
+fn zero_shards() {
+    unbounded_sharded::<()>(0);
+}
+
+#[test]
+fn shards() {
+    let (s, r) = unbounded_sharded::<()>(3);
+    assert_eq!(s.shards(), 3);
+    assert_eq!(r.shards(), 3);
+    assert_eq!(s.clone().shards(), 3);
+    assert_eq!(r.clone().shards(), 3);
+}
+
+#[test]
+fn len() {
+    let (s, r) = unbounded_sharded(4);
+    assert!(s.is_empty());
+    assert!(r.is_empty());
+
+    crossbeam::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for i in 0..10 {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    });
+
+    assert_eq!(s.len(), 80);
+    assert_eq!(r.len(), 80);
+
+    for _ in 0..80 {
+        r.recv().unwrap();
+    }
+    assert!(s.is_empty());
+    assert!(r.is_empty());
+}
+
+#[test]
+fn disconnect_wakes_receiver() {
+    let (s, r) = unbounded_sharded::<()>(4);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(500));
+            drop(s);
+        });
+
+        assert_eq!(r.recv(), Err(RecvError));
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded_sharded(4);
+
+    // Every thread sends into a different shard.
+    for i in 0..4 {
+        let s = s.clone();
+        thread::spawn(move || {
+            thread::sleep(ms(100));
+            s.send(i).unwrap();
+        });
+    }
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv_ready(&r);
+
+    let mut v = Vec::new();
+    while v.len() < 4 {
+        let oper = sel.select_timeout(ms(1000)).unwrap();
+        assert_eq!(oper.index(), oper1);
+        oper.recv_ready(&r);
+        v.extend(r.try_recv());
+    }
+    v.sort();
+    assert_eq!(v, [0, 1, 2, 3]);
+
+    drop(s);
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    oper.recv_ready(&r);
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn disconnect_wakes_sender() {
+    let (s, r) = unbounded_sharded(4);
+    drop(r);
+    assert_eq!(s.send(1), Err(SendError(1)));
+}
+
+#[test]
+fn remaining_after_disconnect() {
+    let (s, r) = unbounded_sharded(4);
+
+    crossbeam::scope(|scope| {
+        for i in 0..4 {
+            let s = s.clone();
+            scope.spawn(move || s.send(i).unwrap());
+        }
+    });
+    drop(s);
+
+    let mut v: Vec<_> = r.iter().collect();
+    v.sort();
+    assert_eq!(v, [0, 1, 2, 3]);
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn recv_timeout() {
+    let (s, r) = unbounded_sharded(4);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv_timeout(ms(1000)), Ok(7));
+            let now = Instant::now();
+            assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+            assert!(now.elapsed() >= ms(100));
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+        });
+    });
+}
+
+#[test]
+fn per_thread_order() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 8;
+
+    let (s, r) = unbounded_sharded(4);
+
+    crossbeam::scope(|scope| {
+        for t in 0..THREADS {
+            let s = s.clone();
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    s.send((t, i)).unwrap();
+                }
+            });
+        }
+        drop(s);
+
+        let mut next = vec![0; THREADS];
+        for (t, i) in r.iter() {
+            assert_eq!(next[t], i);
+            next[t] += 1;
+        }
+        assert!(next.iter().all(|&n| n == COUNT));
+    });
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded_sharded::<usize>(THREADS);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..COUNT {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    });
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
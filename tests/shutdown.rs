@@ -0,0 +1,261 @@
+//! Tests for shutting down groups of channels.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Builder, RecvError, SendError, ShutdownGroup, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let group = ShutdownGroup::new();
+    let (s1, r1) = Builder::new().shutdown_group(&group).bounded(5);
+    let (s2, r2) = Builder::new().shutdown_group(&group).unbounded();
+    let (s3, r3) = Builder::new().shutdown_group(&group).bounded(0);
+    let (s4, r4) = Builder::new().shutdown_group(&group).lanes(4, 2);
+    assert_eq!(group.len(), 4);
+
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+    s4.send(4).unwrap();
+
+    group.shutdown();
+    assert!(group.is_shut_down());
+
+    assert_eq!(s1.send(10), Err(SendError(10)));
+    assert_eq!(s2.send(20), Err(SendError(20)));
+    assert_eq!(s3.send(30), Err(SendError(30)));
+    assert_eq!(s4.send(40), Err(SendError(40)));
+
+    // Messages sent before the shutdown can still be received.
+    assert_eq!(r1.recv(), Ok(1));
+    assert_eq!(r2.recv(), Ok(2));
+    assert_eq!(r4.recv(), Ok(4));
+    assert_eq!(r1.recv(), Err(RecvError));
+    assert_eq!(r2.recv(), Err(RecvError));
+    assert_eq!(r3.recv(), Err(RecvError));
+    assert_eq!(r4.recv(), Err(RecvError));
+}
+
+#[test]
+fn other_channels_unaffected() {
+    let group = ShutdownGroup::new();
+    let (s1, _r1) = Builder::new().shutdown_group(&group).unbounded();
+    let (s2, _r2) = Builder::new().unbounded();
+
+    group.shutdown();
+    assert_eq!(s1.send(1), Err(SendError(1)));
+    assert_eq!(s2.send(2), Ok(()));
+}
+
+#[test]
+fn wakes_blocked_operations() {
+    let group = ShutdownGroup::new();
+    let (_s0, r0) = Builder::<i32>::new().shutdown_group(&group).bounded(0);
+    let (s1, _r1) = Builder::<i32>::new().shutdown_group(&group).bounded(0);
+    let (s2, r2) = Builder::new().shutdown_group(&group).bounded(1);
+    let (_s3, r3) = Builder::<i32>::new().shutdown_group(&group).unbounded();
+    s2.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| assert_eq!(r0.recv(), Err(RecvError)));
+        scope.spawn(|| assert_eq!(s1.send(1), Err(SendError(1))));
+        scope.spawn(|| assert_eq!(s2.send(2), Err(SendError(2))));
+        scope.spawn(|| assert_eq!(r3.recv(), Err(RecvError)));
+
+        thread::sleep(ms(500));
+        group.shutdown();
+    });
+
+    assert_eq!(r2.try_recv(), Ok(1));
+    assert_eq!(r2.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn attach_after_shutdown() {
+    let group = ShutdownGroup::new();
+    group.shutdown();
+
+    let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn shutdown_twice() {
+    let group = ShutdownGroup::new();
+    let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    s.send(1).unwrap();
+
+    group.shutdown();
+    group.shutdown();
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn detach_on_drop() {
+    let group = ShutdownGroup::new();
+    let (s1, r1) = Builder::<i32>::new().shutdown_group(&group).bounded(1);
+    let (s2, r2) = Builder::<i32>::new().shutdown_group(&group).unbounded();
+    assert_eq!(group.len(), 2);
+
+    drop(s1);
+    assert_eq!(group.len(), 2);
+    drop(r1);
+    assert_eq!(group.len(), 1);
+
+    let group2 = group.clone();
+    drop(group);
+    group2.shutdown();
+    assert_eq!(s2.send(1), Err(SendError(1)));
+    drop((s2, r2));
+    assert!(group2.is_empty());
+}
+
+#[test]
+fn reunite_stays_attached() {
+    let group = ShutdownGroup::new();
+    let (s, r) = Builder::new().shutdown_group(&group).bounded(1);
+    s.send(1).unwrap();
+
+    let (s, r) = s.reunite(r).unwrap().into_unbounded();
+    assert_eq!(group.len(), 1);
+
+    group.shutdown();
+    assert_eq!(s.send(2), Err(SendError(2)));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn wait_drained() {
+    let group = ShutdownGroup::new();
+    let (s, r) = Builder::new().shutdown_group(&group).bounded(100);
+    for i in 0..100 {
+        s.send(i).unwrap();
+    }
+
+    crossbeam::scope(|scope| {
+        let consumer = scope.spawn(move || {
+            let mut count = 0;
+            for _ in r.iter() {
+                thread::sleep(ms(1));
+                count += 1;
+            }
+            count
+        });
+
+        group.shutdown();
+        let start = Instant::now();
+        group.wait_drained();
+        assert!(start.elapsed() >= ms(50));
+        assert!(group.is_drained());
+
+        // Every message sent before the shutdown was received.
+        assert_eq!(consumer.join(), 100);
+    });
+}
+
+#[test]
+fn wait_drained_timeout() {
+    let group = ShutdownGroup::new();
+    let (s, r) = Builder::new().shutdown_group(&group).unbounded();
+    s.send(1).unwrap();
+
+    let start = Instant::now();
+    assert!(!group.wait_drained_timeout(ms(200)));
+    assert!(start.elapsed() >= ms(200));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(200));
+            assert_eq!(r.recv(), Ok(1));
+        });
+        assert!(group.wait_drained_timeout(ms(5000)));
+    });
+
+    // A group without channels is always drained.
+    assert!(ShutdownGroup::new().wait_drained_timeout(ms(0)));
+}
+
+#[test]
+fn wait_drained_select_and_chunks() {
+    let group = ShutdownGroup::new();
+    let (s1, r1) = Builder::new().shutdown_group(&group).bounded(10);
+    let (s2, r2) = Builder::new().shutdown_group(&group).unbounded();
+    for i in 0..5 {
+        s1.send(i).unwrap();
+        s2.send(i).unwrap();
+    }
+    group.shutdown();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            for _ in 0..5 {
+                select! {
+                    recv(r1) -> msg => assert!(msg.is_ok()),
+                }
+            }
+            thread::sleep(ms(100));
+            assert_eq!(r2.chunks(10).next().map(|c| c.len()), Some(5));
+        });
+        assert!(group.wait_drained_timeout(ms(5000)));
+    });
+}
+
+#[test]
+fn drop_while_inspected() {
+    let group = ShutdownGroup::new();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for i in 0..1000 {
+                    let (s, r) = Builder::new().shutdown_group(&group).bounded(1);
+                    // Channels attached after the shutdown are disconnected right away.
+                    let _ = s.send(i);
+                    drop(r);
+                    drop(s);
+                }
+            });
+        }
+
+        for _ in 0..1000 {
+            group.is_drained();
+        }
+        group.shutdown();
+    });
+    assert!(group.is_empty());
+}
+
+#[test]
+fn drop_drains() {
+    let group = ShutdownGroup::new();
+    let (s, r) = Builder::new().shutdown_group(&group).bounded(10);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    group.shutdown();
+    assert!(!group.is_drained());
+
+    // The remaining messages can never be received once the last receiver is dropped.
+    drop(r);
+    group.wait_drained();
+    drop(s);
+}
+
+#[test]
+fn debug() {
+    let group = ShutdownGroup::new();
+    let (_s, _r) = Builder::<i32>::new().shutdown_group(&group).unbounded();
+    assert_eq!(format!("{:?}", group), "ShutdownGroup { is_shut_down: false, len: 1 }");
+}
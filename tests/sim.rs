@@ -0,0 +1,173 @@
+//! Tests for the `sim` module.
+
+#![cfg(feature = "sim")]
+
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crossbeam_channel::sim::Simulation;
+use crossbeam_channel::{bounded, unbounded, RecvTimeoutError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Runs a few producers and consumers and returns the trace and the log of received messages.
+fn pipeline(seed: u64) -> (Vec<usize>, Vec<(usize, i32)>) {
+    const PRODUCERS: usize = 3;
+    const CONSUMERS: usize = 2;
+
+    let (s, r) = bounded(1);
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut sim = Simulation::new(seed);
+
+    for p in 0..PRODUCERS {
+        let s = s.clone();
+        sim.spawn(move || {
+            for i in 0..5 {
+                s.send(p as i32 * 10 + i).unwrap();
+            }
+        });
+    }
+    drop(s);
+
+    for c in 0..CONSUMERS {
+        let r = r.clone();
+        let log = log.clone();
+        sim.spawn(move || {
+            for msg in r.iter() {
+                log.lock().unwrap().push((c, msg));
+            }
+        });
+    }
+    drop(r);
+
+    let trace = sim.run();
+    let log = log.lock().unwrap().clone();
+    (trace, log)
+}
+
+#[test]
+fn replay() {
+    for seed in 0..20 {
+        let (trace, log) = pipeline(seed);
+        assert_eq!(log.len(), 15);
+        assert_eq!(pipeline(seed), (trace, log));
+    }
+}
+
+#[test]
+fn explores_interleavings() {
+    let mut logs = (0..20).map(|seed| pipeline(seed).1).collect::<Vec<_>>();
+    logs.sort();
+    logs.dedup();
+    assert!(logs.len() > 1);
+}
+
+#[test]
+fn select_fairness() {
+    let run = |seed| {
+        let (s1, r1) = unbounded();
+        let (s2, r2) = unbounded();
+        let picks = Arc::new(Mutex::new(Vec::new()));
+        let mut sim = Simulation::new(seed);
+
+        let p = picks.clone();
+        sim.spawn(move || {
+            for _ in 0..20 {
+                s1.send(()).unwrap();
+                s2.send(()).unwrap();
+            }
+            for _ in 0..20 {
+                select! {
+                    recv(r1) -> _ => p.lock().unwrap().push(1),
+                    recv(r2) -> _ => p.lock().unwrap().push(2),
+                }
+            }
+        });
+
+        sim.run();
+        let picks = picks.lock().unwrap().clone();
+        picks
+    };
+
+    assert_eq!(run(3), run(3));
+    assert!((0..10).any(|seed| run(seed) != run(3)));
+}
+
+#[test]
+fn zero_capacity() {
+    for seed in 0..10 {
+        let (s, r) = bounded(0);
+        let mut sim = Simulation::new(seed);
+
+        sim.spawn(move || {
+            for i in 0..10 {
+                s.send(i).unwrap();
+            }
+        });
+        sim.spawn(move || {
+            for i in 0..10 {
+                assert_eq!(r.recv(), Ok(i));
+            }
+            assert!(r.recv().is_err());
+        });
+
+        sim.run();
+    }
+}
+
+#[test]
+fn timeout() {
+    for seed in 0..5 {
+        let (s1, r1) = bounded::<i32>(0);
+        let (s2, r2) = bounded(0);
+        let mut sim = Simulation::new(seed);
+
+        sim.spawn(move || {
+            // Times out once the other task blocks too.
+            assert_eq!(r1.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+            s2.send(1).unwrap();
+        });
+        sim.spawn(move || {
+            assert_eq!(r2.recv(), Ok(1));
+        });
+
+        sim.run();
+        drop(s1);
+    }
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn panic_propagates() {
+    let mut sim = Simulation::new(0);
+    sim.spawn(|| panic!("boom"));
+    sim.spawn(|| {});
+    sim.run();
+}
+
+#[test]
+#[should_panic(expected = "simulation deadlocked")]
+fn deadlock() {
+    let (s, r) = bounded::<i32>(0);
+    let mut sim = Simulation::new(0);
+    sim.spawn(move || {
+        let _ = r.recv();
+    });
+    sim.run();
+    drop(s);
+}
+
+#[test]
+fn not_run() {
+    let (s, r) = unbounded();
+    let mut sim = Simulation::new(0);
+    sim.spawn(move || s.send(1).unwrap());
+    drop(sim);
+
+    assert!(r.recv().is_err());
+}
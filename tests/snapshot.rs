@@ -0,0 +1,171 @@
+//! Tests for snapshotting and restoring the contents of channels.
+
+#![cfg(feature = "serde")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+extern crate serde_json;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, lanes, never, unbounded, unbounded_tagged, SenderId};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn array() {
+    let (s, r) = bounded(3);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    s.send(3).unwrap();
+
+    assert_eq!(r.snapshot(), [1, 2, 3]);
+    assert_eq!(r.len(), 3);
+
+    // The messages still take up room in the buffer.
+    assert!(s.is_full());
+    assert_eq!(r.recv(), Ok(1));
+    s.try_send(4).unwrap();
+    assert_eq!(r.snapshot(), [2, 3, 4]);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 3, 4]);
+}
+
+#[test]
+fn list() {
+    let (s, r) = unbounded();
+    for i in 0..100 {
+        s.send(i).unwrap();
+    }
+    r.recv().unwrap();
+
+    assert_eq!(r.snapshot(), (1..100).collect::<Vec<_>>());
+    s.send(100).unwrap();
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), (1..101).collect::<Vec<_>>());
+}
+
+#[test]
+fn lanes_flavor() {
+    let (s, r) = lanes(10, 3);
+    s.send_with_priority(1, 0).unwrap();
+    s.send_with_priority(2, 2).unwrap();
+    s.send_with_priority(3, 1).unwrap();
+    s.send_with_priority(4, 2).unwrap();
+
+    assert_eq!(r.snapshot(), [2, 4, 3, 1]);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [2, 4, 3, 1]);
+}
+
+#[test]
+fn zero_restore() {
+    let (_s, r) = bounded(0);
+    r.restore(vec![1, 2]);
+
+    assert_eq!(r.snapshot(), [1, 2]);
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.snapshot(), [2]);
+    assert_eq!(r.try_recv(), Ok(2));
+    assert!(r.snapshot().is_empty());
+}
+
+#[test]
+fn restore() {
+    let (s1, r1) = unbounded();
+    for i in 0..5 {
+        s1.send(i).unwrap();
+    }
+    let json = serde_json::to_string(&r1.snapshot()).unwrap();
+    assert_eq!(json, "[0,1,2,3,4]");
+
+    let (s2, r2) = bounded(2);
+    r2.restore(serde_json::from_str(&json).unwrap());
+    assert_eq!(r2.len(), 5);
+
+    s2.send(5).unwrap();
+    s2.send(6).unwrap();
+    drop(s2);
+    assert_eq!(r2.iter().collect::<Vec<_>>(), (0..7).collect::<Vec<_>>());
+}
+
+#[test]
+fn restore_in_front() {
+    let (s, r) = unbounded();
+    s.send(3).unwrap();
+    r.restore(vec![1, 2]);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2, 3]);
+
+    let (s, r) = lanes(1, 2);
+    s.send_with_priority(3, 1).unwrap();
+    r.restore(vec![1, 2]);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2, 3]);
+
+    let (_s, r) = bounded(0);
+    r.restore(vec![1, 2]);
+    assert_eq!(r.snapshot(), [1, 2]);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [1, 2]);
+}
+
+#[test]
+fn wakes_blocked_receiver() {
+    let (_s, r) = unbounded();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            r.restore(vec![1]);
+        });
+        assert_eq!(r.recv(), Ok(1));
+    });
+}
+
+#[test]
+fn concurrent() {
+    const COUNT: usize = 10_000;
+
+    let (s, r) = bounded(10);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+        });
+        scope.spawn(|| {
+            for _ in 0..100 {
+                assert!(r.snapshot().iter().all(|&x| x < COUNT));
+            }
+        });
+
+        // No messages are lost or duplicated.
+        let mut received: Vec<_> = (0..COUNT).map(|_| r.recv().unwrap()).collect();
+        received.sort();
+        assert_eq!(received, (0..COUNT).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn generated_messages() {
+    let r = after(ms(0));
+    r.restore(vec![r.recv().unwrap()]);
+    assert!(r.try_recv().is_err());
+
+    let r = never::<i32>();
+    r.restore(vec![1]);
+    assert!(r.snapshot().is_empty());
+}
+
+#[test]
+fn sender_id() {
+    let (s, r) = unbounded_tagged();
+    s.clone().send("a").unwrap();
+
+    let (id, msg) = r.recv_tagged().unwrap();
+    let json = serde_json::to_string(&(id, msg)).unwrap();
+    assert_eq!(json, r#"[1,"a"]"#);
+
+    let (id2, msg2): (SenderId, String) = serde_json::from_str(&json).unwrap();
+    assert_eq!(id2, id);
+    assert_eq!(msg2, "a");
+}
@@ -0,0 +1,234 @@
+//! Tests for handles specialized for a single channel flavor.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, lanes, never, tick, unbounded, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, SendTimeoutError};
+use crossbeam_channel::{TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn conversion() {
+    let (s, r) = bounded::<i32>(1);
+    assert!(s.into_array().is_ok());
+    assert!(r.into_array().is_ok());
+
+    let (s, r) = unbounded::<i32>();
+    assert!(s.into_list().is_ok());
+    assert!(r.into_list().is_ok());
+
+    // Other flavors hand the generic handle back.
+    let (s, r) = bounded::<i32>(0);
+    let s = s.into_array().unwrap_err().into_list().unwrap_err();
+    let r = r.into_array().unwrap_err().into_list().unwrap_err();
+    s.try_send(1).unwrap_err();
+    r.try_recv().unwrap_err();
+
+    let (s, r) = lanes::<i32>(4, 2);
+    assert!(s.into_array().is_err());
+    assert!(r.into_list().is_err());
+
+    let (s, r) = unbounded::<i32>();
+    assert!(s.into_array().is_err());
+    assert!(r.into_array().is_err());
+
+    assert!(after(ms(100)).into_array().is_err());
+    assert!(tick(ms(100)).into_list().is_err());
+    assert!(never::<i32>().into_list().is_err());
+}
+
+#[test]
+fn array_smoke() {
+    let (s, r) = bounded(2);
+    let s = s.into_array().unwrap();
+    let r = r.into_array().unwrap();
+    assert_eq!(s.capacity(), 2);
+    assert_eq!(r.capacity(), 2);
+
+    assert_eq!(s.try_send(1), Ok(()));
+    assert_eq!(s.send(2), Ok(()));
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+    assert_eq!(s.send_timeout(3, ms(50)), Err(SendTimeoutError::Timeout(3)));
+    assert!(s.is_full() && r.is_full());
+    assert_eq!(r.len(), 2);
+
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(50)), Err(RecvTimeoutError::Timeout));
+    assert!(s.is_empty() && r.is_empty());
+}
+
+#[test]
+fn list_smoke() {
+    let (s, r) = unbounded();
+    let s = s.into_list().unwrap();
+    let r = r.into_list().unwrap();
+
+    for i in 0..100 {
+        assert_eq!(s.send(i), Ok(()));
+    }
+    assert_eq!(s.try_send(100), Ok(()));
+    assert_eq!(s.len(), 101);
+
+    for i in 0..50 {
+        assert_eq!(r.try_recv(), Ok(i));
+    }
+    for i in 50..101 {
+        assert_eq!(r.recv_timeout(ms(50)), Ok(i));
+    }
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(50)), Err(RecvTimeoutError::Timeout));
+    assert!(s.is_empty() && r.is_empty());
+}
+
+#[test]
+fn disconnect() {
+    let (s, r) = bounded(1);
+    let s = s.into_array().unwrap();
+    let r = r.into_array().unwrap();
+    s.send(1).unwrap();
+    drop(s);
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+
+    let (s, r) = unbounded();
+    let s = s.into_list().unwrap();
+    let r2 = r.into_list().unwrap().clone();
+    assert_eq!(s.send(1), Ok(()));
+    drop(r2);
+    assert_eq!(s.send(2), Err(SendError(2)));
+    assert_eq!(s.try_send(3), Err(TrySendError::Disconnected(3)));
+}
+
+#[test]
+fn blocking() {
+    let (s, r) = bounded(1);
+    let s = s.into_array().unwrap();
+    let r = r.into_array().unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(r.recv(), Ok(1));
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(2));
+            assert_eq!(r.recv(), Ok(3));
+        });
+        scope.spawn(|| {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+            // Blocks until the receiver wakes up.
+            s.send(3).unwrap();
+        });
+    });
+
+    // Blocking operations are recorded in the generic handles' statistics.
+    assert!(s.as_sender().stats().blocked >= 1);
+}
+
+#[test]
+fn round_trip() {
+    let (s, r) = bounded(1);
+    let s = s.into_array().unwrap().into_sender();
+    let r = r.into_array().unwrap().into_receiver();
+    s.send(1).unwrap();
+    assert_eq!(r.recv(), Ok(1));
+
+    let (s, r) = unbounded();
+    let s = s.into_list().unwrap().into_sender();
+    let r = r.into_list().unwrap().into_receiver();
+    s.send(1).unwrap();
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = bounded(1);
+    let (s2, r2) = unbounded();
+    let r1 = r1.into_array().unwrap();
+    let r2 = r2.into_list().unwrap();
+    s2.send(7).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(r1.as_receiver());
+    let oper2 = sel.recv(r2.as_receiver());
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(r2.as_receiver()), Ok(7));
+
+    s1.send(8).unwrap();
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.recv(r1.as_receiver()), Ok(8));
+}
+
+#[test]
+fn array_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &cap in &[1, 3, 100] {
+        let (s, r) = bounded(cap);
+        let s = s.into_array().unwrap();
+        let r = r.into_array().unwrap();
+
+        let sum = crossbeam::scope(|scope| {
+            for _ in 0..THREADS {
+                let s = s.clone();
+                scope.spawn(move || {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+            let handles = (0..THREADS)
+                .map(|_| {
+                    let r = r.clone();
+                    scope.spawn(move || (0..COUNT).map(|_| r.recv().unwrap()).sum::<usize>())
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|h| h.join()).sum::<usize>()
+        });
+
+        assert_eq!(sum, THREADS * COUNT * (COUNT - 1) / 2);
+        assert!(r.is_empty());
+    }
+}
+
+#[test]
+fn list_mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded();
+    let s = s.into_list().unwrap();
+    let r = r.into_list().unwrap();
+
+    let sum = crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            let s = s.clone();
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+        let handles = (0..THREADS)
+            .map(|_| {
+                let r = r.clone();
+                scope.spawn(move || (0..COUNT).map(|_| r.recv().unwrap()).sum::<usize>())
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter().map(|h| h.join()).sum::<usize>()
+    });
+
+    assert_eq!(sum, THREADS * COUNT * (COUNT - 1) / 2);
+    assert!(r.is_empty());
+}
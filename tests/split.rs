@@ -0,0 +1,288 @@
+//! Tests for `Receiver::split_by`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    let (even, odd) = r.split_by(|&x: &i32| x % 2 == 0);
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    s.send(3).unwrap();
+
+    assert_eq!(even.try_recv(), Ok(2));
+    assert_eq!(even.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(odd.try_recv(), Ok(1));
+    assert_eq!(odd.try_recv(), Ok(3));
+    assert_eq!(odd.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn order() {
+    let (s, r) = unbounded();
+    let (small, big) = r.split_by(|&x| x < 50);
+
+    for i in (0..100).rev() {
+        s.send(i).unwrap();
+    }
+    drop(s);
+
+    let v: Vec<_> = (0..50).map(|_| small.recv().unwrap()).collect();
+    assert_eq!(v, (0..50).rev().collect::<Vec<_>>());
+    assert_eq!(small.recv(), Err(RecvError));
+
+    let v: Vec<_> = (0..50).map(|_| big.recv().unwrap()).collect();
+    assert_eq!(v, (50..100).rev().collect::<Vec<_>>());
+    assert_eq!(big.recv(), Err(RecvError));
+}
+
+#[test]
+fn blocking() {
+    let (s, r) = bounded(1);
+    let (control, data) = r.split_by(|&x| x < 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..10 {
+                s.send(i).unwrap();
+            }
+            s.send(-1).unwrap();
+            thread::sleep(ms(100));
+            drop(s);
+        });
+
+        scope.spawn(|| {
+            let v: Vec<_> = (0..10).map(|_| data.recv().unwrap()).collect();
+            assert_eq!(v, (0..10).collect::<Vec<_>>());
+        });
+
+        assert_eq!(control.recv(), Ok(-1));
+        assert_eq!(control.recv(), Err(RecvError));
+    });
+}
+
+#[test]
+fn backpressure() {
+    let (s, r) = bounded(2);
+    let (control, data) = r.split_by(|&x| x < 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..10 {
+                s.send(i).unwrap();
+            }
+            s.send(-1).unwrap();
+        });
+
+        // The data side lags behind, so the control side stops taking messages out of the channel
+        // and the sender blocks on a full channel.
+        assert_eq!(control.recv_timeout(ms(200)), Err(RecvTimeoutError::Timeout));
+        assert_eq!(control.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(s.len(), 2);
+
+        // Once the data side catches up, the control message gets through.
+        let v: Vec<_> = (0..10).map(|_| data.recv().unwrap()).collect();
+        assert_eq!(v, (0..10).collect::<Vec<_>>());
+        assert_eq!(control.recv(), Ok(-1));
+    });
+}
+
+#[test]
+fn backpressure_wakes_up() {
+    let (s, r) = bounded(1);
+    let (control, data) = r.split_by(|&x| x < 0);
+
+    s.send(0).unwrap();
+    assert_eq!(control.try_recv(), Err(TryRecvError::Empty));
+    s.send(-1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(data.recv(), Ok(0));
+        });
+
+        // Blocked until the data side makes room.
+        assert_eq!(control.recv(), Ok(-1));
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded();
+    let (even, odd) = r.split_by(|&x| x % 2 == 0);
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(even.try_recv(), Ok(2));
+
+    // The message handed over to the odd side makes it ready while the channel is empty.
+    let mut sel = Select::new();
+    let oper1 = sel.recv_ready(&even);
+    let oper2 = sel.recv_ready(&odd);
+    let oper = sel.select_timeout(ms(100)).unwrap();
+    assert_eq!(oper.index(), oper2);
+    oper.recv_ready(&odd);
+    assert_eq!(odd.try_recv(), Ok(1));
+
+    assert!(sel.select_timeout(ms(100)).is_err());
+
+    drop(s);
+    let oper = sel.select_timeout(ms(100)).unwrap();
+    match oper.index() {
+        i if i == oper1 => {
+            oper.recv_ready(&even);
+            assert_eq!(even.try_recv(), Err(TryRecvError::Disconnected));
+        }
+        i if i == oper2 => {
+            oper.recv_ready(&odd);
+            assert_eq!(odd.try_recv(), Err(TryRecvError::Disconnected));
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn select_wakes_up_on_hand_over() {
+    let (s, r) = unbounded();
+    let (even, odd) = r.split_by(|&x| x % 2 == 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(even.recv(), Ok(2));
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+        });
+
+        // Wait until the even side is blocked on the channel.
+        thread::sleep(ms(50));
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv_ready(&odd);
+        loop {
+            let oper = sel.select_timeout(ms(1000)).unwrap();
+            assert_eq!(oper.index(), oper1);
+            oper.recv_ready(&odd);
+            match odd.try_recv() {
+                Ok(x) => {
+                    assert_eq!(x, 1);
+                    break;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(err) => panic!("{:?}", err),
+            }
+        }
+    });
+}
+
+#[test]
+fn both_sides_concurrently() {
+    const COUNT: usize = 10_000;
+
+    let (s, r) = bounded(5);
+    let (even, odd) = r.split_by(|&x| x % 2 == 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+            drop(s);
+        });
+
+        for (side, first) in vec![(&even, 0), (&odd, 1)] {
+            scope.spawn(move || {
+                let mut next = first;
+                while let Ok(x) = side.recv() {
+                    assert_eq!(x, next);
+                    next += 2;
+                }
+                assert_eq!(next, COUNT + first);
+            });
+        }
+    });
+}
+
+#[test]
+fn timeout_while_other_side_waits() {
+    let (s, r) = unbounded();
+    let (even, odd) = r.split_by(|&x| x % 2 == 0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(even.recv_timeout(ms(300)), Ok(2));
+        });
+
+        thread::sleep(ms(50));
+        assert_eq!(odd.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+        s.send(1).unwrap();
+        s.send(2).unwrap();
+        assert_eq!(odd.recv_timeout(ms(300)), Ok(1));
+    });
+}
+
+#[test]
+fn other_side_dropped() {
+    let (s, r) = unbounded();
+    let (even, odd) = r.split_by(|&x| x % 2 == 0);
+
+    s.send(1).unwrap();
+    s.send(3).unwrap();
+    s.send(4).unwrap();
+
+    let odd2 = odd.clone();
+    drop(odd);
+
+    // A clone keeps the side alive.
+    assert_eq!(even.try_recv(), Ok(4));
+    drop(odd2);
+
+    s.send(5).unwrap();
+    s.send(6).unwrap();
+    drop(s);
+
+    // Messages of the dropped side are discarded.
+    assert_eq!(even.recv(), Ok(6));
+    assert_eq!(even.recv(), Err(RecvError));
+}
+
+#[test]
+fn drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct DropCounter(bool);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let (s, r) = unbounded();
+    let (yes, no) = r.split_by(|msg: &DropCounter| msg.0);
+
+    for i in 0..10 {
+        s.send(DropCounter(i % 2 == 0)).unwrap();
+    }
+    drop(yes.recv().unwrap());
+    drop((s, yes, no));
+
+    assert_eq!(DROPS.load(Ordering::SeqCst), 10);
+}
@@ -0,0 +1,150 @@
+//! Tests for blocking statistics of channel handles.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, lanes, unbounded, BlockingStats, CancelToken};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+    assert_eq!(r.recv(), Ok(1));
+
+    assert_eq!(s.stats(), BlockingStats::default());
+    assert_eq!(r.stats(), BlockingStats::default());
+}
+
+#[test]
+fn send_blocks() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..3 {
+                thread::sleep(ms(100));
+                r.recv().unwrap();
+            }
+        });
+
+        for i in 0..4 {
+            s.send(i).unwrap();
+        }
+    });
+
+    let stats = s.stats();
+    assert_eq!(stats.blocked, 3);
+    assert!(stats.blocked_time >= ms(200));
+    assert!(stats.blocked_time < ms(2000));
+    assert_eq!(r.stats().blocked, 0);
+}
+
+#[test]
+fn recv_blocks() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+        });
+
+        assert_eq!(r.recv(), Ok(1));
+    });
+
+    let stats = r.stats();
+    assert_eq!(stats.blocked, 1);
+    assert!(stats.blocked_time >= ms(50));
+}
+
+#[test]
+fn timeouts() {
+    let (s, r) = lanes::<i32>(1, 2);
+    s.send(1).unwrap();
+
+    assert!(s.send_timeout(2, ms(100)).is_err());
+    assert!(s.send_timeout_with_priority(2, 1, ms(100)).is_err());
+    assert_eq!(r.recv(), Ok(1));
+    assert!(r.recv_timeout(ms(100)).is_err());
+    assert!(r.recv_timeout_where(|_| true, ms(100)).is_err());
+
+    let stats = s.stats();
+    assert_eq!(stats.blocked, 2);
+    assert!(stats.blocked_time >= ms(150));
+
+    let stats = r.stats();
+    assert_eq!(stats.blocked, 2);
+    assert!(stats.blocked_time >= ms(150));
+}
+
+#[test]
+fn per_handle() {
+    let (s1, r1) = unbounded::<i32>();
+    let s2 = s1.clone();
+    assert!(r1.recv_timeout(ms(100)).is_err());
+
+    let r2 = r1.clone();
+    assert_eq!(r1.stats().blocked, 1);
+    assert_eq!(r2.stats(), BlockingStats::default());
+
+    assert!(r2.recv_timeout(ms(100)).is_err());
+    assert!(r2.recv_timeout(ms(100)).is_err());
+    assert_eq!(r1.stats().blocked, 1);
+    assert_eq!(r2.stats().blocked, 2);
+
+    drop((s1, s2));
+}
+
+#[test]
+fn cancellable() {
+    let (_s, r) = unbounded::<i32>();
+    let token = CancelToken::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            token.cancel();
+        });
+
+        assert!(r.recv_cancellable(&token).is_err());
+    });
+
+    assert_eq!(r.stats().blocked, 1);
+}
+
+#[test]
+fn select_not_counted() {
+    let (_s1, r1) = unbounded::<i32>();
+    let (_s2, r2) = unbounded::<i32>();
+
+    select! {
+        recv(r1) -> _ => panic!(),
+        recv(r2) -> _ => panic!(),
+        default(ms(100)) => {}
+    }
+    assert_eq!(r1.stats(), BlockingStats::default());
+    assert_eq!(r2.stats(), BlockingStats::default());
+}
+
+#[test]
+fn nested() {
+    let (s1, r1) = unbounded();
+    let (_s2, r2) = unbounded::<i32>();
+    s1.send(1).unwrap();
+
+    // The predicate blocks on another channel while checking a message.
+    assert_eq!(
+        r1.recv_where(|_| r2.recv_timeout(ms(100)).is_err()),
+        Ok(1)
+    );
+    assert_eq!(r1.stats().blocked, 0);
+    assert_eq!(r2.stats().blocked, 1);
+}
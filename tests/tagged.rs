@@ -0,0 +1,178 @@
+//! Tests for channels with tagged messages.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded_tagged, unbounded_tagged, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use crossbeam_channel::{SendError, SendTimeoutError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded_tagged();
+    s.send(7).unwrap();
+    assert_eq!(r.try_recv_tagged(), Ok((s.id(), 7)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn ids() {
+    let (s1, r) = unbounded_tagged::<i32>();
+    let s2 = s1.clone();
+    let s3 = s2.clone();
+    let s4 = s1.clone();
+
+    let ids: Vec<_> = [&s1, &s2, &s3, &s4].iter().map(|s| s.id().as_usize()).collect();
+    assert_eq!(ids, [0, 1, 2, 3]);
+
+    // Ids are not reused after a sender is dropped.
+    drop(s2);
+    assert_eq!(s1.clone().id().as_usize(), 4);
+
+    assert_eq!(s3.id().to_string(), "2");
+    assert_eq!(format!("{:?}", s3), "TaggedSender { id: SenderId(2) }");
+    drop(r);
+}
+
+#[test]
+fn untagged() {
+    let (s, r) = bounded_tagged(3);
+    let other = s.clone();
+
+    s.send(1).unwrap();
+    other.try_send(2).unwrap();
+    s.send_timeout(3, ms(100)).unwrap();
+
+    assert_eq!(r.len(), 3);
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.recv_timeout(ms(100)), Ok(3));
+    assert_eq!(r.recv_timeout(ms(10)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn send_errors() {
+    let (s, r) = bounded_tagged(1);
+
+    s.send(1).unwrap();
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(s.send_timeout(3, ms(10)), Err(SendTimeoutError::Timeout(3)));
+
+    drop(r);
+    assert_eq!(s.send(4), Err(SendError(4)));
+    assert_eq!(s.try_send(5), Err(TrySendError::Disconnected(5)));
+    assert_eq!(
+        s.send_timeout(6, ms(10)),
+        Err(SendTimeoutError::Disconnected(6))
+    );
+}
+
+#[test]
+fn disconnect() {
+    let (s, r) = unbounded_tagged();
+    let id = s.id();
+    let other = s.clone();
+
+    s.send(1).unwrap();
+    drop(s);
+    assert_eq!(r.recv_tagged(), Ok((id, 1)));
+    assert_eq!(r.recv_timeout(ms(10)), Err(RecvTimeoutError::Timeout));
+
+    drop(other);
+    assert_eq!(r.recv_tagged(), Err(RecvError));
+    assert_eq!(r.try_recv_tagged(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded_tagged(0);
+    let id = s.id();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..10 {
+                s.send(i).unwrap();
+            }
+        });
+
+        let v: Vec<_> = r.iter().collect();
+        assert_eq!(v, (0..10).map(|i| (id, i)).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = bounded_tagged(0);
+    let id = s.id();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            s.send("foo").unwrap();
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv_ready(&r);
+
+        let oper = sel.select_timeout(ms(1000)).unwrap();
+        assert_eq!(oper.index(), oper1);
+        oper.recv_ready(&r);
+        assert_eq!(r.try_recv_tagged(), Ok((id, "foo")));
+
+        let oper = sel.select_timeout(ms(1000)).unwrap();
+        oper.recv_ready(&r);
+        assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    });
+}
+
+#[test]
+fn into_inner() {
+    let (s, r) = unbounded_tagged();
+    s.send("foo").unwrap();
+
+    let r = r.into_inner();
+    assert_eq!(r.recv(), Ok((s.id(), "foo")));
+}
+
+#[test]
+fn per_sender_fifo() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    for cap in &[Some(0), Some(1), Some(100), None] {
+        let (s, r) = match *cap {
+            Some(cap) => bounded_tagged(cap),
+            None => unbounded_tagged(),
+        };
+
+        crossbeam::scope(|scope| {
+            for _ in 0..THREADS {
+                let s = s.clone();
+                scope.spawn(move || {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+            drop(s);
+
+            let mut next = HashMap::new();
+            for (id, msg) in &r {
+                let expected = next.entry(id).or_insert(0);
+                assert_eq!(msg, *expected);
+                *expected += 1;
+            }
+
+            assert_eq!(next.len(), THREADS);
+            assert!(next.values().all(|&n| n == COUNT));
+        });
+    }
+}
@@ -0,0 +1,158 @@
+//! Tests for `Receiver::tee`.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, unbounded};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    let branches = r.tee(3, 1);
+    assert_eq!(branches.len(), 3);
+
+    for i in 0..10 {
+        s.send(i).unwrap();
+        for r in &branches {
+            assert_eq!(r.recv(), Ok(i));
+        }
+    }
+    drop(s);
+
+    for r in &branches {
+        assert_eq!(r.recv(), Err(RecvError));
+    }
+}
+
+#[test]
+fn branch_capacity() {
+    let (s, r) = unbounded();
+    let branches = r.tee(2, 3);
+    assert_eq!(branches[0].capacity(), Some(3));
+
+    for i in 0..10 {
+        s.send(i).unwrap();
+    }
+    thread::sleep(ms(100));
+
+    // The fast branch can run ahead of the slow one only by the size of its buffer.
+    let received: Vec<_> = branches[0].try_iter().collect();
+    assert_eq!(received, [0, 1, 2]);
+    thread::sleep(ms(100));
+    assert_eq!(branches[0].try_recv(), Ok(3));
+    assert_eq!(branches[0].try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(s.len(), 6);
+
+    // Once the slow branch catches up, the fast one gets new messages again.
+    assert_eq!(branches[1].recv(), Ok(0));
+    assert_eq!(branches[0].recv_timeout(ms(1000)), Ok(4));
+}
+
+#[test]
+fn drop_branch() {
+    let (s, r) = bounded(0);
+    let mut branches = r.tee(2, 0);
+    let r1 = branches.pop().unwrap();
+    drop(branches);
+
+    for i in 0..10 {
+        s.send(i).unwrap();
+        assert_eq!(r1.recv(), Ok(i));
+    }
+}
+
+#[test]
+fn drop_all_branches() {
+    let (s, r) = unbounded();
+    drop(r.tee(4, 1));
+
+    // The thread stops even though no messages arrive, dropping the original receiver.
+    thread::sleep(ms(100));
+    assert_eq!(s.send(1), Err(SendError(1)));
+}
+
+#[test]
+fn drop_all_branches_keeps_message() {
+    let (s, r) = unbounded();
+    let r2 = r.clone();
+    let branches = r.tee(2, 0);
+
+    // The thread takes the message out and waits for the branches to receive it.
+    s.send(1).unwrap();
+    thread::sleep(ms(100));
+    assert!(r2.is_empty());
+
+    // Nobody got the message, so it goes back into the original channel.
+    drop(branches);
+    assert_eq!(r2.recv_timeout(ms(1000)), Ok(1));
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded();
+    let branches = r.tee(2, 1);
+    let (_s2, r2) = unbounded::<i32>();
+
+    s.send(7).unwrap();
+    for r in &branches {
+        select! {
+            recv(r) -> msg => assert_eq!(msg, Ok(7)),
+            recv(r2) -> _ => panic!(),
+            default(ms(1000)) => panic!(),
+        }
+    }
+}
+
+#[test]
+fn slow_consumer() {
+    const COUNT: usize = 1000;
+
+    let (s, r) = bounded(5);
+    let branches = r.tee(3, 2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+            drop(s);
+        });
+
+        for (j, r) in branches.iter().enumerate() {
+            scope.spawn(move || {
+                let mut next = 0;
+                for i in r {
+                    assert_eq!(i, next);
+                    next += 1;
+                    if j == 0 && i % 100 == 0 {
+                        thread::sleep(ms(1));
+                    }
+                }
+                assert_eq!(next, COUNT);
+            });
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "number of branches must be positive")]
+fn zero_branches() {
+    let (_s, r) = unbounded::<i32>();
+    r.tee(0, 1);
+}
+
+#[test]
+fn timeout() {
+    let (_s, r) = unbounded::<i32>();
+    let branches = r.tee(1, 1);
+    assert_eq!(branches[0].recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+}
@@ -0,0 +1,233 @@
+//! Tests for the channel testing harness.
+
+#![cfg(feature = "testing")]
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::testing::{check, Event, Harness, History, Model, Op, Outcome, TestChannel};
+use crossbeam_channel::{bounded, lanes, unbounded};
+use crossbeam_channel::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn event(thread: usize, op: Op, outcome: Outcome, start: usize, end: usize) -> Event {
+    Event {
+        thread,
+        op,
+        outcome,
+        start,
+        end,
+    }
+}
+
+/// An unbounded channel that hands out the newest message first.
+struct Lifo(Mutex<Vec<u64>>);
+
+impl TestChannel for Lifo {
+    fn try_send(&self, msg: u64) -> Result<(), TrySendError<u64>> {
+        self.0.lock().unwrap().push(msg);
+        Ok(())
+    }
+
+    fn send_timeout(&self, msg: u64, _: Duration) -> Result<(), SendTimeoutError<u64>> {
+        self.0.lock().unwrap().push(msg);
+        Ok(())
+    }
+
+    fn try_recv(&self) -> Result<u64, TryRecvError> {
+        self.0.lock().unwrap().pop().ok_or(TryRecvError::Empty)
+    }
+
+    fn recv_timeout(&self, _: Duration) -> Result<u64, RecvTimeoutError> {
+        self.0.lock().unwrap().pop().ok_or(RecvTimeoutError::Timeout)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// A channel with capacity 2 that silently drops every fifth message.
+struct Lossy {
+    chan: (crossbeam_channel::Sender<u64>, crossbeam_channel::Receiver<u64>),
+    count: AtomicUsize,
+}
+
+impl TestChannel for Lossy {
+    fn try_send(&self, msg: u64) -> Result<(), TrySendError<u64>> {
+        if self.count.fetch_add(1, Ordering::SeqCst) % 5 == 4 {
+            return Ok(());
+        }
+        self.chan.0.try_send(msg)
+    }
+
+    fn send_timeout(&self, msg: u64, timeout: Duration) -> Result<(), SendTimeoutError<u64>> {
+        if self.count.fetch_add(1, Ordering::SeqCst) % 5 == 4 {
+            return Ok(());
+        }
+        self.chan.0.send_timeout(msg, timeout)
+    }
+
+    fn try_recv(&self) -> Result<u64, TryRecvError> {
+        self.chan.1.try_recv()
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<u64, RecvTimeoutError> {
+        self.chan.1.recv_timeout(timeout)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(2)
+    }
+}
+
+#[test]
+fn model() {
+    let mut m = Model::new(Some(2));
+    assert!(m.apply(Op::Recv, Outcome::Empty));
+    assert!(!m.apply(Op::Send(1), Outcome::Full));
+    assert!(m.apply(Op::Send(1), Outcome::Sent));
+    assert!(m.apply(Op::Send(2), Outcome::Sent));
+    assert!(!m.apply(Op::Send(3), Outcome::Sent));
+    assert!(m.apply(Op::Send(3), Outcome::Full));
+    assert_eq!(m.len(), 2);
+
+    assert!(!m.apply(Op::Recv, Outcome::Received(2)));
+    assert!(m.apply(Op::Recv, Outcome::Received(1)));
+    assert!(!m.apply(Op::Recv, Outcome::Disconnected));
+    assert_eq!(m.len(), 1);
+
+    let mut m = Model::new(None);
+    for i in 0..1000 {
+        assert!(m.apply(Op::Send(i), Outcome::Sent));
+    }
+    assert!(!m.apply(Op::Send(1000), Outcome::Full));
+}
+
+#[test]
+fn check_sequential() {
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 1),
+        event(0, Op::Send(2), Outcome::Sent, 2, 3),
+        event(0, Op::Recv, Outcome::Received(2), 4, 5),
+    ];
+    assert!(check(&history, None).is_err());
+
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 1),
+        event(0, Op::Send(2), Outcome::Sent, 2, 3),
+        event(0, Op::Recv, Outcome::Received(1), 4, 5),
+    ];
+    assert!(check(&history, None).is_ok());
+}
+
+#[test]
+fn check_overlapping() {
+    // The sends overlap, so either message may come first.
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 3),
+        event(1, Op::Send(2), Outcome::Sent, 1, 2),
+        event(2, Op::Recv, Outcome::Received(2), 4, 5),
+        event(2, Op::Recv, Outcome::Received(1), 6, 7),
+    ];
+    assert!(check(&history, None).is_ok());
+
+    // A receive that overlaps with a send may see the channel empty or full.
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 3),
+        event(1, Op::Recv, Outcome::Empty, 1, 2),
+    ];
+    assert!(check(&history, Some(1)).is_ok());
+
+    // But not after the send is done.
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 1),
+        event(1, Op::Recv, Outcome::Empty, 2, 3),
+    ];
+    let err = check(&history, Some(1)).unwrap_err();
+    assert_eq!(err.events().len(), 2);
+    assert_eq!(err.seed(), None);
+}
+
+#[test]
+fn check_zero_capacity() {
+    // A message is handed off while both operations are in progress.
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 3),
+        event(1, Op::Recv, Outcome::Received(1), 1, 2),
+        event(0, Op::Send(2), Outcome::Full, 4, 5),
+    ];
+    assert!(check(&history, Some(0)).is_ok());
+
+    // Two messages cannot be in flight at once.
+    let history = [
+        event(0, Op::Send(1), Outcome::Sent, 0, 1),
+        event(1, Op::Send(2), Outcome::Sent, 2, 3),
+        event(2, Op::Recv, Outcome::Received(1), 4, 5),
+    ];
+    assert!(check(&history, Some(0)).is_err());
+}
+
+#[test]
+fn history() {
+    let (s, r) = bounded(1);
+    let history = History::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            history.record(0, Op::Send(1), || {
+                s.send(1).unwrap();
+                Outcome::Sent
+            });
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(50));
+            history.record(1, Op::Recv, || Outcome::Received(r.recv().unwrap()));
+        });
+    });
+
+    let events = history.events();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.start < e.end));
+    assert!(history.check(Some(1)).is_ok());
+}
+
+#[test]
+fn builtin_flavors() {
+    let harness = Harness::new(1).rounds(30).threads(4).ops(12);
+    harness.run(|| bounded(0)).unwrap();
+    harness.run(|| bounded(1)).unwrap();
+    harness.run(|| bounded(5)).unwrap();
+    harness.run(|| unbounded()).unwrap();
+    harness.run(|| lanes(5, 1)).unwrap();
+}
+
+#[test]
+fn detects_reordering() {
+    let err = Harness::new(2)
+        .rounds(200)
+        .run(|| Lifo(Mutex::new(Vec::new())))
+        .unwrap_err();
+    assert!(err.seed().is_some());
+    assert!(err.to_string().contains("not linearizable"));
+}
+
+#[test]
+fn detects_lost_messages() {
+    let err = Harness::new(3)
+        .rounds(200)
+        .run(|| Lossy {
+            chan: bounded(2),
+            count: AtomicUsize::new(0),
+        })
+        .unwrap_err();
+    assert!(err.seed().is_some());
+}
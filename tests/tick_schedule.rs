@@ -0,0 +1,142 @@
+//! Tests for `TickBuilder`.
+
+extern crate crossbeam_channel;
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Clock, ManualClock, Receiver, TickBuilder, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Returns how far past the last multiple of `period` the wall clock is, in milliseconds.
+fn past_boundary(period: u64) -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let millis = now.as_secs() * 1000 + u64::from(now.subsec_nanos()) / 1_000_000;
+    millis % period
+}
+
+#[test]
+fn default_schedule() {
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let r = TickBuilder::new(ms(100)).clock(clock.clone()).build();
+
+    for i in 1..5 {
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+        clock.advance(ms(100));
+        assert_eq!(r.try_recv(), Ok(start + ms(100 * i)));
+    }
+}
+
+#[test]
+fn aligned() {
+    let r = TickBuilder::new(ms(200)).align(ms(0)).build();
+
+    for _ in 0..3 {
+        r.recv().unwrap();
+        assert!(past_boundary(200) < 100);
+    }
+}
+
+#[test]
+fn aligned_with_offset() {
+    let r = TickBuilder::new(ms(200)).align(ms(1250)).build();
+
+    for _ in 0..3 {
+        r.recv().unwrap();
+        let past = (past_boundary(200) + 200 - 50) % 200;
+        assert!(past < 100);
+    }
+}
+
+#[test]
+fn aligned_skips_missed() {
+    let clock = ManualClock::new();
+    let r = TickBuilder::new(ms(100))
+        .align(ms(0))
+        .clock(clock.clone())
+        .build();
+
+    clock.advance(ms(1000));
+    assert!(r.try_recv().is_ok());
+
+    // Only one message was pending, and the next one is due within one interval.
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    clock.advance(ms(100));
+    assert!(r.try_recv().is_ok());
+}
+
+/// Advances the clock in small steps until the next message arrives, and returns it.
+fn next(r: &Receiver<Instant>, clock: &ManualClock) -> Instant {
+    loop {
+        if let Ok(msg) = r.try_recv() {
+            return msg;
+        }
+        clock.advance(Duration::new(0, 100_000));
+    }
+}
+
+#[test]
+fn jitter_bounds() {
+    let clock = ManualClock::new();
+    let r = TickBuilder::new(ms(100))
+        .jitter(ms(30))
+        .clock(clock.clone())
+        .build();
+
+    let mut prev = next(&r, &clock);
+    let mut gaps = Vec::new();
+    for _ in 0..50 {
+        let msg = next(&r, &clock);
+        let gap = msg - prev;
+        assert!(gap >= ms(70));
+        assert!(gap <= ms(131));
+        gaps.push(gap);
+        prev = msg;
+    }
+
+    // Delays differ from message to message.
+    let first = gaps[0];
+    assert!(gaps.iter().any(|&g| g != first));
+}
+
+#[test]
+fn jitter_does_not_accumulate() {
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let r = TickBuilder::new(ms(100))
+        .jitter(ms(50))
+        .clock(clock.clone())
+        .build();
+
+    let mut last = start;
+    for _ in 0..100 {
+        last = next(&r, &clock);
+    }
+
+    // A hundred messages take about a hundred intervals. Each message may be observed up to one
+    // step late, which does push the following ones back.
+    assert!(last >= start + ms(100 * 100));
+    assert!(last <= start + ms(100 * 100 + 10 + 50));
+}
+
+#[test]
+fn jitter_real_time() {
+    let start = Instant::now();
+    let r = TickBuilder::new(ms(50)).jitter(ms(20)).build();
+
+    for _ in 0..5 {
+        r.recv().unwrap();
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= ms(250));
+    assert!(elapsed < ms(1000));
+}
+
+#[test]
+#[should_panic(expected = "non-zero interval")]
+fn aligned_zero_interval() {
+    TickBuilder::new(ms(0)).align(ms(0)).build();
+}
@@ -0,0 +1,125 @@
+//! Tests for timeout operations that report the remaining time budget.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, never, unbounded, RecvTimeoutError, SendTimeoutError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn send_ready() {
+    let (s, r) = bounded(1);
+
+    let left = s.send_timeout_remaining(1, ms(1000)).unwrap();
+    assert!(left > ms(500));
+    assert!(left <= ms(1000));
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn send_waits() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(300));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        let left = s.send_timeout_remaining(1, ms(2000)).unwrap();
+        assert!(left <= ms(1700));
+        assert!(left > ms(0));
+    });
+}
+
+#[test]
+fn send_timeout() {
+    let (s, _r) = bounded(1);
+    s.send(1).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(s.send_timeout_remaining(2, ms(200)), Err(SendTimeoutError::Timeout(2)));
+    assert!(start.elapsed() >= ms(200));
+    assert_eq!(s.send_timeout_remaining(2, ms(0)), Err(SendTimeoutError::Timeout(2)));
+}
+
+#[test]
+fn send_disconnected() {
+    let (s, r) = bounded(1);
+    drop(r);
+    assert_eq!(s.send_timeout_remaining(1, ms(1000)), Err(SendTimeoutError::Disconnected(1)));
+}
+
+#[test]
+fn recv_ready() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+
+    let (msg, left) = r.recv_timeout_remaining(ms(1000)).unwrap();
+    assert_eq!(msg, 1);
+    assert!(left > ms(500));
+    assert!(left <= ms(1000));
+}
+
+#[test]
+fn recv_waits() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(300));
+            s.send(1).unwrap();
+        });
+
+        let (msg, left) = r.recv_timeout_remaining(ms(2000)).unwrap();
+        assert_eq!(msg, 1);
+        assert!(left <= ms(1700));
+        assert!(left > ms(0));
+    });
+}
+
+#[test]
+fn recv_timeout_and_disconnect() {
+    let (s, r) = unbounded::<i32>();
+
+    let start = Instant::now();
+    assert_eq!(r.recv_timeout_remaining(ms(200)), Err(RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= ms(200));
+
+    drop(s);
+    assert_eq!(r.recv_timeout_remaining(ms(1000)), Err(RecvTimeoutError::Disconnected));
+
+    let r = never::<i32>();
+    assert_eq!(r.recv_timeout_remaining(ms(100)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn shared_deadline() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(200));
+            s1.send(1).unwrap();
+            thread::sleep(ms(200));
+            assert_eq!(r2.recv(), Ok(2));
+        });
+
+        // Both steps run under a single budget without reading the clock in between.
+        let start = Instant::now();
+        let (msg, left) = r1.recv_timeout_remaining(ms(3000)).unwrap();
+        assert_eq!(msg, 1);
+        let left = s2.send_timeout_remaining(msg + 1, left).unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= ms(400));
+        assert!(left <= ms(3000) - elapsed + ms(50));
+    });
+}
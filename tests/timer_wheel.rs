@@ -0,0 +1,159 @@
+//! Tests for timers fired by the shared timer wheel.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, tick, unbounded, Poller, Receiver, Select};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Selects over the given receivers and returns the index of the one that fired.
+fn select_index(receivers: &[Receiver<Instant>]) -> usize {
+    let mut sel = Select::new();
+    for r in receivers {
+        sel.recv(r);
+    }
+    let oper = sel.select();
+    let index = oper.index();
+    oper.recv(&receivers[index]).unwrap();
+    index
+}
+
+#[test]
+fn fire_in_order() {
+    let receivers: Vec<_> = (0..10).rev().map(|i| after(ms(i * 20))).collect();
+
+    let mut fired = Vec::new();
+    for _ in 0..10 {
+        fired.push(select_index(&receivers));
+    }
+    assert_eq!(fired, (0..10).rev().collect::<Vec<_>>());
+}
+
+#[test]
+fn never_early() {
+    for &d in &[1, 5, 63, 64, 65, 130] {
+        let start = Instant::now();
+        let r = after(ms(d));
+        select_index(&[r]);
+        assert!(start.elapsed() >= ms(d));
+    }
+}
+
+#[test]
+fn many_timers() {
+    let receivers: Vec<_> = (0..10_000).map(|i| after(ms(50 + i % 100))).collect();
+    let start = Instant::now();
+
+    let mut count = 0;
+    while count < 200 {
+        select_index(&receivers);
+        count += 1;
+    }
+    assert!(start.elapsed() >= ms(50));
+    assert!(start.elapsed() < ms(2000));
+}
+
+#[test]
+fn cascade() {
+    // Far enough to start on a higher level of the wheel.
+    let start = Instant::now();
+    let r = after(ms(4200));
+    select_index(&[r]);
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= ms(4200));
+    assert!(elapsed < ms(5200));
+}
+
+#[test]
+fn select_timeout_cancels() {
+    let r = after(ms(1000));
+
+    let start = Instant::now();
+    let mut sel = Select::new();
+    sel.recv(&r);
+    assert!(sel.select_timeout(ms(50)).is_err());
+    assert!(start.elapsed() < ms(500));
+
+    // Another timer still fires after the cancelled one was removed.
+    let r = after(ms(50));
+    select_index(&[r]);
+}
+
+#[test]
+fn tick_in_select() {
+    let r = tick(ms(50));
+    let start = Instant::now();
+
+    for i in 1..=4 {
+        select_index(&[r.clone()]);
+        assert!(start.elapsed() >= ms(50 * i));
+    }
+    assert!(start.elapsed() < ms(1000));
+}
+
+#[test]
+fn with_other_channels() {
+    let (s, r) = unbounded::<i32>();
+    let timer = after(ms(100));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(50));
+            s.send(1).unwrap();
+        });
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+        sel.recv(&timer);
+        let oper = sel.select();
+        assert_eq!(oper.index(), 0);
+        assert_eq!(oper.recv(&r), Ok(1));
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+        sel.recv(&timer);
+        let oper = sel.select();
+        assert_eq!(oper.index(), 1);
+        assert!(oper.recv(&timer).is_ok());
+    });
+}
+
+#[test]
+fn many_threads() {
+    crossbeam::scope(|scope| {
+        for i in 0..8 {
+            scope.spawn(move || {
+                for j in 0..10 {
+                    let d = ms((i * 7 + j * 3) % 40);
+                    let start = Instant::now();
+                    select_index(&[after(d)]);
+                    assert!(start.elapsed() >= d);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn poller() {
+    let r = tick(ms(50));
+    let mut poller = Poller::new();
+    poller.recv(&r, 7);
+
+    let start = Instant::now();
+    let mut events = Vec::new();
+    for i in 1..=3 {
+        events.clear();
+        assert_eq!(poller.poll(&mut events, Some(ms(1000))), 1);
+        assert_eq!(events, [7]);
+        r.try_recv().unwrap();
+        assert!(start.elapsed() >= ms(50 * i));
+    }
+}
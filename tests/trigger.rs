@@ -0,0 +1,128 @@
+//! Tests for `Trigger`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, Select, Trigger};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn set_and_reset() {
+    let t = Trigger::new();
+    assert!(!t.is_set());
+
+    t.set();
+    t.set();
+    assert!(t.is_set());
+    assert!(t.wait_timeout(ms(0)));
+
+    assert!(t.reset());
+    assert!(!t.is_set());
+    assert!(!t.wait_timeout(ms(0)));
+}
+
+#[test]
+fn wait() {
+    let t = Trigger::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            t.set();
+        });
+
+        let start = Instant::now();
+        t.wait();
+        assert!(start.elapsed() >= ms(50));
+    });
+
+    // Waiting doesn't reset the trigger.
+    t.wait();
+    assert!(t.is_set());
+}
+
+#[test]
+fn wait_timeout() {
+    let t = Trigger::new();
+
+    let start = Instant::now();
+    assert!(!t.wait_timeout(ms(100)));
+    assert!(start.elapsed() >= ms(100));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            t.set();
+        });
+
+        assert!(t.wait_timeout(ms(1000)));
+    });
+}
+
+#[test]
+fn wakes_all() {
+    const THREADS: usize = 4;
+
+    let t = Trigger::new();
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| t.wait());
+        }
+
+        thread::sleep(ms(100));
+        t.set();
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded::<i32>();
+    let t = Trigger::new();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            t.set();
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv(&r);
+        let oper2 = sel.trigger(&t);
+
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper2);
+        oper.trigger(&t);
+
+        // The trigger stays ready until it is reset.
+        s.send(1).unwrap();
+        t.reset();
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper1);
+        assert_eq!(oper.recv(&r), Ok(1));
+    });
+}
+
+#[test]
+fn select_timeout() {
+    let (s, r) = bounded::<i32>(0);
+    let t = Trigger::new();
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+    sel.trigger(&t);
+    assert!(sel.try_select().is_err());
+    assert!(sel.select_timeout(ms(100)).is_err());
+
+    t.set();
+    let oper = sel.select_timeout(ms(100)).unwrap();
+    assert_eq!(oper.index(), 1);
+    oper.trigger(&t);
+    drop(s);
+}
@@ -0,0 +1,124 @@
+//! Tests for the non-blocking iterator that reports why it stopped.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, never, unbounded, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn empty() {
+    let (s, r) = bounded(5);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.status(), None);
+    assert_eq!(iter.by_ref().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(iter.status(), Some(TryRecvError::Empty));
+    assert!(!iter.is_disconnected());
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    drop(s);
+
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.status(), None);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.status(), Some(TryRecvError::Disconnected));
+    assert!(iter.is_disconnected());
+}
+
+#[test]
+fn fused() {
+    let (s, r) = unbounded();
+
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.next(), None);
+
+    // A message sent after the iteration ended is left for the next one.
+    s.send(1).unwrap();
+    drop(s);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.status(), Some(TryRecvError::Empty));
+
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.next(), None);
+    assert!(iter.is_disconnected());
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded::<i32>(0);
+
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.status(), Some(TryRecvError::Empty));
+
+    drop(s);
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.next(), None);
+    assert!(iter.is_disconnected());
+}
+
+#[test]
+fn special_receivers() {
+    let r = never::<i32>();
+    let mut iter = r.try_iter_status();
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.status(), Some(TryRecvError::Empty));
+
+    let r = after(ms(50));
+    thread::sleep(ms(100));
+    let mut iter = r.try_iter_status();
+    assert!(iter.next().is_some());
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.status(), Some(TryRecvError::Empty));
+}
+
+#[test]
+fn poll_until_disconnected() {
+    const COUNT: usize = 10_000;
+
+    let (s, r) = bounded(10);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+        });
+
+        let mut received = Vec::new();
+        loop {
+            let mut iter = r.try_iter_status();
+            received.extend(iter.by_ref());
+            if iter.is_disconnected() {
+                break;
+            }
+            thread::yield_now();
+        }
+
+        assert_eq!(received, (0..COUNT).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn debug() {
+    let (_s, r) = unbounded::<i32>();
+    let mut iter = r.try_iter_status();
+    assert_eq!(format!("{:?}", iter), "TryIterStatus { status: None }");
+    iter.next();
+    assert_eq!(format!("{:?}", iter), "TryIterStatus { status: Some(Empty) }");
+}
@@ -0,0 +1,129 @@
+//! Tests for `Receiver::try_recv_for`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, never, unbounded, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    s.send(7).unwrap();
+    assert_eq!(r.try_recv_for(ms(0)), Ok(7));
+    assert_eq!(r.try_recv_for(ms(0)), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn times_out() {
+    let (_s, r) = unbounded::<i32>();
+
+    let start = Instant::now();
+    assert_eq!(r.try_recv_for(ms(50)), Err(TryRecvError::Empty));
+    let elapsed = start.elapsed();
+    assert!(elapsed >= ms(50));
+    assert!(elapsed < ms(500));
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = unbounded::<i32>();
+    s.send(1).unwrap();
+    drop(s);
+
+    assert_eq!(r.try_recv_for(ms(1000)), Ok(1));
+
+    // Returns right away instead of spinning.
+    let start = Instant::now();
+    assert_eq!(r.try_recv_for(ms(1000)), Err(TryRecvError::Disconnected));
+    assert!(start.elapsed() < ms(500));
+}
+
+#[test]
+fn disconnect_while_spinning() {
+    let (s, r) = unbounded::<i32>();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(50));
+            drop(s);
+        });
+
+        assert_eq!(r.try_recv_for(ms(5000)), Err(TryRecvError::Disconnected));
+    });
+}
+
+#[test]
+fn arrives_while_spinning() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(50));
+            s.send(3).unwrap();
+        });
+
+        let start = Instant::now();
+        assert_eq!(r.try_recv_for(ms(5000)), Ok(3));
+        assert!(start.elapsed() < ms(2500));
+    });
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(5).unwrap());
+        assert_eq!(r.try_recv_for(ms(5000)), Ok(5));
+    });
+    assert_eq!(r.try_recv_for(ms(10)), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn special_flavors() {
+    let r = after(ms(20));
+    assert!(r.try_recv_for(ms(1000)).is_ok());
+
+    let r = never::<i32>();
+    assert_eq!(r.try_recv_for(ms(20)), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn competing_receivers() {
+    const COUNT: usize = 1000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded();
+
+    let received: usize = crossbeam::scope(|scope| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut count = 0;
+                    loop {
+                        match r.try_recv_for(ms(1)) {
+                            Ok(()) => count += 1,
+                            Err(TryRecvError::Empty) => {}
+                            Err(TryRecvError::Disconnected) => return count,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..COUNT {
+            s.send(()).unwrap();
+        }
+        drop(s);
+
+        handles.into_iter().map(|h| h.join()).sum()
+    });
+    assert_eq!(received, COUNT);
+}
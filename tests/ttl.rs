@@ -0,0 +1,195 @@
+//! Tests for channels with expiring messages.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded_ttl, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded_ttl(2);
+    s.send(7).unwrap();
+    s.send_with_ttl(8, ms(1000)).unwrap();
+    assert_eq!(r.try_recv(), Ok(7));
+    assert_eq!(r.recv(), Ok(8));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn capacity() {
+    for i in 1..10 {
+        let (s, r) = bounded_ttl::<()>(i);
+        assert_eq!(s.capacity(), i);
+        assert_eq!(r.capacity(), i);
+    }
+}
+
+#[test]
+fn expired_skipped() {
+    let (s, r) = bounded_ttl(10);
+
+    s.send_with_ttl(1, ms(10)).unwrap();
+    s.send(2).unwrap();
+    s.try_send_with_ttl(3, ms(10)).unwrap();
+    s.send_with_ttl(4, ms(1000)).unwrap();
+    s.send_with_ttl(5, ms(10)).unwrap();
+    assert_eq!(s.len(), 5);
+
+    thread::sleep(ms(50));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.recv_timeout(ms(50)), Ok(4));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert!(r.is_empty());
+
+    drop(s);
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn expired_full() {
+    let (s, r) = bounded_ttl(1);
+
+    s.send_with_ttl(1, ms(10)).unwrap();
+    thread::sleep(ms(50));
+
+    // Expired messages occupy space until a receiver discards them.
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(s.try_send(2), Ok(()));
+    assert_eq!(r.try_recv(), Ok(2));
+}
+
+#[test]
+fn on_expire() {
+    let expired = Arc::new(Mutex::new(Vec::new()));
+    let e = expired.clone();
+
+    let (s, r) = bounded_ttl(10);
+    let r = r.on_expire(move |msg| e.lock().unwrap().push(msg));
+    let r2 = r.clone();
+
+    s.send_with_ttl(1, ms(10)).unwrap();
+    s.send_with_ttl(2, ms(10)).unwrap();
+    s.send(3).unwrap();
+    s.send_with_ttl(4, ms(10)).unwrap();
+    thread::sleep(ms(50));
+
+    assert_eq!(r2.recv(), Ok(3));
+    assert_eq!(*expired.lock().unwrap(), [1, 2]);
+
+    drop(s);
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(*expired.lock().unwrap(), [1, 2, 4]);
+}
+
+#[test]
+fn recv_timeout_expired() {
+    let (s, r) = bounded_ttl(10);
+
+    s.send_with_ttl(1, ms(10)).unwrap();
+    thread::sleep(ms(50));
+
+    let start = Instant::now();
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= ms(100));
+    assert!(r.is_empty());
+
+    drop(s);
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Disconnected));
+}
+
+#[test]
+fn ttl_starts_on_send() {
+    let (s, r) = bounded_ttl(1);
+    s.send(0).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            // Blocks for a while because the channel is full.
+            s.send_with_ttl(1, ms(200)).unwrap();
+        });
+
+        thread::sleep(ms(300));
+        assert_eq!(r.recv(), Ok(0));
+        thread::sleep(ms(50));
+        assert_eq!(r.try_recv(), Ok(1));
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = bounded_ttl(10);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send_with_ttl(1, ms(10)).unwrap();
+            thread::sleep(ms(50));
+            s.send(2).unwrap();
+        });
+
+        let mut sel = Select::new();
+        let oper1 = sel.recv_ready(&r);
+
+        // The expired message makes the operation ready, but isn't received.
+        let mut v = Vec::new();
+        while v.is_empty() {
+            let oper = sel.select_timeout(ms(1000)).unwrap();
+            assert_eq!(oper.index(), oper1);
+            oper.recv_ready(&r);
+            thread::sleep(ms(20));
+            v.extend(r.try_recv());
+        }
+        assert_eq!(v, [2]);
+    });
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded_ttl::<usize>(3);
+    let expired = Arc::new(AtomicUsize::new(0));
+    let e = expired.clone();
+    let r = r.on_expire(move |_| {
+        e.fetch_add(1, Ordering::SeqCst);
+    });
+    let received = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                while r.recv().is_ok() {
+                    received.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            let s = s.clone();
+            scope.spawn(move || {
+                for i in 0..COUNT {
+                    if i % 2 == 0 {
+                        s.send(i).unwrap();
+                    } else {
+                        s.send_with_ttl(i, ms(1)).unwrap();
+                    }
+                }
+            });
+        }
+        drop(s);
+    });
+
+    let total = received.load(Ordering::SeqCst) + expired.load(Ordering::SeqCst);
+    assert_eq!(total, COUNT * THREADS);
+}
@@ -0,0 +1,260 @@
+//! Tests for `Receiver::wait_ready`, `Sender::wait_capacity`, and `Sender::ready`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, lanes, never, tick, unbounded};
+use crossbeam_channel::{RecvTimeoutError, SendError, SendTimeoutError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn ready_right_away() {
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    assert_eq!(r.wait_ready(ms(0)), Ok(()));
+    assert_eq!(s.wait_capacity(ms(0)), Ok(()));
+
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    assert_eq!(r.wait_ready(ms(0)), Ok(()));
+    assert_eq!(s.wait_capacity(ms(0)), Ok(()));
+
+    let (s, r) = lanes(2, 2);
+    s.send(1).unwrap();
+    assert_eq!(r.wait_ready(ms(0)), Ok(()));
+    assert_eq!(s.wait_capacity(ms(0)), Ok(()));
+}
+
+#[test]
+fn timeout() {
+    let (s, r) = bounded::<i32>(1);
+    assert_eq!(r.wait_ready(ms(100)), Err(RecvTimeoutError::Timeout));
+
+    s.send(1).unwrap();
+    assert_eq!(s.wait_capacity(ms(100)), Err(SendTimeoutError::Timeout(())));
+
+    let (s, r) = bounded::<i32>(0);
+    assert_eq!(r.wait_ready(ms(100)), Err(RecvTimeoutError::Timeout));
+    assert_eq!(s.wait_capacity(ms(100)), Err(SendTimeoutError::Timeout(())));
+}
+
+#[test]
+fn does_not_consume() {
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+        });
+
+        assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+        assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+        assert_eq!(r.recv(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+    });
+}
+
+#[test]
+fn wakes_up() {
+    for &cap in &[None, Some(1), Some(3)] {
+        let (s, r) = match cap {
+            None => unbounded(),
+            Some(cap) => bounded(cap),
+        };
+
+        crossbeam::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(ms(100));
+                s.send(7).unwrap();
+            });
+
+            let start = Instant::now();
+            assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+            assert!(start.elapsed() >= ms(50));
+            assert_eq!(r.try_recv(), Ok(7));
+        });
+    }
+}
+
+#[test]
+fn capacity_freed() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(r.recv(), Ok(1));
+            thread::sleep(ms(100));
+            assert_eq!(r.recv(), Ok(2));
+        });
+
+        let start = Instant::now();
+        assert_eq!(s.wait_capacity(ms(1000)), Ok(()));
+        assert!(start.elapsed() >= ms(50));
+        assert_eq!(s.try_send(2), Ok(()));
+    });
+}
+
+#[test]
+fn zero_capacity() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            s.send(1).unwrap();
+            assert_eq!(s.wait_capacity(ms(1000)), Ok(()));
+            s.send(2).unwrap();
+        });
+
+        // A sender blocks on the other side.
+        assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+        assert_eq!(r.recv(), Ok(1));
+
+        thread::sleep(ms(100));
+        assert_eq!(r.recv(), Ok(2));
+    });
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = bounded::<i32>(1);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(s);
+        });
+
+        assert_eq!(r.wait_ready(ms(1000)), Err(RecvTimeoutError::Disconnected));
+    });
+
+    let (s, r) = unbounded();
+    s.send(1).unwrap();
+    drop(s);
+    assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.wait_ready(ms(1000)), Err(RecvTimeoutError::Disconnected));
+
+    let (s, r) = bounded::<i32>(0);
+    drop(r);
+    assert_eq!(
+        s.wait_capacity(ms(1000)),
+        Err(SendTimeoutError::Disconnected(()))
+    );
+}
+
+#[test]
+fn special_receivers() {
+    let r = after(ms(100));
+    let start = Instant::now();
+    assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+    assert!(start.elapsed() >= ms(100));
+    assert!(r.try_recv().is_ok());
+
+    let r = tick(ms(100));
+    assert_eq!(r.wait_ready(ms(1000)), Ok(()));
+    assert!(r.try_recv().is_ok());
+
+    let r = never::<i32>();
+    assert_eq!(r.wait_ready(ms(100)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn sender_ready() {
+    let (s, r) = bounded(2);
+    assert_eq!(s.ready(), Ok(()));
+    assert_eq!(s.ready(), Ok(()));
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        let start = Instant::now();
+        assert_eq!(s.ready(), Ok(()));
+        assert!(start.elapsed() >= ms(100));
+        assert_eq!(s.try_send(3), Ok(()));
+    });
+
+    let (s, r) = unbounded();
+    for i in 0..100 {
+        assert_eq!(s.ready(), Ok(()));
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.len(), 100);
+}
+
+#[test]
+fn sender_ready_zero_capacity() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            assert_eq!(r.recv(), Ok(7));
+        });
+
+        let start = Instant::now();
+        assert_eq!(s.ready(), Ok(()));
+        assert!(start.elapsed() >= ms(100));
+        s.send(7).unwrap();
+    });
+}
+
+#[test]
+fn sender_ready_disconnected() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            thread::sleep(ms(100));
+            drop(r);
+        });
+
+        assert_eq!(s.ready(), Err(SendError(())));
+    });
+
+    let (s, r) = unbounded::<i32>();
+    drop(r);
+    assert_eq!(s.ready(), Err(SendError(())));
+}
+
+#[test]
+fn sender_ready_many_producers() {
+    const THREADS: usize = 4;
+    const COUNT: usize = 1000;
+
+    let (s, r) = bounded(1);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                let mut sent = 0;
+                while sent < COUNT {
+                    s.ready().unwrap();
+                    if s.try_send(()).is_ok() {
+                        sent += 1;
+                    }
+                }
+            });
+        }
+
+        for _ in 0..THREADS * COUNT {
+            r.recv().unwrap();
+        }
+    });
+}
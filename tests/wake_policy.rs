@@ -0,0 +1,193 @@
+//! Tests for the policy deciding which blocked thread gets woken up.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{Builder, Receiver, Sender, WakePolicy};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Sends messages to several blocked receivers and checks that every message arrives once.
+fn spread(s: Sender<usize>, r: Receiver<usize>) {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let sum = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            let r = r.clone();
+            let sum = &sum;
+            scope.spawn(move || {
+                for msg in r {
+                    sum.fetch_add(msg, Ordering::SeqCst);
+                }
+            });
+        }
+
+        for i in 0..COUNT {
+            s.send(i).unwrap();
+        }
+        drop(s);
+    });
+
+    assert_eq!(sum.load(Ordering::SeqCst), COUNT * (COUNT - 1) / 2);
+}
+
+#[test]
+fn fifo_by_default() {
+    let builder = format!("{:?}", Builder::<i32>::new());
+    assert!(builder.contains("wake_policy: Fifo"));
+}
+
+#[test]
+fn affinity_array() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).bounded(4);
+    spread(s, r);
+}
+
+#[test]
+fn affinity_list() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).unbounded();
+    spread(s, r);
+}
+
+#[test]
+fn affinity_zero() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).bounded(0);
+    spread(s, r);
+}
+
+#[test]
+fn affinity_lanes() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).lanes(4, 2);
+    spread(s, r);
+}
+
+#[test]
+fn affinity_blocked_senders() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Affinity).bounded(1);
+    s.send(0).unwrap();
+
+    crossbeam::scope(|scope| {
+        for i in 1..5 {
+            let s = s.clone();
+            scope.spawn(move || s.send(i).unwrap());
+        }
+
+        thread::sleep(ms(100));
+        let mut msgs: Vec<_> = (0..5).map(|_| r.recv().unwrap()).collect();
+        msgs.sort();
+        assert_eq!(msgs, [0, 1, 2, 3, 4]);
+    });
+}
+
+#[test]
+fn exclusive_array() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).bounded(4);
+    spread(s, r);
+}
+
+#[test]
+fn exclusive_list() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).unbounded();
+    spread(s, r);
+}
+
+#[test]
+fn exclusive_zero() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).bounded(0);
+    spread(s, r);
+}
+
+#[test]
+fn exclusive_lanes() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).lanes(4, 2);
+    spread(s, r);
+}
+
+#[test]
+fn exclusive_wakes_one_watcher() {
+    const THREADS: usize = 4;
+
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).unbounded();
+    let woken = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            let r = r.clone();
+            let woken = &woken;
+            scope.spawn(move || {
+                if r.wait_ready(ms(500)).is_ok() {
+                    woken.fetch_add(1, Ordering::SeqCst);
+                    r.try_recv().unwrap();
+                }
+            });
+        }
+
+        thread::sleep(ms(100));
+        s.send(1).unwrap();
+    });
+
+    assert_eq!(woken.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn exclusive_wakes_watcher_per_message() {
+    const THREADS: usize = 4;
+
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).unbounded();
+    let woken = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            let r = r.clone();
+            let woken = &woken;
+            scope.spawn(move || {
+                if r.wait_ready(ms(1000)).is_ok() {
+                    woken.fetch_add(1, Ordering::SeqCst);
+                    r.try_recv().unwrap();
+                }
+            });
+        }
+
+        thread::sleep(ms(100));
+        for i in 0..THREADS {
+            s.send(i).unwrap();
+            thread::sleep(ms(10));
+        }
+    });
+
+    assert_eq!(woken.load(Ordering::SeqCst), THREADS);
+}
+
+#[test]
+fn exclusive_blocked_receivers_first() {
+    let (s, r) = Builder::new().wake_policy(WakePolicy::Exclusive).unbounded();
+    let watched = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        let r1 = r.clone();
+        scope.spawn(move || assert_eq!(r1.recv(), Ok(1)));
+
+        let r2 = r.clone();
+        let watched = &watched;
+        scope.spawn(move || {
+            if r2.wait_ready(ms(300)).is_ok() {
+                watched.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        thread::sleep(ms(100));
+        s.send(1).unwrap();
+    });
+
+    // The blocked receiver took the message, so the watching thread was never woken up.
+    assert_eq!(watched.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,218 @@
+//! Tests for channels bounded by the total weight of their messages.
+
+extern crate crossbeam;
+#[macro_use]
+extern crate crossbeam_channel;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded_by_weight, Builder, Displace, DropPolicy};
+use crossbeam_channel::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded_by_weight(10, |&w: &usize| w);
+    s.send(3).unwrap();
+    s.send(4).unwrap();
+    assert_eq!(s.len(), 2);
+    assert!(!s.is_full());
+
+    s.send(5).unwrap();
+    assert!(s.is_full());
+    assert_eq!(s.try_send(1), Err(TrySendError::Full(1)));
+
+    assert_eq!(r.recv(), Ok(3));
+    assert!(!s.is_full());
+    s.send(1).unwrap();
+
+    assert_eq!(r.try_recv(), Ok(4));
+    assert_eq!(r.try_recv(), Ok(5));
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn capacity() {
+    let (s, r) = bounded_by_weight(100, |v: &Vec<u8>| v.len());
+    assert_eq!(s.capacity(), Some(100));
+    assert_eq!(r.capacity(), Some(100));
+}
+
+#[test]
+#[should_panic(expected = "capacity must be positive")]
+fn zero_weight() {
+    bounded_by_weight(0, |_: &i32| 1);
+}
+
+#[test]
+fn heavier_than_limit() {
+    let (s, r) = bounded_by_weight(10, |&w: &usize| w);
+
+    // A message heavier than the limit still gets in when the channel is empty.
+    s.send(100).unwrap();
+    assert!(s.is_full());
+    assert_eq!(s.send_timeout(1, ms(50)), Err(SendTimeoutError::Timeout(1)));
+
+    assert_eq!(r.recv(), Ok(100));
+    s.send(1).unwrap();
+}
+
+#[test]
+fn weightless() {
+    let (s, r) = bounded_by_weight(1, |_: &i32| 0);
+    for i in 0..1000 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r.len(), 1000);
+    assert!(!r.is_full());
+}
+
+#[test]
+fn blocks_until_received() {
+    let (s, r) = bounded_by_weight(10, |&w: &usize| w);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            s.send(10).unwrap();
+            let start = Instant::now();
+            s.send(1).unwrap();
+            assert!(start.elapsed() >= ms(50));
+        });
+
+        thread::sleep(ms(100));
+        assert_eq!(r.len(), 1);
+        assert_eq!(r.recv(), Ok(10));
+        assert_eq!(r.recv_timeout(ms(1000)), Ok(1));
+        assert_eq!(r.recv_timeout(ms(50)), Err(RecvTimeoutError::Timeout));
+    });
+}
+
+#[test]
+fn select() {
+    let (s, r) = bounded_by_weight(10, |&w: &usize| w);
+    s.send(10).unwrap();
+
+    select! {
+        send(s, 1) -> _ => panic!(),
+        default(ms(50)) => {}
+    }
+
+    r.recv().unwrap();
+    select! {
+        send(s, 1) -> res => res.unwrap(),
+        default => panic!(),
+    }
+    assert_eq!(r.try_recv(), Ok(1));
+}
+
+#[test]
+fn selective_receive() {
+    let (s, r) = bounded_by_weight(10, |&w: &usize| w);
+    s.send(4).unwrap();
+    s.send(6).unwrap();
+    assert!(s.is_full());
+
+    // Receiving a message from behind the front frees up its weight too.
+    assert_eq!(r.recv_where(|&w| w == 6), Ok(6));
+    assert!(!s.is_full());
+    s.send(5).unwrap();
+    assert!(!s.is_full());
+    s.send(1).unwrap();
+    assert!(s.is_full());
+}
+
+#[test]
+fn lossy() {
+    let (s, r) = bounded_by_weight(10, |&w: &usize| w);
+    s.send(4).unwrap();
+    s.send(6).unwrap();
+    assert_eq!(s.send_lossy(2), Ok(Some(6)));
+    assert_eq!(r.try_recv(), Ok(4));
+    assert_eq!(r.try_recv(), Ok(2));
+
+    let (s, r) = Builder::new()
+        .displace(Displace::Oldest)
+        .bounded_by_weight(10, |&w: &usize| w);
+    s.send(4).unwrap();
+    s.send(6).unwrap();
+    assert_eq!(s.send_lossy(2), Ok(Some(4)));
+    assert_eq!(r.try_recv(), Ok(6));
+    assert_eq!(r.try_recv(), Ok(2));
+}
+
+#[test]
+fn reserve() {
+    let (s, r) = bounded_by_weight(3, |&w: &usize| w);
+    s.send(1).unwrap();
+
+    // A permit takes up one unit until its message is weighed.
+    let permit = s.reserve().unwrap();
+    s.send(1).unwrap();
+    assert!(s.is_full());
+
+    permit.send(5).unwrap();
+    assert_eq!(r.len(), 3);
+    for _ in 0..2 {
+        r.recv().unwrap();
+    }
+    assert!(s.is_full());
+    assert_eq!(r.recv(), Ok(5));
+    assert!(!s.is_full());
+}
+
+#[test]
+fn drop_policy() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+
+    let d = dropped.clone();
+    let (s, r) = Builder::new()
+        .drop_policy(DropPolicy::Handler(Box::new(move |w: usize| {
+            d.fetch_add(w, Ordering::SeqCst);
+        })))
+        .bounded_by_weight(100, |&w: &usize| w);
+    s.send(10).unwrap();
+    s.send(20).unwrap();
+
+    drop(r);
+    assert_eq!(dropped.load(Ordering::SeqCst), 30);
+    assert_eq!(s.pending_on_disconnect(), Some(2));
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded_by_weight(100, |&w: &usize| w % 50);
+    let sum = AtomicUsize::new(0);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..COUNT {
+                    let w = r.recv().unwrap();
+                    sum.fetch_add(w, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+
+    assert_eq!(
+        sum.load(Ordering::SeqCst),
+        THREADS * COUNT * (COUNT - 1) / 2
+    );
+    assert!(r.is_empty());
+    assert!(!s.is_full());
+}
@@ -0,0 +1,144 @@
+//! Tests for `Receiver::with_timeout`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, unbounded, Elapsed, RecvError, Select, TryRecvError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    let r = r.with_timeout(ms(100));
+    assert_eq!(r.timeout(), ms(100));
+
+    s.send(1).unwrap();
+    assert_eq!(r.try_recv(), Ok(Ok(1)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    let start = Instant::now();
+    assert_eq!(r.recv(), Ok(Err(Elapsed)));
+    assert!(start.elapsed() >= ms(50));
+
+    drop(s);
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn resets_on_message() {
+    let (s, r) = unbounded();
+    let r = r.with_timeout(ms(200));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            for i in 0..5 {
+                thread::sleep(ms(100));
+                s.send(i).unwrap();
+            }
+        });
+
+        // Messages keep arriving faster than the timeout, so it never elapses.
+        let v: Vec<_> = r.iter().collect();
+        assert_eq!(v, (0..5).map(Ok).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn repeats_while_idle() {
+    let (s, r) = unbounded::<i32>();
+    let r = r.with_timeout(ms(50));
+
+    let start = Instant::now();
+    for _ in 0..4 {
+        assert_eq!(r.recv(), Ok(Err(Elapsed)));
+    }
+    assert!(start.elapsed() >= ms(200));
+    assert!(start.elapsed() < ms(1000));
+    drop(s);
+}
+
+#[test]
+fn messages_before_timeout() {
+    let (s, r) = unbounded();
+    let r = r.with_timeout(ms(50));
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    // Queued messages are received even though the timeout has elapsed.
+    thread::sleep(ms(100));
+    assert_eq!(r.try_recv(), Ok(Ok(1)));
+    assert_eq!(r.try_recv(), Ok(Ok(2)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = unbounded();
+    let (_s2, r2) = unbounded::<i32>();
+    let r1 = r1.with_timeout(ms(100));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(move || {
+            s1.send(1).unwrap();
+            thread::sleep(ms(250));
+            s1.send(2).unwrap();
+        });
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            let mut sel = Select::new();
+            let oper1 = sel.recv_with_timeout(&r1);
+            sel.recv(&r2);
+
+            let oper = sel.select();
+            assert_eq!(oper.index(), oper1);
+            received.push(oper.recv_with_timeout(&r1).unwrap().ok());
+        }
+        assert_eq!(received, [Some(1), None, None, Some(2)]);
+    });
+}
+
+#[test]
+fn select_disconnected() {
+    let (s, r) = bounded::<i32>(1);
+    let r = r.with_timeout(ms(1000));
+    drop(s);
+
+    let mut sel = Select::new();
+    sel.recv_with_timeout(&r);
+
+    let start = Instant::now();
+    let oper = sel.select();
+    assert_eq!(oper.recv_with_timeout(&r), Err(RecvError));
+    assert!(start.elapsed() < ms(500));
+}
+
+#[test]
+fn select_timeout() {
+    let (_s, r) = unbounded::<i32>();
+    let r = r.with_timeout(ms(300));
+
+    let mut sel = Select::new();
+    sel.recv_with_timeout(&r);
+    assert!(sel.select_timeout(ms(50)).is_err());
+
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.recv_with_timeout(&r), Ok(Err(Elapsed)));
+}
+
+#[test]
+fn into_inner() {
+    let (s, r) = unbounded();
+    let r = r.with_timeout(ms(50));
+    s.send(1).unwrap();
+
+    let r = r.into_inner();
+    assert_eq!(r.recv(), Ok(1));
+}
@@ -118,6 +118,62 @@ fn recv_timeout() {
     });
 }
 
+#[test]
+fn recv_where() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| s.send(1).unwrap());
+        scope.spawn(|| s.send(2).unwrap());
+        thread::sleep(ms(500));
+
+        // The message that doesn't match stays with its sender.
+        assert_eq!(r.recv_where(|&x| x == 2), Ok(2));
+        assert_eq!(r.recv_timeout_where(|&x| x == 2, ms(100)), Err(RecvTimeoutError::Timeout));
+        assert_eq!(r.recv(), Ok(1));
+
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            s.send(3).unwrap();
+        });
+        assert_eq!(r.recv_where(|&x| x == 3), Ok(3));
+    });
+
+    drop(s);
+    assert_eq!(r.recv_where(|_| true), Err(RecvError));
+}
+
+#[test]
+fn recv_where_put_back() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(500));
+            drop(r.recv_guard().unwrap());
+        });
+        s.send(1).unwrap();
+
+        // Blocks until the message is put back.
+        assert_eq!(r.recv_timeout_where(|&x| x == 1, ms(2000)), Ok(1));
+    });
+}
+
+#[test]
+fn send_lossy() {
+    let (s, r) = bounded(0);
+    assert_eq!(s.send_lossy(1), Ok(Some(1)));
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| assert_eq!(r.recv(), Ok(2)));
+        thread::sleep(ms(500));
+        assert_eq!(s.send_lossy(2), Ok(None));
+    });
+
+    drop(r);
+    assert_eq!(s.send_lossy(3), Err(SendError(3)));
+}
+
 #[test]
 fn try_send() {
     let (s, r) = bounded(0);
@@ -181,6 +237,49 @@ fn send_timeout() {
     });
 }
 
+#[test]
+fn spin_recv_send() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..100 {
+                assert_eq!(r.spin_recv(1000), Ok(i));
+            }
+            assert_eq!(r.spin_recv(1000), Err(RecvError));
+        });
+        scope.spawn(|| {
+            for i in 0..100 {
+                s.spin_send(i, 1000).unwrap();
+            }
+            drop(s);
+        });
+    });
+}
+
+#[test]
+fn reserve() {
+    let (s, r) = bounded(0);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            let p = s.try_reserve().unwrap();
+            p.send(7).unwrap();
+            let p = s.reserve().unwrap();
+            p.send(8).unwrap();
+        });
+        scope.spawn(|| {
+            thread::sleep(ms(1000));
+            assert_eq!(r.recv(), Ok(7));
+            assert_eq!(r.recv(), Ok(8));
+        });
+    });
+
+    drop(r);
+    assert_eq!(s.reserve().unwrap_err(), SendError(()));
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Disconnected(()));
+}
+
 #[test]
 fn len() {
     const COUNT: usize = 25_000;
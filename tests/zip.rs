@@ -0,0 +1,155 @@
+//! Tests for `zip`.
+
+extern crate crossbeam;
+extern crate crossbeam_channel;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, never, tick, unbounded, zip};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = zip(r1, r2);
+
+    s1.send(1).unwrap();
+    s2.send("a").unwrap();
+    assert_eq!(r.recv(), Ok((1, "a")));
+
+    s2.send("b").unwrap();
+    s2.send("c").unwrap();
+    s1.send(2).unwrap();
+    s1.send(3).unwrap();
+    assert_eq!(r.recv(), Ok((2, "b")));
+    assert_eq!(r.recv(), Ok((3, "c")));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn order_preserved() {
+    const COUNT: usize = 1000;
+
+    let (s1, r1) = bounded(5);
+    let (s2, r2) = bounded(5);
+    let r = zip(r1, r2);
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                s1.send(i).unwrap();
+            }
+        });
+        scope.spawn(|| {
+            for i in 0..COUNT {
+                s2.send(i * 2).unwrap();
+            }
+        });
+
+        for i in 0..COUNT {
+            assert_eq!(r.recv(), Ok((i, i * 2)));
+        }
+    });
+}
+
+#[test]
+fn disconnect_first() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let r = zip(r1, r2);
+
+    s1.send(1).unwrap();
+    s1.send(2).unwrap();
+    s2.send(10).unwrap();
+    drop(s1);
+
+    // Messages queued before the disconnect still get paired up.
+    assert_eq!(r.recv(), Ok((1, 10)));
+    s2.send(20).unwrap();
+    assert_eq!(r.recv(), Ok((2, 20)));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn disconnect_while_waiting() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let r = zip(r1, r2);
+
+    // One side keeps delivering while the other one stalls and then disconnects.
+    s1.send(1).unwrap();
+
+    crossbeam::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(ms(100));
+            drop(s2);
+        });
+
+        let start = Instant::now();
+        assert_eq!(r.recv(), Err(RecvError));
+        assert!(start.elapsed() >= ms(100));
+        assert!(start.elapsed() < ms(1000));
+    });
+    drop(s1);
+}
+
+#[test]
+fn disconnect_idle() {
+    let (_s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let r = zip(r1, r2);
+
+    drop(s2);
+    assert_eq!(r.recv_timeout(ms(1000)), Err(RecvTimeoutError::Disconnected));
+}
+
+#[test]
+fn receiver_dropped() {
+    let (s1, r1) = bounded(1);
+    let (s2, r2) = bounded(1);
+    let r = zip(r1, r2);
+
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+    assert_eq!(r.recv(), Ok((1, 2)));
+    drop(r);
+
+    // The background thread stops even though no messages arrive, and the inputs disconnect.
+    thread::sleep(ms(100));
+    assert_eq!(s1.send(3), Err(SendError(3)));
+    assert_eq!(s2.send(4), Err(SendError(4)));
+}
+
+#[test]
+fn unpaired_put_back() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded::<i32>();
+    let r3 = r1.clone();
+    let r = zip(r1, r2);
+
+    // The message waiting for a partner goes back into its channel once no pair can be formed.
+    s1.send(1).unwrap();
+    thread::sleep(ms(100));
+    drop(s2);
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(r3.recv_timeout(ms(100)), Ok(1));
+}
+
+#[test]
+fn timers() {
+    let r = zip(tick(ms(50)), never::<()>());
+    assert_eq!(r.recv_timeout(ms(200)), Err(RecvTimeoutError::Timeout));
+
+    let r = zip(tick(ms(50)), tick(ms(100)));
+    let start = Instant::now();
+    for _ in 0..3 {
+        r.recv().unwrap();
+    }
+    assert!(start.elapsed() >= ms(300));
+}